@@ -1,8 +1,13 @@
+use super::explain::Explanation;
 use crate::config::Config;
 use crate::fs::{FileSystem, default_fs};
 use crate::model::{Boundary, Issue, Location, Module};
-use std::collections::HashMap;
-use std::path::PathBuf;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+/// Lines of one file crossing each boundary, by boundary name. Discovery
+/// finds them while it has the file's source, so the check needn't read it again.
+pub type Crossings = HashMap<String, Vec<Location>>;
 
 /// Check if the indicator appears inside a string literal definition (e.g., in a config array).
 /// This filters out false positives from config files that define boundary indicators.
@@ -28,45 +33,30 @@ pub fn detect_boundary_violations_with_fs(
     modules: &[Module],
     config: &Config,
     fs: &dyn FileSystem,
+) -> Vec<Issue> {
+    detect_boundary_violations_with_crossings(modules, config, fs, None)
+}
+
+/// Like [`detect_boundary_violations_with_fs`], taking each module's crossings
+/// from `known` when it has them instead of reading the module's source.
+pub(crate) fn detect_boundary_violations_with_crossings(
+    modules: &[Module],
+    config: &Config,
+    fs: &dyn FileSystem,
+    known: Option<&HashMap<PathBuf, Crossings>>,
 ) -> Vec<Issue> {
     let mut issues = Vec::new();
 
     // For each boundary, track where it's crossed
     for boundary in &config.boundaries {
-        let mut occurrences_by_module: HashMap<PathBuf, Vec<Location>> = HashMap::new();
-
-        for module in modules {
-            // Skip modules that are explicitly allowed to cross this boundary
-            if boundary.is_allowed(&module.path) {
-                continue;
-            }
-
-            // Read the file content to scan for indicators
-            let content = match fs.read_to_string(&module.path) {
-                Ok(c) => c,
-                Err(_) => continue,
-            };
-
-            for (line_num, line) in content.lines().enumerate() {
-                for indicator in &boundary.indicators {
-                    if line.contains(indicator) && !is_string_literal_definition(line, indicator) {
-                        occurrences_by_module
-                            .entry(module.path.clone())
-                            .or_default()
-                            .push(Location {
-                                path: module.path.clone(),
-                                line: Some(line_num + 1),
-                                context: Some(line.trim().to_string()),
-                            });
-                        break; // Only count once per line
-                    }
-                }
-            }
-        }
+        let occurrences_by_module = find_crossings(boundary, modules, fs, known, None);
 
         // Apply ownership detection: if one module has most of the occurrences,
         // it's the designated owner and shouldn't be flagged
-        let filtered_occurrences = apply_ownership_filter(&occurrences_by_module, boundary);
+        let mut filtered_occurrences =
+            apply_ownership_filter(&occurrences_by_module, boundary, None);
+        // Stable order keeps issue IDs (and baselines) reproducible across runs
+        filtered_occurrences.sort_by(|a, b| (&a.path, a.line).cmp(&(&b.path, b.line)));
 
         // Re-group after filtering
         let modules_affected: HashMap<_, Vec<_>> =
@@ -90,11 +80,131 @@ pub fn detect_boundary_violations_with_fs(
     issues
 }
 
+/// Every line of `content` crossing one of `boundaries`
+pub(crate) fn scan_crossings(path: &Path, content: &str, boundaries: &[Boundary]) -> Crossings {
+    boundaries
+        .iter()
+        .map(|boundary| (boundary.name.clone(), crossings_in(path, content, boundary)))
+        .filter(|(_, locations)| !locations.is_empty())
+        .collect()
+}
+
+/// Lines of `content` mentioning one of the boundary's indicators
+fn crossings_in(path: &Path, content: &str, boundary: &Boundary) -> Vec<Location> {
+    let mut locations = Vec::new();
+    for (line_num, line) in content.lines().enumerate() {
+        for indicator in &boundary.indicators {
+            if line.contains(indicator) && !is_string_literal_definition(line, indicator) {
+                locations.push(Location {
+                    path: path.to_path_buf(),
+                    line: Some(line_num + 1),
+                    context: Some(line.trim().to_string()),
+                    indicator: Some(indicator.clone()),
+                });
+                break; // Only count once per line
+            }
+        }
+    }
+    locations
+}
+
+/// Scan modules for lines mentioning one of the boundary's indicators, using
+/// the crossings in `known` for modules it has. Records allowed modules and
+/// each matched line when given an explanation.
+fn find_crossings(
+    boundary: &Boundary,
+    modules: &[Module],
+    fs: &dyn FileSystem,
+    known: Option<&HashMap<PathBuf, Crossings>>,
+    explanation: Option<&mut Explanation>,
+) -> HashMap<PathBuf, Vec<Location>> {
+    let mut occurrences_by_module: HashMap<PathBuf, Vec<Location>> = HashMap::new();
+    let mut allowed = Vec::new();
+    let mut matches = Vec::new();
+
+    for module in modules {
+        // Skip modules that are explicitly allowed to cross this boundary
+        if boundary.is_allowed(&module.path) {
+            allowed.push(module.path.clone());
+            continue;
+        }
+
+        let locations = match known.and_then(|known| known.get(&module.path)) {
+            Some(crossings) => crossings.get(&boundary.name).cloned().unwrap_or_default(),
+            // Read the file content to scan for indicators
+            None => match fs.read_to_string(&module.path) {
+                Ok(content) => crossings_in(&module.path, &content, boundary),
+                Err(_) => continue,
+            },
+        };
+        if locations.is_empty() {
+            continue;
+        }
+
+        if explanation.is_some() {
+            matches.extend(locations.iter().map(|loc| {
+                let indicator = loc.indicator.clone().unwrap_or_default();
+                (module.path.clone(), loc.line.unwrap_or(0), indicator)
+            }));
+        }
+        occurrences_by_module.insert(module.path.clone(), locations);
+    }
+
+    if let Some(e) = explanation {
+        matches.sort();
+        let allowed: Vec<String> = allowed.iter().map(|p| e.relative(p)).collect();
+        e.input("Allowed modules (skipped)", allowed.len(), allowed);
+        let matches: Vec<String> = matches
+            .iter()
+            .map(|(path, line, indicator)| {
+                format!("{}:{} matches `{}`", e.relative(path), line, indicator)
+            })
+            .collect();
+        e.input("Matching lines", matches.len(), matches);
+    }
+
+    occurrences_by_module
+}
+
+/// Explain which lines cross a boundary and whether a module owns it.
+pub fn explain_boundary_violation(
+    boundary_name: &str,
+    modules: &[Module],
+    config: &Config,
+    fs: &dyn FileSystem,
+    root: &Path,
+) -> Option<Explanation> {
+    let boundary = config.boundaries.iter().find(|b| b.name == boundary_name)?;
+    let mut explanation = Explanation::new(boundary.name.clone()).with_root(root);
+    explanation.input(
+        "Indicators",
+        boundary.indicators.len(),
+        boundary.indicators.clone(),
+    );
+
+    let occurrences = find_crossings(boundary, modules, fs, None, Some(&mut explanation));
+    let filtered = apply_ownership_filter(&occurrences, boundary, Some(&mut explanation));
+    let affected: HashSet<&PathBuf> = filtered.iter().map(|loc| &loc.path).collect();
+
+    explanation.verdict = format!(
+        "crossed in {} module(s) after ownership filtering; {} (boundary_violation_min {})",
+        affected.len(),
+        if affected.len() >= config.thresholds.boundary_violation_min {
+            "flagged"
+        } else {
+            "not flagged"
+        },
+        config.thresholds.boundary_violation_min
+    );
+    Some(explanation)
+}
+
 /// Detect if a single module "owns" this boundary (has majority of occurrences)
 /// and filter it out from violations. This is language-independent - just counting.
 fn apply_ownership_filter(
     occurrences_by_module: &HashMap<PathBuf, Vec<Location>>,
     boundary: &Boundary,
+    explanation: Option<&mut Explanation>,
 ) -> Vec<Location> {
     if occurrences_by_module.is_empty() {
         return Vec::new();
@@ -114,6 +224,22 @@ fn apply_ownership_filter(
 
     let ownership_ratio = owner_count as f64 / total_occurrences as f64;
 
+    if let Some(e) = explanation {
+        let owner = e.relative(&owner_path);
+        e.input(
+            "Largest share",
+            format!(
+                "{} has {} of {} occurrences ({:.0}%, owner at {:.0}%)",
+                owner,
+                owner_count,
+                total_occurrences,
+                ownership_ratio * 100.0,
+                boundary.ownership_threshold * 100.0
+            ),
+            vec![],
+        );
+    }
+
     // If one module owns enough of the boundary, exclude it from violations
     if ownership_ratio >= boundary.ownership_threshold {
         occurrences_by_module
@@ -129,3 +255,63 @@ fn apply_ownership_filter(
             .collect()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fs::mock::MockFs;
+
+    #[test]
+    fn test_explain_boundary_lists_matches_and_owner() {
+        let fs = MockFs::with_files([
+            (
+                "/project/src/users.rs",
+                "use sqlx::PgPool;\nfn a() { sqlx::query(\"SELECT 1\"); }\nfn b() { sqlx::query(\"x\"); }",
+            ),
+            ("/project/src/orders.rs", "use sqlx::PgPool;"),
+            ("/project/src/db/pool.rs", "use sqlx::PgPool;"),
+        ]);
+        let modules: Vec<Module> = ["src/users.rs", "src/orders.rs", "src/db/pool.rs"]
+            .iter()
+            .map(|p| Module::new(Path::new("/project").join(p)))
+            .collect();
+
+        let explanation = explain_boundary_violation(
+            "Persistence",
+            &modules,
+            &Config::default(),
+            &fs,
+            Path::new("/project"),
+        )
+        .unwrap();
+
+        let input = |label: &str| {
+            explanation
+                .inputs
+                .iter()
+                .find(|i| i.label == label)
+                .unwrap()
+                .clone()
+        };
+        assert_eq!(
+            input("Allowed modules (skipped)").details,
+            ["src/db/pool.rs"]
+        );
+        assert_eq!(
+            input("Matching lines").details,
+            [
+                "src/orders.rs:1 matches `sqlx::`",
+                "src/users.rs:1 matches `sqlx::`",
+                "src/users.rs:2 matches `sqlx::`",
+                "src/users.rs:3 matches `sqlx::`",
+            ]
+        );
+        assert!(
+            input("Largest share")
+                .value
+                .starts_with("src/users.rs has 3 of 4")
+        );
+        // users.rs owns the boundary, leaving a single crossing
+        assert!(explanation.verdict.contains("not flagged"));
+    }
+}
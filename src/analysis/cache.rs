@@ -0,0 +1,226 @@
+//! Parse results kept between runs in `.archmap/cache/`.
+//!
+//! A module is a pure function of its path, its contents and the parser that
+//! read it, so discovery looks each file up by a hash of the three before
+//! parsing it. Boundary crossings found in the file are kept alongside, so
+//! the boundary check doesn't have to scan unchanged files again either. The
+//! whole cache is dropped when the archmap version or the configured
+//! boundaries change.
+
+use super::boundary::Crossings;
+use crate::config::Config;
+use crate::model::{Boundary, Module};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::collections::hash_map::DefaultHasher;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+/// Where the cache lives, relative to the project root
+pub const CACHE_DIR: &str = ".archmap/cache";
+
+/// File holding every cached module
+const CACHE_FILE: &str = "modules.json";
+
+/// `--no-cache`, which turns off the `cache` config key
+static NO_CACHE: AtomicBool = AtomicBool::new(false);
+
+/// Neither read nor write the parse cache for the rest of this process.
+pub fn disable_cache() {
+    NO_CACHE.store(true, Ordering::Relaxed);
+}
+
+/// Whether analysis uses the parse cache: the `cache` config key, unless
+/// `--no-cache` was given.
+pub fn cache_enabled(config: &Config) -> bool {
+    config.cache && !NO_CACHE.load(Ordering::Relaxed)
+}
+
+/// One file's parse result and the boundary crossings found in it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct CachedModule {
+    pub module: Module,
+    pub crossings: Crossings,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct CacheFile {
+    /// archmap version that wrote the cache; any other version ignores it
+    version: String,
+    /// Fingerprint of the boundaries the crossings were found with
+    boundaries: String,
+    entries: HashMap<String, CachedModule>,
+}
+
+/// How many files were found in the cache and how many had to be parsed
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ParseCacheStats {
+    pub hits: usize,
+    pub misses: usize,
+}
+
+impl fmt::Display for ParseCacheStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let total = self.hits + self.misses;
+        let rate = if total == 0 {
+            0.0
+        } else {
+            self.hits as f64 * 100.0 / total as f64
+        };
+        write!(
+            f,
+            "{} of {} files reused ({:.0}%), {} parsed",
+            self.hits, total, rate, self.misses
+        )
+    }
+}
+
+/// The cache for one analysis: entries loaded from disk, and the ones this
+/// run used or added, which are all that get written back.
+pub(crate) struct ParseCache {
+    dir: PathBuf,
+    boundaries: String,
+    loaded: HashMap<String, CachedModule>,
+    used: Mutex<HashMap<String, CachedModule>>,
+    hits: AtomicUsize,
+    misses: AtomicUsize,
+}
+
+impl ParseCache {
+    /// Open the cache under `root`. A missing, unreadable or outdated cache
+    /// starts empty.
+    pub fn open(root: &Path, boundaries: &[Boundary]) -> Self {
+        let dir = root.join(CACHE_DIR);
+        let boundaries = boundaries_fingerprint(boundaries);
+        let loaded = std::fs::read_to_string(dir.join(CACHE_FILE))
+            .ok()
+            .and_then(|json| serde_json::from_str::<CacheFile>(&json).ok())
+            .filter(|file| {
+                file.version == env!("CARGO_PKG_VERSION") && file.boundaries == boundaries
+            })
+            .map(|file| file.entries)
+            .unwrap_or_default();
+        Self {
+            dir,
+            boundaries,
+            loaded,
+            used: Mutex::new(HashMap::new()),
+            hits: AtomicUsize::new(0),
+            misses: AtomicUsize::new(0),
+        }
+    }
+
+    /// The cached result for `path` with these contents, parsed by `parser`
+    pub fn get(&self, path: &Path, parser: &str, source: &str) -> Option<CachedModule> {
+        let key = cache_key(path, parser, source);
+        let Some(entry) = self.loaded.get(&key) else {
+            self.misses.fetch_add(1, Ordering::Relaxed);
+            return None;
+        };
+        self.hits.fetch_add(1, Ordering::Relaxed);
+        self.used.lock().unwrap().insert(key, entry.clone());
+        Some(entry.clone())
+    }
+
+    /// Remember the result of parsing `path`
+    pub fn insert(&self, path: &Path, parser: &str, source: &str, entry: CachedModule) {
+        let key = cache_key(path, parser, source);
+        self.used.lock().unwrap().insert(key, entry);
+    }
+
+    pub fn stats(&self) -> ParseCacheStats {
+        ParseCacheStats {
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Write the entries this run used back to disk, unless nothing changed.
+    /// Files no longer analyzed drop out.
+    pub fn save(self) -> std::io::Result<()> {
+        let used = self.used.into_inner().unwrap();
+        let unchanged = self.misses.into_inner() == 0 && used.len() == self.loaded.len();
+        if unchanged {
+            return Ok(());
+        }
+
+        std::fs::create_dir_all(&self.dir)?;
+        // Keep the cache out of version control without touching the project's .gitignore
+        let gitignore = self.dir.join(".gitignore");
+        if !gitignore.exists() {
+            std::fs::write(&gitignore, "*\n")?;
+        }
+        let file = CacheFile {
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            boundaries: self.boundaries,
+            entries: used,
+        };
+        let json = serde_json::to_string(&file).map_err(std::io::Error::other)?;
+        // Written aside and renamed, so a concurrent run never reads half a file
+        let partial = self
+            .dir
+            .join(format!("{}.{}.tmp", CACHE_FILE, std::process::id()));
+        std::fs::write(&partial, json)?;
+        std::fs::rename(&partial, self.dir.join(CACHE_FILE))
+    }
+}
+
+fn cache_key(path: &Path, parser: &str, source: &str) -> String {
+    let mut hasher = DefaultHasher::new();
+    path.hash(&mut hasher);
+    parser.hash(&mut hasher);
+    source.hash(&mut hasher);
+    format!("{:016x}", hasher.finish())
+}
+
+/// Names and indicators of every boundary, which decide what a crossing is
+fn boundaries_fingerprint(boundaries: &[Boundary]) -> String {
+    let mut hasher = DefaultHasher::new();
+    for boundary in boundaries {
+        boundary.name.hash(&mut hasher);
+        boundary.indicators.hash(&mut hasher);
+    }
+    format!("{:016x}", hasher.finish())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(path: &str) -> CachedModule {
+        CachedModule {
+            module: Module::new(PathBuf::from(path)),
+            crossings: Crossings::new(),
+        }
+    }
+
+    #[test]
+    fn test_cache_round_trips_and_invalidates() {
+        let root = std::env::temp_dir().join(format!("archmap-cache-{}", std::process::id()));
+        let boundaries = Boundary::default_boundaries();
+        let path = root.join("src/lib.rs");
+
+        let cache = ParseCache::open(&root, &boundaries);
+        assert!(cache.get(&path, "rust", "fn a() {}").is_none());
+        cache.insert(&path, "rust", "fn a() {}", entry("src/lib.rs"));
+        cache.save().unwrap();
+
+        let cache = ParseCache::open(&root, &boundaries);
+        assert!(cache.get(&path, "rust", "fn a() {}").is_some());
+        // Other contents, or another parser, miss
+        assert!(cache.get(&path, "rust", "fn b() {}").is_none());
+        assert!(cache.get(&path, "scala", "fn a() {}").is_none());
+        assert_eq!(cache.stats(), ParseCacheStats { hits: 1, misses: 2 });
+
+        // Other boundaries invalidate every entry
+        let cache = ParseCache::open(&root, &boundaries[..1]);
+        assert!(cache.get(&path, "rust", "fn a() {}").is_none());
+
+        let ignored = std::fs::read_to_string(root.join(CACHE_DIR).join(".gitignore"));
+        std::fs::remove_dir_all(&root).unwrap();
+        assert_eq!(ignored.unwrap(), "*\n");
+    }
+}
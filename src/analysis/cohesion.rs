@@ -1,6 +1,8 @@
+use super::explain::Explanation;
 use crate::analysis::DependencyGraph;
-use crate::config::Config;
-use crate::model::{Issue, Module};
+use crate::config::{CohesionMethod, Config};
+use crate::fs::FileSystem;
+use crate::model::{DefinitionKind, Issue, Module};
 use std::collections::{HashMap, HashSet};
 use std::path::Path;
 
@@ -13,11 +15,33 @@ use std::path::Path;
 /// We measure "dependency diversity" - how many distinct external crates are used.
 /// A module using 5 imports from 1 crate is more cohesive than one using 5 imports
 /// from 5 different crates.
+///
+/// With `cohesion_method = "lcom"` the score comes from the module's source
+/// instead (see [`lcom_cohesion`]).
 pub fn detect_low_cohesion(
     modules: &[Module],
     _graph: &DependencyGraph,
     config: &Config,
+    fs: &dyn FileSystem,
 ) -> Vec<Issue> {
+    let mut issues = match config.cohesion_method {
+        CohesionMethod::Imports => import_cohesion_issues(modules, config),
+        CohesionMethod::Lcom => lcom_cohesion_issues(modules, config, fs),
+    };
+
+    // Sort by cohesion score (lowest first)
+    issues.sort_by(|a, b| {
+        let score_a = extract_cohesion_score(&a.message);
+        let score_b = extract_cohesion_score(&b.message);
+        score_a
+            .partial_cmp(&score_b)
+            .unwrap_or(std::cmp::Ordering::Equal)
+    });
+
+    issues
+}
+
+fn import_cohesion_issues(modules: &[Module], config: &Config) -> Vec<Issue> {
     let mut issues = Vec::new();
     let min_cohesion = config.thresholds.min_cohesion;
 
@@ -31,82 +55,340 @@ pub fn detect_low_cohesion(
 
     // For each module, calculate cohesion
     for module in modules {
-        // Skip re-export hub modules - they're designed to have low internal cohesion
-        if is_reexport_hub(module) {
-            continue;
-        }
-
         let package = get_package_name(&module.path);
         let siblings: HashSet<String> = packages
             .get(&package)
             .map(|p| p.iter().map(|m| m.name.clone()).collect())
             .unwrap_or_default();
 
-        // Skip modules with no imports (they're perfectly cohesive by default)
-        if module.imports.is_empty() {
+        let Some(cohesion) = module_cohesion(module, &siblings, None) else {
             continue;
-        }
+        };
 
-        // Count internal imports and track unique external crates
-        let mut internal_imports = 0;
-        let mut external_crates: HashMap<String, usize> = HashMap::new();
-
-        for import in &module.imports {
-            let import_name = extract_module_name(import);
-            if siblings.contains(&import_name) || is_relative_import(import) {
-                internal_imports += 1;
-            } else {
-                // Extract the root crate name (e.g., "petgraph" from "petgraph::graph")
-                let crate_name = extract_crate_name(import);
-                *external_crates.entry(crate_name).or_insert(0) += 1;
-            }
+        // Flag modules with low cohesion (many different external dependencies)
+        // Require at least 3 unique external crates to flag - using 1-2 external libs is normal
+        let unique_external_crates = cohesion.external_crates.len();
+        if cohesion.score < min_cohesion && unique_external_crates >= MIN_EXTERNAL_CRATES {
+            issues.push(Issue::low_cohesion_v2(
+                module.path.clone(),
+                cohesion.score,
+                cohesion.internal_imports,
+                cohesion.external_crates.values().sum::<usize>(),
+                unique_external_crates,
+                top_crates(&cohesion.external_crates, 3),
+            ));
         }
+    }
 
-        let total_external = external_crates.values().sum::<usize>();
-        let unique_external_crates = external_crates.len();
+    issues
+}
 
-        // Skip if no external dependencies
-        if unique_external_crates == 0 {
+fn lcom_cohesion_issues(modules: &[Module], config: &Config, fs: &dyn FileSystem) -> Vec<Issue> {
+    let mut issues = Vec::new();
+    for module in modules {
+        let Ok(source) = fs.read_to_string(&module.path) else {
             continue;
+        };
+        let Some(lcom) = lcom_cohesion(module, &source, None) else {
+            continue;
+        };
+        if lcom.score < config.thresholds.min_cohesion {
+            issues.push(Issue::low_cohesion_lcom(
+                module.path.clone(),
+                lcom.score,
+                lcom.sharing_pairs,
+                lcom.pairs,
+            ));
         }
+    }
+    issues
+}
 
-        // Calculate cohesion based on dependency diversity
-        // Formula: We penalize having many *different* external crates, not many imports from one crate
-        //
-        // A module with 5 petgraph imports has diversity = 1 (focused)
-        // A module with 5 imports from 5 crates has diversity = 5 (scattered)
-        //
-        // cohesion = internal_weight / (internal_weight + diversity_penalty)
-        // where diversity_penalty scales with unique crate count
-        let internal_weight = (internal_imports as f64) + 1.0; // +1 to avoid division issues
-        let diversity_penalty = unique_external_crates as f64;
+/// Distinct external crates a module must use before it can be flagged
+const MIN_EXTERNAL_CRATES: usize = 3;
 
-        let cohesion_score = internal_weight / (internal_weight + diversity_penalty);
+struct Cohesion {
+    score: f64,
+    internal_imports: usize,
+    /// Import count per external crate
+    external_crates: HashMap<String, usize>,
+}
 
-        // Flag modules with low cohesion (many different external dependencies)
-        // Require at least 3 unique external crates to flag - using 1-2 external libs is normal
-        if cohesion_score < min_cohesion && unique_external_crates >= 3 {
-            issues.push(Issue::low_cohesion_v2(
-                module.path.clone(),
-                cohesion_score,
-                internal_imports,
-                total_external,
-                unique_external_crates,
-                top_crates(&external_crates, 3),
-            ));
+/// Score one module, or `None` when it is exempt (re-export hub, no imports,
+/// no external crates). Records how each import was classified when given an
+/// explanation.
+fn module_cohesion(
+    module: &Module,
+    siblings: &HashSet<String>,
+    mut explanation: Option<&mut Explanation>,
+) -> Option<Cohesion> {
+    // Skip re-export hub modules - they're designed to have low internal cohesion
+    if is_reexport_hub(module) {
+        if let Some(e) = explanation {
+            e.verdict = "Exempt: re-export hubs aggregate other modules by design".to_string();
         }
+        return None;
     }
 
-    // Sort by cohesion score (lowest first)
-    issues.sort_by(|a, b| {
-        let score_a = extract_cohesion_score(&a.message);
-        let score_b = extract_cohesion_score(&b.message);
-        score_a
-            .partial_cmp(&score_b)
-            .unwrap_or(std::cmp::Ordering::Equal)
-    });
+    // Count internal imports and track unique external crates
+    let mut internal = Vec::new();
+    let mut external_crates: HashMap<String, usize> = HashMap::new();
+    let mut external = Vec::new();
 
-    issues
+    for import in &module.imports {
+        let import_name = extract_module_name(import);
+        if siblings.contains(&import_name) || is_relative_import(import) {
+            internal.push(import.to_string());
+        } else {
+            // Extract the root crate name (e.g., "petgraph" from "petgraph::graph")
+            let crate_name = extract_crate_name(import);
+            external.push(format!("{} ({})", import, crate_name));
+            *external_crates.entry(crate_name).or_insert(0) += 1;
+        }
+    }
+
+    if let Some(e) = explanation.as_deref_mut() {
+        e.input("Internal imports", internal.len(), internal.clone());
+        e.input("External imports", external.len(), external);
+        e.input(
+            "Distinct external crates",
+            external_crates.len(),
+            top_crates(&external_crates, external_crates.len()),
+        );
+    }
+
+    // Skip modules with no imports (they're perfectly cohesive by default),
+    // or if no external dependencies
+    if external_crates.is_empty() {
+        if let Some(e) = explanation {
+            e.verdict = "Exempt: no external imports".to_string();
+        }
+        return None;
+    }
+
+    // Calculate cohesion based on dependency diversity
+    // Formula: We penalize having many *different* external crates, not many imports from one crate
+    //
+    // A module with 5 petgraph imports has diversity = 1 (focused)
+    // A module with 5 imports from 5 crates has diversity = 5 (scattered)
+    //
+    // cohesion = internal_weight / (internal_weight + diversity_penalty)
+    // where diversity_penalty scales with unique crate count
+    let internal_weight = (internal.len() as f64) + 1.0; // +1 to avoid division issues
+    let diversity_penalty = external_crates.len() as f64;
+    let score = internal_weight / (internal_weight + diversity_penalty);
+
+    if let Some(e) = explanation {
+        e.verdict = format!(
+            "cohesion = ({} + 1) / ({} + 1 + {}) = {:.2}",
+            internal.len(),
+            internal.len(),
+            external_crates.len(),
+            score
+        );
+    }
+
+    Some(Cohesion {
+        score,
+        internal_imports: internal.len(),
+        external_crates,
+    })
+}
+
+/// Functions that must mention imports before the LCOM score is meaningful
+const MIN_LCOM_FUNCTIONS: usize = 3;
+
+struct Lcom {
+    score: f64,
+    /// Pairs of import-using functions that mention an import in common
+    sharing_pairs: usize,
+    pairs: usize,
+}
+
+/// LCOM-style cohesion: the share of function pairs whose bodies mention at
+/// least one import in common. A body runs from a function's definition line to
+/// the next definition, and an import counts as used where its last path
+/// segment appears as a word, so this approximates shared data and types from
+/// the text alone. `None` for re-export hubs and for modules with fewer than
+/// [`MIN_LCOM_FUNCTIONS`] functions using imports.
+fn lcom_cohesion(
+    module: &Module,
+    source: &str,
+    explanation: Option<&mut Explanation>,
+) -> Option<Lcom> {
+    if is_reexport_hub(module) {
+        if let Some(e) = explanation {
+            e.verdict = "Exempt: re-export hubs aggregate other modules by design".to_string();
+        }
+        return None;
+    }
+
+    let symbols: Vec<String> = module
+        .imports
+        .iter()
+        .flat_map(|import| import_symbols(import))
+        .collect::<HashSet<_>>()
+        .into_iter()
+        .collect();
+    let lines: Vec<&str> = source.lines().collect();
+    let mut starts: Vec<usize> = module.definitions.iter().map(|d| d.line).collect();
+    starts.sort_unstable();
+
+    // Imports each function's body mentions, for functions mentioning any
+    let mut functions: Vec<(&str, HashSet<usize>)> = Vec::new();
+    for def in &module.definitions {
+        if def.kind != DefinitionKind::Function || def.line == 0 {
+            continue;
+        }
+        let end = starts
+            .iter()
+            .find(|&&line| line > def.line)
+            .map_or(lines.len(), |&line| line - 1);
+        let body = lines
+            .get(def.line - 1..end.min(lines.len()))
+            .unwrap_or_default();
+        let words: HashSet<&str> = body.iter().flat_map(|line| words(line)).collect();
+        let used: HashSet<usize> = symbols
+            .iter()
+            .enumerate()
+            .filter(|(_, symbol)| words.contains(symbol.as_str()))
+            .map(|(i, _)| i)
+            .collect();
+        if !used.is_empty() {
+            functions.push((&def.name, used));
+        }
+    }
+
+    let mut explanation = explanation;
+    if let Some(e) = explanation.as_deref_mut() {
+        e.input(
+            "Functions using imports",
+            functions.len(),
+            functions.iter().map(|(name, _)| name.to_string()).collect(),
+        );
+    }
+    if functions.len() < MIN_LCOM_FUNCTIONS {
+        if let Some(e) = explanation {
+            e.verdict = format!(
+                "Exempt: fewer than {} functions use imports",
+                MIN_LCOM_FUNCTIONS
+            );
+        }
+        return None;
+    }
+
+    let pairs = functions.len() * (functions.len() - 1) / 2;
+    let mut sharing_pairs = 0;
+    for (i, (_, a)) in functions.iter().enumerate() {
+        for (_, b) in &functions[i + 1..] {
+            if !a.is_disjoint(b) {
+                sharing_pairs += 1;
+            }
+        }
+    }
+    let score = sharing_pairs as f64 / pairs as f64;
+
+    if let Some(e) = explanation {
+        e.input(
+            "Function pairs sharing an import",
+            sharing_pairs,
+            Vec::new(),
+        );
+        e.verdict = format!("cohesion = {} / {} = {:.2}", sharing_pairs, pairs, score);
+    }
+
+    Some(Lcom {
+        score,
+        sharing_pairs,
+        pairs,
+    })
+}
+
+/// Names an import brings into scope: the last path segment, or each one in a
+/// `{A, B}` group.
+fn import_symbols(import: &str) -> Vec<String> {
+    let names: Vec<String> = match (import.find('{'), import.rfind('}')) {
+        (Some(open), Some(close)) if open < close => import[open + 1..close]
+            .split(',')
+            .map(|name| extract_module_name(name.trim()))
+            .collect(),
+        _ => vec![extract_module_name(import)],
+    };
+    names
+        .into_iter()
+        .filter(|name| {
+            !name.is_empty()
+                && name != "self"
+                && name.chars().all(|c| c.is_alphanumeric() || c == '_')
+        })
+        .collect()
+}
+
+fn words(line: &str) -> impl Iterator<Item = &str> {
+    line.split(|c: char| !(c.is_alphanumeric() || c == '_'))
+        .filter(|word| !word.is_empty())
+}
+
+/// Explain how a module's cohesion score is computed.
+pub fn explain_low_cohesion(
+    module: &Module,
+    modules: &[Module],
+    config: &Config,
+    fs: &dyn FileSystem,
+    root: &Path,
+) -> Explanation {
+    if config.cohesion_method == CohesionMethod::Lcom {
+        return explain_lcom_cohesion(module, config, fs, root);
+    }
+
+    let package = get_package_name(&module.path);
+    let siblings: HashSet<String> = modules
+        .iter()
+        .filter(|m| get_package_name(&m.path) == package)
+        .map(|m| m.name.clone())
+        .collect();
+
+    let mut explanation = Explanation::new("").with_root(root);
+    explanation.subject = explanation.relative(&module.path);
+    if let Some(cohesion) = module_cohesion(module, &siblings, Some(&mut explanation)) {
+        let flagged = cohesion.score < config.thresholds.min_cohesion
+            && cohesion.external_crates.len() >= MIN_EXTERNAL_CRATES;
+        explanation.verdict = format!(
+            "{}; {} (min_cohesion {}, needs {}+ external crates)",
+            explanation.verdict,
+            if flagged { "flagged" } else { "not flagged" },
+            config.thresholds.min_cohesion,
+            MIN_EXTERNAL_CRATES
+        );
+    }
+    explanation
+}
+
+fn explain_lcom_cohesion(
+    module: &Module,
+    config: &Config,
+    fs: &dyn FileSystem,
+    root: &Path,
+) -> Explanation {
+    let mut explanation = Explanation::new("").with_root(root);
+    explanation.subject = explanation.relative(&module.path);
+    let source = match fs.read_to_string(&module.path) {
+        Ok(source) => source,
+        Err(e) => {
+            explanation.verdict = format!("Could not read source: {}", e);
+            return explanation;
+        }
+    };
+    if let Some(lcom) = lcom_cohesion(module, &source, Some(&mut explanation)) {
+        let flagged = lcom.score < config.thresholds.min_cohesion;
+        explanation.verdict = format!(
+            "{}; {} (min_cohesion {})",
+            explanation.verdict,
+            if flagged { "flagged" } else { "not flagged" },
+            config.thresholds.min_cohesion
+        );
+    }
+    explanation
 }
 
 /// Extract the root crate name from an import path
@@ -155,7 +437,7 @@ fn extract_crate_name(import: &str) -> String {
 /// Get the top N most-used crates
 fn top_crates(crates: &HashMap<String, usize>, n: usize) -> Vec<String> {
     let mut sorted: Vec<_> = crates.iter().collect();
-    sorted.sort_by(|a, b| b.1.cmp(a.1));
+    sorted.sort_by(|a, b| b.1.cmp(a.1).then_with(|| a.0.cmp(b.0)));
     sorted.into_iter().take(n).map(|(k, _)| k.clone()).collect()
 }
 
@@ -214,3 +496,134 @@ fn extract_cohesion_score(message: &str) -> f64 {
         .and_then(|s| s.parse().ok())
         .unwrap_or(1.0)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fs::default_fs;
+    use crate::fs::mock::MockFs;
+    use crate::model::Definition;
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_explain_cohesion_classifies_imports() {
+        let mut module = Module::new(PathBuf::from("/repo/src/sync.rs"));
+        module.imports = vec![
+            "crate::model::Module".into(),
+            "serde::Serialize".into(),
+            "serde::Deserialize".into(),
+            "reqwest::Client".into(),
+            "tokio::task".into(),
+        ];
+        let modules = vec![module.clone()];
+
+        let explanation = explain_low_cohesion(
+            &module,
+            &modules,
+            &Config::default(),
+            default_fs(),
+            Path::new("/repo"),
+        );
+
+        assert_eq!(explanation.subject, "src/sync.rs");
+        let values: Vec<(&str, &str)> = explanation
+            .inputs
+            .iter()
+            .map(|i| (i.label.as_str(), i.value.as_str()))
+            .collect();
+        assert_eq!(
+            values,
+            [
+                ("Internal imports", "1"),
+                ("External imports", "4"),
+                ("Distinct external crates", "3"),
+            ]
+        );
+        assert_eq!(explanation.inputs[2].details, ["serde", "reqwest", "tokio"]);
+        assert!(
+            explanation
+                .verdict
+                .starts_with("cohesion = (1 + 1) / (1 + 1 + 3) = 0.40")
+        );
+        assert!(explanation.verdict.contains("not flagged"));
+    }
+
+    #[test]
+    fn test_explain_cohesion_reports_exemption() {
+        let mut module = Module::new(PathBuf::from("/repo/src/lib.rs"));
+        module.imports = vec!["serde::Serialize".into()];
+
+        let explanation = explain_low_cohesion(
+            &module,
+            std::slice::from_ref(&module),
+            &Config::default(),
+            default_fs(),
+            Path::new("/repo"),
+        );
+        assert!(explanation.inputs.is_empty());
+        assert!(explanation.verdict.starts_with("Exempt"));
+    }
+
+    #[test]
+    fn test_lcom_flags_functions_with_separate_imports() {
+        let source = "\
+use std::fs::File;
+use std::net::{TcpStream, UdpSocket};
+
+fn load() -> File {
+    File::open(\"a\").unwrap()
+}
+
+fn save(file: File) {}
+
+fn connect() -> TcpStream {
+    TcpStream::connect(\"b\").unwrap()
+}
+
+fn ping(socket: UdpSocket) {}
+";
+        let path = PathBuf::from("/repo/src/misc.rs");
+        let mut module = Module::new(path.clone());
+        module.imports = vec![
+            "std::fs::File".into(),
+            "std::net::{TcpStream, UdpSocket}".into(),
+        ];
+        for (name, line) in [("load", 4), ("save", 8), ("connect", 10), ("ping", 14)] {
+            module.add_definition(Definition {
+                name: name.to_string(),
+                kind: DefinitionKind::Function,
+                line,
+                visibility: Default::default(),
+                signature: None,
+                span: None,
+                compact_signature: None,
+                decorators: Vec::new(),
+            });
+        }
+        let modules = vec![module.clone()];
+        let fs = MockFs::with_files([(path.as_path(), source)]);
+        let graph = DependencyGraph::build(&modules);
+
+        // Two std imports never reach the import-based method's crate minimum
+        assert!(detect_low_cohesion(&modules, &graph, &Config::default(), &fs).is_empty());
+
+        let config = Config {
+            cohesion_method: CohesionMethod::Lcom,
+            ..Config::default()
+        };
+        let issues = detect_low_cohesion(&modules, &graph, &config, &fs);
+        assert_eq!(issues.len(), 1);
+        // Only load/save share File; connect and ping use different names
+        assert_eq!(
+            issues[0].message,
+            "Cohesion score: 0.17 (1 of 6 function pairs share an import)"
+        );
+
+        let explanation = explain_low_cohesion(&module, &modules, &config, &fs, Path::new("/repo"));
+        assert_eq!(explanation.inputs[0].value, "4");
+        assert_eq!(
+            explanation.verdict,
+            "cohesion = 1 / 6 = 0.17; flagged (min_cohesion 0.3)"
+        );
+    }
+}
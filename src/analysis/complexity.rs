@@ -71,6 +71,18 @@ impl ModuleComplexity {
     }
 }
 
+/// Detect modules importing more than `max_imports` things. Every import
+/// statement counts, so long lists of external crates show up even when the
+/// module's fan-out to project modules is small.
+pub fn detect_too_many_imports(modules: &[Module], config: &Config) -> Vec<Issue> {
+    let max_imports = config.thresholds.max_imports;
+    modules
+        .iter()
+        .filter(|m| m.imports.len() > max_imports && !is_test_file(&m.path))
+        .map(|m| Issue::too_many_imports(m.path.clone(), m.imports.len(), m.resolved_imports.len()))
+        .collect()
+}
+
 /// Detect modules with excessive internal complexity ("fat files")
 pub fn detect_fat_modules(modules: &[Module], config: &Config) -> Vec<Issue> {
     let mut issues = Vec::new();
@@ -114,7 +126,7 @@ pub fn detect_fat_modules(modules: &[Module], config: &Config) -> Vec<Issue> {
 }
 
 /// Check if a file is a test file based on path patterns
-fn is_test_file(path: &std::path::Path) -> bool {
+pub(crate) fn is_test_file(path: &std::path::Path) -> bool {
     let path_str = path.to_string_lossy();
 
     // Common test file patterns
@@ -135,7 +147,7 @@ fn is_test_file(path: &std::path::Path) -> bool {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::model::{Definition, Module};
+    use crate::model::{Definition, IssueKind, Module};
     use std::path::PathBuf;
 
     fn make_module(lines: usize, private_fns: usize, public_fns: usize, exports: usize) -> Module {
@@ -148,6 +160,9 @@ mod tests {
                 line: i + 1,
                 visibility: Visibility::Private,
                 signature: None,
+                span: None,
+                compact_signature: None,
+                decorators: Vec::new(),
             });
         }
 
@@ -158,6 +173,9 @@ mod tests {
                 line: private_fns + i + 1,
                 visibility: Visibility::Public,
                 signature: None,
+                span: None,
+                compact_signature: None,
+                decorators: Vec::new(),
             });
         }
 
@@ -166,8 +184,14 @@ mod tests {
             name: "test".to_string(),
             lines,
             imports: vec![],
-            exports: (0..exports).map(|i| format!("export_{}", i)).collect(),
+            resolved_imports: vec![],
+            exports: (0..exports)
+                .map(|i| format!("export_{}", i).into())
+                .collect(),
             definitions,
+            implementations: Vec::new(),
+            tags: Default::default(),
+            aliases: Vec::new(),
         }
     }
 
@@ -242,4 +266,24 @@ mod tests {
         let issues = detect_fat_modules(&[module], &config);
         assert!(issues.is_empty(), "Test files should not be flagged");
     }
+
+    #[test]
+    fn test_flags_long_import_lists() {
+        let mut module = make_module(50, 0, 1, 1);
+        module.path = PathBuf::from("src/handlers.rs");
+        module.imports = (0..31)
+            .map(|i| format!("dep_{}::Thing", i).into())
+            .collect();
+        module.resolved_imports = vec![PathBuf::from("src/model.rs")];
+        let mut within_limit = module.clone();
+        within_limit.imports.truncate(30);
+        let mut test_file = module.clone();
+        test_file.path = PathBuf::from("tests/handlers.rs");
+
+        let issues =
+            detect_too_many_imports(&[module, within_limit, test_file], &Config::default());
+        assert_eq!(issues.len(), 1);
+        assert_eq!(issues[0].kind, IssueKind::TooManyImports { imports: 31 });
+        assert_eq!(issues[0].message, "31 imports (1 internal, 30 external)");
+    }
 }
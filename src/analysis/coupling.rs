@@ -1,26 +1,234 @@
 use crate::analysis::DependencyGraph;
 use crate::config::Config;
-use crate::model::{Issue, glob_match};
+use crate::model::{DefinitionKind, Issue, Module, glob_match};
+use std::path::Path;
+
+/// Functions spanning at most this many lines (accessors, constructors) count as trivial
+const TRIVIAL_FUNCTION_LINES: usize = 3;
 
 pub fn detect_high_coupling(graph: &DependencyGraph, config: &Config) -> Vec<Issue> {
     let mut issues = Vec::new();
+    let threshold = fanin_threshold(graph, config);
 
     for (path, _idx) in graph.node_indices() {
         let fan_in = graph.fan_in(path);
 
-        if fan_in >= config.thresholds.coupling_fanin {
-            // Check if this module is expected to have high coupling
-            let path_str = path.to_string_lossy();
-            let is_expected = config
-                .expected_high_coupling
-                .iter()
-                .any(|pattern| glob_match(pattern, &path_str));
+        if fan_in >= threshold && !is_expected_high_coupling(path, config) {
+            issues.push(Issue::high_coupling(path.clone(), fan_in));
+        }
+    }
 
-            if !is_expected {
-                issues.push(Issue::high_coupling(path.clone(), fan_in));
-            }
+    issues
+}
+
+/// Lowest fan-in counted as high coupling: `coupling_fanin`, or with
+/// `coupling_fanin_percentile` set, one above that percentile of the fan-in of
+/// every module in `graph` (nearest rank).
+pub fn fanin_threshold(graph: &DependencyGraph, config: &Config) -> usize {
+    let Some(percentile) = config.thresholds.coupling_fanin_percentile else {
+        return config.thresholds.coupling_fanin;
+    };
+    let mut fan_ins: Vec<usize> = graph
+        .node_indices()
+        .keys()
+        .map(|path| graph.fan_in(path))
+        .collect();
+    if fan_ins.is_empty() {
+        return config.thresholds.coupling_fanin;
+    }
+    fan_ins.sort_unstable();
+    let rank = (percentile / 100.0 * fan_ins.len() as f64).ceil() as usize;
+    fan_ins[rank.clamp(1, fan_ins.len()) - 1] + 1
+}
+
+/// Flag modules exempt from high-coupling checks (configs, constants, shared types)
+/// that also contain real logic. A pure constants module is a fine hub; one mixing
+/// constants with behavior changes often while everything depends on it.
+pub fn detect_fat_shared_modules(
+    modules: &[Module],
+    graph: &DependencyGraph,
+    config: &Config,
+) -> Vec<Issue> {
+    let mut issues = Vec::new();
+    let threshold = fanin_threshold(graph, config);
+
+    for module in modules {
+        let fan_in = graph.fan_in(&module.path);
+        if fan_in < threshold || !is_expected_high_coupling(&module.path, config) {
+            continue;
+        }
+
+        let data_definitions = module
+            .definitions
+            .iter()
+            .filter(|d| {
+                matches!(
+                    d.kind,
+                    DefinitionKind::Constant
+                        | DefinitionKind::Struct
+                        | DefinitionKind::Enum
+                        | DefinitionKind::Type
+                        | DefinitionKind::Interface
+                )
+            })
+            .count();
+        let functions = non_trivial_functions(module);
+
+        if data_definitions > 0 && functions >= config.thresholds.shared_module_functions {
+            issues.push(Issue::fat_shared_module(
+                module.path.clone(),
+                fan_in,
+                functions,
+                data_definitions,
+            ));
         }
     }
 
     issues
 }
+
+fn is_expected_high_coupling(path: &Path, config: &Config) -> bool {
+    let path_str = path.to_string_lossy();
+    config
+        .expected_high_coupling
+        .iter()
+        .any(|pattern| glob_match(pattern, &path_str))
+}
+
+/// Count functions longer than a few lines, estimating each one's length
+/// as the distance to the next definition (or the end of the file).
+fn non_trivial_functions(module: &Module) -> usize {
+    let mut lines: Vec<_> = module.definitions.iter().map(|d| d.line).collect();
+    lines.sort_unstable();
+
+    module
+        .definitions
+        .iter()
+        .filter(|d| d.kind == DefinitionKind::Function)
+        .filter(|d| {
+            let end = lines
+                .iter()
+                .find(|&&line| line > d.line)
+                .copied()
+                .unwrap_or(module.lines + 1);
+            end - d.line > TRIVIAL_FUNCTION_LINES
+        })
+        .count()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{Definition, Visibility};
+    use std::path::PathBuf;
+
+    fn definition(name: &str, kind: DefinitionKind, line: usize) -> Definition {
+        Definition {
+            name: name.to_string(),
+            kind,
+            line,
+            visibility: Visibility::Public,
+            signature: None,
+            span: None,
+            compact_signature: None,
+            decorators: Vec::new(),
+        }
+    }
+
+    /// `src/config.rs` plus enough importers to clear the fan-in threshold
+    fn shared_config(definitions: Vec<Definition>, lines: usize) -> Vec<Module> {
+        let mut config = Module::new(PathBuf::from("src/config.rs"));
+        config.lines = lines;
+        for def in definitions {
+            config.add_definition(def);
+        }
+
+        let mut modules = vec![config];
+        for i in 0..5 {
+            let mut user = Module::new(PathBuf::from(format!("src/user{}.rs", i)));
+            user.imports = vec!["crate::config::Settings".into()];
+            modules.push(user);
+        }
+        modules
+    }
+
+    fn detect(modules: &[Module]) -> Vec<Issue> {
+        let graph = DependencyGraph::build(modules);
+        detect_fat_shared_modules(modules, &graph, &Config::default())
+    }
+
+    #[test]
+    fn test_constants_only_module_is_fine() {
+        let modules = shared_config(
+            vec![
+                definition("MAX", DefinitionKind::Constant, 1),
+                definition("Settings", DefinitionKind::Struct, 3),
+                definition("default_port", DefinitionKind::Function, 10),
+            ],
+            12,
+        );
+        assert!(detect(&modules).is_empty());
+    }
+
+    #[test]
+    fn test_constants_mixed_with_logic_flagged() {
+        let modules = shared_config(
+            vec![
+                definition("MAX", DefinitionKind::Constant, 1),
+                definition("Settings", DefinitionKind::Struct, 3),
+                definition("load", DefinitionKind::Function, 10),
+                definition("merge", DefinitionKind::Function, 40),
+                definition("validate", DefinitionKind::Function, 70),
+            ],
+            100,
+        );
+        let issues = detect(&modules);
+        assert_eq!(issues.len(), 1);
+        assert!(matches!(
+            issues[0].kind,
+            crate::model::IssueKind::FatSharedModule {
+                fan_in: 5,
+                functions: 3
+            }
+        ));
+    }
+
+    #[test]
+    fn test_percentile_threshold_scales_with_the_project() {
+        // src/core.rs has fan-in 4, src/helper.rs 1, the other eight none
+        let mut modules = vec![
+            Module::new(PathBuf::from("src/core.rs")),
+            Module::new(PathBuf::from("src/helper.rs")),
+        ];
+        for i in 0..8 {
+            let mut module = Module::new(PathBuf::from(format!("src/m{}.rs", i)));
+            if i < 4 {
+                module.imports = vec!["crate::core::run".into()];
+            }
+            if i == 0 {
+                module.imports.push("crate::helper::help".into());
+            }
+            modules.push(module);
+        }
+        let graph = DependencyGraph::build(&modules);
+        let flagged = |percentile| {
+            let mut config = Config::default();
+            config.thresholds.coupling_fanin_percentile = percentile;
+            let mut paths: Vec<_> = detect_high_coupling(&graph, &config)
+                .into_iter()
+                .flat_map(|issue| issue.locations)
+                .map(|location| location.path)
+                .collect();
+            paths.sort();
+            paths
+        };
+
+        // A fan-in of 4 is under the absolute default of 5
+        assert!(flagged(None).is_empty());
+        assert_eq!(flagged(Some(90.0)), [PathBuf::from("src/core.rs")]);
+        assert_eq!(
+            flagged(Some(80.0)),
+            [PathBuf::from("src/core.rs"), PathBuf::from("src/helper.rs")]
+        );
+    }
+}
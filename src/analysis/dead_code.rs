@@ -0,0 +1,136 @@
+use super::complexity::is_test_file;
+use crate::fs::FileSystem;
+use crate::model::{DefinitionKind, Issue, Module, Visibility};
+use std::collections::{HashMap, HashSet};
+
+/// Names that are called by the runtime rather than by project code
+const ENTRY_POINTS: &[&str] = &["main"];
+
+/// Flag public functions whose name occurs exactly once in the whole project:
+/// at their own definition. Anything importing or calling one has to spell its
+/// name, so a single occurrence means neither other modules nor the defining
+/// module use it. The scan is textual, so uses through macros, reflection or
+/// callers outside the project are missed; the check is opt-in for that reason.
+pub fn detect_dead_public_functions(modules: &[Module], fs: &dyn FileSystem) -> Vec<Issue> {
+    let is_candidate = |module: &Module| !is_test_file(&module.path);
+    let candidates: HashSet<&str> = modules
+        .iter()
+        .filter(|m| is_candidate(m))
+        .flat_map(public_functions)
+        .map(|(name, _)| name)
+        .filter(|name| !ENTRY_POINTS.contains(name))
+        .collect();
+    if candidates.is_empty() {
+        return Vec::new();
+    }
+
+    // Occurrences of each candidate name across every module, tests included
+    let mut occurrences: HashMap<&str, usize> = HashMap::new();
+    for module in modules {
+        let Ok(source) = fs.read_to_string(&module.path) else {
+            continue;
+        };
+        for word in identifiers(&source) {
+            if let Some(name) = candidates.get(word) {
+                *occurrences.entry(name).or_default() += 1;
+            }
+        }
+    }
+
+    let mut issues = Vec::new();
+    let mut sorted: Vec<&Module> = modules.iter().filter(|m| is_candidate(m)).collect();
+    sorted.sort_by(|a, b| a.path.cmp(&b.path));
+    for module in sorted {
+        for (name, line) in public_functions(module) {
+            // Unreadable sources count zero; only a confirmed single mention is dead
+            if candidates.contains(name) && occurrences.get(name) == Some(&1) {
+                issues.push(Issue::dead_public_function(
+                    module.path.clone(),
+                    name.to_string(),
+                    line,
+                ));
+            }
+        }
+    }
+
+    issues
+}
+
+fn public_functions(module: &Module) -> impl Iterator<Item = (&str, usize)> {
+    module
+        .definitions
+        .iter()
+        .filter(|d| d.kind == DefinitionKind::Function && d.visibility == Visibility::Public)
+        .map(|d| (d.name.as_str(), d.line))
+}
+
+/// Identifier-like words in source text, comments and strings included
+fn identifiers(source: &str) -> impl Iterator<Item = &str> {
+    source
+        .split(|c: char| !(c.is_alphanumeric() || c == '_' || c == '$'))
+        .filter(|word| !word.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fs::mock::MockFs;
+    use crate::model::{Definition, IssueKind};
+    use std::path::PathBuf;
+
+    fn module(path: &str, functions: &[(&str, Visibility)]) -> Module {
+        let mut module = Module::new(PathBuf::from(path));
+        for (i, (name, visibility)) in functions.iter().enumerate() {
+            module.add_definition(Definition {
+                name: name.to_string(),
+                kind: DefinitionKind::Function,
+                line: i + 1,
+                visibility: *visibility,
+                signature: None,
+                span: None,
+                compact_signature: None,
+                decorators: Vec::new(),
+            });
+        }
+        module
+    }
+
+    #[test]
+    fn test_flags_public_functions_named_only_once() {
+        let fs = MockFs::with_files([
+            (
+                "/demo/src/util.rs",
+                "pub fn used_elsewhere() {}\npub fn used_locally() {}\npub fn orphan() {}\n\
+                 fn private() { used_locally() }\n",
+            ),
+            (
+                "/demo/src/app.rs",
+                "use crate::util::used_elsewhere;\npub fn main() { used_elsewhere() }\n",
+            ),
+            ("/demo/tests/helpers.rs", "pub fn fixture() {}\n"),
+        ]);
+        let modules = vec![
+            module(
+                "/demo/src/util.rs",
+                &[
+                    ("used_elsewhere", Visibility::Public),
+                    ("used_locally", Visibility::Public),
+                    ("orphan", Visibility::Public),
+                    ("private", Visibility::Private),
+                ],
+            ),
+            module("/demo/src/app.rs", &[("main", Visibility::Public)]),
+            module("/demo/tests/helpers.rs", &[("fixture", Visibility::Public)]),
+        ];
+
+        let issues = detect_dead_public_functions(&modules, &fs);
+        assert_eq!(issues.len(), 1);
+        assert_eq!(
+            issues[0].kind,
+            IssueKind::DeadPublicFunction {
+                function: "orphan".to_string()
+            }
+        );
+        assert_eq!(issues[0].locations[0].line, Some(3));
+    }
+}
@@ -0,0 +1,282 @@
+//! The detectors analysis runs, by name. `run_detectors` runs every enabled one
+//! in a [`DetectorSet`]; `archmap check --only` picks a subset of the built-in
+//! ones by the same names. Library users can add their own by implementing
+//! [`Detector`].
+
+use super::boundary::detect_boundary_violations_with_crossings;
+use super::{
+    Crossings, DependencyGraph, detect_circular_dependencies, detect_dead_public_functions,
+    detect_deep_dependency_chains, detect_empty_tests, detect_fat_modules,
+    detect_fat_shared_modules, detect_god_objects, detect_god_packages, detect_high_coupling,
+    detect_low_cohesion, detect_role_violations, detect_scattered_implementations,
+    detect_too_many_imports,
+};
+use crate::config::Config;
+use crate::fs::FileSystem;
+use crate::model::{Issue, IssueKind, Module};
+use std::collections::HashMap;
+use std::fmt;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+/// Everything a detector may look at
+pub struct DetectorInput<'a> {
+    pub modules: &'a [Module],
+    pub graph: &'a DependencyGraph,
+    pub config: &'a Config,
+    pub fs: &'a dyn FileSystem,
+    /// Boundary crossings discovery found in each file, so the boundary
+    /// check needn't read them again; `None` reads every file
+    pub crossings: Option<&'a HashMap<PathBuf, Crossings>>,
+}
+
+/// A check run over the parsed modules
+pub trait Detector: Send + Sync {
+    /// Short name, reported in [`Progress`](super::Progress) steps
+    fn name(&self) -> &str;
+
+    /// Issue kind it reports, as in [`IssueKind::NAMES`], so `disabled_checks`
+    /// and `enabled_checks` apply to it. Detectors without one always run.
+    fn kind(&self) -> Option<&str> {
+        None
+    }
+
+    fn run(&self, input: &DetectorInput<'_>) -> Vec<Issue>;
+}
+
+/// One of archmap's own checks, reporting a single issue kind
+#[derive(Clone, Copy)]
+pub struct BuiltinDetector {
+    /// Short name accepted by `check --only`
+    pub name: &'static str,
+    /// Issue kind it reports, as in [`IssueKind::NAMES`]
+    pub kind: &'static str,
+    pub detect: fn(&DetectorInput<'_>) -> Vec<Issue>,
+}
+
+impl Detector for BuiltinDetector {
+    fn name(&self) -> &str {
+        self.name
+    }
+
+    fn kind(&self) -> Option<&str> {
+        Some(self.kind)
+    }
+
+    fn run(&self, input: &DetectorInput<'_>) -> Vec<Issue> {
+        (self.detect)(input)
+    }
+}
+
+/// The detectors an analysis runs, in order. The default holds every built-in
+/// detector; push your own onto it, or start from [`DetectorSet::empty`].
+#[derive(Clone)]
+pub struct DetectorSet {
+    detectors: Vec<Arc<dyn Detector>>,
+}
+
+impl DetectorSet {
+    /// Every built-in detector, in the order analysis runs them
+    pub fn builtin() -> Self {
+        Self {
+            detectors: DETECTORS
+                .iter()
+                .map(|d| Arc::new(*d) as Arc<dyn Detector>)
+                .collect(),
+        }
+    }
+
+    pub fn empty() -> Self {
+        Self {
+            detectors: Vec::new(),
+        }
+    }
+
+    /// Run `detector` after the ones already in the set
+    pub fn push(&mut self, detector: Box<dyn Detector>) {
+        self.detectors.push(Arc::from(detector));
+    }
+
+    /// Like [`DetectorSet::push`], for chaining
+    pub fn with(mut self, detector: impl Detector + 'static) -> Self {
+        self.push(Box::new(detector));
+        self
+    }
+
+    /// Drop the detectors with this name or issue kind, returning whether any were
+    pub fn remove(&mut self, name: &str) -> bool {
+        let before = self.detectors.len();
+        self.detectors
+            .retain(|d| d.name() != name && d.kind() != Some(name));
+        self.detectors.len() != before
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &dyn Detector> {
+        self.detectors.iter().map(|d| d.as_ref())
+    }
+
+    pub fn len(&self) -> usize {
+        self.detectors.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.detectors.is_empty()
+    }
+}
+
+impl Default for DetectorSet {
+    fn default() -> Self {
+        Self::builtin()
+    }
+}
+
+impl fmt::Debug for DetectorSet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list()
+            .entries(self.detectors.iter().map(|d| d.name()))
+            .finish()
+    }
+}
+
+/// Every built-in detector, in the order analysis runs them
+pub const DETECTORS: &[BuiltinDetector] = &[
+    BuiltinDetector {
+        name: "cycles",
+        kind: "CircularDependency",
+        detect: |input| detect_circular_dependencies(input.graph),
+    },
+    BuiltinDetector {
+        name: "god-objects",
+        kind: "GodObject",
+        detect: |input| detect_god_objects(input.modules, input.config),
+    },
+    BuiltinDetector {
+        name: "coupling",
+        kind: "HighCoupling",
+        detect: |input| detect_high_coupling(input.graph, input.config),
+    },
+    BuiltinDetector {
+        name: "boundaries",
+        kind: "BoundaryViolation",
+        detect: |input| {
+            detect_boundary_violations_with_crossings(
+                input.modules,
+                input.config,
+                input.fs,
+                input.crossings,
+            )
+        },
+    },
+    BuiltinDetector {
+        name: "depth",
+        kind: "DeepDependencyChain",
+        detect: |input| detect_deep_dependency_chains(input.graph, input.config),
+    },
+    BuiltinDetector {
+        name: "cohesion",
+        kind: "LowCohesion",
+        detect: |input| detect_low_cohesion(input.modules, input.graph, input.config, input.fs),
+    },
+    BuiltinDetector {
+        name: "fat-modules",
+        kind: "FatModule",
+        detect: |input| detect_fat_modules(input.modules, input.config),
+    },
+    // Widely shared constants/types modules that also carry logic
+    BuiltinDetector {
+        name: "fat-shared-modules",
+        kind: "FatSharedModule",
+        detect: |input| detect_fat_shared_modules(input.modules, input.graph, input.config),
+    },
+    // Traits/interfaces implemented all over the codebase
+    BuiltinDetector {
+        name: "scattered-implementations",
+        kind: "ScatteredImplementation",
+        detect: |input| detect_scattered_implementations(input.modules, input.config),
+    },
+    // Public functions nothing refers to (opt-in)
+    BuiltinDetector {
+        name: "dead-functions",
+        kind: "DeadPublicFunction",
+        detect: |input| detect_dead_public_functions(input.modules, input.fs),
+    },
+    BuiltinDetector {
+        name: "imports",
+        kind: "TooManyImports",
+        detect: |input| detect_too_many_imports(input.modules, input.config),
+    },
+    // Top-level directories grown too large
+    BuiltinDetector {
+        name: "god-packages",
+        kind: "GodPackage",
+        detect: |input| detect_god_packages(input.modules, input.graph, input.config),
+    },
+    // Tests left behind by the code they tested
+    BuiltinDetector {
+        name: "empty-tests",
+        kind: "EmptyTest",
+        detect: |input| detect_empty_tests(input.modules, input.graph, input.config),
+    },
+    // Framework classes (@Component, @Controller) reaching past their service layer
+    BuiltinDetector {
+        name: "framework-roles",
+        kind: "RoleViolation",
+        detect: |input| detect_role_violations(input.modules, input.graph),
+    },
+];
+
+/// Look up a detector by its short name or by the issue kind it reports, in
+/// any of the spellings [`IssueKind::canonical_name`] accepts.
+pub fn find_detector(name: &str) -> Option<&'static BuiltinDetector> {
+    let short = name.trim().to_ascii_lowercase().replace('_', "-");
+    let kind = IssueKind::canonical_name(name);
+    DETECTORS
+        .iter()
+        .find(|d| d.name == short || Some(d.kind) == kind)
+}
+
+/// A copy of `config` under which analysis runs just the `selected` detectors,
+/// opt-in ones included, whatever `disabled_checks` and `enabled_checks` said.
+pub fn run_only(config: &Config, selected: &[&BuiltinDetector]) -> Config {
+    let is_selected = |d: &BuiltinDetector| selected.iter().any(|s| s.kind == d.kind);
+    let mut config = config.clone();
+    config.disabled_checks = DETECTORS
+        .iter()
+        .filter(|d| !is_selected(d))
+        .map(|d| d.kind.to_string())
+        .collect();
+    config.enabled_checks = selected
+        .iter()
+        .filter(|d| IssueKind::OPT_IN.contains(&d.kind))
+        .map(|d| d.kind.to_string())
+        .collect();
+    config
+}
+
+/// Short names of every detector, for error messages
+pub fn detector_names() -> Vec<&'static str> {
+    DETECTORS.iter().map(|d| d.name).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_every_issue_kind_has_one_detector() {
+        for kind in IssueKind::NAMES {
+            assert_eq!(
+                DETECTORS.iter().filter(|d| d.kind == kind).count(),
+                1,
+                "{}",
+                kind
+            );
+        }
+        assert_eq!(DETECTORS.len(), IssueKind::NAMES.len());
+
+        assert_eq!(find_detector("cycles").unwrap().kind, "CircularDependency");
+        assert_eq!(find_detector("Fat_Modules").unwrap().kind, "FatModule");
+        assert_eq!(find_detector("low-cohesion").unwrap().name, "cohesion");
+        assert!(find_detector("spaghetti").is_none());
+    }
+}
@@ -0,0 +1,86 @@
+//! Test files that exercise no production code.
+//!
+//! The flip side of untested code: a test whose imports resolve only to other
+//! tests, or to nothing in the project, is usually left over from code that
+//! has since been deleted.
+
+use super::DependencyGraph;
+use super::complexity::is_test_file;
+use crate::config::Config;
+use crate::model::{Issue, Module, glob_match};
+
+/// Flag test modules none of whose resolved imports is a production module.
+/// Files matching `test_harness_paths` (shared fixtures, `conftest.py`, test
+/// entry points) are exempt, since importing only test code is their job.
+pub fn detect_empty_tests(
+    modules: &[Module],
+    graph: &DependencyGraph,
+    config: &Config,
+) -> Vec<Issue> {
+    let is_harness = |module: &Module| {
+        let path = module.path.to_string_lossy();
+        config
+            .test_harness_paths
+            .iter()
+            .any(|pattern| glob_match(pattern, &path))
+    };
+
+    let mut issues = Vec::new();
+    for module in modules {
+        if !is_test_file(&module.path) || is_harness(module) {
+            continue;
+        }
+        let imported = graph.direct_dependencies(&module.path);
+        if imported.iter().all(|dep| is_test_file(dep)) {
+            issues.push(Issue::empty_test(module.path.clone(), imported.len()));
+        }
+    }
+
+    issues.sort_by(|a, b| a.locations[0].path.cmp(&b.locations[0].path));
+    issues
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::IssueKind;
+    use std::path::{Path, PathBuf};
+
+    fn module(path: &str, imports: &[&str]) -> Module {
+        let mut module = Module::new(PathBuf::from(path));
+        module.imports = imports.iter().map(|&i| i.into()).collect();
+        module
+    }
+
+    #[test]
+    fn test_flags_tests_importing_no_production_code() {
+        let modules = vec![
+            module("/p/src/orders.ts", &[]),
+            module("/p/src/orders.test.ts", &["./orders"]),
+            module("/p/tests/fixtures/data.ts", &[]),
+            module("/p/tests/stale.test.ts", &["./fixtures/data"]),
+            module("/p/tests/orphan.test.ts", &["../src/deleted"]),
+            module("/p/tests/conftest.py", &[]),
+        ];
+        let config = Config {
+            test_harness_paths: vec!["**/conftest.py".to_string(), "**/fixtures/**".to_string()],
+            ..Default::default()
+        };
+        let graph = DependencyGraph::build(&modules);
+        let issues = detect_empty_tests(&modules, &graph, &config);
+
+        let flagged: Vec<_> = issues
+            .iter()
+            .map(|i| i.locations[0].path.as_path())
+            .collect();
+        assert_eq!(
+            flagged,
+            [
+                Path::new("/p/tests/orphan.test.ts"),
+                Path::new("/p/tests/stale.test.ts")
+            ]
+        );
+        assert!(issues.iter().all(|i| i.kind == IssueKind::EmptyTest));
+        assert!(issues[1].message.contains("1 test module(s)"));
+    }
+}
@@ -0,0 +1,169 @@
+//! Evaluation of `[expectations]`: explicit, named architectural assertions
+//! (unlike issue detection, which applies heuristics everywhere).
+
+use crate::config::{Expectations, LayerRule};
+use crate::model::{AnalysisResult, IssueKindFilter, glob_match};
+use std::path::Path;
+
+/// Outcome of a single expectation
+#[derive(Debug, Clone)]
+pub struct ExpectationResult {
+    pub name: String,
+    pub passed: bool,
+    /// What was measured (e.g. "1 cycle")
+    pub actual: String,
+    /// What was required (e.g. "at most 2")
+    pub expected: String,
+    /// Offending items, such as forbidden dependency edges
+    pub details: Vec<String>,
+}
+
+/// Evaluate every configured expectation against an analysis result.
+pub fn evaluate_expectations(
+    result: &AnalysisResult,
+    expectations: &Expectations,
+    project_root: &Path,
+) -> Vec<ExpectationResult> {
+    let mut results = Vec::new();
+
+    if let Some(max) = expectations.max_cycles {
+        let cycles = result
+            .issues_of_kind(IssueKindFilter::CircularDependency)
+            .count();
+        results.push(limit_result("max_cycles", cycles, max, "cycle"));
+    }
+
+    if let Some(max) = expectations.max_god_objects {
+        let god_objects = result.issues_of_kind(IssueKindFilter::GodObject).count();
+        results.push(limit_result(
+            "max_god_objects",
+            god_objects,
+            max,
+            "god object",
+        ));
+    }
+
+    for rule in &expectations.layers {
+        results.push(evaluate_layer(result, rule, project_root));
+    }
+
+    results
+}
+
+fn limit_result(name: &str, actual: usize, max: usize, noun: &str) -> ExpectationResult {
+    ExpectationResult {
+        name: name.to_string(),
+        passed: actual <= max,
+        actual: plural(actual, noun),
+        expected: format!("at most {}", max),
+        details: Vec::new(),
+    }
+}
+
+fn evaluate_layer(result: &AnalysisResult, rule: &LayerRule, root: &Path) -> ExpectationResult {
+    let relative = |path: &Path| {
+        path.strip_prefix(root)
+            .unwrap_or(path)
+            .to_string_lossy()
+            .into_owned()
+    };
+
+    let mut violations = Vec::new();
+    for module in &result.modules {
+        let from = relative(&module.path);
+        if !glob_match(&rule.from, &from) {
+            continue;
+        }
+        for dep in &module.resolved_imports {
+            let to = relative(dep);
+            if rule.deny.iter().any(|pattern| glob_match(pattern, &to)) {
+                violations.push(format!("{} → {}", from, to));
+            }
+        }
+    }
+    violations.sort();
+
+    ExpectationResult {
+        name: format!("layer {}", rule.name),
+        passed: violations.is_empty(),
+        actual: plural(violations.len(), "forbidden dependency"),
+        expected: format!(
+            "no imports from {} into {}",
+            rule.from,
+            rule.deny.join(", ")
+        ),
+        details: violations,
+    }
+}
+
+fn plural(count: usize, noun: &str) -> String {
+    match (count, noun.strip_suffix('y')) {
+        (1, _) => format!("1 {}", noun),
+        (_, Some(stem)) => format!("{} {}ies", count, stem),
+        _ => format!("{} {}s", count, noun),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{Issue, Module};
+    use std::path::PathBuf;
+
+    fn layered_result() -> AnalysisResult {
+        let mut core = Module::new(PathBuf::from("/demo/src/core/engine.rs"));
+        core.resolved_imports = vec![
+            PathBuf::from("/demo/src/app/main.rs"),
+            PathBuf::from("/demo/src/util.rs"),
+        ];
+        let app = Module::new(PathBuf::from("/demo/src/app/main.rs"));
+        let modules = vec![core, app];
+
+        AnalysisResult::new(
+            "demo".to_string(),
+            modules,
+            vec![Issue::circular_dependency(vec![
+                PathBuf::from("/demo/src/core/engine.rs"),
+                PathBuf::from("/demo/src/app/main.rs"),
+            ])],
+        )
+    }
+
+    #[test]
+    fn test_limits_report_actual_values() {
+        let expectations = Expectations {
+            max_cycles: Some(0),
+            max_god_objects: Some(1),
+            layers: Vec::new(),
+        };
+
+        let results = evaluate_expectations(&layered_result(), &expectations, Path::new("/demo"));
+        assert_eq!(results.len(), 2);
+        assert!(!results[0].passed);
+        assert_eq!(results[0].actual, "1 cycle");
+        assert_eq!(results[0].expected, "at most 0");
+        assert!(results[1].passed);
+        assert_eq!(results[1].actual, "0 god objects");
+    }
+
+    #[test]
+    fn test_layer_rule_lists_forbidden_edges() {
+        let expectations = Expectations {
+            layers: vec![LayerRule {
+                name: "core_independent".to_string(),
+                from: "src/core/**".to_string(),
+                deny: vec!["src/app/**".to_string()],
+            }],
+            ..Default::default()
+        };
+
+        let results = evaluate_expectations(&layered_result(), &expectations, Path::new("/demo"));
+        assert!(!results[0].passed);
+        assert_eq!(results[0].name, "layer core_independent");
+        assert_eq!(results[0].actual, "1 forbidden dependency");
+        assert_eq!(
+            results[0].details,
+            vec!["src/core/engine.rs → src/app/main.rs"]
+        );
+    }
+}
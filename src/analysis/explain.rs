@@ -0,0 +1,365 @@
+//! Explanations for `archmap explain`: what each check measures, the thresholds
+//! in effect, and the concrete inputs a detector used for one issue.
+//!
+//! Detectors fill an [`Explanation`] only when handed one, so normal analysis
+//! pays nothing for it.
+
+use super::{
+    DependencyGraph, ModuleComplexity, explain_boundary_violation, explain_low_cohesion,
+    fanin_threshold,
+};
+use crate::config::Config;
+use crate::fs::FileSystem;
+use crate::model::{Issue, IssueKind, Module};
+use std::path::{Path, PathBuf};
+
+/// One measured input, e.g. "Internal imports: 2" with the imports listed below it
+#[derive(Debug, Clone)]
+pub struct ExplanationInput {
+    pub label: String,
+    pub value: String,
+    pub details: Vec<String>,
+}
+
+/// The inputs behind one result, in the order the check uses them
+#[derive(Debug, Clone, Default)]
+pub struct Explanation {
+    /// Module (or boundary) the explanation is about
+    pub subject: String,
+    pub inputs: Vec<ExplanationInput>,
+    /// How the inputs combine into the outcome
+    pub verdict: String,
+    /// Stripped from paths mentioned in the inputs
+    root: PathBuf,
+}
+
+impl Explanation {
+    pub fn new(subject: impl Into<String>) -> Self {
+        Self {
+            subject: subject.into(),
+            ..Default::default()
+        }
+    }
+
+    pub fn with_root(mut self, root: &Path) -> Self {
+        self.root = root.to_path_buf();
+        self
+    }
+
+    /// Display a path relative to the project root.
+    pub fn relative(&self, path: &Path) -> String {
+        path.strip_prefix(&self.root)
+            .unwrap_or(path)
+            .display()
+            .to_string()
+    }
+
+    /// Record an input; `details` may be empty.
+    pub fn input(&mut self, label: &str, value: impl ToString, details: Vec<String>) {
+        self.inputs.push(ExplanationInput {
+            label: label.to_string(),
+            value: value.to_string(),
+            details,
+        });
+    }
+}
+
+/// What a check measures and how to tune it
+#[derive(Debug, Clone)]
+pub struct CheckDescription {
+    pub kind: &'static str,
+    pub definition: &'static str,
+    /// Config keys and their effective values
+    pub thresholds: Vec<(&'static str, String)>,
+    pub guidance: &'static str,
+}
+
+/// Describe a check by its issue kind name (as returned by [`IssueKind::canonical_name`]).
+pub fn describe_check(kind: &str, config: &Config) -> Option<CheckDescription> {
+    let t = &config.thresholds;
+    let (definition, thresholds, guidance) = match kind {
+        "CircularDependency" => (
+            "Modules that import each other, directly or through a chain, so none can be \
+             understood, tested or changed in isolation.",
+            vec![],
+            "Break the cycle by extracting the shared pieces into a module both sides \
+             depend on, or invert one edge behind a trait or interface.",
+        ),
+        "GodObject" => (
+            "A large module that mixes several responsibilities (types, free functions, \
+             traits, many impl blocks).",
+            vec![(
+                "thresholds.god_object_lines",
+                t.god_object_lines.to_string(),
+            )],
+            "Raise god_object_lines for codebases with naturally long files; otherwise \
+             split the module along the responsibilities listed in the issue.",
+        ),
+        "HighCoupling" => (
+            "A module imported by many others, so changes to it ripple widely.",
+            vec![
+                ("thresholds.coupling_fanin", t.coupling_fanin.to_string()),
+                (
+                    "thresholds.coupling_fanin_percentile",
+                    percentile_setting(t.coupling_fanin_percentile),
+                ),
+                (
+                    "expected_high_coupling",
+                    config.expected_high_coupling.join(", "),
+                ),
+            ],
+            "Add genuinely shared modules (models, config, entry points) to \
+             expected_high_coupling; otherwise narrow the interface dependents use.",
+        ),
+        "BoundaryViolation" => (
+            "Code touching an architectural boundary (database, HTTP, filesystem, ...) \
+             spread across several modules instead of behind one gateway.",
+            vec![
+                (
+                    "thresholds.boundary_violation_min",
+                    t.boundary_violation_min.to_string(),
+                ),
+                (
+                    "boundaries",
+                    config
+                        .boundaries
+                        .iter()
+                        .map(|b| b.name.as_str())
+                        .collect::<Vec<_>>()
+                        .join(", "),
+                ),
+            ],
+            "List gateway modules in the boundary's allowed_in, tune its indicators, or \
+             lower ownership_threshold so a dominant module counts as the owner.",
+        ),
+        "DeepDependencyChain" => (
+            "A long chain of imports (A → B → C → ...) where a change at the end can \
+             break everything above it.",
+            vec![(
+                "thresholds.max_dependency_depth",
+                t.max_dependency_depth.to_string(),
+            )],
+            "Raise max_dependency_depth (or pass --max-depth) for layered codebases; \
+             otherwise flatten the chain by depending on abstractions.",
+        ),
+        "LowCohesion" => (
+            "A module pulling in many different external crates or packages, suggesting \
+             scattered concerns. Score = (internal imports + 1) / (internal imports + 1 + \
+             distinct external crates); only modules using 3+ external crates are flagged. \
+             With cohesion_method = \"lcom\", score = share of function pairs whose bodies \
+             mention an import in common, for modules with 3+ such functions.",
+            vec![
+                ("thresholds.min_cohesion", t.min_cohesion.to_string()),
+                ("cohesion_method", config.cohesion_method.name().to_string()),
+            ],
+            "Lower min_cohesion (or pass --min-cohesion) if integration modules are \
+             expected; otherwise move each external concern into its own module.",
+        ),
+        "FatModule" => (
+            "A module hiding lots of private code behind a small public interface.",
+            vec![
+                (
+                    "thresholds.fat_module_lines",
+                    t.fat_module_lines.to_string(),
+                ),
+                (
+                    "thresholds.fat_module_private_functions",
+                    t.fat_module_private_functions.to_string(),
+                ),
+                (
+                    "thresholds.fat_module_lines_per_export",
+                    t.fat_module_lines_per_export.to_string(),
+                ),
+            ],
+            "Extract cohesive groups of private helpers into their own modules.",
+        ),
+        "FatSharedModule" => (
+            "A widely imported constants/types module (matching expected_high_coupling) \
+             that also carries non-trivial logic.",
+            vec![
+                ("thresholds.coupling_fanin", t.coupling_fanin.to_string()),
+                (
+                    "thresholds.coupling_fanin_percentile",
+                    percentile_setting(t.coupling_fanin_percentile),
+                ),
+                (
+                    "thresholds.shared_module_functions",
+                    t.shared_module_functions.to_string(),
+                ),
+            ],
+            "Keep shared modules to data definitions and move the functions next to \
+             their callers.",
+        ),
+        "ScatteredImplementation" => (
+            "A trait or interface defined in the project and implemented (`impl Trait for`, \
+             `implements`) in many different modules, so its behavior is hard to survey.",
+            vec![(
+                "thresholds.scattered_impl_files",
+                t.scattered_impl_files.to_string(),
+            )],
+            "Raise scattered_impl_files for plugin-style designs; otherwise group the \
+             implementations next to the trait or by feature.",
+        ),
+        "DeadPublicFunction" => (
+            "A public function whose name appears nowhere else in the project, neither in \
+             other modules nor elsewhere in its own. Opt-in, since the scan is textual and \
+             misses uses through macros, reflection or external callers.",
+            vec![(
+                "enabled_checks",
+                if config.is_check_disabled("DeadPublicFunction") {
+                    "off".to_string()
+                } else {
+                    "on".to_string()
+                },
+            )],
+            "Enable with enabled_checks = [\"DeadPublicFunction\"] or --enable, then delete \
+             the functions or make them private. A library's API meant for outside callers \
+             is a known false positive.",
+        ),
+        "TooManyImports" => (
+            "A module with a long import list. Every import counts, external crates and \
+             packages included, so it catches modules that fan-out (project modules only) \
+             misses.",
+            vec![("thresholds.max_imports", t.max_imports.to_string())],
+            "Raise max_imports for glue code such as entry points; otherwise split the \
+             module along the groups of imports its parts use.",
+        ),
+        "GodPackage" => (
+            "A top-level directory (one level below `src`, `lib`, `app` or `packages`) \
+             holding so many modules or lines that it has become a dumping ground.",
+            vec![
+                (
+                    "thresholds.god_package_modules",
+                    t.god_package_modules.to_string(),
+                ),
+                (
+                    "thresholds.god_package_lines",
+                    t.god_package_lines.to_string(),
+                ),
+            ],
+            "Raise the thresholds for directories that are large by design, such as \
+             generated code; otherwise split the directory into subpackages along the \
+             groups of modules that import each other.",
+        ),
+        "EmptyTest" => (
+            "A test file whose resolved imports reach no production module, only other \
+             tests or nothing in the project at all. Usually the code it tested was deleted \
+             and the test was left behind.",
+            vec![(
+                "test_harness_paths",
+                format!("{} pattern(s)", config.test_harness_paths.len()),
+            )],
+            "Delete the test or point it at the code it should exercise. Add shared \
+             fixtures and harness files, which import only test code by design, to \
+             test_harness_paths.",
+        ),
+        "RoleViolation" => (
+            "A module whose framework role, taken from a class decorator (`@Component`, \
+             `@Controller`, `@Repository`, ...) or a `role` tag, imports a module of a role \
+             it should only reach through a service: a component, directive, pipe or \
+             controller importing a repository.",
+            Vec::new(),
+            "Move the data access into an @Injectable service and inject it. Tag a module \
+             with `// @archmap: role=service` if its decorator misstates its role.",
+        ),
+        _ => return None,
+    };
+
+    Some(CheckDescription {
+        kind: IssueKind::canonical_name(kind)?,
+        definition,
+        thresholds,
+        guidance,
+    })
+}
+
+/// `coupling_fanin_percentile` as shown by `explain`; when unset,
+/// `coupling_fanin` applies
+fn percentile_setting(percentile: Option<f64>) -> String {
+    percentile.map_or_else(|| "unset".to_string(), |p| p.to_string())
+}
+
+/// Re-derive the inputs behind an issue from the analyzed modules.
+pub fn explain_issue(
+    issue: &Issue,
+    modules: &[Module],
+    graph: &DependencyGraph,
+    config: &Config,
+    fs: &dyn FileSystem,
+    root: &Path,
+) -> Explanation {
+    let relative = |path: &Path| {
+        path.strip_prefix(root)
+            .unwrap_or(path)
+            .display()
+            .to_string()
+    };
+    let module = issue
+        .locations
+        .first()
+        .and_then(|loc| modules.iter().find(|m| m.path == loc.path));
+
+    let detailed = match (&issue.kind, module) {
+        (IssueKind::LowCohesion { .. }, Some(module)) => {
+            Some(explain_low_cohesion(module, modules, config, fs, root))
+        }
+        (IssueKind::BoundaryViolation { boundary_name }, _) => {
+            explain_boundary_violation(boundary_name, modules, config, fs, root)
+        }
+        (IssueKind::HighCoupling, Some(module)) => {
+            let mut explanation = Explanation::new(relative(&module.path));
+            let dependents: Vec<String> = graph
+                .direct_dependents(&module.path)
+                .iter()
+                .map(|p| relative(p))
+                .collect();
+            explanation.input("Dependents (fan-in)", dependents.len(), dependents);
+            let fan_in = graph.fan_in(&module.path);
+            explanation.verdict = match config.thresholds.coupling_fanin_percentile {
+                Some(percentile) => format!(
+                    "fan-in {} is above the {}th percentile (flagged from {})",
+                    fan_in,
+                    percentile,
+                    fanin_threshold(graph, config)
+                ),
+                None => format!(
+                    "fan-in {} exceeds coupling_fanin {}",
+                    fan_in, config.thresholds.coupling_fanin
+                ),
+            };
+            Some(explanation)
+        }
+        (IssueKind::FatModule { .. }, Some(module)) => {
+            let c = ModuleComplexity::compute(module);
+            let mut explanation = Explanation::new(relative(&module.path));
+            explanation.input("Lines", c.lines, vec![]);
+            explanation.input("Private functions", c.private_functions, vec![]);
+            explanation.input("Public functions", c.public_functions, vec![]);
+            explanation.input("Exports", c.exports, vec![]);
+            explanation.input(
+                "Lines per export",
+                format!("{:.0}", c.lines_per_export),
+                vec![],
+            );
+            explanation.verdict = issue.message.clone();
+            Some(explanation)
+        }
+        _ => None,
+    };
+
+    detailed.unwrap_or_else(|| {
+        let subject = module.map(|m| relative(&m.path)).unwrap_or_default();
+        let mut explanation = Explanation::new(subject);
+        let locations: Vec<String> = issue
+            .locations
+            .iter()
+            .map(|loc| match loc.line {
+                Some(line) => format!("{}:{}", relative(&loc.path), line),
+                None => relative(&loc.path),
+            })
+            .collect();
+        explanation.input("Locations", locations.len(), locations);
+        explanation.verdict = issue.message.clone();
+        explanation
+    })
+}
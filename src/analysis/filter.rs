@@ -0,0 +1,117 @@
+//! Include/exclude glob filtering for narrowing the set of analyzed files.
+
+use crate::model::glob_match;
+use std::collections::HashMap;
+use std::path::Path;
+
+/// Include and exclude glob patterns, matched against paths relative to the project root.
+///
+/// A path is selected when it matches no exclude pattern and, if any include patterns
+/// are given, at least one of them. A pattern naming a directory (e.g. `migrations`)
+/// also matches everything beneath it.
+///
+/// `tags` further narrows the selection to files whose `@archmap:` annotations carry
+/// every listed `key=value` pair (a bare `key` matches any value).
+#[derive(Debug, Clone, Default)]
+pub struct PathFilter {
+    pub include: Vec<String>,
+    pub exclude: Vec<String>,
+    pub tags: Vec<String>,
+}
+
+impl PathFilter {
+    pub fn new(include: Vec<String>, exclude: Vec<String>) -> Self {
+        Self {
+            include,
+            exclude,
+            tags: Vec::new(),
+        }
+    }
+
+    /// Require these module tags (`key=value` or bare `key`).
+    pub fn with_tags(mut self, tags: Vec<String>) -> Self {
+        self.tags = tags;
+        self
+    }
+
+    /// Check whether a module's tags satisfy every required tag.
+    pub fn matches_tags(&self, tags: &HashMap<String, String>) -> bool {
+        self.tags
+            .iter()
+            .all(|required| match required.split_once('=') {
+                Some((key, value)) => tags.get(key.trim()).is_some_and(|v| v == value.trim()),
+                None => tags.contains_key(required.trim()),
+            })
+    }
+
+    /// True when no patterns are configured, so every path is selected.
+    pub fn is_empty(&self) -> bool {
+        self.include.is_empty() && self.exclude.is_empty()
+    }
+
+    /// Check whether a path passes the filter. `root` is stripped before matching.
+    pub fn matches(&self, path: &Path, root: Option<&Path>) -> bool {
+        if self.is_empty() {
+            return true;
+        }
+
+        let rel = match root {
+            Some(r) => path.strip_prefix(r).unwrap_or(path),
+            None => path,
+        };
+        let rel = rel.to_string_lossy();
+
+        if self.exclude.iter().any(|p| pattern_matches(p, &rel)) {
+            return false;
+        }
+
+        self.include.is_empty() || self.include.iter().any(|p| pattern_matches(p, &rel))
+    }
+}
+
+fn pattern_matches(pattern: &str, path: &str) -> bool {
+    let dir_pattern = format!("{}/**", pattern.trim_end_matches('/'));
+    glob_match(pattern, path) || glob_match(&dir_pattern, path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_empty_filter_selects_everything() {
+        let filter = PathFilter::default();
+        assert!(filter.matches(Path::new("/repo/src/lib.rs"), Some(Path::new("/repo"))));
+    }
+
+    #[test]
+    fn test_exclude_and_include() {
+        let root = Path::new("/repo");
+        let filter = PathFilter::new(vec!["src/**".to_string()], vec!["migrations".to_string()]);
+
+        assert!(filter.matches(Path::new("/repo/src/lib.rs"), Some(root)));
+        assert!(!filter.matches(Path::new("/repo/migrations/001.py"), Some(root)));
+        assert!(!filter.matches(Path::new("/repo/scripts/build.py"), Some(root)));
+
+        let filter = PathFilter::new(vec![], vec!["**/generated/**".to_string()]);
+        assert!(!filter.matches(Path::new("/repo/src/generated/api.ts"), Some(root)));
+        assert!(filter.matches(Path::new("/repo/src/api.ts"), Some(root)));
+    }
+
+    #[test]
+    fn test_tag_filter() {
+        let tags: HashMap<String, String> = [("layer", "domain"), ("owner", "payments")]
+            .into_iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect();
+
+        assert!(PathFilter::default().matches_tags(&HashMap::new()));
+
+        let filter = PathFilter::default().with_tags(vec!["layer=domain".to_string()]);
+        assert!(filter.matches_tags(&tags));
+        assert!(!filter.matches_tags(&HashMap::new()));
+
+        let filter = filter.with_tags(vec!["owner".to_string(), "layer=infra".to_string()]);
+        assert!(!filter.matches_tags(&tags));
+    }
+}
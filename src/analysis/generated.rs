@@ -0,0 +1,106 @@
+//! Detection of generated source files (protobuf stubs, OpenAPI clients, build output).
+//!
+//! Generated files stay in the module list and dependency graph so imports resolve,
+//! but issues touching only generated files are dropped unless `include_generated`
+//! is set - nobody wants a god-object warning on a 10k-line generated client.
+
+use crate::config::Config;
+use crate::model::{Issue, glob_match};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+/// Number of leading lines scanned for a generated-code marker
+const HEADER_LINES: usize = 10;
+
+/// Check whether a file looks generated, by path pattern or by a marker in its header.
+pub fn is_generated(path: &Path, source: &str, config: &Config) -> bool {
+    let path_str = path.to_string_lossy();
+    if config
+        .generated_paths
+        .iter()
+        .any(|pattern| glob_match(pattern, &path_str))
+    {
+        return true;
+    }
+
+    source.lines().take(HEADER_LINES).any(|line| {
+        config
+            .generated_markers
+            .iter()
+            .any(|marker| line.contains(marker.as_str()))
+    })
+}
+
+/// Drop issues whose locations all fall in generated files.
+pub fn drop_generated_issues(issues: &mut Vec<Issue>, generated: &[PathBuf]) {
+    if generated.is_empty() {
+        return;
+    }
+    let generated: HashSet<&PathBuf> = generated.iter().collect();
+    issues.retain(|issue| {
+        issue.locations.is_empty()
+            || issue
+                .locations
+                .iter()
+                .any(|loc| !generated.contains(&loc.path))
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detects_header_markers() {
+        let config = Config::default();
+        let path = Path::new("/repo/src/client.rs");
+
+        assert!(is_generated(
+            path,
+            "// @generated by protoc-gen-rust\npub struct Req;",
+            &config
+        ));
+        assert!(is_generated(
+            Path::new("/repo/api/client.py"),
+            "# Code generated by openapi-generator. DO NOT EDIT.\nimport os",
+            &config
+        ));
+        assert!(!is_generated(path, "pub struct Handwritten;", &config));
+    }
+
+    #[test]
+    fn test_detects_generated_paths() {
+        let config = Config::default();
+        assert!(is_generated(
+            Path::new("/repo/proto/user_pb2.py"),
+            "",
+            &config
+        ));
+        assert!(is_generated(
+            Path::new("/repo/src/__generated__/schema.ts"),
+            "",
+            &config
+        ));
+    }
+
+    #[test]
+    fn test_drops_issues_only_in_generated_files() {
+        let generated = vec![PathBuf::from("/repo/gen.rs")];
+        let mut issues = vec![
+            Issue::god_object(PathBuf::from("/repo/gen.rs"), 10_000, vec![]),
+            Issue::god_object(PathBuf::from("/repo/lib.rs"), 900, vec![]),
+            Issue::circular_dependency(vec![
+                PathBuf::from("/repo/gen.rs"),
+                PathBuf::from("/repo/lib.rs"),
+            ]),
+        ];
+
+        drop_generated_issues(&mut issues, &generated);
+        assert_eq!(issues.len(), 2);
+        assert!(
+            issues
+                .iter()
+                .all(|i| i.locations.iter().any(|l| l.path.ends_with("lib.rs")))
+        );
+    }
+}
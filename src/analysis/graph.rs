@@ -1,16 +1,108 @@
-use crate::model::Module;
+use crate::fs::normalize_path;
+use crate::model::{AnalysisResult, Module, Symbol};
 use petgraph::Direction;
-use petgraph::algo::toposort;
+use petgraph::algo::{tarjan_scc, toposort};
 use petgraph::graph::{DiGraph, NodeIndex};
+use petgraph::visit::EdgeRef;
+use serde::{Deserialize, Serialize};
+use std::cell::Cell;
 use std::collections::{HashMap, HashSet, VecDeque};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
 
+/// Serialized as an [`AdjacencyList`]
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(into = "AdjacencyList", from = "AdjacencyList")]
 pub struct DependencyGraph {
-    graph: DiGraph<PathBuf, ()>,
+    graph: DiGraph<PathBuf, EdgeInfo>,
     node_indices: HashMap<PathBuf, NodeIndex>,
+    /// Betweenness scores, computed on the first [`DependencyGraph::betweenness_centrality`] call
+    betweenness: OnceLock<HashMap<PathBuf, f64>>,
+}
+
+/// Why one module depends on another
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct EdgeInfo {
+    /// The import, as written in the source, that resolved to the dependency
+    pub import: Symbol,
+}
+
+/// The serialized form of a [`DependencyGraph`]: every module path, and for
+/// each one the indices into `nodes` of the modules it depends on, alongside
+/// the imports behind those edges
+#[derive(Serialize, Deserialize)]
+struct AdjacencyList {
+    nodes: Vec<PathBuf>,
+    dependencies: Vec<Vec<usize>>,
+    #[serde(default)]
+    imports: Vec<Vec<Symbol>>,
+}
+
+impl From<DependencyGraph> for AdjacencyList {
+    fn from(dependency_graph: DependencyGraph) -> Self {
+        let graph = dependency_graph.into_inner();
+        let (dependencies, imports) = graph
+            .node_indices()
+            .map(|idx| {
+                // Petgraph lists the newest edge first
+                let mut edges: Vec<_> = graph.edges(idx).collect();
+                edges.reverse();
+                edges
+                    .into_iter()
+                    .map(|e| (e.target().index(), e.weight().import.clone()))
+                    .unzip::<_, _, Vec<_>, Vec<_>>()
+            })
+            .unzip();
+        let (nodes, _) = graph.into_nodes_edges();
+        Self {
+            nodes: nodes.into_iter().map(|node| node.weight).collect(),
+            dependencies,
+            imports,
+        }
+    }
+}
+
+impl From<AdjacencyList> for DependencyGraph {
+    fn from(list: AdjacencyList) -> Self {
+        let mut graph = DiGraph::new();
+        let mut node_indices = HashMap::new();
+        for path in list.nodes {
+            let idx = graph.add_node(path.clone());
+            node_indices.insert(path, idx);
+        }
+        for (from, targets) in list.dependencies.iter().enumerate() {
+            for (i, &to) in targets.iter().enumerate() {
+                // Indices past the node list come from a damaged file; skip them
+                if from < graph.node_count() && to < graph.node_count() {
+                    let import = list
+                        .imports
+                        .get(from)
+                        .and_then(|imports| imports.get(i))
+                        .cloned()
+                        .unwrap_or_default();
+                    graph.add_edge(
+                        NodeIndex::new(from),
+                        NodeIndex::new(to),
+                        EdgeInfo { import },
+                    );
+                }
+            }
+        }
+        Self {
+            graph,
+            node_indices,
+            betweenness: OnceLock::new(),
+        }
+    }
 }
 
 impl DependencyGraph {
+    /// Rebuild the graph from a result's modules and their imports, e.g. when
+    /// only the modules of a serialized result were kept.
+    pub fn from_result(result: &AnalysisResult) -> Self {
+        Self::build(&result.modules)
+    }
+
     pub fn build(modules: &[Module]) -> Self {
         let mut graph = DiGraph::new();
         let mut node_indices = HashMap::new();
@@ -20,8 +112,19 @@ impl DependencyGraph {
             let idx = graph.add_node(module.path.clone());
             node_indices.insert(module.path.clone(), idx);
         }
+        // Symlinked paths lead to the module they alias, so edges through
+        // every link land on one node
+        let aliases: HashMap<&Path, &Path> = modules
+            .iter()
+            .flat_map(|m| {
+                m.aliases
+                    .iter()
+                    .map(|alias| (alias.as_path(), m.path.as_path()))
+            })
+            .collect();
 
         // Add edges based on imports
+        let crate_paths = CratePaths::new(modules);
         for module in modules {
             let from_idx = match node_indices.get(&module.path) {
                 Some(idx) => *idx,
@@ -30,10 +133,22 @@ impl DependencyGraph {
 
             for import in &module.imports {
                 // Try to resolve import to a module path
-                if let Some(target_path) = resolve_import(import, modules) {
-                    if let Some(to_idx) = node_indices.get(&target_path) {
-                        graph.add_edge(from_idx, *to_idx, ());
-                    }
+                let target = if is_relative_specifier(import) {
+                    resolve_relative_import(import, &module.path, |p| {
+                        node_indices.contains_key(p) || aliases.contains_key(p)
+                    })
+                    .map(|t| match aliases.get(t.as_path()) {
+                        Some(kept) => kept.to_path_buf(),
+                        None => t,
+                    })
+                } else {
+                    crate_paths.resolve(import)
+                };
+                if let Some(to_idx) = target.and_then(|t| node_indices.get(&t)) {
+                    let edge = EdgeInfo {
+                        import: import.clone(),
+                    };
+                    graph.add_edge(from_idx, *to_idx, edge);
                 }
             }
         }
@@ -41,10 +156,11 @@ impl DependencyGraph {
         Self {
             graph,
             node_indices,
+            betweenness: OnceLock::new(),
         }
     }
 
-    pub fn graph(&self) -> &DiGraph<PathBuf, ()> {
+    pub fn graph(&self) -> &DiGraph<PathBuf, EdgeInfo> {
         &self.graph
     }
 
@@ -52,10 +168,154 @@ impl DependencyGraph {
         &self.node_indices
     }
 
-    pub fn into_inner(self) -> DiGraph<PathBuf, ()> {
+    pub fn into_inner(self) -> DiGraph<PathBuf, EdgeInfo> {
         self.graph
     }
 
+    /// Every dependency edge as (importing module, imported module, edge info),
+    /// one per resolved import, so a module imported twice appears twice.
+    pub fn edges(&self) -> impl Iterator<Item = (&Path, &Path, &EdgeInfo)> {
+        self.graph.edge_references().map(|e| {
+            (
+                self.graph[e.source()].as_path(),
+                self.graph[e.target()].as_path(),
+                e.weight(),
+            )
+        })
+    }
+
+    /// Modules that depend on `path` directly or through others, grouped by
+    /// distance: index 0 holds direct dependents, index 1 their dependents, and
+    /// so on, each group sorted. `max_depth` caps the number of groups.
+    pub fn transitive_dependents(
+        &self,
+        path: &Path,
+        max_depth: Option<usize>,
+    ) -> Vec<Vec<PathBuf>> {
+        self.levels(path, Direction::Incoming, max_depth)
+    }
+
+    /// Modules `path` depends on directly or through others, grouped by
+    /// distance as in [`transitive_dependents`](Self::transitive_dependents).
+    pub fn transitive_dependencies(
+        &self,
+        path: &Path,
+        max_depth: Option<usize>,
+    ) -> Vec<Vec<PathBuf>> {
+        self.levels(path, Direction::Outgoing, max_depth)
+    }
+
+    /// Breadth-first search from `path`, each module in the group of its first visit
+    fn levels(
+        &self,
+        path: &Path,
+        direction: Direction,
+        max_depth: Option<usize>,
+    ) -> Vec<Vec<PathBuf>> {
+        let Some(&start) = self.node_indices.get(path) else {
+            return Vec::new();
+        };
+
+        let mut visited = HashSet::from([start]);
+        let mut levels: Vec<Vec<PathBuf>> = Vec::new();
+        let mut frontier = vec![start];
+        while !frontier.is_empty() && max_depth.is_none_or(|max| levels.len() < max) {
+            let mut next = Vec::new();
+            for idx in frontier {
+                for neighbor in self.graph.neighbors_directed(idx, direction) {
+                    if visited.insert(neighbor) {
+                        next.push(neighbor);
+                    }
+                }
+            }
+            if next.is_empty() {
+                break;
+            }
+            let mut level: Vec<PathBuf> = next.iter().map(|&idx| self.graph[idx].clone()).collect();
+            level.sort();
+            levels.push(level);
+            frontier = next;
+        }
+        levels
+    }
+
+    /// The shortest chain of imports leading from `from` to `to`, both included,
+    /// or `None` when `from` doesn't depend on `to` even indirectly.
+    pub fn shortest_path(&self, from: &Path, to: &Path) -> Option<Vec<PathBuf>> {
+        let start = *self.node_indices.get(from)?;
+        let goal = *self.node_indices.get(to)?;
+
+        let mut previous: HashMap<NodeIndex, NodeIndex> = HashMap::new();
+        let mut visited = HashSet::from([start]);
+        let mut queue = VecDeque::from([start]);
+        while let Some(idx) = queue.pop_front() {
+            if idx == goal {
+                let mut path = vec![self.graph[goal].clone()];
+                let mut current = goal;
+                while let Some(&prev) = previous.get(&current) {
+                    path.push(self.graph[prev].clone());
+                    current = prev;
+                }
+                path.reverse();
+                return Some(path);
+            }
+            for neighbor in self.graph.neighbors_directed(idx, Direction::Outgoing) {
+                if visited.insert(neighbor) {
+                    previous.insert(neighbor, idx);
+                    queue.push_back(neighbor);
+                }
+            }
+        }
+        None
+    }
+
+    /// Every dependency cycle: each group of modules that can all reach one
+    /// another, and each module that imports itself.
+    pub fn cycles(&self) -> Vec<Vec<PathBuf>> {
+        tarjan_scc(&self.graph)
+            .into_iter()
+            .filter(|scc| {
+                scc.len() > 1
+                    || self
+                        .graph
+                        .neighbors_directed(scc[0], Direction::Outgoing)
+                        .any(|n| n == scc[0])
+            })
+            .map(|scc| scc.iter().map(|&idx| self.graph[idx].clone()).collect())
+            .collect()
+    }
+
+    /// The graph restricted to `paths`, keeping the edges between them. Paths
+    /// not in the graph are ignored.
+    pub fn subgraph<P: AsRef<Path>>(&self, paths: impl IntoIterator<Item = P>) -> Self {
+        let mut graph = DiGraph::new();
+        let mut node_indices = HashMap::new();
+        let mut mapping = HashMap::new();
+        for path in paths {
+            let Some(&old) = self.node_indices.get(path.as_ref()) else {
+                continue;
+            };
+            if mapping.contains_key(&old) {
+                continue;
+            }
+            let new = graph.add_node(self.graph[old].clone());
+            node_indices.insert(self.graph[old].clone(), new);
+            mapping.insert(old, new);
+        }
+        for edge in self.graph.edge_references() {
+            if let (Some(&from), Some(&to)) =
+                (mapping.get(&edge.source()), mapping.get(&edge.target()))
+            {
+                graph.add_edge(from, to, edge.weight().clone());
+            }
+        }
+        Self {
+            graph,
+            node_indices,
+            betweenness: OnceLock::new(),
+        }
+    }
+
     pub fn fan_in(&self, path: &PathBuf) -> usize {
         if let Some(idx) = self.node_indices.get(path) {
             self.graph
@@ -155,6 +415,113 @@ impl DependencyGraph {
         }
     }
 
+    /// Get all direct dependencies (modules this module imports), without duplicates
+    pub fn direct_dependencies(&self, path: &PathBuf) -> Vec<PathBuf> {
+        let Some(idx) = self.node_indices.get(path) else {
+            return Vec::new();
+        };
+
+        let mut seen = HashSet::new();
+        self.graph
+            .neighbors_directed(*idx, Direction::Outgoing)
+            .filter(|idx| seen.insert(*idx))
+            .filter_map(|idx| self.graph.node_weight(idx).cloned())
+            .collect()
+    }
+
+    /// Betweenness centrality of every module (Brandes' algorithm), normalized to
+    /// 0.0-1.0: the share of shortest dependency paths between other modules that
+    /// pass through it. High values mark bottlenecks, even at modest fan-in.
+    ///
+    /// The search visits every pair of modules, so it only runs when a report
+    /// asks for the scores, and once per graph.
+    pub fn betweenness_centrality(&self) -> &HashMap<PathBuf, f64> {
+        self.betweenness.get_or_init(|| self.compute_betweenness())
+    }
+
+    fn compute_betweenness(&self) -> HashMap<PathBuf, f64> {
+        let n = self.graph.node_count();
+
+        // Distinct successors; repeated imports of one module add parallel edges
+        let successors: Vec<Vec<usize>> = self
+            .graph
+            .node_indices()
+            .map(|idx| {
+                let mut next: Vec<usize> = self
+                    .graph
+                    .neighbors_directed(idx, Direction::Outgoing)
+                    .map(|n| n.index())
+                    .collect();
+                next.sort_unstable();
+                next.dedup();
+                next
+            })
+            .collect();
+
+        let mut centrality = vec![0.0; n];
+        for source in 0..n {
+            // Breadth-first search counting shortest paths from `source`
+            let mut order = Vec::with_capacity(n);
+            let mut predecessors: Vec<Vec<usize>> = vec![Vec::new(); n];
+            let mut paths = vec![0.0; n];
+            let mut distance: Vec<Option<usize>> = vec![None; n];
+            paths[source] = 1.0;
+            distance[source] = Some(0);
+
+            let mut queue = VecDeque::from([source]);
+            while let Some(v) = queue.pop_front() {
+                order.push(v);
+                let next_distance = distance[v].map(|d| d + 1);
+                for &w in &successors[v] {
+                    if distance[w].is_none() {
+                        distance[w] = next_distance;
+                        queue.push_back(w);
+                    }
+                    if distance[w] == next_distance {
+                        paths[w] += paths[v];
+                        predecessors[w].push(v);
+                    }
+                }
+            }
+
+            // Accumulate dependencies, farthest modules first
+            let mut dependency = vec![0.0; n];
+            for &w in order.iter().rev() {
+                for &v in &predecessors[w] {
+                    dependency[v] += paths[v] / paths[w] * (1.0 + dependency[w]);
+                }
+                if w != source {
+                    centrality[w] += dependency[w];
+                }
+            }
+        }
+
+        // Ordered pairs of other modules in a directed graph
+        let scale = if n > 2 {
+            1.0 / ((n - 1) * (n - 2)) as f64
+        } else {
+            0.0
+        };
+        self.graph
+            .node_indices()
+            .map(|idx| (self.graph[idx].clone(), centrality[idx.index()] * scale))
+            .collect()
+    }
+
+    /// Modules on the most shortest dependency paths, highest betweenness first.
+    /// Modules that lie on no such path are left out.
+    pub fn critical_modules(&self, limit: usize) -> Vec<(PathBuf, f64)> {
+        let mut ranked: Vec<_> = self
+            .betweenness_centrality()
+            .iter()
+            .filter(|(_, score)| **score > 0.0)
+            .map(|(path, score)| (path.clone(), *score))
+            .collect();
+        ranked.sort_by(|a, b| b.1.total_cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        ranked.truncate(limit);
+        ranked
+    }
+
     /// Check if a path exists in the graph
     pub fn contains(&self, path: &PathBuf) -> bool {
         self.node_indices.contains_key(path)
@@ -163,12 +530,22 @@ impl DependencyGraph {
     /// Get importance score for a module (higher = more important for context)
     /// Prioritizes modules with high fan-in (many dependents)
     pub fn importance_score(&self, path: &PathBuf, modules: &[Module]) -> f64 {
+        match modules.iter().find(|m| &m.path == path) {
+            Some(module) => self.module_importance(module),
+            None => self.score(path, None),
+        }
+    }
+
+    /// [`DependencyGraph::importance_score`] for a module already at hand,
+    /// without searching the module list for it
+    pub fn module_importance(&self, module: &Module) -> f64 {
+        self.score(&module.path, Some(module))
+    }
+
+    fn score(&self, path: &PathBuf, module: Option<&Module>) -> f64 {
         let fan_in = self.fan_in(path) as f64;
         let fan_out = self.fan_out(path) as f64;
 
-        // Find the module for additional scoring
-        let module = modules.iter().find(|m| &m.path == path);
-
         // Bonus for model/types modules (core data structures)
         let model_bonus = if let Some(m) = module {
             if m.name.contains("model")
@@ -205,56 +582,412 @@ impl DependencyGraph {
     }
 }
 
-fn resolve_import(import: &str, modules: &[Module]) -> Option<PathBuf> {
-    // Extract the path segments (e.g., "crate::model::Module" -> ["crate", "model", "Module"])
-    let segments: Vec<&str> = import.split("::").collect();
-
-    if segments.is_empty() {
-        return None;
-    }
-
-    // Handle crate:: prefix - get the module path segments after "crate"
-    let module_segments = if segments[0] == "crate" && segments.len() > 1 {
-        &segments[1..]
-    } else if segments[0] == "super" || segments[0] == "self" {
-        // Skip relative imports for now
-        return None;
-    } else {
-        // External crate import - skip
-        return None;
-    };
-
-    if module_segments.is_empty() {
-        return None;
-    }
-
-    // Try to find a matching module by path components
-    // For "crate::model::Module", we want to match "src/model/mod.rs" or "src/model.rs"
-    let first_segment = module_segments[0].to_lowercase();
-
-    // Find modules whose path contains this segment as a directory or file name
-    modules
-        .iter()
-        .find(|m| {
-            let path_str = m.path.to_string_lossy().to_lowercase();
-
-            // Check for exact module name match (e.g., "model" matches "src/model/mod.rs" or "src/model.rs")
-            // The module name in the path should be a directory containing mod.rs or a .rs file
-            let is_mod_file = path_str.ends_with(&format!("/{}/mod.rs", first_segment))
-                || path_str.ends_with(&format!("\\{}\\mod.rs", first_segment));
-            let is_direct_file = path_str.ends_with(&format!("/{}.rs", first_segment))
-                || path_str.ends_with(&format!("\\{}.rs", first_segment));
-
-            // Also check for submodule files like "src/model/issue.rs" when importing "crate::model::issue"
-            let is_submodule = if module_segments.len() > 1 {
-                let second_segment = module_segments[1].to_lowercase();
-                path_str.ends_with(&format!("/{}/{}.rs", first_segment, second_segment))
-                    || path_str.ends_with(&format!("\\{}\\{}.rs", first_segment, second_segment))
-            } else {
-                false
+/// File extensions tried for an extensionless JS/TS specifier, in TypeScript's order
+const TS_EXTENSIONS: &[&str] = &["ts", "tsx", "d.ts", "js", "jsx"];
+
+/// Whether an import is a relative JS/TS specifier (`./foo`, `../bar/baz`)
+/// or a relative Python module (`.models`, `..pkg.mod`)
+pub fn is_relative_specifier(import: &str) -> bool {
+    import.starts_with("./") || import.starts_with("../") || python_relative(import).is_some()
+}
+
+/// Leading dots and dotted module path of a relative Python import:
+/// `..pkg.mod` is `(2, "pkg.mod")`.
+fn python_relative(import: &str) -> Option<(usize, &str)> {
+    let module = import.trim_start_matches('.');
+    let dots = import.len() - module.len();
+    (dots > 0 && !module.contains('/')).then_some((dots, module))
+}
+
+/// Resolve a relative import to a known module file.
+///
+/// Candidates are checked with `exists` (typically membership in the discovered
+/// module set). JS/TS specifiers follow TypeScript's resolution order: the path
+/// as written, then with `.ts`, `.tsx`, `.d.ts`, `.js`, `.jsx` appended, then
+/// `index.*` inside it as a directory. A `.js` specifier also tries the `.ts`
+/// source it compiles from. Python modules are looked up from the importing
+/// file's package, one directory up per extra dot: `..pkg.mod` is
+/// `../pkg/mod.py` or else `../pkg/mod/__init__.py`.
+pub fn resolve_relative_import(
+    specifier: &str,
+    from: &Path,
+    exists: impl Fn(&Path) -> bool,
+) -> Option<PathBuf> {
+    if let Some((dots, module)) = python_relative(specifier) {
+        return resolve_python_import(dots, module, from, exists);
+    }
+
+    let base = normalize_path(&from.parent()?.join(specifier));
+
+    let mut candidates = vec![base.clone()];
+    let base_str = base.to_string_lossy();
+    for ext in TS_EXTENSIONS {
+        candidates.push(PathBuf::from(format!("{}.{}", base_str, ext)));
+    }
+    for ext in TS_EXTENSIONS {
+        candidates.push(base.join(format!("index.{}", ext)));
+    }
+    // ESM-style `./foo.js` pointing at `foo.ts`
+    if let Some(stem) = base_str
+        .strip_suffix(".js")
+        .or_else(|| base_str.strip_suffix(".jsx"))
+    {
+        candidates.push(PathBuf::from(format!("{}.ts", stem)));
+        candidates.push(PathBuf::from(format!("{}.tsx", stem)));
+    }
+
+    candidates.into_iter().find(|c| exists(c))
+}
+
+fn resolve_python_import(
+    dots: usize,
+    module: &str,
+    from: &Path,
+    exists: impl Fn(&Path) -> bool,
+) -> Option<PathBuf> {
+    let mut package = from.parent()?.to_path_buf();
+    for _ in 1..dots {
+        if !package.pop() {
+            return None;
+        }
+    }
+    let base = module
+        .split('.')
+        .filter(|part| !part.is_empty())
+        .fold(package, |dir, part| dir.join(part));
+
+    let mut candidates = Vec::new();
+    // `from .. import *` names the package itself
+    if !module.is_empty() {
+        candidates.push(PathBuf::from(format!("{}.py", base.to_string_lossy())));
+    }
+    candidates.push(base.join("__init__.py"));
+    candidates.into_iter().find(|c| exists(c))
+}
+
+/// Rust modules by lowercased file stem and by (directory, stem), so a
+/// `crate::` import resolves with a few hash lookups rather than a scan over
+/// every module. Each key keeps the first module in list order.
+struct CratePaths<'a> {
+    modules: &'a [Module],
+    /// `x` for every `.../x.rs`
+    by_stem: HashMap<String, usize>,
+    /// (`d`, `x`) for every `.../d/x.rs`, `mod.rs` included
+    by_dir_stem: HashMap<(String, String), usize>,
+    /// Hash lookups made so far, which tests use to check resolution stays
+    /// independent of the number of modules
+    lookups: Cell<usize>,
+}
+
+impl<'a> CratePaths<'a> {
+    fn new(modules: &'a [Module]) -> Self {
+        let mut by_stem = HashMap::new();
+        let mut by_dir_stem = HashMap::new();
+        for (i, module) in modules.iter().enumerate() {
+            let path = module
+                .path
+                .to_string_lossy()
+                .to_lowercase()
+                .replace('\\', "/");
+            let mut parts = path.rsplitn(3, '/');
+            let (Some(file), Some(dir)) = (parts.next(), parts.next()) else {
+                continue;
             };
+            let Some(stem) = file.strip_suffix(".rs") else {
+                continue;
+            };
+            by_stem.entry(stem.to_string()).or_insert(i);
+            // The directory must itself follow a separator, as in `/model/mod.rs`
+            if parts.next().is_some() {
+                by_dir_stem
+                    .entry((dir.to_string(), stem.to_string()))
+                    .or_insert(i);
+            }
+        }
+        Self {
+            modules,
+            by_stem,
+            by_dir_stem,
+            lookups: Cell::new(0),
+        }
+    }
 
-            is_mod_file || is_direct_file || is_submodule
-        })
-        .map(|m| m.path.clone())
+    /// The module a `crate::` import names: `crate::model::Module` matches
+    /// `.../model/mod.rs`, `.../model.rs` or, with a second segment,
+    /// `.../model/module.rs`, whichever comes first in the module list.
+    /// Other imports (external crates, `super::`, `self::`) aren't resolved.
+    fn resolve(&self, import: &str) -> Option<PathBuf> {
+        let mut segments = import.split("::");
+        if segments.next() != Some("crate") {
+            return None;
+        }
+        let first = segments.next()?.to_lowercase();
+        let second = segments.next().map(str::to_lowercase);
+
+        let dir_stem = |stem: String| {
+            self.lookups.set(self.lookups.get() + 1);
+            self.by_dir_stem.get(&(first.clone(), stem)).copied()
+        };
+        self.lookups.set(self.lookups.get() + 1);
+        let found = [
+            dir_stem("mod".to_string()),
+            self.by_stem.get(&first).copied(),
+            second.and_then(dir_stem),
+        ];
+        found
+            .into_iter()
+            .flatten()
+            .min()
+            .map(|i| self.modules[i].path.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn known(paths: &[&str]) -> HashSet<PathBuf> {
+        paths.iter().map(PathBuf::from).collect()
+    }
+
+    fn ts_module(path: &str, imports: &[&str]) -> Module {
+        let mut module = Module::new(PathBuf::from(path));
+        module.imports = imports.iter().map(|&i| i.into()).collect();
+        module
+    }
+
+    #[test]
+    fn test_crate_imports_resolve_in_constant_lookups() {
+        // Thousands of modules, each importing the next by a `crate::` path in
+        // one of the three shapes the resolver accepts
+        let count = 3000;
+        let path = |i: usize| match i % 3 {
+            0 => format!("/p/src/m{}.rs", i),
+            1 => format!("/p/src/m{}/mod.rs", i),
+            _ => format!("/p/src/pkg/m{}.rs", i),
+        };
+        let import = |i: usize| match i % 3 {
+            0 => format!("crate::m{}::Item", i),
+            1 => format!("crate::m{}", i),
+            _ => format!("crate::pkg::m{}", i),
+        };
+        let modules: Vec<_> = (0..count)
+            .map(|i| ts_module(&path(i), &[import((i + 1) % count).as_str()]))
+            .collect();
+        let paths = CratePaths::new(&modules);
+
+        let resolved: Vec<_> = modules
+            .iter()
+            .map(|m| paths.resolve(&m.imports[0]))
+            .collect();
+        for (i, target) in resolved.iter().enumerate() {
+            let next = &modules[(i + 1) % count].path;
+            assert_eq!(target.as_ref(), Some(next), "import of module {}", i);
+        }
+        // At most three lookups per import, however many modules there are
+        assert!(paths.lookups.get() <= 3 * count);
+
+        assert_eq!(paths.resolve("crate"), None);
+        assert_eq!(paths.resolve("serde::Serialize"), None);
+        assert_eq!(paths.resolve("super::m1"), None);
+    }
+
+    #[test]
+    fn test_betweenness_finds_bottleneck() {
+        // Two apps reach two leaves only through `core`, which has fan-in 2
+        let graph = DependencyGraph::build(&[
+            ts_module("/p/app.ts", &["./core", "./core"]),
+            ts_module("/p/cli.ts", &["./core"]),
+            ts_module("/p/core.ts", &["./db", "./log"]),
+            ts_module("/p/db.ts", &[]),
+            ts_module("/p/log.ts", &[]),
+        ]);
+        assert!(
+            graph.betweenness.get().is_none(),
+            "scores wait for a caller"
+        );
+
+        let scores = graph.betweenness_centrality();
+        // 4 of the 12 ordered pairs of other modules route through core
+        let core = scores[Path::new("/p/core.ts")];
+        assert!((core - 1.0 / 3.0).abs() < 1e-9);
+        assert_eq!(scores[Path::new("/p/app.ts")], 0.0);
+        assert_eq!(scores[Path::new("/p/db.ts")], 0.0);
+
+        assert_eq!(
+            graph.critical_modules(3),
+            vec![(PathBuf::from("/p/core.ts"), core)]
+        );
+    }
+
+    #[test]
+    fn test_resolves_relative_ts_imports() {
+        let files = known(&[
+            "/app/src/foo.ts",
+            "/app/src/foo/index.ts",
+            "/app/src/widgets/index.tsx",
+            "/app/src/types.d.ts",
+            "/app/lib/util.ts",
+        ]);
+        let from = Path::new("/app/src/main.ts");
+        let resolve = |spec: &str| resolve_relative_import(spec, from, |p| files.contains(p));
+
+        // `.ts` wins over `/index.ts`
+        assert_eq!(resolve("./foo"), Some(PathBuf::from("/app/src/foo.ts")));
+        assert_eq!(
+            resolve("./widgets"),
+            Some(PathBuf::from("/app/src/widgets/index.tsx"))
+        );
+        assert_eq!(
+            resolve("./types"),
+            Some(PathBuf::from("/app/src/types.d.ts"))
+        );
+        assert_eq!(
+            resolve("../lib/util"),
+            Some(PathBuf::from("/app/lib/util.ts"))
+        );
+        assert_eq!(
+            resolve("../lib/util.js"),
+            Some(PathBuf::from("/app/lib/util.ts"))
+        );
+        assert_eq!(resolve("./missing"), None);
+    }
+
+    #[test]
+    fn test_resolves_relative_python_imports() {
+        let files = known(&[
+            "/app/shop/models.py",
+            "/app/shop/views/__init__.py",
+            "/app/core/db.py",
+            "/app/core/__init__.py",
+        ]);
+        let from = Path::new("/app/shop/api.py");
+        let resolve = |spec: &str| resolve_relative_import(spec, from, |p| files.contains(p));
+
+        assert!(is_relative_specifier(".models"));
+        assert!(!is_relative_specifier("models"));
+        assert_eq!(
+            resolve(".models"),
+            Some(PathBuf::from("/app/shop/models.py"))
+        );
+        assert_eq!(
+            resolve(".views"),
+            Some(PathBuf::from("/app/shop/views/__init__.py"))
+        );
+        assert_eq!(resolve("..core.db"), Some(PathBuf::from("/app/core/db.py")));
+        assert_eq!(
+            resolve("..core"),
+            Some(PathBuf::from("/app/core/__init__.py"))
+        );
+        assert_eq!(resolve(".missing"), None);
+        assert_eq!(resolve("......too.far"), None);
+    }
+
+    #[test]
+    fn test_graph_links_ts_modules_by_path() {
+        let mut main = Module::new(PathBuf::from("/app/src/main.ts"));
+        main.imports.push("./api/client".into());
+        let client = Module::new(PathBuf::from("/app/src/api/client.ts"));
+        // Same file name elsewhere must not be picked up
+        let other = Module::new(PathBuf::from("/app/tools/client.ts"));
+        let modules = vec![main, client, other];
+
+        let graph = DependencyGraph::build(&modules);
+        assert_eq!(graph.fan_in(&PathBuf::from("/app/src/api/client.ts")), 1);
+        assert_eq!(graph.fan_in(&PathBuf::from("/app/tools/client.ts")), 0);
+    }
+
+    /// app -> core -> db -> log, cli -> core, and a cycle between a and b
+    fn fixture() -> DependencyGraph {
+        DependencyGraph::build(&[
+            ts_module("/p/app.ts", &["./core"]),
+            ts_module("/p/cli.ts", &["./core"]),
+            ts_module("/p/core.ts", &["./db"]),
+            ts_module("/p/db.ts", &["./log"]),
+            ts_module("/p/log.ts", &[]),
+            ts_module("/p/a.ts", &["./b"]),
+            ts_module("/p/b.ts", &["./a"]),
+        ])
+    }
+
+    fn paths(names: &[&str]) -> Vec<PathBuf> {
+        names
+            .iter()
+            .map(|n| PathBuf::from(format!("/p/{}.ts", n)))
+            .collect()
+    }
+
+    #[test]
+    fn test_edges_carry_their_import() {
+        let graph = fixture();
+        let edges: Vec<_> = graph
+            .edges()
+            .map(|(from, to, info)| (from.to_path_buf(), to.to_path_buf(), info.import.as_str()))
+            .collect();
+        assert_eq!(edges.len(), 6);
+        assert!(edges.contains(&(
+            PathBuf::from("/p/app.ts"),
+            PathBuf::from("/p/core.ts"),
+            "./core"
+        )));
+    }
+
+    #[test]
+    fn test_transitive_queries_group_by_distance() {
+        let graph = fixture();
+        let log = Path::new("/p/log.ts");
+        assert_eq!(
+            graph.transitive_dependents(log, None),
+            [paths(&["db"]), paths(&["core"]), paths(&["app", "cli"])]
+        );
+        assert_eq!(
+            graph.transitive_dependents(log, Some(2)),
+            [paths(&["db"]), paths(&["core"])]
+        );
+        assert_eq!(
+            graph.transitive_dependencies(Path::new("/p/app.ts"), None),
+            [paths(&["core"]), paths(&["db"]), paths(&["log"])]
+        );
+        // The start of a cycle isn't its own dependent
+        assert_eq!(
+            graph.transitive_dependents(Path::new("/p/a.ts"), None),
+            [paths(&["b"])]
+        );
+        assert!(
+            graph
+                .transitive_dependents(Path::new("/p/missing.ts"), None)
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_shortest_path_follows_imports() {
+        let graph = fixture();
+        let app = Path::new("/p/app.ts");
+        let log = Path::new("/p/log.ts");
+        assert_eq!(
+            graph.shortest_path(app, log),
+            Some(paths(&["app", "core", "db", "log"]))
+        );
+        assert_eq!(graph.shortest_path(app, app), Some(paths(&["app"])));
+        // Dependencies only point one way
+        assert_eq!(graph.shortest_path(log, app), None);
+    }
+
+    #[test]
+    fn test_cycles_and_subgraph() {
+        let graph = fixture();
+        let mut cycles = graph.cycles();
+        for cycle in &mut cycles {
+            cycle.sort();
+        }
+        assert_eq!(cycles, [paths(&["a", "b"])]);
+
+        let core = graph.subgraph(paths(&["app", "core", "log", "missing"]));
+        assert_eq!(core.graph().node_count(), 3);
+        assert_eq!(core.fan_in(&PathBuf::from("/p/core.ts")), 1);
+        // db is gone, and with it the only route to log
+        assert_eq!(core.fan_in(&PathBuf::from("/p/log.ts")), 0);
+        assert!(core.cycles().is_empty());
+    }
 }
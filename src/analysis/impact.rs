@@ -1,4 +1,5 @@
 use crate::analysis::DependencyGraph;
+use serde::Serialize;
 use std::collections::{HashMap, HashSet, VecDeque};
 use std::path::{Path, PathBuf};
 use thiserror::Error;
@@ -28,14 +29,39 @@ pub struct ImpactAnalysis {
     pub tree: ImpactNode,
 }
 
-/// Node in the impact tree
-#[derive(Debug, Clone)]
+/// Node in the impact tree. The tree is built breadth-first, so each affected
+/// file appears once, under a dependent one step closer to the target, and its
+/// `depth` is its group in `affected_by_depth` plus one.
+#[derive(Debug, Clone, Serialize)]
 pub struct ImpactNode {
+    /// File this node stands for, as analyzed (usually absolute)
     pub path: PathBuf,
+    /// Distance from the target; the target itself is 0
     pub depth: usize,
+    /// Files importing this one that weren't already placed closer to the
+    /// target, sorted by path
     pub children: Vec<ImpactNode>,
 }
 
+impl ImpactNode {
+    /// This node and every node below it, level by level
+    pub fn iter_breadth_first(&self) -> impl Iterator<Item = &ImpactNode> {
+        let mut queue = VecDeque::from([self]);
+        std::iter::from_fn(move || {
+            let node = queue.pop_front()?;
+            queue.extend(&node.children);
+            Some(node)
+        })
+    }
+
+    /// Depth of the node for `path`, if it is in this tree
+    pub fn depth_of(&self, path: &Path) -> Option<usize> {
+        self.iter_breadth_first()
+            .find(|node| node.path == path)
+            .map(|node| node.depth)
+    }
+}
+
 /// Compute the impact of changes to a target file
 /// Returns all modules that directly or transitively depend on the target
 pub fn compute_impact(
@@ -49,58 +75,9 @@ pub fn compute_impact(
         return Err(ImpactError::NotInGraph(target_canonical));
     }
 
-    // BFS to find all dependents
-    let mut visited: HashSet<PathBuf> = HashSet::new();
-    let mut depth_map: HashMap<PathBuf, usize> = HashMap::new();
-    let mut queue: VecDeque<(PathBuf, usize)> = VecDeque::new();
-    let mut parent_map: HashMap<PathBuf, Vec<PathBuf>> = HashMap::new();
-
-    // Start with direct dependents at depth 1
-    let direct = graph.direct_dependents(&target_canonical);
-    for dep in direct {
-        if !visited.contains(&dep) {
-            visited.insert(dep.clone());
-            depth_map.insert(dep.clone(), 1);
-            parent_map.insert(dep.clone(), vec![target_canonical.clone()]);
-            queue.push_back((dep, 1));
-        }
-    }
-
-    // BFS traversal
-    while let Some((node, depth)) = queue.pop_front() {
-        // Check depth limit
-        if let Some(max) = max_depth {
-            if depth >= max {
-                continue;
-            }
-        }
-
-        // Find dependents of this node (who imports this node)
-        let dependents = graph.direct_dependents(&node);
-        for dep in dependents {
-            if !visited.contains(&dep) {
-                visited.insert(dep.clone());
-                depth_map.insert(dep.clone(), depth + 1);
-                parent_map.insert(dep.clone(), vec![node.clone()]);
-                queue.push_back((dep.clone(), depth + 1));
-            }
-        }
-    }
-
-    // Organize results by depth
-    let max_chain_length = depth_map.values().copied().max().unwrap_or(0);
-    let mut affected_by_depth: Vec<Vec<PathBuf>> = vec![Vec::new(); max_chain_length];
-
-    for (path, depth) in &depth_map {
-        if *depth > 0 && *depth <= max_chain_length {
-            affected_by_depth[depth - 1].push(path.clone());
-        }
-    }
-
-    // Sort each depth level for consistent output
-    for level in &mut affected_by_depth {
-        level.sort();
-    }
+    let affected_by_depth = graph.transitive_dependents(&target_canonical, max_depth);
+    let total_affected = affected_by_depth.iter().map(Vec::len).sum();
+    let max_chain_length = affected_by_depth.len();
 
     // Build tree for visualization
     let tree = build_impact_tree(&target_canonical, graph, max_depth);
@@ -108,54 +85,61 @@ pub fn compute_impact(
     Ok(ImpactAnalysis {
         target: target_canonical,
         affected_by_depth,
-        total_affected: visited.len(),
+        total_affected,
         max_chain_length,
         tree,
     })
 }
 
+/// Build the tree breadth-first, so a file reached along several chains hangs
+/// under the shortest one, as in `affected_by_depth`.
 fn build_impact_tree(
     root: &PathBuf,
     graph: &DependencyGraph,
     max_depth: Option<usize>,
 ) -> ImpactNode {
-    build_tree_recursive(root, graph, 0, max_depth, &mut HashSet::new())
+    let mut children_of: HashMap<PathBuf, Vec<PathBuf>> = HashMap::new();
+    let mut visited = HashSet::from([root.clone()]);
+    let mut level = vec![root.clone()];
+    let mut depth = 0;
+
+    while !level.is_empty() && max_depth.is_none_or(|max| depth < max) {
+        let mut next = Vec::new();
+        for node in &level {
+            // Sort dependents for consistent output
+            let mut dependents = graph.direct_dependents(node);
+            dependents.sort();
+            for dep in dependents {
+                if visited.insert(dep.clone()) {
+                    children_of
+                        .entry(node.clone())
+                        .or_default()
+                        .push(dep.clone());
+                    next.push(dep);
+                }
+            }
+        }
+        level = next;
+        depth += 1;
+    }
+
+    assemble_tree(root.clone(), 0, &mut children_of)
 }
 
-fn build_tree_recursive(
-    node: &PathBuf,
-    graph: &DependencyGraph,
+fn assemble_tree(
+    path: PathBuf,
     depth: usize,
-    max_depth: Option<usize>,
-    visited: &mut HashSet<PathBuf>,
+    children_of: &mut HashMap<PathBuf, Vec<PathBuf>>,
 ) -> ImpactNode {
-    let mut children = Vec::new();
-
-    // Check depth limit
-    if max_depth.map_or(false, |max| depth >= max) {
-        return ImpactNode {
-            path: node.clone(),
-            depth,
-            children,
-        };
-    }
-
-    // Add to visited to prevent cycles
-    visited.insert(node.clone());
-
-    // Get direct dependents
-    for dep in graph.direct_dependents(node) {
-        if !visited.contains(&dep) {
-            let child = build_tree_recursive(&dep, graph, depth + 1, max_depth, visited);
-            children.push(child);
-        }
-    }
-
-    // Sort children for consistent output
-    children.sort_by(|a, b| a.path.cmp(&b.path));
+    let children = children_of
+        .remove(&path)
+        .unwrap_or_default()
+        .into_iter()
+        .map(|child| assemble_tree(child, depth + 1, children_of))
+        .collect();
 
     ImpactNode {
-        path: node.clone(),
+        path,
         depth,
         children,
     }
@@ -264,6 +248,45 @@ pub fn format_impact_json(analysis: &ImpactAnalysis, project_root: Option<&Path>
     serde_json::to_string_pretty(&output).unwrap_or_else(|_| "{}".to_string())
 }
 
+/// Fill colors for affected files by depth, nearest first; deeper files reuse
+/// the last one.
+const DOT_DEPTH_COLORS: &[&str] = &["#f5a05a", "#f7c873", "#fae3a0", "#e8e8e8"];
+
+/// Format the impact tree as a Graphviz DOT digraph. Edges point from a file
+/// to the files importing it, the direction a change spreads. The target is
+/// red and bold; affected files fade with their depth.
+pub fn format_impact_dot(analysis: &ImpactAnalysis, project_root: Option<&Path>) -> String {
+    let quote = |path: &Path| {
+        let path = relative_path(path, project_root);
+        format!("\"{}\"", path.replace('\\', "\\\\").replace('"', "\\\""))
+    };
+
+    let mut output = String::from(
+        "digraph impact {\n    rankdir=LR;\n    node [shape=box, style=filled, fontname=\"Helvetica\"];\n",
+    );
+    for node in analysis.tree.iter_breadth_first() {
+        let attrs = match node.depth {
+            0 => "fillcolor=\"#e0463e\", fontcolor=white, style=\"filled,bold\"".to_string(),
+            depth => {
+                let color = DOT_DEPTH_COLORS[(depth - 1).min(DOT_DEPTH_COLORS.len() - 1)];
+                format!("fillcolor=\"{}\"", color)
+            }
+        };
+        output.push_str(&format!("    {} [{}];\n", quote(&node.path), attrs));
+    }
+    for node in analysis.tree.iter_breadth_first() {
+        for child in &node.children {
+            output.push_str(&format!(
+                "    {} -> {};\n",
+                quote(&node.path),
+                quote(&child.path)
+            ));
+        }
+    }
+    output.push_str("}\n");
+    output
+}
+
 fn format_tree_json(node: &ImpactNode, project_root: Option<&Path>) -> serde_json::Value {
     use serde_json::json;
 
@@ -327,3 +350,77 @@ fn relative_path(path: &Path, root: Option<&Path>) -> String {
         path.display().to_string()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::Module;
+
+    fn ts_module(path: &str, imports: &[&str]) -> Module {
+        let mut module = Module::new(PathBuf::from(path));
+        module.imports = imports.iter().map(|&i| i.into()).collect();
+        module
+    }
+
+    /// `app` imports `db` directly and through `repo`; `cli` imports `app`
+    fn impact() -> ImpactAnalysis {
+        let graph = DependencyGraph::build(&[
+            ts_module("/p/app.ts", &["./repo", "./db"]),
+            ts_module("/p/cli.ts", &["./app"]),
+            ts_module("/p/db.ts", &[]),
+            ts_module("/p/repo.ts", &["./db"]),
+        ]);
+        compute_impact(&graph, Path::new("/p/db.ts"), None).unwrap()
+    }
+
+    #[test]
+    fn test_tree_follows_shortest_chains() {
+        let analysis = impact();
+        let order: Vec<_> = analysis
+            .tree
+            .iter_breadth_first()
+            .map(|n| (n.path.to_str().unwrap(), n.depth))
+            .collect();
+        assert_eq!(
+            order,
+            [
+                ("/p/db.ts", 0),
+                ("/p/app.ts", 1),
+                ("/p/repo.ts", 1),
+                ("/p/cli.ts", 2),
+            ]
+        );
+
+        // Tree depths agree with the groups in `affected_by_depth`
+        for (idx, files) in analysis.affected_by_depth.iter().enumerate() {
+            for file in files {
+                assert_eq!(analysis.tree.depth_of(file), Some(idx + 1));
+            }
+        }
+        assert_eq!(analysis.tree.depth_of(Path::new("/p/db.ts")), Some(0));
+        assert_eq!(analysis.tree.depth_of(Path::new("/p/other.ts")), None);
+    }
+
+    #[test]
+    fn test_tree_serializes_and_renders_as_dot() {
+        let analysis = impact();
+        let json = serde_json::to_value(&analysis.tree).unwrap();
+        assert_eq!(json["path"], "/p/db.ts");
+        assert_eq!(json["children"][0]["path"], "/p/app.ts");
+        assert_eq!(json["children"][0]["children"][0]["depth"], 2);
+        assert_eq!(json["children"][1]["children"], serde_json::json!([]));
+
+        let dot = format_impact_dot(&analysis, Some(Path::new("/p")));
+        assert_eq!(
+            dot,
+            "digraph impact {\n    rankdir=LR;\n    \
+             node [shape=box, style=filled, fontname=\"Helvetica\"];\n    \
+             \"db.ts\" [fillcolor=\"#e0463e\", fontcolor=white, style=\"filled,bold\"];\n    \
+             \"app.ts\" [fillcolor=\"#f5a05a\"];\n    \
+             \"repo.ts\" [fillcolor=\"#f5a05a\"];\n    \
+             \"cli.ts\" [fillcolor=\"#f7c873\"];\n    \
+             \"db.ts\" -> \"app.ts\";\n    \"db.ts\" -> \"repo.ts\";\n    \
+             \"app.ts\" -> \"cli.ts\";\n}\n"
+        );
+    }
+}
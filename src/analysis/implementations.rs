@@ -0,0 +1,134 @@
+use crate::config::Config;
+use crate::model::{DefinitionKind, Issue, Location, Module};
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::Path;
+
+/// Flag project traits and interfaces implemented in many different modules.
+/// Only names defined in the project count, so `impl Display for ...` across
+/// the codebase is not reported.
+pub fn detect_scattered_implementations(modules: &[Module], config: &Config) -> Vec<Issue> {
+    // First definition of each trait/interface, by path for stable output
+    let mut definitions: BTreeMap<&str, (&Path, usize)> = BTreeMap::new();
+    let mut sorted: Vec<&Module> = modules.iter().collect();
+    sorted.sort_by(|a, b| a.path.cmp(&b.path));
+    for module in &sorted {
+        for def in &module.definitions {
+            if matches!(def.kind, DefinitionKind::Trait | DefinitionKind::Interface) {
+                definitions
+                    .entry(def.name.as_str())
+                    .or_insert((&module.path, def.line));
+            }
+        }
+    }
+
+    let mut implementations: BTreeMap<&str, Vec<Location>> = BTreeMap::new();
+    for module in &sorted {
+        for implementation in &module.implementations {
+            if definitions.contains_key(implementation.trait_name.as_str()) {
+                implementations
+                    .entry(&implementation.trait_name)
+                    .or_default()
+                    .push(Location {
+                        path: module.path.clone(),
+                        line: Some(implementation.line),
+                        context: Some(format!(
+                            "{} for {}",
+                            implementation.trait_name, implementation.type_name
+                        )),
+                        indicator: None,
+                    });
+            }
+        }
+    }
+
+    let mut issues = Vec::new();
+    for (trait_name, locations) in implementations {
+        let file_count = locations
+            .iter()
+            .map(|loc| &loc.path)
+            .collect::<BTreeSet<_>>()
+            .len();
+        if file_count < config.thresholds.scattered_impl_files {
+            continue;
+        }
+
+        let (path, line) = definitions[trait_name];
+        let definition = Location {
+            path: path.to_path_buf(),
+            line: Some(line),
+            context: Some(format!("definition of {}", trait_name)),
+            indicator: None,
+        };
+        issues.push(Issue::scattered_implementation(
+            trait_name.to_string(),
+            file_count,
+            std::iter::once(definition).chain(locations).collect(),
+        ));
+    }
+
+    issues
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{Definition, Implementation, IssueKind, Visibility};
+    use std::path::PathBuf;
+
+    fn implementor(path: &str, trait_name: &str) -> Module {
+        let mut module = Module::new(PathBuf::from(path));
+        module.implementations.push(Implementation {
+            trait_name: trait_name.to_string(),
+            type_name: module.name.clone(),
+            line: 3,
+        });
+        module
+    }
+
+    #[test]
+    fn test_flags_project_traits_implemented_in_many_modules() {
+        let mut plugin = Module::new(PathBuf::from("/p/plugin.rs"));
+        plugin.add_definition(Definition {
+            name: "Plugin".to_string(),
+            kind: DefinitionKind::Trait,
+            line: 1,
+            visibility: Visibility::Public,
+            signature: None,
+            span: None,
+            compact_signature: None,
+            decorators: Vec::new(),
+        });
+
+        let mut modules = vec![plugin];
+        for name in ["a", "b", "c"] {
+            modules.push(implementor(&format!("/p/{}.rs", name), "Plugin"));
+            // Not defined in the project
+            modules.push(implementor(&format!("/p/{}_fmt.rs", name), "Display"));
+        }
+        // A second impl in the same file doesn't widen the spread
+        modules.push(implementor("/p/a.rs", "Plugin"));
+
+        let config = Config {
+            thresholds: crate::config::Thresholds {
+                scattered_impl_files: 3,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let issues = detect_scattered_implementations(&modules, &config);
+
+        assert_eq!(issues.len(), 1);
+        assert_eq!(
+            issues[0].kind,
+            IssueKind::ScatteredImplementation {
+                trait_name: "Plugin".to_string(),
+                file_count: 3,
+            }
+        );
+        assert_eq!(issues[0].locations[0].path, PathBuf::from("/p/plugin.rs"));
+        assert_eq!(issues[0].locations.len(), 5);
+
+        let stricter = Config::default();
+        assert!(detect_scattered_implementations(&modules, &stricter).is_empty());
+    }
+}
@@ -0,0 +1,98 @@
+//! "Where do I start reading?": the files that, once understood, explain the
+//! most of a project's architecture.
+
+use super::DependencyGraph;
+use crate::model::Module;
+use std::path::PathBuf;
+
+/// Share of the score from each metric. Dependents weigh most: understanding
+/// a module explains every one of them a little.
+const FAN_IN_WEIGHT: f64 = 0.5;
+const BETWEENNESS_WEIGHT: f64 = 0.3;
+const EXPORTS_WEIGHT: f64 = 0.2;
+
+/// One file in the key files ranking
+#[derive(Debug, Clone, PartialEq)]
+pub struct KeyFile {
+    pub path: PathBuf,
+    /// 0.0-1.0; 1.0 would lead the project on every metric at once
+    pub score: f64,
+    pub fan_in: usize,
+    pub betweenness: f64,
+    pub exports: usize,
+}
+
+/// The `limit` files most worth reading first, best first. Fan-in (many
+/// dependents), betweenness (on many dependency paths) and export count are
+/// each scaled against the project's largest value, then weighted. Files
+/// scoring 0 on all three are left out.
+pub fn rank_key_files(modules: &[Module], graph: &DependencyGraph, limit: usize) -> Vec<KeyFile> {
+    if limit == 0 {
+        return Vec::new();
+    }
+    let betweenness = graph.betweenness_centrality();
+    let mut ranked: Vec<KeyFile> = modules
+        .iter()
+        .map(|m| KeyFile {
+            path: m.path.clone(),
+            score: 0.0,
+            fan_in: graph.fan_in(&m.path),
+            betweenness: betweenness.get(&m.path).copied().unwrap_or(0.0),
+            exports: m.exports.len(),
+        })
+        .collect();
+
+    let max_fan_in = ranked.iter().map(|f| f.fan_in).max().unwrap_or(0);
+    let max_betweenness = ranked.iter().map(|f| f.betweenness).fold(0.0, f64::max);
+    let max_exports = ranked.iter().map(|f| f.exports).max().unwrap_or(0);
+    let scaled = |value: f64, max: f64| if max > 0.0 { value / max } else { 0.0 };
+    for file in &mut ranked {
+        file.score = FAN_IN_WEIGHT * scaled(file.fan_in as f64, max_fan_in as f64)
+            + BETWEENNESS_WEIGHT * scaled(file.betweenness, max_betweenness)
+            + EXPORTS_WEIGHT * scaled(file.exports as f64, max_exports as f64);
+    }
+
+    ranked.retain(|f| f.score > 0.0);
+    ranked.sort_by(|a, b| {
+        b.score
+            .total_cmp(&a.score)
+            .then_with(|| a.path.cmp(&b.path))
+    });
+    ranked.truncate(limit);
+    ranked
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn module(path: &str, imports: &[&str], exports: usize) -> Module {
+        let mut module = Module::new(PathBuf::from(path));
+        module.imports = imports.iter().map(|&i| i.into()).collect();
+        module.exports = (0..exports).map(|i| format!("item{}", i).into()).collect();
+        module
+    }
+
+    #[test]
+    fn test_ranks_by_dependents_paths_and_exports() {
+        // app -> service -> model, cli -> model; util is imported by nothing
+        let modules = vec![
+            module("/p/app.ts", &["./service"], 0),
+            module("/p/cli.ts", &["./model"], 0),
+            module("/p/service.ts", &["./model"], 1),
+            module("/p/model.ts", &[], 4),
+            module("/p/util.ts", &[], 8),
+        ];
+        let graph = DependencyGraph::build(&modules);
+        let ranked = rank_key_files(&modules, &graph, 10);
+
+        let paths: Vec<_> = ranked.iter().map(|f| f.path.to_str().unwrap()).collect();
+        assert_eq!(paths, ["/p/model.ts", "/p/service.ts", "/p/util.ts"]);
+        assert_eq!(ranked[0].fan_in, 2);
+        assert!(ranked[1].betweenness > 0.0);
+        // Exports alone make a file worth a look, but not before the hubs
+        assert!((ranked[2].score - EXPORTS_WEIGHT).abs() < 1e-9);
+
+        assert_eq!(rank_key_files(&modules, &graph, 1).len(), 1);
+    }
+}
@@ -0,0 +1,238 @@
+//! Files not worth parsing: huge bundles, binaries, minified code.
+//!
+//! A 60 MB generated bundle dominates parse time and yields nothing but noise,
+//! so discovery skips it and leaves a [`Skipped`](crate::model::DiagnosticKind::Skipped)
+//! diagnostic saying why. Files that get past these checks are still parsed
+//! under `parse_timeout_ms`, and a parser that panics loses only that file,
+//! reported through its diagnostic. The panic hook is left alone unless the
+//! binary opts into [`quiet_contained_panics`].
+
+use crate::config::Config;
+use crate::model::{Diagnostic, Module};
+use crate::parser::{LanguageParser, with_deadline};
+use std::any::Any;
+use std::cell::{Cell, RefCell};
+use std::panic::{self, AssertUnwindSafe, catch_unwind};
+use std::path::Path;
+use std::sync::Once;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+
+/// Leading bytes searched for a NUL when deciding whether a file is binary
+const SNIFF_BYTES: usize = 8 * 1024;
+
+/// `--no-size-limit`, which turns off `max_file_size_kb` and `max_line_length`
+static NO_SIZE_LIMIT: AtomicBool = AtomicBool::new(false);
+
+/// Ignore the size and line length limits for the rest of this process.
+pub fn disable_size_limits() {
+    NO_SIZE_LIMIT.store(true, Ordering::Relaxed);
+}
+
+/// Whether `--no-size-limit` was given
+pub fn size_limits_disabled() -> bool {
+    NO_SIZE_LIMIT.load(Ordering::Relaxed)
+}
+
+/// Why `source` shouldn't be parsed, or `None` when it should. Binary files
+/// are always skipped; the size limits are off under `--no-size-limit` or
+/// when set to 0.
+pub fn skip_reason(source: &str, config: &Config) -> Option<String> {
+    let head = &source.as_bytes()[..source.len().min(SNIFF_BYTES)];
+    if head.contains(&0) {
+        return Some("binary file (NUL byte in the first 8 KB)".to_string());
+    }
+    if size_limits_disabled() {
+        return None;
+    }
+
+    let size_kb = source.len() as u64 / 1024;
+    if config.max_file_size_kb > 0 && size_kb > config.max_file_size_kb {
+        return Some(format!(
+            "{} KB is over max_file_size_kb ({})",
+            size_kb, config.max_file_size_kb
+        ));
+    }
+
+    if config.max_line_length > 0 {
+        let longest = source.lines().map(str::len).max().unwrap_or(0);
+        if longest > config.max_line_length {
+            return Some(format!(
+                "looks minified: a {}-character line is over max_line_length ({})",
+                longest, config.max_line_length
+            ));
+        }
+    }
+    None
+}
+
+thread_local! {
+    /// Set while this thread runs a contained parse, whose panic becomes a diagnostic
+    static CONTAINED: Cell<bool> = const { Cell::new(false) };
+    /// Where the last contained panic on this thread was raised, kept by
+    /// [`quiet_contained_panics`] for the diagnostic
+    static PANIC_LOCATION: RefCell<Option<String>> = const { RefCell::new(None) };
+}
+
+/// Wrap the panic hook, once per process, so a panic inside a contained parse
+/// doesn't print a banner on top of its diagnostic, which names the panic's
+/// location instead. Panics anywhere else still reach the hook that was
+/// installed before. Meant for binaries: the library never touches the hook.
+pub fn quiet_contained_panics() {
+    static INSTALL: Once = Once::new();
+    INSTALL.call_once(|| {
+        let previous = panic::take_hook();
+        panic::set_hook(Box::new(move |info| {
+            if CONTAINED.with(Cell::get) {
+                PANIC_LOCATION.set(info.location().map(ToString::to_string));
+            } else {
+                previous(info);
+            }
+        }));
+    });
+}
+
+/// Parse one file, turning a parser panic or a parse running past
+/// `parse_timeout_ms` into a diagnostic. Tree-sitter parsers are cancelled at
+/// the deadline; any other parser is checked once it returns.
+pub(crate) fn parse_contained(
+    parser: &dyn LanguageParser,
+    path: &Path,
+    source: &str,
+    config: &Config,
+) -> Result<Module, Diagnostic> {
+    let limit = config.parse_timeout_ms;
+    let started = Instant::now();
+    let deadline = (limit > 0).then(|| started + Duration::from_millis(limit));
+
+    let outer = CONTAINED.replace(true);
+    let parsed = catch_unwind(AssertUnwindSafe(|| {
+        with_deadline(deadline, || parser.parse_module(path, source))
+    }));
+    CONTAINED.set(outer);
+
+    let timed_out = deadline.is_some_and(|d| Instant::now() >= d);
+    match parsed {
+        Err(payload) => {
+            let message = panic_message(payload.as_ref());
+            Err(Diagnostic::parse_panicked(
+                path.to_path_buf(),
+                match PANIC_LOCATION.take() {
+                    Some(location) => format!("{} at {}", message, location),
+                    None => message.to_string(),
+                },
+            ))
+        }
+        Ok(_) if timed_out => Err(Diagnostic::parse_timed_out(path.to_path_buf(), limit)),
+        Ok(Err(e)) => Err(Diagnostic::parse_failed(path.to_path_buf(), e)),
+        Ok(Ok(module)) => Ok(module),
+    }
+}
+
+/// The message a panic was raised with, when it's a string
+fn panic_message(payload: &(dyn Any + Send)) -> &str {
+    payload
+        .downcast_ref::<&str>()
+        .copied()
+        .or_else(|| payload.downcast_ref::<String>().map(String::as_str))
+        .unwrap_or("panicked")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::DiagnosticKind;
+    use crate::parser::ParseError;
+
+    /// Panics, sleeps or parses depending on the file name
+    struct StubParser;
+
+    impl LanguageParser for StubParser {
+        fn name(&self) -> &str {
+            "stub"
+        }
+
+        fn extensions(&self) -> &[&str] {
+            &["stub"]
+        }
+
+        fn parse_module(&self, path: &Path, _source: &str) -> Result<Module, ParseError> {
+            match path.file_stem().and_then(|s| s.to_str()) {
+                Some("panics") => panic!("unexpected node"),
+                Some("sleeps") => std::thread::sleep(Duration::from_millis(50)),
+                _ => {}
+            }
+            Ok(Module::new(path.to_path_buf()))
+        }
+    }
+
+    #[test]
+    fn test_panicking_and_slow_parsers_are_contained() {
+        let config = Config {
+            parse_timeout_ms: 10,
+            ..Default::default()
+        };
+        let parse = |name: &str| parse_contained(&StubParser, Path::new(name), "", &config);
+
+        assert!(parse("fine.stub").is_ok());
+
+        let panicked = parse("panics.stub").unwrap_err();
+        assert_eq!(panicked.kind, DiagnosticKind::ParseFailed);
+        assert!(panicked.message.contains("unexpected node"));
+        // Later panics on this thread reach the usual hook again
+        assert!(!CONTAINED.with(Cell::get));
+
+        // With the quiet hook the banner's location moves into the diagnostic
+        quiet_contained_panics();
+        let quiet = parse("panics.stub").unwrap_err();
+        assert!(quiet.message.contains("unexpected node at "));
+
+        let slow = parse("sleeps.stub").unwrap_err();
+        assert_eq!(slow.kind, DiagnosticKind::ParseFailed);
+        assert!(slow.message.contains("over 10 ms"));
+
+        // 0 turns the limit off
+        let unlimited = Config {
+            parse_timeout_ms: 0,
+            ..Default::default()
+        };
+        assert!(parse_contained(&StubParser, Path::new("sleeps.stub"), "", &unlimited).is_ok());
+    }
+
+    #[test]
+    fn test_skip_reasons() {
+        let config = Config {
+            max_file_size_kb: 1,
+            max_line_length: 100,
+            ..Default::default()
+        };
+
+        assert_eq!(skip_reason("fn main() {}\n", &config), None);
+        assert!(
+            skip_reason("abc\0def", &config)
+                .unwrap()
+                .starts_with("binary file")
+        );
+        let big = "let x = 1;\n".repeat(200);
+        assert!(
+            skip_reason(&big, &config)
+                .unwrap()
+                .contains("max_file_size_kb (1)")
+        );
+        let minified = "x".repeat(150);
+        assert!(
+            skip_reason(&minified, &config)
+                .unwrap()
+                .contains("150-character line")
+        );
+
+        // 0 turns a limit off
+        let unlimited = Config {
+            max_file_size_kb: 0,
+            max_line_length: 0,
+            ..Default::default()
+        };
+        assert_eq!(skip_reason(&big, &unlimited), None);
+        assert_eq!(skip_reason(&minified, &unlimited), None);
+    }
+}
@@ -1,33 +1,126 @@
 mod boundary;
+mod cache;
 mod circular;
 mod cohesion;
 mod complexity;
 mod coupling;
+mod dead_code;
 mod depth;
+mod detectors;
+mod empty_tests;
+mod expectations;
+mod explain;
+mod filter;
+mod generated;
 mod god_object;
 mod graph;
 mod impact;
+mod implementations;
+mod key_files;
+mod limits;
+mod packages;
+mod progress;
+mod roles;
 
-pub use boundary::{detect_boundary_violations, detect_boundary_violations_with_fs};
+pub use boundary::{
+    Crossings, detect_boundary_violations, detect_boundary_violations_with_fs,
+    explain_boundary_violation,
+};
+pub use cache::{CACHE_DIR, ParseCacheStats, cache_enabled, disable_cache};
 pub use circular::detect_circular_dependencies;
-pub use cohesion::detect_low_cohesion;
-pub use complexity::{ModuleComplexity, detect_fat_modules};
-pub use coupling::detect_high_coupling;
+pub use cohesion::{detect_low_cohesion, explain_low_cohesion};
+pub use complexity::{ModuleComplexity, detect_fat_modules, detect_too_many_imports};
+pub use coupling::{detect_fat_shared_modules, detect_high_coupling, fanin_threshold};
+pub use dead_code::detect_dead_public_functions;
 pub use depth::detect_deep_dependency_chains;
+pub use detectors::{
+    BuiltinDetector, DETECTORS, Detector, DetectorInput, DetectorSet, detector_names,
+    find_detector, run_only,
+};
+pub use empty_tests::detect_empty_tests;
+pub use expectations::{ExpectationResult, evaluate_expectations};
+pub use explain::{CheckDescription, Explanation, ExplanationInput, describe_check, explain_issue};
+pub use filter::PathFilter;
+pub use generated::{drop_generated_issues, is_generated};
 pub use god_object::detect_god_objects;
-pub use graph::DependencyGraph;
+pub use graph::{DependencyGraph, EdgeInfo, is_relative_specifier, resolve_relative_import};
 pub use impact::{
-    ImpactAnalysis, ImpactError, compute_impact, format_impact_json, format_impact_markdown,
+    ImpactAnalysis, ImpactError, ImpactNode, compute_impact, format_impact_dot, format_impact_json,
+    format_impact_markdown,
 };
+pub use implementations::detect_scattered_implementations;
+pub use key_files::{KeyFile, rank_key_files};
+pub use limits::{disable_size_limits, quiet_contained_panics, size_limits_disabled, skip_reason};
+pub use packages::{detect_god_packages, package_dir};
+pub use progress::{CancellationToken, Cancelled, Monitor, Progress, ProgressCallback};
+pub use roles::detect_role_violations;
 
 use crate::config::Config;
-use crate::fs::{FileSystem, default_fs};
-use crate::model::{AnalysisResult, Module};
-use crate::parser::ParserRegistry;
+use crate::fs::{FileSystem, WalkOptions, default_fs};
+use crate::model::{AnalysisResult, Diagnostic, Issue, IssueKind, Module, intern_modules};
+use crate::parser::{LanguageParser, ParserRegistry, parse_tags};
 use crate::style;
-use ignore::{WalkBuilder, WalkState};
-use std::path::Path;
-use std::sync::Mutex;
+use boundary::scan_crossings;
+use cache::{CachedModule, ParseCache};
+use limits::parse_contained;
+use rayon::iter::{IntoParallelRefIterator, ParallelIterator};
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Mutex, OnceLock};
+use std::time::Instant;
+
+/// `--threads`, which takes precedence over the `threads` config key
+static THREADS_FLAG: OnceLock<usize> = OnceLock::new();
+
+/// Set the `--threads` value for this process; 0 means auto.
+pub fn set_threads(threads: usize) {
+    let _ = THREADS_FLAG.set(threads);
+}
+
+/// `--follow-symlinks`, which turns on the `follow_symlinks` config key
+static FOLLOW_SYMLINKS_FLAG: AtomicBool = AtomicBool::new(false);
+
+/// Follow symlinked directories for the rest of this process.
+pub fn set_follow_symlinks() {
+    FOLLOW_SYMLINKS_FLAG.store(true, Ordering::Relaxed);
+}
+
+/// Whether discovery descends into symlinked directories: `--follow-symlinks`
+/// or the `follow_symlinks` config key.
+pub fn follow_symlinks(config: &Config) -> bool {
+    FOLLOW_SYMLINKS_FLAG.load(Ordering::Relaxed) || config.follow_symlinks
+}
+
+/// A thread count that takes precedence over the `threads` config key, and
+/// where it comes from: `--threads` or `ARCHMAP_THREADS`.
+pub fn threads_override() -> Option<(&'static str, usize)> {
+    let env = std::env::var("ARCHMAP_THREADS").ok();
+    threads_override_from(THREADS_FLAG.get().copied(), env.as_deref())
+}
+
+/// [`threads_override`] given the `--threads` value and `ARCHMAP_THREADS`.
+fn threads_override_from(flag: Option<usize>, env: Option<&str>) -> Option<(&'static str, usize)> {
+    match flag {
+        Some(threads) => Some(("--threads", threads)),
+        None => env
+            .and_then(parse_threads_env)
+            .map(|threads| ("ARCHMAP_THREADS", threads)),
+    }
+}
+
+/// Worker threads for analysis from the `threads` config key, which commands
+/// fill in from [`threads_override`] when they load the config. 0 means auto:
+/// every core, or half of them in watch mode, which keeps re-running in the
+/// background. Watch mode stores the result back in the config, so discovery
+/// sees a count rather than auto.
+pub fn thread_count(config: &Config, watch: bool) -> usize {
+    match config.threads {
+        0 if watch => (num_cpus() / 2).max(1),
+        0 => num_cpus(),
+        threads => threads,
+    }
+}
 
 pub fn analyze(
     path: &Path,
@@ -45,120 +138,559 @@ pub fn analyze_with_fs(
     exclude: &[String],
     fs: &dyn FileSystem,
 ) -> AnalysisResult {
-    let project_name = path
+    analyze_filtered_with_fs(path, config, registry, exclude, &PathFilter::default(), fs)
+}
+
+/// Run analysis on only the files selected by `filter` (include/exclude globs).
+pub fn analyze_filtered(
+    path: &Path,
+    config: &Config,
+    registry: &ParserRegistry,
+    exclude: &[String],
+    filter: &PathFilter,
+) -> AnalysisResult {
+    analyze_filtered_with_fs(path, config, registry, exclude, filter, default_fs())
+}
+
+pub fn analyze_filtered_with_fs(
+    path: &Path,
+    config: &Config,
+    registry: &ParserRegistry,
+    exclude: &[String],
+    filter: &PathFilter,
+    fs: &dyn FileSystem,
+) -> AnalysisResult {
+    analyze_paths_with_fs(
+        path,
+        &[path.to_path_buf()],
+        config,
+        registry,
+        exclude,
+        filter,
+        fs,
+    )
+}
+
+/// Analyze several directories as one project. `root` (usually their common
+/// ancestor) names the project and anchors relative paths and include globs.
+pub fn analyze_paths(
+    root: &Path,
+    paths: &[PathBuf],
+    config: &Config,
+    registry: &ParserRegistry,
+    exclude: &[String],
+    filter: &PathFilter,
+) -> AnalysisResult {
+    analyze_paths_with_fs(root, paths, config, registry, exclude, filter, default_fs())
+}
+
+pub fn analyze_paths_with_fs(
+    root: &Path,
+    paths: &[PathBuf],
+    config: &Config,
+    registry: &ParserRegistry,
+    exclude: &[String],
+    filter: &PathFilter,
+    fs: &dyn FileSystem,
+) -> AnalysisResult {
+    let sources = Sources {
+        root,
+        paths,
+        exclude,
+        filter,
+    };
+    analyze_sources(
+        &sources,
+        config,
+        registry,
+        fs,
+        &Monitor::default(),
+        &DetectorSet::builtin(),
+    )
+    .expect("analysis without a cancellation token runs to the end")
+}
+
+/// Analyze `path` as [`analyze_filtered_with_fs`] does, reporting each step to
+/// `monitor` and stopping early once its cancellation token is cancelled.
+pub fn analyze_monitored(
+    path: &Path,
+    config: &Config,
+    registry: &ParserRegistry,
+    exclude: &[String],
+    filter: &PathFilter,
+    fs: &dyn FileSystem,
+    monitor: &Monitor,
+) -> Result<AnalysisResult, Cancelled> {
+    let sources = Sources {
+        root: path,
+        paths: &[path.to_path_buf()],
+        exclude,
+        filter,
+    };
+    analyze_sources(
+        &sources,
+        config,
+        registry,
+        fs,
+        monitor,
+        &DetectorSet::builtin(),
+    )
+}
+
+/// Where analysis looks for source files
+pub struct Sources<'a> {
+    /// Names the project and anchors relative paths and include globs
+    pub root: &'a Path,
+    /// Directories walked for source files
+    pub paths: &'a [PathBuf],
+    /// Directory names or globs pruned from the walk
+    pub exclude: &'a [String],
+    pub filter: &'a PathFilter,
+}
+
+/// The analysis every other `analyze*` function runs: discover and parse
+/// `sources`, then run `detectors` over them, reporting to `monitor`.
+pub fn analyze_sources(
+    sources: &Sources<'_>,
+    config: &Config,
+    registry: &ParserRegistry,
+    fs: &dyn FileSystem,
+    monitor: &Monitor,
+    detectors: &DetectorSet,
+) -> Result<AnalysisResult, Cancelled> {
+    let project_name = sources
+        .root
         .file_name()
         .and_then(|s| s.to_str())
         .unwrap_or("project")
         .to_string();
 
+    let started = Instant::now();
+
     // Discover and parse all modules
-    let modules = discover_modules(path, registry, exclude, fs);
+    let Discovered {
+        mut modules,
+        generated: generated_files,
+        mut diagnostics,
+        crossings,
+    } = discover_modules(sources, config, registry, fs, monitor)?;
+    monitor.report(Progress::Discovered {
+        modules: modules.len(),
+    });
+    // Files were parsed independently, each with its own copy of every name
+    style::verbose(&format!("Memory: {}", intern_modules(&mut modules)));
 
     // Build dependency graph
-    let dep_graph = DependencyGraph::build(&modules);
+    let dep_graph = {
+        let _span = tracing::info_span!("graph").entered();
+        DependencyGraph::build(&modules)
+    };
+    for module in &mut modules {
+        module.resolved_imports = dep_graph.direct_dependencies(&module.path);
+    }
+    diagnostics.extend(unresolved_imports(&modules, &dep_graph));
 
-    // Run all analyses
-    let mut issues = Vec::new();
+    diagnostics.extend(unknown_checks(config));
+    let input = DetectorInput {
+        modules: &modules,
+        graph: &dep_graph,
+        config,
+        fs,
+        crossings: Some(&crossings),
+    };
+    let mut issues = run_detectors(&input, detectors, monitor)?;
 
-    // Circular dependencies
-    issues.extend(detect_circular_dependencies(&dep_graph));
+    // Generated files take part in the graph, but their issues are noise
+    if !config.include_generated {
+        drop_generated_issues(&mut issues, &generated_files);
+    }
 
-    // God objects
-    issues.extend(detect_god_objects(&modules, config));
+    style::verbose(&format!(
+        "Analyzed {} modules in {:.1?} ({} issues)",
+        modules.len(),
+        started.elapsed(),
+        issues.len()
+    ));
 
-    // High coupling
-    issues.extend(detect_high_coupling(&dep_graph, config));
+    Ok(AnalysisResult {
+        project_name,
+        modules,
+        issues,
+        dependency_graph: dep_graph,
+        generated_files,
+        diagnostics,
+        module_index: Default::default(),
+    })
+}
 
-    // Boundary violations
-    issues.extend(detect_boundary_violations_with_fs(&modules, config, fs));
+/// Run every enabled detector over already-parsed modules. Each detector runs
+/// in its own span so `--log-format json` reports its timing.
+pub fn detect_issues(
+    modules: &[Module],
+    graph: &DependencyGraph,
+    config: &Config,
+    fs: &dyn FileSystem,
+) -> Vec<Issue> {
+    detect_issues_monitored(modules, graph, config, fs, &Monitor::default())
+        .expect("detection without a cancellation token runs to the end")
+}
 
-    // Deep dependency chains
-    issues.extend(detect_deep_dependency_chains(&dep_graph, config));
+/// Run the enabled detectors as [`detect_issues`] does, reporting each one to
+/// `monitor` and checking for cancellation before it starts.
+pub fn detect_issues_monitored(
+    modules: &[Module],
+    graph: &DependencyGraph,
+    config: &Config,
+    fs: &dyn FileSystem,
+    monitor: &Monitor,
+) -> Result<Vec<Issue>, Cancelled> {
+    let input = DetectorInput {
+        modules,
+        graph,
+        config,
+        fs,
+        crossings: None,
+    };
+    run_detectors(&input, &DetectorSet::builtin(), monitor)
+}
+
+/// Run each detector in `detectors` whose issue kind the config leaves enabled.
+pub fn run_detectors(
+    input: &DetectorInput<'_>,
+    detectors: &DetectorSet,
+    monitor: &Monitor,
+) -> Result<Vec<Issue>, Cancelled> {
+    let mut issues = Vec::new();
+    // Skip detectors for disabled issue kinds
+    let enabled: Vec<&dyn Detector> = detectors
+        .iter()
+        .filter(|d| {
+            d.kind()
+                .is_none_or(|kind| !input.config.is_check_disabled(kind))
+        })
+        .collect();
+    let total = enabled.len();
+    for (i, detector) in enabled.into_iter().enumerate() {
+        monitor.check()?;
+        monitor.report(Progress::DetectorStarted {
+            name: detector.name().to_string(),
+            index: i + 1,
+            total,
+        });
+        let span = tracing::info_span!(
+            "detector",
+            kind = detector.kind().unwrap_or(detector.name()),
+            issues = tracing::field::Empty
+        )
+        .entered();
+        let found = detector.run(input);
+        span.record("issues", found.len());
+        monitor.report(Progress::DetectorFinished {
+            name: detector.name().to_string(),
+            index: i + 1,
+            total,
+            issues: found.len(),
+        });
+        issues.extend(found);
+    }
 
-    // Low cohesion modules
-    issues.extend(detect_low_cohesion(&modules, &dep_graph, config));
+    Ok(issues)
+}
 
-    // Fat modules (excessive internal complexity)
-    issues.extend(detect_fat_modules(&modules, config));
+fn unknown_checks(config: &Config) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+    for name in &config.disabled_checks {
+        if IssueKind::canonical_name(name).is_none() {
+            diagnostics.push(Diagnostic::unknown_check(format!(
+                "Unknown check '{}' cannot be disabled. Known checks: {}",
+                name,
+                IssueKind::NAMES.join(", ")
+            )));
+        }
+    }
+    for name in &config.enabled_checks {
+        if !IssueKind::canonical_name(name).is_some_and(|kind| IssueKind::OPT_IN.contains(&kind)) {
+            diagnostics.push(Diagnostic::unknown_check(format!(
+                "'{}' is not an opt-in check; other checks always run. Opt-in checks: {}",
+                name,
+                IssueKind::OPT_IN.join(", ")
+            )));
+        }
+    }
+    diagnostics
+}
 
-    AnalysisResult {
-        project_name,
-        modules,
-        issues,
-        dependency_graph: dep_graph.into_inner(),
+/// Unresolved relative imports below this count are usually a stray typo
+const UNRESOLVED_IMPORTS_MIN: usize = 5;
+
+/// A diagnostic when at least a quarter of the relative imports point at
+/// files that weren't analyzed, which usually means part of the project was
+/// excluded or filtered out by language.
+fn unresolved_imports(modules: &[Module], graph: &DependencyGraph) -> Option<Diagnostic> {
+    let known = graph.node_indices();
+    let aliases: HashSet<&Path> = modules
+        .iter()
+        .flat_map(|m| m.aliases.iter().map(PathBuf::as_path))
+        .collect();
+    let exists = |p: &Path| known.contains_key(p) || aliases.contains(p);
+    let (mut relative, mut unresolved) = (0, 0);
+    for module in modules {
+        for import in module.imports.iter().filter(|i| is_relative_specifier(i)) {
+            relative += 1;
+            if resolve_relative_import(import, &module.path, exists).is_none() {
+                unresolved += 1;
+            }
+        }
     }
+    (unresolved >= UNRESOLVED_IMPORTS_MIN && unresolved * 4 >= relative)
+        .then(|| Diagnostic::unresolved_imports(unresolved, relative))
+}
+
+/// What discovery found: parsed modules, files detected as generated, and
+/// files it had to skip.
+struct Discovered {
+    /// Sorted by path, however the walk's threads happened to finish, so
+    /// reports, JSON arrays and snapshots list modules in the same order
+    /// every run
+    modules: Vec<Module>,
+    generated: Vec<PathBuf>,
+    diagnostics: Vec<Diagnostic>,
+    /// Boundary crossings in each module, found while its source was at hand
+    crossings: HashMap<PathBuf, Crossings>,
 }
 
+/// Parse every source file under the sources' paths.
 fn discover_modules(
-    path: &Path,
+    sources: &Sources<'_>,
+    config: &Config,
     registry: &ParserRegistry,
-    exclude: &[String],
     fs: &dyn FileSystem,
-) -> Vec<Module> {
+    monitor: &Monitor,
+) -> Result<Discovered, Cancelled> {
+    let Sources {
+        root,
+        paths,
+        exclude,
+        filter,
+    } = *sources;
     let modules = Mutex::new(Vec::new());
-    let exclude: Vec<String> = exclude.to_vec();
-
-    // Use parallel walker from ignore crate - much faster than sequential + rayon
-    let mut builder = WalkBuilder::new(path);
-    builder
-        .hidden(true)
-        .git_ignore(true)
-        .threads(num_cpus())
-        .filter_entry(move |entry| {
-            // Check if this entry matches any exclusion pattern
-            let path = entry.path();
-            for pattern in &exclude {
-                if path.ends_with(pattern)
-                    || path.to_string_lossy().contains(&format!("/{}/", pattern))
-                {
-                    return false;
-                }
-            }
-            true
-        });
+    let generated = Mutex::new(Vec::new());
+    let diagnostics = Mutex::new(Vec::new());
+    let crossings = Mutex::new(HashMap::new());
+    let parsed = AtomicUsize::new(0);
+    let cached = AtomicUsize::new(0);
+    // Only files on disk are worth keeping between runs
+    let cache =
+        (cache_enabled(config) && fs.on_disk()).then(|| ParseCache::open(root, &config.boundaries));
 
-    let walker = builder.build_parallel();
+    let threads = thread_count(config, false);
+    let started = Instant::now();
+    if let Some(value) = ignored_env_threads() {
+        diagnostics
+            .lock()
+            .unwrap()
+            .push(Diagnostic::config_fallback(
+                None,
+                format!(
+                    "Ignoring ARCHMAP_THREADS={}: expected a thread count (0 = auto)",
+                    value
+                ),
+            ));
+    }
+    let options = WalkOptions {
+        exclude: exclude.to_vec(),
+        follow_links: follow_symlinks(config),
+    };
 
-    walker.run(|| {
-        Box::new(|entry| {
-            let entry = match entry {
-                Ok(e) => e,
-                Err(_) => return WalkState::Continue,
+    // List the files some parser handles, outside the include/exclude globs
+    let files = {
+        let span = tracing::info_span!("discover", files = tracing::field::Empty).entered();
+        let mut files = Vec::new();
+        for file_path in paths.iter().flat_map(|path| fs.walk(path, &options)) {
+            monitor.check()?;
+            if !filter.matches(&file_path, Some(root)) {
+                continue;
+            }
+            let Some(parser) = registry.find_parser(&file_path) else {
+                continue;
             };
+            files.push((file_path, parser));
+            monitor.report(Progress::FileDiscovered {
+                path: files[files.len() - 1].0.clone(),
+                discovered: files.len(),
+            });
+        }
+        span.record("files", files.len());
+        files
+    };
 
-            let file_path = entry.path();
-
-            // Skip non-files
-            if !file_path.is_file() {
-                return WalkState::Continue;
+    let visit = |file_path: &Path, parser: &dyn LanguageParser| {
+        // Read and parse using the FileSystem abstraction
+        let source = match fs.read_to_string(file_path) {
+            Ok(source) => source,
+            Err(e) => {
+                let diagnostic = Diagnostic::read_failed(file_path.to_path_buf(), e);
+                diagnostics.lock().unwrap().push(diagnostic);
+                return;
             }
+        };
 
-            // Find parser for this file type
-            let parser = match registry.find_parser(file_path) {
-                Some(p) => p,
-                None => return WalkState::Continue,
-            };
+        if let Some(reason) = skip_reason(&source, config) {
+            let diagnostic = Diagnostic::skipped(file_path.to_path_buf(), reason);
+            diagnostics.lock().unwrap().push(diagnostic);
+            return;
+        }
 
-            // Read and parse using the FileSystem abstraction
-            let source = match fs.read_to_string(file_path) {
-                Ok(s) => s,
-                Err(_) => return WalkState::Continue,
-            };
+        // Skip files without the requested tags before paying for a parse
+        let tags = parse_tags(&source);
+        if !filter.matches_tags(&tags) {
+            return;
+        }
 
-            match parser.parse_module(file_path, &source) {
-                Ok(module) => {
-                    modules.lock().unwrap().push(module);
-                }
-                Err(e) => {
-                    style::warning(&format!("Failed to parse {}: {}", file_path.display(), e));
+        if is_generated(file_path, &source, config) {
+            generated.lock().unwrap().push(file_path.to_path_buf());
+        }
+
+        let hit = cache
+            .as_ref()
+            .and_then(|cache| cache.get(file_path, parser.name(), &source));
+        let entry = match hit {
+            Some(entry) => {
+                monitor.report(Progress::FileCached {
+                    path: file_path.to_path_buf(),
+                    cached: cached.fetch_add(1, Ordering::Relaxed) + 1,
+                });
+                entry
+            }
+            None => {
+                let parse_started = Instant::now();
+                let mut module = match parse_contained(parser, file_path, &source, config) {
+                    Ok(module) => module,
+                    Err(diagnostic) => {
+                        diagnostics.lock().unwrap().push(diagnostic);
+                        return;
+                    }
+                };
+                module.tags = tags;
+                style::verbose(&format!(
+                    "Parsed {} ({} lines, {:.1?})",
+                    file_path.strip_prefix(root).unwrap_or(file_path).display(),
+                    module.lines,
+                    parse_started.elapsed()
+                ));
+                monitor.report(Progress::FileParsed {
+                    path: file_path.to_path_buf(),
+                    parsed: parsed.fetch_add(1, Ordering::Relaxed) + 1,
+                });
+                let entry = CachedModule {
+                    crossings: scan_crossings(file_path, &source, &config.boundaries),
+                    module,
+                };
+                if let Some(cache) = &cache {
+                    cache.insert(file_path, parser.name(), &source, entry.clone());
                 }
+                entry
             }
+        };
+        crossings
+            .lock()
+            .unwrap()
+            .insert(file_path.to_path_buf(), entry.crossings);
+        modules.lock().unwrap().push(entry.module);
+    };
 
-            WalkState::Continue
+    let parse_span = tracing::info_span!(
+        "parse",
+        files = files.len(),
+        parsed = tracing::field::Empty,
+        cached = tracing::field::Empty
+    )
+    .entered();
+    let parse_all = || {
+        files.par_iter().try_for_each(|(file_path, parser)| {
+            monitor.check()?;
+            visit(file_path, *parser);
+            Ok(())
         })
-    });
+    };
+    match rayon::ThreadPoolBuilder::new().num_threads(threads).build() {
+        Ok(pool) => pool.install(parse_all)?,
+        Err(_) => parse_all()?,
+    }
+    parse_span.record("parsed", parsed.load(Ordering::Relaxed));
+    parse_span.record("cached", cached.load(Ordering::Relaxed));
+    parse_span.exit();
+
+    monitor.check()?;
+
+    let mut modules = modules.into_inner().unwrap();
+    let mut generated = generated.into_inner().unwrap();
+    let mut diagnostics = diagnostics.into_inner().unwrap();
+    style::verbose(&format!(
+        "Discovered and parsed {} files in {:.1?} on {} threads",
+        modules.len(),
+        started.elapsed(),
+        threads
+    ));
+    if let Some(cache) = cache {
+        style::verbose(&format!("Parse cache: {}", cache.stats()));
+        // A read-only checkout just goes without
+        if let Err(e) = cache.save() {
+            style::verbose(&format!("Could not write the parse cache: {}", e));
+        }
+    }
+    generated.sort();
+    generated.dedup();
+    // Parallel discovery finishes files in any order
+    diagnostics.sort_by(|a: &Diagnostic, b| a.path.cmp(&b.path));
+    diagnostics.dedup();
+
+    // Parallel discovery finishes files in any order, and nested paths
+    // (e.g. `src src/core`) walk the same files twice
+    modules.sort_by(|a, b| a.path.cmp(&b.path));
+    modules.dedup_by(|a, b| a.path == b.path);
+    // Following symlinks can reach one file through several paths; merging
+    // keeps the sorted order
+    if options.follow_links && fs.on_disk() {
+        modules = merge_symlinked(modules);
+    }
+
+    Ok(Discovered {
+        modules,
+        generated,
+        diagnostics,
+        crossings: crossings.into_inner().unwrap(),
+    })
+}
 
-    modules.into_inner().unwrap()
+/// Merge modules that are one file reached through different symlinks into
+/// the one whose path sorts first, recording the other paths as its aliases.
+fn merge_symlinked(mut modules: Vec<Module>) -> Vec<Module> {
+    modules.sort_by(|a, b| a.path.cmp(&b.path));
+    let mut by_target: HashMap<PathBuf, usize> = HashMap::new();
+    let mut merged: Vec<Module> = Vec::with_capacity(modules.len());
+    for module in modules {
+        let target = std::fs::canonicalize(&module.path).unwrap_or_else(|_| module.path.clone());
+        match by_target.get(&target) {
+            Some(&kept) => merged[kept].aliases.push(module.path),
+            None => {
+                by_target.insert(target, merged.len());
+                merged.push(module);
+            }
+        }
+    }
+    merged
+}
+
+/// `ARCHMAP_THREADS` when it is set but isn't a thread count
+fn ignored_env_threads() -> Option<String> {
+    std::env::var("ARCHMAP_THREADS")
+        .ok()
+        .filter(|value| parse_threads_env(value).is_none())
+}
+
+fn parse_threads_env(value: &str) -> Option<usize> {
+    value.trim().parse().ok()
 }
 
 fn num_cpus() -> usize {
@@ -166,3 +698,135 @@ fn num_cpus() -> usize {
         .map(|n| n.get())
         .unwrap_or(4)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_thread_count_uses_config() {
+        let config = Config {
+            threads: 3,
+            ..Default::default()
+        };
+        let threads = thread_count(&config, false);
+        assert_eq!(threads, 3);
+
+        // Auto leaves cores free in watch mode
+        let auto = Config::default();
+        assert_eq!(thread_count(&auto, false), num_cpus());
+        assert_eq!(thread_count(&auto, true), (num_cpus() / 2).max(1));
+
+        // Discovery keeps the count watch mode resolved
+        let watching = Config {
+            threads: thread_count(&auto, true),
+            ..Default::default()
+        };
+        assert_eq!(thread_count(&watching, false), (num_cpus() / 2).max(1));
+    }
+
+    #[test]
+    fn test_threads_env_must_be_a_count() {
+        assert_eq!(parse_threads_env("4"), Some(4));
+        assert_eq!(parse_threads_env(" 0 "), Some(0));
+        assert_eq!(parse_threads_env("-1"), None);
+        assert_eq!(parse_threads_env("many"), None);
+    }
+
+    #[test]
+    fn test_threads_flag_beats_env() {
+        assert_eq!(threads_override_from(None, None), None);
+        assert_eq!(
+            threads_override_from(None, Some("2")),
+            Some(("ARCHMAP_THREADS", 2))
+        );
+        assert_eq!(
+            threads_override_from(Some(0), Some("2")),
+            Some(("--threads", 0))
+        );
+        assert_eq!(threads_override_from(None, Some("many")), None);
+    }
+
+    #[test]
+    fn test_diagnostics_collect_unknown_checks_and_unresolved_imports() {
+        use crate::fs::MemoryFileSystem;
+        use crate::model::DiagnosticKind;
+
+        let missing: String = (0..5)
+            .map(|i| format!("import {{ x{i} }} from './gone{i}';\n"))
+            .collect();
+        let fs = MemoryFileSystem::new()
+            .with_file(
+                "/repo/src/app.ts",
+                format!("{missing}import {{ y }} from './util';\n"),
+            )
+            .with_file("/repo/src/util.ts", "export const y = 1;\n");
+        let config = Config {
+            disabled_checks: vec!["nonsense".to_string()],
+            ..Default::default()
+        };
+        let result = analyze_with_fs(
+            Path::new("/repo"),
+            &config,
+            &ParserRegistry::new(),
+            &[],
+            &fs,
+        );
+
+        let kinds: Vec<_> = result.diagnostics.iter().map(|d| d.kind).collect();
+        assert_eq!(
+            kinds,
+            [
+                DiagnosticKind::UnresolvedImports,
+                DiagnosticKind::UnknownCheck
+            ]
+        );
+        assert!(
+            result.diagnostics[0]
+                .message
+                .starts_with("5 of 6 relative imports")
+        );
+        assert!(result.diagnostics[1].message.contains("'nonsense'"));
+
+        // A single stray import is below the threshold
+        let fs = MemoryFileSystem::new().with_file("/repo/src/app.ts", "import './gone';\n");
+        let result = analyze_with_fs(
+            Path::new("/repo"),
+            &Config::default(),
+            &ParserRegistry::new(),
+            &[],
+            &fs,
+        );
+        assert!(result.diagnostics.is_empty());
+    }
+
+    #[test]
+    fn test_discovery_and_parsing_are_timed_separately() {
+        use crate::fs::MemoryFileSystem;
+        use crate::logging::capture::capture;
+
+        let fs = MemoryFileSystem::new()
+            .with_file("/repo/src/app.ts", "import { y } from './util';\n")
+            .with_file("/repo/src/util.ts", "export const y = 1;\n")
+            .with_file("/repo/README.md", "# not source\n");
+        let lines = capture(tracing::Level::INFO, || {
+            analyze_with_fs(
+                Path::new("/repo"),
+                &Config::default(),
+                &ParserRegistry::new(),
+                &[],
+                &fs,
+            );
+        });
+
+        let span = |name: &str| {
+            lines
+                .iter()
+                .find(|line| line["span"]["name"] == name)
+                .unwrap_or_else(|| panic!("no {} span in {:?}", name, lines))
+        };
+        assert_eq!(span("discover")["span"]["files"], 2);
+        assert_eq!(span("parse")["span"]["parsed"], 2);
+        assert!(span("parse")["time.busy"].is_string());
+    }
+}
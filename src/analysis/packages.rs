@@ -0,0 +1,198 @@
+//! Package-level checks. A package is a top-level directory, grouped the way
+//! the AI layout summary groups them (`src/analysis`, not `src`).
+
+use crate::analysis::DependencyGraph;
+use crate::config::Config;
+use crate::model::{Issue, Location, Module};
+use std::collections::{BTreeMap, HashSet};
+use std::path::{Path, PathBuf};
+
+/// Directory names conventionally holding all of a project's source code.
+/// Modules beneath them are grouped one level deeper (`src/analysis`, not `src`).
+const SOURCE_ROOTS: &[&str] = &["src", "lib", "app", "packages"];
+
+/// Modules listed on a god package issue, largest first
+const LISTED_MODULES: usize = 5;
+
+/// Top-level directory a path relative to the project root belongs to; empty
+/// for files at the root itself.
+pub fn package_dir(rel: &Path) -> PathBuf {
+    let dirs: Vec<_> = rel
+        .parent()
+        .map(|p| p.components().collect())
+        .unwrap_or_default();
+    match dirs.as_slice() {
+        [] => PathBuf::new(),
+        [root, sub, ..] if SOURCE_ROOTS.contains(&root.as_os_str().to_str().unwrap_or("")) => {
+            PathBuf::from(root.as_os_str()).join(sub.as_os_str())
+        }
+        [first, ..] => PathBuf::from(first.as_os_str()),
+    }
+}
+
+/// Directory every module lives under. Detectors aren't told the project
+/// root, so this stands in for it; when it is itself a source root such as
+/// `src`, its parent is used so packages are still named `src/...`.
+fn common_root(modules: &[Module]) -> PathBuf {
+    let mut parents = modules.iter().filter_map(|m| m.path.parent());
+    let Some(first) = parents.next() else {
+        return PathBuf::new();
+    };
+    let mut root = first.to_path_buf();
+    for parent in parents {
+        while !parent.starts_with(&root) {
+            if !root.pop() {
+                return PathBuf::new();
+            }
+        }
+    }
+    let is_source_root = root
+        .file_name()
+        .and_then(|name| name.to_str())
+        .is_some_and(|name| SOURCE_ROOTS.contains(&name));
+    if is_source_root {
+        root.pop();
+    }
+    root
+}
+
+/// Flag packages with more than `god_package_modules` modules or
+/// `god_package_lines` lines, largest first.
+pub fn detect_god_packages(
+    modules: &[Module],
+    graph: &DependencyGraph,
+    config: &Config,
+) -> Vec<Issue> {
+    let root = common_root(modules);
+    let mut packages: BTreeMap<PathBuf, Vec<&Module>> = BTreeMap::new();
+    for module in modules {
+        let rel = module.path.strip_prefix(&root).unwrap_or(&module.path);
+        let dir = package_dir(rel);
+        // The project root isn't a package
+        if !dir.as_os_str().is_empty() {
+            packages.entry(dir).or_default().push(module);
+        }
+    }
+
+    let t = &config.thresholds;
+    let mut found: Vec<(usize, Issue)> = Vec::new();
+    for (dir, mut members) in packages {
+        let lines: usize = members.iter().map(|m| m.lines).sum();
+        if members.len() <= t.god_package_modules && lines <= t.god_package_lines {
+            continue;
+        }
+
+        let inside: HashSet<&Path> = members.iter().map(|m| m.path.as_path()).collect();
+        let outgoing: HashSet<PathBuf> = members
+            .iter()
+            .flat_map(|m| graph.direct_dependencies(&m.path))
+            .filter(|dep| !inside.contains(dep.as_path()))
+            .collect();
+        let incoming: HashSet<PathBuf> = members
+            .iter()
+            .flat_map(|m| graph.direct_dependents(&m.path))
+            .filter(|dep| !inside.contains(dep.as_path()))
+            .collect();
+
+        members.sort_by(|a, b| b.lines.cmp(&a.lines).then_with(|| a.path.cmp(&b.path)));
+        let locations = members
+            .iter()
+            .take(LISTED_MODULES)
+            .map(|m| Location {
+                path: m.path.clone(),
+                line: None,
+                context: Some(format!("{} lines", m.lines)),
+                indicator: None,
+            })
+            .collect();
+
+        let issue = Issue::god_package(
+            dir.display().to_string(),
+            members.len(),
+            lines,
+            outgoing.len(),
+            incoming.len(),
+            locations,
+        );
+        found.push((lines, issue));
+    }
+
+    found.sort_by_key(|(lines, _)| std::cmp::Reverse(*lines));
+    found.into_iter().map(|(_, issue)| issue).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Thresholds;
+    use crate::model::IssueKind;
+
+    fn module(path: &str, lines: usize, imports: &[&str]) -> Module {
+        let mut module = Module::new(PathBuf::from(path));
+        module.lines = lines;
+        module.imports = imports.iter().map(|&i| i.into()).collect();
+        module
+    }
+
+    #[test]
+    fn test_package_dir_groups_below_source_roots() {
+        assert_eq!(
+            package_dir(Path::new("src/analysis/graph.rs")),
+            Path::new("src/analysis")
+        );
+        assert_eq!(package_dir(Path::new("tests/api.rs")), Path::new("tests"));
+        assert_eq!(package_dir(Path::new("src/lib.rs")), Path::new("src"));
+        assert_eq!(package_dir(Path::new("main.rs")), Path::new(""));
+    }
+
+    #[test]
+    fn test_large_packages_are_flagged_largest_first() {
+        let mut modules = vec![
+            module("/p/src/app.ts", 50, &["./services/orders"]),
+            module("/p/src/util/log.ts", 9000, &[]),
+        ];
+        for i in 0..5 {
+            let path = format!("/p/src/services/s{}.ts", i);
+            modules.push(module(&path, 100 + i, &["../util/log"]));
+        }
+        modules.push(module("/p/src/services/orders.ts", 10, &["./s0"]));
+
+        let config = Config {
+            thresholds: Thresholds {
+                god_package_modules: 5,
+                god_package_lines: 5000,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let graph = DependencyGraph::build(&modules);
+        let issues = detect_god_packages(&modules, &graph, &config);
+
+        let kinds: Vec<_> = issues.iter().map(|i| i.kind.clone()).collect();
+        assert_eq!(
+            kinds,
+            [
+                IssueKind::GodPackage {
+                    dir: "src/util".to_string(),
+                    modules: 1,
+                    lines: 9000,
+                },
+                IssueKind::GodPackage {
+                    dir: "src/services".to_string(),
+                    modules: 6,
+                    lines: 520,
+                },
+            ]
+        );
+
+        let services = &issues[1];
+        assert_eq!(services.locations.len(), LISTED_MODULES);
+        assert_eq!(
+            services.locations[0].path,
+            Path::new("/p/src/services/s4.ts")
+        );
+        // One package depended on (util), one depending on it (src/app.ts)
+        assert!(services.message.contains("1 modules outside"));
+        assert!(services.message.contains("imported by 1"));
+    }
+}
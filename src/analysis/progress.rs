@@ -0,0 +1,94 @@
+//! Progress reporting and cancellation for long analyses. Library callers pass
+//! a [`Monitor`] to follow along as files are parsed and detectors run, and to
+//! stop the analysis part way through.
+
+use std::fmt;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use thiserror::Error;
+
+/// One step of an analysis, in the order they happen
+#[derive(Debug, Clone, PartialEq)]
+pub enum Progress {
+    /// A source file some parser handles was found; `discovered` counts them so far
+    FileDiscovered { path: PathBuf, discovered: usize },
+    /// A file was parsed into a module; `parsed` counts them so far
+    FileParsed { path: PathBuf, parsed: usize },
+    /// A file's module came from the parse cache instead; `cached` counts them so far
+    FileCached { path: PathBuf, cached: usize },
+    /// Discovery finished with `modules` parsed modules
+    Discovered { modules: usize },
+    /// Detector `index` of `total` (counting from 1) started
+    DetectorStarted {
+        name: String,
+        index: usize,
+        total: usize,
+    },
+    /// Detector `index` of `total` finished, having found `issues`
+    DetectorFinished {
+        name: String,
+        index: usize,
+        total: usize,
+        issues: usize,
+    },
+}
+
+/// Called with each [`Progress`] step, possibly from several walker threads
+pub type ProgressCallback = Arc<dyn Fn(Progress) + Send + Sync>;
+
+/// A flag that stops an analysis between files and between detectors. Clones
+/// share the flag, so one can be handed to the analysis and another kept to
+/// cancel it from elsewhere.
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken(Arc<AtomicBool>);
+
+impl CancellationToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+/// The analysis stopped because its [`CancellationToken`] was cancelled
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Error)]
+#[error("analysis was cancelled")]
+pub struct Cancelled;
+
+/// What an analysis reports progress to and checks for cancellation
+#[derive(Clone, Default)]
+pub struct Monitor {
+    pub progress: Option<ProgressCallback>,
+    pub cancellation: Option<CancellationToken>,
+}
+
+impl Monitor {
+    pub(crate) fn report(&self, step: Progress) {
+        if let Some(progress) = &self.progress {
+            progress(step);
+        }
+    }
+
+    pub(crate) fn check(&self) -> Result<(), Cancelled> {
+        match &self.cancellation {
+            Some(token) if token.is_cancelled() => Err(Cancelled),
+            _ => Ok(()),
+        }
+    }
+}
+
+impl fmt::Debug for Monitor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Monitor")
+            .field("progress", &self.progress.is_some())
+            .field("cancellation", &self.cancellation)
+            .finish()
+    }
+}
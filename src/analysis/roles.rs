@@ -0,0 +1,129 @@
+//! Dependencies between framework roles that skip a layer.
+//!
+//! Angular and NestJS classes declare their role with a decorator
+//! (`@Component`, `@Controller`, `@Repository`), which the TypeScript parser
+//! records. Views and request handlers are meant to reach data access through
+//! a service, so a direct import from one to a repository is flagged.
+
+use super::DependencyGraph;
+use crate::model::{Issue, Location, Module, ModuleIndex};
+
+/// Roles that may not import modules of another role directly, as
+/// `(importer, imported)`
+const FORBIDDEN: &[(&str, &str)] = &[
+    ("component", "repository"),
+    ("directive", "repository"),
+    ("pipe", "repository"),
+    ("controller", "repository"),
+];
+
+/// Flag modules whose role imports a module of a role it should only reach
+/// through a service. A role comes from a class decorator or a `role` tag.
+pub fn detect_role_violations(modules: &[Module], graph: &DependencyGraph) -> Vec<Issue> {
+    let index = ModuleIndex::new(modules);
+    let mut issues = Vec::new();
+    for module in modules {
+        let Some(role) = module.role() else {
+            continue;
+        };
+        for dep in graph.direct_dependencies(&module.path) {
+            let Some(imported) = index.get(&dep) else {
+                continue;
+            };
+            let Some(imported_role) = imported.role() else {
+                continue;
+            };
+            if FORBIDDEN.contains(&(role, imported_role)) {
+                issues.push(Issue::role_violation(
+                    role.to_string(),
+                    imported_role.to_string(),
+                    vec![location(module), location(imported)],
+                ));
+            }
+        }
+    }
+    issues
+}
+
+/// Points at the module's first decorated class, or the top of the file
+fn location(module: &Module) -> Location {
+    let class = module
+        .definitions
+        .iter()
+        .find(|def| !def.decorators.is_empty());
+    Location {
+        path: module.path.clone(),
+        line: class.map(|def| def.line),
+        context: class.map(|def| format!("@{} {}", def.decorators[0], def.name)),
+        indicator: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{Definition, DefinitionKind, IssueKind, Visibility};
+    use std::path::{Path, PathBuf};
+
+    fn module(path: &str, decorator: Option<&str>, imports: &[&str]) -> Module {
+        let mut module = Module::new(PathBuf::from(path));
+        module.imports = imports.iter().map(|&i| i.into()).collect();
+        module.add_definition(Definition {
+            name: module.name.clone(),
+            kind: DefinitionKind::Class,
+            line: 4,
+            visibility: Visibility::Public,
+            signature: None,
+            span: None,
+            compact_signature: None,
+            decorators: decorator.map(str::to_string).into_iter().collect(),
+        });
+        module
+    }
+
+    #[test]
+    fn test_flags_views_importing_repositories() {
+        let modules = vec![
+            module(
+                "/p/users.component.ts",
+                Some("Component"),
+                &["./users.repo", "./users.service"],
+            ),
+            module("/p/users.service.ts", Some("Injectable"), &["./users.repo"]),
+            module(
+                "/p/users.controller.ts",
+                Some("Controller"),
+                &["./users.service"],
+            ),
+            module("/p/users.repo.ts", Some("Repository"), &[]),
+            module("/p/util.ts", None, &["./users.repo"]),
+        ];
+        let graph = DependencyGraph::build(&modules);
+        let issues = detect_role_violations(&modules, &graph);
+
+        assert_eq!(issues.len(), 1);
+        assert_eq!(
+            issues[0].kind,
+            IssueKind::RoleViolation {
+                role: "component".to_string(),
+                imported_role: "repository".to_string(),
+            }
+        );
+        let paths: Vec<_> = issues[0]
+            .locations
+            .iter()
+            .map(|l| l.path.as_path())
+            .collect();
+        assert_eq!(
+            paths,
+            [
+                Path::new("/p/users.component.ts"),
+                Path::new("/p/users.repo.ts")
+            ]
+        );
+        assert_eq!(
+            issues[0].locations[0].context.as_deref(),
+            Some("@Component users.component")
+        );
+    }
+}
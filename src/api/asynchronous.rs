@@ -0,0 +1,119 @@
+//! Async wrappers around the library functions, behind the `async` feature.
+//!
+//! The blocking functions remain the primary API; these run them on tokio's
+//! blocking pool so async services don't stall a worker thread. Dropping one of
+//! the returned futures, for example when `tokio::time::timeout` elapses,
+//! cancels the analysis through the options' [`CancellationToken`], so the
+//! blocking thread stops before its next file or detector.
+
+use super::{AiOptions, AnalysisOptions, ArchmapError, ImpactOptions, ImpactResult};
+use crate::analysis::{CancellationToken, Progress, ProgressCallback};
+use crate::model::AnalysisResult;
+use std::path::PathBuf;
+use std::sync::Arc;
+use tokio::sync::mpsc;
+
+/// A progress callback for [`AnalysisOptions::progress`] that forwards every
+/// step to the returned receiver.
+///
+/// ```
+/// # async fn run() -> Result<(), archmap::ArchmapError> {
+/// use archmap::api::{analyze_async, progress_channel};
+/// use archmap::{AnalysisOptions, Progress};
+///
+/// let (progress, mut steps) = progress_channel();
+/// let mut options = AnalysisOptions::default();
+/// options.progress = Some(progress);
+///
+/// let analysis = tokio::spawn(analyze_async(".", options));
+/// while let Some(step) = steps.recv().await {
+///     if let Progress::FileParsed { parsed, .. } = step {
+///         println!("parsed {} files", parsed);
+///     }
+/// }
+/// let result = analysis.await.expect("analysis panicked")?;
+/// # Ok(())
+/// # }
+/// ```
+pub fn progress_channel() -> (ProgressCallback, mpsc::UnboundedReceiver<Progress>) {
+    let (sender, receiver) = mpsc::unbounded_channel();
+    let callback: ProgressCallback = Arc::new(move |step| {
+        // A dropped receiver only means nobody is listening any more
+        let _ = sender.send(step);
+    });
+    (callback, receiver)
+}
+
+/// [`analyze`](super::analyze) on the blocking pool.
+///
+/// ```no_run
+/// # async fn run() {
+/// use archmap::AnalysisOptions;
+/// use archmap::api::analyze_async;
+/// use std::time::Duration;
+///
+/// let analysis = analyze_async(".", AnalysisOptions::default());
+/// match tokio::time::timeout(Duration::from_secs(30), analysis).await {
+///     Ok(result) => println!("{} modules", result.unwrap().modules.len()),
+///     Err(_) => println!("gave up; the analysis stops on its own"),
+/// }
+/// # }
+/// ```
+pub async fn analyze_async(
+    path: impl Into<PathBuf>,
+    mut options: AnalysisOptions,
+) -> Result<AnalysisResult, ArchmapError> {
+    let path = path.into();
+    let token = options.cancellation.get_or_insert_default().clone();
+    run_blocking(token, move || super::analyze(&path, options)).await
+}
+
+/// [`impact`](super::impact) on the blocking pool.
+pub async fn impact_async(
+    project_path: impl Into<PathBuf>,
+    file: impl Into<PathBuf>,
+    mut options: ImpactOptions,
+) -> Result<ImpactResult, ArchmapError> {
+    let (project_path, file) = (project_path.into(), file.into());
+    let token = options.cancellation.get_or_insert_default().clone();
+    run_blocking(token, move || super::impact(&project_path, &file, options)).await
+}
+
+/// [`ai_context`](super::ai_context) on the blocking pool.
+pub async fn ai_context_async(
+    path: impl Into<PathBuf>,
+    mut options: AiOptions,
+) -> Result<String, ArchmapError> {
+    let path = path.into();
+    let token = options.cancellation.get_or_insert_default().clone();
+    run_blocking(token, move || super::ai_context(&path, options)).await
+}
+
+/// Cancels its token when dropped, unless disarmed first
+struct CancelOnDrop(Option<CancellationToken>);
+
+impl Drop for CancelOnDrop {
+    fn drop(&mut self) {
+        if let Some(token) = self.0.take() {
+            token.cancel();
+        }
+    }
+}
+
+async fn run_blocking<T: Send + 'static>(
+    token: CancellationToken,
+    work: impl FnOnce() -> Result<T, ArchmapError> + Send + 'static,
+) -> Result<T, ArchmapError> {
+    let mut guard = CancelOnDrop(Some(token));
+    let joined = tokio::task::spawn_blocking(work).await;
+    // Finished: leave the caller's token as it was
+    guard.0 = None;
+
+    match joined {
+        Ok(result) => result,
+        Err(e) => match e.try_into_panic() {
+            Ok(panic) => std::panic::resume_unwind(panic),
+            Err(e) => Err(ArchmapError::Io(std::io::Error::other(e))),
+        },
+    }
+}
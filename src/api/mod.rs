@@ -0,0 +1,684 @@
+//! Clean library API for archmap.
+//!
+//! This module provides a programmatic interface for using archmap as a Rust library.
+//! Unlike the CLI commands which print output and return exit codes, these functions
+//! return proper Result types that can be handled by calling code.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use archmap::{analyze, AnalysisOptions};
+//! use std::path::Path;
+//!
+//! let result = analyze(Path::new("."), AnalysisOptions::default())?;
+//! println!("Found {} modules", result.modules.len());
+//! for issue in &result.issues {
+//!     println!("Issue: {}", issue.message);
+//! }
+//! # Ok::<(), archmap::ArchmapError>(())
+//! ```
+//!
+//! # Querying results
+//!
+//! [`AnalysisResult`] answers the common lookups itself:
+//!
+//! ```
+//! use archmap::fs::MemoryFileSystem;
+//! use archmap::{analyze_with_fs, AnalysisOptions, IssueKindFilter, IssueSeverity};
+//! use std::path::Path;
+//!
+//! let fs = MemoryFileSystem::new()
+//!     .with_file("/repo/src/a.ts", "import { b } from './b';\nexport const a = 1;\n")
+//!     .with_file("/repo/src/b.ts", "import { a } from './a';\nexport const b = 2;\n");
+//! let result = analyze_with_fs(Path::new("/repo"), AnalysisOptions::default(), &fs)?;
+//!
+//! let a = result.module(Path::new("/repo/src/a.ts")).expect("analyzed");
+//! assert_eq!(a.imports, ["./b"]);
+//! assert_eq!(result.modules_matching("**/src/*.ts").count(), 2);
+//!
+//! let cycles = result.issues_of_kind(IssueKindFilter::CircularDependency).count();
+//! assert_eq!(cycles, 1);
+//! assert!(result.issues_for(Path::new("/repo/src/b.ts")).count() >= 1);
+//! assert!(result.issues_at_least(IssueSeverity::Error).count() >= 1);
+//!
+//! let summary = result.summary();
+//! assert_eq!(summary.modules, 2);
+//! assert_eq!(summary.by_kind["CircularDependency"], 1);
+//! # Ok::<(), archmap::ArchmapError>(())
+//! ```
+
+#[cfg(feature = "async")]
+mod asynchronous;
+mod options;
+
+#[cfg(feature = "async")]
+pub use asynchronous::{ai_context_async, analyze_async, impact_async, progress_channel};
+pub use options::{
+    AiFormat, AiOptions, AiOptionsBuilder, AiVisibility, AnalysisOptions, AnalysisOptionsBuilder,
+    ImpactOptions, ImpactOptionsBuilder, OptionsError, Priority,
+};
+
+use crate::analysis::{
+    self, Cancelled, ImpactAnalysis, ImpactError, ImpactNode, Monitor, PathFilter,
+};
+use crate::config::{Config, ConfigError};
+use crate::fs::{FileSystem, default_fs, normalize_path};
+use crate::graph::GraphData;
+use crate::model::{AnalysisResult, Diagnostic, DiagnosticKind};
+use crate::output::{AiOutput, FileSources, OutputFormatter};
+use crate::parser::{LanguageInfo, ParserRegistry};
+use crate::snapshot::SnapshotError;
+use std::io::Cursor;
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+
+/// Errors that can occur during archmap operations.
+#[derive(Debug, Error)]
+pub enum ArchmapError {
+    /// The specified path could not be found or resolved.
+    #[error("Path not found: {0}")]
+    PathNotFound(PathBuf),
+
+    /// Invalid analysis, impact or AI options.
+    #[error("Invalid options: {0}")]
+    InvalidOptions(#[from] OptionsError),
+
+    /// Configuration file error.
+    #[error("Configuration error: {0}")]
+    Config(#[from] ConfigError),
+
+    /// Impact analysis error.
+    #[error("Impact analysis error: {0}")]
+    Impact(#[from] ImpactError),
+
+    /// The analysis was cancelled through its cancellation token.
+    #[error("Analysis was cancelled")]
+    Cancelled,
+
+    /// A snapshot couldn't be read, parsed or written, or is newer than this build.
+    #[error(transparent)]
+    Snapshot(#[from] SnapshotError),
+
+    /// Source files were found, but none of them could be read or parsed.
+    /// Each failure is listed with its path.
+    #[error(
+        "{} source file(s) found, but none could be read or parsed; first: {}",
+        failures.len(),
+        failures.first().map_or("", |d| d.message.as_str())
+    )]
+    Parse { failures: Vec<Diagnostic> },
+
+    /// No parser handles this file, or the language filter leaves it out.
+    #[error(
+        "No parser for {}; analyzed languages: {}",
+        path.display(),
+        languages.join(", ")
+    )]
+    UnsupportedLanguage {
+        path: PathBuf,
+        languages: Vec<String>,
+    },
+
+    /// Writing the formatted output failed.
+    #[error("Failed to format {format} output: {source}")]
+    Format {
+        format: String,
+        source: std::io::Error,
+    },
+
+    /// IO error during analysis.
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+}
+
+impl From<Cancelled> for ArchmapError {
+    fn from(_: Cancelled) -> Self {
+        ArchmapError::Cancelled
+    }
+}
+
+/// `result`, unless files were found and every one of them failed to read or parse.
+fn require_parsed(result: AnalysisResult) -> Result<AnalysisResult, ArchmapError> {
+    if !result.modules.is_empty() {
+        return Ok(result);
+    }
+    let failures: Vec<Diagnostic> = result
+        .diagnostics
+        .iter()
+        .filter(|d| {
+            matches!(
+                d.kind,
+                DiagnosticKind::ReadFailed | DiagnosticKind::ParseFailed
+            )
+        })
+        .cloned()
+        .collect();
+    if failures.is_empty() {
+        Ok(result)
+    } else {
+        Err(ArchmapError::Parse { failures })
+    }
+}
+
+/// Result of impact analysis for a file.
+///
+/// This struct wraps the internal `ImpactAnalysis` and provides a clean API.
+pub struct ImpactResult {
+    /// The underlying impact analysis data.
+    inner: ImpactAnalysis,
+    /// Project root for relative path calculation.
+    project_root: PathBuf,
+}
+
+impl ImpactResult {
+    /// The target file that was analyzed.
+    pub fn target(&self) -> &Path {
+        &self.inner.target
+    }
+
+    /// Total number of files affected by changes to the target.
+    pub fn total_affected(&self) -> usize {
+        self.inner.total_affected
+    }
+
+    /// Maximum chain length (depth) from target to farthest dependent.
+    pub fn max_chain_length(&self) -> usize {
+        self.inner.max_chain_length
+    }
+
+    /// Files affected, organized by dependency depth from target.
+    /// Index 0 = direct dependents (depth 1), index 1 = depth 2, etc.
+    pub fn affected_by_depth(&self) -> &[Vec<PathBuf>] {
+        &self.inner.affected_by_depth
+    }
+
+    /// Get all affected files as a flat list.
+    pub fn all_affected(&self) -> Vec<&Path> {
+        self.inner
+            .affected_by_depth
+            .iter()
+            .flatten()
+            .map(|p| p.as_path())
+            .collect()
+    }
+
+    /// The tree of dependents rooted at the target, for rendering it yourself.
+    /// Node paths are as analyzed, usually absolute.
+    pub fn tree(&self) -> &ImpactNode {
+        &self.inner.tree
+    }
+
+    /// The target and every affected file, nearest first.
+    pub fn iter_breadth_first(&self) -> impl Iterator<Item = &ImpactNode> {
+        self.inner.tree.iter_breadth_first()
+    }
+
+    /// Distance of `path` from the target: 0 for the target itself, 1 for a
+    /// direct dependent, and so on. A relative path is taken from the project
+    /// root. `None` if the file isn't affected.
+    pub fn depth_of(&self, path: &Path) -> Option<usize> {
+        self.inner.tree.depth_of(&self.project_root.join(path))
+    }
+
+    /// Format the result as markdown.
+    pub fn to_markdown(&self, show_tree: bool) -> String {
+        analysis::format_impact_markdown(&self.inner, Some(&self.project_root), show_tree)
+    }
+
+    /// Format the result as JSON.
+    pub fn to_json(&self) -> String {
+        analysis::format_impact_json(&self.inner, Some(&self.project_root))
+    }
+
+    /// Format the tree as a Graphviz DOT digraph, with paths relative to the
+    /// project root.
+    pub fn to_dot(&self) -> String {
+        analysis::format_impact_dot(&self.inner, Some(&self.project_root))
+    }
+
+    /// Access the inner ImpactAnalysis for advanced use.
+    #[deprecated(note = "use `tree()` and the other accessors")]
+    pub fn inner(&self) -> &ImpactAnalysis {
+        &self.inner
+    }
+}
+
+/// Run architectural analysis on a codebase.
+///
+/// Analyzes the given path for architectural issues like circular dependencies,
+/// high coupling, god objects, boundary violations, and more.
+///
+/// # Arguments
+///
+/// * `path` - The root path of the codebase to analyze.
+/// * `options` - Analysis options controlling which languages to analyze, exclusions, and thresholds.
+///
+/// # Returns
+///
+/// An `AnalysisResult` containing modules, issues, and the dependency graph.
+/// Files that couldn't be read or parsed and settings that were ignored are
+/// listed in its `diagnostics` rather than printed.
+///
+/// # Example
+///
+/// ```no_run
+/// use archmap::{analyze, AnalysisOptions};
+/// use std::path::Path;
+///
+/// let result = analyze(Path::new("."), AnalysisOptions::default())?;
+/// println!("Analyzed {} modules", result.modules.len());
+/// println!("Found {} issues", result.issues.len());
+/// # Ok::<(), archmap::ArchmapError>(())
+/// ```
+pub fn analyze(path: &Path, options: AnalysisOptions) -> Result<AnalysisResult, ArchmapError> {
+    analyze_with_fs(path, options, default_fs())
+}
+
+/// Analyze a codebase read through `fs` instead of the disk.
+///
+/// With a virtual filesystem such as [`MemoryFileSystem`](crate::fs::MemoryFileSystem),
+/// `path` is not canonicalized and nothing on disk is read, so sources fetched
+/// from elsewhere can be analyzed as they are. `.archmap.toml` under `path` is
+/// read through `fs` too.
+///
+/// # Example
+///
+/// ```
+/// use archmap::fs::MemoryFileSystem;
+/// use archmap::{analyze_with_fs, AnalysisOptions};
+/// use std::path::Path;
+///
+/// let fs = MemoryFileSystem::new()
+///     .with_file("/repo/src/lib.rs", "mod util;\nuse crate::util::help;\npub fn run() { help(); }\n")
+///     .with_file("/repo/src/util.rs", "pub fn help() {}\n");
+///
+/// let result = analyze_with_fs(Path::new("/repo"), AnalysisOptions::default(), &fs)?;
+/// assert_eq!(result.modules.len(), 2);
+/// # Ok::<(), archmap::ArchmapError>(())
+/// ```
+pub fn analyze_with_fs(
+    path: &Path,
+    options: AnalysisOptions,
+    fs: &dyn FileSystem,
+) -> Result<AnalysisResult, ArchmapError> {
+    options.validate()?;
+    let resolved_path = resolve(path, fs)?;
+
+    // An unreadable .archmap.toml falls back to the defaults, noted in the result
+    let (mut config, config_fallback) = match Config::load_with_fs(&resolved_path, fs) {
+        Ok(config) => (config, None),
+        Err(e) => (
+            Config::default(),
+            Some(Diagnostic::config_fallback(
+                Some(resolved_path.join(".archmap.toml")),
+                format!("Ignoring .archmap.toml, using the defaults: {}", e),
+            )),
+        ),
+    };
+
+    // Apply options to config
+    config.thresholds.max_dependency_depth = options.max_depth;
+    config.thresholds.min_cohesion = options.min_cohesion;
+    config.include_generated = options.include_generated;
+    config.disabled_checks.extend(options.disabled_checks);
+    config.enabled_checks.extend(options.enabled_checks);
+    config.cache &= options.cache;
+
+    let registry = if options.languages.is_empty() {
+        ParserRegistry::new()
+    } else {
+        ParserRegistry::with_languages(&options.languages)
+    };
+
+    // Directory names prune the walk; globs are matched against relative paths
+    let filter = PathFilter::new(Vec::new(), options.exclude.clone());
+    let monitor = Monitor {
+        progress: options.progress,
+        cancellation: options.cancellation,
+    };
+    let sources = analysis::Sources {
+        root: &resolved_path,
+        paths: std::slice::from_ref(&resolved_path),
+        exclude: &options.exclude,
+        filter: &filter,
+    };
+    let mut result = analysis::analyze_sources(
+        &sources,
+        &config,
+        &registry,
+        fs,
+        &monitor,
+        &options.detectors,
+    )?;
+    result.diagnostics.splice(0..0, config_fallback);
+
+    require_parsed(result)
+}
+
+/// Analyze the change impact of a specific file.
+///
+/// Determines which files would be affected if the target file changes,
+/// by traversing the dependency graph in reverse (finding dependents).
+///
+/// # Arguments
+///
+/// * `project_path` - The root path of the codebase.
+/// * `file` - The file to analyze for change impact.
+/// * `options` - Impact analysis options.
+///
+/// # Returns
+///
+/// An `ImpactResult` containing affected files organized by dependency depth.
+///
+/// # Example
+///
+/// ```no_run
+/// use archmap::{impact, ImpactOptions};
+/// use std::path::Path;
+///
+/// let result = impact(
+///     Path::new("."),
+///     Path::new("src/lib.rs"),
+///     ImpactOptions::default()
+/// )?;
+/// println!("{} files would be affected", result.total_affected());
+/// # Ok::<(), archmap::ArchmapError>(())
+/// ```
+pub fn impact(
+    project_path: &Path,
+    file: &Path,
+    options: ImpactOptions,
+) -> Result<ImpactResult, ArchmapError> {
+    impact_with_fs(project_path, file, options, default_fs())
+}
+
+/// Analyze change impact in a codebase read through `fs`, as [`impact`] does
+/// on disk. See [`analyze_with_fs`] for how virtual paths are handled.
+///
+/// # Example
+///
+/// ```
+/// use archmap::fs::MemoryFileSystem;
+/// use archmap::{impact_with_fs, ImpactOptions};
+/// use std::path::Path;
+///
+/// let fs = MemoryFileSystem::new()
+///     .with_file("/repo/src/lib.rs", "mod util;\nuse crate::util::help;\npub fn run() { help(); }\n")
+///     .with_file("/repo/src/util.rs", "pub fn help() {}\n");
+///
+/// let result = impact_with_fs(
+///     Path::new("/repo"),
+///     Path::new("src/util.rs"),
+///     ImpactOptions::default(),
+///     &fs,
+/// )?;
+/// assert_eq!(result.total_affected(), 1);
+/// # Ok::<(), archmap::ArchmapError>(())
+/// ```
+pub fn impact_with_fs(
+    project_path: &Path,
+    file: &Path,
+    options: ImpactOptions,
+    fs: &dyn FileSystem,
+) -> Result<ImpactResult, ArchmapError> {
+    options.validate()?;
+    let resolved_path = resolve(project_path, fs)?;
+
+    let target_file = if file.is_absolute() {
+        file.to_path_buf()
+    } else {
+        resolved_path.join(file)
+    };
+
+    let target_file =
+        resolve(&target_file, fs).map_err(|_| ArchmapError::PathNotFound(file.to_path_buf()))?;
+
+    let mut config = Config::load_with_fs(&resolved_path, fs).unwrap_or_default();
+    config.cache &= options.cache;
+
+    let registry = if options.languages.is_empty() {
+        ParserRegistry::new()
+    } else {
+        ParserRegistry::with_languages(&options.languages)
+    };
+    if registry.find_parser(&target_file).is_none() {
+        return Err(ArchmapError::UnsupportedLanguage {
+            path: file.to_path_buf(),
+            languages: registry.languages().into_iter().map(|l| l.name).collect(),
+        });
+    }
+
+    // Run analysis to build dependency graph
+    let monitor = Monitor {
+        progress: None,
+        cancellation: options.cancellation,
+    };
+    let result = analysis::analyze_monitored(
+        &resolved_path,
+        &config,
+        &registry,
+        &[],
+        &PathFilter::default(),
+        fs,
+        &monitor,
+    )?;
+    let result = require_parsed(result)?;
+
+    // Compute impact
+    let impact_analysis =
+        analysis::compute_impact(&result.dependency_graph, &target_file, options.depth)?;
+
+    Ok(ImpactResult {
+        inner: impact_analysis,
+        project_root: resolved_path,
+    })
+}
+
+/// Generate AI-optimized context output.
+///
+/// Produces a compact, AI-friendly representation of the codebase architecture
+/// suitable for feeding to LLMs.
+///
+/// # Arguments
+///
+/// * `path` - The root path of the codebase to analyze.
+/// * `options` - AI context options controlling format, token budget, and prioritization.
+///
+/// # Returns
+///
+/// A string containing the formatted AI context.
+///
+/// # Example
+///
+/// ```no_run
+/// use archmap::{ai_context, AiOptions, AiFormat};
+/// use std::path::Path;
+///
+/// let options = AiOptions::builder()
+///     .format(AiFormat::Markdown)
+///     .tokens(4000)
+///     .build()?;
+/// let context = ai_context(Path::new("."), options)?;
+/// println!("{}", context);
+/// # Ok::<(), archmap::ArchmapError>(())
+/// ```
+pub fn ai_context(path: &Path, options: AiOptions) -> Result<String, ArchmapError> {
+    ai_context_with_fs(path, options, default_fs())
+}
+
+/// Generate AI context for a codebase read through `fs`, as [`ai_context`]
+/// does on disk. See [`analyze_with_fs`] for how virtual paths are handled.
+///
+/// # Example
+///
+/// ```
+/// use archmap::fs::MemoryFileSystem;
+/// use archmap::{ai_context_with_fs, AiOptions};
+/// use std::path::Path;
+///
+/// let fs = MemoryFileSystem::new()
+///     .with_file("/repo/src/lib.rs", "mod util;\nuse crate::util::help;\npub fn run() { help(); }\n")
+///     .with_file("/repo/src/util.rs", "pub fn help() {}\n");
+///
+/// let context = ai_context_with_fs(Path::new("/repo"), AiOptions::default(), &fs)?;
+/// assert!(context.contains("help"));
+/// # Ok::<(), archmap::ArchmapError>(())
+/// ```
+pub fn ai_context_with_fs(
+    path: &Path,
+    options: AiOptions,
+    fs: &dyn FileSystem,
+) -> Result<String, ArchmapError> {
+    options.validate()?;
+    let resolved_path = resolve(path, fs)?;
+
+    let mut config = Config::load_with_fs(&resolved_path, fs).unwrap_or_default();
+    config.cache &= options.cache;
+
+    let registry = if options.languages.is_empty() {
+        ParserRegistry::new()
+    } else {
+        ParserRegistry::with_languages(&options.languages)
+    };
+
+    let filter = PathFilter::new(options.include.clone(), options.exclude.clone());
+
+    // Run analysis
+    let monitor = Monitor {
+        progress: None,
+        cancellation: options.cancellation,
+    };
+    let result = analysis::analyze_monitored(
+        &resolved_path,
+        &config,
+        &registry,
+        &[],
+        &filter,
+        fs,
+        &monitor,
+    )?;
+    let result = require_parsed(result)?;
+
+    // Build AI output formatter
+    let mut formatter = AiOutput::new(Some(resolved_path))
+        .with_topo_order(options.topo_order)
+        .with_signatures_only(options.signatures_only)
+        .with_visibility(options.visibility.into())
+        .with_kinds(options.kinds)
+        .with_compact_types(options.compact_types)
+        .with_anchors(options.anchors)
+        .with_priority(options.priority.into())
+        .with_format(options.format.into())
+        .with_source_provider(FileSources::new(fs))
+        .with_include(options.include)
+        .with_exclude(options.exclude);
+
+    if let Some(tokens) = options.tokens {
+        formatter = formatter.with_token_budget(tokens);
+    }
+    if let Some(top) = options.top {
+        formatter = formatter.with_top(top);
+    }
+
+    // Format to string
+    let format = options.format;
+    let mut buffer = Cursor::new(Vec::new());
+    formatter
+        .format(&result, &mut buffer)
+        .map_err(|source| ArchmapError::Format {
+            format: format.name().to_string(),
+            source,
+        })?;
+
+    let output = String::from_utf8_lossy(&buffer.into_inner()).to_string();
+    Ok(output)
+}
+
+/// Analyze a codebase and derive the dependency graph `archmap graph` draws,
+/// for rendering it with your own visualization.
+///
+/// Node ids and paths are relative to `path`. To graph a result you already
+/// have, use [`GraphData::from_result`] instead of analyzing again.
+///
+/// # Example
+///
+/// ```no_run
+/// use archmap::{graph_data, AnalysisOptions};
+/// use std::path::Path;
+///
+/// let graph = graph_data(Path::new("."), AnalysisOptions::default())?;
+/// for link in graph.links.iter().filter(|l| l.is_cycle) {
+///     println!("{} -> {} is part of a cycle", link.source, link.target);
+/// }
+/// # Ok::<(), archmap::ArchmapError>(())
+/// ```
+pub fn graph_data(path: &Path, options: AnalysisOptions) -> Result<GraphData, ArchmapError> {
+    graph_data_with_fs(path, options, default_fs())
+}
+
+/// Derive the graph for a codebase read through `fs`, as [`graph_data`] does
+/// on disk.
+///
+/// # Example
+///
+/// ```
+/// use archmap::fs::MemoryFileSystem;
+/// use archmap::{graph_data_with_fs, AnalysisOptions, GraphData};
+/// use std::path::Path;
+///
+/// let fs = MemoryFileSystem::new()
+///     .with_file("/repo/src/app.ts", "import { db } from './db';\nexport const app = db;\n")
+///     .with_file("/repo/src/db.ts", "export const db = 1;\n");
+///
+/// let graph = graph_data_with_fs(Path::new("/repo"), AnalysisOptions::default(), &fs)?;
+/// let json = serde_json::to_string(&graph).unwrap();
+/// assert!(json.contains(r#""source":"src/app.ts","target":"src/db.ts""#));
+///
+/// let restored: GraphData = serde_json::from_str(&json).unwrap();
+/// assert_eq!(restored.nodes.len(), 2);
+/// # Ok::<(), archmap::ArchmapError>(())
+/// ```
+pub fn graph_data_with_fs(
+    path: &Path,
+    options: AnalysisOptions,
+    fs: &dyn FileSystem,
+) -> Result<GraphData, ArchmapError> {
+    let resolved_path = resolve(path, fs)?;
+    let result = analyze_with_fs(&resolved_path, options, fs)?;
+    Ok(GraphData::from_result(&result, &resolved_path))
+}
+
+/// List the languages archmap analyzes and the file extensions of each.
+///
+/// Tools that watch or filter files around archmap can use this instead of
+/// hardcoding the supported set.
+///
+/// # Arguments
+///
+/// * `languages` - Restrict the list as `--lang` would (empty means all supported languages).
+///
+/// # Example
+///
+/// ```
+/// let languages = archmap::languages(&[]);
+/// assert!(languages.iter().any(|l| l.name == "rust" && l.extensions == ["rs"]));
+/// ```
+pub fn languages(languages: &[String]) -> Vec<LanguageInfo> {
+    ParserRegistry::with_languages(languages).languages()
+}
+
+/// Resolve a project or file path: canonicalized on disk, only normalized in a
+/// virtual filesystem, where it must still exist.
+fn resolve(path: &Path, fs: &dyn FileSystem) -> Result<PathBuf, ArchmapError> {
+    if fs.on_disk() {
+        return path
+            .canonicalize()
+            .map_err(|_| ArchmapError::PathNotFound(path.to_path_buf()));
+    }
+    let path = normalize_path(path);
+    if fs.exists(&path) {
+        Ok(path)
+    } else {
+        Err(ArchmapError::PathNotFound(path))
+    }
+}
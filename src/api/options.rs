@@ -0,0 +1,706 @@
+//! Options for the library functions, with builders that validate them.
+//!
+//! The structs are `#[non_exhaustive]`: start from `Default` and set fields, or
+//! use a builder, whose `build()` reports every invalid value at once.
+
+use crate::analysis::{CancellationToken, Detector, DetectorSet, Progress, ProgressCallback};
+use crate::cli::{AiOutputFormat, PriorityStrategy, VisibilityLevel};
+use crate::model::{DefinitionKind, IssueKind};
+use crate::parser::ParserRegistry;
+use std::fmt;
+use std::sync::Arc;
+use thiserror::Error;
+
+/// Options for the `analyze` function.
+#[derive(Clone)]
+#[non_exhaustive]
+pub struct AnalysisOptions {
+    /// Languages to analyze (empty means all supported languages).
+    pub languages: Vec<String>,
+
+    /// Directory names (e.g. `vendor`) or globs (e.g. `vendor/**`) to exclude.
+    pub exclude: Vec<String>,
+
+    /// Maximum dependency depth before flagging.
+    pub max_depth: usize,
+
+    /// Minimum cohesion score before flagging (0.0-1.0).
+    pub min_cohesion: f64,
+
+    /// Report issues in generated files (skipped by default).
+    pub include_generated: bool,
+
+    /// Issue kinds whose checks are skipped, in addition to the config's `disabled_checks`.
+    pub disabled_checks: Vec<String>,
+
+    /// Opt-in issue kinds to detect, in addition to the config's `enabled_checks`.
+    pub enabled_checks: Vec<String>,
+
+    /// Called as files are discovered and parsed and as each detector runs.
+    pub progress: Option<ProgressCallback>,
+
+    /// Checked between files and between detectors; once cancelled, `analyze`
+    /// returns [`ArchmapError::Cancelled`](crate::ArchmapError::Cancelled).
+    pub cancellation: Option<CancellationToken>,
+
+    /// Detectors run over the parsed modules; the built-in set by default.
+    pub detectors: DetectorSet,
+
+    /// Reuse parse results from `.archmap/cache/` in the analyzed project and
+    /// write them back. Off by default, so analysis leaves the project as it
+    /// found it; the config's `cache = false` still turns it off.
+    pub cache: bool,
+}
+
+impl Default for AnalysisOptions {
+    fn default() -> Self {
+        Self {
+            languages: Vec::new(),
+            exclude: Vec::new(),
+            max_depth: 5,
+            min_cohesion: 0.3,
+            include_generated: false,
+            disabled_checks: Vec::new(),
+            enabled_checks: Vec::new(),
+            progress: None,
+            cancellation: None,
+            detectors: DetectorSet::builtin(),
+            cache: false,
+        }
+    }
+}
+
+impl fmt::Debug for AnalysisOptions {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AnalysisOptions")
+            .field("languages", &self.languages)
+            .field("exclude", &self.exclude)
+            .field("max_depth", &self.max_depth)
+            .field("min_cohesion", &self.min_cohesion)
+            .field("include_generated", &self.include_generated)
+            .field("disabled_checks", &self.disabled_checks)
+            .field("enabled_checks", &self.enabled_checks)
+            .field("progress", &self.progress.is_some())
+            .field("cancellation", &self.cancellation)
+            .field("detectors", &self.detectors)
+            .field("cache", &self.cache)
+            .finish()
+    }
+}
+
+/// Options for the `impact` function.
+#[derive(Debug, Clone, Default)]
+#[non_exhaustive]
+pub struct ImpactOptions {
+    /// Languages to analyze (empty means all supported languages).
+    pub languages: Vec<String>,
+
+    /// Maximum depth to traverse (None means unlimited).
+    pub depth: Option<usize>,
+
+    /// Checked while the project is analyzed; once cancelled, `impact`
+    /// returns [`ArchmapError::Cancelled`](crate::ArchmapError::Cancelled).
+    pub cancellation: Option<CancellationToken>,
+
+    /// Reuse parse results from `.archmap/cache/` in the analyzed project and
+    /// write them back. Off by default, so analysis leaves the project as it
+    /// found it; the config's `cache = false` still turns it off.
+    pub cache: bool,
+}
+
+/// Options for the `ai_context` function.
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub struct AiOptions {
+    /// Languages to analyze (empty means all supported languages).
+    pub languages: Vec<String>,
+
+    /// Maximum tokens for output.
+    pub tokens: Option<usize>,
+
+    /// List only this many of the most important modules.
+    pub top: Option<usize>,
+
+    /// Output only architectural signatures (public API surface).
+    pub signatures_only: bool,
+
+    /// Which definitions to include in signatures.
+    pub visibility: AiVisibility,
+
+    /// Definition kinds to include in signatures (empty means all kinds).
+    pub kinds: Vec<DefinitionKind>,
+
+    /// Show structs and enums in signatures by their field and variant names.
+    pub compact_types: bool,
+
+    /// Use topological ordering (dependencies before dependents).
+    pub topo_order: bool,
+
+    /// Output format.
+    pub format: AiFormat,
+
+    /// Prioritization strategy for token budgeting.
+    pub priority: Priority,
+
+    /// Glob patterns to include (empty means everything).
+    pub include: Vec<String>,
+
+    /// Glob patterns to exclude.
+    pub exclude: Vec<String>,
+
+    /// Emit `<!-- archmap:... -->` module/definition anchors in markdown output.
+    pub anchors: bool,
+
+    /// Checked while the project is analyzed; once cancelled, `ai_context`
+    /// returns [`ArchmapError::Cancelled`](crate::ArchmapError::Cancelled).
+    pub cancellation: Option<CancellationToken>,
+
+    /// Reuse parse results from `.archmap/cache/` in the analyzed project and
+    /// write them back. Off by default, so analysis leaves the project as it
+    /// found it; the config's `cache = false` still turns it off.
+    pub cache: bool,
+}
+
+impl Default for AiOptions {
+    fn default() -> Self {
+        Self {
+            languages: Vec::new(),
+            tokens: None,
+            top: None,
+            signatures_only: false,
+            visibility: AiVisibility::Public,
+            kinds: Vec::new(),
+            compact_types: false,
+            topo_order: true,
+            format: AiFormat::Markdown,
+            priority: Priority::FanIn,
+            include: Vec::new(),
+            exclude: Vec::new(),
+            anchors: true,
+            cancellation: None,
+            cache: false,
+        }
+    }
+}
+
+/// Output format for AI context.
+#[derive(Debug, Clone, Copy, Default)]
+pub enum AiFormat {
+    #[default]
+    Markdown,
+    Json,
+    Xml,
+}
+
+impl AiFormat {
+    pub fn name(self) -> &'static str {
+        match self {
+            AiFormat::Markdown => "markdown",
+            AiFormat::Json => "json",
+            AiFormat::Xml => "xml",
+        }
+    }
+}
+
+impl From<AiFormat> for AiOutputFormat {
+    fn from(f: AiFormat) -> Self {
+        match f {
+            AiFormat::Markdown => AiOutputFormat::Markdown,
+            AiFormat::Json => AiOutputFormat::Json,
+            AiFormat::Xml => AiOutputFormat::Xml,
+        }
+    }
+}
+
+/// Which definitions AI context signatures include.
+#[derive(Debug, Clone, Copy, Default)]
+pub enum AiVisibility {
+    /// Public items only.
+    #[default]
+    Public,
+    /// Public and crate-visible items.
+    Crate,
+    /// Everything, including private items.
+    All,
+}
+
+impl From<AiVisibility> for VisibilityLevel {
+    fn from(v: AiVisibility) -> Self {
+        match v {
+            AiVisibility::Public => VisibilityLevel::Public,
+            AiVisibility::Crate => VisibilityLevel::Crate,
+            AiVisibility::All => VisibilityLevel::All,
+        }
+    }
+}
+
+/// Prioritization strategy for AI context.
+#[derive(Debug, Clone, Copy, Default)]
+pub enum Priority {
+    /// Prioritize modules by number of dependents (most imported first).
+    #[default]
+    FanIn,
+    /// Prioritize modules by number of dependencies.
+    FanOut,
+    /// Combined score using fan-in, fan-out, and data structures.
+    Combined,
+}
+
+impl From<Priority> for PriorityStrategy {
+    fn from(p: Priority) -> Self {
+        match p {
+            Priority::FanIn => PriorityStrategy::FanIn,
+            Priority::FanOut => PriorityStrategy::FanOut,
+            Priority::Combined => PriorityStrategy::Combined,
+        }
+    }
+}
+
+/// Every problem found in a set of options
+#[derive(Debug, Clone, PartialEq, Error)]
+#[error("{}", problems.join("; "))]
+pub struct OptionsError {
+    pub problems: Vec<String>,
+}
+
+/// Collects problems while options are checked
+#[derive(Default)]
+struct Problems(Vec<String>);
+
+impl Problems {
+    fn languages(&mut self, languages: &[String]) {
+        for name in languages {
+            if !ParserRegistry::knows_language(name) {
+                self.0.push(format!(
+                    "unknown language '{}' (expected rust, typescript, javascript or python)",
+                    name
+                ));
+            }
+        }
+    }
+
+    /// archmap globs support `*` and `**` only; anything else would be matched literally
+    fn globs(&mut self, field: &str, patterns: &[String]) {
+        for pattern in patterns {
+            if pattern.trim().is_empty() {
+                self.0.push(format!("{}: empty pattern", field));
+            } else if pattern.contains(['?', '[', ']', '{', '}']) {
+                self.0.push(format!(
+                    "{}: '{}' uses ?, [...] or {{...}}; only * and ** are supported",
+                    field, pattern
+                ));
+            }
+        }
+    }
+
+    fn check_names(&mut self, field: &str, names: &[String], opt_in_only: bool) {
+        for name in names {
+            match IssueKind::canonical_name(name) {
+                None => self.0.push(format!(
+                    "{}: unknown check '{}' (known: {})",
+                    field,
+                    name,
+                    IssueKind::NAMES.join(", ")
+                )),
+                Some(kind) if opt_in_only && !IssueKind::OPT_IN.contains(&kind) => {
+                    self.0.push(format!(
+                        "{}: '{}' is not an opt-in check (opt-in: {})",
+                        field,
+                        name,
+                        IssueKind::OPT_IN.join(", ")
+                    ))
+                }
+                Some(_) => {}
+            }
+        }
+    }
+
+    fn at_least_one(&mut self, field: &str, value: Option<usize>) {
+        if value == Some(0) {
+            self.0.push(format!("{} must be at least 1", field));
+        }
+    }
+
+    fn finish(self) -> Result<(), OptionsError> {
+        if self.0.is_empty() {
+            Ok(())
+        } else {
+            Err(OptionsError { problems: self.0 })
+        }
+    }
+}
+
+impl AnalysisOptions {
+    pub fn builder() -> AnalysisOptionsBuilder {
+        AnalysisOptionsBuilder::default()
+    }
+
+    /// Check every field, reporting all problems together.
+    pub fn validate(&self) -> Result<(), OptionsError> {
+        let mut problems = Problems::default();
+        problems.languages(&self.languages);
+        problems.globs("exclude", &self.exclude);
+        problems.at_least_one("max_depth", Some(self.max_depth));
+        if !(0.0..=1.0).contains(&self.min_cohesion) {
+            problems.0.push(format!(
+                "min_cohesion must be between 0.0 and 1.0, got {}",
+                self.min_cohesion
+            ));
+        }
+        problems.check_names("disabled_checks", &self.disabled_checks, false);
+        problems.check_names("enabled_checks", &self.enabled_checks, true);
+        problems.finish()
+    }
+}
+
+impl ImpactOptions {
+    pub fn builder() -> ImpactOptionsBuilder {
+        ImpactOptionsBuilder::default()
+    }
+
+    /// Check every field, reporting all problems together.
+    pub fn validate(&self) -> Result<(), OptionsError> {
+        let mut problems = Problems::default();
+        problems.languages(&self.languages);
+        problems.at_least_one("depth", self.depth);
+        problems.finish()
+    }
+}
+
+impl AiOptions {
+    pub fn builder() -> AiOptionsBuilder {
+        AiOptionsBuilder::default()
+    }
+
+    /// Check every field, reporting all problems together.
+    pub fn validate(&self) -> Result<(), OptionsError> {
+        let mut problems = Problems::default();
+        problems.languages(&self.languages);
+        problems.at_least_one("tokens", self.tokens);
+        problems.at_least_one("top", self.top);
+        problems.globs("include", &self.include);
+        problems.globs("exclude", &self.exclude);
+        problems.finish()
+    }
+}
+
+fn strings<S: Into<String>>(values: impl IntoIterator<Item = S>) -> Vec<String> {
+    values.into_iter().map(Into::into).collect()
+}
+
+/// Builds [`AnalysisOptions`], starting from the defaults.
+///
+/// ```
+/// use archmap::AnalysisOptions;
+///
+/// let options = AnalysisOptions::builder()
+///     .languages(["rust"])
+///     .exclude(["vendor/**"])
+///     .max_depth(6)
+///     .build()?;
+/// assert_eq!(options.max_depth, 6);
+///
+/// let invalid = AnalysisOptions::builder().min_cohesion(7.3).languages(["cobol"]).build();
+/// assert_eq!(invalid.unwrap_err().problems.len(), 2);
+/// # Ok::<(), archmap::OptionsError>(())
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct AnalysisOptionsBuilder {
+    options: AnalysisOptions,
+}
+
+impl AnalysisOptionsBuilder {
+    pub fn languages<S: Into<String>>(mut self, languages: impl IntoIterator<Item = S>) -> Self {
+        self.options.languages = strings(languages);
+        self
+    }
+
+    pub fn exclude<S: Into<String>>(mut self, patterns: impl IntoIterator<Item = S>) -> Self {
+        self.options.exclude = strings(patterns);
+        self
+    }
+
+    pub fn max_depth(mut self, depth: usize) -> Self {
+        self.options.max_depth = depth;
+        self
+    }
+
+    pub fn min_cohesion(mut self, score: f64) -> Self {
+        self.options.min_cohesion = score;
+        self
+    }
+
+    pub fn include_generated(mut self, enabled: bool) -> Self {
+        self.options.include_generated = enabled;
+        self
+    }
+
+    pub fn disabled_checks<S: Into<String>>(mut self, kinds: impl IntoIterator<Item = S>) -> Self {
+        self.options.disabled_checks = strings(kinds);
+        self
+    }
+
+    pub fn enabled_checks<S: Into<String>>(mut self, kinds: impl IntoIterator<Item = S>) -> Self {
+        self.options.enabled_checks = strings(kinds);
+        self
+    }
+
+    pub fn progress(mut self, callback: impl Fn(Progress) + Send + Sync + 'static) -> Self {
+        self.options.progress = Some(Arc::new(callback));
+        self
+    }
+
+    pub fn cancellation(mut self, token: CancellationToken) -> Self {
+        self.options.cancellation = Some(token);
+        self
+    }
+
+    /// Replace the detectors, e.g. with `DetectorSet::empty()` plus your own.
+    pub fn detectors(mut self, detectors: DetectorSet) -> Self {
+        self.options.detectors = detectors;
+        self
+    }
+
+    /// Run `detector` after the current set.
+    pub fn detector(mut self, detector: impl Detector + 'static) -> Self {
+        self.options.detectors = self.options.detectors.with(detector);
+        self
+    }
+
+    /// Use the parse cache in `.archmap/cache/` (off by default).
+    pub fn cache(mut self, enabled: bool) -> Self {
+        self.options.cache = enabled;
+        self
+    }
+
+    pub fn build(self) -> Result<AnalysisOptions, OptionsError> {
+        self.options.validate()?;
+        Ok(self.options)
+    }
+}
+
+/// Builds [`ImpactOptions`], starting from the defaults.
+#[derive(Debug, Clone, Default)]
+pub struct ImpactOptionsBuilder {
+    options: ImpactOptions,
+}
+
+impl ImpactOptionsBuilder {
+    pub fn languages<S: Into<String>>(mut self, languages: impl IntoIterator<Item = S>) -> Self {
+        self.options.languages = strings(languages);
+        self
+    }
+
+    pub fn depth(mut self, depth: usize) -> Self {
+        self.options.depth = Some(depth);
+        self
+    }
+
+    pub fn cancellation(mut self, token: CancellationToken) -> Self {
+        self.options.cancellation = Some(token);
+        self
+    }
+
+    /// Use the parse cache in `.archmap/cache/` (off by default).
+    pub fn cache(mut self, enabled: bool) -> Self {
+        self.options.cache = enabled;
+        self
+    }
+
+    pub fn build(self) -> Result<ImpactOptions, OptionsError> {
+        self.options.validate()?;
+        Ok(self.options)
+    }
+}
+
+/// Builds [`AiOptions`], starting from the defaults.
+#[derive(Debug, Clone, Default)]
+pub struct AiOptionsBuilder {
+    options: AiOptions,
+}
+
+impl AiOptionsBuilder {
+    pub fn languages<S: Into<String>>(mut self, languages: impl IntoIterator<Item = S>) -> Self {
+        self.options.languages = strings(languages);
+        self
+    }
+
+    pub fn tokens(mut self, tokens: usize) -> Self {
+        self.options.tokens = Some(tokens);
+        self
+    }
+
+    pub fn top(mut self, count: usize) -> Self {
+        self.options.top = Some(count);
+        self
+    }
+
+    pub fn signatures_only(mut self, enabled: bool) -> Self {
+        self.options.signatures_only = enabled;
+        self
+    }
+
+    pub fn visibility(mut self, visibility: AiVisibility) -> Self {
+        self.options.visibility = visibility;
+        self
+    }
+
+    pub fn compact_types(mut self, enabled: bool) -> Self {
+        self.options.compact_types = enabled;
+        self
+    }
+
+    pub fn kinds(mut self, kinds: impl IntoIterator<Item = DefinitionKind>) -> Self {
+        self.options.kinds = kinds.into_iter().collect();
+        self
+    }
+
+    pub fn topo_order(mut self, enabled: bool) -> Self {
+        self.options.topo_order = enabled;
+        self
+    }
+
+    pub fn format(mut self, format: AiFormat) -> Self {
+        self.options.format = format;
+        self
+    }
+
+    pub fn priority(mut self, priority: Priority) -> Self {
+        self.options.priority = priority;
+        self
+    }
+
+    pub fn include<S: Into<String>>(mut self, patterns: impl IntoIterator<Item = S>) -> Self {
+        self.options.include = strings(patterns);
+        self
+    }
+
+    pub fn exclude<S: Into<String>>(mut self, patterns: impl IntoIterator<Item = S>) -> Self {
+        self.options.exclude = strings(patterns);
+        self
+    }
+
+    pub fn anchors(mut self, enabled: bool) -> Self {
+        self.options.anchors = enabled;
+        self
+    }
+
+    pub fn cancellation(mut self, token: CancellationToken) -> Self {
+        self.options.cancellation = Some(token);
+        self
+    }
+
+    /// Use the parse cache in `.archmap/cache/` (off by default).
+    pub fn cache(mut self, enabled: bool) -> Self {
+        self.options.cache = enabled;
+        self
+    }
+
+    pub fn build(self) -> Result<AiOptions, OptionsError> {
+        self.options.validate()?;
+        Ok(self.options)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn problems(result: Result<(), OptionsError>) -> Vec<String> {
+        result.err().map(|e| e.problems).unwrap_or_default()
+    }
+
+    #[test]
+    fn test_defaults_are_valid() {
+        assert!(AnalysisOptions::default().validate().is_ok());
+        assert!(ImpactOptions::default().validate().is_ok());
+        assert!(AiOptions::default().validate().is_ok());
+    }
+
+    #[test]
+    fn test_languages_are_checked_against_the_registry() {
+        let ok = AnalysisOptions::builder()
+            .languages(["Rust", "ts", "py"])
+            .build();
+        assert!(ok.is_ok());
+
+        let found = problems(ImpactOptions::builder().languages(["go"]).build().map(drop));
+        assert_eq!(found.len(), 1);
+        assert!(found[0].starts_with("unknown language 'go'"));
+    }
+
+    #[test]
+    fn test_ranges_are_checked() {
+        for score in [-0.1, 7.3, f64::NAN] {
+            let found = problems(
+                AnalysisOptions::builder()
+                    .min_cohesion(score)
+                    .build()
+                    .map(drop),
+            );
+            assert_eq!(found.len(), 1, "{}", score);
+            assert!(found[0].starts_with("min_cohesion"));
+        }
+        let found = problems(AnalysisOptions::builder().max_depth(0).build().map(drop));
+        assert_eq!(found, ["max_depth must be at least 1"]);
+        let found = problems(ImpactOptions::builder().depth(0).build().map(drop));
+        assert_eq!(found, ["depth must be at least 1"]);
+        let found = problems(AiOptions::builder().tokens(0).build().map(drop));
+        assert_eq!(found, ["tokens must be at least 1"]);
+    }
+
+    #[test]
+    fn test_unsupported_globs_are_rejected() {
+        let ok = AiOptions::builder()
+            .include(["src/**/*.rs"])
+            .exclude(["migrations"])
+            .build();
+        assert!(ok.is_ok());
+
+        let found = problems(
+            AiOptions::builder()
+                .include(["src/*.{rs,ts}", ""])
+                .exclude(["test?.rs"])
+                .build()
+                .map(drop),
+        );
+        assert_eq!(found.len(), 3);
+        assert!(found[0].starts_with("include: 'src/*.{rs,ts}'"));
+        assert_eq!(found[1], "include: empty pattern");
+        assert!(found[2].starts_with("exclude: 'test?.rs'"));
+    }
+
+    #[test]
+    fn test_check_names_are_validated() {
+        let ok = AnalysisOptions::builder()
+            .disabled_checks(["low-cohesion"])
+            .enabled_checks(["DeadPublicFunction"])
+            .build();
+        assert!(ok.is_ok());
+
+        let found = problems(
+            AnalysisOptions::builder()
+                .disabled_checks(["Spaghetti"])
+                .enabled_checks(["GodObject"])
+                .build()
+                .map(drop),
+        );
+        assert_eq!(found.len(), 2);
+        assert!(found[0].starts_with("disabled_checks: unknown check 'Spaghetti'"));
+        assert!(found[1].starts_with("enabled_checks: 'GodObject' is not an opt-in check"));
+    }
+
+    #[test]
+    fn test_error_lists_every_problem() {
+        let error = AnalysisOptions::builder()
+            .languages(["cobol"])
+            .min_cohesion(2.0)
+            .build()
+            .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "unknown language 'cobol' (expected rust, typescript, javascript or python); \
+             min_cohesion must be between 0.0 and 1.0, got 2"
+        );
+    }
+}
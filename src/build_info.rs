@@ -0,0 +1,52 @@
+//! What this archmap binary is: its version, commit and features, plus the
+//! schema versions of the files it writes. Shown by `archmap version` and
+//! recorded in snapshots and graph data.
+
+use crate::parser::ParserRegistry;
+use serde::Serialize;
+
+/// Crate version
+pub const VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// Git commit the binary was built from, or "unknown" when built outside a checkout
+pub const GIT_COMMIT: &str = env!("ARCHMAP_GIT_COMMIT");
+
+/// Everything `archmap version --format json` reports
+#[derive(Debug, Clone, Serialize)]
+pub struct BuildInfo {
+    pub version: &'static str,
+    pub commit: &'static str,
+    /// Cargo features compiled in
+    pub features: Vec<&'static str>,
+    /// File extensions the parsers handle
+    pub extensions: Vec<String>,
+    pub snapshot_schema: u32,
+    pub graph_schema: u32,
+}
+
+impl BuildInfo {
+    /// Facts about the running binary
+    pub fn current() -> Self {
+        let mut features = Vec::new();
+        if cfg!(feature = "tui") {
+            features.push("tui");
+        }
+
+        let mut extensions: Vec<String> = ParserRegistry::new()
+            .supported_extensions()
+            .into_iter()
+            .map(String::from)
+            .collect();
+        extensions.sort();
+        extensions.dedup();
+
+        Self {
+            version: VERSION,
+            commit: GIT_COMMIT,
+            features,
+            extensions,
+            snapshot_schema: crate::snapshot::SNAPSHOT_SCHEMA,
+            graph_schema: crate::graph::GRAPH_SCHEMA,
+        }
+    }
+}
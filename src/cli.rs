@@ -1,6 +1,9 @@
-use crate::model::IssueSeverity;
+use crate::config::{ConfigSource, Preset};
+use crate::logging::LogFormat;
+use crate::model::{DefinitionKind, IssueSeverity, Visibility};
+use crate::style::Verbosity;
 use clap::{Parser, Subcommand};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 #[derive(Parser, Debug)]
 #[command(name = "archmap")]
@@ -18,6 +21,95 @@ pub struct Cli {
     /// Output MCP server manifest JSON for MCP client configuration
     #[arg(long)]
     pub mcp_manifest: bool,
+
+    /// Only print errors (suppresses status and warning lines)
+    #[arg(short, long, global = true, conflicts_with = "verbose")]
+    pub quiet: bool,
+
+    /// Print per-file progress and timing to stderr
+    #[arg(short, long, global = true)]
+    pub verbose: bool,
+
+    /// Disable colored output (the NO_COLOR environment variable is also honored)
+    #[arg(long, global = true)]
+    pub no_color: bool,
+
+    /// Use paths as given (made absolute, `.` and `..` resolved) instead of
+    /// canonicalizing them: symlinks are kept and paths need not exist
+    #[arg(long, global = true)]
+    pub no_canonicalize: bool,
+
+    /// Config file to use instead of discovering `.archmap.toml`
+    #[arg(long, global = true, value_name = "FILE", conflicts_with = "no_config")]
+    pub config: Option<PathBuf>,
+
+    /// Ignore config files and use the built-in defaults
+    #[arg(long, global = true)]
+    pub no_config: bool,
+
+    /// Architecture style whose boundaries and layer rules apply when there is
+    /// no `.archmap.toml`; with `init`, written into the new config
+    #[arg(long, global = true, conflicts_with_all = ["config", "no_config"])]
+    pub preset: Option<Preset>,
+
+    /// Worker threads for analysis (0 = one per core, half in watch mode).
+    /// Also read from ARCHMAP_THREADS
+    #[arg(long, global = true, value_name = "N")]
+    pub threads: Option<usize>,
+
+    /// Parse files however large they are and however long their lines; the
+    /// `max_file_size_kb` and `max_line_length` config keys are ignored
+    #[arg(long, global = true)]
+    pub no_size_limit: bool,
+
+    /// Descend into symlinked directories (also the `follow_symlinks` config
+    /// key); files reached through several paths are analyzed once
+    #[arg(long, global = true)]
+    pub follow_symlinks: bool,
+
+    /// Print counts in reports as plain digits (1847293, not 1,847,293)
+    #[arg(long, global = true)]
+    pub no_thousands_sep: bool,
+
+    /// Start each issue line in markdown reports with its severity: ✖ error,
+    /// ⚠ warn, ℹ info, or [E] [W] [I] without colors or a UTF-8 locale
+    #[arg(long, global = true)]
+    pub severity_icons: bool,
+
+    /// Parse every file afresh, neither reading nor updating `.archmap/cache/`
+    #[arg(long, global = true)]
+    pub no_cache: bool,
+
+    /// Diagnostics format: colored messages, or one JSON object per line on
+    /// stderr including phase timings
+    #[arg(long, global = true, default_value = "pretty")]
+    pub log_format: LogFormat,
+
+    /// Exit 0 even when error-level issues, failed expectations or regressions
+    /// are found; the report is unchanged. Usage and I/O errors still fail.
+    #[arg(long, global = true)]
+    pub exit_zero: bool,
+}
+
+impl Cli {
+    pub fn config_source(&self) -> ConfigSource {
+        match (&self.config, self.preset) {
+            (Some(path), _) => ConfigSource::File(path.clone()),
+            (None, _) if self.no_config => ConfigSource::Defaults,
+            (None, Some(preset)) => ConfigSource::Preset(preset),
+            (None, None) => ConfigSource::Discover,
+        }
+    }
+
+    pub fn verbosity(&self) -> Verbosity {
+        if self.quiet {
+            Verbosity::Quiet
+        } else if self.verbose {
+            Verbosity::Verbose
+        } else {
+            Verbosity::Normal
+        }
+    }
 }
 
 #[derive(Subcommand, Debug, Clone)]
@@ -37,33 +129,75 @@ pub enum Command {
     /// Compare current state against a baseline snapshot
     Diff(DiffArgs),
 
+    /// Check the `[expectations]` from .archmap.toml, failing if any are violated
+    Check(CheckArgs),
+
+    /// List modules, a file's exports or imports, or issues
+    List(ListArgs),
+
+    /// Explain a check, or the inputs behind a specific issue
+    Explain(ExplainArgs),
+
     /// Launch interactive graph visualization
     Graph(GraphArgs),
 
     /// Generate a starter .archmap.toml configuration file
     Init(InitArgs),
 
+    /// Print the configuration in effect after defaults, config files and
+    /// command-line flags are merged, and where it came from
+    ConfigDump(ConfigDumpArgs),
+
     /// Start MCP server for AI assistant integration (stdio transport)
     Mcp(McpArgs),
+
+    /// Browse modules and issues in an interactive terminal UI (requires the `tui` feature)
+    Tui(TuiArgs),
+
+    /// Show the version, commit, features and schema versions of this build
+    Version(VersionArgs),
+
+    /// Print the supported languages and their file extensions as JSON
+    Languages(LanguagesArgs),
 }
 
 #[derive(Parser, Debug, Clone)]
 pub struct AnalyzeArgs {
-    /// Path to analyze (defaults to current directory)
+    /// Paths to analyze as one project (defaults to current directory; with
+    /// --git, paths inside the clone)
     #[arg(default_value = ".")]
-    pub path: PathBuf,
+    pub paths: Vec<PathBuf>,
 
-    /// Output format
-    #[arg(short, long, default_value = "markdown")]
-    pub format: OutputFormat,
+    /// Analyze a remote repository: shallow-clone it to a temporary directory,
+    /// which is removed afterwards
+    #[arg(long, value_name = "URL", conflicts_with_all = ["watch", "stdin"])]
+    pub git: Option<String>,
+
+    /// Branch or tag to clone with --git (default: the remote's default branch)
+    #[arg(long = "ref", value_name = "REF", requires = "git")]
+    pub git_ref: Option<String>,
 
-    /// Output file (defaults to stdout)
+    /// Output format (default: inferred from the --output extension, else
+    /// markdown, or json with --stdin). Repeatable with --output-dir
+    #[arg(short, long, value_delimiter = ',')]
+    pub format: Vec<OutputFormat>,
+
+    /// Output file; repeat to write several formats from one run (defaults to stdout)
     #[arg(short, long)]
-    pub output: Option<PathBuf>,
+    pub output: Vec<PathBuf>,
+
+    /// Write report.md and report.json to this directory (only the --format
+    /// ones, if given)
+    #[arg(long, value_name = "DIR", conflicts_with = "output")]
+    pub output_dir: Option<PathBuf>,
 
-    /// Minimum severity to report
-    #[arg(long, default_value = "info")]
-    pub min_severity: IssueSeverity,
+    /// Also write the JSON report to this file, e.g. next to markdown on stdout
+    #[arg(long, value_name = "FILE")]
+    pub json_out: Option<PathBuf>,
+
+    /// Minimum severity to report (default: min_severity from the config, else info)
+    #[arg(long)]
+    pub min_severity: Option<IssueSeverity>,
 
     /// Languages to analyze (comma-separated: rust,typescript,python)
     #[arg(long, value_delimiter = ',')]
@@ -73,60 +207,179 @@ pub struct AnalyzeArgs {
     #[arg(short, long)]
     pub watch: bool,
 
-    /// Maximum dependency chain depth before flagging (default: 5)
-    #[arg(long, default_value = "5")]
-    pub max_depth: usize,
+    /// Poll for changed files instead of waiting for filesystem events (for
+    /// network filesystems and others without inotify or FSEvents)
+    #[arg(long, requires = "watch")]
+    pub poll: bool,
 
-    /// Minimum cohesion score before flagging (0.0-1.0, default: 0.3)
-    #[arg(long, default_value = "0.3")]
-    pub min_cohesion: f64,
+    /// Milliseconds between checks for changed files when polling (default: 1000)
+    #[arg(long, value_name = "MS", requires = "watch", value_parser = clap::value_parser!(u64).range(1..))]
+    pub watch_interval: Option<u64>,
+
+    /// Milliseconds without further changes before a burst of edits triggers a
+    /// rerun in watch mode (default: 300)
+    #[arg(long, value_name = "MS", requires = "watch")]
+    pub watch_debounce: Option<u64>,
+
+    /// Read one file's source from stdin in place of --stdin-path and report
+    /// only the issues touching it (for editor integrations)
+    #[arg(long, requires = "stdin_path", conflicts_with = "watch")]
+    pub stdin: bool,
+
+    /// Path the stdin source stands in for; picks the parser and may not exist yet
+    #[arg(long, value_name = "PATH", requires = "stdin")]
+    pub stdin_path: Option<PathBuf>,
+
+    /// Maximum dependency chain depth before flagging (overrides the config; default: 5)
+    #[arg(long)]
+    pub max_depth: Option<usize>,
+
+    /// Minimum cohesion score before flagging (0.0-1.0, overrides the config; default: 0.3)
+    #[arg(long)]
+    pub min_cohesion: Option<f64>,
 
     /// Directories to exclude (can be specified multiple times)
     #[arg(long = "exclude", short = 'x', value_name = "DIR")]
     pub exclude: Vec<String>,
+
+    /// Issue kinds whose checks are skipped entirely, e.g. LowCohesion (repeatable)
+    #[arg(long, value_name = "KIND")]
+    pub disable: Vec<String>,
+
+    /// Opt-in issue kinds to detect, e.g. DeadPublicFunction (repeatable)
+    #[arg(long, value_name = "KIND")]
+    pub enable: Vec<String>,
+
+    /// Only analyze modules annotated with this tag, e.g. layer=domain (repeatable)
+    #[arg(long = "filter-tag", value_name = "KEY=VALUE")]
+    pub filter_tag: Vec<String>,
+
+    /// Markdown template file with {{placeholder}} markers (see README for the list)
+    #[arg(long, alias = "output-template", value_name = "FILE")]
+    pub template: Option<PathBuf>,
+
+    /// Report issues in generated files (skipped by default)
+    #[arg(long)]
+    pub include_generated: bool,
+
+    /// Baseline snapshot; issues not in it are marked new, the rest pre-existing
+    #[arg(long, value_name = "SNAPSHOT")]
+    pub baseline: Option<PathBuf>,
+
+    /// Show N lines of source on each side of every issue location (markdown only)
+    #[arg(long, value_name = "N")]
+    pub with_context: Option<usize>,
+
+    /// List the N files to read first (default 10), ranked by dependents,
+    /// betweenness and exports
+    #[arg(long, value_name = "N", num_args = 0..=1, default_missing_value = "10")]
+    pub key_files: Option<usize>,
+
+    /// Print the configuration this run would use, with this command's flags
+    /// applied, and exit without analyzing
+    #[arg(long)]
+    pub dump_config: bool,
 }
 
 impl Default for AnalyzeArgs {
     fn default() -> Self {
         Self {
-            path: PathBuf::from("."),
-            format: OutputFormat::Markdown,
-            output: None,
-            min_severity: IssueSeverity::Info,
+            paths: vec![PathBuf::from(".")],
+            git: None,
+            git_ref: None,
+            format: Vec::new(),
+            output: Vec::new(),
+            output_dir: None,
+            json_out: None,
+            min_severity: None,
             lang: None,
             watch: false,
-            max_depth: 5,
-            min_cohesion: 0.3,
+            poll: false,
+            watch_interval: None,
+            watch_debounce: None,
+            stdin: false,
+            stdin_path: None,
+            max_depth: None,
+            min_cohesion: None,
             exclude: Vec::new(),
+            disable: Vec::new(),
+            enable: Vec::new(),
+            filter_tag: Vec::new(),
+            template: None,
+            include_generated: false,
+            baseline: None,
+            with_context: None,
+            key_files: None,
+            dump_config: false,
+        }
+    }
+}
+
+impl AnalyzeArgs {
+    /// The requested format, defaulting to JSON for editor (stdin) requests.
+    pub fn output_format(&self) -> OutputFormat {
+        match self.format.first() {
+            Some(&format) => format,
+            None if self.stdin => OutputFormat::Json,
+            None => OutputFormat::Markdown,
         }
     }
 }
 
 #[derive(Parser, Debug, Clone)]
 pub struct AiArgs {
-    /// Path to analyze (defaults to current directory)
+    /// Paths to analyze as one project (defaults to current directory)
     #[arg(default_value = ".")]
-    pub path: PathBuf,
+    pub paths: Vec<PathBuf>,
 
     /// Maximum tokens for output (uses tiktoken for accurate counting)
     #[arg(long)]
     pub tokens: Option<usize>,
 
+    /// Only list the N most important modules (most imported first), with or
+    /// without a token budget
+    #[arg(long, value_name = "N")]
+    pub top: Option<usize>,
+
     /// Output only architectural signatures (public API surface)
     #[arg(long)]
     pub signatures: bool,
 
+    /// Which definitions to include in signatures
+    #[arg(long, default_value = "public")]
+    pub visibility: VisibilityLevel,
+
+    /// Only include these definition kinds in signatures (comma-separated:
+    /// function,struct,enum,trait,impl,class,interface,type,constant; default all)
+    #[arg(long, value_delimiter = ',')]
+    pub kinds: Vec<DefinitionKind>,
+
+    /// With --signatures, show structs and enums as their field and variant
+    /// names instead of their full definitions
+    #[arg(long)]
+    pub compact_types: bool,
+
     /// Use topological ordering (dependencies before dependents)
     #[arg(long, default_value = "true")]
     pub topo_order: bool,
 
-    /// Output format
-    #[arg(short, long, default_value = "markdown")]
-    pub format: AiOutputFormat,
+    /// Output format (default: inferred from the --output extension, else markdown)
+    #[arg(short, long)]
+    pub format: Option<AiOutputFormat>,
 
-    /// Output file (defaults to stdout)
+    /// Output file; repeat to write several formats from one run (defaults to stdout)
     #[arg(short, long)]
-    pub output: Option<PathBuf>,
+    pub output: Vec<PathBuf>,
+
+    /// Stop after this many bytes of output: markdown is cut at a line and
+    /// marked as truncated, JSON and XML aren't written at all. Defaults to
+    /// 10 MB when stdout is piped and no --tokens budget is set
+    #[arg(long, value_name = "BYTES")]
+    pub max_output_bytes: Option<u64>,
+
+    /// Write the whole context however large it is, lifting the output limit
+    #[arg(long, conflicts_with = "max_output_bytes")]
+    pub force: bool,
 
     /// Prioritization strategy for token budgeting
     #[arg(long, default_value = "fan-in")]
@@ -135,6 +388,57 @@ pub struct AiArgs {
     /// Languages to analyze (comma-separated: rust,typescript,python)
     #[arg(long, value_delimiter = ',')]
     pub lang: Option<Vec<String>>,
+
+    /// Glob patterns to exclude (can be specified multiple times)
+    #[arg(long = "exclude", short = 'x', value_name = "GLOB")]
+    pub exclude: Vec<String>,
+
+    /// Glob patterns to include; only matching files are analyzed (can be specified multiple times)
+    #[arg(long = "include", value_name = "GLOB")]
+    pub include: Vec<String>,
+
+    /// Report issues in generated files (skipped by default)
+    #[arg(long)]
+    pub include_generated: bool,
+
+    /// Task preamble to prepend: a built-in (refactor, onboarding, bugfix) or a template file
+    #[arg(long, value_name = "FILE|NAME")]
+    pub preamble: Option<String>,
+
+    /// File the task centers on, filled into the preamble's {focus} variable
+    #[arg(long, value_name = "FILE")]
+    pub focus: Option<String>,
+
+    /// Token cap for the Project Layout section (0 disables it)
+    #[arg(long, default_value = "300", value_name = "N")]
+    pub layout_tokens: usize,
+
+    /// Omit `<!-- archmap:... -->` module/definition anchors from markdown output
+    #[arg(long)]
+    pub no_anchors: bool,
+
+    /// Watch for file changes and rewrite the --output files
+    #[arg(short, long, requires = "output")]
+    pub watch: bool,
+
+    /// Poll for changed files instead of waiting for filesystem events (for
+    /// network filesystems and others without inotify or FSEvents)
+    #[arg(long, requires = "watch")]
+    pub poll: bool,
+
+    /// Milliseconds between checks for changed files when polling (default: 1000)
+    #[arg(long, value_name = "MS", requires = "watch", value_parser = clap::value_parser!(u64).range(1..))]
+    pub watch_interval: Option<u64>,
+
+    /// Milliseconds without further changes before a burst of edits triggers a
+    /// rerun in watch mode (default: 300)
+    #[arg(long, value_name = "MS", requires = "watch")]
+    pub watch_debounce: Option<u64>,
+
+    /// Leave issues below this severity out of the layout and recommendations
+    /// (default: min_severity from the config, else info)
+    #[arg(long)]
+    pub min_severity: Option<IssueSeverity>,
 }
 
 #[derive(Parser, Debug, Clone)]
@@ -150,13 +454,14 @@ pub struct ImpactArgs {
     #[arg(short, long)]
     pub depth: Option<usize>,
 
-    /// Output format
-    #[arg(short, long, default_value = "markdown")]
-    pub format: OutputFormat,
+    /// Output format (default: inferred from the --output extension, else markdown).
+    /// `dot` renders the affected files as a Graphviz graph (pipe to `dot -Tpng`)
+    #[arg(short, long)]
+    pub format: Option<ImpactFormat>,
 
-    /// Output file (defaults to stdout)
+    /// Output file; repeat to write several formats from one run (defaults to stdout)
     #[arg(short, long)]
-    pub output: Option<PathBuf>,
+    pub output: Vec<PathBuf>,
 
     /// Show ASCII tree visualization
     #[arg(long)]
@@ -173,31 +478,67 @@ pub struct SnapshotArgs {
     #[arg(long)]
     pub save: PathBuf,
 
-    /// Path to analyze (defaults to current directory)
+    /// Paths to analyze as one project (defaults to current directory)
     #[arg(default_value = ".")]
-    pub path: PathBuf,
+    pub paths: Vec<PathBuf>,
 
     /// Languages to analyze (comma-separated: rust,typescript,python)
     #[arg(long, value_delimiter = ',')]
     pub lang: Option<Vec<String>>,
+
+    /// How to print the summary after saving; json writes only the metrics
+    /// object to stdout
+    #[arg(long, default_value = "text")]
+    pub summary_format: SummaryFormat,
+
+    /// Watch for file changes and save the snapshot again
+    #[arg(short, long)]
+    pub watch: bool,
+
+    /// Poll for changed files instead of waiting for filesystem events (for
+    /// network filesystems and others without inotify or FSEvents)
+    #[arg(long, requires = "watch")]
+    pub poll: bool,
+
+    /// Milliseconds between checks for changed files when polling (default: 1000)
+    #[arg(long, value_name = "MS", requires = "watch", value_parser = clap::value_parser!(u64).range(1..))]
+    pub watch_interval: Option<u64>,
+
+    /// Milliseconds without further changes before a burst of edits triggers a
+    /// rerun in watch mode (default: 300)
+    #[arg(long, value_name = "MS", requires = "watch")]
+    pub watch_debounce: Option<u64>,
 }
 
 #[derive(Parser, Debug, Clone)]
 pub struct DiffArgs {
-    /// Baseline snapshot file to compare against
-    pub baseline: PathBuf,
+    /// Baseline snapshot file to compare against (or give it with --baseline)
+    #[arg(value_name = "BASELINE", required_unless_present = "baseline_file")]
+    pub baseline: Option<PathBuf>,
 
     /// Path to analyze (defaults to current directory)
-    #[arg(default_value = ".")]
-    pub path: PathBuf,
-
-    /// Output format
-    #[arg(short, long, default_value = "markdown")]
-    pub format: OutputFormat,
+    #[arg(value_name = "PATH")]
+    pub path: Option<PathBuf>,
+
+    /// Baseline snapshot file, in place of the BASELINE argument
+    #[arg(
+        long = "baseline",
+        value_name = "SNAPSHOT",
+        conflicts_with = "baseline"
+    )]
+    pub baseline_file: Option<PathBuf>,
+
+    /// Snapshot to compare instead of analyzing PATH, e.g. one saved by another CI run
+    #[arg(long, value_name = "SNAPSHOT", conflicts_with_all = ["path", "lang"])]
+    pub current: Option<PathBuf>,
+
+    /// Output format (default: inferred from the --output extension, else markdown)
+    #[arg(short, long)]
+    pub format: Option<OutputFormat>,
 
-    /// Output file (defaults to stdout)
+    /// Output file; repeat to write several formats from one run (defaults to stdout)
     #[arg(short, long)]
-    pub output: Option<PathBuf>,
+    pub output: Vec<PathBuf>,
 
     /// Languages to analyze (comma-separated: rust,typescript,python)
     #[arg(long, value_delimiter = ',')]
@@ -206,6 +547,24 @@ pub struct DiffArgs {
     /// Exit with error if architectural regressions are found
     #[arg(long)]
     pub fail_on_regression: bool,
+
+    /// Leave new and resolved issues below this severity out of the report;
+    /// --fail-on-regression still counts them (default: min_severity from the
+    /// config, else info)
+    #[arg(long)]
+    pub min_severity: Option<IssueSeverity>,
+}
+
+impl DiffArgs {
+    /// The baseline snapshot, given as the first argument or with --baseline
+    pub fn baseline(&self) -> Option<&Path> {
+        self.baseline.as_deref().or(self.baseline_file.as_deref())
+    }
+
+    /// The path to analyze (defaults to current directory)
+    pub fn path(&self) -> PathBuf {
+        self.path.clone().unwrap_or_else(|| PathBuf::from("."))
+    }
 }
 
 #[derive(Parser, Debug, Clone)]
@@ -230,10 +589,168 @@ pub struct GraphArgs {
     #[arg(short, long)]
     pub watch: bool,
 
+    /// Poll for changed files instead of waiting for filesystem events (for
+    /// network filesystems and others without inotify or FSEvents)
+    #[arg(long, requires = "watch")]
+    pub poll: bool,
+
+    /// Milliseconds between checks for changed files when polling (default: 1000)
+    #[arg(long, value_name = "MS", requires = "watch", value_parser = clap::value_parser!(u64).range(1..))]
+    pub watch_interval: Option<u64>,
+
+    /// Milliseconds without further changes before a burst of edits triggers a
+    /// rerun in watch mode (default: 300)
+    #[arg(long, value_name = "MS", requires = "watch")]
+    pub watch_debounce: Option<u64>,
+
     /// Export graph as static HTML file instead of serving
     #[arg(long)]
     pub export: Option<PathBuf>,
 
+    /// Graph format: html (interactive page) or matrix (CSV adjacency matrix,
+    /// written to --export or stdout)
+    #[arg(long, default_value = "html")]
+    pub format: GraphFormat,
+
+    /// Minimum severity counted in node issue totals, overridable per request via
+    /// `?min_severity=` (default: min_severity from the config, else warn)
+    #[arg(long)]
+    pub min_severity: Option<IssueSeverity>,
+
+    /// Force between nodes; more negative spreads them further (default: scaled
+    /// with node count, -300 at 50 modules)
+    #[arg(long, allow_hyphen_values = true)]
+    pub charge: Option<f64>,
+
+    /// Target length of dependency links (default: scaled with node count, 100
+    /// at 50 modules)
+    #[arg(long)]
+    pub link_distance: Option<f64>,
+
+    /// Spacing kept around each node (default: scaled with node count, 5 at 50
+    /// modules)
+    #[arg(long)]
+    pub collision_radius: Option<f64>,
+
+    /// Only graph modules annotated with this tag, e.g. layer=domain (repeatable)
+    #[arg(long = "filter-tag", value_name = "KEY=VALUE")]
+    pub filter_tag: Vec<String>,
+
+    /// Languages to analyze (comma-separated: rust,typescript,python)
+    #[arg(long, value_delimiter = ',')]
+    pub lang: Option<Vec<String>>,
+}
+
+#[derive(Parser, Debug, Clone)]
+pub struct CheckArgs {
+    /// Path to analyze (defaults to current directory)
+    #[arg(default_value = ".")]
+    pub path: PathBuf,
+
+    /// Languages to analyze (comma-separated: rust,typescript,python)
+    #[arg(long, value_delimiter = ',')]
+    pub lang: Option<Vec<String>>,
+
+    /// Run only these detectors instead of checking expectations
+    /// (comma-separated, e.g. cycles,boundaries)
+    #[arg(long, value_delimiter = ',', value_name = "DETECTORS")]
+    pub only: Vec<String>,
+
+    /// With --only, fail when an issue at or above this severity is found
+    #[arg(long, default_value = "error", requires = "only")]
+    pub fail_on: IssueSeverity,
+}
+
+#[derive(Parser, Debug, Clone)]
+pub struct ListArgs {
+    #[command(subcommand)]
+    pub target: ListTarget,
+
+    /// Project path (defaults to current directory)
+    #[arg(long, default_value = ".", global = true)]
+    pub path: PathBuf,
+
+    /// Output format
+    #[arg(short, long, default_value = "table", global = true)]
+    pub format: ListFormat,
+
+    /// Languages to analyze (comma-separated: rust,typescript,python)
+    #[arg(long, value_delimiter = ',', global = true)]
+    pub lang: Option<Vec<String>>,
+}
+
+#[derive(Subcommand, Debug, Clone)]
+pub enum ListTarget {
+    /// Every module with size, coupling and issue counts
+    Modules {
+        /// Column to sort by (counts sort largest first)
+        #[arg(long, default_value = "path")]
+        sort: ModuleSort,
+
+        /// Only list modules whose relative path matches this glob
+        #[arg(long, value_name = "GLOB")]
+        filter: Option<String>,
+    },
+
+    /// Exported names of a file
+    Exports {
+        /// File to list
+        file: PathBuf,
+    },
+
+    /// Internal dependencies and import statements of a file
+    Imports {
+        /// File to list
+        file: PathBuf,
+    },
+
+    /// Detected issues
+    Issues {
+        /// Only list issues of this kind (e.g. LowCohesion)
+        #[arg(long, value_name = "KIND")]
+        kind: Option<String>,
+
+        /// Minimum severity to list (default: min_severity from the config, else info)
+        #[arg(long)]
+        min_severity: Option<IssueSeverity>,
+    },
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum ListFormat {
+    /// Aligned columns with a header row
+    #[default]
+    Table,
+    Json,
+    /// Tab-separated rows without a header, for scripting
+    Plain,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum ModuleSort {
+    #[default]
+    Path,
+    Lines,
+    Defs,
+    Exports,
+    FanIn,
+    FanOut,
+    Issues,
+}
+
+#[derive(Parser, Debug, Clone)]
+pub struct ExplainArgs {
+    /// Issue ID (as listed by `list issues` and stored in snapshots) or issue kind
+    pub target: String,
+
+    /// With a kind, explain that check for this file
+    #[arg(long)]
+    pub file: Option<PathBuf>,
+
+    /// Project path (defaults to current directory)
+    #[arg(long, default_value = ".")]
+    pub path: PathBuf,
+
     /// Languages to analyze (comma-separated: rust,typescript,python)
     #[arg(long, value_delimiter = ',')]
     pub lang: Option<Vec<String>>,
@@ -244,6 +761,19 @@ pub struct InitArgs {
     /// Path where to create .archmap.toml (defaults to current directory)
     #[arg(default_value = ".")]
     pub path: PathBuf,
+
+    /// Analyze the project first: thresholds just above today's worst values,
+    /// high fan-in modules as expected coupling, only boundaries that occur
+    #[arg(long)]
+    pub detect: bool,
+
+    /// Ask before including each detected section (implies --detect)
+    #[arg(long)]
+    pub interactive: bool,
+
+    /// Overwrite an existing .archmap.toml
+    #[arg(long)]
+    pub force: bool,
 }
 
 #[derive(Parser, Debug, Clone)]
@@ -253,6 +783,47 @@ pub struct McpArgs {
     pub path: PathBuf,
 }
 
+#[derive(Parser, Debug, Clone)]
+pub struct TuiArgs {
+    /// Path to analyze (defaults to current directory)
+    #[arg(default_value = ".")]
+    pub path: PathBuf,
+
+    /// Initial minimum severity of issues shown, cycled with `f` (default:
+    /// min_severity from the config, else info)
+    #[arg(long)]
+    pub min_severity: Option<IssueSeverity>,
+
+    /// Languages to analyze (comma-separated: rust,typescript,python)
+    #[arg(long, value_delimiter = ',')]
+    pub lang: Option<Vec<String>>,
+}
+
+#[derive(Parser, Debug, Clone)]
+pub struct VersionArgs {
+    /// text prints one line; json adds languages and schema versions
+    #[arg(short, long, default_value = "text")]
+    pub format: SummaryFormat,
+}
+
+#[derive(Parser, Debug, Clone)]
+pub struct ConfigDumpArgs {
+    /// Project whose configuration to show (defaults to current directory)
+    #[arg(default_value = ".")]
+    pub paths: Vec<PathBuf>,
+
+    /// Output format
+    #[arg(short, long, default_value = "toml")]
+    pub format: ConfigFormat,
+}
+
+#[derive(Parser, Debug, Clone)]
+pub struct LanguagesArgs {
+    /// Only list these languages, as analysis with the same `--lang` would
+    #[arg(long, value_delimiter = ',')]
+    pub lang: Option<Vec<String>>,
+}
+
 #[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
 pub enum OutputFormat {
     #[default]
@@ -260,7 +831,37 @@ pub enum OutputFormat {
     Json,
 }
 
-#[derive(Debug, Clone, Copy, Default, clap::ValueEnum)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum ConfigFormat {
+    #[default]
+    Toml,
+    Json,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum SummaryFormat {
+    #[default]
+    Text,
+    Json,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum GraphFormat {
+    #[default]
+    Html,
+    Matrix,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum ImpactFormat {
+    #[default]
+    Markdown,
+    Json,
+    /// Graphviz digraph of the affected files, colored by depth
+    Dot,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
 pub enum AiOutputFormat {
     #[default]
     Markdown,
@@ -268,6 +869,28 @@ pub enum AiOutputFormat {
     Xml,
 }
 
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum VisibilityLevel {
+    /// Public items only
+    #[default]
+    Public,
+    /// Public and crate-visible items
+    Crate,
+    /// Everything, including private items
+    All,
+}
+
+impl VisibilityLevel {
+    /// Whether a definition with this visibility passes the filter
+    pub fn includes(self, visibility: Visibility) -> bool {
+        match self {
+            VisibilityLevel::Public => visibility == Visibility::Public,
+            VisibilityLevel::Crate => visibility != Visibility::Private,
+            VisibilityLevel::All => true,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Copy, Default, clap::ValueEnum)]
 pub enum PriorityStrategy {
     /// Prioritize modules by number of dependents (most imported first)
@@ -278,3 +901,54 @@ pub enum PriorityStrategy {
     /// Combined score using fan-in, fan-out, and data structures
     Combined,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn diff_args(argv: &[&str]) -> DiffArgs {
+        let cli = Cli::try_parse_from(argv).expect("arguments should parse");
+        match cli.command {
+            Some(Command::Diff(args)) => args,
+            other => panic!("expected diff, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_diff_takes_the_baseline_as_a_flag() {
+        let args = diff_args(&[
+            "archmap",
+            "diff",
+            "--baseline",
+            "a.json",
+            "--current",
+            "b.json",
+        ]);
+        assert_eq!(args.baseline(), Some(Path::new("a.json")));
+        assert_eq!(args.current, Some(PathBuf::from("b.json")));
+        assert_eq!(args.path(), PathBuf::from("."));
+    }
+
+    #[test]
+    fn test_diff_takes_the_baseline_as_an_argument() {
+        let args = diff_args(&["archmap", "diff", "a.json", "src"]);
+        assert_eq!(args.baseline(), Some(Path::new("a.json")));
+        assert_eq!(args.path(), PathBuf::from("src"));
+    }
+
+    #[test]
+    fn test_diff_requires_one_baseline() {
+        assert!(Cli::try_parse_from(["archmap", "diff"]).is_err());
+        assert!(Cli::try_parse_from(["archmap", "diff", "--baseline", "a.json", "x"]).is_err());
+    }
+
+    #[test]
+    fn test_diff_current_snapshot_replaces_the_path() {
+        let two_snapshots = ["archmap", "diff", "a.json", "--current", "b.json"];
+        assert!(Cli::try_parse_from(two_snapshots).is_ok());
+        assert!(
+            Cli::try_parse_from(["archmap", "diff", "a.json", "src", "--current", "b.json"])
+                .is_err()
+        );
+    }
+}
@@ -1,80 +1,242 @@
-use crate::cli::AiArgs;
-use crate::fs::{FileSystem, default_fs};
-use crate::parser::ParserRegistry;
+use crate::analysis::PathFilter;
+use crate::cli::{AiArgs, AiOutputFormat};
+use crate::config::Config;
+use crate::fs::{CachingFileSystem, FileSystem, default_fs};
+use crate::model::IssueSeverity;
+use crate::output::templates::{self, PreambleVars};
+use crate::output::{FileSources, LimitedWriter, OutputFormatter};
 use crate::style;
-use std::collections::HashMap;
-use std::io::{self, Write};
-use std::path::{Path, PathBuf};
+use std::io::Write;
+use std::path::Path;
 
-use super::CommandContext;
+use super::sinks::{OutputKind, Sink, sinks};
+use super::watch::{Watcher, debounce_period, poll_interval};
+use super::{CommandContext, exit_code, output_options, report_cache, report_diagnostics};
+
+/// Output limit when stdout is piped and nothing else bounds the context
+const DEFAULT_PIPE_LIMIT: u64 = 10 * 1024 * 1024;
+
+const TRUNCATION_HINT: &str =
+    "Narrow the context with --tokens, --top or --include, or pass --force to write all of it";
 
 pub fn cmd_ai(args: AiArgs) -> i32 {
     cmd_ai_with_fs(args, default_fs())
 }
 
 fn cmd_ai_with_fs(args: AiArgs, fs: &dyn FileSystem) -> i32 {
-    let ctx = match CommandContext::new(&args.path, args.lang.as_deref()) {
+    // Source collection and analysis read each file once between them
+    let cache = CachingFileSystem::new(fs);
+    let fs: &dyn FileSystem = &cache;
+
+    let ctx = match CommandContext::with_paths(&args.paths, args.lang.as_deref()) {
         Ok(ctx) => ctx,
         Err(code) => return code,
     };
 
-    // Collect source files for AI output
-    let sources = collect_sources_with_fs(&ctx.path, &ctx.registry, fs);
+    let mut config = ctx.config.clone();
+    config.include_generated = args.include_generated;
+
+    let preamble_template = match &args.preamble {
+        Some(preamble) => match load_preamble(preamble, fs) {
+            Ok(t) => Some(t),
+            Err(code) => return code,
+        },
+        None => None,
+    };
+
+    if !args.watch {
+        let code = generate(&ctx, &config, &args, preamble_template.as_deref(), fs);
+        report_cache(&cache);
+        return code;
+    }
+
+    style::status(&format!(
+        "Watching {} for changes (Ctrl+C to stop)...",
+        style::path(&ctx.path)
+    ));
+    println!();
+    config.threads = crate::analysis::thread_count(&config, true);
+    let watcher = Watcher::new(&ctx, &[], fs)
+        .poll_only(args.poll)
+        .poll_every(poll_interval(args.watch_interval))
+        .debounce_for(debounce_period(args.watch_debounce));
+    let regenerate = || {
+        if generate(&ctx, &config, &args, preamble_template.as_deref(), fs) == exit_code::SUCCESS {
+            for path in &args.output {
+                style::success(&format!("Wrote {}", style::path(path)));
+            }
+        }
+        report_cache(&cache);
+    };
+    regenerate();
+    watcher.run(|_| regenerate())
+}
+
+/// Analyze the project and write the context to every `--output` (or stdout).
+fn generate(
+    ctx: &CommandContext,
+    config: &Config,
+    args: &AiArgs,
+    preamble_template: Option<&str>,
+    fs: &dyn FileSystem,
+) -> i32 {
+    let filter = PathFilter::new(args.include.clone(), args.exclude.clone());
 
     // Run analysis
-    let result = crate::analysis::analyze(&ctx.path, &ctx.config, &ctx.registry, &[]);
-
-    // Set up output
-    let mut output: Box<dyn Write> = match &args.output {
-        Some(output_path) => match fs.create_file(output_path) {
-            Ok(writer) => writer,
-            Err(e) => {
-                style::error(&format!("Could not create output file: {}", e));
-                return 1;
+    let result = crate::analysis::analyze_paths_with_fs(
+        &ctx.path,
+        &ctx.roots,
+        config,
+        &ctx.registry,
+        &[],
+        &filter,
+        fs,
+    );
+    report_diagnostics(&result.diagnostics);
+    if ctx.nothing_found(&result.modules) {
+        return exit_code::SUCCESS;
+    }
+
+    let preamble = match preamble_template {
+        Some(template) => {
+            let vars = PreambleVars {
+                project_name: result.project_name.clone(),
+                module_count: result.modules.len(),
+                budget: args.tokens,
+                focus: args.focus.clone(),
+            };
+            match templates::render_preamble(template, &vars) {
+                Ok(p) => Some(p),
+                Err(e) => {
+                    style::error(&e.to_string());
+                    return exit_code::USAGE;
+                }
             }
-        },
-        None => Box::new(io::stdout()),
+        }
+        None => None,
     };
 
     // Build AI output formatter
-    let mut formatter = crate::output::AiOutput::new(Some(ctx.path))
+    let mut formatter = crate::output::AiOutput::new(Some(ctx.path.clone()))
         .with_topo_order(args.topo_order)
         .with_signatures_only(args.signatures)
+        .with_visibility(args.visibility)
+        .with_kinds(args.kinds.clone())
+        .with_compact_types(args.compact_types)
+        .with_layout_tokens(args.layout_tokens)
+        .with_anchors(!args.no_anchors)
         .with_priority(args.priority)
-        .with_format(args.format)
-        .with_sources(sources);
+        .with_source_provider(FileSources::new(fs))
+        .with_include(args.include.clone())
+        .with_exclude(args.exclude.clone())
+        .with_options(output_options(
+            args.min_severity,
+            config,
+            IssueSeverity::Info,
+        ));
 
     if let Some(tokens) = args.tokens {
         formatter = formatter.with_token_budget(tokens);
     }
+    if let Some(top) = args.top {
+        formatter = formatter.with_top(top);
+    }
+    if let Some(preamble) = preamble {
+        formatter = formatter.with_preamble(preamble);
+    }
 
-    if let Err(e) = crate::output::OutputFormatter::format(&formatter, &result, &mut output) {
-        style::error(&format!("Failed to write output: {}", e));
-        return 1;
+    for sink in sinks(&args.output, args.format, AiOutputFormat::Markdown) {
+        formatter = formatter.with_format(sink.format);
+        let written = match output_limit(args, &sink) {
+            None => sink
+                .open(fs)
+                .map(|mut output| formatter.format(&result, &mut output)),
+            Some(limit) if sink.format == AiOutputFormat::Markdown => sink
+                .open(fs)
+                .map(|output| write_truncated(&formatter, &result, output, limit)),
+            Some(limit) => {
+                // Half a JSON or XML document is no use to anyone, so these
+                // are rendered in memory and refused whole when too large
+                let mut buffer = LimitedWriter::new(Vec::new(), limit);
+                let rendered = formatter.format(&result, &mut buffer);
+                if buffer.truncated() {
+                    style::error(&format!(
+                        "The {} context is over the {} byte output limit (--max-output-bytes)",
+                        sink.format.name(),
+                        limit
+                    ));
+                    style::hint(TRUNCATION_HINT);
+                    return exit_code::FAILURE;
+                }
+                sink.open(fs).map(|mut output| {
+                    rendered.and_then(|()| output.write_all(&buffer.into_inner()))
+                })
+            }
+        };
+        match written {
+            Ok(Ok(())) => {}
+            Ok(Err(e)) => {
+                style::error(&format!("Failed to write output: {}", e));
+                return exit_code::FAILURE;
+            }
+            Err(code) => return code,
+        }
     }
 
-    0
+    exit_code::SUCCESS
 }
 
-fn collect_sources_with_fs(
-    path: &Path,
-    registry: &ParserRegistry,
-    fs: &dyn FileSystem,
-) -> HashMap<PathBuf, String> {
-    let mut sources = HashMap::new();
-    let walker = ignore::WalkBuilder::new(path)
-        .hidden(true)
-        .git_ignore(true)
-        .build();
-
-    for entry in walker.flatten() {
-        let file_path = entry.path();
-        if file_path.is_file() && registry.find_parser(file_path).is_some() {
-            if let Ok(content) = fs.read_to_string(file_path) {
-                sources.insert(file_path.to_path_buf(), content);
-            }
-        }
+/// Byte limit for one sink: `--max-output-bytes`, or when piping to another
+/// program without a token budget, [`DEFAULT_PIPE_LIMIT`]. `--force` lifts it.
+fn output_limit(args: &AiArgs, sink: &Sink<AiOutputFormat>) -> Option<u64> {
+    if args.force {
+        return None;
+    }
+    args.max_output_bytes.or_else(|| {
+        (sink.is_stdout() && !style::is_terminal() && args.tokens.is_none())
+            .then_some(DEFAULT_PIPE_LIMIT)
+    })
+}
+
+/// Write markdown context up to `limit` bytes, cut at a line and ending in a
+/// marker when it doesn't fit.
+fn write_truncated(
+    formatter: &crate::output::AiOutput,
+    result: &crate::model::AnalysisResult,
+    output: Box<dyn Write + Send>,
+    limit: u64,
+) -> std::io::Result<()> {
+    let mut limited = LimitedWriter::new(output, limit);
+    let written = formatter.format(result, &mut limited);
+    if !limited.truncated() {
+        return written;
+    }
+
+    style::warning(&format!(
+        "Output cut off at the {} byte limit (--max-output-bytes)",
+        limit
+    ));
+    style::hint(TRUNCATION_HINT);
+    let mut output = limited.into_inner();
+    writeln!(
+        output,
+        "\n<!-- archmap: output truncated at {} bytes; narrow it with --tokens, --top or --include, or pass --force -->",
+        limit
+    )
+}
+
+/// Resolve `--preamble` to template text: a built-in name, or else a file path.
+fn load_preamble(preamble: &str, fs: &dyn FileSystem) -> Result<String, i32> {
+    if let Some(template) = templates::builtin(preamble) {
+        return Ok(template.to_string());
     }
 
-    sources
+    fs.read_to_string(Path::new(preamble)).map_err(|e| {
+        style::error(&format!("Could not read preamble '{}': {}", preamble, e));
+        style::hint(&format!(
+            "Use a template file or a built-in: {}",
+            templates::BUILTIN_NAMES.join(", ")
+        ));
+        exit_code::USAGE
+    })
 }
@@ -1,201 +1,410 @@
-use crate::cli::{AnalyzeArgs, OutputFormat};
+use crate::analysis::PathFilter;
+use crate::cli::{AnalyzeArgs, ConfigFormat, OutputFormat};
 use crate::config::Config;
-use crate::fs::{FileSystem, default_fs};
+use crate::fs::{CachingFileSystem, FileSystem, OverlayFs, default_fs, resolve_virtual_path};
 use crate::model::IssueSeverity;
 use crate::output::{JsonOutput, MarkdownOutput, OutputFormatter};
-use crate::parser::ParserRegistry;
 use crate::style;
-use std::io::{self, Write};
+use std::collections::HashMap;
+use std::io::{self, Read};
 use std::path::Path;
-use std::time::Duration;
 
-use super::CommandContext;
+use super::config_dump::{apply_global_overrides, print_config};
+use super::remote::Checkout;
+use super::sinks::{OutputKind, Sink, sinks};
+use super::watch::{Watcher, debounce_period, poll_interval};
+use super::{CommandContext, exit_code, output_options, report_cache, report_diagnostics};
 
-pub fn cmd_analyze(args: AnalyzeArgs) -> i32 {
-    let ctx = match CommandContext::new(&args.path, args.lang.as_deref()) {
+pub fn cmd_analyze(mut args: AnalyzeArgs) -> i32 {
+    // Kept until the analysis is done; dropping it deletes the clone
+    let _checkout = match &args.git {
+        Some(url) => {
+            style::status(&format!("Cloning {}...", url));
+            let checkout = match Checkout::shallow_clone(url, args.git_ref.as_deref()) {
+                Ok(checkout) => checkout,
+                Err(e) => {
+                    style::error(&e);
+                    return exit_code::FAILURE;
+                }
+            };
+            if let Some(absolute) = args.paths.iter().find(|p| p.is_absolute()) {
+                style::error(&format!(
+                    "{} is absolute; with --git, paths are inside the clone",
+                    style::path(absolute)
+                ));
+                return exit_code::USAGE;
+            }
+            args.paths = args.paths.iter().map(|p| checkout.path().join(p)).collect();
+            Some(checkout)
+        }
+        None => None,
+    };
+
+    let ctx = match CommandContext::with_paths(&args.paths, args.lang.as_deref()) {
         Ok(ctx) => ctx,
         Err(code) => return code,
     };
 
+    if args.dump_config {
+        let (mut config, mut overrides) = effective_config(&ctx, &args);
+        overrides.extend(apply_global_overrides(&mut config));
+        return print_config(&ctx, &config, overrides, ConfigFormat::Toml);
+    }
+
     if args.watch {
-        run_watch_mode(&ctx.path, &ctx.config, &ctx.registry, &args);
-        0
+        run_watch_mode(&ctx, &args);
+        exit_code::SUCCESS
+    } else if args.stdin {
+        run_stdin_analysis(&ctx, &args)
     } else {
-        run_analysis(&ctx.path, &ctx.config, &ctx.registry, &args)
+        run_analysis(&ctx, &args)
     }
 }
 
-fn run_analysis(
-    path: &Path,
-    config: &Config,
-    registry: &ParserRegistry,
-    args: &AnalyzeArgs,
-) -> i32 {
-    run_analysis_with_fs(path, config, registry, args, default_fs())
+fn run_analysis(ctx: &CommandContext, args: &AnalyzeArgs) -> i32 {
+    run_analysis_with_fs(ctx, args, default_fs(), None)
+}
+
+/// Analyze the project with stdin standing in for `--stdin-path`, e.g. an
+/// unsaved editor buffer, and report only the issues touching that file.
+fn run_stdin_analysis(ctx: &CommandContext, args: &AnalyzeArgs) -> i32 {
+    let Some(stdin_path) = &args.stdin_path else {
+        style::error("--stdin requires --stdin-path");
+        return exit_code::USAGE;
+    };
+    let file = resolve_virtual_path(stdin_path);
+
+    if !ctx.roots.iter().any(|root| file.starts_with(root)) {
+        style::error(&format!(
+            "{} is outside the analyzed paths",
+            style::path(stdin_path)
+        ));
+        return exit_code::USAGE;
+    }
+    if ctx.registry.find_parser(&file).is_none() {
+        style::error(&format!("No parser for {}", style::path(stdin_path)));
+        style::hint("The file extension selects the language (see --lang)");
+        return exit_code::USAGE;
+    }
+
+    let mut source = String::new();
+    if let Err(e) = io::stdin().read_to_string(&mut source) {
+        style::error(&format!("Could not read stdin: {}", e));
+        return exit_code::FAILURE;
+    }
+
+    let fs = OverlayFs::new(default_fs()).with_file(&file, source);
+    run_analysis_with_fs(ctx, args, &fs, Some(&file))
 }
 
+/// The project config with this command's threshold and check flags applied,
+/// and those flags as given.
+fn effective_config(ctx: &CommandContext, args: &AnalyzeArgs) -> (Config, Vec<String>) {
+    let mut config = ctx.config.clone();
+    let mut overrides = Vec::new();
+    if let Some(max_depth) = args.max_depth {
+        config.thresholds.max_dependency_depth = max_depth;
+        overrides.push(format!("--max-depth {}", max_depth));
+    }
+    if let Some(min_cohesion) = args.min_cohesion {
+        config.thresholds.min_cohesion = min_cohesion;
+        overrides.push(format!("--min-cohesion {}", min_cohesion));
+    }
+    if args.include_generated {
+        config.include_generated = true;
+        overrides.push("--include-generated".to_string());
+    }
+    for kind in &args.disable {
+        config.disabled_checks.push(kind.clone());
+        overrides.push(format!("--disable {}", kind));
+    }
+    for kind in &args.enable {
+        config.enabled_checks.push(kind.clone());
+        overrides.push(format!("--enable {}", kind));
+    }
+    (config, overrides)
+}
+
+/// Run the analysis and write the report. With `focus`, only issues with a
+/// location in that file are reported.
 fn run_analysis_with_fs(
-    path: &Path,
-    config: &Config,
-    registry: &ParserRegistry,
+    ctx: &CommandContext,
     args: &AnalyzeArgs,
     fs: &dyn FileSystem,
+    focus: Option<&Path>,
 ) -> i32 {
-    // Run analysis with CLI overrides for thresholds
-    let mut effective_config = config.clone();
-    effective_config.thresholds.max_dependency_depth = args.max_depth;
-    effective_config.thresholds.min_cohesion = args.min_cohesion;
+    let path = ctx.path.as_path();
 
-    let result = crate::analysis::analyze(path, &effective_config, registry, &args.exclude);
+    let (mut effective_config, _) = effective_config(ctx, args);
+    if args.watch {
+        effective_config.threads = crate::analysis::thread_count(&effective_config, true);
+    }
 
-    // Set up output
-    let mut output: Box<dyn Write> = match &args.output {
-        Some(output_path) => match fs.create_file(output_path) {
-            Ok(writer) => writer,
+    // Parsing and the detectors that reread sources share one read per file
+    let cache = CachingFileSystem::new(fs);
+    let mut result = crate::analysis::analyze_paths_with_fs(
+        path,
+        &ctx.roots,
+        &effective_config,
+        &ctx.registry,
+        &args.exclude,
+        &PathFilter::default().with_tags(args.filter_tag.clone()),
+        &cache,
+    );
+    report_diagnostics(&result.diagnostics);
+    report_cache(&cache);
+    if ctx.nothing_found(&result.modules) {
+        return exit_code::SUCCESS;
+    }
+
+    if let Some(focus) = focus {
+        result
+            .issues
+            .retain(|issue| issue.locations.iter().any(|loc| loc.path == focus));
+    }
+
+    let template = match &args.template {
+        Some(template_path) => match fs.read_to_string(template_path) {
+            Ok(t) => Some(t),
             Err(e) => {
-                style::error(&format!("Could not create output file: {}", e));
-                return 1;
+                style::error(&format!("Could not read template file: {}", e));
+                return exit_code::FAILURE;
             }
         },
-        None => Box::new(io::stdout()),
+        None => None,
+    };
+    let baseline = match &args.baseline {
+        Some(baseline_path) => match crate::snapshot::load_snapshot_with_fs(baseline_path, fs) {
+            Ok(snapshot) => Some(snapshot.issue_ids()),
+            Err(e) => {
+                style::error(&e.to_string());
+                return exit_code::FAILURE;
+            }
+        },
+        None => None,
     };
 
-    // Format output to string first
-    let mut buffer = Vec::new();
-    let format_result = match args.format {
-        OutputFormat::Markdown => {
-            let formatter = MarkdownOutput::new(args.min_severity, Some(path.to_path_buf()));
-            formatter.format(&result, &mut buffer)
-        }
-        OutputFormat::Json => {
-            let formatter = JsonOutput::new(Some(path.to_path_buf()));
-            formatter.format(&result, &mut buffer)
-        }
+    let sinks = match analyze_sinks(args) {
+        Ok(sinks) => sinks,
+        Err(code) => return code,
     };
+    let has_markdown = sinks.iter().any(|s| s.format == OutputFormat::Markdown);
+    if template.is_some() && !has_markdown {
+        style::warning("--template only applies to markdown output; ignoring it.");
+    }
+    if args.with_context.is_some() && !has_markdown {
+        style::warning("--with-context only applies to markdown output; ignoring it.");
+    }
+
+    // Issues below the floor are left out of every format, but still count
+    // toward the exit code below
+    let options = output_options(args.min_severity, &ctx.config, IssueSeverity::Info);
 
-    if let Err(e) = format_result {
-        style::error(&format!("Failed to format output: {}", e));
-        return 1;
+    // Snippets come from the files the reported issues point at, most of
+    // them still in the cache
+    let mut snippet_sources = HashMap::new();
+    if args.with_context.is_some_and(|lines| lines > 0) && has_markdown {
+        for issue in result.issues_at_least(options.min_severity) {
+            for loc in &issue.locations {
+                if !snippet_sources.contains_key(&loc.path)
+                    && let Ok(source) = cache.read_to_string(&loc.path)
+                {
+                    snippet_sources.insert(loc.path.clone(), source);
+                }
+            }
+        }
     }
 
-    let output_str = String::from_utf8_lossy(&buffer);
+    // Format once per output; every format reads the same analysis result
+    for sink in sinks {
+        let mut buffer = Vec::new();
+        let format_span = tracing::info_span!("format", format = ?sink.format).entered();
+        let format_result = match sink.format {
+            OutputFormat::Markdown => {
+                let mut formatter = MarkdownOutput::new(options, Some(path.to_path_buf()));
+                if let Some(template) = &template {
+                    formatter = formatter.with_template(template.clone());
+                }
+                if let Some(ids) = &baseline {
+                    formatter = formatter.with_baseline(ids.clone());
+                }
+                if let Some(lines) = args.with_context {
+                    formatter = formatter.with_context(lines, snippet_sources.clone());
+                }
+                if let Some(count) = args.key_files {
+                    formatter = formatter.with_key_files(count);
+                }
+                formatter.format(&result, &mut buffer)
+            }
+            OutputFormat::Json => {
+                let mut formatter = JsonOutput::new(Some(path.to_path_buf())).with_options(options);
+                if let Some(ids) = &baseline {
+                    formatter = formatter.with_baseline(ids.clone());
+                }
+                if let Some(count) = args.key_files {
+                    formatter = formatter.with_key_files(count);
+                }
+                formatter.format(&result, &mut buffer)
+            }
+        };
+        format_span.exit();
 
-    // Render markdown nicely to terminal, or write plain text to file/pipe
-    let write_result = if args.output.is_none() && args.format == OutputFormat::Markdown {
-        style::render_markdown(&output_str, &mut output)
-    } else {
-        write!(output, "{}", output_str)
-    };
+        if let Err(e) = format_result {
+            style::error(&format!("Failed to format output: {}", e));
+            return exit_code::FAILURE;
+        }
 
-    if let Err(e) = write_result {
-        style::error(&format!("Failed to write output: {}", e));
-        return 1;
+        // Render markdown nicely to terminal, or write plain text to file/pipe
+        let output_str = String::from_utf8_lossy(&buffer);
+        let render = sink.format == OutputFormat::Markdown;
+        if let Err(code) = sink.write(fs, &output_str, render) {
+            return code;
+        }
     }
 
-    // Exit code 0 = ran successfully (with or without warnings/info)
-    // Exit code 1 = has errors (architectural violations that should block CI)
-    // This allows using archmap in CI pipelines where warnings are informational
-    let has_errors = result
-        .issues
-        .iter()
-        .any(|issue| issue.severity == IssueSeverity::Error);
+    // Only error-level issues (architectural violations that should block CI)
+    // fail the run; warnings and info are informational
+    let has_errors = result.summary().count(IssueSeverity::Error) > 0;
 
-    if has_errors { 1 } else { 0 }
+    if has_errors {
+        exit_code::ISSUES
+    } else {
+        exit_code::SUCCESS
+    }
 }
 
-fn run_watch_mode(path: &Path, config: &Config, registry: &ParserRegistry, args: &AnalyzeArgs) {
-    run_watch_mode_with_fs(path, config, registry, args, default_fs())
+/// Every destination for the report: `--output-dir` gets one `report.<ext>` per
+/// requested format (all of them by default), otherwise `--output`/stdout as
+/// usual, plus the `--json-out` file.
+fn analyze_sinks(args: &AnalyzeArgs) -> Result<Vec<Sink<OutputFormat>>, i32> {
+    let mut sinks = match &args.output_dir {
+        Some(dir) => {
+            if let Err(e) = std::fs::create_dir_all(dir) {
+                style::error(&format!(
+                    "Could not create output directory {}: {}",
+                    style::path(dir),
+                    e
+                ));
+                return Err(exit_code::FAILURE);
+            }
+            let mut formats = args.format.clone();
+            if formats.is_empty() {
+                formats = vec![OutputFormat::Markdown, OutputFormat::Json];
+            }
+            formats.dedup();
+            formats
+                .into_iter()
+                .map(|format| Sink {
+                    path: Some(dir.join("report").with_extension(format.extension())),
+                    format,
+                })
+                .collect()
+        }
+        None if args.format.len() > 1 => {
+            style::error("Several --format values need --output-dir");
+            style::hint("Or pass one --output per format and let the extensions pick");
+            return Err(exit_code::USAGE);
+        }
+        None => sinks(
+            &args.output,
+            args.format.first().copied(),
+            args.output_format(),
+        ),
+    };
+    if let Some(path) = &args.json_out {
+        sinks.push(Sink {
+            path: Some(path.clone()),
+            format: OutputFormat::Json,
+        });
+    }
+    Ok(sinks)
 }
 
-fn run_watch_mode_with_fs(
-    path: &Path,
-    config: &Config,
-    registry: &ParserRegistry,
-    args: &AnalyzeArgs,
-    fs: &dyn FileSystem,
-) {
-    use std::collections::HashMap;
+fn run_watch_mode(ctx: &CommandContext, args: &AnalyzeArgs) {
+    run_watch_mode_with_fs(ctx, args, default_fs())
+}
 
+fn run_watch_mode_with_fs(ctx: &CommandContext, args: &AnalyzeArgs, fs: &dyn FileSystem) {
     style::status(&format!(
         "Watching {} for changes (Ctrl+C to stop)...",
-        style::path(path)
+        style::path(&ctx.path)
     ));
     println!();
 
-    // Initial scan using FileSystem abstraction
-    fn scan_files(
-        path: &Path,
-        fs: &dyn FileSystem,
-    ) -> HashMap<std::path::PathBuf, std::time::SystemTime> {
-        let mut files = HashMap::new();
-        let walker = ignore::WalkBuilder::new(path)
-            .hidden(true)
-            .git_ignore(true)
-            .build();
-
-        for entry in walker.flatten() {
-            let file_path = entry.path();
-            if file_path.is_file() {
-                if let Ok(modified) = fs.modified(file_path) {
-                    files.insert(file_path.to_path_buf(), modified);
-                }
-            }
-        }
-        files
-    }
-
-    let mut last_modified = scan_files(path, fs);
+    let watcher = Watcher::new(ctx, &args.exclude, fs)
+        .poll_only(args.poll)
+        .poll_every(poll_interval(args.watch_interval))
+        .debounce_for(debounce_period(args.watch_debounce));
 
     // Run initial analysis
     style::header("=== Initial Analysis ===");
-    let _ = run_analysis_with_fs(path, config, registry, args, fs);
+    let _ = run_analysis_with_fs(ctx, args, fs, None);
     println!();
 
-    loop {
-        std::thread::sleep(Duration::from_secs(1));
-
-        let current_files = scan_files(path, fs);
-        let mut changed = false;
-
-        // Check for new or modified files
-        for (file_path, modified) in &current_files {
-            let display_path = file_path
-                .strip_prefix(path)
-                .unwrap_or(file_path)
-                .display()
-                .to_string();
-            match last_modified.get(file_path) {
-                Some(last) if last != modified => {
-                    println!("{}", style::file_changed(&display_path));
-                    changed = true;
-                }
-                None => {
-                    println!("{}", style::file_added(&display_path));
-                    changed = true;
-                }
-                _ => {}
-            }
-        }
+    watcher.run(|_| {
+        style::header("=== Re-analyzing ===");
+        let _ = run_analysis_with_fs(ctx, args, fs, None);
+        println!();
+    })
+}
 
-        // Check for deleted files
-        for file_path in last_modified.keys() {
-            if !current_files.contains_key(file_path) {
-                let display_path = file_path
-                    .strip_prefix(path)
-                    .unwrap_or(file_path)
-                    .display()
-                    .to_string();
-                println!("{}", style::file_deleted(&display_path));
-                changed = true;
-            }
-        }
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
 
-        if changed {
-            println!();
-            style::header("=== Re-analyzing ===");
-            let _ = run_analysis_with_fs(path, config, registry, args, fs);
-            println!();
-            last_modified = current_files;
-        }
+    #[test]
+    fn test_repeated_output_writes_each_format() {
+        let dir = std::env::temp_dir().join(format!("archmap-outputs-{}", std::process::id()));
+        fs::create_dir_all(dir.join("src")).unwrap();
+        fs::write(dir.join("src/lib.rs"), "pub fn run() {}\n").unwrap();
+
+        let args = AnalyzeArgs {
+            paths: vec![dir.join("src")],
+            output: vec![dir.join("report.md"), dir.join("report.json")],
+            ..Default::default()
+        };
+        let code = cmd_analyze(args);
+        let markdown = fs::read_to_string(dir.join("report.md"));
+        let json = fs::read_to_string(dir.join("report.json"));
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(code, 0);
+        assert!(markdown.unwrap().starts_with("# Architecture Analysis"));
+        let json: serde_json::Value = serde_json::from_str(&json.unwrap()).unwrap();
+        assert!(json["issues"].is_array());
+    }
+
+    #[test]
+    fn test_output_dir_and_json_out_share_one_analysis() {
+        let dir = std::env::temp_dir().join(format!("archmap-output-dir-{}", std::process::id()));
+        let args = AnalyzeArgs {
+            output_dir: Some(dir.join("out")),
+            json_out: Some(dir.join("extra.json")),
+            ..Default::default()
+        };
+        let paths: Vec<_> = analyze_sinks(&args)
+            .unwrap()
+            .into_iter()
+            .map(|sink| (sink.path.unwrap(), sink.format))
+            .collect();
+        let created = dir.join("out").is_dir();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert!(created);
+        assert_eq!(
+            paths,
+            [
+                (dir.join("out/report.md"), OutputFormat::Markdown),
+                (dir.join("out/report.json"), OutputFormat::Json),
+                (dir.join("extra.json"), OutputFormat::Json),
+            ]
+        );
+
+        let several = AnalyzeArgs {
+            format: vec![OutputFormat::Markdown, OutputFormat::Json],
+            ..Default::default()
+        };
+        assert_eq!(analyze_sinks(&several).unwrap_err(), exit_code::USAGE);
     }
 }
@@ -0,0 +1,121 @@
+use crate::analysis::{detector_names, find_detector, run_only};
+use crate::cli::CheckArgs;
+use crate::model::{Issue, IssueSeverity};
+use crate::style;
+
+use super::{CommandContext, exit_code, report_diagnostics};
+
+pub fn cmd_check(args: CheckArgs) -> i32 {
+    let ctx = match CommandContext::new(&args.path, args.lang.as_deref()) {
+        Ok(ctx) => ctx,
+        Err(code) => return code,
+    };
+
+    if !args.only.is_empty() {
+        return run_detectors(&ctx, &args.only, args.fail_on);
+    }
+
+    let expectations = &ctx.config.expectations;
+    if expectations.is_empty() {
+        style::error("No expectations configured");
+        style::hint("Add an [expectations] section to .archmap.toml (see `archmap init`)");
+        return exit_code::USAGE;
+    }
+
+    let result = ctx.analyze();
+    let results = crate::analysis::evaluate_expectations(&result, expectations, &ctx.path);
+
+    println!("Checking expectations for {}\n", result.project_name);
+    for check in &results {
+        println!(
+            "  {} {}: {} (expected {})",
+            style::check_mark(check.passed),
+            check.name,
+            check.actual,
+            check.expected
+        );
+        for detail in check.details.iter().take(10) {
+            println!("      {}", detail);
+        }
+        if check.details.len() > 10 {
+            println!("      ... and {} more", check.details.len() - 10);
+        }
+    }
+
+    let failed = results.iter().filter(|r| !r.passed).count();
+    println!(
+        "\n{} expectations: {} passed, {} failed",
+        results.len(),
+        results.len() - failed,
+        failed
+    );
+
+    if failed > 0 {
+        exit_code::ISSUES
+    } else {
+        exit_code::SUCCESS
+    }
+}
+
+/// Run just the named detectors and list what they find, for fast hooks that
+/// only care about a few checks.
+fn run_detectors(ctx: &CommandContext, names: &[String], fail_on: IssueSeverity) -> i32 {
+    let mut selected = Vec::new();
+    for name in names {
+        match find_detector(name) {
+            Some(detector) => selected.push(detector),
+            None => {
+                style::error(&format!("Unknown detector: {}", name));
+                style::hint(&format!("Detectors: {}", detector_names().join(", ")));
+                return exit_code::USAGE;
+            }
+        }
+    }
+
+    let config = run_only(&ctx.config, &selected);
+    let result = crate::analysis::analyze(&ctx.path, &config, &ctx.registry, &[]);
+    report_diagnostics(&result.diagnostics);
+
+    let detectors: Vec<&str> = selected.iter().map(|d| d.name).collect();
+    println!(
+        "Running {} for {}\n",
+        detectors.join(", "),
+        result.project_name
+    );
+    let mut issues: Vec<&Issue> = result.issues.iter().collect();
+    issues.sort_by_key(|i| std::cmp::Reverse(i.severity));
+    for issue in &issues {
+        let location = issue
+            .locations
+            .first()
+            .map(|loc| {
+                let path = loc.path.strip_prefix(&ctx.path).unwrap_or(&loc.path);
+                match loc.line {
+                    Some(line) => format!("{}:{}", path.display(), line),
+                    None => path.display().to_string(),
+                }
+            })
+            .unwrap_or_default();
+        println!(
+            "  {} {} {}: {}",
+            style::check_mark(issue.severity < fail_on),
+            issue.severity,
+            location,
+            issue.message
+        );
+    }
+
+    let failing = issues.iter().filter(|i| i.severity >= fail_on).count();
+    println!(
+        "\n{} issues, {} at or above {}",
+        issues.len(),
+        failing,
+        fail_on
+    );
+
+    if failing > 0 {
+        exit_code::ISSUES
+    } else {
+        exit_code::SUCCESS
+    }
+}
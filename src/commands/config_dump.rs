@@ -0,0 +1,156 @@
+//! `archmap config-dump` and `analyze --dump-config`: the configuration in
+//! effect once defaults, config files, a preset and command-line flags are
+//! merged, for finding out why analysis behaves as it does.
+
+use crate::analysis::{cache_enabled, follow_symlinks, size_limits_disabled, threads_override};
+use crate::cli::{ConfigDumpArgs, ConfigFormat};
+use crate::config::{Config, ConfigSource, config_source};
+use crate::style;
+use serde::Serialize;
+
+use super::{CommandContext, config_dir, exit_code};
+
+/// The resolved configuration and what went into it
+#[derive(Debug, Serialize)]
+struct EffectiveConfig<'a> {
+    /// Config files read, lowest precedence first, or the preset or defaults
+    sources: Vec<String>,
+    /// Command-line flags and environment variables that changed a setting
+    overrides: Vec<String>,
+    config: &'a Config,
+}
+
+pub fn cmd_config_dump(args: ConfigDumpArgs) -> i32 {
+    let ctx = match CommandContext::with_paths(&args.paths, None) {
+        Ok(ctx) => ctx,
+        Err(code) => return code,
+    };
+    let mut config = ctx.config.clone();
+    let overrides = apply_global_overrides(&mut config);
+    print_config(&ctx, &config, overrides, args.format)
+}
+
+/// Print `config` as loaded for `ctx`, listing `overrides` as the flags that
+/// changed it.
+pub(crate) fn print_config(
+    ctx: &CommandContext,
+    config: &Config,
+    overrides: Vec<String>,
+    format: ConfigFormat,
+) -> i32 {
+    let effective = EffectiveConfig {
+        sources: config_sources(ctx),
+        overrides,
+        config,
+    };
+    match render(&effective, format) {
+        Ok(text) => {
+            println!("{}", text.trim_end());
+            exit_code::SUCCESS
+        }
+        Err(e) => {
+            style::error(&format!("Could not serialize the config: {}", e));
+            exit_code::FAILURE
+        }
+    }
+}
+
+/// Fold the process-wide flags (`--threads`, `--no-size-limit`,
+/// `--follow-symlinks`, `--no-cache`) into `config`, returning each one that
+/// changed a setting.
+pub(crate) fn apply_global_overrides(config: &mut Config) -> Vec<String> {
+    let mut overrides = Vec::new();
+    if let Some((source, threads)) = threads_override() {
+        config.threads = threads;
+        overrides.push(format!("{} {}", source, threads));
+    }
+    if size_limits_disabled() {
+        config.max_file_size_kb = 0;
+        config.max_line_length = 0;
+        overrides.push("--no-size-limit".to_string());
+    }
+    if !config.follow_symlinks && follow_symlinks(config) {
+        config.follow_symlinks = true;
+        overrides.push("--follow-symlinks".to_string());
+    }
+    if config.cache && !cache_enabled(config) {
+        config.cache = false;
+        overrides.push("--no-cache".to_string());
+    }
+    overrides
+}
+
+/// Where the configuration came from, lowest precedence first
+fn config_sources(ctx: &CommandContext) -> Vec<String> {
+    let source = config_source();
+    let project = config_dir(&ctx.path, &ctx.roots);
+    let mut sources: Vec<String> = source
+        .files(project)
+        .iter()
+        .map(|path| path.display().to_string())
+        .collect();
+    if let ConfigSource::Preset(preset) = source
+        && !project.join(".archmap.toml").exists()
+    {
+        sources.push(format!("preset {}", preset.name()));
+    }
+    if sources.is_empty() {
+        sources.push("built-in defaults".to_string());
+    }
+    sources
+}
+
+fn render(effective: &EffectiveConfig, format: ConfigFormat) -> Result<String, String> {
+    match format {
+        ConfigFormat::Json => serde_json::to_string_pretty(effective).map_err(|e| e.to_string()),
+        ConfigFormat::Toml => {
+            let body = toml::to_string_pretty(effective.config).map_err(|e| e.to_string())?;
+            let overrides = if effective.overrides.is_empty() {
+                "none".to_string()
+            } else {
+                effective.overrides.join(", ")
+            };
+            Ok(format!(
+                "# Effective archmap configuration\n\
+                 # Loaded from: {}\n\
+                 # Command-line overrides: {}\n\n{}",
+                effective.sources.join(", "),
+                overrides,
+                body
+            ))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Thresholds;
+
+    #[test]
+    fn test_config_renders_with_its_sources() {
+        let config = Config {
+            thresholds: Thresholds {
+                coupling_fanin: 12,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let effective = EffectiveConfig {
+            sources: vec!["/p/.archmap.toml".to_string()],
+            overrides: vec!["--threads 4".to_string()],
+            config: &config,
+        };
+
+        let toml = render(&effective, ConfigFormat::Toml).unwrap();
+        assert!(toml.contains("# Loaded from: /p/.archmap.toml"));
+        assert!(toml.contains("# Command-line overrides: --threads 4"));
+        assert!(toml.contains("coupling_fanin = 12"));
+        assert!(toml.contains("[[boundaries]]"));
+
+        let json = render(&effective, ConfigFormat::Json).unwrap();
+        let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(value["config"]["thresholds"]["coupling_fanin"], 12);
+        assert_eq!(value["overrides"][0], "--threads 4");
+    }
+}
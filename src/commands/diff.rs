@@ -1,63 +1,81 @@
 use crate::cli::{DiffArgs, OutputFormat};
-use crate::fs::{FileSystem, default_fs};
+use crate::config::{Config, config_source};
+use crate::fs::default_fs;
+use crate::model::IssueSeverity;
+use crate::snapshot::Snapshot;
 use crate::style;
-use std::io::{self, Write};
 
-use super::CommandContext;
+use super::sinks::sinks;
+use super::{CommandContext, exit_code, output_options};
 
 pub fn cmd_diff(args: DiffArgs) -> i32 {
+    let Some(baseline) = args.baseline() else {
+        style::error("diff needs a baseline snapshot");
+        return exit_code::USAGE;
+    };
+
     // Load baseline snapshot
-    let baseline = match crate::snapshot::load_snapshot(&args.baseline) {
+    let baseline = match crate::snapshot::load_snapshot(baseline) {
         Ok(s) => s,
         Err(e) => {
-            style::error(&format!("Failed to load baseline snapshot: {}", e));
-            return 1;
+            style::error(&e.to_string());
+            return exit_code::FAILURE;
         }
     };
 
-    let ctx = match CommandContext::new(&args.path, args.lang.as_deref()) {
-        Ok(ctx) => ctx,
-        Err(code) => return code,
+    // Load the current snapshot, or create one by analyzing the path
+    let (current, config) = match &args.current {
+        Some(path) => match crate::snapshot::load_snapshot(path) {
+            Ok(s) => match config_source().load(&args.path()) {
+                Ok(config) => (s, config),
+                Err(e) => {
+                    style::error(&e.to_string());
+                    return exit_code::USAGE;
+                }
+            },
+            Err(e) => {
+                style::error(&e.to_string());
+                return exit_code::FAILURE;
+            }
+        },
+        None => match current_snapshot(&args) {
+            Ok(current) => current,
+            Err(code) => return code,
+        },
     };
 
-    // Run current analysis
-    let result = crate::analysis::analyze(&ctx.path, &ctx.config, &ctx.registry, &[]);
-
-    // Create current snapshot
-    let current = crate::snapshot::Snapshot::from_analysis(&result, &ctx.path);
+    if let Some(mismatch) = crate::snapshot::version_mismatch(&baseline, &current) {
+        style::warning(&mismatch);
+    }
 
     // Compute diff
     let diff = crate::snapshot::compute_diff(&baseline, &current);
 
-    // Set up output
-    let mut output: Box<dyn Write> = match &args.output {
-        Some(output_path) => match default_fs().create_file(output_path) {
-            Ok(writer) => writer,
-            Err(e) => {
-                style::error(&format!("Could not create output file: {}", e));
-                return 1;
-            }
-        },
-        None => Box::new(io::stdout()),
-    };
+    // The severity floor only trims the report; --fail-on-regression sees every issue
+    let options = output_options(args.min_severity, &config, IssueSeverity::Info);
 
-    // Format output
-    let output_str = match args.format {
-        OutputFormat::Markdown => crate::snapshot::format_diff_markdown(&diff),
-        OutputFormat::Json => crate::snapshot::format_diff_json(&diff),
-    };
+    // Format once per output, rendering markdown nicely on the terminal
+    for sink in sinks(&args.output, args.format, OutputFormat::Markdown) {
+        let output_str = match sink.format {
+            OutputFormat::Markdown => crate::snapshot::format_diff_markdown(&diff, &options),
+            OutputFormat::Json => crate::snapshot::format_diff_json(&diff, &options),
+        };
+        let render = sink.format == OutputFormat::Markdown;
+        if let Err(code) = sink.write(default_fs(), &output_str, render) {
+            return code;
+        }
+    }
 
-    // Render markdown nicely to terminal, or write plain text to file/pipe
-    let write_result = if args.output.is_none() && args.format == OutputFormat::Markdown {
-        style::render_markdown(&output_str, &mut output)
+    if args.fail_on_regression && diff.has_regressions() {
+        exit_code::ISSUES
     } else {
-        writeln!(output, "{}", output_str)
-    };
-
-    if let Err(e) = write_result {
-        style::error(&format!("Failed to write output: {}", e));
-        return 1;
+        exit_code::SUCCESS
     }
+}
 
-    0
+/// Snapshot of the analyzed path, with the config it was analyzed under
+fn current_snapshot(args: &DiffArgs) -> Result<(Snapshot, Config), i32> {
+    let ctx = CommandContext::new(&args.path(), args.lang.as_deref())?;
+    let result = ctx.analyze();
+    Ok((Snapshot::from_analysis(&result, &ctx.path), ctx.config))
 }
@@ -0,0 +1,18 @@
+//! Process exit codes. Scripts wrapping archmap rely on these, so they are a
+//! stable contract: new failure kinds map onto an existing code.
+
+/// The command ran and found nothing that should fail a build
+pub const SUCCESS: i32 = 0;
+
+/// The command ran and found error-level issues, failed expectations or, with
+/// `--fail-on-regression`, regressions. `--exit-zero` turns this into success.
+pub const ISSUES: i32 = 1;
+
+/// Invalid arguments or configuration: a missing path, an unknown issue kind, a
+/// config file that doesn't exist or doesn't parse. Clap's own argument errors
+/// use this code too.
+pub const USAGE: i32 = 2;
+
+/// The command could not finish: reading or writing a file, loading a snapshot,
+/// or running the server failed.
+pub const FAILURE: i32 = 3;
@@ -0,0 +1,151 @@
+use crate::analysis::{CheckDescription, Explanation, describe_check, explain_issue};
+use crate::cli::ExplainArgs;
+use crate::fs::{default_fs, resolve_path};
+use crate::model::{AnalysisResult, Issue, IssueKind};
+use crate::snapshot::issue_id;
+use crate::style;
+use std::path::Path;
+
+use super::{CommandContext, exit_code};
+
+pub fn cmd_explain(args: ExplainArgs) -> i32 {
+    let ctx = match CommandContext::new(&args.path, args.lang.as_deref()) {
+        Ok(ctx) => ctx,
+        Err(code) => return code,
+    };
+
+    if let Some(kind) = IssueKind::canonical_name(&args.target) {
+        if let Some(description) = describe_check(kind, &ctx.config) {
+            print_description(&description);
+        }
+        return match &args.file {
+            Some(file) => explain_kind_for_file(&ctx, kind, file),
+            None => exit_code::SUCCESS,
+        };
+    }
+
+    if args.file.is_some() {
+        style::warning("--file only applies when explaining an issue kind; ignoring it.");
+    }
+
+    let result = ctx.analyze();
+    let Some(issue) = find_issue(&result, &ctx.path, &args.target) else {
+        style::error(&format!("No issue or issue kind matches '{}'", args.target));
+        style::hint("Issue IDs are listed by `archmap list issues` and stored in snapshots");
+        style::hint(&format!("Issue kinds: {}", IssueKind::NAMES.join(", ")));
+        return exit_code::USAGE;
+    };
+
+    style::header(&format!("{} {}", issue.kind.name(), args.target));
+    println!("{}", issue.message);
+    if let Some(description) = describe_check(issue.kind.name(), &ctx.config) {
+        print_thresholds(&description);
+    }
+    print_explanation(&explain_issue(
+        issue,
+        &result.modules,
+        &result.dependency_graph,
+        &ctx.config,
+        default_fs(),
+        &ctx.path,
+    ));
+    exit_code::SUCCESS
+}
+
+/// Find an issue by its stable ID, or by an unambiguous prefix of one.
+fn find_issue<'a>(result: &'a AnalysisResult, root: &Path, id: &str) -> Option<&'a Issue> {
+    let mut matches = result
+        .issues
+        .iter()
+        .filter(|issue| issue_id(issue, root).starts_with(id));
+    let first = matches.next()?;
+    matches.next().is_none().then_some(first)
+}
+
+fn explain_kind_for_file(ctx: &CommandContext, kind: &str, file: &Path) -> i32 {
+    let target = if file.is_absolute() {
+        file.to_path_buf()
+    } else {
+        ctx.path.join(file)
+    };
+    let Ok(target) = resolve_path(&target) else {
+        style::error(&format!("Could not find file: {}", style::path(file)));
+        return exit_code::USAGE;
+    };
+
+    let result = ctx.analyze();
+    let Some(module) = result.module(&target) else {
+        style::error(&format!("Not an analyzed module: {}", style::path(file)));
+        return exit_code::USAGE;
+    };
+
+    let issues: Vec<&Issue> = result
+        .issues_for(&target)
+        .filter(|i| i.kind.name() == kind)
+        .collect();
+
+    if issues.is_empty() {
+        style::success(&format!("No {} issue for {}", kind, style::path(file)));
+        // Cohesion is scored for every module, so show why it passed
+        if kind == "LowCohesion" {
+            print_explanation(&crate::analysis::explain_low_cohesion(
+                module,
+                &result.modules,
+                &ctx.config,
+                default_fs(),
+                &ctx.path,
+            ));
+        }
+        return exit_code::SUCCESS;
+    }
+
+    for issue in issues {
+        println!();
+        style::header(&format!(
+            "{} {}",
+            issue.kind.name(),
+            issue_id(issue, &ctx.path)
+        ));
+        println!("{}", issue.message);
+        print_explanation(&explain_issue(
+            issue,
+            &result.modules,
+            &result.dependency_graph,
+            &ctx.config,
+            default_fs(),
+            &ctx.path,
+        ));
+    }
+    exit_code::SUCCESS
+}
+
+fn print_description(description: &CheckDescription) {
+    style::header(description.kind);
+    println!("{}", description.definition);
+    print_thresholds(description);
+    style::section("Tuning");
+    println!("{}", description.guidance);
+}
+
+fn print_thresholds(description: &CheckDescription) {
+    if description.thresholds.is_empty() {
+        return;
+    }
+    style::section("Thresholds in effect");
+    for (key, value) in &description.thresholds {
+        println!("{}", style::metric(key, value));
+    }
+}
+
+fn print_explanation(explanation: &Explanation) {
+    style::section(&format!("Inputs for {}", explanation.subject));
+    for input in &explanation.inputs {
+        println!("{}", style::metric(&input.label, &input.value));
+        for detail in &input.details {
+            println!("      - {}", detail);
+        }
+    }
+    if !explanation.verdict.is_empty() {
+        println!("\n  {}", explanation.verdict);
+    }
+}
@@ -1,24 +1,75 @@
-use crate::cli::GraphArgs;
+use crate::analysis::PathFilter;
+use crate::cli::{GraphArgs, GraphFormat};
 use crate::fs::{FileSystem, default_fs};
+use crate::graph::{GraphData, PhysicsOverrides};
+use crate::model::IssueSeverity;
 use crate::style;
+use std::path::Path;
 
-use super::CommandContext;
+use super::watch::{debounce_period, poll_interval};
+use super::{CommandContext, exit_code, output_options, report_diagnostics};
 
+/// Serve or export the dependency graph. Exits with `SUCCESS` once the export
+/// is written or the server shuts down, `USAGE` when neither `--serve`,
+/// `--watch` nor `--export` is given or `--export` is combined with serving, and
+/// `FAILURE` when the analysis can't be serialized, the file can't be written or
+/// the server can't run.
 pub fn cmd_graph(args: GraphArgs) -> i32 {
+    let html = args.format == GraphFormat::Html;
+    let serving = args.serve || args.watch;
+    if html && serving && args.export.is_some() {
+        style::error(
+            "--export writes a file and exits; it can't be combined with --serve or --watch",
+        );
+        return exit_code::USAGE;
+    }
+    if html && !serving && args.export.is_none() {
+        style::error(
+            "Use --serve to start the visualization server, or --export to save static HTML",
+        );
+        return exit_code::USAGE;
+    }
+
     let ctx = match CommandContext::new(&args.path, args.lang.as_deref()) {
         Ok(ctx) => ctx,
         Err(code) => return code,
     };
 
     // Run analysis
-    let result = crate::analysis::analyze(&ctx.path, &ctx.config, &ctx.registry, &[]);
+    let filter = PathFilter::default().with_tags(args.filter_tag.clone());
+    let result =
+        crate::analysis::analyze_filtered(&ctx.path, &ctx.config, &ctx.registry, &[], &filter);
+    report_diagnostics(&result.diagnostics);
+    // The page shows a placeholder instead; with --watch, files may still appear
+    ctx.nothing_found(&result.modules);
 
     // Build graph data
-    let graph_data = crate::graph::GraphData::from_analysis(&result, &ctx.path);
+    let graph_data = GraphData::from_result(&result, &ctx.path);
+    let min_severity =
+        output_options(args.min_severity, &ctx.config, IssueSeverity::Warn).min_severity;
+
+    if args.format == GraphFormat::Matrix {
+        if args.serve || args.watch {
+            style::warning("--format matrix writes a CSV file; ignoring --serve and --watch.");
+        }
+        return export_matrix(&graph_data, args.export.as_deref());
+    }
 
-    if args.serve || args.watch {
+    let physics = PhysicsOverrides {
+        charge: args.charge,
+        link_distance: args.link_distance,
+        collision_radius: args.collision_radius,
+    };
+
+    if serving {
         // Start web server
-        let rt = tokio::runtime::Runtime::new().expect("Failed to create tokio runtime");
+        let rt = match tokio::runtime::Runtime::new() {
+            Ok(rt) => rt,
+            Err(e) => {
+                style::error(&format!("Failed to start the server runtime: {}", e));
+                return exit_code::FAILURE;
+            }
+        };
 
         if args.watch {
             // Watch mode with live updates
@@ -26,34 +77,83 @@ pub fn cmd_graph(args: GraphArgs) -> i32 {
                 path: ctx.path.clone(),
                 config: ctx.config,
                 registry: ctx.registry,
+                filter,
+                poll: args.poll,
+                interval: poll_interval(args.watch_interval),
+                debounce: debounce_period(args.watch_debounce),
             };
             if let Err(e) = rt.block_on(crate::graph::serve_with_watch(
-                graph_data, args.port, args.open, watch_ctx,
+                graph_data,
+                args.port,
+                args.open,
+                min_severity,
+                physics,
+                watch_ctx,
             )) {
                 style::error(&format!("Server failed: {}", e));
-                return 1;
+                return exit_code::FAILURE;
             }
         } else {
             // Static serve mode
-            if let Err(e) = rt.block_on(crate::graph::serve(graph_data, args.port, args.open)) {
+            if let Err(e) = rt.block_on(crate::graph::serve(
+                graph_data,
+                args.port,
+                args.open,
+                min_severity,
+                physics,
+            )) {
                 style::error(&format!("Server failed: {}", e));
-                return 1;
+                return exit_code::FAILURE;
             }
         }
     } else if let Some(export_path) = args.export {
         // Export static HTML
-        let html = crate::graph::generate_static_html(&graph_data);
-        if let Err(e) = default_fs().write(&export_path, &html) {
+        let page = match crate::graph::generate_static_html(
+            &graph_data
+                .with_min_severity(min_severity)
+                .with_physics(physics),
+        ) {
+            Ok(page) => page,
+            Err(e) => {
+                style::error(&format!("Failed to serialize graph data: {}", e));
+                return exit_code::FAILURE;
+            }
+        };
+        if let Err(e) = default_fs().write(&export_path, &page) {
             style::error(&format!("Failed to write export file: {}", e));
-            return 1;
+            return exit_code::FAILURE;
         }
         style::success(&format!("Graph exported to: {}", style::path(&export_path)));
-    } else {
-        style::error(
-            "Use --serve to start the visualization server, or --export to save static HTML",
-        );
-        return 1;
     }
 
-    0
+    exit_code::SUCCESS
+}
+
+/// Modules above which the N² matrix gets unwieldy for spreadsheets
+const LARGE_MATRIX_MODULES: usize = 300;
+
+fn export_matrix(graph_data: &GraphData, export_path: Option<&Path>) -> i32 {
+    let modules = graph_data.nodes.len();
+    if modules > LARGE_MATRIX_MODULES {
+        style::warning(&format!(
+            "{} modules make a {}x{} matrix ({} cells); consider --filter-tag to narrow it",
+            modules,
+            modules,
+            modules,
+            modules * modules
+        ));
+    }
+
+    let csv = graph_data.to_matrix_csv();
+    match export_path {
+        Some(path) => {
+            if let Err(e) = default_fs().write(path, &csv) {
+                style::error(&format!("Failed to write export file: {}", e));
+                return exit_code::FAILURE;
+            }
+            style::success(&format!("Matrix exported to: {}", style::path(path)));
+        }
+        None => print!("{}", csv),
+    }
+    exit_code::SUCCESS
 }
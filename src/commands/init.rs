@@ -1,7 +1,22 @@
+use crate::analysis::{detect_issues, drop_generated_issues};
 use crate::cli::InitArgs;
-use crate::config::generate_config_template;
-use crate::fs::{FileSystem, default_fs};
+use crate::config::{
+    Config, ConfigSource, LayerRule, Preset, Thresholds, config_source, generate_config_template,
+    generate_preset_template, layers_toml,
+};
+use crate::fs::{FileSystem, default_fs, resolve_path};
+use crate::model::{AnalysisResult, Boundary, Issue, IssueKind};
+use crate::parser::ParserRegistry;
 use crate::style;
+use std::collections::BTreeSet;
+use std::io::{self, BufRead, Write};
+use std::path::Path;
+
+use super::{exit_code, report_diagnostics};
+
+/// Raising a limit can expose new issues (a longer chain, a newly exempt shared
+/// module), so detection repeats until nothing changes, at most this many times.
+const MAX_CALIBRATION_ROUNDS: usize = 10;
 
 pub fn cmd_init(args: InitArgs) -> i32 {
     cmd_init_with_fs(args, default_fs())
@@ -9,23 +24,472 @@ pub fn cmd_init(args: InitArgs) -> i32 {
 
 pub fn cmd_init_with_fs(args: InitArgs, fs: &dyn FileSystem) -> i32 {
     let config_path = args.path.join(".archmap.toml");
-    if fs.exists(&config_path) {
+    if fs.exists(&config_path) && !args.force {
         style::error(&format!(
             ".archmap.toml already exists at {}",
             style::path(&config_path)
         ));
-        return 1;
+        style::hint("Pass --force to overwrite it");
+        return exit_code::USAGE;
     }
 
-    let template = generate_config_template();
+    // `--preset` seeds the new config instead of the defaults
+    let preset = match config_source() {
+        ConfigSource::Preset(preset) => Some(preset),
+        _ => None,
+    };
+
+    let template = if args.detect || args.interactive {
+        let Ok(root) = resolve_path(&args.path) else {
+            style::error(&format!(
+                "Could not resolve path: {}",
+                style::path(&args.path)
+            ));
+            return exit_code::USAGE;
+        };
+        let base = preset.map_or_else(Config::default, Preset::config);
+        let detected = detect_config(&root, base, fs);
+        let sections = if args.interactive {
+            Sections::prompt(&detected, &mut io::stdin().lock())
+        } else {
+            Sections::ALL
+        };
+        detected.render(sections)
+    } else if let Some(preset) = preset {
+        generate_preset_template(preset)
+    } else {
+        generate_config_template()
+    };
+
     if let Err(e) = fs.write(&config_path, &template) {
         style::error(&format!("Failed to write config file: {}", e));
-        return 1;
+        return exit_code::FAILURE;
     }
 
     style::success(&format!(
         "Created .archmap.toml at {}",
         style::path(&config_path)
     ));
-    0
+    exit_code::SUCCESS
+}
+
+/// A config tailored to the project as it is today
+struct DetectedConfig {
+    languages: Vec<&'static str>,
+    /// Defaults, raised until the current code passes every check
+    thresholds: Thresholds,
+    /// Default patterns plus the high fan-in modules found
+    expected_high_coupling: Vec<String>,
+    /// How many of `expected_high_coupling` were added for this project
+    coupling_suggestions: usize,
+    /// Default boundaries whose indicators appear somewhere in the code
+    boundaries: Vec<Boundary>,
+    /// The preset's layer rules, if one was given
+    layers: Vec<LayerRule>,
+}
+
+/// Which detected sections to write; skipped ones fall back to the defaults
+#[derive(Debug, Clone, Copy)]
+struct Sections {
+    thresholds: bool,
+    expected_high_coupling: bool,
+    boundaries: bool,
+}
+
+impl Sections {
+    const ALL: Sections = Sections {
+        thresholds: true,
+        expected_high_coupling: true,
+        boundaries: true,
+    };
+
+    fn prompt(detected: &DetectedConfig, input: &mut dyn BufRead) -> Self {
+        let boundaries: Vec<&str> = detected
+            .boundaries
+            .iter()
+            .map(|b| b.name.as_str())
+            .collect();
+        Sections {
+            thresholds: confirm(input, "Set thresholds just above the current worst values?"),
+            expected_high_coupling: confirm(
+                input,
+                &format!(
+                    "Mark {} high fan-in module(s) as expected coupling?",
+                    detected.coupling_suggestions
+                ),
+            ),
+            boundaries: confirm(
+                input,
+                &format!(
+                    "Only check the boundaries found in the code ({})?",
+                    if boundaries.is_empty() {
+                        "none".to_string()
+                    } else {
+                        boundaries.join(", ")
+                    }
+                ),
+            ),
+        }
+    }
+}
+
+/// Ask a yes/no question; an empty answer (or end of input) means yes.
+fn confirm(input: &mut dyn BufRead, question: &str) -> bool {
+    print!("{} [Y/n] ", question);
+    let _ = io::stdout().flush();
+    let mut answer = String::new();
+    if input.read_line(&mut answer).unwrap_or(0) == 0 {
+        println!();
+        return true;
+    }
+    !matches!(answer.trim().to_lowercase().as_str(), "n" | "no")
+}
+
+/// Analyze the project with `config` (the built-in defaults or a preset) and
+/// derive a config under which it passes, so later changes can only ratchet the
+/// values down.
+fn detect_config(root: &Path, mut config: Config, fs: &dyn FileSystem) -> DetectedConfig {
+    let result = crate::analysis::analyze(root, &config, &ParserRegistry::new(), &[]);
+    report_diagnostics(&result.diagnostics);
+
+    let languages: BTreeSet<&'static str> = result
+        .modules
+        .iter()
+        .filter_map(|m| language_name(&m.path))
+        .collect();
+
+    config.boundaries.retain(|boundary| {
+        result.modules.iter().any(|module| {
+            fs.read_to_string(&module.path)
+                .is_ok_and(|source| boundary.indicators.iter().any(|i| source.contains(i)))
+        })
+    });
+
+    let defaults = config.expected_high_coupling.len();
+    for _ in 0..MAX_CALIBRATION_ROUNDS {
+        let mut issues = detect_issues(&result.modules, &result.dependency_graph, &config, fs);
+        drop_generated_issues(&mut issues, &result.generated_files);
+        if !calibrate(&mut config, &result, &issues, root) {
+            break;
+        }
+    }
+
+    DetectedConfig {
+        languages: languages.into_iter().collect(),
+        coupling_suggestions: config.expected_high_coupling.len() - defaults,
+        thresholds: config.thresholds,
+        expected_high_coupling: config.expected_high_coupling,
+        boundaries: config.boundaries,
+        layers: config.expectations.layers,
+    }
+}
+
+/// Raise the thresholds (and extend expected coupling) just enough to clear each
+/// issue. Returns false once nothing changes.
+fn calibrate(config: &mut Config, result: &AnalysisResult, issues: &[Issue], root: &Path) -> bool {
+    let before = format!("{:?}", config.thresholds);
+    let patterns = config.expected_high_coupling.len();
+    let t = &mut config.thresholds;
+
+    for issue in issues {
+        let Some(path) = issue.locations.first().map(|l| l.path.as_path()) else {
+            continue;
+        };
+        let lines = result.module(path).map_or(0, |m| m.lines);
+
+        match &issue.kind {
+            IssueKind::GodObject => t.god_object_lines = t.god_object_lines.max(lines + 1),
+            IssueKind::FatModule { .. } => t.fat_module_lines = t.fat_module_lines.max(lines + 1),
+            IssueKind::HighCoupling => {
+                let relative = path.strip_prefix(root).unwrap_or(path);
+                let pattern = format!("**/{}", relative.to_string_lossy());
+                if !config.expected_high_coupling.contains(&pattern) {
+                    config.expected_high_coupling.push(pattern);
+                }
+            }
+            IssueKind::BoundaryViolation { .. } => {
+                let modules: BTreeSet<&Path> =
+                    issue.locations.iter().map(|l| l.path.as_path()).collect();
+                t.boundary_violation_min = t.boundary_violation_min.max(modules.len() + 1);
+            }
+            IssueKind::DeepDependencyChain { depth } => {
+                t.max_dependency_depth = t.max_dependency_depth.max(*depth);
+            }
+            IssueKind::LowCohesion { score } => {
+                // Two decimals, rounded down so the module's own score passes
+                t.min_cohesion = t.min_cohesion.min((score * 100.0).floor() / 100.0);
+            }
+            IssueKind::FatSharedModule { functions, .. } => {
+                t.shared_module_functions = t.shared_module_functions.max(functions + 1);
+            }
+            IssueKind::ScatteredImplementation { file_count, .. } => {
+                t.scattered_impl_files = t.scattered_impl_files.max(file_count + 1);
+            }
+            IssueKind::TooManyImports { imports } => {
+                t.max_imports = t.max_imports.max(*imports);
+            }
+            IssueKind::GodPackage { modules, lines, .. } => {
+                t.god_package_modules = t.god_package_modules.max(*modules);
+                t.god_package_lines = t.god_package_lines.max(*lines);
+            }
+            // No threshold makes a cycle or an empty test acceptable; dead code is opt-in
+            IssueKind::CircularDependency
+            | IssueKind::DeadPublicFunction { .. }
+            | IssueKind::EmptyTest
+            | IssueKind::RoleViolation { .. } => {}
+        }
+    }
+
+    format!("{:?}", config.thresholds) != before || config.expected_high_coupling.len() != patterns
+}
+
+fn language_name(path: &Path) -> Option<&'static str> {
+    match path.extension()?.to_str()? {
+        "rs" => Some("rust"),
+        "ts" | "tsx" | "js" | "jsx" | "mjs" | "cjs" => Some("typescript"),
+        "py" => Some("python"),
+        "scala" => Some("scala"),
+        _ => None,
+    }
+}
+
+impl DetectedConfig {
+    fn render(&self, sections: Sections) -> String {
+        let defaults = Thresholds::default();
+        let mut out = String::from(
+            "# Archmap Configuration\n\
+             # Generated by `archmap init --detect` from the project as it is today.\n\
+             # Thresholds sit just above the current worst values (never below the\n\
+             # defaults), so analysis starts clean: lower them as the code improves.\n",
+        );
+        if !self.languages.is_empty() {
+            out.push_str(&format!(
+                "#\n# Detected languages: {} (restrict analysis with --lang {})\n",
+                self.languages.join(", "),
+                self.languages.join(",")
+            ));
+        }
+
+        // Top-level keys must precede the first table
+        if sections.expected_high_coupling {
+            out.push_str(
+                "\n# Modules where high fan-in is expected: the default patterns, then the\n\
+                 # modules this project already depends on heavily\n",
+            );
+            out.push_str(&format!(
+                "expected_high_coupling = {}\n",
+                string_array(&self.expected_high_coupling)
+            ));
+        }
+
+        if sections.thresholds {
+            let t = &self.thresholds;
+            out.push_str("\n[thresholds]\n");
+            let rows: [(&str, String, String); 13] = [
+                (
+                    "god_object_lines",
+                    t.god_object_lines.to_string(),
+                    defaults.god_object_lines.to_string(),
+                ),
+                (
+                    "coupling_fanin",
+                    t.coupling_fanin.to_string(),
+                    defaults.coupling_fanin.to_string(),
+                ),
+                (
+                    "boundary_violation_min",
+                    t.boundary_violation_min.to_string(),
+                    defaults.boundary_violation_min.to_string(),
+                ),
+                (
+                    "max_dependency_depth",
+                    t.max_dependency_depth.to_string(),
+                    defaults.max_dependency_depth.to_string(),
+                ),
+                (
+                    "min_cohesion",
+                    toml::Value::from(t.min_cohesion).to_string(),
+                    toml::Value::from(defaults.min_cohesion).to_string(),
+                ),
+                (
+                    "fat_module_lines",
+                    t.fat_module_lines.to_string(),
+                    defaults.fat_module_lines.to_string(),
+                ),
+                (
+                    "fat_module_private_functions",
+                    t.fat_module_private_functions.to_string(),
+                    defaults.fat_module_private_functions.to_string(),
+                ),
+                (
+                    "fat_module_lines_per_export",
+                    toml::Value::from(t.fat_module_lines_per_export).to_string(),
+                    toml::Value::from(defaults.fat_module_lines_per_export).to_string(),
+                ),
+                (
+                    "shared_module_functions",
+                    t.shared_module_functions.to_string(),
+                    defaults.shared_module_functions.to_string(),
+                ),
+                (
+                    "scattered_impl_files",
+                    t.scattered_impl_files.to_string(),
+                    defaults.scattered_impl_files.to_string(),
+                ),
+                (
+                    "max_imports",
+                    t.max_imports.to_string(),
+                    defaults.max_imports.to_string(),
+                ),
+                (
+                    "god_package_modules",
+                    t.god_package_modules.to_string(),
+                    defaults.god_package_modules.to_string(),
+                ),
+                (
+                    "god_package_lines",
+                    t.god_package_lines.to_string(),
+                    defaults.god_package_lines.to_string(),
+                ),
+            ];
+            for (key, value, default) in rows {
+                if value == default {
+                    out.push_str(&format!("{} = {}\n", key, value));
+                } else {
+                    out.push_str(&format!("{} = {}  # default: {}\n", key, value, default));
+                }
+            }
+            if let Some(percentile) = t.coupling_fanin_percentile {
+                out.push_str(&format!("coupling_fanin_percentile = {:?}\n", percentile));
+            }
+        }
+
+        if sections.boundaries {
+            // An empty table disables the boundaries that never occur
+            out.push_str(
+                "\n# Only the boundaries whose indicators appear in the code\n[boundaries]\n",
+            );
+            for boundary in &self.boundaries {
+                let key = boundary.name.to_lowercase();
+                out.push_str(&format!("\n[boundaries.{}]\n", key));
+                out.push_str(&format!(
+                    "name = {}\n",
+                    toml::Value::from(boundary.name.as_str())
+                ));
+                out.push_str(&format!(
+                    "indicators = {}\n",
+                    string_array(&boundary.indicators)
+                ));
+                out.push_str(&format!(
+                    "suggestion = {}\n",
+                    toml::Value::from(boundary.suggestion.as_str())
+                ));
+                out.push_str(&format!(
+                    "allowed_in = {}\n",
+                    string_array(&boundary.allowed_in)
+                ));
+            }
+        }
+
+        if !self.layers.is_empty() {
+            out.push_str("\n# Layer rules from the preset, checked by `archmap check`\n");
+            out.push_str(&layers_toml(&self.layers));
+        }
+
+        out
+    }
+}
+
+/// A TOML array of strings, one item per line
+fn string_array(items: &[String]) -> String {
+    if items.is_empty() {
+        return "[]".to_string();
+    }
+    let mut out = String::from("[\n");
+    for item in items {
+        out.push_str(&format!("    {},\n", toml::Value::from(item.as_str())));
+    }
+    out.push(']');
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn test_detect_raises_thresholds_to_current_maxima() {
+        let dir = std::env::temp_dir().join(format!("archmap-init-{}", std::process::id()));
+        let src = dir.join("src");
+        fs::create_dir_all(&src).unwrap();
+
+        // A 700-line god object and a module five others import
+        let mut god = String::from("pub struct Engine;\npub trait Run {}\nimpl Engine {}\n");
+        for i in 0..60 {
+            god.push_str(&format!(
+                "pub fn step_{}() {{}}\nfn helper_{}() {{}}\n",
+                i, i
+            ));
+        }
+        god.push_str(&"// filler\n".repeat(700 - god.lines().count()));
+        fs::write(src.join("engine.rs"), &god).unwrap();
+        fs::write(src.join("core.rs"), "pub fn shared() {}\n").unwrap();
+        for name in ["a", "b", "c", "d", "e"] {
+            fs::write(
+                src.join(format!("{}.rs", name)),
+                "use crate::core::shared;\nuse std::fs::read;\n",
+            )
+            .unwrap();
+        }
+
+        let root = dir.canonicalize().unwrap();
+        let args = InitArgs {
+            path: root.clone(),
+            detect: true,
+            interactive: false,
+            force: false,
+        };
+        assert_eq!(cmd_init_with_fs(args, default_fs()), 0);
+
+        let text = fs::read_to_string(root.join(".archmap.toml")).unwrap();
+        let config = Config::load(&root);
+        fs::remove_dir_all(&dir).unwrap();
+        let config = config.unwrap();
+
+        assert!(text.contains("# Detected languages: rust"));
+        assert_eq!(config.thresholds.god_object_lines, 701);
+        assert!(text.contains("god_object_lines = 701  # default: 500"));
+        assert_eq!(config.thresholds.coupling_fanin, 5);
+        assert!(
+            config
+                .expected_high_coupling
+                .contains(&"**/src/core.rs".to_string())
+        );
+        // Only the filesystem indicators occur, and five modules cross it
+        let names: Vec<&str> = config.boundaries.iter().map(|b| b.name.as_str()).collect();
+        assert_eq!(names, ["Filesystem"]);
+        assert_eq!(config.thresholds.boundary_violation_min, 6);
+    }
+
+    #[test]
+    fn test_prompt_defaults_to_yes() {
+        let detected = DetectedConfig {
+            languages: vec!["rust"],
+            thresholds: Thresholds::default(),
+            expected_high_coupling: Vec::new(),
+            coupling_suggestions: 0,
+            boundaries: Vec::new(),
+            layers: Preset::Clean.layers(),
+        };
+        let sections = Sections::prompt(&detected, &mut "n\n\n".as_bytes());
+        assert!(!sections.thresholds);
+        assert!(sections.expected_high_coupling);
+        assert!(sections.boundaries);
+
+        let rendered = detected.render(sections);
+        assert!(!rendered.contains("[thresholds]"));
+        assert!(rendered.contains("[boundaries]\n"));
+        assert!(rendered.contains("[expectations.layers.entities_independent]\n"));
+    }
 }
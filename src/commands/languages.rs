@@ -0,0 +1,12 @@
+use crate::cli::LanguagesArgs;
+
+use super::exit_code;
+
+pub fn cmd_languages(args: LanguagesArgs) -> i32 {
+    let languages = crate::api::languages(args.lang.as_deref().unwrap_or_default());
+    println!(
+        "{}",
+        serde_json::to_string_pretty(&languages).unwrap_or_else(|_| "[]".to_string())
+    );
+    exit_code::SUCCESS
+}
@@ -0,0 +1,348 @@
+use crate::cli::{ListArgs, ListFormat, ListTarget, ModuleSort};
+use crate::fs::resolve_path;
+use crate::model::{AnalysisResult, IssueKind, IssueSeverity, Module, glob_match};
+use crate::snapshot::issue_id;
+use crate::style;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use super::{CommandContext, exit_code, output_options};
+
+pub fn cmd_list(args: ListArgs) -> i32 {
+    let ctx = match CommandContext::new(&args.path, args.lang.as_deref()) {
+        Ok(ctx) => ctx,
+        Err(code) => return code,
+    };
+
+    let result = ctx.analyze();
+    if ctx.nothing_found(&result.modules) {
+        return exit_code::SUCCESS;
+    }
+
+    let listing = match &args.target {
+        ListTarget::Modules { sort, filter } => {
+            module_listing(&result, &ctx.path, *sort, filter.as_deref())
+        }
+        ListTarget::Exports { file } => match find_module(&result, &ctx.path, file) {
+            Ok(module) => exports_listing(module),
+            Err(code) => return code,
+        },
+        ListTarget::Imports { file } => match find_module(&result, &ctx.path, file) {
+            Ok(module) => imports_listing(module, &ctx.path),
+            Err(code) => return code,
+        },
+        ListTarget::Issues { kind, min_severity } => {
+            let kind = match kind {
+                Some(name) => match IssueKind::canonical_name(name) {
+                    Some(kind) => Some(kind),
+                    None => {
+                        style::error(&format!("Unknown issue kind: {}", name));
+                        style::hint(&format!("Known kinds: {}", IssueKind::NAMES.join(", ")));
+                        return exit_code::USAGE;
+                    }
+                },
+                None => None,
+            };
+            let options = output_options(*min_severity, &ctx.config, IssueSeverity::Info);
+            issues_listing(&result, &ctx.path, kind, options.min_severity)
+        }
+    };
+
+    print!("{}", listing.render(args.format));
+    exit_code::SUCCESS
+}
+
+/// Rows for one `list` target. Cells are JSON values so counts stay numbers
+/// in JSON output; `null` cells print as `-`.
+struct Listing {
+    columns: &'static [&'static str],
+    rows: Vec<Vec<Value>>,
+}
+
+impl Listing {
+    fn render(&self, format: ListFormat) -> String {
+        match format {
+            ListFormat::Table => style::table(self.columns, &self.text_rows(true)),
+            ListFormat::Plain => self
+                .text_rows(false)
+                .iter()
+                .map(|row| format!("{}\n", row.join("\t")))
+                .collect(),
+            ListFormat::Json => {
+                let objects: Vec<Value> = self
+                    .rows
+                    .iter()
+                    .map(|row| {
+                        let fields = self
+                            .columns
+                            .iter()
+                            .zip(row)
+                            .map(|(column, cell)| (column.replace('-', "_"), cell.clone()));
+                        Value::Object(fields.collect())
+                    })
+                    .collect();
+                let json = serde_json::to_string_pretty(&objects).unwrap_or_default();
+                format!("{}\n", json)
+            }
+        }
+    }
+
+    /// Cells as text; `grouped` separates thousands in whole numbers for
+    /// the table, which people read, but not for plain output, which scripts do
+    fn text_rows(&self, grouped: bool) -> Vec<Vec<String>> {
+        self.rows
+            .iter()
+            .map(|row| {
+                row.iter()
+                    .map(|cell| match cell {
+                        Value::String(s) => s.clone(),
+                        Value::Null => "-".to_string(),
+                        Value::Number(n) if grouped && !n.is_f64() => style::count(n),
+                        other => other.to_string(),
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+}
+
+fn relative(root: &Path, path: &Path) -> String {
+    path.strip_prefix(root)
+        .unwrap_or(path)
+        .to_string_lossy()
+        .into_owned()
+}
+
+fn module_listing(
+    result: &AnalysisResult,
+    root: &Path,
+    sort: ModuleSort,
+    filter: Option<&str>,
+) -> Listing {
+    let graph = &result.dependency_graph;
+
+    // Issues touching each module, counted once per issue
+    let mut issue_counts: HashMap<&Path, usize> = HashMap::new();
+    for issue in &result.issues {
+        let mut paths: Vec<&Path> = issue.locations.iter().map(|l| l.path.as_path()).collect();
+        paths.sort();
+        paths.dedup();
+        for path in paths {
+            *issue_counts.entry(path).or_default() += 1;
+        }
+    }
+    let issues = |m: &Module| issue_counts.get(m.path.as_path()).copied().unwrap_or(0);
+
+    let mut modules: Vec<&Module> = result
+        .modules
+        .iter()
+        .filter(|m| filter.is_none_or(|pattern| glob_match(pattern, &relative(root, &m.path))))
+        .collect();
+
+    // Counts sort largest first; ties (and `--sort path`) fall back to the path
+    let key = |m: &Module| match sort {
+        ModuleSort::Path => 0,
+        ModuleSort::Lines => m.lines,
+        ModuleSort::Defs => m.definitions.len(),
+        ModuleSort::Exports => m.exports.len(),
+        ModuleSort::FanIn => graph.fan_in(&m.path),
+        ModuleSort::FanOut => graph.fan_out(&m.path),
+        ModuleSort::Issues => issues(m),
+    };
+    modules.sort_by(|a, b| key(b).cmp(&key(a)).then_with(|| a.path.cmp(&b.path)));
+
+    Listing {
+        columns: &[
+            "path", "lines", "defs", "exports", "fan-in", "fan-out", "issues",
+        ],
+        rows: modules
+            .into_iter()
+            .map(|m| {
+                vec![
+                    relative(root, &m.path).into(),
+                    m.lines.into(),
+                    m.definitions.len().into(),
+                    m.exports.len().into(),
+                    graph.fan_in(&m.path).into(),
+                    graph.fan_out(&m.path).into(),
+                    issues(m).into(),
+                ]
+            })
+            .collect(),
+    }
+}
+
+fn exports_listing(module: &Module) -> Listing {
+    Listing {
+        columns: &["name", "kind", "line"],
+        rows: module
+            .exports
+            .iter()
+            .map(|name| {
+                let def = module.definitions.iter().find(|d| name == &d.name);
+                vec![
+                    name.as_str().into(),
+                    def.map(|d| format!("{:?}", d.kind).to_lowercase().into())
+                        .unwrap_or(Value::Null),
+                    def.map(|d| d.line.into()).unwrap_or(Value::Null),
+                ]
+            })
+            .collect(),
+    }
+}
+
+fn imports_listing(module: &Module, root: &Path) -> Listing {
+    let resolved = module
+        .resolved_imports
+        .iter()
+        .map(|path| vec!["resolved".into(), relative(root, path).into()]);
+    let statements = module
+        .imports
+        .iter()
+        .map(|import| vec!["import".into(), import.as_str().into()]);
+
+    Listing {
+        columns: &["kind", "target"],
+        rows: resolved.chain(statements).collect(),
+    }
+}
+
+fn issues_listing(
+    result: &AnalysisResult,
+    root: &Path,
+    kind: Option<&str>,
+    min_severity: IssueSeverity,
+) -> Listing {
+    let location = |issue: &crate::model::Issue| match issue.locations.first() {
+        Some(loc) => match loc.line {
+            Some(line) => format!("{}:{}", relative(root, &loc.path), line),
+            None => relative(root, &loc.path),
+        },
+        None => String::new(),
+    };
+
+    let mut issues: Vec<_> = result
+        .issues_at_least(min_severity)
+        .filter(|i| kind.is_none_or(|k| i.kind.name() == k))
+        .collect();
+    issues.sort_by(|a, b| {
+        b.severity
+            .cmp(&a.severity)
+            .then_with(|| location(a).cmp(&location(b)))
+    });
+
+    Listing {
+        columns: &["id", "severity", "kind", "location", "message"],
+        rows: issues
+            .into_iter()
+            .map(|i| {
+                vec![
+                    issue_id(i, root).into(),
+                    i.severity.to_string().into(),
+                    i.kind.name().into(),
+                    location(i).into(),
+                    i.message.as_str().into(),
+                ]
+            })
+            .collect(),
+    }
+}
+
+/// Resolve a file argument (relative to the project) to its analyzed module.
+fn find_module<'a>(
+    result: &'a AnalysisResult,
+    root: &Path,
+    file: &Path,
+) -> Result<&'a Module, i32> {
+    let target: PathBuf = if file.is_absolute() {
+        file.to_path_buf()
+    } else {
+        root.join(file)
+    };
+
+    let Ok(target) = resolve_path(&target) else {
+        style::error(&format!("Could not find file: {}", style::path(file)));
+        return Err(exit_code::USAGE);
+    };
+
+    result.module(&target).ok_or_else(|| {
+        style::error(&format!("Not an analyzed module: {}", style::path(file)));
+        style::hint("Only files in a supported language are analyzed (see --lang)");
+        exit_code::USAGE
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{Definition, DefinitionKind, Visibility};
+
+    fn module(path: &str, lines: usize, exports: &[&str]) -> Module {
+        let mut module = Module::new(PathBuf::from(path));
+        module.lines = lines;
+        module.exports = exports.iter().map(|&e| e.into()).collect();
+        module
+    }
+
+    fn result(modules: Vec<Module>) -> AnalysisResult {
+        AnalysisResult::new("demo".to_string(), modules, Vec::new())
+    }
+
+    fn paths(listing: &Listing) -> Vec<&str> {
+        listing
+            .rows
+            .iter()
+            .map(|r| r[0].as_str().unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_modules_sort_and_filter() {
+        let result = result(vec![
+            module("/demo/src/b.rs", 40, &["one"]),
+            module("/demo/src/a.rs", 10, &["one", "two", "three"]),
+            module("/demo/tests/c.rs", 90, &[]),
+        ]);
+        let root = Path::new("/demo");
+
+        let by_path = module_listing(&result, root, ModuleSort::Path, None);
+        assert_eq!(paths(&by_path), ["src/a.rs", "src/b.rs", "tests/c.rs"]);
+
+        let by_lines = module_listing(&result, root, ModuleSort::Lines, None);
+        assert_eq!(paths(&by_lines), ["tests/c.rs", "src/b.rs", "src/a.rs"]);
+
+        let by_exports = module_listing(&result, root, ModuleSort::Exports, Some("src/**"));
+        assert_eq!(paths(&by_exports), ["src/a.rs", "src/b.rs"]);
+        assert_eq!(by_exports.rows[0][3], 3);
+    }
+
+    #[test]
+    fn test_exports_listing() {
+        let mut module = module("/demo/src/config.rs", 20, &["Config", "load"]);
+        module.definitions.push(Definition {
+            name: "Config".to_string(),
+            kind: DefinitionKind::Struct,
+            line: 4,
+            visibility: Visibility::Public,
+            signature: None,
+            span: None,
+            compact_signature: None,
+            decorators: Vec::new(),
+        });
+
+        let listing = exports_listing(&module);
+        assert_eq!(
+            listing.text_rows(true),
+            [["Config", "struct", "4"], ["load", "-", "-"]]
+        );
+        assert_eq!(
+            listing.render(ListFormat::Plain),
+            "Config\tstruct\t4\nload\t-\t-\n"
+        );
+
+        let json: Value = serde_json::from_str(&listing.render(ListFormat::Json)).unwrap();
+        assert_eq!(json[0]["line"], 4);
+        assert!(json[1]["kind"].is_null());
+    }
+}
@@ -1,5 +1,7 @@
 use crate::cli::McpArgs;
-use crate::config::Config;
+use crate::config::{Config, ConfigSource, config_source};
+use crate::fs::{FileSystem, WalkOptions, default_fs, resolve_path};
+use crate::model::IssueSeverity;
 use crate::output::{JsonOutput, OutputFormatter};
 use crate::parser::ParserRegistry;
 use rmcp::handler::server::tool::cached_schema_for_type;
@@ -12,20 +14,22 @@ use rmcp::schemars::JsonSchema;
 use rmcp::service::{RequestContext, RoleServer};
 use rmcp::{ErrorData as McpError, ServerHandler, ServiceExt};
 use serde::Deserialize;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 
+use super::{exit_code, report_diagnostics};
+
 pub fn cmd_mcp(args: McpArgs) -> i32 {
     let rt = tokio::runtime::Runtime::new().expect("Failed to create Tokio runtime");
     rt.block_on(run_mcp_server(args))
 }
 
 async fn run_mcp_server(args: McpArgs) -> i32 {
-    let working_dir = match args.path.canonicalize() {
+    let working_dir = match resolve_path(&args.path) {
         Ok(p) => p,
         Err(e) => {
             eprintln!("Failed to resolve working directory: {}", e);
-            return 1;
+            return exit_code::USAGE;
         }
     };
 
@@ -37,17 +41,32 @@ async fn run_mcp_server(args: McpArgs) -> i32 {
         Ok(service) => service,
         Err(e) => {
             eprintln!("MCP server error: {}", e);
-            return 1;
+            return exit_code::FAILURE;
         }
     };
 
     // Keep the server running until the transport closes
     if let Err(e) = running_service.waiting().await {
         eprintln!("MCP server task error: {}", e);
-        return 1;
+        return exit_code::FAILURE;
     }
 
-    0
+    exit_code::SUCCESS
+}
+
+/// Config for a tool call. `--config` / `--no-config` apply as for other
+/// commands; a broken discovered `.archmap.toml` falls back to defaults.
+fn load_config(project_path: &Path) -> Result<Config, String> {
+    let mut config = match config_source() {
+        source @ (ConfigSource::Discover | ConfigSource::Preset(_)) => {
+            source.load(project_path).unwrap_or_default()
+        }
+        source => source.load(project_path).map_err(|e| e.to_string())?,
+    };
+    if let Some((_, threads)) = crate::analysis::threads_override() {
+        config.threads = threads;
+    }
+    Ok(config)
 }
 
 #[derive(Clone)]
@@ -75,29 +94,29 @@ impl ArchmapService {
             None => self.working_dir.as_ref().clone(),
         };
 
-        let target_path = target_path
-            .canonicalize()
-            .map_err(|e| format!("Failed to resolve path: {}", e))?;
+        let target_path =
+            resolve_path(&target_path).map_err(|e| format!("Failed to resolve path: {}", e))?;
 
-        let config = Config::load(&target_path).unwrap_or_default();
+        let config = load_config(&target_path)?;
         let registry = ParserRegistry::new();
 
         let result = crate::analysis::analyze(&target_path, &config, &registry, &[]);
+        report_diagnostics(&result.diagnostics);
 
         let output_format = format.as_deref().unwrap_or("json");
 
+        let options = super::output_options(None, &config, IssueSeverity::Info);
         let mut buffer = Vec::new();
         match output_format {
             "json" => {
-                let formatter = JsonOutput::new(Some(target_path));
+                let formatter = JsonOutput::new(Some(target_path)).with_options(options);
                 formatter
                     .format(&result, &mut buffer)
                     .map_err(|e| format!("Failed to format output: {}", e))?;
             }
             "markdown" => {
-                use crate::model::IssueSeverity;
                 use crate::output::MarkdownOutput;
-                let formatter = MarkdownOutput::new(IssueSeverity::Info, Some(target_path));
+                let formatter = MarkdownOutput::new(options, Some(target_path));
                 formatter
                     .format(&result, &mut buffer)
                     .map_err(|e| format!("Failed to format output: {}", e))?;
@@ -131,30 +150,26 @@ impl ArchmapService {
             None => self.working_dir.as_ref().clone(),
         };
 
-        let target_path = target_path
-            .canonicalize()
-            .map_err(|e| format!("Failed to resolve path: {}", e))?;
+        let target_path =
+            resolve_path(&target_path).map_err(|e| format!("Failed to resolve path: {}", e))?;
 
-        let config = Config::load(&target_path).unwrap_or_default();
+        let config = load_config(&target_path)?;
         let registry = ParserRegistry::new();
 
         // Collect sources
+        let fs = default_fs();
         let mut sources = HashMap::new();
-        let walker = ignore::WalkBuilder::new(&target_path)
-            .hidden(true)
-            .git_ignore(true)
-            .build();
-
-        for entry in walker.flatten() {
-            let file_path = entry.path();
-            if file_path.is_file() && registry.find_parser(file_path).is_some() {
-                if let Ok(content) = std::fs::read_to_string(file_path) {
-                    sources.insert(file_path.to_path_buf(), content);
-                }
+        for file_path in fs.walk(&target_path, &WalkOptions::default()) {
+            if registry.find_parser(&file_path).is_some()
+                && let Ok(content) = fs.read_to_string(&file_path)
+                && crate::analysis::skip_reason(&content, &config).is_none()
+            {
+                sources.insert(file_path, content);
             }
         }
 
         let result = crate::analysis::analyze(&target_path, &config, &registry, &[]);
+        report_diagnostics(&result.diagnostics);
 
         let output_format = match format.as_deref() {
             Some("xml") => AiOutputFormat::Xml,
@@ -167,7 +182,8 @@ impl ArchmapService {
             .with_signatures_only(signatures.unwrap_or(false))
             .with_priority(PriorityStrategy::FanIn)
             .with_format(output_format)
-            .with_sources(sources);
+            .with_sources(sources)
+            .with_options(super::output_options(None, &config, IssueSeverity::Info));
 
         if let Some(t) = tokens {
             formatter = formatter.with_token_budget(t);
@@ -198,9 +214,8 @@ impl ArchmapService {
             None => self.working_dir.as_ref().clone(),
         };
 
-        let project_path = project_path
-            .canonicalize()
-            .map_err(|e| format!("Failed to resolve path: {}", e))?;
+        let project_path =
+            resolve_path(&project_path).map_err(|e| format!("Failed to resolve path: {}", e))?;
 
         let file_path = {
             let p = PathBuf::from(&file);
@@ -211,17 +226,15 @@ impl ArchmapService {
             }
         };
 
-        let file_path = file_path
-            .canonicalize()
-            .map_err(|e| format!("Failed to resolve file: {}", e))?;
+        let file_path =
+            resolve_path(&file_path).map_err(|e| format!("Failed to resolve file: {}", e))?;
 
-        let config = Config::load(&project_path).unwrap_or_default();
+        let config = load_config(&project_path)?;
         let registry = ParserRegistry::new();
 
         let result = crate::analysis::analyze(&project_path, &config, &registry, &[]);
-        let graph = crate::analysis::DependencyGraph::build(&result.modules);
-
-        let impact = crate::analysis::compute_impact(&graph, &file_path, depth)
+        report_diagnostics(&result.diagnostics);
+        let impact = crate::analysis::compute_impact(&result.dependency_graph, &file_path, depth)
             .map_err(|e| format!("{}", e))?;
 
         Ok(crate::analysis::format_impact_json(
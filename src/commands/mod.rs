@@ -1,29 +1,53 @@
 mod ai;
 mod analyze;
+mod check;
+mod config_dump;
 mod diff;
+pub mod exit_code;
+mod explain;
 mod graph;
 mod impact;
 mod init;
+mod languages;
+mod list;
 mod mcp;
+mod remote;
+mod sinks;
 mod snapshot;
+mod tui;
+mod version;
+pub(crate) mod watch;
 
 pub use ai::cmd_ai;
 pub use analyze::cmd_analyze;
+pub use check::cmd_check;
+pub use config_dump::cmd_config_dump;
 pub use diff::cmd_diff;
+pub use explain::cmd_explain;
 pub use graph::cmd_graph;
 pub use impact::cmd_impact;
 pub use init::cmd_init;
+pub use languages::cmd_languages;
+pub use list::cmd_list;
 pub use mcp::cmd_mcp;
 pub use snapshot::cmd_snapshot;
+pub use tui::cmd_tui;
+pub use version::cmd_version;
 
-use crate::config::Config;
+use crate::config::{Config, ConfigSource, config_source};
+use crate::fs::{CachingFileSystem, default_fs, resolve_path};
+use crate::model::{AnalysisResult, Diagnostic, IssueSeverity, Module};
+use crate::output::OutputOptions;
 use crate::parser::ParserRegistry;
 use crate::style;
 use std::path::{Path, PathBuf};
 
 /// Shared context for command execution, reducing boilerplate across commands.
 pub struct CommandContext {
+    /// Project root: the analyzed path, or the common ancestor of several
     pub path: PathBuf,
+    /// Directories to analyze (just `path` unless several were given)
+    pub roots: Vec<PathBuf>,
     pub config: Config,
     pub registry: ParserRegistry,
 }
@@ -32,18 +56,63 @@ impl CommandContext {
     /// Create a new command context by resolving the path, loading config, and setting up parsers.
     /// Returns Err(exit_code) if setup fails.
     pub fn new(path: &Path, lang: Option<&[String]>) -> Result<Self, i32> {
-        let resolved_path = match path.canonicalize() {
-            Ok(p) => p,
-            Err(_) => {
-                style::error(&format!("Could not resolve path: {}", style::path(path)));
-                return Err(1);
-            }
+        Self::with_paths(&[path.to_path_buf()], lang)
+    }
+
+    /// Like [`CommandContext::new`], for several paths analyzed as one project.
+    /// Config comes from their common ancestor, or else the first path with a
+    /// `.archmap.toml`.
+    pub fn with_paths(paths: &[PathBuf], lang: Option<&[String]>) -> Result<Self, i32> {
+        Self::with_config_source(paths, lang, &config_source())
+    }
+
+    /// Like [`CommandContext::with_paths`], loading config from `source` instead
+    /// of the process-wide `--config` / `--no-config` setting. An explicit
+    /// config file that is missing or invalid is an error; a discovered one
+    /// falls back to defaults with a warning.
+    pub fn with_config_source(
+        paths: &[PathBuf],
+        lang: Option<&[String]>,
+        source: &ConfigSource,
+    ) -> Result<Self, i32> {
+        let current_dir = [PathBuf::from(".")];
+        let paths = if paths.is_empty() {
+            &current_dir
+        } else {
+            paths
         };
 
-        let config = Config::load(&resolved_path).unwrap_or_else(|e| {
-            style::warning(&format!("Failed to load config: {}. Using defaults.", e));
-            Config::default()
-        });
+        let mut roots = Vec::with_capacity(paths.len());
+        for path in paths {
+            match resolve_path(path) {
+                Ok(p) => roots.push(p),
+                Err(_) => {
+                    style::error(&format!("Could not resolve path: {}", style::path(path)));
+                    return Err(exit_code::USAGE);
+                }
+            }
+        }
+        let resolved_path = common_ancestor(&roots);
+
+        let mut config = match source {
+            ConfigSource::Discover | ConfigSource::Preset(_) => {
+                let config_dir = config_dir(&resolved_path, &roots);
+                source.load(config_dir).unwrap_or_else(|e| {
+                    style::warning(&format!("Failed to load config: {}. Using defaults.", e));
+                    Config::default()
+                })
+            }
+            source => match source.load(&resolved_path) {
+                Ok(config) => config,
+                Err(e) => {
+                    style::error(&e.to_string());
+                    return Err(exit_code::USAGE);
+                }
+            },
+        };
+        if let Some((_, threads)) = crate::analysis::threads_override() {
+            config.threads = threads;
+        }
 
         let registry = match lang {
             Some(langs) => ParserRegistry::with_languages(langs),
@@ -52,8 +121,159 @@ impl CommandContext {
 
         Ok(Self {
             path: resolved_path,
+            roots,
             config,
             registry,
         })
     }
+
+    /// Analyze the project with its config, printing any diagnostics.
+    pub fn analyze(&self) -> AnalysisResult {
+        let fs = CachingFileSystem::new(default_fs());
+        let result =
+            crate::analysis::analyze_with_fs(&self.path, &self.config, &self.registry, &[], &fs);
+        report_diagnostics(&result.diagnostics);
+        report_cache(&fs);
+        result
+    }
+
+    /// Whether analysis found no source files, telling the user so. Usually
+    /// archmap was pointed at the wrong directory.
+    pub fn nothing_found(&self, modules: &[Module]) -> bool {
+        if !modules.is_empty() {
+            return false;
+        }
+        let mut extensions: Vec<String> = self
+            .registry
+            .supported_extensions()
+            .into_iter()
+            .map(|ext| format!(".{}", ext))
+            .collect();
+        extensions.dedup();
+        style::warning(&format!(
+            "No supported source files found in {}",
+            style::path(&self.path)
+        ));
+        style::hint(&format!(
+            "archmap reads {} files; check the path, --lang and --exclude",
+            extensions.join(", ")
+        ));
+        true
+    }
+}
+
+/// Report options from `--min-severity` if given, else `min_severity` from the
+/// config, else the command's own `default`.
+/// Print what analysis skipped or ignored. Analysis itself never writes to the
+/// terminal, so each command reports these once its analysis returns.
+pub fn report_diagnostics(diagnostics: &[Diagnostic]) {
+    for diagnostic in diagnostics {
+        style::warning(&diagnostic.message);
+    }
+}
+
+/// Print how often a command's file cache spared a read, in verbose output.
+fn report_cache(fs: &CachingFileSystem) {
+    style::verbose(&format!("File cache: {}", fs.stats()));
+}
+
+fn output_options(
+    min_severity: Option<IssueSeverity>,
+    config: &Config,
+    default: IssueSeverity,
+) -> OutputOptions {
+    OutputOptions::new(min_severity.or(config.min_severity).unwrap_or(default))
+}
+
+/// Directory a discovered `.archmap.toml` is read from: the project root, or
+/// else the first analyzed path that has one.
+fn config_dir<'a>(project: &'a PathBuf, roots: &'a [PathBuf]) -> &'a PathBuf {
+    std::iter::once(project)
+        .chain(roots)
+        .find(|dir| dir.join(".archmap.toml").exists())
+        .unwrap_or(project)
+}
+
+/// Deepest directory containing every path (the path itself when there is one).
+fn common_ancestor(paths: &[PathBuf]) -> PathBuf {
+    let Some((first, rest)) = paths.split_first() else {
+        return PathBuf::new();
+    };
+
+    let mut ancestor = first.clone();
+    for path in rest {
+        while !path.starts_with(&ancestor) {
+            if !ancestor.pop() {
+                return ancestor;
+            }
+        }
+    }
+    ancestor
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_common_ancestor() {
+        let paths = [
+            PathBuf::from("/repo/services/api"),
+            PathBuf::from("/repo/services/worker/src"),
+            PathBuf::from("/repo/services/api/handlers"),
+        ];
+        assert_eq!(common_ancestor(&paths), PathBuf::from("/repo/services"));
+        assert_eq!(
+            common_ancestor(&paths[..1]),
+            PathBuf::from("/repo/services/api")
+        );
+    }
+
+    #[test]
+    fn test_config_source_precedence() {
+        let root = std::env::temp_dir().join(format!("archmap-config-{}", std::process::id()));
+        std::fs::create_dir_all(&root).unwrap();
+        std::fs::write(
+            root.join(".archmap.toml"),
+            "[thresholds]\ncoupling_fanin = 11\n",
+        )
+        .unwrap();
+        let shared = root.join("shared.toml");
+        std::fs::write(&shared, "[thresholds]\ncoupling_fanin = 22\n").unwrap();
+        let invalid = root.join("invalid.toml");
+        std::fs::write(&invalid, "[thresholds\n").unwrap();
+
+        let fanin = |source: &ConfigSource| {
+            CommandContext::with_config_source(std::slice::from_ref(&root), None, source)
+                .map(|ctx| ctx.config.thresholds.coupling_fanin)
+        };
+        let discovered = fanin(&ConfigSource::Discover);
+        let explicit = fanin(&ConfigSource::File(shared));
+        let defaults = fanin(&ConfigSource::Defaults);
+        let missing = fanin(&ConfigSource::File(root.join("missing.toml")));
+        let broken = fanin(&ConfigSource::File(invalid.clone()));
+        let missing_error = ConfigSource::File(root.join("missing.toml"))
+            .load(&root)
+            .unwrap_err()
+            .to_string();
+        let broken_error = ConfigSource::File(invalid)
+            .load(&root)
+            .unwrap_err()
+            .to_string();
+
+        // A broken discovered config only warns
+        std::fs::write(root.join(".archmap.toml"), "[thresholds\n").unwrap();
+        let broken_discovered = fanin(&ConfigSource::Discover);
+        std::fs::remove_dir_all(&root).unwrap();
+
+        assert_eq!(discovered, Ok(11));
+        assert_eq!(explicit, Ok(22));
+        assert_eq!(defaults, Ok(5));
+        assert_eq!(missing, Err(exit_code::USAGE));
+        assert_eq!(broken, Err(exit_code::USAGE));
+        assert_eq!(broken_discovered, Ok(5));
+        assert!(missing_error.starts_with("Config file not found: "));
+        assert!(missing_error.ends_with("missing.toml"));
+        assert!(broken_error.starts_with("Failed to parse config file: "));
+    }
 }
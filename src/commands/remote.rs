@@ -0,0 +1,135 @@
+//! `analyze --git`: a shallow clone of a remote repository in a temporary
+//! directory, removed again once the analysis is done.
+
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// A repository cloned for one run. The clone is deleted on drop.
+#[derive(Debug)]
+pub struct Checkout {
+    /// Temporary directory holding the clone
+    dir: PathBuf,
+    /// The clone itself, named after the repository so reports show its name
+    root: PathBuf,
+}
+
+impl Checkout {
+    /// Shallow-clone `url`, at branch or tag `reference` if given, by running
+    /// `git clone --depth 1`.
+    pub fn shallow_clone(url: &str, reference: Option<&str>) -> Result<Self, String> {
+        static CLONES: AtomicUsize = AtomicUsize::new(0);
+        let dir = std::env::temp_dir().join(format!(
+            "archmap-git-{}-{}",
+            std::process::id(),
+            CLONES.fetch_add(1, Ordering::Relaxed)
+        ));
+        std::fs::create_dir_all(&dir)
+            .map_err(|e| format!("Could not create {}: {}", dir.display(), e))?;
+        // Dropped on failure too, which removes the directory again
+        let checkout = Self {
+            root: dir.join(repo_name(url)),
+            dir,
+        };
+
+        let mut git = Command::new("git");
+        git.args(["clone", "--depth", "1", "--quiet"]);
+        if let Some(reference) = reference {
+            git.args(["--branch", reference]);
+        }
+        git.arg("--").arg(url).arg(&checkout.root);
+
+        let output = git.output().map_err(|e| match e.kind() {
+            io::ErrorKind::NotFound => "git is not installed or not on PATH".to_string(),
+            _ => format!("Could not run git: {}", e),
+        })?;
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            return Err(format!("git clone {} failed: {}", url, stderr.trim()));
+        }
+        Ok(checkout)
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.root
+    }
+}
+
+impl Drop for Checkout {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.dir);
+    }
+}
+
+/// Directory name for a clone of `url`: its last path segment without `.git`
+fn repo_name(url: &str) -> String {
+    let name = url
+        .trim_end_matches('/')
+        .rsplit(['/', ':'])
+        .next()
+        .unwrap_or_default();
+    let name = name.strip_suffix(".git").unwrap_or(name);
+    if name.is_empty() {
+        "repo".to_string()
+    } else {
+        name.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn test_repo_name_from_url() {
+        assert_eq!(
+            repo_name("https://github.com/xandwr/archmap.git"),
+            "archmap"
+        );
+        assert_eq!(repo_name("https://github.com/xandwr/archmap/"), "archmap");
+        assert_eq!(repo_name("git@github.com:xandwr/archmap.git"), "archmap");
+        assert_eq!(repo_name("git@host:archmap"), "archmap");
+        assert_eq!(repo_name("/"), "repo");
+    }
+
+    #[test]
+    fn test_clone_checks_out_the_ref_and_cleans_up() {
+        let origin = std::env::temp_dir().join(format!("archmap-origin-{}", std::process::id()));
+        fs::create_dir_all(&origin).unwrap();
+        let git = |args: &[&str]| {
+            Command::new("git")
+                .args(["-c", "user.name=a", "-c", "user.email=a@b"])
+                .args(args)
+                .current_dir(&origin)
+                .output()
+                .unwrap()
+        };
+        git(&["init", "--quiet", "--initial-branch=main"]);
+        fs::write(origin.join("lib.rs"), "pub fn main_branch() {}\n").unwrap();
+        git(&["add", "."]);
+        git(&["commit", "--quiet", "-m", "main"]);
+        git(&["tag", "v1"]);
+        fs::write(origin.join("lib.rs"), "pub fn later() {}\n").unwrap();
+        git(&["commit", "--quiet", "-am", "later"]);
+
+        let url = format!("file://{}", origin.display());
+        let latest = Checkout::shallow_clone(&url, None).unwrap();
+        let tagged = Checkout::shallow_clone(&url, Some("v1")).unwrap();
+        let missing = Checkout::shallow_clone(&url, Some("no-such-branch"));
+
+        let latest_source = fs::read_to_string(latest.path().join("lib.rs")).unwrap();
+        let tagged_source = fs::read_to_string(tagged.path().join("lib.rs")).unwrap();
+        let clone_dir = latest.dir.clone();
+        assert!(latest.path().ends_with(origin.file_name().unwrap()));
+        drop(latest);
+        drop(tagged);
+        fs::remove_dir_all(&origin).unwrap();
+
+        assert_eq!(latest_source, "pub fn later() {}\n");
+        assert_eq!(tagged_source, "pub fn main_branch() {}\n");
+        assert!(!clone_dir.exists());
+        assert!(missing.unwrap_err().contains("no-such-branch"));
+    }
+}
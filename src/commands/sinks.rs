@@ -0,0 +1,254 @@
+//! Where report output goes. Each `--output` file gets the format from
+//! `--format`, or else the one its extension implies; without any `--output`
+//! the report goes to stdout. One analysis can feed several files this way.
+
+use crate::cli::{AiOutputFormat, ImpactFormat, OutputFormat};
+use crate::fs::FileSystem;
+use crate::style;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+use super::exit_code;
+
+/// Report formats a file extension can select
+pub trait OutputKind: Copy + PartialEq {
+    /// The format implied by a lowercase extension, without the dot
+    fn from_extension(extension: &str) -> Option<Self>;
+
+    /// Name as accepted by `--format`
+    fn name(self) -> &'static str;
+
+    /// File extension, without the dot
+    fn extension(self) -> &'static str;
+}
+
+impl OutputKind for OutputFormat {
+    fn from_extension(extension: &str) -> Option<Self> {
+        match extension {
+            "md" | "markdown" => Some(OutputFormat::Markdown),
+            "json" => Some(OutputFormat::Json),
+            _ => None,
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            OutputFormat::Markdown => "markdown",
+            OutputFormat::Json => "json",
+        }
+    }
+
+    fn extension(self) -> &'static str {
+        match self {
+            OutputFormat::Markdown => "md",
+            OutputFormat::Json => "json",
+        }
+    }
+}
+
+impl OutputKind for ImpactFormat {
+    fn from_extension(extension: &str) -> Option<Self> {
+        match extension {
+            "md" | "markdown" => Some(ImpactFormat::Markdown),
+            "json" => Some(ImpactFormat::Json),
+            "dot" | "gv" => Some(ImpactFormat::Dot),
+            _ => None,
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            ImpactFormat::Markdown => "markdown",
+            ImpactFormat::Json => "json",
+            ImpactFormat::Dot => "dot",
+        }
+    }
+
+    fn extension(self) -> &'static str {
+        match self {
+            ImpactFormat::Markdown => "md",
+            ImpactFormat::Json => "json",
+            ImpactFormat::Dot => "dot",
+        }
+    }
+}
+
+impl OutputKind for AiOutputFormat {
+    fn from_extension(extension: &str) -> Option<Self> {
+        match extension {
+            "md" | "markdown" => Some(AiOutputFormat::Markdown),
+            "json" => Some(AiOutputFormat::Json),
+            "xml" => Some(AiOutputFormat::Xml),
+            _ => None,
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            AiOutputFormat::Markdown => "markdown",
+            AiOutputFormat::Json => "json",
+            AiOutputFormat::Xml => "xml",
+        }
+    }
+
+    fn extension(self) -> &'static str {
+        match self {
+            AiOutputFormat::Markdown => "md",
+            AiOutputFormat::Json => "json",
+            AiOutputFormat::Xml => "xml",
+        }
+    }
+}
+
+/// One destination and the format written to it
+#[derive(Debug, Clone, PartialEq)]
+pub struct Sink<F> {
+    /// `None` for stdout
+    pub path: Option<PathBuf>,
+    pub format: F,
+}
+
+/// Pair every `--output` path with its format. An explicit `--format` applies to
+/// all of them, with a warning for paths whose extension implies another format;
+/// otherwise the extension decides, falling back to `default`.
+pub fn sinks<F: OutputKind>(outputs: &[PathBuf], format: Option<F>, default: F) -> Vec<Sink<F>> {
+    if outputs.is_empty() {
+        return vec![Sink {
+            path: None,
+            format: format.unwrap_or(default),
+        }];
+    }
+
+    outputs
+        .iter()
+        .map(|path| {
+            let implied = path
+                .extension()
+                .and_then(|e| e.to_str())
+                .and_then(|e| F::from_extension(&e.to_lowercase()));
+            let format = match (format, implied) {
+                (Some(explicit), Some(implied)) if explicit != implied => {
+                    style::warning(&format!(
+                        "Writing {} to {}, whose extension suggests {}",
+                        explicit.name(),
+                        style::path(path),
+                        implied.name()
+                    ));
+                    explicit
+                }
+                (Some(explicit), _) => explicit,
+                (None, implied) => implied.unwrap_or(default),
+            };
+            Sink {
+                path: Some(path.clone()),
+                format,
+            }
+        })
+        .collect()
+}
+
+impl<F> Sink<F> {
+    pub fn is_stdout(&self) -> bool {
+        self.path.is_none()
+    }
+
+    /// Open the destination, reporting failures.
+    pub fn open(&self, fs: &dyn FileSystem) -> Result<Box<dyn Write + Send>, i32> {
+        match &self.path {
+            Some(path) => fs.create_file(path).map_err(|e| {
+                style::error(&format!(
+                    "Could not create output file {}: {}",
+                    style::path(path),
+                    e
+                ));
+                exit_code::FAILURE
+            }),
+            None => Ok(Box::new(io::stdout())),
+        }
+    }
+
+    /// Write a finished report, ending it with a newline. Markdown headed for the
+    /// terminal is rendered when `render` is set.
+    pub fn write(&self, fs: &dyn FileSystem, text: &str, render: bool) -> Result<(), i32> {
+        let mut output = self.open(fs)?;
+        let result = if render && self.is_stdout() {
+            style::render_markdown(text, &mut output)
+        } else if text.ends_with('\n') {
+            write!(output, "{}", text)
+        } else {
+            writeln!(output, "{}", text)
+        };
+
+        result.map_err(|e| {
+            let target = self.path.as_deref().unwrap_or(Path::new("stdout"));
+            style::error(&format!(
+                "Failed to write output to {}: {}",
+                style::path(target),
+                e
+            ));
+            exit_code::FAILURE
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::logging::capture::capture;
+    use tracing::Level;
+
+    fn paths(names: &[&str]) -> Vec<PathBuf> {
+        names.iter().map(PathBuf::from).collect()
+    }
+
+    #[test]
+    fn test_format_follows_extension() {
+        let sinks = sinks(
+            &paths(&["report.json", "report.MD", "notes.txt"]),
+            None,
+            OutputFormat::Markdown,
+        );
+        let formats: Vec<_> = sinks.iter().map(|s| s.format).collect();
+        assert_eq!(
+            formats,
+            [
+                OutputFormat::Json,
+                OutputFormat::Markdown,
+                OutputFormat::Markdown
+            ]
+        );
+
+        let ai = super::sinks(&paths(&["context.xml"]), None, AiOutputFormat::Markdown);
+        assert!(matches!(ai[0].format, AiOutputFormat::Xml));
+        let impact = super::sinks(&paths(&["blast.gv"]), None, ImpactFormat::Markdown);
+        assert_eq!(impact[0].format, ImpactFormat::Dot);
+
+        let stdout = super::sinks(&[], None, OutputFormat::Json);
+        assert_eq!(
+            stdout,
+            [Sink {
+                path: None,
+                format: OutputFormat::Json
+            }]
+        );
+    }
+
+    #[test]
+    fn test_explicit_format_wins_with_a_warning() {
+        let mut result = Vec::new();
+        let lines = capture(Level::WARN, || {
+            result = sinks(
+                &paths(&["report.json", "report.txt"]),
+                Some(OutputFormat::Markdown),
+                OutputFormat::Markdown,
+            );
+        });
+
+        assert!(result.iter().all(|s| s.format == OutputFormat::Markdown));
+        assert_eq!(lines.len(), 1);
+        let message = lines[0]["message"].as_str().unwrap();
+        assert!(message.starts_with("Writing markdown to"));
+        assert!(message.contains("report.json"));
+        assert!(message.ends_with("suggests json"));
+    }
+}
@@ -1,41 +1,149 @@
-use crate::cli::SnapshotArgs;
+use crate::analysis::PathFilter;
+use crate::cli::{SnapshotArgs, SummaryFormat};
+use crate::config::Config;
+use crate::fs::default_fs;
+use crate::snapshot::Snapshot;
 use crate::style;
+use std::path::Path;
 
-use super::CommandContext;
+use super::watch::{Watcher, debounce_period, poll_interval};
+use super::{CommandContext, exit_code, report_diagnostics};
 
 pub fn cmd_snapshot(args: SnapshotArgs) -> i32 {
-    let ctx = match CommandContext::new(&args.path, args.lang.as_deref()) {
+    let ctx = match CommandContext::with_paths(&args.paths, args.lang.as_deref()) {
         Ok(ctx) => ctx,
         Err(code) => return code,
     };
 
+    if !args.watch {
+        return save(&ctx, &ctx.config, &args);
+    }
+
+    style::status(&format!(
+        "Watching {} for changes (Ctrl+C to stop)...",
+        style::path(&ctx.path)
+    ));
+    println!();
+    let mut config = ctx.config.clone();
+    config.threads = crate::analysis::thread_count(&config, true);
+    let watcher = Watcher::new(&ctx, &[], default_fs())
+        .poll_only(args.poll)
+        .poll_every(poll_interval(args.watch_interval))
+        .debounce_for(debounce_period(args.watch_debounce));
+    let _ = save(&ctx, &config, &args);
+    watcher.run(|_| {
+        let _ = save(&ctx, &config, &args);
+        println!();
+    })
+}
+
+/// Analyze the project, save the snapshot to `--save` and print the summary.
+fn save(ctx: &CommandContext, config: &Config, args: &SnapshotArgs) -> i32 {
     // Run analysis
-    let result = crate::analysis::analyze(&ctx.path, &ctx.config, &ctx.registry, &[]);
+    let result = crate::analysis::analyze_paths(
+        &ctx.path,
+        &ctx.roots,
+        config,
+        &ctx.registry,
+        &[],
+        &PathFilter::default(),
+    );
+    report_diagnostics(&result.diagnostics);
+    // An empty snapshot would make a useless baseline, so nothing is saved
+    if ctx.nothing_found(&result.modules) {
+        return exit_code::SUCCESS;
+    }
 
     // Create snapshot
-    let snapshot = crate::snapshot::Snapshot::from_analysis(&result, &ctx.path);
+    let snapshot = Snapshot::from_analysis(&result, &ctx.path);
 
     // Use the save path from args
     let output_path = &args.save;
 
     // Save snapshot
     if let Err(e) = crate::snapshot::save_snapshot(&snapshot, output_path) {
-        style::error(&format!("Failed to save snapshot: {}", e));
-        return 1;
+        style::error(&e.to_string());
+        return exit_code::FAILURE;
+    }
+
+    if args.summary_format == SummaryFormat::Json {
+        // Stdout carries only the JSON object (which names the saved file) so CI can capture it
+        println!("{}", summary_json(&snapshot, output_path));
+        return exit_code::SUCCESS;
     }
 
     style::success(&format!("Snapshot saved to: {}", style::path(output_path)));
     style::section("Summary");
     println!(
         "{}",
-        style::metric("Modules", snapshot.metrics.total_modules)
+        style::metric("Modules", style::count(snapshot.metrics.total_modules))
+    );
+    println!(
+        "{}",
+        style::metric("Lines", style::count(snapshot.metrics.total_lines))
     );
-    println!("{}", style::metric("Lines", snapshot.metrics.total_lines));
     println!(
         "{}",
-        style::metric("Dependencies", snapshot.metrics.total_dependencies)
+        style::metric(
+            "Dependencies",
+            style::count(snapshot.metrics.total_dependencies)
+        )
     );
-    println!("{}", style::metric("Issues", snapshot.issues.len()));
+    println!(
+        "{}",
+        style::metric("Issues", style::count(snapshot.issues.len()))
+    );
+
+    exit_code::SUCCESS
+}
 
-    0
+/// Post-save metrics for `--summary-format json`
+fn summary_json(snapshot: &Snapshot, path: &Path) -> String {
+    let summary = serde_json::json!({
+        "snapshot": path.display().to_string(),
+        "modules": snapshot.metrics.total_modules,
+        "lines": snapshot.metrics.total_lines,
+        "dependencies": snapshot.metrics.total_dependencies,
+        "cycles": snapshot.metrics.cycle_count,
+        "issues": snapshot.issues.len(),
+        "issue_counts": snapshot.metrics.issue_counts,
+    });
+    serde_json::to_string_pretty(&summary).unwrap_or_else(|_| "{}".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{AnalysisResult, Issue, Module};
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_summary_json_reports_metrics() {
+        let path = PathBuf::from("/demo/src/lib.rs");
+        let mut module = Module::new(path.clone());
+        module.lines = 42;
+        let result = AnalysisResult::new(
+            "demo".to_string(),
+            vec![module],
+            vec![
+                Issue::high_coupling(path.clone(), 7),
+                Issue::low_cohesion(path.clone(), 0.2, 1, 4),
+                Issue::low_cohesion(path, 0.25, 1, 3),
+            ],
+        );
+        let snapshot = Snapshot::from_analysis(&result, Path::new("/demo"));
+
+        let summary: serde_json::Value =
+            serde_json::from_str(&summary_json(&snapshot, Path::new("baseline.json"))).unwrap();
+        assert_eq!(summary["snapshot"], "baseline.json");
+        assert_eq!(summary["modules"], 1);
+        assert_eq!(summary["lines"], 42);
+        assert_eq!(summary["dependencies"], 0);
+        assert_eq!(summary["issues"], 3);
+        // Kinds carrying data are counted under their name alone
+        assert_eq!(
+            summary["issue_counts"],
+            serde_json::json!({ "HighCoupling": 1, "LowCohesion": 2 })
+        );
+    }
 }
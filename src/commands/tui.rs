@@ -0,0 +1,32 @@
+use crate::cli::TuiArgs;
+use crate::style;
+
+#[cfg(feature = "tui")]
+pub fn cmd_tui(args: TuiArgs) -> i32 {
+    use super::{CommandContext, exit_code, output_options};
+    use crate::model::IssueSeverity;
+
+    let ctx = match CommandContext::new(&args.path, args.lang.as_deref()) {
+        Ok(ctx) => ctx,
+        Err(code) => return code,
+    };
+
+    let result = ctx.analyze();
+    if ctx.nothing_found(&result.modules) {
+        return exit_code::SUCCESS;
+    }
+
+    let options = output_options(args.min_severity, &ctx.config, IssueSeverity::Info);
+    if let Err(e) = crate::tui::run(&result, &ctx.path, options.min_severity) {
+        style::error(&format!("Terminal UI failed: {}", e));
+        return exit_code::FAILURE;
+    }
+    exit_code::SUCCESS
+}
+
+#[cfg(not(feature = "tui"))]
+pub fn cmd_tui(_args: TuiArgs) -> i32 {
+    style::error("archmap was built without terminal UI support");
+    style::hint("Rebuild with `cargo install archmap --features tui`");
+    super::exit_code::USAGE
+}
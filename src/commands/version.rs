@@ -0,0 +1,50 @@
+use crate::build_info::BuildInfo;
+use crate::cli::{SummaryFormat, VersionArgs};
+
+use super::exit_code;
+
+pub fn cmd_version(args: VersionArgs) -> i32 {
+    let info = BuildInfo::current();
+    match args.format {
+        SummaryFormat::Text => println!("{}", one_line(&info)),
+        SummaryFormat::Json => println!(
+            "{}",
+            serde_json::to_string_pretty(&info).unwrap_or_else(|_| "{}".to_string())
+        ),
+    }
+    exit_code::SUCCESS
+}
+
+/// e.g. `archmap 1.0.2 (3f9c2a1b7d04, features: tui)`
+fn one_line(info: &BuildInfo) -> String {
+    let mut details = vec![info.commit.to_string()];
+    if !info.features.is_empty() {
+        details.push(format!("features: {}", info.features.join(", ")));
+    }
+    format!("archmap {} ({})", info.version, details.join(", "))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_build_info_json_keys() {
+        let info = BuildInfo::current();
+        let json = serde_json::to_value(&info).unwrap();
+        for key in [
+            "version",
+            "commit",
+            "features",
+            "extensions",
+            "snapshot_schema",
+            "graph_schema",
+        ] {
+            assert!(json.get(key).is_some(), "missing {}", key);
+        }
+        assert_eq!(json["version"], env!("CARGO_PKG_VERSION"));
+        assert!(!info.commit.is_empty());
+        assert!(info.extensions.iter().any(|ext| ext == "rs"));
+        assert!(one_line(&info).starts_with(&format!("archmap {} (", info.version)));
+    }
+}
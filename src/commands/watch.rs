@@ -0,0 +1,583 @@
+//! File watcher shared by the `--watch` modes of `analyze`, `ai`, `snapshot`
+//! and `graph`. Only files a parser handles are watched, so rewriting a report
+//! inside the project doesn't trigger another run.
+//!
+//! Changes arrive as filesystem events through `notify`. With `--poll`, or
+//! when events can't be watched (a filesystem without inotify or FSEvents,
+//! or too many directories for the watch limit), every file is rescanned
+//! each interval instead.
+//!
+//! Changes are debounced: a burst of saves (an editor writing a swap file, a
+//! formatter touching twenty files, a branch switch) is merged into one net
+//! change set that is handed on once no change has been seen for the quiet
+//! period.
+
+use crate::fs::{FileSystem, WalkOptions, is_excluded};
+use crate::parser::ParserRegistry;
+use crate::style;
+use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher as _};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{Receiver, RecvTimeoutError, channel};
+use std::time::{Duration, Instant, SystemTime};
+
+use super::CommandContext;
+
+/// How often the watched files are rescanned when polling, and how often an
+/// idle event watcher wakes up, without `--watch-interval`
+const DEFAULT_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// How long the files must stay unchanged before a change set is handed on
+/// without `--watch-debounce`
+const DEFAULT_DEBOUNCE: Duration = Duration::from_millis(300);
+
+/// The `--watch-interval` period, or the default of one second
+pub fn poll_interval(millis: Option<u64>) -> Duration {
+    millis.map_or(DEFAULT_POLL_INTERVAL, Duration::from_millis)
+}
+
+/// The `--watch-debounce` quiet period, or the default of 300 ms
+pub fn debounce_period(millis: Option<u64>) -> Duration {
+    millis.map_or(DEFAULT_DEBOUNCE, Duration::from_millis)
+}
+
+/// A source file that appeared, changed or disappeared between two scans
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Change {
+    Added(PathBuf),
+    Modified(PathBuf),
+    Deleted(PathBuf),
+}
+
+impl Change {
+    pub fn path(&self) -> &Path {
+        match self {
+            Change::Added(path) | Change::Modified(path) | Change::Deleted(path) => path,
+        }
+    }
+}
+
+/// The net effect of `earlier` followed by `later` on the same file, or `None`
+/// when they cancel out (a file created and removed again).
+fn coalesce(earlier: Change, later: Change) -> Option<Change> {
+    match (earlier, later) {
+        (Change::Added(_), Change::Deleted(_)) => None,
+        (Change::Added(path), _) => Some(Change::Added(path)),
+        // Removed and written back, as editors saving through a rename do
+        (Change::Deleted(path), Change::Added(_) | Change::Modified(_)) => {
+            Some(Change::Modified(path))
+        }
+        (_, later) => Some(later),
+    }
+}
+
+/// Changes collected until none has arrived for a quiet period, merged per
+/// file into their net effect.
+#[derive(Debug)]
+pub struct Debouncer {
+    quiet: Duration,
+    pending: BTreeMap<PathBuf, Change>,
+    last_change: Option<Instant>,
+}
+
+impl Debouncer {
+    pub fn new(quiet: Duration) -> Self {
+        Self {
+            quiet,
+            pending: BTreeMap::new(),
+            last_change: None,
+        }
+    }
+
+    /// Record a change seen at `at`, which restarts the quiet period.
+    pub fn push(&mut self, change: Change, at: Instant) {
+        self.last_change = Some(at);
+        let path = change.path().to_path_buf();
+        let merged = match self.pending.remove(&path) {
+            Some(earlier) => coalesce(earlier, change),
+            None => Some(change),
+        };
+        if let Some(merged) = merged {
+            self.pending.insert(path, merged);
+        }
+    }
+
+    /// Whether changes are waiting for the quiet period to pass
+    pub fn is_waiting(&self) -> bool {
+        self.last_change.is_some()
+    }
+
+    /// The merged changes, sorted by kind and then path, once the quiet
+    /// period has passed since the last one. A burst that cancelled itself
+    /// out yields nothing.
+    pub fn ready(&mut self, now: Instant) -> Option<Vec<Change>> {
+        let last = self.last_change?;
+        if now.duration_since(last) < self.quiet {
+            return None;
+        }
+        self.last_change = None;
+        let merged: BTreeSet<Change> = std::mem::take(&mut self.pending).into_values().collect();
+        (!merged.is_empty()).then(|| merged.into_iter().collect())
+    }
+}
+
+/// Where changes are noticed
+enum Backend {
+    /// Filesystem events; the watcher stops when dropped
+    Events {
+        _watcher: RecommendedWatcher,
+        events: Receiver<notify::Result<Event>>,
+    },
+    /// A rescan every interval
+    Poll,
+}
+
+/// Source files under the project roots and their modification times
+pub struct Watcher<'a> {
+    /// Project root, which changed paths are shown relative to
+    root: &'a Path,
+    roots: &'a [PathBuf],
+    registry: &'a ParserRegistry,
+    options: WalkOptions,
+    fs: &'a dyn FileSystem,
+    files: HashMap<PathBuf, SystemTime>,
+    interval: Duration,
+    poll: bool,
+    backend: Backend,
+    debouncer: Debouncer,
+}
+
+impl<'a> Watcher<'a> {
+    /// Start watching from the files as they are now. `excludes` are directory
+    /// names skipped the same way analysis skips them; `.gitignore` and hidden
+    /// files are honored too.
+    pub fn new(ctx: &'a CommandContext, excludes: &'a [String], fs: &'a dyn FileSystem) -> Self {
+        let options = WalkOptions {
+            exclude: excludes.to_vec(),
+            follow_links: crate::analysis::follow_symlinks(&ctx.config),
+        };
+        Self::for_roots(&ctx.path, &ctx.roots, &ctx.registry, options, fs)
+    }
+
+    /// Watch the files under `roots` that a parser in `registry` handles,
+    /// walked with `options`. Changes are shown relative to `root`.
+    pub fn for_roots(
+        root: &'a Path,
+        roots: &'a [PathBuf],
+        registry: &'a ParserRegistry,
+        options: WalkOptions,
+        fs: &'a dyn FileSystem,
+    ) -> Self {
+        let mut watcher = Self {
+            root,
+            roots,
+            registry,
+            options,
+            fs,
+            files: HashMap::new(),
+            interval: DEFAULT_POLL_INTERVAL,
+            poll: false,
+            backend: Backend::Poll,
+            debouncer: Debouncer::new(DEFAULT_DEBOUNCE),
+        };
+        watcher.files = watcher.scan();
+        watcher
+    }
+
+    /// Rescan every `interval` instead of every second.
+    pub fn poll_every(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+
+    /// Rescan every interval instead of waiting for filesystem events
+    /// (`--poll`).
+    pub fn poll_only(mut self, poll: bool) -> Self {
+        self.poll = poll;
+        self
+    }
+
+    /// Wait until the files have been unchanged for `quiet` instead of 300 ms
+    /// before handing on a change set.
+    pub fn debounce_for(mut self, quiet: Duration) -> Self {
+        self.debouncer = Debouncer::new(quiet);
+        self
+    }
+
+    fn scan(&self) -> HashMap<PathBuf, SystemTime> {
+        let mut files = HashMap::new();
+        for root in self.roots {
+            for path in self.fs.walk(root, &self.options) {
+                if self.registry.find_parser(&path).is_none() {
+                    continue;
+                }
+                if let Ok(metadata) = self.fs.metadata(&path) {
+                    files.insert(path, metadata.modified);
+                }
+            }
+        }
+        files
+    }
+
+    /// Rescan and return what changed since the last call.
+    pub fn poll(&mut self) -> Vec<Change> {
+        let current = self.scan();
+        let found = changes(&self.files, &current);
+        self.files = current;
+        found
+    }
+
+    /// Subscribe to filesystem events unless polling was asked for. When the
+    /// subscription fails, or the files aren't on disk where events come
+    /// from, this warns and falls back to polling.
+    pub fn start(&mut self) {
+        if self.poll || !self.fs.on_disk() {
+            return;
+        }
+        match self.subscribe() {
+            Ok(backend) => self.backend = backend,
+            Err(e) => self.fall_back(&e.to_string()),
+        }
+    }
+
+    fn subscribe(&self) -> notify::Result<Backend> {
+        let (sender, events) = channel();
+        let mut watcher = notify::recommended_watcher(sender)?;
+        for root in self.roots {
+            watcher.watch(root, RecursiveMode::Recursive)?;
+        }
+        Ok(Backend::Events {
+            _watcher: watcher,
+            events,
+        })
+    }
+
+    fn fall_back(&mut self, reason: &str) {
+        style::warning(&format!(
+            "Can't watch for file events ({}); checking for changes every {} ms instead",
+            reason,
+            self.interval.as_millis()
+        ));
+        self.backend = Backend::Poll;
+    }
+
+    /// Wait for changes, for at most one interval or, while changes are
+    /// pending, one quiet period. Returns the debounced change set once it
+    /// is ready.
+    pub fn next_change_set(&mut self) -> Option<Vec<Change>> {
+        let wait = if self.debouncer.is_waiting() {
+            self.interval.min(self.debouncer.quiet)
+        } else {
+            self.interval
+        };
+        let found = match &self.backend {
+            Backend::Events { events, .. } => match events.recv_timeout(wait) {
+                Ok(first) => {
+                    let batch: Vec<_> = std::iter::once(first).chain(events.try_iter()).collect();
+                    self.apply_events(batch)
+                }
+                Err(RecvTimeoutError::Timeout) => Vec::new(),
+                Err(RecvTimeoutError::Disconnected) => {
+                    self.fall_back("the event watcher stopped");
+                    self.poll()
+                }
+            },
+            Backend::Poll => {
+                std::thread::sleep(wait);
+                self.poll()
+            }
+        };
+        let now = Instant::now();
+        for change in found {
+            self.debouncer.push(change, now);
+        }
+        self.debouncer.ready(now)
+    }
+
+    /// Changes to watched files behind a batch of events. Events on known
+    /// files are checked directly; anything that may add or remove files
+    /// (new files, moved or removed directories, dropped events) rescans.
+    fn apply_events(&mut self, events: Vec<notify::Result<Event>>) -> Vec<Change> {
+        let mut found = BTreeSet::new();
+        let mut rescan = false;
+        for event in events {
+            let Ok(event) = event else {
+                rescan = true;
+                continue;
+            };
+            if event.need_rescan() {
+                rescan = true;
+                continue;
+            }
+            if matches!(event.kind, EventKind::Access(_)) {
+                continue;
+            }
+            for path in event.paths {
+                if !self.files.contains_key(&path) {
+                    rescan |= self.may_hold_sources(&path);
+                    continue;
+                }
+                match self.fs.metadata(&path) {
+                    Ok(metadata) => {
+                        let last = self.files.insert(path.clone(), metadata.modified);
+                        if last != Some(metadata.modified) {
+                            found.insert(Change::Modified(path));
+                        }
+                    }
+                    Err(_) => {
+                        self.files.remove(&path);
+                        found.insert(Change::Deleted(path));
+                    }
+                }
+            }
+        }
+        if rescan {
+            found.extend(self.poll());
+        }
+        found.into_iter().collect()
+    }
+
+    /// Whether an event on `path`, which isn't a watched file, could still
+    /// concern one: a new file a parser handles, or a directory moved or
+    /// removed with watched files inside. Paths a walk skips are left out.
+    fn may_hold_sources(&self, path: &Path) -> bool {
+        let Some(root) = self.roots.iter().find(|root| path.starts_with(root)) else {
+            return false;
+        };
+        let hidden = path
+            .strip_prefix(root)
+            .unwrap_or(path)
+            .components()
+            .any(|c| c.as_os_str().to_string_lossy().starts_with('.'));
+        if hidden || is_excluded(path, &self.options.exclude) {
+            return false;
+        }
+        self.registry.find_parser(path).is_some()
+            || path.is_dir()
+            || self.files.keys().any(|file| file.starts_with(path))
+    }
+
+    /// Watch until interrupted, printing each debounced change set and then
+    /// calling `on_change` once with it.
+    pub fn run(mut self, mut on_change: impl FnMut(&[Change])) -> ! {
+        self.start();
+        loop {
+            if let Some(found) = self.next_change_set() {
+                dispatch(&found, self.root, &mut on_change);
+            }
+        }
+    }
+}
+
+/// Differences between two scans, sorted by kind and then path
+fn changes(
+    before: &HashMap<PathBuf, SystemTime>,
+    after: &HashMap<PathBuf, SystemTime>,
+) -> Vec<Change> {
+    let mut found = BTreeSet::new();
+    for (path, modified) in after {
+        match before.get(path) {
+            Some(last) if last != modified => {
+                found.insert(Change::Modified(path.clone()));
+            }
+            None => {
+                found.insert(Change::Added(path.clone()));
+            }
+            _ => {}
+        }
+    }
+    for path in before.keys().filter(|p| !after.contains_key(*p)) {
+        found.insert(Change::Deleted(path.clone()));
+    }
+    found.into_iter().collect()
+}
+
+/// Report a change set relative to `root` and hand it to `on_change`; an empty
+/// set does nothing.
+fn dispatch(changes: &[Change], root: &Path, on_change: &mut impl FnMut(&[Change])) {
+    if changes.is_empty() {
+        return;
+    }
+    let display = |path: &Path| {
+        path.strip_prefix(root)
+            .unwrap_or(path)
+            .display()
+            .to_string()
+    };
+    for change in changes {
+        let line = match change {
+            Change::Added(path) => style::file_added(&display(path)),
+            Change::Modified(path) => style::file_changed(&display(path)),
+            Change::Deleted(path) => style::file_deleted(&display(path)),
+        };
+        style::note(&line);
+    }
+    println!();
+    on_change(changes);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fs::default_fs;
+
+    /// Edit, add and remove source files under a fresh directory, and return
+    /// the change set the watcher hands on and whether it saw events.
+    fn edits_seen(name: &str, poll: bool) -> (Vec<Change>, bool) {
+        let root =
+            std::env::temp_dir().join(format!("archmap-watch-{}-{}", name, std::process::id()));
+        let src = root.join("src");
+        std::fs::create_dir_all(&src).unwrap();
+        std::fs::write(src.join("lib.rs"), "pub fn a() {}\n").unwrap();
+        std::fs::write(src.join("old.rs"), "pub fn b() {}\n").unwrap();
+        std::fs::write(root.join("notes.txt"), "").unwrap();
+
+        let registry = ParserRegistry::new();
+        let roots = [root.clone()];
+        let mut watcher = Watcher::for_roots(
+            &root,
+            &roots,
+            &registry,
+            WalkOptions::default(),
+            default_fs(),
+        )
+        .poll_only(poll)
+        .poll_every(Duration::from_millis(20))
+        .debounce_for(Duration::from_millis(50));
+        watcher.start();
+        let events = matches!(watcher.backend, Backend::Events { .. });
+
+        // Past the filesystem's timestamp granularity
+        std::thread::sleep(Duration::from_millis(50));
+        std::fs::write(src.join("lib.rs"), "pub fn a() {}\npub fn c() {}\n").unwrap();
+        std::fs::write(src.join("new.rs"), "pub fn d() {}\n").unwrap();
+        std::fs::remove_file(src.join("old.rs")).unwrap();
+        std::fs::write(root.join("notes.txt"), "not source").unwrap();
+
+        let deadline = Instant::now() + Duration::from_secs(5);
+        let mut found = Vec::new();
+        while found.is_empty() && Instant::now() < deadline {
+            found = watcher.next_change_set().unwrap_or_default();
+        }
+        drop(watcher);
+        std::fs::remove_dir_all(&root).unwrap();
+        (found, events)
+    }
+
+    fn expected_edits(name: &str) -> Vec<Change> {
+        let src = std::env::temp_dir()
+            .join(format!("archmap-watch-{}-{}", name, std::process::id()))
+            .join("src");
+        vec![
+            Change::Added(src.join("new.rs")),
+            Change::Modified(src.join("lib.rs")),
+            Change::Deleted(src.join("old.rs")),
+        ]
+    }
+
+    #[test]
+    fn test_events_report_source_changes() {
+        let (found, events) = edits_seen("events", false);
+        assert!(events);
+        assert_eq!(found, expected_edits("events"));
+    }
+
+    #[test]
+    fn test_polling_reports_source_changes() {
+        let (found, events) = edits_seen("poll", true);
+        assert!(!events);
+        assert_eq!(found, expected_edits("poll"));
+    }
+
+    #[test]
+    fn test_falls_back_to_polling_when_events_cannot_be_watched() {
+        let registry = ParserRegistry::new();
+        let roots = [std::env::temp_dir().join("archmap-watch-missing-root")];
+        let mut watcher = Watcher::for_roots(
+            &roots[0],
+            &roots,
+            &registry,
+            WalkOptions::default(),
+            default_fs(),
+        );
+        watcher.start();
+        assert!(matches!(watcher.backend, Backend::Poll));
+    }
+
+    #[test]
+    fn test_changes_between_scans_reach_the_callback() {
+        let t0 = SystemTime::UNIX_EPOCH;
+        let t1 = t0 + Duration::from_secs(1);
+        let before = HashMap::from([
+            (PathBuf::from("/demo/src/kept.rs"), t0),
+            (PathBuf::from("/demo/src/edited.rs"), t0),
+            (PathBuf::from("/demo/src/gone.rs"), t0),
+        ]);
+        let after = HashMap::from([
+            (PathBuf::from("/demo/src/kept.rs"), t0),
+            (PathBuf::from("/demo/src/edited.rs"), t1),
+            (PathBuf::from("/demo/src/new.rs"), t1),
+        ]);
+
+        let mut calls = Vec::new();
+        let mut record = |changes: &[Change]| calls.push(changes.to_vec());
+        dispatch(&changes(&before, &before), Path::new("/demo"), &mut record);
+        dispatch(&changes(&before, &after), Path::new("/demo"), &mut record);
+
+        assert_eq!(
+            calls,
+            [vec![
+                Change::Added(PathBuf::from("/demo/src/new.rs")),
+                Change::Modified(PathBuf::from("/demo/src/edited.rs")),
+                Change::Deleted(PathBuf::from("/demo/src/gone.rs")),
+            ]]
+        );
+    }
+
+    #[test]
+    fn test_bursts_are_merged_and_handed_on_once_quiet() {
+        let path = PathBuf::from;
+        let start = Instant::now();
+        let at = |ms| start + Duration::from_millis(ms);
+        let mut debouncer = Debouncer::new(Duration::from_millis(300));
+
+        // An editor saving through a temporary file, a file created and
+        // removed again, and one edited twice
+        debouncer.push(Change::Added(path("/demo/src/tmp.rs")), at(0));
+        debouncer.push(Change::Modified(path("/demo/src/edited.rs")), at(0));
+        debouncer.push(Change::Deleted(path("/demo/src/saved.rs")), at(50));
+        debouncer.push(Change::Added(path("/demo/src/saved.rs")), at(60));
+        debouncer.push(Change::Deleted(path("/demo/src/tmp.rs")), at(100));
+        debouncer.push(Change::Modified(path("/demo/src/edited.rs")), at(200));
+
+        // Still inside the quiet period of the last change
+        assert_eq!(debouncer.ready(at(400)), None);
+        assert_eq!(
+            debouncer.ready(at(500)),
+            Some(vec![
+                Change::Modified(path("/demo/src/edited.rs")),
+                Change::Modified(path("/demo/src/saved.rs")),
+            ])
+        );
+        assert_eq!(debouncer.ready(at(900)), None);
+        assert!(!debouncer.is_waiting());
+
+        // A new file edited before it settles is still new; one that cancels
+        // itself out hands on nothing
+        debouncer.push(Change::Added(path("/demo/src/new.rs")), at(1000));
+        debouncer.push(Change::Modified(path("/demo/src/new.rs")), at(1100));
+        debouncer.push(Change::Modified(path("/demo/src/gone.rs")), at(1100));
+        debouncer.push(Change::Deleted(path("/demo/src/gone.rs")), at(1150));
+        assert_eq!(
+            debouncer.ready(at(1450)),
+            Some(vec![
+                Change::Added(path("/demo/src/new.rs")),
+                Change::Deleted(path("/demo/src/gone.rs")),
+            ])
+        );
+        debouncer.push(Change::Added(path("/demo/src/blip.rs")), at(2000));
+        debouncer.push(Change::Deleted(path("/demo/src/blip.rs")), at(2010));
+        assert_eq!(debouncer.ready(at(2400)), None);
+        assert!(!debouncer.is_waiting());
+    }
+}
@@ -1,8 +1,9 @@
 use crate::fs::{FileSystem, default_fs};
-use crate::model::{Boundary, BoundaryKind};
-use serde::Deserialize;
-use std::collections::HashMap;
-use std::path::Path;
+use crate::model::{Boundary, BoundaryKind, IssueKind, IssueSeverity};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
 use thiserror::Error;
 
 #[derive(Debug, Error)]
@@ -11,21 +12,169 @@ pub enum ConfigError {
     Io(#[from] std::io::Error),
     #[error("Failed to parse config file: {0}")]
     Parse(#[from] toml::de::Error),
+    #[error("Config file not found: {}", .0.display())]
+    NotFound(PathBuf),
+    #[error("Invalid config value: {0}")]
+    Invalid(String),
 }
 
-#[derive(Debug, Clone)]
+/// Where commands get their configuration from
+#[derive(Debug, Clone, Default, PartialEq)]
+pub enum ConfigSource {
+    /// `.archmap.toml` in the project directory, when there is one, over the
+    /// user-global config
+    #[default]
+    Discover,
+    /// Like `Discover`, but a project without `.archmap.toml` starts from a
+    /// preset instead of the defaults (`--preset`)
+    Preset(Preset),
+    /// An explicit file (`--config`) in place of `.archmap.toml`; a missing or
+    /// invalid file is an error
+    File(PathBuf),
+    /// Built-in defaults, ignoring every config file, user-global included (`--no-config`)
+    Defaults,
+}
+
+impl ConfigSource {
+    /// Load the configuration for a project directory.
+    pub fn load(&self, project_path: &Path) -> Result<Config, ConfigError> {
+        match self {
+            ConfigSource::Discover => Config::load_layered(project_path),
+            ConfigSource::Preset(preset) => Config::load_preset(project_path, *preset),
+            ConfigSource::File(path) => Config::load_over_user(path),
+            ConfigSource::Defaults => Ok(Config::default()),
+        }
+    }
+
+    /// The config files [`ConfigSource::load`] reads for a project directory,
+    /// lowest precedence first. Empty when only built-in defaults (or a
+    /// preset) apply.
+    pub fn files(&self, project_path: &Path) -> Vec<PathBuf> {
+        let user = user_config_path().filter(|path| path.exists());
+        let project = match self {
+            ConfigSource::Discover | ConfigSource::Preset(_) => {
+                Some(project_path.join(".archmap.toml")).filter(|path| path.exists())
+            }
+            ConfigSource::File(path) => Some(path.clone()),
+            ConfigSource::Defaults => return Vec::new(),
+        };
+        user.into_iter().chain(project).collect()
+    }
+}
+
+/// `--config` / `--no-config` for this process
+static CONFIG_SOURCE: OnceLock<ConfigSource> = OnceLock::new();
+
+/// Set where commands load their configuration from.
+pub fn set_config_source(source: ConfigSource) {
+    let _ = CONFIG_SOURCE.set(source);
+}
+
+/// The configuration source set by `--config` / `--no-config`, else discovery.
+pub fn config_source() -> ConfigSource {
+    CONFIG_SOURCE.get().cloned().unwrap_or_default()
+}
+
+/// Serializes to the effective settings (`archmap config-dump`); the result
+/// is for reading, not a config file archmap loads back.
+#[derive(Debug, Clone, Serialize)]
 pub struct Config {
     pub thresholds: Thresholds,
     pub boundaries: Vec<Boundary>,
     /// Glob patterns for modules where high coupling is expected (e.g., core domain models).
     /// Modules matching these patterns won't be flagged for high fan-in.
     pub expected_high_coupling: Vec<String>,
+    /// Header markers (checked in the first few lines) that identify generated files.
+    pub generated_markers: Vec<String>,
+    /// Glob patterns for generated files, regardless of their contents.
+    pub generated_paths: Vec<String>,
+    /// Report issues in generated files too (normally they are skipped).
+    pub include_generated: bool,
+    /// Glob patterns for test files that needn't import production code:
+    /// shared fixtures, harnesses and test entry points.
+    pub test_harness_paths: Vec<String>,
+    /// Named invariants checked by `archmap check`.
+    pub expectations: Expectations,
+    /// Issue kinds whose detectors are skipped entirely (e.g. `LowCohesion`).
+    pub disabled_checks: Vec<String>,
+    /// Opt-in issue kinds to detect (see [`IssueKind::OPT_IN`]).
+    pub enabled_checks: Vec<String>,
+    /// Worker threads for discovery and parsing; 0 means one per core.
+    pub threads: usize,
+    /// Files larger than this many KB are skipped; 0 means no limit.
+    pub max_file_size_kb: u64,
+    /// Files with a longer line are taken to be minified and skipped; 0 means no limit.
+    pub max_line_length: usize,
+    /// Milliseconds one file may take to parse before it is skipped; 0 means no limit.
+    pub parse_timeout_ms: u64,
+    /// Descend into symlinked directories during discovery.
+    pub follow_symlinks: bool,
+    /// Reuse parse results from `.archmap/cache/` for files that haven't changed.
+    pub cache: bool,
+    /// Severity floor for reports when `--min-severity` isn't given; `None`
+    /// leaves each command's own default.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub min_severity: Option<IssueSeverity>,
+    /// How `LowCohesion` scores a module.
+    pub cohesion_method: CohesionMethod,
+}
+
+/// Cohesion metric behind the `LowCohesion` check
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CohesionMethod {
+    /// Internal imports against the number of distinct external crates
+    #[default]
+    Imports,
+    /// LCOM-style: how many pairs of functions mention an import in common
+    Lcom,
+}
+
+impl CohesionMethod {
+    /// Name as written in `.archmap.toml`
+    pub fn name(self) -> &'static str {
+        match self {
+            CohesionMethod::Imports => "imports",
+            CohesionMethod::Lcom => "lcom",
+        }
+    }
+}
+
+/// Explicit architectural assertions from the `[expectations]` section
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct Expectations {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_cycles: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_god_objects: Option<usize>,
+    pub layers: Vec<LayerRule>,
 }
 
-#[derive(Debug, Clone)]
+impl Expectations {
+    pub fn is_empty(&self) -> bool {
+        self.max_cycles.is_none() && self.max_god_objects.is_none() && self.layers.is_empty()
+    }
+}
+
+/// Modules matching `from` must not import modules matching any `deny` pattern
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct LayerRule {
+    pub name: String,
+    /// Glob for the constrained modules, relative to the project root
+    pub from: String,
+    /// Globs for modules they may not depend on
+    pub deny: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
 pub struct Thresholds {
     pub god_object_lines: usize,
     pub coupling_fanin: usize,
+    /// Flag modules whose fan-in is above this percentile of every module's
+    /// fan-in instead of using `coupling_fanin`, so the threshold scales with
+    /// the project
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub coupling_fanin_percentile: Option<f64>,
     pub boundary_violation_min: usize,
     pub max_dependency_depth: usize,
     pub min_cohesion: f64,
@@ -35,26 +184,71 @@ pub struct Thresholds {
     pub fat_module_private_functions: usize,
     /// Maximum lines per export before flagging as fat
     pub fat_module_lines_per_export: f64,
+    /// Non-trivial functions that make an expected-high-coupling module a fat shared module
+    pub shared_module_functions: usize,
+    /// Modules implementing one trait or interface before it counts as scattered
+    pub scattered_impl_files: usize,
+    /// Imports, internal and external, a module may have before it is flagged
+    pub max_imports: usize,
+    /// Modules a top-level directory may hold before it is a god package
+    pub god_package_modules: usize,
+    /// Lines a top-level directory may hold before it is a god package
+    pub god_package_lines: usize,
 }
 
-#[derive(Debug, Deserialize)]
+/// One config file as written: a setting it leaves out is `None`, so layers
+/// can be merged before the defaults fill the gaps
+#[derive(Debug, Default, Deserialize)]
 struct RawConfig {
     thresholds: Option<RawThresholds>,
     boundaries: Option<HashMap<String, RawBoundary>>,
+    expected_high_coupling: Option<Vec<String>>,
+    generated_markers: Option<Vec<String>>,
+    generated_paths: Option<Vec<String>>,
+    test_harness_paths: Option<Vec<String>>,
+    expectations: Option<RawExpectations>,
+    disabled_checks: Option<Vec<String>>,
+    enabled_checks: Option<Vec<String>>,
+    threads: Option<usize>,
+    max_file_size_kb: Option<u64>,
+    max_line_length: Option<usize>,
+    parse_timeout_ms: Option<u64>,
+    follow_symlinks: Option<bool>,
+    cache: Option<bool>,
+    min_severity: Option<String>,
+    cohesion_method: Option<CohesionMethod>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct RawExpectations {
+    max_cycles: Option<usize>,
+    max_god_objects: Option<usize>,
     #[serde(default)]
-    expected_high_coupling: Vec<String>,
+    layers: BTreeMap<String, RawLayerRule>,
 }
 
 #[derive(Debug, Deserialize)]
+struct RawLayerRule {
+    from: String,
+    deny: Vec<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
 struct RawThresholds {
     god_object_lines: Option<usize>,
     coupling_fanin: Option<usize>,
+    coupling_fanin_percentile: Option<f64>,
     boundary_violation_min: Option<usize>,
     max_dependency_depth: Option<usize>,
     min_cohesion: Option<f64>,
     fat_module_lines: Option<usize>,
     fat_module_private_functions: Option<usize>,
     fat_module_lines_per_export: Option<f64>,
+    shared_module_functions: Option<usize>,
+    scattered_impl_files: Option<usize>,
+    max_imports: Option<usize>,
+    god_package_modules: Option<usize>,
+    god_package_lines: Option<usize>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -67,16 +261,137 @@ struct RawBoundary {
     ownership_threshold: Option<f64>,
 }
 
+impl RawConfig {
+    /// `over` layered on `self`: every setting `over` gives wins, even one
+    /// equal to its default. Boundaries and expectation layers merge by name;
+    /// lists, `disabled_checks` and `enabled_checks` included, are replaced whole.
+    fn merge(self, over: RawConfig) -> RawConfig {
+        RawConfig {
+            thresholds: merge_with(self.thresholds, over.thresholds, RawThresholds::merge),
+            boundaries: merge_with(self.boundaries, over.boundaries, |mut base, over| {
+                base.extend(over);
+                base
+            }),
+            expected_high_coupling: over.expected_high_coupling.or(self.expected_high_coupling),
+            generated_markers: over.generated_markers.or(self.generated_markers),
+            generated_paths: over.generated_paths.or(self.generated_paths),
+            test_harness_paths: over.test_harness_paths.or(self.test_harness_paths),
+            expectations: merge_with(self.expectations, over.expectations, |base, over| {
+                RawExpectations {
+                    max_cycles: over.max_cycles.or(base.max_cycles),
+                    max_god_objects: over.max_god_objects.or(base.max_god_objects),
+                    layers: base.layers.into_iter().chain(over.layers).collect(),
+                }
+            }),
+            disabled_checks: over.disabled_checks.or(self.disabled_checks),
+            enabled_checks: over.enabled_checks.or(self.enabled_checks),
+            threads: over.threads.or(self.threads),
+            max_file_size_kb: over.max_file_size_kb.or(self.max_file_size_kb),
+            max_line_length: over.max_line_length.or(self.max_line_length),
+            parse_timeout_ms: over.parse_timeout_ms.or(self.parse_timeout_ms),
+            follow_symlinks: over.follow_symlinks.or(self.follow_symlinks),
+            cache: over.cache.or(self.cache),
+            min_severity: over.min_severity.or(self.min_severity),
+            cohesion_method: over.cohesion_method.or(self.cohesion_method),
+        }
+    }
+}
+
+impl RawThresholds {
+    fn merge(self, over: RawThresholds) -> RawThresholds {
+        RawThresholds {
+            god_object_lines: over.god_object_lines.or(self.god_object_lines),
+            coupling_fanin: over.coupling_fanin.or(self.coupling_fanin),
+            coupling_fanin_percentile: over
+                .coupling_fanin_percentile
+                .or(self.coupling_fanin_percentile),
+            boundary_violation_min: over.boundary_violation_min.or(self.boundary_violation_min),
+            max_dependency_depth: over.max_dependency_depth.or(self.max_dependency_depth),
+            min_cohesion: over.min_cohesion.or(self.min_cohesion),
+            fat_module_lines: over.fat_module_lines.or(self.fat_module_lines),
+            fat_module_private_functions: over
+                .fat_module_private_functions
+                .or(self.fat_module_private_functions),
+            fat_module_lines_per_export: over
+                .fat_module_lines_per_export
+                .or(self.fat_module_lines_per_export),
+            shared_module_functions: over
+                .shared_module_functions
+                .or(self.shared_module_functions),
+            scattered_impl_files: over.scattered_impl_files.or(self.scattered_impl_files),
+            max_imports: over.max_imports.or(self.max_imports),
+            god_package_modules: over.god_package_modules.or(self.god_package_modules),
+            god_package_lines: over.god_package_lines.or(self.god_package_lines),
+        }
+    }
+}
+
+/// Both tables merged with `merge`, or whichever one is set
+fn merge_with<T>(base: Option<T>, over: Option<T>, merge: impl FnOnce(T, T) -> T) -> Option<T> {
+    match (base, over) {
+        (Some(base), Some(over)) => Some(merge(base, over)),
+        (base, over) => over.or(base),
+    }
+}
+
 impl Default for Config {
     fn default() -> Self {
         Self {
             thresholds: Thresholds::default(),
             boundaries: Boundary::default_boundaries(),
             expected_high_coupling: default_expected_high_coupling(),
+            generated_markers: default_generated_markers(),
+            generated_paths: default_generated_paths(),
+            include_generated: false,
+            test_harness_paths: default_test_harness_paths(),
+            expectations: Expectations::default(),
+            disabled_checks: Vec::new(),
+            enabled_checks: Vec::new(),
+            threads: 0,
+            max_file_size_kb: 1024,
+            max_line_length: 5000,
+            parse_timeout_ms: 5000,
+            follow_symlinks: false,
+            cache: true,
+            min_severity: None,
+            cohesion_method: CohesionMethod::default(),
         }
     }
 }
 
+fn default_generated_markers() -> Vec<String> {
+    vec![
+        "@generated".to_string(),
+        "Code generated".to_string(),
+        "DO NOT EDIT".to_string(),
+        "<auto-generated".to_string(),
+        "This file is automatically generated".to_string(),
+    ]
+}
+
+fn default_generated_paths() -> Vec<String> {
+    vec![
+        "**/*.pb.rs".to_string(),
+        "**/*_pb2.py".to_string(),
+        "**/*_pb2_grpc.py".to_string(),
+        "**/*.generated.ts".to_string(),
+        "**/*.pb.ts".to_string(),
+        "**/__generated__/**".to_string(),
+    ]
+}
+
+fn default_test_harness_paths() -> Vec<String> {
+    vec![
+        "**/conftest.py".to_string(),
+        "**/tests/common/**".to_string(),
+        "**/tests/common.rs".to_string(),
+        "**/tests/*.rs".to_string(), // Rust integration tests import the crate by name
+        "**/fixtures/**".to_string(),
+        "**/setupTests.*".to_string(),
+        "**/jest.setup.*".to_string(),
+    ]
+}
+
 fn default_expected_high_coupling() -> Vec<String> {
     vec![
         "**/model/**".to_string(),
@@ -101,16 +416,38 @@ impl Default for Thresholds {
         Self {
             god_object_lines: 500,
             coupling_fanin: 5,
+            coupling_fanin_percentile: None,
             boundary_violation_min: 2,
             max_dependency_depth: 5,
             min_cohesion: 0.3,
             fat_module_lines: 400,
             fat_module_private_functions: 8,
             fat_module_lines_per_export: 100.0,
+            shared_module_functions: 3,
+            scattered_impl_files: 8,
+            max_imports: 30,
+            god_package_modules: 40,
+            god_package_lines: 10000,
         }
     }
 }
 
+/// Where the user-global config lives: `$XDG_CONFIG_HOME/archmap/config.toml`,
+/// else `~/.config/archmap/config.toml` (`%APPDATA%\\archmap\\config.toml` on Windows).
+pub fn user_config_path() -> Option<PathBuf> {
+    let var = |name| {
+        std::env::var_os(name)
+            .filter(|v| !v.is_empty())
+            .map(PathBuf::from)
+    };
+    let dir = if cfg!(windows) {
+        var("APPDATA")
+    } else {
+        var("XDG_CONFIG_HOME").or_else(|| var("HOME").map(|home| home.join(".config")))
+    };
+    dir.map(|dir| dir.join("archmap").join("config.toml"))
+}
+
 impl Config {
     pub fn load(project_path: &Path) -> Result<Self, ConfigError> {
         Self::load_with_fs(project_path, default_fs())
@@ -122,20 +459,46 @@ impl Config {
         if !fs.exists(&config_path) {
             return Ok(Self::default());
         }
+        Self::load_file_with_fs(&config_path, fs)
+    }
 
-        let content = fs.read_to_string(&config_path)?;
-        let raw: RawConfig = toml::from_str(&content)?;
+    /// Load a specific config file, which must exist.
+    pub fn load_file(config_path: &Path) -> Result<Self, ConfigError> {
+        Self::load_file_with_fs(config_path, default_fs())
+    }
 
+    pub fn load_file_with_fs(config_path: &Path, fs: &dyn FileSystem) -> Result<Self, ConfigError> {
+        Self::from_raw(read_raw(config_path, fs)?)
+    }
+
+    /// Config files layered in order, lowest precedence first, each of which
+    /// must exist. A setting a later file gives wins even when it equals the
+    /// default; defaults only fill what no file sets.
+    pub fn load_files_with_fs(paths: &[PathBuf], fs: &dyn FileSystem) -> Result<Self, ConfigError> {
+        let mut raw = RawConfig::default();
+        for path in paths {
+            raw = raw.merge(read_raw(path, fs)?);
+        }
+        Self::from_raw(raw)
+    }
+
+    fn from_raw(raw: RawConfig) -> Result<Self, ConfigError> {
         let thresholds = match raw.thresholds {
             Some(t) => Thresholds {
                 god_object_lines: t.god_object_lines.unwrap_or(500),
                 coupling_fanin: t.coupling_fanin.unwrap_or(5),
+                coupling_fanin_percentile: t.coupling_fanin_percentile,
                 boundary_violation_min: t.boundary_violation_min.unwrap_or(2),
                 max_dependency_depth: t.max_dependency_depth.unwrap_or(5),
                 min_cohesion: t.min_cohesion.unwrap_or(0.3),
                 fat_module_lines: t.fat_module_lines.unwrap_or(400),
                 fat_module_private_functions: t.fat_module_private_functions.unwrap_or(8),
                 fat_module_lines_per_export: t.fat_module_lines_per_export.unwrap_or(100.0),
+                shared_module_functions: t.shared_module_functions.unwrap_or(3),
+                scattered_impl_files: t.scattered_impl_files.unwrap_or(8),
+                max_imports: t.max_imports.unwrap_or(30),
+                god_package_modules: t.god_package_modules.unwrap_or(40),
+                god_package_lines: t.god_package_lines.unwrap_or(10000),
             },
             None => Thresholds::default(),
         };
@@ -173,18 +536,132 @@ impl Config {
             None => Boundary::default_boundaries(),
         };
 
-        let expected_high_coupling = if raw.expected_high_coupling.is_empty() {
-            default_expected_high_coupling()
-        } else {
-            raw.expected_high_coupling
+        let min_severity = match raw.min_severity {
+            Some(severity) => Some(
+                severity
+                    .parse::<IssueSeverity>()
+                    .map_err(|e| ConfigError::Invalid(format!("min_severity: {}", e)))?,
+            ),
+            None => None,
         };
 
+        let expected_high_coupling = raw
+            .expected_high_coupling
+            .filter(|patterns| !patterns.is_empty())
+            .unwrap_or_else(default_expected_high_coupling);
+
         Ok(Self {
             thresholds,
             boundaries,
             expected_high_coupling,
+            generated_markers: raw
+                .generated_markers
+                .unwrap_or_else(default_generated_markers),
+            generated_paths: raw.generated_paths.unwrap_or_else(default_generated_paths),
+            include_generated: false,
+            test_harness_paths: raw
+                .test_harness_paths
+                .unwrap_or_else(default_test_harness_paths),
+            expectations: raw
+                .expectations
+                .map(|e| Expectations {
+                    max_cycles: e.max_cycles,
+                    max_god_objects: e.max_god_objects,
+                    layers: e
+                        .layers
+                        .into_iter()
+                        .map(|(name, rule)| LayerRule {
+                            name,
+                            from: rule.from,
+                            deny: rule.deny,
+                        })
+                        .collect(),
+                })
+                .unwrap_or_default(),
+            disabled_checks: raw.disabled_checks.unwrap_or_default(),
+            enabled_checks: raw.enabled_checks.unwrap_or_default(),
+            threads: raw.threads.unwrap_or(0),
+            max_file_size_kb: raw.max_file_size_kb.unwrap_or(1024),
+            max_line_length: raw.max_line_length.unwrap_or(5000),
+            parse_timeout_ms: raw.parse_timeout_ms.unwrap_or(5000),
+            follow_symlinks: raw.follow_symlinks.unwrap_or(false),
+            cache: raw.cache.unwrap_or(true),
+            min_severity,
+            cohesion_method: raw.cohesion_method.unwrap_or_default(),
         })
     }
+
+    /// The user-global config (see [`user_config_path`]), or defaults when there is none.
+    pub fn load_user() -> Result<Self, ConfigError> {
+        Self::from_raw(user_layer()?)
+    }
+
+    /// The project's `.archmap.toml` layered over the user-global config.
+    pub fn load_layered(project_path: &Path) -> Result<Self, ConfigError> {
+        let project = project_path.join(".archmap.toml");
+        let mut raw = user_layer()?;
+        if project.exists() {
+            raw = raw.merge(read_raw(&project, default_fs())?);
+        }
+        Self::from_raw(raw)
+    }
+
+    /// Like [`Config::load_layered`], with `preset`'s config standing in for a
+    /// missing `.archmap.toml`.
+    pub fn load_preset(project_path: &Path, preset: Preset) -> Result<Self, ConfigError> {
+        if project_path.join(".archmap.toml").exists() {
+            return Self::load_layered(project_path);
+        }
+        Self::from_raw(user_layer()?.merge(preset.layer()))
+    }
+
+    /// An explicit config file (`--config`) layered over the user-global config.
+    pub fn load_over_user(config_path: &Path) -> Result<Self, ConfigError> {
+        Self::from_raw(user_layer()?.merge(read_raw(config_path, default_fs())?))
+    }
+
+    /// Whether the detector for this issue kind is off: listed in `disabled_checks`,
+    /// or opt-in and missing from `enabled_checks`.
+    pub fn is_check_disabled(&self, kind: &str) -> bool {
+        let listed = |names: &[String]| {
+            names
+                .iter()
+                .any(|name| IssueKind::canonical_name(name) == Some(kind))
+        };
+        listed(&self.disabled_checks)
+            || (IssueKind::OPT_IN.contains(&kind) && !listed(&self.enabled_checks))
+    }
+}
+
+/// A config file's settings as written, which must exist
+fn read_raw(config_path: &Path, fs: &dyn FileSystem) -> Result<RawConfig, ConfigError> {
+    if !fs.exists(config_path) {
+        return Err(ConfigError::NotFound(config_path.to_path_buf()));
+    }
+
+    let content = fs.read_to_string(config_path)?;
+    let raw: RawConfig = toml::from_str(&content)?;
+
+    if let Some(percentile) = raw
+        .thresholds
+        .as_ref()
+        .and_then(|t| t.coupling_fanin_percentile)
+        && !(0.0..100.0).contains(&percentile)
+    {
+        return Err(ConfigError::Invalid(format!(
+            "coupling_fanin_percentile: {} is not in 0..100",
+            percentile
+        )));
+    }
+    Ok(raw)
+}
+
+/// The user-global config's settings, or none when there is no such file
+fn user_layer() -> Result<RawConfig, ConfigError> {
+    match user_config_path() {
+        Some(path) if path.exists() => read_raw(&path, default_fs()),
+        _ => Ok(RawConfig::default()),
+    }
 }
 
 fn capitalize(s: &str) -> String {
@@ -229,11 +706,339 @@ fn get_boundary_defaults(kind: &BoundaryKind) -> (Vec<String>, f64) {
     }
 }
 
+/// A well-known architecture style: boundaries that allow I/O where the style
+/// puts it, and layer rules for the direction its dependencies must point
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum Preset {
+    /// Ports and adapters: the domain and application core never import adapters
+    Hexagonal,
+    /// Entities, use cases, adapters and infrastructure, each depending only inward
+    Clean,
+    /// Models independent of views and controllers, views independent of controllers
+    Mvc,
+    /// Domain-driven design layers: domain, application, infrastructure, interfaces
+    Ddd,
+}
+
+/// A layer rule as `(name, from, deny)`
+type PresetLayer = (&'static str, &'static str, &'static [&'static str]);
+
+impl Preset {
+    /// Name as passed to `--preset`
+    pub fn name(self) -> &'static str {
+        match self {
+            Preset::Hexagonal => "hexagonal",
+            Preset::Clean => "clean",
+            Preset::Mvc => "mvc",
+            Preset::Ddd => "ddd",
+        }
+    }
+
+    /// Directories where this style does its I/O, so boundary crossings there are allowed
+    fn io_dirs(self) -> &'static [&'static str] {
+        match self {
+            Preset::Hexagonal => &["**/adapters/**"],
+            Preset::Clean => &["**/adapters/**", "**/infrastructure/**"],
+            Preset::Mvc => &["**/models/**", "**/controllers/**"],
+            Preset::Ddd => &["**/infrastructure/**"],
+        }
+    }
+
+    fn layer_table(self) -> &'static [PresetLayer] {
+        match self {
+            Preset::Hexagonal => &[
+                (
+                    "domain_is_pure",
+                    "**/domain/**",
+                    &["**/application/**", "**/ports/**", "**/adapters/**"],
+                ),
+                ("ports_are_abstract", "**/ports/**", &["**/adapters/**"]),
+                (
+                    "application_uses_ports",
+                    "**/application/**",
+                    &["**/adapters/**"],
+                ),
+            ],
+            Preset::Clean => &[
+                (
+                    "entities_independent",
+                    "**/entities/**",
+                    &[
+                        "**/use_cases/**",
+                        "**/usecases/**",
+                        "**/adapters/**",
+                        "**/infrastructure/**",
+                    ],
+                ),
+                (
+                    "use_cases_independent",
+                    "**/use_cases/**",
+                    &["**/adapters/**", "**/infrastructure/**"],
+                ),
+                (
+                    "usecases_independent",
+                    "**/usecases/**",
+                    &["**/adapters/**", "**/infrastructure/**"],
+                ),
+                (
+                    "adapters_independent",
+                    "**/adapters/**",
+                    &["**/infrastructure/**"],
+                ),
+            ],
+            Preset::Mvc => &[
+                (
+                    "models_independent",
+                    "**/models/**",
+                    &["**/views/**", "**/controllers/**"],
+                ),
+                ("views_independent", "**/views/**", &["**/controllers/**"]),
+            ],
+            Preset::Ddd => &[
+                (
+                    "domain_independent",
+                    "**/domain/**",
+                    &[
+                        "**/application/**",
+                        "**/infrastructure/**",
+                        "**/interfaces/**",
+                    ],
+                ),
+                (
+                    "application_independent",
+                    "**/application/**",
+                    &["**/infrastructure/**", "**/interfaces/**"],
+                ),
+            ],
+        }
+    }
+
+    /// Layer rules checked by `archmap check`
+    pub fn layers(self) -> Vec<LayerRule> {
+        self.layer_table()
+            .iter()
+            .map(|(name, from, deny)| LayerRule {
+                name: name.to_string(),
+                from: from.to_string(),
+                deny: deny.iter().map(|d| d.to_string()).collect(),
+            })
+            .collect()
+    }
+
+    /// The default boundaries, also allowed in the style's I/O directories
+    pub fn boundaries(self) -> Vec<Boundary> {
+        let mut boundaries = Boundary::default_boundaries();
+        for boundary in &mut boundaries {
+            boundary
+                .allowed_in
+                .extend(self.io_dirs().iter().map(|d| d.to_string()));
+        }
+        boundaries
+    }
+
+    /// This style's boundaries and layer rules as a config layer, the way
+    /// [`generate_preset_template`] writes them
+    fn layer(self) -> RawConfig {
+        let boundaries = self
+            .boundaries()
+            .into_iter()
+            .map(|b| {
+                let raw = RawBoundary {
+                    name: Some(b.name.clone()),
+                    indicators: b.indicators,
+                    suggestion: Some(b.suggestion),
+                    allowed_in: b.allowed_in,
+                    ownership_threshold: Some(b.ownership_threshold),
+                };
+                (b.name.to_lowercase(), raw)
+            })
+            .collect();
+        let layers = self
+            .layers()
+            .into_iter()
+            .map(|l| {
+                let rule = RawLayerRule {
+                    from: l.from,
+                    deny: l.deny,
+                };
+                (l.name, rule)
+            })
+            .collect();
+        RawConfig {
+            boundaries: Some(boundaries),
+            expectations: Some(RawExpectations {
+                layers,
+                ..Default::default()
+            }),
+            ..Default::default()
+        }
+    }
+
+    /// The defaults with this style's boundaries and layer rules
+    pub fn config(self) -> Config {
+        Config {
+            boundaries: self.boundaries(),
+            expectations: Expectations {
+                layers: self.layers(),
+                ..Default::default()
+            },
+            ..Default::default()
+        }
+    }
+}
+
+/// `[expectations.layers.*]` tables for `layers`
+pub fn layers_toml(layers: &[LayerRule]) -> String {
+    let mut out = String::new();
+    for layer in layers {
+        out.push_str(&format!("\n[expectations.layers.{}]\n", layer.name));
+        out.push_str(&format!(
+            "from = {}\n",
+            toml::Value::from(layer.from.as_str())
+        ));
+        out.push_str(&format!(
+            "deny = {}\n",
+            toml::Value::from(layer.deny.clone())
+        ));
+    }
+    out
+}
+
+/// A starter .archmap.toml for `preset`: its boundaries and layer rules, with
+/// everything else left at the defaults
+pub fn generate_preset_template(preset: Preset) -> String {
+    let mut out = format!(
+        "# Archmap Configuration\n\
+         # Generated by `archmap init --preset {}`. Other settings keep their defaults;\n\
+         # `archmap init` without --preset writes a template documenting all of them.\n",
+        preset.name()
+    );
+
+    out.push_str("\n# I/O is allowed where this architecture puts it\n");
+    for boundary in preset.boundaries() {
+        out.push_str(&format!(
+            "\n[boundaries.{}]\n",
+            boundary.name.to_lowercase()
+        ));
+        out.push_str(&format!("name = {}\n", toml::Value::from(boundary.name)));
+        out.push_str(&format!(
+            "indicators = {}\n",
+            toml::Value::from(boundary.indicators)
+        ));
+        out.push_str(&format!(
+            "suggestion = {}\n",
+            toml::Value::from(boundary.suggestion)
+        ));
+        out.push_str(&format!(
+            "allowed_in = {}\n",
+            toml::Value::from(boundary.allowed_in)
+        ));
+    }
+
+    out.push_str(
+        "\n# Dependencies point inward: `archmap check` fails when a module\n\
+         # matching `from` imports one matching `deny`\n",
+    );
+    out.push_str(&layers_toml(&preset.layers()));
+    out
+}
+
 /// Generate a starter .archmap.toml configuration file with all defaults documented
 pub fn generate_config_template() -> String {
     r#"# Archmap Configuration
 # This file configures architectural analysis for your project.
 
+# Generated Files
+# Files whose first lines contain one of these markers, or whose path matches one of
+# these patterns, are treated as generated: they stay in the dependency graph but
+# issues in them are not reported (use --include-generated to report them anyway).
+generated_markers = [
+    "@generated",
+    "Code generated",
+    "DO NOT EDIT",
+    "<auto-generated",
+    "This file is automatically generated",
+]
+generated_paths = [
+    "**/*.pb.rs",
+    "**/*_pb2.py",
+    "**/*_pb2_grpc.py",
+    "**/*.generated.ts",
+    "**/*.pb.ts",
+    "**/__generated__/**",
+]
+
+# Test Harness Files
+# Test files that import no production code are reported as EmptyTest, since the
+# code they tested is usually gone. Files matching these patterns (shared fixtures,
+# harnesses, test entry points) are exempt. Rust integration tests are exempt by
+# default because they import the crate by name, which archmap doesn't resolve.
+test_harness_paths = [
+    "**/conftest.py",
+    "**/tests/common/**",
+    "**/tests/common.rs",
+    "**/tests/*.rs",
+    "**/fixtures/**",
+    "**/setupTests.*",
+    "**/jest.setup.*",
+]
+
+# Disabled Checks
+# Issue kinds whose detectors never run, e.g. ["LowCohesion", "DeepDependencyChain"].
+# Same as passing --disable for each. Kinds: CircularDependency, GodObject,
+# HighCoupling, BoundaryViolation, DeepDependencyChain, LowCohesion, FatModule,
+# FatSharedModule, ScatteredImplementation, TooManyImports, GodPackage, EmptyTest,
+# RoleViolation
+disabled_checks = []
+
+# Opt-in Checks
+# Noisier checks that only run when listed here (or passed with --enable):
+# DeadPublicFunction flags public functions whose name appears nowhere else in
+# the project, which misses calls made through macros or reflection
+enabled_checks = []
+
+# Worker threads for file discovery and parsing. Same as --threads.
+# Default: 0 (one per core; half of them in watch mode)
+threads = 0
+
+# Files skipped during discovery, each reported with the reason: larger than
+# max_file_size_kb, or with a line longer than max_line_length (minified code).
+# 0 turns a limit off; --no-size-limit turns both off. Binary files (a NUL byte
+# in the first 8 KB) are always skipped.
+# Default: 1024 and 5000
+max_file_size_kb = 1024
+max_line_length = 5000
+
+# Milliseconds one file may take to parse. A file that takes longer (say, a
+# huge generated bundle) is skipped with a warning, as is a file the parser
+# crashes on. 0 turns the limit off.
+# Default: 5000
+parse_timeout_ms = 5000
+
+# Descend into symlinked directories, e.g. shared packages linked into several
+# services. A file reached through more than one path is analyzed once, under
+# the first path in sort order. Same as --follow-symlinks.
+# Default: false
+follow_symlinks = false
+
+# Keep parse results in .archmap/cache/ and reuse them for files whose contents
+# haven't changed. The cache is rebuilt after an archmap upgrade or a change to
+# the boundaries. Same as --no-cache when false.
+# Default: true
+cache = true
+
+# Least severe issues reported by analyze, ai, diff, list and tui (info, warn or
+# error) unless --min-severity is given. Exit codes still count every issue.
+# Default: info (warn for the graph view's issue counts)
+# min_severity = "info"
+
+# How LowCohesion scores a module against min_cohesion:
+# "imports" weighs internal imports against distinct external crates;
+# "lcom" is the share of function pairs whose bodies mention an import in
+# common, which also catches modules that import little but do unrelated things
+# Default: "imports"
+cohesion_method = "imports"
+
 [thresholds]
 # Maximum lines before a file is flagged as a "god object"
 # Default: 500
@@ -243,6 +1048,12 @@ god_object_lines = 500
 # Default: 5
 coupling_fanin = 5
 
+# Flag modules whose fan-in is above this percentile of all modules' fan-in
+# instead, so one config suits a 20-module project and a 2000-module one alike.
+# 95 flags roughly the top 5%. Replaces coupling_fanin when set.
+# Default: unset
+# coupling_fanin_percentile = 95
+
 # Minimum number of boundary violations before reporting
 # Default: 2
 boundary_violation_min = 2
@@ -267,6 +1078,28 @@ fat_module_private_functions = 8
 # Default: 100 lines per export maximum
 fat_module_lines_per_export = 100.0
 
+# Fat shared module detection - modules matching expected_high_coupling (configs,
+# constants, shared types) are exempt from coupling checks, but one that also holds
+# this many non-trivial functions mixes data with behavior everyone depends on
+# Default: 3
+shared_module_functions = 3
+
+# A trait or interface implemented in this many different modules is reported
+# as a scattered implementation
+# Default: 8
+scattered_impl_files = 8
+
+# Maximum imports in one module, counting external crates and packages too
+# (unlike fan-out, which only counts imports of project modules)
+# Default: 30
+max_imports = 30
+
+# A top-level directory (src/<dir> below a source root) with more modules or
+# lines than this is reported as a god package
+# Default: 40 modules, 10000 lines
+god_package_modules = 40
+god_package_lines = 10000
+
 # Expected High Coupling
 # Glob patterns for modules where high fan-in is expected and shouldn't be flagged.
 # Core domain models, config files, and index/entry modules typically have high coupling.
@@ -345,6 +1178,17 @@ indicators = [
 suggestion = "Consider centralizing file operations or using dependency injection"
 allowed_in = ["**/fs.rs", "**/io.rs", "**/io/**", "**/storage/**"]
 
+# Expectations
+# Named assertions checked by `archmap check`, which exits non-zero if any fail.
+# Layer rules are globs relative to the project root.
+# [expectations]
+# max_cycles = 0
+# max_god_objects = 2
+#
+# [expectations.layers.core_independent]
+# from = "src/core/**"
+# deny = ["src/app/**", "src/cli/**"]
+
 # Custom boundaries example (uncomment to use):
 # [boundaries.logging]
 # name = "Logging"
@@ -355,3 +1199,187 @@ allowed_in = ["**/fs.rs", "**/io.rs", "**/io/**", "**/storage/**"]
 "#
     .to_string()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fs::MemoryFileSystem;
+
+    fn parse(content: &str) -> Config {
+        let fs = MemoryFileSystem::new().with_file("/cfg.toml", content);
+        Config::load_file_with_fs(Path::new("/cfg.toml"), &fs).unwrap()
+    }
+
+    #[test]
+    fn test_coupling_percentile_must_be_below_100() {
+        let config = parse("[thresholds]\ncoupling_fanin_percentile = 95\n");
+        assert_eq!(config.thresholds.coupling_fanin_percentile, Some(95.0));
+
+        let fs = MemoryFileSystem::new().with_file(
+            "/cfg.toml",
+            "[thresholds]\ncoupling_fanin_percentile = 150\n",
+        );
+        let err = Config::load_file_with_fs(Path::new("/cfg.toml"), &fs).unwrap_err();
+        assert!(err.to_string().contains("coupling_fanin_percentile"));
+    }
+
+    /// The user config and the project's `.archmap.toml`, layered
+    fn layered(user: &str, project: &str) -> Config {
+        let fs = MemoryFileSystem::new()
+            .with_file("/user.toml", user)
+            .with_file("/project.toml", project);
+        let paths = [PathBuf::from("/user.toml"), PathBuf::from("/project.toml")];
+        Config::load_files_with_fs(&paths, &fs).unwrap()
+    }
+
+    #[test]
+    fn test_layers_merge_field_by_field() {
+        let merged = layered(
+            r#"
+            disabled_checks = ["GodObject"]
+            min_severity = "warn"
+
+            [thresholds]
+            coupling_fanin = 12
+            min_cohesion = 0.5
+
+            [boundaries.logging]
+            indicators = ["log::"]
+
+            [boundaries.network]
+            indicators = ["reqwest::"]
+
+            [expectations]
+            max_cycles = 0
+
+            [expectations.layers.core]
+            from = "src/core/**"
+            deny = ["src/cli/**"]
+            "#,
+            r#"
+            disabled_checks = ["LowCohesion"]
+
+            [thresholds]
+            coupling_fanin = 20
+
+            [boundaries.network]
+            indicators = ["fetch("]
+
+            [expectations.layers.core]
+            from = "src/domain/**"
+            deny = ["src/web/**"]
+            "#,
+        );
+        assert_eq!(merged.thresholds.coupling_fanin, 20);
+        assert_eq!(merged.thresholds.min_cohesion, 0.5);
+        assert_eq!(merged.thresholds.god_object_lines, 500);
+        assert_eq!(merged.min_severity, Some(IssueSeverity::Warn));
+        assert_eq!(merged.disabled_checks, ["LowCohesion"]);
+        assert_eq!(merged.expectations.max_cycles, Some(0));
+        assert_eq!(merged.expectations.layers.len(), 1);
+        assert_eq!(merged.expectations.layers[0].from, "src/domain/**");
+
+        let indicators = |name: &str| {
+            let boundary = merged.boundaries.iter().find(|b| b.name == name).unwrap();
+            boundary.indicators.clone()
+        };
+        assert_eq!(merged.boundaries.len(), 2);
+        assert_eq!(indicators("Logging"), ["log::"]);
+        assert_eq!(indicators("Network"), ["fetch("]);
+    }
+
+    #[test]
+    fn test_project_setting_the_default_still_wins() {
+        let merged = layered(
+            r#"
+            cache = false
+            disabled_checks = ["GodObject"]
+
+            [thresholds]
+            god_object_lines = 800
+            "#,
+            r#"
+            cache = true
+            disabled_checks = []
+
+            [thresholds]
+            god_object_lines = 500
+            "#,
+        );
+        assert_eq!(merged.thresholds.god_object_lines, 500);
+        assert!(merged.cache);
+        assert!(!merged.is_check_disabled("GodObject"));
+    }
+
+    #[test]
+    fn test_project_can_turn_a_user_setting_off() {
+        let merged = layered(
+            "follow_symlinks = true\ncache = true\n",
+            "follow_symlinks = false\ncache = false\n",
+        );
+        assert!(!merged.follow_symlinks);
+        assert!(!merged.cache);
+    }
+
+    #[test]
+    fn test_layers_over_nothing_match_a_single_file() {
+        let project = "[boundaries.logging]\nindicators = [\"log::\"]\n";
+        assert_eq!(layered("", project).boundaries, parse(project).boundaries);
+
+        let merged = layered("threads = 4\n", "");
+        assert_eq!(merged.threads, 4);
+        assert_eq!(merged.boundaries, Config::default().boundaries);
+    }
+
+    #[test]
+    fn test_preset_templates_parse_back_to_the_preset() {
+        use clap::ValueEnum;
+
+        for &preset in Preset::value_variants() {
+            let config = parse(&generate_preset_template(preset));
+            assert_eq!(config.expectations.layers.len(), preset.layers().len());
+            for layer in preset.layers() {
+                assert!(config.expectations.layers.contains(&layer), "{:?}", layer);
+            }
+            assert_eq!(config.boundaries.len(), 3);
+            for boundary in preset.boundaries() {
+                assert!(config.boundaries.contains(&boundary), "{}", boundary.name);
+            }
+            assert_eq!(config.thresholds, Thresholds::default());
+        }
+
+        // The layer a preset contributes says the same as its template
+        for &preset in Preset::value_variants() {
+            let layered = Config::from_raw(preset.layer()).unwrap();
+            assert_eq!(layered.boundaries.len(), preset.boundaries().len());
+            for boundary in preset.boundaries() {
+                assert!(layered.boundaries.contains(&boundary), "{}", boundary.name);
+            }
+            assert_eq!(layered.expectations.layers.len(), preset.layers().len());
+        }
+
+        let hexagonal = Preset::Hexagonal.config();
+        assert!(
+            hexagonal
+                .boundaries
+                .iter()
+                .all(|b| b.allowed_in.iter().any(|p| p == "**/adapters/**"))
+        );
+        assert_eq!(hexagonal.expectations.layers[0].from, "**/domain/**");
+    }
+
+    #[test]
+    fn test_preset_only_stands_in_for_a_missing_config() {
+        let dir = std::env::temp_dir().join(format!("archmap-preset-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let seeded = Config::load_preset(&dir, Preset::Mvc);
+        std::fs::write(dir.join(".archmap.toml"), "threads = 2\n").unwrap();
+        let existing = Config::load_preset(&dir, Preset::Mvc);
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(seeded.unwrap().expectations.layers, Preset::Mvc.layers());
+        let existing = existing.unwrap();
+        assert_eq!(existing.threads, 2);
+        assert!(existing.expectations.layers.is_empty());
+    }
+}
@@ -3,8 +3,16 @@
 //! This module provides a `FileSystem` trait that abstracts file operations,
 //! allowing for easy mocking in tests and consistent error handling.
 
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt;
 use std::io::{self, Write};
-use std::path::Path;
+use std::path::{Component, Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::SystemTime;
+
+/// Whether [`resolve_path`] canonicalizes; off with `--no-canonicalize`
+static CANONICALIZE: AtomicBool = AtomicBool::new(true);
 
 /// Trait for filesystem operations, enabling dependency injection and testing.
 pub trait FileSystem: Send + Sync {
@@ -20,8 +28,180 @@ pub trait FileSystem: Send + Sync {
     /// Get the modification time of a file.
     fn modified(&self, path: &Path) -> io::Result<std::time::SystemTime>;
 
+    /// Size and modification time of a file. The default reads the whole
+    /// file to measure it.
+    fn metadata(&self, path: &Path) -> io::Result<FsMetadata> {
+        Ok(FsMetadata {
+            len: self.read_to_string(path)?.len() as u64,
+            modified: self.modified(path)?,
+        })
+    }
+
     /// Create and return a writer for a file (for output streams).
     fn create_file(&self, path: &Path) -> io::Result<Box<dyn Write + Send>>;
+
+    /// Files that exist only in this filesystem. Directory walks can't see
+    /// them, so analysis asks for them explicitly.
+    fn virtual_files(&self) -> Vec<PathBuf> {
+        Vec::new()
+    }
+
+    /// Files a directory walk still finds on disk that this filesystem has
+    /// removed. Analysis skips them.
+    fn hidden_files(&self) -> Vec<PathBuf> {
+        Vec::new()
+    }
+
+    /// Whether files live on disk, where directory walks and canonicalization
+    /// can find them. A filesystem that returns `false` must list every file
+    /// in [`FileSystem::virtual_files`].
+    fn on_disk(&self) -> bool {
+        true
+    }
+
+    /// Every file under `root`, in no particular order. The default walks the
+    /// disk when [`FileSystem::on_disk`], adds the [`FileSystem::virtual_files`]
+    /// under `root` and leaves out the [`FileSystem::hidden_files`].
+    fn walk<'a>(
+        &'a self,
+        root: &Path,
+        options: &WalkOptions,
+    ) -> Box<dyn Iterator<Item = PathBuf> + Send + 'a> {
+        let hidden: HashSet<PathBuf> = self.hidden_files().into_iter().collect();
+        let on_disk = self.on_disk().then(|| walk_disk(root, options));
+        let virtual_files = virtual_files_under(self.virtual_files(), root, options);
+        Box::new(
+            on_disk
+                .into_iter()
+                .flatten()
+                .chain(virtual_files)
+                .filter(move |path| !hidden.contains(path)),
+        )
+    }
+}
+
+/// Size and modification time of a file
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FsMetadata {
+    /// Size in bytes
+    pub len: u64,
+    pub modified: SystemTime,
+}
+
+/// What [`FileSystem::walk`] leaves out besides hidden and gitignored entries
+#[derive(Debug, Clone, Default)]
+pub struct WalkOptions {
+    /// Directory names skipped along with everything below them
+    pub exclude: Vec<String>,
+    /// Descend into symlinked directories; otherwise symlinks, to files
+    /// included, are skipped
+    pub follow_links: bool,
+}
+
+/// Files under `root` on disk, skipping hidden, gitignored and excluded
+/// entries. Unreadable entries and symlink loops are skipped too.
+pub fn walk_disk(root: &Path, options: &WalkOptions) -> Box<dyn Iterator<Item = PathBuf> + Send> {
+    let exclude = options.exclude.clone();
+    let walker = ignore::WalkBuilder::new(root)
+        .hidden(true)
+        .git_ignore(true)
+        .follow_links(options.follow_links)
+        .filter_entry(move |entry| !is_excluded(entry.path(), &exclude))
+        .build();
+    Box::new(
+        walker
+            .flatten()
+            .filter(|entry| entry.file_type().is_some_and(|t| t.is_file()))
+            .map(ignore::DirEntry::into_path),
+    )
+}
+
+/// The paths in `files` under `root` and outside excluded directories
+fn virtual_files_under(
+    files: Vec<PathBuf>,
+    root: &Path,
+    options: &WalkOptions,
+) -> impl Iterator<Item = PathBuf> + Send + use<> {
+    let root = root.to_path_buf();
+    let exclude = options.exclude.clone();
+    files
+        .into_iter()
+        .filter(move |path| path.starts_with(&root) && !is_excluded(path, &exclude))
+}
+
+/// Whether `path` is, or lies inside, a directory named by an exclusion pattern
+pub(crate) fn is_excluded(path: &Path, exclude: &[String]) -> bool {
+    exclude.iter().any(|pattern| {
+        path.ends_with(pattern) || path.to_string_lossy().contains(&format!("/{}/", pattern))
+    })
+}
+
+/// Text of a file as read from disk: UTF-8, or UTF-16 when it starts with a
+/// UTF-16 byte order mark or looks like UTF-16 without one. A UTF-8 byte order
+/// mark is dropped, since parsers would take it for part of the first token.
+/// Transcoding UTF-16 needs the `encoding` feature (on by default).
+pub fn decode_text(mut bytes: Vec<u8>) -> io::Result<String> {
+    if bytes.starts_with(b"\xef\xbb\xbf") {
+        bytes.drain(..3);
+    } else if let Some((big_endian, bom)) = utf16_layout(&bytes) {
+        return decode_utf16(&bytes[bom..], big_endian);
+    }
+    String::from_utf8(bytes)
+        .map_err(|e| invalid_text(format!("invalid UTF-8 ({})", e.utf8_error())))
+}
+
+/// Byte order and byte order mark length of UTF-16 text, or `None` for
+/// anything else. Without a mark, UTF-16 is recognized by the NUL bytes that
+/// ASCII characters leave on one side of each unit: source code is mostly
+/// ASCII, and never contains NUL itself.
+fn utf16_layout(bytes: &[u8]) -> Option<(bool, usize)> {
+    match bytes {
+        [0xff, 0xfe, ..] => return Some((false, 2)),
+        [0xfe, 0xff, ..] => return Some((true, 2)),
+        _ => {}
+    }
+    if bytes.len() < 2 || !bytes.len().is_multiple_of(2) {
+        return None;
+    }
+    let units = bytes.len() / 2;
+    let (high_first, low_first) = bytes.chunks_exact(2).fold((0, 0), |(first, second), pair| {
+        (
+            first + usize::from(pair[0] == 0),
+            second + usize::from(pair[1] == 0),
+        )
+    });
+    let mostly = |nuls: usize, other: usize| nuls * 2 >= units && nuls > other * 4;
+    if mostly(low_first, high_first) {
+        Some((false, 0))
+    } else if mostly(high_first, low_first) {
+        Some((true, 0))
+    } else {
+        None
+    }
+}
+
+#[cfg(feature = "encoding")]
+fn decode_utf16(bytes: &[u8], big_endian: bool) -> io::Result<String> {
+    let encoding = if big_endian {
+        encoding_rs::UTF_16BE
+    } else {
+        encoding_rs::UTF_16LE
+    };
+    encoding
+        .decode_without_bom_handling_and_without_replacement(bytes)
+        .map(|text| text.into_owned())
+        .ok_or_else(|| invalid_text(format!("invalid {}", encoding.name())))
+}
+
+#[cfg(not(feature = "encoding"))]
+fn decode_utf16(_bytes: &[u8], _big_endian: bool) -> io::Result<String> {
+    Err(invalid_text(
+        "UTF-16 text needs archmap's `encoding` feature".to_string(),
+    ))
+}
+
+fn invalid_text(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
 }
 
 /// Real filesystem implementation using std::fs.
@@ -36,7 +216,7 @@ impl RealFs {
 
 impl FileSystem for RealFs {
     fn read_to_string(&self, path: &Path) -> io::Result<String> {
-        std::fs::read_to_string(path)
+        decode_text(std::fs::read(path)?)
     }
 
     fn write(&self, path: &Path, content: &str) -> io::Result<()> {
@@ -51,12 +231,550 @@ impl FileSystem for RealFs {
         std::fs::metadata(path)?.modified()
     }
 
+    fn metadata(&self, path: &Path) -> io::Result<FsMetadata> {
+        let metadata = std::fs::metadata(path)?;
+        Ok(FsMetadata {
+            len: metadata.len(),
+            modified: metadata.modified()?,
+        })
+    }
+
     fn create_file(&self, path: &Path) -> io::Result<Box<dyn Write + Send>> {
         let file = std::fs::File::create(path)?;
         Ok(Box::new(io::BufWriter::new(file)))
     }
 }
 
+/// In-memory changes layered over another filesystem, e.g. unsaved editor
+/// buffers and files deleted in the editor but not yet on disk. Reads of
+/// overlaid paths see the overlay; everything else, including all writes,
+/// goes to the base.
+///
+/// Paths are resolved as they are added, the way project roots are (see
+/// [`resolve_virtual_path`]), so a relative or symlinked path matches the
+/// paths analysis walks.
+pub struct OverlayFs<'a> {
+    base: &'a dyn FileSystem,
+    /// `None` marks a removed file
+    files: RwLock<HashMap<PathBuf, Option<String>>>,
+}
+
+impl<'a> OverlayFs<'a> {
+    pub fn new(base: &'a dyn FileSystem) -> Self {
+        Self {
+            base,
+            files: RwLock::new(HashMap::new()),
+        }
+    }
+
+    /// Replace (or add) the contents of `path`.
+    pub fn with_file(self, path: impl Into<PathBuf>, content: impl Into<String>) -> Self {
+        self.insert(path, content);
+        self
+    }
+
+    /// Replace (or add) the contents of `path`.
+    pub fn insert(&self, path: impl Into<PathBuf>, content: impl Into<String>) {
+        let path = self.key(&path.into());
+        self.files
+            .write()
+            .unwrap()
+            .insert(path, Some(content.into()));
+    }
+
+    /// Hide `path`, as if it were deleted: reads fail with `NotFound` and
+    /// analysis leaves it out, even though the base still has it.
+    pub fn remove(&self, path: impl Into<PathBuf>) {
+        let path = self.key(&path.into());
+        self.files.write().unwrap().insert(path, None);
+    }
+
+    fn key(&self, path: &Path) -> PathBuf {
+        if self.base.on_disk() {
+            resolve_virtual_path(path)
+        } else {
+            normalize_path(path)
+        }
+    }
+
+    fn entry(&self, path: &Path) -> Option<Option<String>> {
+        self.files.read().unwrap().get(path).cloned()
+    }
+}
+
+impl FileSystem for OverlayFs<'_> {
+    fn read_to_string(&self, path: &Path) -> io::Result<String> {
+        match self.entry(path) {
+            Some(Some(content)) => Ok(content),
+            Some(None) => Err(removed(path)),
+            None => self.base.read_to_string(path),
+        }
+    }
+
+    fn write(&self, path: &Path, content: &str) -> io::Result<()> {
+        self.base.write(path, content)
+    }
+
+    fn exists(&self, path: &Path) -> bool {
+        match self.entry(path) {
+            Some(entry) => entry.is_some(),
+            None => self.base.exists(path),
+        }
+    }
+
+    fn modified(&self, path: &Path) -> io::Result<std::time::SystemTime> {
+        match self.entry(path) {
+            Some(Some(_)) => Ok(std::time::SystemTime::now()),
+            Some(None) => Err(removed(path)),
+            None => self.base.modified(path),
+        }
+    }
+
+    fn metadata(&self, path: &Path) -> io::Result<FsMetadata> {
+        match self.entry(path) {
+            Some(Some(content)) => Ok(FsMetadata {
+                len: content.len() as u64,
+                modified: SystemTime::now(),
+            }),
+            Some(None) => Err(removed(path)),
+            None => self.base.metadata(path),
+        }
+    }
+
+    fn create_file(&self, path: &Path) -> io::Result<Box<dyn Write + Send>> {
+        self.base.create_file(path)
+    }
+
+    fn on_disk(&self) -> bool {
+        self.base.on_disk()
+    }
+
+    fn virtual_files(&self) -> Vec<PathBuf> {
+        let files = self.files.read().unwrap();
+        let mut paths: Vec<PathBuf> = files
+            .iter()
+            .filter(|(path, entry)| entry.is_some() && !self.base.exists(path))
+            .map(|(path, _)| path.clone())
+            .collect();
+        paths.extend(
+            self.base
+                .virtual_files()
+                .into_iter()
+                .filter(|path| !matches!(files.get(path), Some(None))),
+        );
+        paths.sort();
+        paths
+    }
+
+    fn hidden_files(&self) -> Vec<PathBuf> {
+        let mut paths: Vec<PathBuf> = self
+            .files
+            .read()
+            .unwrap()
+            .iter()
+            .filter(|(_, entry)| entry.is_none())
+            .map(|(path, _)| path.clone())
+            .collect();
+        paths.extend(self.base.hidden_files());
+        paths.sort();
+        paths
+    }
+}
+
+fn removed(path: &Path) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::NotFound,
+        format!("file removed: {}", path.display()),
+    )
+}
+
+/// Memory [`CachingFileSystem::new`] allows for cached contents
+pub const DEFAULT_CACHE_CAPACITY: usize = 256 * 1024 * 1024;
+
+/// Remembers what another filesystem read, so parsing, boundary checks and
+/// source collection within one run read each file once. An entry is reused
+/// while the file's modification time and size are unchanged; past the
+/// memory cap, the least recently read files are dropped. Everything but
+/// `read_to_string` goes straight to the base, and writes drop the entry.
+pub struct CachingFileSystem<'a> {
+    base: &'a dyn FileSystem,
+    capacity: usize,
+    cache: Mutex<ReadCache>,
+}
+
+/// How often a [`CachingFileSystem`] could answer a read itself
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CacheStats {
+    pub hits: usize,
+    pub misses: usize,
+    /// Entries dropped to stay under the memory cap
+    pub evictions: usize,
+    /// Bytes of file contents held
+    pub bytes: usize,
+}
+
+impl fmt::Display for CacheStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} hits, {} misses, {} evicted, {} KB held",
+            self.hits,
+            self.misses,
+            self.evictions,
+            self.bytes / 1024
+        )
+    }
+}
+
+#[derive(Default)]
+struct ReadCache {
+    entries: HashMap<PathBuf, CachedFile>,
+    /// Paths by the tick of their last read, oldest first
+    recency: BTreeMap<u64, PathBuf>,
+    tick: u64,
+    stats: CacheStats,
+}
+
+struct CachedFile {
+    modified: SystemTime,
+    size: u64,
+    content: String,
+    last_read: u64,
+}
+
+impl ReadCache {
+    fn touch(&mut self, path: &Path) -> u64 {
+        self.tick += 1;
+        self.recency.insert(self.tick, path.to_path_buf());
+        self.tick
+    }
+
+    fn remove(&mut self, path: &Path) {
+        if let Some(entry) = self.entries.remove(path) {
+            self.recency.remove(&entry.last_read);
+            self.stats.bytes -= entry.content.len();
+        }
+    }
+}
+
+impl<'a> CachingFileSystem<'a> {
+    /// Cache reads from `base`, holding up to [`DEFAULT_CACHE_CAPACITY`] bytes.
+    pub fn new(base: &'a dyn FileSystem) -> Self {
+        Self {
+            base,
+            capacity: DEFAULT_CACHE_CAPACITY,
+            cache: Mutex::new(ReadCache::default()),
+        }
+    }
+
+    /// Hold at most `bytes` of file contents; larger files aren't cached at all.
+    pub fn with_capacity(mut self, bytes: usize) -> Self {
+        self.capacity = bytes;
+        self
+    }
+
+    pub fn stats(&self) -> CacheStats {
+        self.cache.lock().unwrap().stats
+    }
+}
+
+impl FileSystem for CachingFileSystem<'_> {
+    fn read_to_string(&self, path: &Path) -> io::Result<String> {
+        // Without a stamp to check against, nothing can be reused safely
+        let Ok(FsMetadata {
+            len: size,
+            modified,
+        }) = self.base.metadata(path)
+        else {
+            return self.base.read_to_string(path);
+        };
+
+        {
+            let mut cache = self.cache.lock().unwrap();
+            let fresh = cache
+                .entries
+                .get(path)
+                .is_some_and(|entry| entry.modified == modified && entry.size == size);
+            if fresh {
+                cache.stats.hits += 1;
+                let previous = cache.entries[path].last_read;
+                cache.recency.remove(&previous);
+                let tick = cache.touch(path);
+                let entry = cache.entries.get_mut(path).unwrap();
+                entry.last_read = tick;
+                return Ok(entry.content.clone());
+            }
+            cache.stats.misses += 1;
+            cache.remove(path);
+        }
+
+        // Read without holding the lock, so threads don't wait on each other's I/O
+        let content = self.base.read_to_string(path)?;
+        if content.len() > self.capacity {
+            return Ok(content);
+        }
+
+        let mut cache = self.cache.lock().unwrap();
+        // Another thread may have read the same file meanwhile
+        cache.remove(path);
+        while cache.stats.bytes + content.len() > self.capacity {
+            let Some((_, oldest)) = cache.recency.pop_first() else {
+                break;
+            };
+            if let Some(entry) = cache.entries.remove(&oldest) {
+                cache.stats.bytes -= entry.content.len();
+                cache.stats.evictions += 1;
+            }
+        }
+        let tick = cache.touch(path);
+        cache.stats.bytes += content.len();
+        cache.entries.insert(
+            path.to_path_buf(),
+            CachedFile {
+                modified,
+                size,
+                content: content.clone(),
+                last_read: tick,
+            },
+        );
+        Ok(content)
+    }
+
+    fn write(&self, path: &Path, content: &str) -> io::Result<()> {
+        self.cache.lock().unwrap().remove(path);
+        self.base.write(path, content)
+    }
+
+    fn exists(&self, path: &Path) -> bool {
+        self.base.exists(path)
+    }
+
+    fn modified(&self, path: &Path) -> io::Result<SystemTime> {
+        self.base.modified(path)
+    }
+
+    fn metadata(&self, path: &Path) -> io::Result<FsMetadata> {
+        self.base.metadata(path)
+    }
+
+    fn create_file(&self, path: &Path) -> io::Result<Box<dyn Write + Send>> {
+        self.cache.lock().unwrap().remove(path);
+        self.base.create_file(path)
+    }
+
+    fn virtual_files(&self) -> Vec<PathBuf> {
+        self.base.virtual_files()
+    }
+
+    fn hidden_files(&self) -> Vec<PathBuf> {
+        self.base.hidden_files()
+    }
+
+    fn on_disk(&self) -> bool {
+        self.base.on_disk()
+    }
+
+    fn walk<'b>(
+        &'b self,
+        root: &Path,
+        options: &WalkOptions,
+    ) -> Box<dyn Iterator<Item = PathBuf> + Send + 'b> {
+        self.base.walk(root, options)
+    }
+}
+
+/// A filesystem held entirely in memory, for analyzing sources that were never
+/// written to disk (e.g. fetched from a git host). Directories exist implicitly
+/// when a file lies under them; writes and created files land in the map.
+#[derive(Debug, Default, Clone)]
+pub struct MemoryFileSystem {
+    files: Arc<RwLock<HashMap<PathBuf, (String, SystemTime)>>>,
+}
+
+impl MemoryFileSystem {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add (or replace) the contents of `path`.
+    pub fn with_file(self, path: impl Into<PathBuf>, content: impl Into<String>) -> Self {
+        self.insert(path, content);
+        self
+    }
+
+    /// Add (or replace) the contents of `path`.
+    pub fn insert(&self, path: impl Into<PathBuf>, content: impl Into<String>) {
+        self.files
+            .write()
+            .unwrap()
+            .insert(path.into(), (content.into(), SystemTime::now()));
+    }
+
+    /// Remove `path`, returning its contents if it was there.
+    pub fn remove(&self, path: &Path) -> Option<String> {
+        self.files
+            .write()
+            .unwrap()
+            .remove(path)
+            .map(|(content, _)| content)
+    }
+}
+
+impl FileSystem for MemoryFileSystem {
+    fn read_to_string(&self, path: &Path) -> io::Result<String> {
+        self.files
+            .read()
+            .unwrap()
+            .get(path)
+            .map(|(content, _)| content.clone())
+            .ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::NotFound,
+                    format!("file not found: {}", path.display()),
+                )
+            })
+    }
+
+    fn write(&self, path: &Path, content: &str) -> io::Result<()> {
+        self.insert(path, content);
+        Ok(())
+    }
+
+    fn exists(&self, path: &Path) -> bool {
+        self.files
+            .read()
+            .unwrap()
+            .keys()
+            .any(|file| file.starts_with(path))
+    }
+
+    fn modified(&self, path: &Path) -> io::Result<SystemTime> {
+        self.files
+            .read()
+            .unwrap()
+            .get(path)
+            .map(|(_, modified)| *modified)
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, path.display().to_string()))
+    }
+
+    fn metadata(&self, path: &Path) -> io::Result<FsMetadata> {
+        self.files
+            .read()
+            .unwrap()
+            .get(path)
+            .map(|(content, modified)| FsMetadata {
+                len: content.len() as u64,
+                modified: *modified,
+            })
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, path.display().to_string()))
+    }
+
+    fn create_file(&self, path: &Path) -> io::Result<Box<dyn Write + Send>> {
+        self.insert(path, "");
+        Ok(Box::new(MemoryWriter {
+            fs: self.clone(),
+            path: path.to_path_buf(),
+            buffer: Vec::new(),
+        }))
+    }
+
+    fn virtual_files(&self) -> Vec<PathBuf> {
+        let mut paths: Vec<PathBuf> = self.files.read().unwrap().keys().cloned().collect();
+        paths.sort();
+        paths
+    }
+
+    fn on_disk(&self) -> bool {
+        false
+    }
+
+    fn walk<'a>(
+        &'a self,
+        root: &Path,
+        options: &WalkOptions,
+    ) -> Box<dyn Iterator<Item = PathBuf> + Send + 'a> {
+        Box::new(virtual_files_under(self.virtual_files(), root, options))
+    }
+}
+
+/// Collects what is written to a [`MemoryFileSystem`] file, storing it on
+/// flush and drop.
+struct MemoryWriter {
+    fs: MemoryFileSystem,
+    path: PathBuf,
+    buffer: Vec<u8>,
+}
+
+impl Write for MemoryWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.buffer.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.fs.insert(
+            &self.path,
+            String::from_utf8_lossy(&self.buffer).into_owned(),
+        );
+        Ok(())
+    }
+}
+
+impl Drop for MemoryWriter {
+    fn drop(&mut self) {
+        let _ = self.flush();
+    }
+}
+
+/// Turn canonicalization of user-supplied paths on or off.
+pub fn set_canonicalize(enabled: bool) {
+    CANONICALIZE.store(enabled, Ordering::Relaxed);
+}
+
+/// Resolve a user-supplied path to an absolute one. By default it is
+/// canonicalized, which requires it to exist and resolves symlinks; with
+/// `--no-canonicalize` it is only made absolute and normalized, so it may be
+/// virtual and displays as given.
+pub fn resolve_path(path: &Path) -> io::Result<PathBuf> {
+    resolve(path, CANONICALIZE.load(Ordering::Relaxed))
+}
+
+fn resolve(path: &Path, canonicalize: bool) -> io::Result<PathBuf> {
+    if canonicalize {
+        path.canonicalize()
+    } else {
+        Ok(normalize_path(&std::path::absolute(path)?))
+    }
+}
+
+/// Resolve a path that may not exist yet: the nearest existing parent is
+/// resolved so it lines up with the analyzed roots.
+pub fn resolve_virtual_path(path: &Path) -> PathBuf {
+    let absolute = std::path::absolute(path).unwrap_or_else(|_| path.to_path_buf());
+    if let Ok(resolved) = resolve_path(&absolute) {
+        return resolved;
+    }
+    match (absolute.parent(), absolute.file_name()) {
+        (Some(parent), Some(name)) => resolve_virtual_path(parent).join(name),
+        _ => absolute,
+    }
+}
+
+/// Lexically resolve `.` and `..` components without touching the filesystem.
+pub fn normalize_path(path: &Path) -> PathBuf {
+    let mut out = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                out.pop();
+            }
+            other => out.push(other),
+        }
+    }
+    out
+}
+
 /// Global default filesystem for use when dependency injection isn't practical.
 /// This provides a migration path - code can start using `default_fs()` and
 /// later be refactored to accept `&dyn FileSystem` parameters.
@@ -68,7 +786,6 @@ pub fn default_fs() -> &'static RealFs {
 #[cfg(test)]
 pub mod mock {
     use super::*;
-    use std::collections::HashMap;
     use std::sync::RwLock;
 
     /// In-memory filesystem for testing.
@@ -173,5 +890,237 @@ pub mod mock {
             assert_eq!(fs.read_to_string(Path::new("/a.txt")).unwrap(), "content a");
             assert_eq!(fs.read_to_string(Path::new("/b.txt")).unwrap(), "content b");
         }
+
+        #[test]
+        fn test_overlay_fs_shadows_base() {
+            let base = MockFs::with_files([(Path::new("/a.txt"), "on disk")]);
+            let fs = OverlayFs::new(&base)
+                .with_file("/a.txt", "edited")
+                .with_file("/new.txt", "unsaved");
+
+            assert_eq!(fs.read_to_string(Path::new("/a.txt")).unwrap(), "edited");
+            assert!(fs.exists(Path::new("/new.txt")));
+            assert_eq!(fs.virtual_files(), vec![PathBuf::from("/new.txt")]);
+
+            // Writes bypass the overlay
+            fs.write(Path::new("/b.txt"), "saved").unwrap();
+            assert_eq!(base.read_to_string(Path::new("/b.txt")).unwrap(), "saved");
+        }
+
+        #[test]
+        fn test_overlay_fs_removes_and_restores() {
+            let base = MemoryFileSystem::new()
+                .with_file("/repo/a.rs", "on disk")
+                .with_file("/repo/b.rs", "kept");
+            let fs = OverlayFs::new(&base);
+            fs.remove("/repo/src/../a.rs");
+
+            let a = Path::new("/repo/a.rs");
+            assert!(!fs.exists(a));
+            let err = fs.read_to_string(a).unwrap_err();
+            assert_eq!(err.kind(), io::ErrorKind::NotFound);
+            assert_eq!(fs.virtual_files(), vec![PathBuf::from("/repo/b.rs")]);
+            assert_eq!(fs.hidden_files(), vec![PathBuf::from("/repo/a.rs")]);
+
+            // Inserting again replaces the tombstone
+            fs.insert(a, "edited");
+            assert_eq!(fs.read_to_string(a).unwrap(), "edited");
+            assert!(fs.hidden_files().is_empty());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resolve_without_canonicalizing_keeps_path_as_given() {
+        let missing = Path::new("/no/such/dir/../project/./src");
+        assert!(resolve(missing, true).is_err());
+        assert_eq!(
+            resolve(missing, false).unwrap(),
+            PathBuf::from("/no/such/project/src")
+        );
+
+        let relative = resolve(Path::new("src/../tests"), false).unwrap();
+        assert_eq!(relative, std::env::current_dir().unwrap().join("tests"));
+    }
+
+    #[test]
+    fn test_memory_fs_holds_writes_and_lists_every_file() {
+        let fs = MemoryFileSystem::new().with_file("/repo/src/lib.rs", "mod a;");
+        fs.write(Path::new("/repo/src/a.rs"), "pub fn a() {}")
+            .unwrap();
+        {
+            let mut report = fs.create_file(Path::new("/repo/report.md")).unwrap();
+            write!(report, "# Report").unwrap();
+        }
+
+        assert!(fs.exists(Path::new("/repo/src")));
+        assert!(!fs.exists(Path::new("/repo/tests")));
+        assert_eq!(
+            fs.read_to_string(Path::new("/repo/report.md")).unwrap(),
+            "# Report"
+        );
+        assert_eq!(
+            fs.remove(Path::new("/repo/src/lib.rs")).as_deref(),
+            Some("mod a;")
+        );
+        assert!(fs.read_to_string(Path::new("/repo/src/lib.rs")).is_err());
+        assert_eq!(
+            fs.virtual_files(),
+            [
+                PathBuf::from("/repo/report.md"),
+                PathBuf::from("/repo/src/a.rs")
+            ]
+        );
+        assert!(!fs.on_disk());
+    }
+
+    fn utf16(text: &str, bom: &[u8], unit: fn(u16) -> [u8; 2]) -> Vec<u8> {
+        let mut bytes = bom.to_vec();
+        bytes.extend(text.encode_utf16().flat_map(unit));
+        bytes
+    }
+
+    #[test]
+    fn test_decode_text_drops_utf8_byte_order_mark() {
+        assert_eq!(decode_text(b"let a;".to_vec()).unwrap(), "let a;");
+        assert_eq!(
+            decode_text(b"\xef\xbb\xbfimport x;".to_vec()).unwrap(),
+            "import x;"
+        );
+        let latin1 = decode_text(b"caf\xe9".to_vec()).unwrap_err();
+        assert_eq!(latin1.kind(), io::ErrorKind::InvalidData);
+        assert!(latin1.to_string().contains("invalid UTF-8"));
+    }
+
+    #[test]
+    fn test_utf16_is_recognized_with_or_without_byte_order_mark() {
+        let source = "const é = 1;";
+        let le = |bom: &[u8]| utf16(source, bom, u16::to_le_bytes);
+        let be = |bom: &[u8]| utf16(source, bom, u16::to_be_bytes);
+        assert_eq!(utf16_layout(&le(&[0xff, 0xfe])), Some((false, 2)));
+        assert_eq!(utf16_layout(&be(&[0xfe, 0xff])), Some((true, 2)));
+        assert_eq!(utf16_layout(&le(&[])), Some((false, 0)));
+        assert_eq!(utf16_layout(&be(&[])), Some((true, 0)));
+
+        assert_eq!(utf16_layout(source.as_bytes()), None);
+        assert_eq!(utf16_layout("é".repeat(8).as_bytes()), None);
+    }
+
+    #[test]
+    #[cfg(feature = "encoding")]
+    fn test_decode_text_transcodes_utf16() {
+        let source = "const é = 1;";
+        for bytes in [
+            utf16(source, &[0xff, 0xfe], u16::to_le_bytes),
+            utf16(source, &[0xfe, 0xff], u16::to_be_bytes),
+            utf16(source, &[], u16::to_le_bytes),
+            utf16(source, &[], u16::to_be_bytes),
+        ] {
+            assert_eq!(decode_text(bytes).unwrap(), source);
+        }
+
+        // An odd UTF-16 length and an unpaired surrogate
+        for bytes in [vec![0xff, 0xfe, 0x41], vec![0xff, 0xfe, 0x00, 0xd8]] {
+            let error = decode_text(bytes).unwrap_err();
+            assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+            assert!(error.to_string().contains("invalid UTF-16LE"));
+        }
+    }
+
+    #[test]
+    fn test_memory_fs_walks_and_measures_its_files() {
+        let fs = MemoryFileSystem::new()
+            .with_file("/repo/src/lib.rs", "mod a;")
+            .with_file("/repo/src/a.rs", "pub fn a() {}")
+            .with_file("/repo/vendor/dep.rs", "")
+            .with_file("/other/b.rs", "");
+
+        let options = WalkOptions {
+            exclude: vec!["vendor".to_string()],
+            ..Default::default()
+        };
+        let mut found: Vec<_> = fs.walk(Path::new("/repo"), &options).collect();
+        found.sort();
+        assert_eq!(
+            found,
+            [
+                PathBuf::from("/repo/src/a.rs"),
+                PathBuf::from("/repo/src/lib.rs")
+            ]
+        );
+
+        let metadata = fs.metadata(Path::new("/repo/src/a.rs")).unwrap();
+        assert_eq!(metadata.len, 13);
+        assert_eq!(
+            metadata.modified,
+            fs.modified(Path::new("/repo/src/a.rs")).unwrap()
+        );
+        assert!(fs.metadata(Path::new("/repo/src/b.rs")).is_err());
+    }
+
+    #[test]
+    fn test_caching_fs_reuses_reads_until_the_file_changes() {
+        let base = MemoryFileSystem::new().with_file("/repo/a.rs", "fn a() {}");
+        let fs = CachingFileSystem::new(&base);
+        let a = Path::new("/repo/a.rs");
+
+        assert_eq!(fs.read_to_string(a).unwrap(), "fn a() {}");
+        assert_eq!(fs.read_to_string(a).unwrap(), "fn a() {}");
+        assert_eq!((fs.stats().hits, fs.stats().misses), (1, 1));
+
+        // A new modification time and size invalidate the entry
+        base.insert(a, "fn a() { b(); }");
+        assert_eq!(fs.read_to_string(a).unwrap(), "fn a() { b(); }");
+        assert_eq!((fs.stats().hits, fs.stats().misses), (1, 2));
+        assert_eq!(fs.stats().bytes, 15);
+
+        // Writes through the cache drop the entry too
+        fs.write(a, "fn c() {}").unwrap();
+        assert_eq!(fs.read_to_string(a).unwrap(), "fn c() {}");
+        assert!(fs.read_to_string(Path::new("/repo/missing.rs")).is_err());
+
+        // Reads from many threads at once share the entries
+        std::thread::scope(|scope| {
+            for _ in 0..4 {
+                scope.spawn(|| assert_eq!(fs.read_to_string(a).unwrap(), "fn c() {}"));
+            }
+        });
+        assert_eq!(fs.stats().hits, 5);
+    }
+
+    #[test]
+    fn test_caching_fs_evicts_least_recently_read() {
+        let base = MemoryFileSystem::new()
+            .with_file("/a", "aaaa")
+            .with_file("/b", "bbbb")
+            .with_file("/c", "cccc")
+            .with_file("/huge", "x".repeat(20));
+        let fs = CachingFileSystem::new(&base).with_capacity(10);
+        let read = |path: &str| fs.read_to_string(Path::new(path)).unwrap();
+
+        read("/a");
+        read("/b");
+        read("/a");
+        // Over the cap: /b, read least recently, goes
+        read("/c");
+        assert_eq!(fs.stats().evictions, 1);
+        assert_eq!(fs.stats().bytes, 8);
+
+        let hits = fs.stats().hits;
+        read("/a");
+        read("/c");
+        assert_eq!(fs.stats().hits, hits + 2);
+        read("/b");
+        assert_eq!(fs.stats().evictions, 2);
+
+        // Files bigger than the whole cache are never held
+        read("/huge");
+        read("/huge");
+        assert_eq!(fs.stats().bytes, 8);
+        assert_eq!(fs.stats().evictions, 2);
     }
 }
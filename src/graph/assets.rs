@@ -28,9 +28,29 @@ pub const INDEX_HTML: &str = r#"<!DOCTYPE html>
 
         #graph {
             flex: 1;
+            position: relative;
             background: #16213e;
         }
 
+        /* Shown instead of the graph when no modules were found */
+        #placeholder {
+            display: none;
+            position: absolute;
+            inset: 0;
+            flex-direction: column;
+            align-items: center;
+            justify-content: center;
+            gap: 8px;
+            color: #888;
+            text-align: center;
+            pointer-events: none;
+        }
+
+        #placeholder strong {
+            font-size: 1.2em;
+            color: #eee;
+        }
+
         #sidebar {
             width: 320px;
             background: #1a1a2e;
@@ -200,7 +220,12 @@ pub const INDEX_HTML: &str = r#"<!DOCTYPE html>
 </head>
 <body>
     <div id="container">
-        <div id="graph"></div>
+        <div id="graph">
+            <div id="placeholder">
+                <strong>No supported source files found</strong>
+                <span>archmap reads Rust, TypeScript, JavaScript, Python and Scala sources.<br>Check the path, --lang and --exclude.</span>
+            </div>
+        </div>
         <div id="sidebar">
             <h1>Archmap</h1>
             <div id="project-name"></div>
@@ -291,10 +316,18 @@ pub const INDEX_HTML: &str = r#"<!DOCTYPE html>
                     <span>Fan-out (dependencies)</span>
                     <span id="node-fan-out">-</span>
                 </div>
+                <div class="node-stat">
+                    <span>Betweenness</span>
+                    <span id="node-betweenness">-</span>
+                </div>
                 <div class="node-stat">
                     <span>Issues</span>
                     <span id="node-issues">-</span>
                 </div>
+                <div class="node-stat">
+                    <span>Tags</span>
+                    <span id="node-tags">-</span>
+                </div>
                 <div class="exports-list">
                     <strong>Exports:</strong>
                     <div id="node-exports"></div>
@@ -316,6 +349,13 @@ pub const INDEX_HTML: &str = r#"<!DOCTYPE html>
             'output': '#6c5ce7',
             'cli': '#fdcb6e',
             'test': '#a29bfe',
+            'component': '#00b894',
+            'directive': '#55efc4',
+            'pipe': '#81ecec',
+            'service': '#0984e3',
+            'controller': '#e17055',
+            'repository': '#d63031',
+            'entity': '#e84393',
             'module': '#74b9ff'
         };
 
@@ -325,7 +365,7 @@ pub const INDEX_HTML: &str = r#"<!DOCTYPE html>
         let lastUpdatePopup = 0;
 
         async function init() {
-            const response = await fetch('/api/graph');
+            const response = await fetch('/api/graph' + window.location.search);
             graphData = await response.json();
 
             // Update stats
@@ -334,11 +374,16 @@ pub const INDEX_HTML: &str = r#"<!DOCTYPE html>
             document.getElementById('stat-deps').textContent = graphData.metadata.total_dependencies;
             document.getElementById('stat-issues').textContent = graphData.metadata.total_issues;
             document.getElementById('stat-cycles').textContent = graphData.metadata.cycle_count;
+            showPlaceholder(graphData);
 
             createGraph();
             setupControls();
         }
 
+        function showPlaceholder(data) {
+            document.getElementById('placeholder').style.display = data.nodes.length === 0 ? 'flex' : 'none';
+        }
+
         function createGraph() {
             const container = document.getElementById('graph');
             const width = container.clientWidth;
@@ -434,14 +479,15 @@ pub const INDEX_HTML: &str = r#"<!DOCTYPE html>
                 showNodeInfo(d);
             });
 
-            // Force simulation
+            // Force simulation, with constants scaled to the graph size by the server
+            const physics = graphData.physics;
             simulation = d3.forceSimulation(graphData.nodes)
                 .force('link', d3.forceLink(graphData.links)
                     .id(d => d.id)
-                    .distance(100))
-                .force('charge', d3.forceManyBody().strength(-300))
+                    .distance(physics.link_distance))
+                .force('charge', d3.forceManyBody().strength(physics.charge))
                 .force('center', d3.forceCenter(width / 2, height / 2))
-                .force('collision', d3.forceCollide().radius(d => getNodeRadius(d) + 5))
+                .force('collision', d3.forceCollide().radius(d => getNodeRadius(d) + physics.collision_radius))
                 .on('tick', ticked);
 
             // Run simulation to completion synchronously for deterministic layout
@@ -507,13 +553,25 @@ pub const INDEX_HTML: &str = r#"<!DOCTYPE html>
             node.classed('highlighted', false);
         }
 
+        function formatTags(tags) {
+            const entries = Object.entries(tags || {});
+            return entries.length > 0 ? entries.map(([k, v]) => `${k}=${v}`).join(', ') : '-';
+        }
+
+        // Two significant digits below 0.01, so bridges in large graphs don't read 0.00
+        function formatBetweenness(score) {
+            return score === 0 || score >= 0.01 ? score.toFixed(2) : score.toPrecision(2);
+        }
+
         function showNodeInfo(d) {
             document.getElementById('node-info').classList.add('visible');
             document.getElementById('node-name').textContent = d.path;
             document.getElementById('node-lines').textContent = d.lines;
             document.getElementById('node-fan-in').textContent = d.fan_in;
             document.getElementById('node-fan-out').textContent = d.fan_out;
+            document.getElementById('node-betweenness').textContent = formatBetweenness(d.betweenness);
             document.getElementById('node-issues').textContent = d.issue_count;
+            document.getElementById('node-tags').textContent = formatTags(d.tags);
 
             const exportsDiv = document.getElementById('node-exports');
             if (d.exports && d.exports.length > 0) {
@@ -531,8 +589,12 @@ pub const INDEX_HTML: &str = r#"<!DOCTYPE html>
             document.getElementById('highlight-cycles').addEventListener('change', function() {
                 if (this.checked) {
                     link.filter(d => d.is_cycle).style('stroke', '#ff4444').style('stroke-width', 3);
+                    // One color per cycle group, so separate tangles stand apart
+                    node.filter(d => d.scc_id !== null).select('circle')
+                        .style('fill', d => d3.schemeTableau10[d.scc_id % 10]);
                 } else {
                     link.filter(d => d.is_cycle).style('stroke', '#ff4444').style('stroke-width', 2);
+                    node.select('circle').style('fill', null);
                 }
             });
 
@@ -540,7 +602,7 @@ pub const INDEX_HTML: &str = r#"<!DOCTYPE html>
                 nodeScale = parseFloat(this.value);
                 node.selectAll('circle').attr('r', d => getNodeRadius(d));
                 // Just update collision radius for future drags, don't restart simulation
-                simulation.force('collision', d3.forceCollide().radius(d => getNodeRadius(d) + 5));
+                simulation.force('collision', d3.forceCollide().radius(d => getNodeRadius(d) + graphData.physics.collision_radius));
             });
         }
 
@@ -560,7 +622,7 @@ pub const INDEX_HTML: &str = r#"<!DOCTYPE html>
                 console.log('Graph update received, version:', event.data);
 
                 // Fetch new graph data
-                const response = await fetch('/api/graph');
+                const response = await fetch('/api/graph' + window.location.search);
                 const newData = await response.json();
 
                 // Update stats
@@ -568,6 +630,7 @@ pub const INDEX_HTML: &str = r#"<!DOCTYPE html>
                 document.getElementById('stat-deps').textContent = newData.metadata.total_dependencies;
                 document.getElementById('stat-issues').textContent = newData.metadata.total_issues;
                 document.getElementById('stat-cycles').textContent = newData.metadata.cycle_count;
+                showPlaceholder(newData);
 
                 // Preserve node positions where possible
                 const oldPositions = {};
@@ -649,9 +712,12 @@ pub const INDEX_HTML: &str = r#"<!DOCTYPE html>
                     newData.nodes.some(n => !oldPositions[n.id]);
                 const linksChanged = link.data().length !== graphData.links.length;
 
-                // Update simulation with new data
+                // Update simulation with new data; the physics rescale as modules come and go
+                const physics = graphData.physics;
                 simulation.nodes(graphData.nodes);
-                simulation.force('link').links(graphData.links);
+                simulation.force('link').links(graphData.links).distance(physics.link_distance);
+                simulation.force('charge').strength(physics.charge);
+                simulation.force('collision', d3.forceCollide().radius(d => getNodeRadius(d) + physics.collision_radius));
 
                 // Only re-layout if structure actually changed
                 if (nodesChanged || linksChanged) {
@@ -686,11 +752,12 @@ pub const INDEX_HTML: &str = r#"<!DOCTYPE html>
 </html>
 "#;
 
-/// Generate a standalone HTML file with embedded graph data for export
-pub fn generate_static_html(graph_data: &super::GraphData) -> String {
-    let json_data = serde_json::to_string(graph_data).unwrap_or_else(|_| "{}".to_string());
+/// Generate a standalone HTML file with embedded graph data for export.
+/// Fails if the graph data doesn't serialize, rather than embedding an empty graph.
+pub fn generate_static_html(graph_data: &super::GraphData) -> Result<String, serde_json::Error> {
+    let json_data = serde_json::to_string(graph_data)?;
 
-    format!(
+    Ok(format!(
         r#"<!DOCTYPE html>
 <html lang="en">
 <head>
@@ -702,7 +769,9 @@ pub fn generate_static_html(graph_data: &super::GraphData) -> String {
         * {{ margin: 0; padding: 0; box-sizing: border-box; }}
         body {{ font-family: -apple-system, BlinkMacSystemFont, 'Segoe UI', Roboto, sans-serif; background: #1a1a2e; color: #eee; overflow: hidden; }}
         #container {{ display: flex; height: 100vh; }}
-        #graph {{ flex: 1; background: #16213e; }}
+        #graph {{ flex: 1; position: relative; background: #16213e; }}
+        #placeholder {{ display: none; position: absolute; inset: 0; flex-direction: column; align-items: center; justify-content: center; gap: 8px; color: #888; text-align: center; pointer-events: none; }}
+        #placeholder strong {{ font-size: 1.2em; color: #eee; }}
         #sidebar {{ width: 320px; background: #1a1a2e; border-left: 1px solid #333; padding: 20px; overflow-y: auto; }}
         h1 {{ font-size: 1.4em; margin-bottom: 10px; color: #00d9ff; }}
         h2 {{ font-size: 1.1em; margin: 15px 0 10px; color: #888; text-transform: uppercase; letter-spacing: 1px; }}
@@ -729,7 +798,7 @@ pub fn generate_static_html(graph_data: &super::GraphData) -> String {
 </head>
 <body>
     <div id="container">
-        <div id="graph"></div>
+        <div id="graph"><div id="placeholder"><strong>No supported source files found</strong><span>archmap reads Rust, TypeScript, JavaScript, Python and Scala sources.<br>Check the path, --lang and --exclude.</span></div></div>
         <div id="sidebar">
             <h1>Archmap</h1>
             <div id="project-name"></div>
@@ -756,7 +825,9 @@ pub fn generate_static_html(graph_data: &super::GraphData) -> String {
                 <div class="node-stat"><span>Lines</span><span id="node-lines">-</span></div>
                 <div class="node-stat"><span>Fan-in</span><span id="node-fan-in">-</span></div>
                 <div class="node-stat"><span>Fan-out</span><span id="node-fan-out">-</span></div>
+                <div class="node-stat"><span>Betweenness</span><span id="node-betweenness">-</span></div>
                 <div class="node-stat"><span>Issues</span><span id="node-issues">-</span></div>
+                <div class="node-stat"><span>Tags</span><span id="node-tags">-</span></div>
                 <div class="exports-list"><strong>Exports:</strong><div id="node-exports"></div></div>
             </div>
         </div>
@@ -764,7 +835,7 @@ pub fn generate_static_html(graph_data: &super::GraphData) -> String {
     <div class="tooltip" style="display: none;"></div>
     <script>
         const graphData = {json_data};
-        const categoryColors = {{ 'index': '#4ecdc4', 'entry': '#ff6b6b', 'config': '#ffe66d', 'model': '#c9b1ff', 'analysis': '#95e1d3', 'parser': '#f38181', 'output': '#6c5ce7', 'cli': '#fdcb6e', 'test': '#a29bfe', 'module': '#74b9ff' }};
+        const categoryColors = {{ 'index': '#4ecdc4', 'entry': '#ff6b6b', 'config': '#ffe66d', 'model': '#c9b1ff', 'analysis': '#95e1d3', 'parser': '#f38181', 'output': '#6c5ce7', 'cli': '#fdcb6e', 'test': '#a29bfe', 'component': '#00b894', 'directive': '#55efc4', 'pipe': '#81ecec', 'service': '#0984e3', 'controller': '#e17055', 'repository': '#d63031', 'entity': '#e84393', 'module': '#74b9ff' }};
         let simulation, svg, g, link, node, label;
         let nodeScale = 1;
 
@@ -774,6 +845,7 @@ pub fn generate_static_html(graph_data: &super::GraphData) -> String {
             document.getElementById('stat-deps').textContent = graphData.metadata.total_dependencies;
             document.getElementById('stat-issues').textContent = graphData.metadata.total_issues;
             document.getElementById('stat-cycles').textContent = graphData.metadata.cycle_count;
+            if (graphData.nodes.length === 0) document.getElementById('placeholder').style.display = 'flex';
             createGraph();
         }}
 
@@ -794,7 +866,8 @@ pub fn generate_static_html(graph_data: &super::GraphData) -> String {
             label = node.append('text').attr('dy', -12).attr('text-anchor', 'middle').text(d => d.name);
             const tooltip = d3.select('.tooltip');
             node.on('mouseover', function(event, d) {{ tooltip.style('display', 'block').html(`<strong>${{d.name}}</strong><br>${{d.path}}<br>Lines: ${{d.lines}}<br>Fan-in: ${{d.fan_in}} | Fan-out: ${{d.fan_out}}`).style('left', (event.pageX + 10) + 'px').style('top', (event.pageY - 10) + 'px'); highlightConnections(d); }}).on('mouseout', function() {{ tooltip.style('display', 'none'); clearHighlights(); }}).on('click', function(event, d) {{ showNodeInfo(d); }});
-            simulation = d3.forceSimulation(graphData.nodes).force('link', d3.forceLink(graphData.links).id(d => d.id).distance(100)).force('charge', d3.forceManyBody().strength(-300)).force('center', d3.forceCenter(width / 2, height / 2)).force('collision', d3.forceCollide().radius(d => getNodeRadius(d) + 5)).on('tick', ticked);
+            const physics = graphData.physics;
+            simulation = d3.forceSimulation(graphData.nodes).force('link', d3.forceLink(graphData.links).id(d => d.id).distance(physics.link_distance)).force('charge', d3.forceManyBody().strength(physics.charge)).force('center', d3.forceCenter(width / 2, height / 2)).force('collision', d3.forceCollide().radius(d => getNodeRadius(d) + physics.collision_radius)).on('tick', ticked);
             // Run simulation to completion synchronously for deterministic layout
             simulation.stop(); for (let i = 0; i < 300; i++) simulation.tick(); ticked(); simulation.alpha(0);
         }}
@@ -806,7 +879,9 @@ pub fn generate_static_html(graph_data: &super::GraphData) -> String {
         function dragended(event) {{ event.subject.fx = null; event.subject.fy = null; }}
         function highlightConnections(d) {{ const connected = new Set(); connected.add(d.id); link.each(function(l) {{ if (l.source.id === d.id || l.target.id === d.id) {{ connected.add(l.source.id); connected.add(l.target.id); d3.select(this).classed('highlighted', true); }} }}); node.classed('highlighted', n => connected.has(n.id)); }}
         function clearHighlights() {{ link.classed('highlighted', false); node.classed('highlighted', false); }}
-        function showNodeInfo(d) {{ document.getElementById('node-info').classList.add('visible'); document.getElementById('node-name').textContent = d.path; document.getElementById('node-lines').textContent = d.lines; document.getElementById('node-fan-in').textContent = d.fan_in; document.getElementById('node-fan-out').textContent = d.fan_out; document.getElementById('node-issues').textContent = d.issue_count; const exportsDiv = document.getElementById('node-exports'); exportsDiv.innerHTML = d.exports && d.exports.length > 0 ? d.exports.map(e => `<span>${{e}}</span>`).join('') : '<em>None</em>'; }}
+        function formatTags(tags) {{ const entries = Object.entries(tags || {{}}); return entries.length > 0 ? entries.map(([k, v]) => `${{k}}=${{v}}`).join(', ') : '-'; }}
+        function formatBetweenness(score) {{ return score === 0 || score >= 0.01 ? score.toFixed(2) : score.toPrecision(2); }}
+        function showNodeInfo(d) {{ document.getElementById('node-info').classList.add('visible'); document.getElementById('node-name').textContent = d.path; document.getElementById('node-lines').textContent = d.lines; document.getElementById('node-fan-in').textContent = d.fan_in; document.getElementById('node-fan-out').textContent = d.fan_out; document.getElementById('node-betweenness').textContent = formatBetweenness(d.betweenness); document.getElementById('node-issues').textContent = d.issue_count; document.getElementById('node-tags').textContent = formatTags(d.tags); const exportsDiv = document.getElementById('node-exports'); exportsDiv.innerHTML = d.exports && d.exports.length > 0 ? d.exports.map(e => `<span>${{e}}</span>`).join('') : '<em>None</em>'; }}
         window.addEventListener('resize', () => {{ const container = document.getElementById('graph'); svg.attr('width', container.clientWidth).attr('height', container.clientHeight); simulation.force('center', d3.forceCenter(container.clientWidth / 2, container.clientHeight / 2)); }});
         init();
     </script>
@@ -814,5 +889,5 @@ pub fn generate_static_html(graph_data: &super::GraphData) -> String {
 </html>
 "#,
         json_data = json_data
-    )
+    ))
 }
@@ -1,66 +1,159 @@
-use crate::model::{AnalysisResult, IssueKind, Module};
-use serde::Serialize;
-use std::collections::{HashMap, HashSet};
+use crate::model::{AnalysisResult, IssueKind, IssueSeverity, Symbol};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::path::Path;
 
-/// Graph data in D3.js force-directed graph format
-#[derive(Debug, Clone, Serialize)]
+/// Graph data in D3.js force-directed graph format: what `archmap graph` draws
+/// and `/api/graph` serves. Severity lists behind the issue counts are not
+/// serialized, so [`GraphData::with_min_severity`] only works on data built by
+/// [`GraphData::from_result`].
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GraphData {
+    /// One node per analyzed module
     pub nodes: Vec<GraphNode>,
+    /// One link per resolved import, so a module importing another twice has two
     pub links: Vec<GraphLink>,
     pub metadata: GraphMetadata,
+    /// Force-layout parameters the page feeds to its simulation
+    pub physics: GraphPhysics,
 }
 
-#[derive(Debug, Clone, Serialize)]
+/// Format version of the graph data the pages and `/api/graph` serve
+pub const GRAPH_SCHEMA: u32 = 1;
+
+/// Constants for the D3 force simulation
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct GraphPhysics {
+    /// Many-body strength; negative values push nodes apart
+    pub charge: f64,
+    /// Target length of dependency links
+    pub link_distance: f64,
+    /// Spacing kept around each node's circle
+    pub collision_radius: f64,
+}
+
+/// Node count the unscaled constants were tuned for
+const REFERENCE_NODES: f64 = 50.0;
+
+impl GraphPhysics {
+    /// Defaults scaled to the graph size. Repulsion and link length grow with the
+    /// square root of the node count, so large graphs spread out instead of
+    /// collapsing into a ball and small ones stay together, within 0.5x-3x of the
+    /// constants tuned for about 50 nodes.
+    pub fn for_nodes(nodes: usize) -> Self {
+        let scale = (nodes as f64 / REFERENCE_NODES).sqrt().clamp(0.5, 3.0);
+        Self {
+            charge: -300.0 * scale,
+            link_distance: 100.0 * scale,
+            collision_radius: 5.0 * scale,
+        }
+    }
+}
+
+/// Physics values given on the command line; unset ones keep the scaled default
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct PhysicsOverrides {
+    pub charge: Option<f64>,
+    pub link_distance: Option<f64>,
+    pub collision_radius: Option<f64>,
+}
+
+/// A module in the graph
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GraphNode {
+    /// Path relative to the project root; links refer to nodes by it
     pub id: String,
+    /// Module name as the parser reported it
     pub name: String,
+    /// Path relative to the project root, same as `id`
     pub path: String,
     pub lines: usize,
+    /// Imports from other modules that resolve to this one
     pub fan_in: usize,
+    /// Imports this module makes, resolved or not
     pub fan_out: usize,
+    /// Issues touching this module, at or above the severity floor
     pub issue_count: usize,
+    /// Normalized betweenness centrality (0.0-1.0)
+    pub betweenness: f64,
+    /// The module's framework role (`component`, `controller`, `repository`,
+    /// ...) when it has one, else a role guessed from the path: `test`,
+    /// `index`, `entry`, `config`, `model`, `cli`, `output`, `parser`,
+    /// `analysis` or `module`
     pub category: String,
-    pub exports: Vec<String>,
+    /// Names the module exports
+    pub exports: Vec<Symbol>,
+    /// Tags from `@archmap:` annotations, sorted by key
+    pub tags: BTreeMap<String, String>,
+    /// Strongly connected component this module shares with at least one other
+    /// module; every member of one cycle group has the same id
+    pub scc_id: Option<usize>,
+    /// Severity of every issue touching this module, used to recompute `issue_count`
+    #[serde(skip)]
+    pub issue_severities: Vec<IssueSeverity>,
 }
 
-#[derive(Debug, Clone, Serialize)]
+/// An import from `source` to `target`, both node ids
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GraphLink {
     pub source: String,
     pub target: String,
+    /// Whether the import is part of a reported circular dependency
     pub is_cycle: bool,
 }
 
-#[derive(Debug, Clone, Serialize)]
+/// Project-wide totals
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GraphMetadata {
+    /// Format version of this data, see [`GRAPH_SCHEMA`]
+    pub schema_version: u32,
+    /// archmap version that produced it
+    pub archmap_version: String,
     pub project_name: String,
     pub total_modules: usize,
+    /// Number of links
     pub total_dependencies: usize,
+    /// Issues at or above the severity floor
     pub total_issues: usize,
+    /// Reported circular dependencies
     pub cycle_count: usize,
+    /// Severity of every issue in the project, used to recompute `total_issues`
+    #[serde(skip)]
+    pub issue_severities: Vec<IssueSeverity>,
 }
 
 impl GraphData {
-    pub fn from_analysis(result: &AnalysisResult, project_root: &Path) -> Self {
-        // Build fan-in counts
+    /// Derive the graph from an existing analysis; paths become relative to
+    /// `project_root`.
+    pub fn from_result(result: &AnalysisResult, project_root: &Path) -> Self {
+        // Build fan-in counts, one per resolved import
         let mut fan_ins: HashMap<String, usize> = HashMap::new();
-        for module in &result.modules {
-            let path = relative_path(&module.path, project_root);
-            for import in &module.imports {
-                // Try to resolve import to a module path
-                if let Some(target) = resolve_import(import, &result.modules, project_root) {
-                    *fan_ins.entry(target).or_insert(0) += 1;
-                }
-            }
-            fan_ins.entry(path).or_insert(0);
+        for (_, target, _) in result.dependency_graph.edges() {
+            *fan_ins
+                .entry(relative_path(target, project_root))
+                .or_insert(0) += 1;
         }
 
-        // Build issue counts per module
-        let mut issue_counts: HashMap<String, usize> = HashMap::new();
+        // Collect issue severities per module
+        let mut issue_severities: HashMap<String, Vec<IssueSeverity>> = HashMap::new();
         for issue in &result.issues {
             for loc in &issue.locations {
                 let path = relative_path(&loc.path, project_root);
-                *issue_counts.entry(path).or_insert(0) += 1;
+                issue_severities
+                    .entry(path)
+                    .or_default()
+                    .push(issue.severity);
+            }
+        }
+
+        let betweenness = result.dependency_graph.betweenness_centrality();
+
+        // Number the multi-module cycle groups; self-imports stay ungrouped
+        let mut scc_ids: HashMap<&Path, usize> = HashMap::new();
+        let groups = result.dependency_graph.cycles();
+        for (id, group) in groups.iter().filter(|g| g.len() > 1).enumerate() {
+            for path in group {
+                scc_ids.insert(path, id);
             }
         }
 
@@ -72,8 +165,11 @@ impl GraphData {
                 let path = relative_path(&m.path, project_root);
                 let fan_in = fan_ins.get(&path).copied().unwrap_or(0);
                 let fan_out = m.imports.len();
-                let issue_count = issue_counts.get(&path).copied().unwrap_or(0);
-                let category = categorize_module(&m.path, project_root);
+                let severities = issue_severities.remove(&path).unwrap_or_default();
+                let category = m
+                    .role()
+                    .map(str::to_string)
+                    .unwrap_or_else(|| categorize_module(&m.path, project_root));
 
                 GraphNode {
                     id: path.clone(),
@@ -82,9 +178,13 @@ impl GraphData {
                     lines: m.lines,
                     fan_in,
                     fan_out,
-                    issue_count,
+                    issue_count: severities.len(),
+                    betweenness: betweenness.get(&m.path).copied().unwrap_or(0.0),
                     category,
                     exports: m.exports.clone(),
+                    tags: m.tags.clone().into_iter().collect(),
+                    scc_id: scc_ids.get(m.path.as_path()).copied(),
+                    issue_severities: severities,
                 }
             })
             .collect();
@@ -110,22 +210,21 @@ impl GraphData {
         }
 
         // Build dependency links
-        for module in &result.modules {
-            let source = relative_path(&module.path, project_root);
-            for import in &module.imports {
-                if let Some(target) = resolve_import(import, &result.modules, project_root) {
-                    let is_cycle = cycle_edges.contains(&(source.clone(), target.clone()));
-                    links.push(GraphLink {
-                        source: source.clone(),
-                        target,
-                        is_cycle,
-                    });
-                }
-            }
+        for (source, target, _) in result.dependency_graph.edges() {
+            let source = relative_path(source, project_root);
+            let target = relative_path(target, project_root);
+            let is_cycle = cycle_edges.contains(&(source.clone(), target.clone()));
+            links.push(GraphLink {
+                source,
+                target,
+                is_cycle,
+            });
         }
 
         // Metadata
         let metadata = GraphMetadata {
+            schema_version: GRAPH_SCHEMA,
+            archmap_version: crate::build_info::VERSION.to_string(),
             project_name: result.project_name.clone(),
             total_modules: result.modules.len(),
             total_dependencies: links.len(),
@@ -135,14 +234,79 @@ impl GraphData {
                 .iter()
                 .filter(|i| matches!(i.kind, IssueKind::CircularDependency))
                 .count(),
+            issue_severities: result.issues.iter().map(|i| i.severity).collect(),
         };
 
         GraphData {
+            physics: GraphPhysics::for_nodes(nodes.len()),
             nodes,
             links,
             metadata,
         }
     }
+
+    /// Adjacency matrix as CSV: the first row and column hold module paths, and
+    /// cell `[i][j]` counts the imports from module `i` that resolve to module `j`.
+    /// Modules are sorted by path.
+    pub fn to_matrix_csv(&self) -> String {
+        let mut ids: Vec<&str> = self.nodes.iter().map(|n| n.id.as_str()).collect();
+        ids.sort_unstable();
+        let index: HashMap<&str, usize> = ids.iter().enumerate().map(|(i, id)| (*id, i)).collect();
+
+        let mut weights = vec![vec![0usize; ids.len()]; ids.len()];
+        for link in &self.links {
+            if let (Some(&i), Some(&j)) = (
+                index.get(link.source.as_str()),
+                index.get(link.target.as_str()),
+            ) {
+                weights[i][j] += 1;
+            }
+        }
+
+        let mut csv = String::new();
+        let header: Vec<String> = ids.iter().map(|id| csv_field(id)).collect();
+        csv.push_str(&format!(",{}\n", header.join(",")));
+        for (id, row) in ids.iter().zip(&weights) {
+            let cells: Vec<String> = row.iter().map(|w| w.to_string()).collect();
+            csv.push_str(&format!("{},{}\n", csv_field(id), cells.join(",")));
+        }
+        csv
+    }
+
+    /// Replace the scaled physics defaults with any values given explicitly.
+    pub fn with_physics(mut self, overrides: PhysicsOverrides) -> Self {
+        if let Some(charge) = overrides.charge {
+            self.physics.charge = charge;
+        }
+        if let Some(distance) = overrides.link_distance {
+            self.physics.link_distance = distance;
+        }
+        if let Some(radius) = overrides.collision_radius {
+            self.physics.collision_radius = radius;
+        }
+        self
+    }
+
+    /// Restrict node and project issue counts to issues at or above `min_severity`.
+    pub fn with_min_severity(mut self, min_severity: IssueSeverity) -> Self {
+        let count = |severities: &[IssueSeverity]| {
+            severities.iter().filter(|s| **s >= min_severity).count()
+        };
+        for node in &mut self.nodes {
+            node.issue_count = count(&node.issue_severities);
+        }
+        self.metadata.total_issues = count(&self.metadata.issue_severities);
+        self
+    }
+}
+
+/// Quote a CSV field when it contains a separator, quote or line break.
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
 }
 
 fn relative_path(path: &Path, root: &Path) -> String {
@@ -152,27 +316,6 @@ fn relative_path(path: &Path, root: &Path) -> String {
         .to_string()
 }
 
-fn resolve_import(import: &str, modules: &[Module], project_root: &Path) -> Option<String> {
-    // Extract the first meaningful path segment
-    let segments: Vec<&str> = import.split("::").collect();
-    if segments.is_empty() {
-        return None;
-    }
-
-    let search_name = if segments[0] == "crate" && segments.len() > 1 {
-        segments[1]
-    } else if segments[0] == "super" || segments[0] == "self" {
-        return None;
-    } else {
-        segments[0]
-    };
-
-    modules
-        .iter()
-        .find(|m| m.name == search_name)
-        .map(|m| relative_path(&m.path, project_root))
-}
-
 fn categorize_module(path: &Path, project_root: &Path) -> String {
     let rel_path = path.strip_prefix(project_root).unwrap_or(path);
     let path_str = rel_path.display().to_string();
@@ -199,3 +342,128 @@ fn categorize_module(path: &Path, project_root: &Path) -> String {
         "module".to_string()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{Issue, Module};
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_min_severity_filters_issue_counts() {
+        let path = PathBuf::from("/demo/src/lib.rs");
+        let result = AnalysisResult::new(
+            "demo".to_string(),
+            vec![Module::new(path.clone())],
+            vec![
+                Issue::high_coupling(path.clone(), 7),
+                Issue::low_cohesion(path, 0.2, 1, 5),
+            ],
+        );
+
+        let data = GraphData::from_result(&result, Path::new("/demo"));
+        assert_eq!(data.nodes[0].issue_count, 2);
+
+        let warn = data.clone().with_min_severity(IssueSeverity::Warn);
+        assert_eq!(warn.nodes[0].issue_count, 1);
+        assert_eq!(warn.metadata.total_issues, 1);
+
+        let error = data.with_min_severity(IssueSeverity::Error);
+        assert_eq!(error.nodes[0].issue_count, 0);
+        assert_eq!(error.metadata.total_issues, 0);
+    }
+
+    #[test]
+    fn test_matrix_csv_counts_imports() {
+        let mut main = Module::new(PathBuf::from("/demo/src/main.ts"));
+        main.imports = vec!["./util".into(), "./util".into()];
+        let mut util = Module::new(PathBuf::from("/demo/src/util.ts"));
+        util.imports = vec!["./a,b".into()];
+        let quoted = Module::new(PathBuf::from("/demo/src/a,b.ts"));
+        let modules = vec![main, util, quoted];
+
+        let result = AnalysisResult::new("demo".to_string(), modules, Vec::new());
+        let csv = GraphData::from_result(&result, Path::new("/demo")).to_matrix_csv();
+
+        assert_eq!(
+            csv,
+            ",\"src/a,b.ts\",src/main.ts,src/util.ts\n\
+             \"src/a,b.ts\",0,0,0\n\
+             src/main.ts,0,0,2\n\
+             src/util.ts,1,0,0\n"
+        );
+    }
+
+    #[test]
+    fn test_scc_ids_group_each_cycle() {
+        let module = |name: &str, imports: &[&str]| {
+            let mut m = Module::new(PathBuf::from(format!("/demo/src/{}.ts", name)));
+            m.imports = imports.iter().map(|&i| i.into()).collect();
+            m
+        };
+        let modules = vec![
+            module("a", &["./b"]),
+            module("b", &["./a", "./c"]),
+            module("c", &["./d"]),
+            module("d", &["./c"]),
+            module("e", &["./e", "./a"]),
+        ];
+        let result = AnalysisResult::new("demo".to_string(), modules, Vec::new());
+
+        let data = GraphData::from_result(&result, Path::new("/demo"));
+        let scc = |id: &str| data.nodes.iter().find(|n| n.id == id).unwrap().scc_id;
+        assert!(scc("src/a.ts").is_some());
+        assert_eq!(scc("src/a.ts"), scc("src/b.ts"));
+        assert!(scc("src/c.ts").is_some());
+        assert_eq!(scc("src/c.ts"), scc("src/d.ts"));
+        assert_ne!(scc("src/a.ts"), scc("src/c.ts"));
+        assert_eq!(scc("src/e.ts"), None);
+    }
+
+    #[test]
+    fn test_empty_project_has_finite_metrics() {
+        let result = AnalysisResult::new("empty".to_string(), Vec::new(), Vec::new());
+
+        let data = GraphData::from_result(&result, Path::new("/empty"));
+        assert!(data.nodes.is_empty());
+        assert!(data.physics.charge.is_finite() && data.physics.link_distance.is_finite());
+        assert!(
+            crate::graph::generate_static_html(&data)
+                .unwrap()
+                .contains("No supported source files found")
+        );
+
+        let snapshot = crate::snapshot::Snapshot::from_analysis(&result, Path::new("/empty"));
+        assert_eq!(snapshot.metrics.avg_coupling, 0.0);
+    }
+
+    #[test]
+    fn test_physics_scales_with_node_count() {
+        let reference = GraphPhysics::for_nodes(50);
+        assert_eq!(reference.charge, -300.0);
+        assert_eq!(reference.link_distance, 100.0);
+        assert_eq!(reference.collision_radius, 5.0);
+
+        let large = GraphPhysics::for_nodes(200);
+        assert_eq!(large.charge, -600.0);
+        assert_eq!(large.link_distance, 200.0);
+        assert_eq!(
+            GraphPhysics::for_nodes(100_000),
+            GraphPhysics::for_nodes(450)
+        );
+        assert_eq!(GraphPhysics::for_nodes(2).link_distance, 50.0);
+
+        let result = AnalysisResult::new(
+            "demo".to_string(),
+            vec![Module::new(PathBuf::from("/demo/src/lib.rs"))],
+            Vec::new(),
+        );
+        let data =
+            GraphData::from_result(&result, Path::new("/demo")).with_physics(PhysicsOverrides {
+                charge: Some(-80.0),
+                ..Default::default()
+            });
+        assert_eq!(data.physics.charge, -80.0);
+        assert_eq!(data.physics.link_distance, 50.0);
+    }
+}
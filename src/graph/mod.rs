@@ -3,5 +3,7 @@ mod data;
 mod routes;
 
 pub use assets::generate_static_html;
-pub use data::GraphData;
+pub use data::{
+    GRAPH_SCHEMA, GraphData, GraphLink, GraphMetadata, GraphNode, GraphPhysics, PhysicsOverrides,
+};
 pub use routes::{WatchContext, serve, serve_with_watch};
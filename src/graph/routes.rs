@@ -1,17 +1,20 @@
 use super::assets::INDEX_HTML;
-use super::data::GraphData;
-use crate::fs::{FileSystem, default_fs};
+use super::data::{GraphData, PhysicsOverrides};
+use crate::commands::watch::Watcher;
+use crate::fs::{WalkOptions, default_fs};
+use crate::model::IssueSeverity;
 use crate::style;
 use axum::{
     Json, Router,
-    extract::State,
+    extract::{Query, State},
+    http::StatusCode,
     response::{
         Html, IntoResponse,
         sse::{Event, Sse},
     },
     routing::get,
 };
-use std::collections::HashMap;
+use serde::Deserialize;
 use std::convert::Infallible;
 use std::path::PathBuf;
 use std::sync::Arc;
@@ -35,6 +38,16 @@ fn get_local_ip() -> Option<String> {
 pub struct AppState {
     pub graph_data: Arc<tokio::sync::RwLock<GraphData>>,
     pub update_rx: watch::Receiver<u64>,
+    /// Severity floor applied when `/api/graph` has no `min_severity` parameter
+    pub min_severity: IssueSeverity,
+    /// Applied over the size-scaled layout defaults on every request
+    pub physics: PhysicsOverrides,
+}
+
+/// Query parameters accepted by `/api/graph`
+#[derive(Debug, Deserialize)]
+pub struct GraphQuery {
+    pub min_severity: Option<String>,
 }
 
 /// Context needed to rebuild the graph
@@ -42,6 +55,14 @@ pub struct WatchContext {
     pub path: PathBuf,
     pub config: crate::config::Config,
     pub registry: crate::parser::ParserRegistry,
+    /// Applied on every re-analysis (e.g. `--filter-tag`)
+    pub filter: crate::analysis::PathFilter,
+    /// Rescan every interval instead of waiting for filesystem events
+    pub poll: bool,
+    /// How often source files are rescanned when polling
+    pub interval: Duration,
+    /// How long files must stay unchanged before the graph is rebuilt
+    pub debounce: Duration,
 }
 
 /// Start the HTTP server for graph visualization
@@ -49,11 +70,15 @@ pub async fn serve(
     graph_data: GraphData,
     port: u16,
     open_browser: bool,
+    min_severity: IssueSeverity,
+    physics: PhysicsOverrides,
 ) -> Result<(), Box<dyn std::error::Error>> {
     let (update_tx, update_rx) = watch::channel(0u64);
     let state = Arc::new(AppState {
         graph_data: Arc::new(tokio::sync::RwLock::new(graph_data)),
         update_rx,
+        min_severity,
+        physics,
     });
 
     let cors = CorsLayer::new().allow_origin(Any).allow_methods(Any);
@@ -76,7 +101,7 @@ pub async fn serve(
             style::url(&format!("http://{}:{}", ip, port))
         ));
     }
-    println!("Press Ctrl+C to stop");
+    style::note("Press Ctrl+C to stop");
 
     // Keep update_tx alive but unused for non-watch mode
     drop(update_tx);
@@ -98,6 +123,8 @@ pub async fn serve_with_watch(
     graph_data: GraphData,
     port: u16,
     open_browser: bool,
+    min_severity: IssueSeverity,
+    physics: PhysicsOverrides,
     watch_ctx: WatchContext,
 ) -> Result<(), Box<dyn std::error::Error>> {
     let (update_tx, update_rx) = watch::channel(0u64);
@@ -106,6 +133,8 @@ pub async fn serve_with_watch(
     let state = Arc::new(AppState {
         graph_data: graph_data.clone(),
         update_rx,
+        min_severity,
+        physics,
     });
 
     let cors = CorsLayer::new().allow_origin(Any).allow_methods(Any);
@@ -129,7 +158,7 @@ pub async fn serve_with_watch(
         ));
     }
     style::status("Watching for file changes...");
-    println!("Press Ctrl+C to stop");
+    style::note("Press Ctrl+C to stop");
 
     if open_browser {
         if let Err(e) = open::that(&url) {
@@ -139,8 +168,8 @@ pub async fn serve_with_watch(
 
     // Spawn the file watcher task
     let watcher_graph = graph_data.clone();
-    tokio::spawn(async move {
-        watch_files(watch_ctx, watcher_graph, update_tx).await;
+    tokio::task::spawn_blocking(move || {
+        watch_files(watch_ctx, watcher_graph, update_tx);
     });
 
     let listener = tokio::net::TcpListener::bind(&addr).await?;
@@ -149,106 +178,73 @@ pub async fn serve_with_watch(
     Ok(())
 }
 
-/// Watch for file changes and update the graph
-async fn watch_files(
+/// Watch for file changes and update the graph once a burst of them settles.
+/// Runs on a blocking thread, since it waits on the watcher and re-analyzes.
+fn watch_files(
     ctx: WatchContext,
     graph_data: Arc<tokio::sync::RwLock<GraphData>>,
     update_tx: watch::Sender<u64>,
 ) {
-    let mut last_modified: HashMap<PathBuf, std::time::SystemTime> = HashMap::new();
+    let options = WalkOptions {
+        exclude: Vec::new(),
+        follow_links: crate::analysis::follow_symlinks(&ctx.config),
+    };
+    let roots = [ctx.path.clone()];
+    let watcher = Watcher::for_roots(&ctx.path, &roots, &ctx.registry, options, default_fs())
+        .poll_only(ctx.poll)
+        .poll_every(ctx.interval)
+        .debounce_for(ctx.debounce);
     let mut version = 0u64;
 
-    // Initial scan
-    scan_files(&ctx.path, &mut last_modified);
-
-    loop {
-        tokio::time::sleep(Duration::from_secs(1)).await;
-
-        let mut current_files: HashMap<PathBuf, std::time::SystemTime> = HashMap::new();
-        scan_files(&ctx.path, &mut current_files);
-
-        let mut changed = false;
-
-        // Check for new or modified files
-        for (file_path, modified) in &current_files {
-            let display_path = file_path
-                .strip_prefix(&ctx.path)
-                .unwrap_or(file_path)
-                .display()
-                .to_string();
-            match last_modified.get(file_path) {
-                Some(last) if last != modified => {
-                    println!("  {}", style::file_changed(&display_path));
-                    changed = true;
-                }
-                None => {
-                    println!("  {}", style::file_added(&display_path));
-                    changed = true;
-                }
-                _ => {}
-            }
-        }
-
-        // Check for deleted files
-        for file_path in last_modified.keys() {
-            if !current_files.contains_key(file_path) {
-                let display_path = file_path
-                    .strip_prefix(&ctx.path)
-                    .unwrap_or(file_path)
-                    .display()
-                    .to_string();
-                println!("  {}", style::file_deleted(&display_path));
-                changed = true;
-            }
+    watcher.run(|_| {
+        style::status("Re-analyzing...");
+
+        // Re-run analysis
+        let result = crate::analysis::analyze_filtered(
+            &ctx.path,
+            &ctx.config,
+            &ctx.registry,
+            &[],
+            &ctx.filter,
+        );
+        for diagnostic in &result.diagnostics {
+            style::warning(&diagnostic.message);
         }
+        let new_graph = GraphData::from_result(&result, &ctx.path);
 
-        if changed {
-            style::status("Re-analyzing...");
-
-            // Re-run analysis
-            let result = crate::analysis::analyze(&ctx.path, &ctx.config, &ctx.registry, &[]);
-            let new_graph = GraphData::from_analysis(&result, &ctx.path);
-
-            // Update the shared graph data
-            {
-                let mut graph = graph_data.write().await;
-                *graph = new_graph;
-            }
-
-            // Notify clients
-            version += 1;
-            let _ = update_tx.send(version);
+        // Update the shared graph data
+        *graph_data.blocking_write() = new_graph;
 
-            style::success(&format!("Graph updated (version {})", version));
-            last_modified = current_files;
-        }
-    }
-}
+        // Notify clients
+        version += 1;
+        let _ = update_tx.send(version);
 
-fn scan_files(path: &PathBuf, files: &mut HashMap<PathBuf, std::time::SystemTime>) {
-    let fs = default_fs();
-    let walker = ignore::WalkBuilder::new(path)
-        .hidden(true)
-        .git_ignore(true)
-        .build();
-
-    for entry in walker.flatten() {
-        let file_path = entry.path();
-        if file_path.is_file() {
-            if let Ok(modified) = fs.modified(file_path) {
-                files.insert(file_path.to_path_buf(), modified);
-            }
-        }
-    }
+        style::success(&format!("Graph updated (version {})", version));
+    })
 }
 
 async fn index_handler() -> impl IntoResponse {
     Html(INDEX_HTML)
 }
 
-async fn graph_handler(State(state): State<Arc<AppState>>) -> impl IntoResponse {
+async fn graph_handler(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<GraphQuery>,
+) -> Result<Json<GraphData>, (StatusCode, String)> {
+    let min_severity = match query.min_severity {
+        Some(s) => s
+            .parse::<IssueSeverity>()
+            .map_err(|e| (StatusCode::BAD_REQUEST, e))?,
+        None => state.min_severity,
+    };
+
     let graph = state.graph_data.read().await;
-    Json(graph.clone())
+    Ok(Json(
+        graph
+            .clone()
+            .with_min_severity(min_severity)
+            .with_physics(state.physics),
+    ))
 }
 
 async fn sse_handler(
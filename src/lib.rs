@@ -1,39 +1,64 @@
 pub mod analysis;
 pub mod api;
+pub mod build_info;
 pub mod cli;
 pub mod commands;
 pub mod config;
 pub mod fs;
 pub mod graph;
+pub mod logging;
 pub mod model;
 pub mod output;
 pub mod parser;
 pub mod snapshot;
 pub mod style;
+#[cfg(feature = "tui")]
+pub mod tui;
 
 // =============================================================================
 // Library API (for use as a Rust dependency)
 // =============================================================================
 
 // Core library functions
-pub use api::{ai_context, analyze, impact};
+pub use api::{
+    ai_context, ai_context_with_fs, analyze, analyze_with_fs, graph_data, graph_data_with_fs,
+    impact, impact_with_fs, languages,
+};
+#[cfg(feature = "async")]
+pub use api::{ai_context_async, analyze_async, impact_async, progress_channel};
 
 // Options types for library functions
 pub use api::{
-    AiFormat, AiOptions, AnalysisOptions, ArchmapError, ImpactOptions, ImpactResult, Priority,
+    AiFormat, AiOptions, AiOptionsBuilder, AiVisibility, AnalysisOptions, AnalysisOptionsBuilder,
+    ArchmapError, ImpactOptions, ImpactOptionsBuilder, ImpactResult, OptionsError, Priority,
 };
 
 // Core model types
 pub use model::{
-    AnalysisResult, Definition, DefinitionKind, Issue, IssueKind, IssueSeverity, Location, Module,
-    Visibility,
+    AnalysisResult, Definition, DefinitionKind, Diagnostic, DiagnosticKind, Issue, IssueKind,
+    IssueKindFilter, IssueSeverity, Location, Module, ResultSummary, Span, Symbol, Visibility,
 };
 
 // Configuration
 pub use config::Config;
 
-// Re-export ImpactAnalysis for advanced use cases
-pub use analysis::ImpactAnalysis;
+// Supported languages, as returned by `languages`
+pub use parser::LanguageInfo;
+
+// Re-export ImpactAnalysis and the dependency graph for advanced use cases
+pub use analysis::{DependencyGraph, EdgeInfo, ImpactAnalysis, ImpactNode};
+
+// Files ranked by how much of the architecture they explain
+pub use analysis::{KeyFile, rank_key_files};
+
+// Graph data for custom visualizations
+pub use graph::{GraphData, GraphLink, GraphMetadata, GraphNode, GraphPhysics};
+
+// Progress reporting and cancellation for `analyze`
+pub use analysis::{CancellationToken, Progress, ProgressCallback};
+
+// Custom checks run alongside (or instead of) the built-in detectors
+pub use analysis::{BuiltinDetector, Detector, DetectorInput, DetectorSet};
 
 // =============================================================================
 // CLI API (for building CLI tools)
@@ -41,5 +66,6 @@ pub use analysis::ImpactAnalysis;
 
 pub use cli::Cli;
 pub use commands::{
-    cmd_ai, cmd_analyze, cmd_diff, cmd_graph, cmd_impact, cmd_init, cmd_mcp, cmd_snapshot,
+    cmd_ai, cmd_analyze, cmd_check, cmd_config_dump, cmd_diff, cmd_explain, cmd_graph, cmd_impact,
+    cmd_init, cmd_languages, cmd_list, cmd_mcp, cmd_snapshot, cmd_tui, cmd_version,
 };
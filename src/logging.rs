@@ -0,0 +1,155 @@
+//! Structured logging for `--log-format json`.
+//!
+//! The [`style`](crate::style) helpers for diagnostics (`error`, `warning`,
+//! `hint`, `status`, `verbose`) always emit `tracing` events, and analysis
+//! phases run inside spans. With the default pretty format no subscriber is
+//! installed, so the events cost nothing and the terminal output is unchanged.
+//! With JSON, [`init`] installs `tracing_subscriber`'s JSON formatter, which
+//! writes one object per event or closed span to stderr, and the pretty
+//! printing is skipped.
+
+use crate::style::Verbosity;
+use std::io;
+use std::sync::atomic::{AtomicBool, Ordering};
+use tracing::{Level, Subscriber};
+use tracing_subscriber::fmt::MakeWriter;
+use tracing_subscriber::fmt::format::FmtSpan;
+
+/// Diagnostic output format, set from the global `--log-format` flag
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum LogFormat {
+    /// Colored human-readable messages
+    #[default]
+    Pretty,
+    /// One JSON object per line on stderr
+    Json,
+}
+
+static JSON: AtomicBool = AtomicBool::new(false);
+
+/// Install the subscriber for `format`. Verbosity maps to the most detailed level
+/// written: errors only with `--quiet`, debug (per-file progress) with `--verbose`.
+pub fn init(format: LogFormat, verbosity: Verbosity) {
+    if format != LogFormat::Json {
+        return;
+    }
+    let max_level = match verbosity {
+        Verbosity::Quiet => Level::ERROR,
+        Verbosity::Normal => Level::INFO,
+        Verbosity::Verbose => Level::DEBUG,
+    };
+    let subscriber = json_subscriber(io::stderr, max_level);
+    if tracing::subscriber::set_global_default(subscriber).is_ok() {
+        JSON.store(true, Ordering::Relaxed);
+    }
+}
+
+/// Whether diagnostics go to the JSON subscriber instead of the terminal
+pub fn json_enabled() -> bool {
+    JSON.load(Ordering::Relaxed)
+}
+
+/// JSON lines on `writer`: each event with its fields next to `timestamp`,
+/// `level` and `target`, and each span once more as it closes, with its fields
+/// under `span` and its duration in `time.busy` and `time.idle`.
+fn json_subscriber<W>(writer: W, max_level: Level) -> impl Subscriber + Send + Sync
+where
+    W: for<'w> MakeWriter<'w> + Send + Sync + 'static,
+{
+    tracing_subscriber::fmt()
+        .json()
+        .flatten_event(true)
+        .with_current_span(true)
+        .with_span_list(false)
+        .with_span_events(FmtSpan::CLOSE)
+        .with_max_level(max_level)
+        .with_writer(writer)
+        .finish()
+}
+
+#[cfg(test)]
+pub mod capture {
+    use super::*;
+    use serde_json::Value;
+    use std::io::Write;
+    use std::sync::{Arc, Mutex};
+
+    /// Writer whose output the test can read back
+    #[derive(Clone, Default)]
+    struct Captured(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for Captured {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    impl Captured {
+        fn lines(&self) -> Vec<Value> {
+            let bytes = self.0.lock().unwrap();
+            String::from_utf8_lossy(&bytes)
+                .lines()
+                .map(|line| serde_json::from_str(line).unwrap())
+                .collect()
+        }
+    }
+
+    /// Run `f` with a JSON subscriber on this thread and return the logged lines,
+    /// so tests can assert on warnings without touching global state.
+    pub fn capture(max_level: Level, f: impl FnOnce()) -> Vec<Value> {
+        let captured = Captured::default();
+        let writer = captured.clone();
+        let subscriber = json_subscriber(move || writer.clone(), max_level);
+        tracing::subscriber::with_default(subscriber, f);
+        captured.lines()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::capture::capture;
+    use super::*;
+
+    #[test]
+    fn test_events_carry_level_message_and_fields() {
+        let lines = capture(Level::INFO, || {
+            crate::style::warning("config not found");
+            tracing::info!(files = 3, cached = false, "parsed");
+            tracing::debug!("hidden below the max level");
+        });
+
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0]["level"], "WARN");
+        assert_eq!(lines[0]["target"], "archmap::style");
+        assert_eq!(lines[0]["message"], "config not found");
+        assert_eq!(lines[1]["level"], "INFO");
+        assert_eq!(lines[1]["message"], "parsed");
+        assert_eq!(lines[1]["files"], 3);
+        assert_eq!(lines[1]["cached"], false);
+        assert!(lines[1]["timestamp"].is_string());
+    }
+
+    #[test]
+    fn test_spans_report_elapsed_time_when_closed() {
+        let lines = capture(Level::INFO, || {
+            let span = tracing::info_span!(
+                "detector",
+                kind = "GodObject",
+                issues = tracing::field::Empty
+            );
+            let _entered = span.enter();
+            span.record("issues", 2);
+        });
+
+        assert_eq!(lines.len(), 1);
+        assert_eq!(lines[0]["message"], "close");
+        assert_eq!(lines[0]["span"]["name"], "detector");
+        assert_eq!(lines[0]["span"]["kind"], "GodObject");
+        assert_eq!(lines[0]["span"]["issues"], 2);
+        assert!(lines[0]["time.busy"].is_string());
+    }
+}
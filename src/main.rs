@@ -1,38 +1,70 @@
 use archmap::cli::{AnalyzeArgs, Cli, Command};
+use archmap::commands::exit_code;
 use archmap::{
-    cmd_ai, cmd_analyze, cmd_diff, cmd_graph, cmd_impact, cmd_init, cmd_mcp, cmd_snapshot,
+    cmd_ai, cmd_analyze, cmd_check, cmd_config_dump, cmd_diff, cmd_explain, cmd_graph, cmd_impact,
+    cmd_init, cmd_languages, cmd_list, cmd_mcp, cmd_snapshot, cmd_tui, cmd_version,
 };
 use clap::Parser;
 
 fn main() {
     let cli = Cli::parse();
+    archmap::style::init(cli.verbosity(), cli.no_color);
+    archmap::style::set_thousands_separator(!cli.no_thousands_sep);
+    archmap::style::set_severity_icons(cli.severity_icons);
+    archmap::logging::init(cli.log_format, cli.verbosity());
+    archmap::analysis::quiet_contained_panics();
+    archmap::fs::set_canonicalize(!cli.no_canonicalize);
+    archmap::config::set_config_source(cli.config_source());
+    if let Some(threads) = cli.threads {
+        archmap::analysis::set_threads(threads);
+    }
+    if cli.no_size_limit {
+        archmap::analysis::disable_size_limits();
+    }
+    if cli.follow_symlinks {
+        archmap::analysis::set_follow_symlinks();
+    }
+    if cli.no_cache {
+        archmap::analysis::disable_cache();
+    }
 
     // Handle --mcp-manifest flag
     if cli.mcp_manifest {
         print_mcp_manifest();
-        std::process::exit(0);
+        std::process::exit(exit_code::SUCCESS);
     }
 
-    let exit_code = match cli.command {
+    let code = match cli.command {
         Some(Command::Analyze(args)) => cmd_analyze(args),
         Some(Command::Ai(args)) => cmd_ai(args),
         Some(Command::Impact(args)) => cmd_impact(args),
         Some(Command::Snapshot(args)) => cmd_snapshot(args),
         Some(Command::Diff(args)) => cmd_diff(args),
+        Some(Command::Check(args)) => cmd_check(args),
+        Some(Command::List(args)) => cmd_list(args),
+        Some(Command::Explain(args)) => cmd_explain(args),
         Some(Command::Graph(args)) => cmd_graph(args),
         Some(Command::Init(args)) => cmd_init(args),
+        Some(Command::ConfigDump(args)) => cmd_config_dump(args),
         Some(Command::Mcp(args)) => cmd_mcp(args),
+        Some(Command::Tui(args)) => cmd_tui(args),
+        Some(Command::Version(args)) => cmd_version(args),
+        Some(Command::Languages(args)) => cmd_languages(args),
         None => {
             // Backward compatibility: treat path as analyze command
             let args = AnalyzeArgs {
-                path: cli.path,
+                paths: vec![cli.path],
                 ..Default::default()
             };
             cmd_analyze(args)
         }
     };
 
-    std::process::exit(exit_code);
+    // --exit-zero only forgives findings, never failures to run
+    if cli.exit_zero && code == exit_code::ISSUES {
+        std::process::exit(exit_code::SUCCESS);
+    }
+    std::process::exit(code);
 }
 
 fn print_mcp_manifest() {
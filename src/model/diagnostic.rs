@@ -0,0 +1,112 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::path::PathBuf;
+
+/// Something analysis worked around rather than reported as an issue: a file
+/// it had to skip, a setting it ignored. The CLI prints these as warnings;
+/// library callers decide for themselves.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Diagnostic {
+    pub kind: DiagnosticKind,
+    /// File the diagnostic is about, if any
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub path: Option<PathBuf>,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum DiagnosticKind {
+    /// A source file couldn't be read (e.g. neither UTF-8 nor UTF-16) and is missing from the result
+    ReadFailed,
+    /// A source file couldn't be parsed, took too long to parse or crashed
+    /// the parser, and is missing from the result
+    ParseFailed,
+    /// A source file was left out on purpose: too large, binary or minified
+    Skipped,
+    /// Many relative imports point at files that weren't analyzed
+    UnresolvedImports,
+    /// A name in `disabled_checks` or `enabled_checks` that matches no check
+    UnknownCheck,
+    /// A setting that couldn't be used, so its default applies instead
+    ConfigFallback,
+}
+
+impl Diagnostic {
+    pub fn read_failed(path: PathBuf, error: impl fmt::Display) -> Self {
+        Self {
+            kind: DiagnosticKind::ReadFailed,
+            message: format!("Failed to read {}: {}", path.display(), error),
+            path: Some(path),
+        }
+    }
+
+    pub fn parse_failed(path: PathBuf, error: impl fmt::Display) -> Self {
+        Self {
+            kind: DiagnosticKind::ParseFailed,
+            message: format!("Failed to parse {}: {}", path.display(), error),
+            path: Some(path),
+        }
+    }
+
+    pub fn parse_timed_out(path: PathBuf, limit_ms: u64) -> Self {
+        Self {
+            kind: DiagnosticKind::ParseFailed,
+            message: format!(
+                "Skipped {}: parsing took over {} ms (parse_timeout_ms)",
+                path.display(),
+                limit_ms
+            ),
+            path: Some(path),
+        }
+    }
+
+    pub fn parse_panicked(path: PathBuf, message: impl fmt::Display) -> Self {
+        Self {
+            kind: DiagnosticKind::ParseFailed,
+            message: format!("Parser crashed on {}: {}", path.display(), message),
+            path: Some(path),
+        }
+    }
+
+    pub fn skipped(path: PathBuf, reason: impl fmt::Display) -> Self {
+        Self {
+            kind: DiagnosticKind::Skipped,
+            message: format!("Skipped {}: {}", path.display(), reason),
+            path: Some(path),
+        }
+    }
+
+    pub fn unresolved_imports(unresolved: usize, relative: usize) -> Self {
+        Self {
+            kind: DiagnosticKind::UnresolvedImports,
+            path: None,
+            message: format!(
+                "{} of {} relative imports don't resolve to an analyzed file; \
+                 dependencies through them are missing (check the path, --lang and --exclude)",
+                unresolved, relative
+            ),
+        }
+    }
+
+    pub fn unknown_check(message: String) -> Self {
+        Self {
+            kind: DiagnosticKind::UnknownCheck,
+            path: None,
+            message,
+        }
+    }
+
+    pub fn config_fallback(path: Option<PathBuf>, message: String) -> Self {
+        Self {
+            kind: DiagnosticKind::ConfigFallback,
+            path,
+            message,
+        }
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
@@ -29,6 +29,58 @@ pub enum IssueKind {
         private_functions: usize,
         public_functions: usize,
     },
+    /// Widely imported constants/types module that also carries logic
+    FatSharedModule {
+        fan_in: usize,
+        functions: usize,
+    },
+    /// Trait or interface implemented across many modules
+    ScatteredImplementation {
+        trait_name: String,
+        file_count: usize,
+    },
+    /// Public function whose name appears nowhere but its own definition
+    DeadPublicFunction {
+        function: String,
+    },
+    /// Module with a long import list, whether the imports resolve internally or not
+    TooManyImports {
+        imports: usize,
+    },
+    /// Top-level directory with too many modules or lines
+    GodPackage {
+        /// Directory relative to the project, e.g. `src/services`
+        dir: String,
+        modules: usize,
+        lines: usize,
+    },
+    /// Test module that imports no production code, only other tests or nothing
+    EmptyTest,
+    /// Module of one framework role importing one it should reach through a
+    /// service, e.g. a `@Component` importing a `@Repository`
+    RoleViolation {
+        role: String,
+        imported_role: String,
+    },
+}
+
+/// An [`IssueKind`] without its data, for selecting issues of one kind
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum IssueKindFilter {
+    CircularDependency,
+    GodObject,
+    HighCoupling,
+    BoundaryViolation,
+    DeepDependencyChain,
+    LowCohesion,
+    FatModule,
+    FatSharedModule,
+    ScatteredImplementation,
+    DeadPublicFunction,
+    TooManyImports,
+    GodPackage,
+    EmptyTest,
+    RoleViolation,
 }
 
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord)]
@@ -43,6 +95,66 @@ pub struct Location {
     pub path: PathBuf,
     pub line: Option<usize>,
     pub context: Option<String>,
+    /// Boundary indicator the line matched, for boundary violations
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub indicator: Option<String>,
+}
+
+impl IssueKind {
+    /// Every variant name, in detection order
+    pub const NAMES: [&'static str; 14] = [
+        "CircularDependency",
+        "GodObject",
+        "HighCoupling",
+        "BoundaryViolation",
+        "DeepDependencyChain",
+        "LowCohesion",
+        "FatModule",
+        "FatSharedModule",
+        "ScatteredImplementation",
+        "DeadPublicFunction",
+        "TooManyImports",
+        "GodPackage",
+        "EmptyTest",
+        "RoleViolation",
+    ];
+
+    /// Kinds whose detectors only run when listed in `enabled_checks` (or `--enable`),
+    /// because their heuristics are too noisy to apply by default
+    pub const OPT_IN: [&'static str; 1] = ["DeadPublicFunction"];
+
+    /// Resolve a user-supplied kind name, ignoring case, `_` and `-`
+    /// (`low_cohesion`, `low-cohesion` and `LowCohesion` are all accepted).
+    pub fn canonical_name(name: &str) -> Option<&'static str> {
+        let normalize = |s: &str| {
+            s.chars()
+                .filter(|c| *c != '_' && *c != '-')
+                .flat_map(char::to_lowercase)
+                .collect::<String>()
+        };
+        let wanted = normalize(name);
+        Self::NAMES.into_iter().find(|n| normalize(n) == wanted)
+    }
+
+    /// Stable variant name without any embedded data (e.g. `LowCohesion`).
+    pub fn name(&self) -> &'static str {
+        match self {
+            IssueKind::CircularDependency => "CircularDependency",
+            IssueKind::GodObject => "GodObject",
+            IssueKind::HighCoupling => "HighCoupling",
+            IssueKind::BoundaryViolation { .. } => "BoundaryViolation",
+            IssueKind::DeepDependencyChain { .. } => "DeepDependencyChain",
+            IssueKind::LowCohesion { .. } => "LowCohesion",
+            IssueKind::FatModule { .. } => "FatModule",
+            IssueKind::FatSharedModule { .. } => "FatSharedModule",
+            IssueKind::ScatteredImplementation { .. } => "ScatteredImplementation",
+            IssueKind::DeadPublicFunction { .. } => "DeadPublicFunction",
+            IssueKind::TooManyImports { .. } => "TooManyImports",
+            IssueKind::GodPackage { .. } => "GodPackage",
+            IssueKind::EmptyTest => "EmptyTest",
+            IssueKind::RoleViolation { .. } => "RoleViolation",
+        }
+    }
 }
 
 impl Issue {
@@ -53,6 +165,7 @@ impl Issue {
                 path: p.clone(),
                 line: None,
                 context: None,
+                indicator: None,
             })
             .collect();
 
@@ -81,6 +194,7 @@ impl Issue {
                 path,
                 line: None,
                 context: None,
+                indicator: None,
             }],
             message: format!(
                 "{} lines with mixed responsibilities: {}",
@@ -99,6 +213,7 @@ impl Issue {
                 path,
                 line: None,
                 context: None,
+                indicator: None,
             }],
             message: format!("Imported by {} other modules", fan_in),
             suggestion: Some("High coupling makes changes risky. Consider if this module has too many responsibilities".to_string()),
@@ -133,6 +248,7 @@ impl Issue {
                 path: p.clone(),
                 line: None,
                 context: None,
+                indicator: None,
             })
             .collect();
 
@@ -170,6 +286,7 @@ impl Issue {
                 path,
                 line: None,
                 context: None,
+                indicator: None,
             }],
             message: format!(
                 "Cohesion score: {:.2} ({} internal, {} external imports)",
@@ -203,6 +320,7 @@ impl Issue {
                 path,
                 line: None,
                 context: None,
+                indicator: None,
             }],
             message: format!(
                 "Cohesion score: {:.2} ({} internal imports, {} external from {} different crates{})",
@@ -214,6 +332,32 @@ impl Issue {
         }
     }
 
+    /// LCOM-style cohesion: few function pairs mention an import in common
+    pub fn low_cohesion_lcom(
+        path: PathBuf,
+        score: f64,
+        sharing_pairs: usize,
+        pairs: usize,
+    ) -> Self {
+        Self {
+            kind: IssueKind::LowCohesion { score },
+            severity: IssueSeverity::Info,
+            locations: vec![Location {
+                path,
+                line: None,
+                context: None,
+                indicator: None,
+            }],
+            message: format!(
+                "Cohesion score: {:.2} ({} of {} function pairs share an import)",
+                score, sharing_pairs, pairs
+            ),
+            suggestion: Some(
+                "Its functions work with mostly separate imports, suggesting unrelated concerns. Consider splitting along those groups.".to_string(),
+            ),
+        }
+    }
+
     /// Fat module: excessive internal complexity hidden behind a small interface
     pub fn fat_module(
         path: PathBuf,
@@ -232,6 +376,7 @@ impl Issue {
                 path,
                 line: None,
                 context: None,
+                indicator: None,
             }],
             message: format!(
                 "{} lines with {} private functions but only {} exports",
@@ -244,6 +389,275 @@ impl Issue {
             ),
         }
     }
+
+    /// Fat shared module: a high fan-in constants/types module that also contains logic
+    pub fn fat_shared_module(
+        path: PathBuf,
+        fan_in: usize,
+        functions: usize,
+        data_definitions: usize,
+    ) -> Self {
+        Self {
+            kind: IssueKind::FatSharedModule { fan_in, functions },
+            severity: IssueSeverity::Warn,
+            locations: vec![Location {
+                path,
+                line: None,
+                context: None,
+                indicator: None,
+            }],
+            message: format!(
+                "Imported by {} modules; mixes {} constant/type definitions with {} non-trivial functions",
+                fan_in, data_definitions, functions
+            ),
+            suggestion: Some(
+                "Every dependent is exposed to changes in this module's logic. \
+                Split the constants and types into their own module and move the functions elsewhere."
+                    .to_string(),
+            ),
+        }
+    }
+
+    /// Scattered implementation: one trait or interface implemented in many modules.
+    /// `locations` lists the definition first, then each implementation.
+    pub fn scattered_implementation(
+        trait_name: String,
+        file_count: usize,
+        locations: Vec<Location>,
+    ) -> Self {
+        Self {
+            message: format!(
+                "{} is implemented in {} different modules",
+                trait_name, file_count
+            ),
+            kind: IssueKind::ScatteredImplementation {
+                trait_name,
+                file_count,
+            },
+            severity: IssueSeverity::Info,
+            locations,
+            suggestion: Some(
+                "Readers have to visit every file to see all implementations. \
+                Group the implementations next to the trait, or by the feature they serve."
+                    .to_string(),
+            ),
+        }
+    }
+
+    /// Dead public function: exported, yet not mentioned in any other module or
+    /// anywhere else in its own
+    pub fn dead_public_function(path: PathBuf, function: String, line: usize) -> Self {
+        Self {
+            message: format!(
+                "Public function `{}` is not referenced anywhere in the project",
+                function
+            ),
+            kind: IssueKind::DeadPublicFunction { function },
+            severity: IssueSeverity::Info,
+            locations: vec![Location {
+                path,
+                line: Some(line),
+                context: None,
+                indicator: None,
+            }],
+            suggestion: Some(
+                "Remove it, or make it private if it is kept for later. \
+                Functions called only through macros, reflection or an external API are false positives."
+                    .to_string(),
+            ),
+        }
+    }
+
+    /// Too many imports: a long import list, counting external crates and
+    /// packages that fan-out ignores
+    pub fn too_many_imports(path: PathBuf, imports: usize, internal: usize) -> Self {
+        Self {
+            kind: IssueKind::TooManyImports { imports },
+            severity: IssueSeverity::Warn,
+            locations: vec![Location {
+                path,
+                line: None,
+                context: None,
+                indicator: None,
+            }],
+            message: format!(
+                "{} imports ({} internal, {} external)",
+                imports,
+                internal,
+                imports.saturating_sub(internal)
+            ),
+            suggestion: Some(
+                "A module that needs this much is likely doing several jobs. \
+                Split it along the groups of imports each part of the code uses."
+                    .to_string(),
+            ),
+        }
+    }
+
+    /// God package: a directory grown too large. `outgoing` and `incoming`
+    /// count the modules outside it that its modules import and that import
+    /// them; `locations` are its largest modules.
+    pub fn god_package(
+        dir: String,
+        modules: usize,
+        lines: usize,
+        outgoing: usize,
+        incoming: usize,
+        locations: Vec<Location>,
+    ) -> Self {
+        Self {
+            message: format!(
+                "`{}/` holds {} modules and {} lines; they import {} modules outside it and are imported by {}",
+                dir, modules, lines, outgoing, incoming
+            ),
+            kind: IssueKind::GodPackage {
+                dir,
+                modules,
+                lines,
+            },
+            severity: IssueSeverity::Warn,
+            locations,
+            suggestion: Some(
+                "A directory this size has no single purpose a reader can keep in mind. \
+                Split it into subpackages along the groups of modules that import each other."
+                    .to_string(),
+            ),
+        }
+    }
+
+    /// Empty test: a test module whose resolved imports reach no production
+    /// module. `imports` counts the test modules it does import.
+    pub fn empty_test(path: PathBuf, imports: usize) -> Self {
+        let message = if imports == 0 {
+            "Test imports no module of the project".to_string()
+        } else {
+            format!(
+                "Test imports {} test module(s) but no production code",
+                imports
+            )
+        };
+        Self {
+            kind: IssueKind::EmptyTest,
+            severity: IssueSeverity::Info,
+            locations: vec![Location {
+                path,
+                line: None,
+                context: None,
+                indicator: None,
+            }],
+            message,
+            suggestion: Some(
+                "The code it tested may be gone. Delete the test, or point it at the code it \
+                should exercise. Shared fixtures and harness files belong in test_harness_paths."
+                    .to_string(),
+            ),
+        }
+    }
+
+    /// Role violation: a module of `role` importing one of `imported_role`
+    /// directly. `locations` are the importing module, then the imported one.
+    pub fn role_violation(role: String, imported_role: String, locations: Vec<Location>) -> Self {
+        let imported = locations
+            .get(1)
+            .and_then(|loc| loc.path.file_name())
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        Self {
+            message: format!(
+                "A {} imports the {} `{}` directly instead of going through a service",
+                role, imported_role, imported
+            ),
+            kind: IssueKind::RoleViolation {
+                role,
+                imported_role,
+            },
+            severity: IssueSeverity::Warn,
+            locations,
+            suggestion: Some(
+                "Move the data access into an @Injectable service and inject that instead, \
+                so views and request handlers stay independent of storage."
+                    .to_string(),
+            ),
+        }
+    }
+}
+
+impl IssueKindFilter {
+    /// Every kind, in the order of [`IssueKind::NAMES`]
+    pub const ALL: [IssueKindFilter; 14] = [
+        IssueKindFilter::CircularDependency,
+        IssueKindFilter::GodObject,
+        IssueKindFilter::HighCoupling,
+        IssueKindFilter::BoundaryViolation,
+        IssueKindFilter::DeepDependencyChain,
+        IssueKindFilter::LowCohesion,
+        IssueKindFilter::FatModule,
+        IssueKindFilter::FatSharedModule,
+        IssueKindFilter::ScatteredImplementation,
+        IssueKindFilter::DeadPublicFunction,
+        IssueKindFilter::TooManyImports,
+        IssueKindFilter::GodPackage,
+        IssueKindFilter::EmptyTest,
+        IssueKindFilter::RoleViolation,
+    ];
+
+    /// Variant name shared with [`IssueKind::name`]
+    pub fn name(&self) -> &'static str {
+        match self {
+            IssueKindFilter::CircularDependency => "CircularDependency",
+            IssueKindFilter::GodObject => "GodObject",
+            IssueKindFilter::HighCoupling => "HighCoupling",
+            IssueKindFilter::BoundaryViolation => "BoundaryViolation",
+            IssueKindFilter::DeepDependencyChain => "DeepDependencyChain",
+            IssueKindFilter::LowCohesion => "LowCohesion",
+            IssueKindFilter::FatModule => "FatModule",
+            IssueKindFilter::FatSharedModule => "FatSharedModule",
+            IssueKindFilter::ScatteredImplementation => "ScatteredImplementation",
+            IssueKindFilter::DeadPublicFunction => "DeadPublicFunction",
+            IssueKindFilter::TooManyImports => "TooManyImports",
+            IssueKindFilter::GodPackage => "GodPackage",
+            IssueKindFilter::EmptyTest => "EmptyTest",
+            IssueKindFilter::RoleViolation => "RoleViolation",
+        }
+    }
+
+    pub fn matches(&self, kind: &IssueKind) -> bool {
+        self.name() == kind.name()
+    }
+}
+
+impl From<&IssueKind> for IssueKindFilter {
+    fn from(kind: &IssueKind) -> Self {
+        match kind {
+            IssueKind::CircularDependency => IssueKindFilter::CircularDependency,
+            IssueKind::GodObject => IssueKindFilter::GodObject,
+            IssueKind::HighCoupling => IssueKindFilter::HighCoupling,
+            IssueKind::BoundaryViolation { .. } => IssueKindFilter::BoundaryViolation,
+            IssueKind::DeepDependencyChain { .. } => IssueKindFilter::DeepDependencyChain,
+            IssueKind::LowCohesion { .. } => IssueKindFilter::LowCohesion,
+            IssueKind::FatModule { .. } => IssueKindFilter::FatModule,
+            IssueKind::FatSharedModule { .. } => IssueKindFilter::FatSharedModule,
+            IssueKind::ScatteredImplementation { .. } => IssueKindFilter::ScatteredImplementation,
+            IssueKind::DeadPublicFunction { .. } => IssueKindFilter::DeadPublicFunction,
+            IssueKind::TooManyImports { .. } => IssueKindFilter::TooManyImports,
+            IssueKind::GodPackage { .. } => IssueKindFilter::GodPackage,
+            IssueKind::EmptyTest => IssueKindFilter::EmptyTest,
+            IssueKind::RoleViolation { .. } => IssueKindFilter::RoleViolation,
+        }
+    }
+}
+
+impl std::str::FromStr for IssueKindFilter {
+    type Err = String;
+
+    /// Accepts the same spellings as [`IssueKind::canonical_name`]
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let name = IssueKind::canonical_name(s);
+        Self::ALL
+            .into_iter()
+            .find(|filter| Some(filter.name()) == name)
+            .ok_or_else(|| format!("Unknown issue kind: {}", s))
+    }
 }
 
 impl std::fmt::Display for IssueSeverity {
@@ -1,17 +1,116 @@
 mod boundary;
+mod diagnostic;
 mod issue;
 mod module;
+mod query;
+mod symbol;
 
 pub use boundary::{Boundary, BoundaryKind, BoundaryViolation, glob_match};
-pub use issue::{Issue, IssueKind, IssueSeverity, Location};
-pub use module::{Definition, DefinitionKind, Module, Visibility};
+pub use diagnostic::{Diagnostic, DiagnosticKind};
+pub use issue::{Issue, IssueKind, IssueKindFilter, IssueSeverity, Location};
+pub use module::{
+    Definition, DefinitionKind, Implementation, Module, ModuleIndex, Span, Visibility,
+};
+pub use query::ResultSummary;
+pub use symbol::{InternStats, Symbol, intern_modules};
 
-use petgraph::graph::DiGraph;
+pub(crate) use query::LazyModuleIndex;
+
+use crate::analysis::DependencyGraph;
+use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
 
+/// Everything one analysis found. Serializes with definitions, issues and the
+/// dependency graph intact, so it can be stored or sent to another process.
+#[derive(Clone, Serialize, Deserialize)]
 pub struct AnalysisResult {
     pub project_name: String,
+    /// Sorted by path
     pub modules: Vec<Module>,
     pub issues: Vec<Issue>,
-    pub dependency_graph: DiGraph<PathBuf, ()>,
+    /// Dependency graph built during analysis, reusable by formatters
+    pub dependency_graph: DependencyGraph,
+    /// Files detected as generated (see `Config::generated_markers`)
+    pub generated_files: Vec<PathBuf>,
+    /// Files skipped and settings ignored along the way
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub diagnostics: Vec<Diagnostic>,
+    #[serde(skip)]
+    pub(crate) module_index: LazyModuleIndex,
+}
+
+impl AnalysisResult {
+    /// A result for `modules`, with the dependency graph built from their imports
+    pub fn new(project_name: impl Into<String>, modules: Vec<Module>, issues: Vec<Issue>) -> Self {
+        Self {
+            project_name: project_name.into(),
+            dependency_graph: DependencyGraph::build(&modules),
+            modules,
+            issues,
+            generated_files: Vec::new(),
+            diagnostics: Vec::new(),
+            module_index: LazyModuleIndex::default(),
+        }
+    }
+
+    /// Compact JSON holding the whole result, readable by [`AnalysisResult::from_json`]
+    pub fn to_json(&self) -> serde_json::Result<String> {
+        serde_json::to_string(self)
+    }
+
+    pub fn from_json(json: &str) -> serde_json::Result<Self> {
+        serde_json::from_str(json)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+
+    #[test]
+    fn test_round_trip_keeps_definitions_issues_and_edges() {
+        let mut lib = Module::new(PathBuf::from("/p/lib.ts"));
+        lib.imports = vec!["./core".into(), "./log".into()];
+        lib.definitions.push(Definition {
+            name: "run".to_string(),
+            kind: DefinitionKind::Function,
+            line: 3,
+            visibility: Visibility::Public,
+            signature: Some("export function run()".to_string()),
+            span: None,
+            compact_signature: None,
+            decorators: Vec::new(),
+        });
+        let mut core = Module::new(PathBuf::from("/p/core.ts"));
+        core.imports = vec!["./lib".into()];
+        let log = Module::new(PathBuf::from("/p/log.ts"));
+        let modules = vec![lib, core, log];
+        let mut result = AnalysisResult::new(
+            "p".to_string(),
+            modules,
+            vec![Issue::high_coupling(PathBuf::from("/p/core.ts"), 7)],
+        );
+        result.generated_files = vec![PathBuf::from("/p/gen.ts")];
+
+        let json = result.to_json().unwrap();
+        let restored = AnalysisResult::from_json(&json).unwrap();
+        assert_eq!(restored.to_json().unwrap(), json);
+
+        assert_eq!(restored.modules[0].definitions[0].name, "run");
+        assert_eq!(restored.issues[0].message, result.issues[0].message);
+        let graph = &restored.dependency_graph;
+        let lib = PathBuf::from("/p/lib.ts");
+        assert_eq!(
+            graph.direct_dependencies(&lib),
+            result.dependency_graph.direct_dependencies(&lib)
+        );
+        assert_eq!(graph.fan_in(&lib), 1);
+        assert!(graph.contains(&PathBuf::from("/p/log.ts")));
+        assert_eq!(graph.graph().edge_count(), 3);
+
+        let rebuilt = DependencyGraph::from_result(&restored);
+        assert_eq!(rebuilt.fan_out(&lib), graph.fan_out(&lib));
+        assert_eq!(restored.generated_files, [Path::new("/p/gen.ts")]);
+    }
 }
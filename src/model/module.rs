@@ -1,14 +1,40 @@
+use super::Symbol;
 use serde::{Deserialize, Serialize};
-use std::path::PathBuf;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Module {
     pub path: PathBuf,
     pub name: String,
     pub lines: usize,
-    pub imports: Vec<String>,
-    pub exports: Vec<String>,
+    /// Import specifiers as written (`./util`, `std::fmt`), shared between
+    /// modules that import the same thing
+    pub imports: Vec<Symbol>,
+    /// Project modules this module depends on, resolved from `imports` during analysis
+    #[serde(default)]
+    pub resolved_imports: Vec<PathBuf>,
+    pub exports: Vec<Symbol>,
     pub definitions: Vec<Definition>,
+    /// Traits and interfaces implemented in this module
+    #[serde(default)]
+    pub implementations: Vec<Implementation>,
+    /// `key=value` tags from `@archmap:` annotation comments
+    #[serde(default)]
+    pub tags: HashMap<String, String>,
+    /// Other paths the same file was found at through symlinks. Imports of
+    /// any of them resolve to this module.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub aliases: Vec<PathBuf>,
+}
+
+/// `impl Trait for Type` in Rust, `class Type implements Interface` in TypeScript.
+/// Names are bare, without paths or generic arguments.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Implementation {
+    pub trait_name: String,
+    pub type_name: String,
+    pub line: usize,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -19,11 +45,65 @@ pub struct Definition {
     /// Visibility of the definition (public, private, crate-visible)
     #[serde(default)]
     pub visibility: Visibility,
-    /// Full signature text (for functions, structs, etc.)
+    /// Signature text: the declaration of a function; for structs, enums,
+    /// traits, classes and the like only their first line, the rest being at
+    /// `span` in the module's source (see [`Definition::full_text`])
     #[serde(default)]
     pub signature: Option<String>,
+    /// Where the whole definition is in the module's source, for
+    /// definitions too large to keep a copy of
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub span: Option<Span>,
+    /// Declaration without its body: field names for structs, variant names
+    /// for enums. Set by parsers that can tell them apart (currently Rust).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub compact_signature: Option<String>,
+    /// Names of the decorators on a class, without `@` or arguments
+    /// (`Component` for `@Component({...})`). Set by the TypeScript parser.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub decorators: Vec<String>,
+}
+
+/// Byte range of a definition in its module's source
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+}
+
+impl Span {
+    /// The text this span covers in `source`, or `None` when the source has
+    /// changed so much that it no longer fits
+    pub fn text<'a>(&self, source: &'a str) -> Option<&'a str> {
+        source.get(self.start..self.end)
+    }
+}
+
+impl Definition {
+    /// The definition as written, taken from the module's `source` when it
+    /// has a span, or else its signature.
+    pub fn full_text<'a>(&'a self, source: Option<&'a str>) -> Option<&'a str> {
+        self.span
+            .zip(source)
+            .and_then(|(span, source)| span.text(source))
+            .or(self.signature.as_deref())
+    }
 }
 
+/// Class decorators that give a module a framework role (Angular, NestJS,
+/// TypeORM), and the role each names
+const FRAMEWORK_ROLES: &[(&str, &str)] = &[
+    ("Component", "component"),
+    ("Directive", "directive"),
+    ("Pipe", "pipe"),
+    ("Injectable", "service"),
+    ("Controller", "controller"),
+    ("Resolver", "controller"),
+    ("Repository", "repository"),
+    ("EntityRepository", "repository"),
+    ("Entity", "entity"),
+];
+
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
 pub enum Visibility {
     Public,
@@ -46,6 +126,71 @@ pub enum DefinitionKind {
     Constant,
 }
 
+impl DefinitionKind {
+    /// Every kind, in declaration order
+    pub const ALL: [DefinitionKind; 9] = [
+        DefinitionKind::Function,
+        DefinitionKind::Struct,
+        DefinitionKind::Enum,
+        DefinitionKind::Trait,
+        DefinitionKind::Impl,
+        DefinitionKind::Class,
+        DefinitionKind::Interface,
+        DefinitionKind::Type,
+        DefinitionKind::Constant,
+    ];
+
+    /// Lowercase name, as accepted by `ai --kinds`
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DefinitionKind::Function => "function",
+            DefinitionKind::Struct => "struct",
+            DefinitionKind::Enum => "enum",
+            DefinitionKind::Trait => "trait",
+            DefinitionKind::Impl => "impl",
+            DefinitionKind::Class => "class",
+            DefinitionKind::Interface => "interface",
+            DefinitionKind::Type => "type",
+            DefinitionKind::Constant => "constant",
+        }
+    }
+}
+
+impl std::str::FromStr for DefinitionKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let name = s.trim().to_lowercase();
+        let name = match name.as_str() {
+            "fn" => "function",
+            "const" => "constant",
+            other => other,
+        };
+        DefinitionKind::ALL
+            .into_iter()
+            .find(|kind| kind.as_str() == name)
+            .ok_or_else(|| {
+                let names: Vec<_> = DefinitionKind::ALL.iter().map(|k| k.as_str()).collect();
+                format!(
+                    "Unknown definition kind: {} (expected {})",
+                    s,
+                    names.join(", ")
+                )
+            })
+    }
+}
+
+impl Visibility {
+    /// Lowercase label used in structured output
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Visibility::Public => "public",
+            Visibility::Crate => "crate",
+            Visibility::Private => "private",
+        }
+    }
+}
+
 impl Module {
     pub fn new(path: PathBuf) -> Self {
         let name = path
@@ -59,16 +204,60 @@ impl Module {
             name,
             lines: 0,
             imports: Vec::new(),
+            resolved_imports: Vec::new(),
             exports: Vec::new(),
             definitions: Vec::new(),
+            implementations: Vec::new(),
+            tags: HashMap::new(),
+            aliases: Vec::new(),
+        }
+    }
+
+    /// Architectural role: the `role` tag of an `@archmap:` annotation, else
+    /// the role named by the first framework decorator on one of its classes
+    /// (`controller` for `@Controller()`).
+    pub fn role(&self) -> Option<&str> {
+        if let Some(role) = self.tags.get("role") {
+            return Some(role);
         }
+        self.definitions
+            .iter()
+            .flat_map(|def| &def.decorators)
+            .find_map(|name| {
+                FRAMEWORK_ROLES
+                    .iter()
+                    .find(|(decorator, _)| decorator == name)
+                    .map(|(_, role)| *role)
+            })
     }
 
     /// Add a definition to the module, automatically updating exports if public.
     pub fn add_definition(&mut self, def: Definition) {
         if def.visibility == Visibility::Public {
-            self.exports.push(def.name.clone());
+            self.exports.push(Symbol::from(&def.name));
         }
         self.definitions.push(def);
     }
 }
+
+/// Modules by path, built once for code that looks modules up while walking
+/// a graph's paths. The first module with a path wins, as a linear search
+/// would find it.
+#[derive(Debug, Clone, Default)]
+pub struct ModuleIndex<'a> {
+    by_path: HashMap<&'a Path, &'a Module>,
+}
+
+impl<'a> ModuleIndex<'a> {
+    pub fn new(modules: &'a [Module]) -> Self {
+        let mut by_path = HashMap::with_capacity(modules.len());
+        for module in modules {
+            by_path.entry(module.path.as_path()).or_insert(module);
+        }
+        Self { by_path }
+    }
+
+    pub fn get(&self, path: &Path) -> Option<&'a Module> {
+        self.by_path.get(path).copied()
+    }
+}
@@ -0,0 +1,182 @@
+//! Lookups over an [`AnalysisResult`] that every consumer would otherwise
+//! write for itself.
+
+use super::{AnalysisResult, Issue, IssueKindFilter, IssueSeverity, Module, glob_match};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+/// Position of each module by path, built on the first [`AnalysisResult::module`] call
+#[derive(Clone, Default)]
+pub(crate) struct LazyModuleIndex(OnceLock<HashMap<PathBuf, usize>>);
+
+/// Issue and module counts for a whole result
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ResultSummary {
+    pub modules: usize,
+    pub lines: usize,
+    pub dependencies: usize,
+    pub issues: usize,
+    pub generated_files: usize,
+    /// Issues per severity; severities without issues are left out
+    pub by_severity: BTreeMap<IssueSeverity, usize>,
+    /// Issues per [`IssueKind::name`](super::IssueKind::name); kinds without issues are left out
+    pub by_kind: BTreeMap<&'static str, usize>,
+}
+
+impl ResultSummary {
+    /// Issues of exactly this severity
+    pub fn count(&self, severity: IssueSeverity) -> usize {
+        self.by_severity.get(&severity).copied().unwrap_or(0)
+    }
+}
+
+impl AnalysisResult {
+    /// The module parsed from `path`.
+    ///
+    /// Lookups go through an index built on first use. `modules` is a public
+    /// field, so a path the index no longer places correctly falls back to a
+    /// scan rather than returning a stale module.
+    pub fn module(&self, path: &Path) -> Option<&Module> {
+        let index = self.module_index.0.get_or_init(|| {
+            self.modules
+                .iter()
+                .enumerate()
+                .map(|(i, m)| (m.path.clone(), i))
+                .collect()
+        });
+        index
+            .get(path)
+            .and_then(|&i| self.modules.get(i))
+            .filter(|m| m.path == path)
+            .or_else(|| self.modules.iter().find(|m| m.path == path))
+    }
+
+    /// Issues with a location in `path`
+    pub fn issues_for<'a>(&'a self, path: &'a Path) -> impl Iterator<Item = &'a Issue> + 'a {
+        self.issues
+            .iter()
+            .filter(move |i| i.locations.iter().any(|loc| loc.path == path))
+    }
+
+    pub fn issues_of_kind(&self, kind: IssueKindFilter) -> impl Iterator<Item = &Issue> + '_ {
+        self.issues.iter().filter(move |i| kind.matches(&i.kind))
+    }
+
+    /// Issues at `severity` or above
+    pub fn issues_at_least(&self, severity: IssueSeverity) -> impl Iterator<Item = &Issue> + '_ {
+        self.issues.iter().filter(move |i| i.severity >= severity)
+    }
+
+    /// Modules whose path matches `pattern` (`*` within a segment, `**` across
+    /// segments). Paths are as analyzed, so usually absolute: start relative
+    /// patterns with `**/`.
+    pub fn modules_matching<'a>(
+        &'a self,
+        pattern: &'a str,
+    ) -> impl Iterator<Item = &'a Module> + 'a {
+        self.modules
+            .iter()
+            .filter(move |m| glob_match(pattern, &m.path.to_string_lossy()))
+    }
+
+    pub fn summary(&self) -> ResultSummary {
+        let mut by_severity = BTreeMap::new();
+        let mut by_kind = BTreeMap::new();
+        for issue in &self.issues {
+            *by_severity.entry(issue.severity).or_insert(0) += 1;
+            *by_kind.entry(issue.kind.name()).or_insert(0) += 1;
+        }
+
+        ResultSummary {
+            modules: self.modules.len(),
+            lines: self.modules.iter().map(|m| m.lines).sum(),
+            dependencies: self.dependency_graph.graph().edge_count(),
+            issues: self.issues.len(),
+            generated_files: self.generated_files.len(),
+            by_severity,
+            by_kind,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn result() -> AnalysisResult {
+        let mut app = Module::new(PathBuf::from("/p/src/app.ts"));
+        app.imports = vec!["./db/conn".into()];
+        app.lines = 40;
+        let mut conn = Module::new(PathBuf::from("/p/src/db/conn.ts"));
+        conn.lines = 10;
+        let modules = vec![app, conn];
+        AnalysisResult::new(
+            "p",
+            modules,
+            vec![
+                Issue::high_coupling(PathBuf::from("/p/src/db/conn.ts"), 9),
+                Issue::low_cohesion(PathBuf::from("/p/src/app.ts"), 0.2, 1, 5),
+                Issue::circular_dependency(vec![
+                    PathBuf::from("/p/src/app.ts"),
+                    PathBuf::from("/p/src/db/conn.ts"),
+                ]),
+            ],
+        )
+    }
+
+    #[test]
+    fn test_module_lookup_survives_edits() {
+        let mut result = result();
+        let conn = Path::new("/p/src/db/conn.ts");
+        assert_eq!(result.module(conn).unwrap().lines, 10);
+        assert!(result.module(Path::new("/p/src/missing.ts")).is_none());
+
+        // The index is stale after this, but lookups still find the right module
+        result.modules.swap(0, 1);
+        assert_eq!(result.module(conn).unwrap().path, conn);
+        result
+            .modules
+            .push(Module::new(PathBuf::from("/p/src/new.ts")));
+        assert!(result.module(Path::new("/p/src/new.ts")).is_some());
+    }
+
+    #[test]
+    fn test_issue_filters() {
+        let result = result();
+        assert_eq!(result.issues_for(Path::new("/p/src/app.ts")).count(), 2);
+        assert_eq!(result.issues_for(Path::new("/p/src/db/conn.ts")).count(), 2);
+
+        let cycles: Vec<_> = result
+            .issues_of_kind(IssueKindFilter::CircularDependency)
+            .collect();
+        assert_eq!(cycles.len(), 1);
+        assert_eq!(
+            "low-cohesion".parse::<IssueKindFilter>(),
+            Ok(IssueKindFilter::LowCohesion)
+        );
+        assert!("nonsense".parse::<IssueKindFilter>().is_err());
+
+        assert_eq!(result.issues_at_least(IssueSeverity::Info).count(), 3);
+        assert_eq!(result.issues_at_least(IssueSeverity::Error).count(), 1);
+    }
+
+    #[test]
+    fn test_modules_matching_and_summary() {
+        let result = result();
+        let db: Vec<_> = result.modules_matching("**/db/*.ts").collect();
+        assert_eq!(db.len(), 1);
+        assert_eq!(db[0].path, Path::new("/p/src/db/conn.ts"));
+        assert_eq!(result.modules_matching("**/*.ts").count(), 2);
+
+        let summary = result.summary();
+        assert_eq!(summary.modules, 2);
+        assert_eq!(summary.lines, 50);
+        assert_eq!(summary.dependencies, 1);
+        assert_eq!(summary.issues, 3);
+        assert_eq!(summary.count(IssueSeverity::Error), 1);
+        assert_eq!(summary.by_kind["HighCoupling"], 1);
+        assert!(!summary.by_kind.contains_key("GodObject"));
+    }
+}
@@ -0,0 +1,190 @@
+//! Shared strings for the names modules repeat. The same import path or
+//! export name turns up in many modules; after parsing, [`intern_modules`]
+//! makes every occurrence point at one copy.
+
+use super::Module;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::borrow::Borrow;
+use std::collections::HashSet;
+use std::fmt;
+use std::ops::Deref;
+use std::sync::Arc;
+
+/// An immutable string that clones without allocating. Serializes as a plain
+/// string, so it reads and writes the same JSON a `String` would.
+#[derive(Clone, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Symbol(Arc<str>);
+
+impl Symbol {
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Deref for Symbol {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl AsRef<str> for Symbol {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Borrow<str> for Symbol {
+    fn borrow(&self) -> &str {
+        &self.0
+    }
+}
+
+impl From<&str> for Symbol {
+    fn from(s: &str) -> Self {
+        Self(Arc::from(s))
+    }
+}
+
+impl From<String> for Symbol {
+    fn from(s: String) -> Self {
+        Self(Arc::from(s))
+    }
+}
+
+impl From<&String> for Symbol {
+    fn from(s: &String) -> Self {
+        Self::from(s.as_str())
+    }
+}
+
+impl PartialEq<str> for Symbol {
+    fn eq(&self, other: &str) -> bool {
+        &*self.0 == other
+    }
+}
+
+impl PartialEq<&str> for Symbol {
+    fn eq(&self, other: &&str) -> bool {
+        &*self.0 == *other
+    }
+}
+
+impl PartialEq<String> for Symbol {
+    fn eq(&self, other: &String) -> bool {
+        *self.0 == **other
+    }
+}
+
+impl fmt::Display for Symbol {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl fmt::Debug for Symbol {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&*self.0, f)
+    }
+}
+
+impl Serialize for Symbol {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.0)
+    }
+}
+
+impl<'de> Deserialize<'de> for Symbol {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer).map(Symbol::from)
+    }
+}
+
+/// What [`intern_modules`] shared: how many import and export names there
+/// were, how many distinct strings they now point at, and the bytes freed
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct InternStats {
+    pub names: usize,
+    pub distinct: usize,
+    pub bytes_saved: usize,
+}
+
+impl fmt::Display for InternStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} import and export names share {} strings ({} KB saved)",
+            self.names,
+            self.distinct,
+            self.bytes_saved / 1024
+        )
+    }
+}
+
+/// Point every equal import and export name of `modules` at one shared copy.
+pub fn intern_modules(modules: &mut [Module]) -> InternStats {
+    // Each separate copy carries the two reference counts of its `Arc`
+    const HEADER: usize = 2 * std::mem::size_of::<usize>();
+
+    let mut seen: HashSet<Symbol> = HashSet::new();
+    let mut stats = InternStats::default();
+    for module in modules {
+        for name in module.imports.iter_mut().chain(module.exports.iter_mut()) {
+            stats.names += 1;
+            match seen.get(name.as_str()) {
+                Some(shared) if Arc::ptr_eq(&shared.0, &name.0) => {}
+                Some(shared) => {
+                    // Only this module held the copy, so it is freed
+                    if Arc::strong_count(&name.0) == 1 {
+                        stats.bytes_saved += name.len() + HEADER;
+                    }
+                    *name = shared.clone();
+                }
+                None => {
+                    seen.insert(name.clone());
+                }
+            }
+        }
+    }
+    stats.distinct = seen.len();
+    stats
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_symbols_serialize_as_strings_and_share_storage() {
+        let names: Vec<Symbol> = vec!["react".into(), "./util".into()];
+        let json = serde_json::to_string(&names).unwrap();
+        assert_eq!(json, serde_json::to_string(&["react", "./util"]).unwrap());
+        let back: Vec<Symbol> = serde_json::from_str(&json).unwrap();
+        assert_eq!(back, names);
+
+        let module = |name: &str| {
+            let mut module = Module::new(PathBuf::from(name));
+            module.imports = vec!["react".into(), "./util".into()];
+            module.exports = vec!["App".into()];
+            module
+        };
+        let mut modules = vec![module("a.ts"), module("b.ts")];
+        let stats = intern_modules(&mut modules);
+
+        assert_eq!(stats.names, 6);
+        assert_eq!(stats.distinct, 3);
+        assert!(stats.bytes_saved >= "react./utilApp".len());
+        assert!(Arc::ptr_eq(
+            &modules[0].imports[0].0,
+            &modules[1].imports[0].0
+        ));
+        assert!(Arc::ptr_eq(
+            &modules[0].exports[0].0,
+            &modules[1].exports[0].0
+        ));
+        // A second pass finds nothing left to share
+        assert_eq!(intern_modules(&mut modules).bytes_saved, 0);
+    }
+}
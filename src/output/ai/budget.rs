@@ -0,0 +1,119 @@
+//! Token budget allocation for AI output: which modules are shown in full,
+//! cut down to their imports, summarized in a single line, or dropped.
+
+use crate::model::{Module, Symbol};
+use std::borrow::Cow;
+
+/// Tokens set aside for headings, refactoring order and recommendations
+pub const STRUCTURE_RESERVE: usize = 800;
+
+/// A module rendered with some content (full source, signatures or imports only)
+#[derive(Debug, Clone)]
+pub struct BudgetedModule<'a> {
+    pub module: &'a Module,
+    pub score: f64,
+    /// Borrowed from the collected sources when the full file is included
+    pub content: Cow<'a, str>,
+    pub tokens: usize,
+}
+
+/// One-line stand-in for a module whose content did not fit the budget
+#[derive(Debug, Clone)]
+pub struct OmittedSummary {
+    /// Module path relative to the project root
+    pub path: String,
+    /// First few export names
+    pub exports: Vec<Symbol>,
+    /// Exports not listed in `exports`
+    pub more_exports: usize,
+    pub lines: usize,
+    pub fan_in: usize,
+}
+
+impl OmittedSummary {
+    /// Export names listed before collapsing the rest into a count
+    pub const MAX_EXPORTS: usize = 5;
+
+    pub fn new(module: &Module, path: String, fan_in: usize) -> Self {
+        let exports: Vec<_> = module
+            .exports
+            .iter()
+            .take(Self::MAX_EXPORTS)
+            .cloned()
+            .collect();
+        Self {
+            path,
+            more_exports: module.exports.len() - exports.len(),
+            exports,
+            lines: module.lines,
+            fan_in,
+        }
+    }
+
+    /// One-line markdown rendering, also used to measure the token cost.
+    pub fn to_markdown(&self) -> String {
+        let mut line = format!(
+            "- `{}` — {} lines, fan-in {}",
+            self.path, self.lines, self.fan_in
+        );
+        if !self.exports.is_empty() {
+            line.push_str(&format!(", exports: {}", self.exports.join(", ")));
+            if self.more_exports > 0 {
+                line.push_str(&format!(" (+{})", self.more_exports));
+            }
+        }
+        line
+    }
+}
+
+/// How a token budget was spent across modules, highest priority first
+#[derive(Debug, Clone, Default)]
+pub struct BudgetPlan<'a> {
+    pub budget: usize,
+    /// Tokens reserved for structure, the already-written layout and the
+    /// omitted section's header and trailer
+    pub reserve: usize,
+    /// Tokens spent on module content and omitted-module summaries
+    pub used_tokens: usize,
+    pub included: Vec<BudgetedModule<'a>>,
+    /// Modules reduced to their imports
+    pub truncated: Vec<BudgetedModule<'a>>,
+    pub omitted: Vec<OmittedSummary>,
+    /// Omitted modules that did not even fit a summary line
+    pub unlisted: usize,
+}
+
+impl BudgetPlan<'_> {
+    pub fn total_tokens(&self) -> usize {
+        self.used_tokens + self.reserve
+    }
+
+    pub fn omitted_count(&self) -> usize {
+        self.omitted.len() + self.unlisted
+    }
+
+    /// "Omitted Modules" section, empty when nothing was omitted.
+    pub fn omitted_markdown(&self) -> String {
+        if self.omitted_count() == 0 {
+            return String::new();
+        }
+
+        let mut out = omitted_header(self.omitted_count());
+        for summary in &self.omitted {
+            out.push_str(&summary.to_markdown());
+            out.push('\n');
+        }
+        if self.unlisted > 0 {
+            out.push_str(&omitted_trailer(self.unlisted));
+        }
+        out
+    }
+}
+
+pub(super) fn omitted_header(count: usize) -> String {
+    format!("## Omitted Modules ({})\n\n", count)
+}
+
+pub(super) fn omitted_trailer(count: usize) -> String {
+    format!("- ... and {} more modules\n", count)
+}
@@ -1,37 +1,199 @@
-use crate::analysis::DependencyGraph;
-use crate::model::{DefinitionKind, Issue, IssueKind, Module, Visibility};
-use crate::output::relative_path;
-use std::collections::HashMap;
+use super::budget::{self, BudgetPlan, BudgetedModule, OmittedSummary, STRUCTURE_RESERVE};
+use super::layout::{ProjectLayout, summarize_directories};
+use super::sources::SourceProvider;
+use crate::analysis::{DependencyGraph, PathFilter};
+use crate::cli::VisibilityLevel;
+use crate::model::{
+    AnalysisResult, Definition, DefinitionKind, Issue, IssueKind, Module, ModuleIndex, Visibility,
+};
+use crate::output::{OutputOptions, relative_path};
+use std::borrow::Cow;
+use std::collections::HashSet;
 use std::path::{Path, PathBuf};
-use tiktoken_rs::cl100k_base;
+use std::sync::{Arc, OnceLock};
+use tiktoken_rs::{CoreBPE, cl100k_base};
 
 /// Shared context and helper methods for AI output formatters
-pub struct AiContext {
+pub struct AiContext<'a> {
     pub project_root: Option<PathBuf>,
     pub topo_order: bool,
     pub signatures_only: bool,
+    /// Which definitions to include in signatures
+    pub visibility: VisibilityLevel,
+    /// Definition kinds shown in signatures; empty shows every kind
+    pub kinds: Vec<DefinitionKind>,
+    /// Prefer each definition's compact signature when it has one
+    pub compact_types: bool,
     pub token_budget: Option<usize>,
-    pub sources: HashMap<PathBuf, String>,
+    /// Most important modules listed; `None` lists all
+    pub top: Option<usize>,
+    /// Source text by path, shared with the `AiOutput` that built this context
+    pub sources: Arc<dyn SourceProvider + 'a>,
+    pub filter: PathFilter,
+    pub preamble: Option<String>,
+    /// Token cap for the Project Layout section (0 disables it)
+    pub layout_tokens: usize,
+    /// Emit `<!-- archmap:... -->` anchors in markdown output
+    pub anchors: bool,
+    pub options: OutputOptions,
 }
 
-impl AiContext {
+impl AiContext<'_> {
     pub fn relative_path(&self, path: &Path) -> String {
         relative_path(path, self.project_root.as_ref())
     }
 
+    /// Narrow modules to those selected by the include/exclude filter.
+    /// Borrows the original slice when no filter is active.
+    pub fn filter_modules<'a>(&self, modules: &'a [Module]) -> Cow<'a, [Module]> {
+        if self.filter.is_empty() {
+            return Cow::Borrowed(modules);
+        }
+
+        let root = self.project_root.as_deref();
+        Cow::Owned(
+            modules
+                .iter()
+                .filter(|m| self.filter.matches(&m.path, root))
+                .cloned()
+                .collect(),
+        )
+    }
+
+    /// Issues at or above the severity floor. Borrows the original slice when
+    /// every issue is reported.
+    pub fn reported_issues<'a>(&self, issues: &'a [Issue]) -> Cow<'a, [Issue]> {
+        if issues.iter().all(|i| self.options.shows(i.severity)) {
+            return Cow::Borrowed(issues);
+        }
+        Cow::Owned(
+            issues
+                .iter()
+                .filter(|i| self.options.shows(i.severity))
+                .cloned()
+                .collect(),
+        )
+    }
+
+    /// Dependency graph for the given (possibly filtered) module set.
+    /// Reuses the graph built during analysis unless filtering removed modules,
+    /// and otherwise keeps just its edges between the remaining ones.
+    pub fn graph_for<'a>(
+        &self,
+        result: &'a AnalysisResult,
+        modules: &[Module],
+    ) -> Cow<'a, DependencyGraph> {
+        if modules.len() == result.modules.len() {
+            Cow::Borrowed(&result.dependency_graph)
+        } else {
+            Cow::Owned(
+                result
+                    .dependency_graph
+                    .subgraph(modules.iter().map(|m| &m.path)),
+            )
+        }
+    }
+
+    /// Describe the active include/exclude filters for output headers.
+    pub fn filter_summary(&self) -> Option<String> {
+        if self.filter.is_empty() {
+            return None;
+        }
+
+        let mut parts = Vec::new();
+        if !self.filter.include.is_empty() {
+            parts.push(format!("include {}", self.filter.include.join(", ")));
+        }
+        if !self.filter.exclude.is_empty() {
+            parts.push(format!("exclude {}", self.filter.exclude.join(", ")));
+        }
+        Some(parts.join("; "))
+    }
+
+    /// Token cap for the Project Layout section: `layout_tokens`, cut down to
+    /// what a token budget leaves after the preamble and the structure reserve.
+    pub fn layout_cap(&self) -> usize {
+        match self.token_budget {
+            Some(budget) => self
+                .layout_tokens
+                .min(budget.saturating_sub(STRUCTURE_RESERVE + self.preamble_tokens())),
+            None => self.layout_tokens,
+        }
+    }
+
+    /// Tokens spent on the task preamble, which every budget pays first
+    fn preamble_tokens(&self) -> usize {
+        self.preamble
+            .as_deref()
+            .map_or(0, |preamble| self.count_tokens(preamble))
+    }
+
+    /// Summarize top-level directories, keeping as many as fit [`Self::layout_cap`].
+    pub fn layout_summary(
+        &self,
+        result: &AnalysisResult,
+        modules: &[Module],
+        graph: &DependencyGraph,
+    ) -> ProjectLayout {
+        let cap = self.layout_cap();
+        if cap == 0 {
+            return ProjectLayout::default();
+        }
+
+        let summaries = summarize_directories(
+            modules,
+            &self.reported_issues(&result.issues),
+            graph,
+            self.project_root.as_deref(),
+        );
+        let total = summaries.len();
+
+        // Header plus the "... and N more" trailer
+        let mut used = self.count_tokens("## Project Layout\n\n- ... and 00 more directories\n");
+        let mut directories = Vec::new();
+        for summary in summaries {
+            let tokens = self.count_tokens(&format!("{}\n", summary.to_markdown()));
+            if used + tokens > cap {
+                break;
+            }
+            used += tokens;
+            directories.push(summary);
+        }
+
+        ProjectLayout {
+            omitted: total - directories.len(),
+            directories,
+        }
+    }
+
     pub fn order_modules<'a>(
         &self,
         modules: &'a [Module],
         graph: &DependencyGraph,
     ) -> Vec<&'a Module> {
-        if self.topo_order {
+        let ordered: Vec<&Module> = if self.topo_order {
+            let index = ModuleIndex::new(modules);
             let order = graph.topological_order_with_cycles();
-            order
-                .iter()
-                .filter_map(|path| modules.iter().find(|m| &m.path == path))
-                .collect()
+            order.iter().filter_map(|path| index.get(path)).collect()
         } else {
             modules.iter().collect()
+        };
+
+        // Keep the most important modules, still in dependency order
+        match self.top {
+            Some(top) if top < ordered.len() => {
+                let kept: HashSet<&PathBuf> = self
+                    .prioritize_modules(modules, graph)
+                    .into_iter()
+                    .take(top)
+                    .map(|(m, _)| &m.path)
+                    .collect();
+                ordered
+                    .into_iter()
+                    .filter(|m| kept.contains(&m.path))
+                    .collect()
+            }
+            _ => ordered,
         }
     }
 
@@ -42,20 +204,128 @@ impl AiContext {
     ) -> Vec<(&'a Module, f64)> {
         let mut scored: Vec<_> = modules
             .iter()
-            .map(|m| {
-                let score = graph.importance_score(&m.path, modules);
-                (m, score)
-            })
+            .map(|m| (m, graph.module_importance(m)))
             .collect();
 
-        scored.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal));
+        // Discovery order varies between runs, so ties go by path
+        scored.sort_by(|a, b| {
+            b.1.partial_cmp(&a.1)
+                .unwrap_or(std::cmp::Ordering::Equal)
+                .then_with(|| a.0.path.cmp(&b.0.path))
+        });
         scored
     }
 
+    /// Spend `budget` on modules in priority order. Each module gets its content,
+    /// else its imports, else a one-line summary, else only a count in the trailer.
+    pub fn plan_budget<'a>(
+        &'a self,
+        modules: &'a [Module],
+        graph: &DependencyGraph,
+        budget: usize,
+        layout_tokens: usize,
+    ) -> BudgetPlan<'a> {
+        // The preamble and layout are written already, but the layout never
+        // gets more than the preamble and structure leave
+        let preamble_tokens = self.preamble_tokens();
+        let layout_tokens =
+            layout_tokens.min(budget.saturating_sub(STRUCTURE_RESERVE + preamble_tokens));
+
+        // The omitted section's header and "... and N more" trailer are set
+        // aside up front, so omitting a module never pushes the total over.
+        // Even a budget too small for the reserve is never exceeded.
+        let omitted_overhead = self.count_tokens(&format!(
+            "{}{}",
+            budget::omitted_header(modules.len()),
+            budget::omitted_trailer(modules.len())
+        ));
+        let reserve =
+            (STRUCTURE_RESERVE + preamble_tokens + layout_tokens + omitted_overhead).min(budget);
+        let available = budget.saturating_sub(reserve);
+        let mut plan = BudgetPlan {
+            budget,
+            reserve,
+            ..Default::default()
+        };
+
+        let prioritized = self.prioritize_modules(modules, graph);
+        let top = self.top.unwrap_or(prioritized.len());
+        for (module, score) in prioritized.into_iter().take(top) {
+            let minimal = format!(
+                "// {}\n{}",
+                module.name,
+                module
+                    .imports
+                    .iter()
+                    .map(|i| format!("use {};", i))
+                    .collect::<Vec<_>>()
+                    .join("\n")
+            );
+            let minimal_tokens = self.count_tokens(&minimal);
+            let fits = |tokens: usize| plan.used_tokens + tokens <= available;
+
+            // A file is taken to cost at least its import list, so once even
+            // that doesn't fit the source isn't loaded at all
+            let content = if self.signatures_only {
+                Some(Cow::Owned(self.format_module_signature(module)))
+            } else if fits(minimal_tokens) {
+                Some(self.format_module_full(module))
+            } else {
+                None
+            };
+            if content.as_ref().is_some_and(|c| c.is_empty()) {
+                continue;
+            }
+            if let Some(content) = content {
+                let mut tokens = self.count_tokens(&content);
+                if self.signatures_only {
+                    // Written ahead of the code block, but still part of the module's share
+                    tokens += self.count_tokens(&self.definition_anchors(module));
+                }
+                if fits(tokens) {
+                    plan.used_tokens += tokens;
+                    plan.included.push(BudgetedModule {
+                        module,
+                        score,
+                        content,
+                        tokens,
+                    });
+                    continue;
+                }
+            }
+
+            if fits(minimal_tokens) {
+                plan.used_tokens += minimal_tokens;
+                plan.truncated.push(BudgetedModule {
+                    module,
+                    score,
+                    content: Cow::Owned(minimal),
+                    tokens: minimal_tokens,
+                });
+                continue;
+            }
+
+            let summary = OmittedSummary::new(
+                module,
+                self.relative_path(&module.path),
+                graph.fan_in(&module.path),
+            );
+            let summary_tokens = self.count_tokens(&format!("{}\n", summary.to_markdown()));
+            if plan.used_tokens + summary_tokens <= available {
+                plan.used_tokens += summary_tokens;
+                plan.omitted.push(summary);
+            } else {
+                plan.unlisted += 1;
+            }
+        }
+
+        plan
+    }
+
     pub fn count_tokens(&self, text: &str) -> usize {
-        match cl100k_base() {
-            Ok(bpe) => bpe.encode_with_special_tokens(text).len(),
-            Err(_) => text.len() / 4,
+        match tokenizer() {
+            Some(bpe) => bpe.encode_with_special_tokens(text).len(),
+            None => text.len() / 4,
         }
     }
 
@@ -65,12 +335,11 @@ impl AiContext {
         modules: &'a [Module],
         graph: &DependencyGraph,
     ) -> Vec<&'a Module> {
+        let index = ModuleIndex::new(modules);
         let topo = graph.topological_order_with_cycles();
-        let reversed: Vec<_> = topo.into_iter().rev().collect();
-
-        reversed
-            .iter()
-            .filter_map(|path| modules.iter().find(|m| &m.path == path))
+        topo.iter()
+            .rev()
+            .filter_map(|path| index.get(path))
             .collect()
     }
 
@@ -86,7 +355,7 @@ impl AiContext {
 
         for issue in issues {
             let affects_this_module = issue.locations.iter().any(|loc| &loc.path == path);
-            if !affects_this_module {
+            if !affects_this_module || !self.options.shows(issue.severity) {
                 continue;
             }
 
@@ -180,22 +449,148 @@ impl AiContext {
                         private_functions, public_functions
                     ));
                 }
+                IssueKind::FatSharedModule { fan_in, functions } => {
+                    recommendations.push(format!(
+                        "SPLIT: {} modules import this, and it holds {} functions besides its constants/types. \
+                        Move the constants and types to their own module so dependents don't churn with the logic.",
+                        fan_in, functions
+                    ));
+                }
+                IssueKind::ScatteredImplementation {
+                    trait_name,
+                    file_count,
+                } => {
+                    recommendations.push(format!(
+                        "GROUP: {} is implemented across {} modules. \
+                        Keep implementations near the trait or grouped by feature so they can be found together.",
+                        trait_name, file_count
+                    ));
+                }
+                IssueKind::DeadPublicFunction { function } => {
+                    recommendations.push(format!(
+                        "REMOVE: `{}` is public but never referenced in the project. \
+                        Delete it, or make it private if it is kept on purpose.",
+                        function
+                    ));
+                }
+                IssueKind::TooManyImports { imports } => {
+                    recommendations.push(format!(
+                        "SPLIT: {} imports. Group the code by the imports each part uses and move each group \
+                        into its own module.",
+                        imports
+                    ));
+                }
+                IssueKind::GodPackage {
+                    dir,
+                    modules,
+                    lines,
+                } => {
+                    recommendations.push(format!(
+                        "SPLIT PACKAGE: One of the largest modules in `{}/` ({} modules, {} lines). \
+                        Move it into a subpackage with the modules it works with.",
+                        dir, modules, lines
+                    ));
+                }
+                IssueKind::EmptyTest => {
+                    recommendations.push(
+                        "REMOVE: This test imports no production code, so what it tested is likely gone. \
+                        Delete it, or add it to test_harness_paths if it is a shared fixture."
+                            .to_string(),
+                    );
+                }
+                IssueKind::RoleViolation {
+                    role,
+                    imported_role,
+                } => {
+                    recommendations.push(format!(
+                        "DECOUPLE: This {} imports a {} directly. Put the data access behind an \
+                        injected service.",
+                        role, imported_role
+                    ));
+                }
             }
         }
 
         recommendations
     }
 
+    /// Definitions passing the visibility and kind filters
+    pub fn visible_definitions<'a>(
+        &'a self,
+        module: &'a Module,
+    ) -> impl Iterator<Item = &'a Definition> {
+        let level = self.visibility;
+        module.definitions.iter().filter(move |d| {
+            level.includes(d.visibility) && (self.kinds.is_empty() || self.kinds.contains(&d.kind))
+        })
+    }
+
+    /// Hash of a module's source text, so consumers can detect stale context.
+    /// `None` when the source was not collected.
+    pub fn module_hash(&self, module: &Module) -> Option<String> {
+        use std::collections::hash_map::DefaultHasher;
+        use std::hash::{Hash, Hasher};
+
+        let source = self.sources.source(&module.path)?;
+        let mut hasher = DefaultHasher::new();
+        source.hash(&mut hasher);
+        Some(format!("{:016x}", hasher.finish()))
+    }
+
+    /// Machine-readable anchor identifying a module, or an empty string when anchors are off.
+    pub fn module_anchor(&self, module: &Module) -> String {
+        if !self.anchors {
+            return String::new();
+        }
+        let hash = self
+            .module_hash(module)
+            .map(|h| format!(" hash=\"{}\"", h))
+            .unwrap_or_default();
+        format!(
+            "<!-- archmap:module path=\"{}\"{} -->\n",
+            anchor_attr(&self.relative_path(&module.path)),
+            hash
+        )
+    }
+
+    /// Machine-readable anchors for the definitions in a module's signature, one
+    /// per line, or an empty string when anchors are off. They belong before the
+    /// code block: inside it they would read as source.
+    pub fn definition_anchors(&self, module: &Module) -> String {
+        if !self.anchors {
+            return String::new();
+        }
+        self.visible_definitions(module)
+            .filter(|d| {
+                d.signature.is_some()
+                    || d.span.is_some()
+                    || (self.compact_types && d.compact_signature.is_some())
+            })
+            .map(|d| {
+                format!(
+                    "<!-- archmap:def name=\"{}\" line=\"{}\" -->\n",
+                    anchor_attr(&d.name),
+                    d.line
+                )
+            })
+            .collect()
+    }
+
+    /// The module's source when a visible definition keeps only a span of it,
+    /// read once for all of them; `None` when the signatures are enough.
+    pub fn definition_source(&self, module: &Module) -> Option<Cow<'_, str>> {
+        self.visible_definitions(module)
+            .any(|d| d.span.is_some())
+            .then(|| self.sources.source(&module.path))
+            .flatten()
+    }
+
     pub fn format_module_signature(&self, module: &Module) -> String {
         let mut output = String::new();
 
-        let public_defs: Vec<_> = module
-            .definitions
-            .iter()
-            .filter(|d| d.visibility == Visibility::Public)
-            .collect();
+        let visible_defs: Vec<_> = self.visible_definitions(module).collect();
 
-        if public_defs.is_empty() && module.imports.is_empty() {
+        if visible_defs.is_empty() && module.imports.is_empty() {
             return output;
         }
 
@@ -206,8 +601,23 @@ impl AiContext {
             output.push('\n');
         }
 
-        for def in public_defs {
-            if let Some(ref sig) = def.signature {
+        let source = self.definition_source(module);
+        let rust = module.path.extension().is_some_and(|ext| ext == "rs");
+        for def in visible_defs {
+            let compact = self
+                .compact_types
+                .then_some(def.compact_signature.as_deref())
+                .flatten();
+            if let Some(sig) = compact.or_else(|| def.full_text(source.as_deref())) {
+                match def.visibility {
+                    Visibility::Private => output.push_str("// private\n"),
+                    // Rust signatures usually carry their `pub(crate)` already; other
+                    // languages spell it their own way, like Scala's `private[pkg]`
+                    Visibility::Crate if rust && !sig.starts_with("pub(") => {
+                        output.push_str("pub(crate) ")
+                    }
+                    _ => {}
+                }
                 if def.kind == DefinitionKind::Function {
                     output.push_str(sig);
                     output.push_str(" { ... }\n\n");
@@ -221,11 +631,23 @@ impl AiContext {
         output
     }
 
-    pub fn format_module_full(&self, module: &Module) -> String {
-        if let Some(source) = self.sources.get(&module.path) {
-            source.clone()
-        } else {
-            self.format_module_signature(module)
+    /// Full source text, borrowed when the sources are held in memory so large
+    /// files are never copied. Falls back to the signature when there is no source.
+    pub fn format_module_full(&self, module: &Module) -> Cow<'_, str> {
+        match self.sources.source(&module.path) {
+            Some(source) => source,
+            None => Cow::Owned(self.format_module_signature(module)),
         }
     }
 }
+
+/// Escape a value for use inside a double-quoted anchor attribute.
+fn anchor_attr(value: &str) -> String {
+    value.replace('"', "&quot;")
+}
+
+/// Tokenizer loaded once per process; building it is far slower than encoding.
+fn tokenizer() -> Option<&'static CoreBPE> {
+    static BPE: OnceLock<Option<CoreBPE>> = OnceLock::new();
+    BPE.get_or_init(|| cl100k_base().ok()).as_ref()
+}
@@ -1,26 +1,58 @@
 use super::AiContext;
-use crate::analysis::DependencyGraph;
-use crate::model::{AnalysisResult, Visibility};
-use serde_json::json;
+use super::budget::BudgetedModule;
+use crate::model::{AnalysisResult, Module};
+use serde::Serialize;
+use serde::ser::{SerializeSeq, Serializer};
+use serde_json::{Value, json};
+use std::collections::HashSet;
 use std::io::Write;
 
-pub struct JsonFormatter {
-    ctx: AiContext,
+pub struct JsonFormatter<'a> {
+    ctx: AiContext<'a>,
 }
 
-impl JsonFormatter {
-    pub fn new(ctx: AiContext) -> Self {
+impl<'a> JsonFormatter<'a> {
+    pub fn new(ctx: AiContext<'a>) -> Self {
         Self { ctx }
     }
 
     pub fn format<W: Write>(&self, result: &AnalysisResult, writer: &mut W) -> std::io::Result<()> {
-        let graph = DependencyGraph::build(&result.modules);
-        let ordered = self.ctx.order_modules(&result.modules, &graph);
+        let modules = self.ctx.filter_modules(&result.modules);
+        let graph = self.ctx.graph_for(result, &modules);
+        let ordered = self.ctx.order_modules(&modules, &graph);
+
+        let layout = self.ctx.layout_summary(result, &modules, &graph);
+        let layout_json: Vec<_> = layout
+            .directories
+            .iter()
+            .map(|d| {
+                json!({
+                    "path": d.path,
+                    "modules": d.modules,
+                    "lines": d.lines,
+                    "dominant_kinds": d
+                        .dominant_kinds
+                        .iter()
+                        .map(|(kind, count)| json!({ "kind": kind, "count": count }))
+                        .collect::<Vec<_>>(),
+                    "top_fan_in": d
+                        .top_fan_in
+                        .iter()
+                        .map(|(path, fan_in)| json!({ "path": path, "fan_in": fan_in }))
+                        .collect::<Vec<_>>(),
+                    "issues": d
+                        .issues
+                        .iter()
+                        .map(|(kind, count)| json!({ "kind": kind, "count": count }))
+                        .collect::<Vec<_>>()
+                })
+            })
+            .collect();
 
         // Build refactoring order
         let refactor_order: Vec<_> = self
             .ctx
-            .refactoring_order(&result.modules, &graph)
+            .refactoring_order(&modules, &graph)
             .iter()
             .map(|m| {
                 json!({
@@ -31,8 +63,7 @@ impl JsonFormatter {
             .collect();
 
         // Build recommendations
-        let recommendations: Vec<_> = result
-            .modules
+        let recommendations: Vec<_> = modules
             .iter()
             .filter_map(|m| {
                 let recs = self.ctx.file_recommendations(m, &result.issues, &graph);
@@ -47,46 +78,144 @@ impl JsonFormatter {
             })
             .collect();
 
-        let modules_json: Vec<_> = ordered
-            .iter()
-            .map(|m| {
-                let sig = self.ctx.format_module_signature(m);
-                let public_defs: Vec<_> = m
-                    .definitions
+        // Under a budget, only modules that received content are listed in full
+        let plan = self.ctx.token_budget.map(|budget| {
+            let layout_tokens = self.ctx.count_tokens(&layout.to_markdown());
+            self.ctx
+                .plan_budget(&modules, &graph, budget, layout_tokens)
+        });
+        let budget_json = plan.as_ref().map(|plan| {
+            let entries = |entries: &[BudgetedModule]| {
+                entries
                     .iter()
-                    .filter(|d| d.visibility == Visibility::Public)
-                    .map(|d| {
+                    .map(|e| {
                         json!({
-                            "name": d.name,
-                            "kind": format!("{:?}", d.kind),
-                            "line": d.line,
-                            "signature": d.signature
+                            "path": self.ctx.relative_path(&e.module.path),
+                            "tokens": e.tokens
                         })
                     })
-                    .collect();
-
-                json!({
-                    "path": self.ctx.relative_path(&m.path),
-                    "name": m.name,
-                    "lines": m.lines,
-                    "imports": m.imports,
-                    "exports": m.exports,
-                    "definitions": public_defs,
-                    "signature": sig
-                })
+                    .collect::<Vec<_>>()
+            };
+            json!({
+                "limit": plan.budget,
+                "used": plan.total_tokens(),
+                "included": entries(&plan.included),
+                "truncated": entries(&plan.truncated),
+                "omitted": plan
+                    .omitted
+                    .iter()
+                    .map(|o| {
+                        json!({
+                            "path": o.path,
+                            "exports": o.exports,
+                            "more_exports": o.more_exports,
+                            "lines": o.lines,
+                            "fan_in": o.fan_in
+                        })
+                    })
+                    .collect::<Vec<_>>(),
+                "unlisted": plan.unlisted
             })
+        });
+        let listed: Option<HashSet<_>> = plan.as_ref().map(|plan| {
+            plan.included
+                .iter()
+                .chain(&plan.truncated)
+                .map(|e| &e.module.path)
+                .collect()
+        });
+
+        let listed_modules: Vec<&Module> = ordered
+            .into_iter()
+            .filter(|m| listed.as_ref().is_none_or(|l| l.contains(&m.path)))
             .collect();
 
-        let output = json!({
-            "project": result.project_name,
-            "ordering": if self.ctx.topo_order { "topological" } else { "filesystem" },
-            "refactoring_order": refactor_order,
-            "recommendations": recommendations,
-            "modules": modules_json
-        });
+        let output = JsonOutput {
+            budget: budget_json,
+            filters: json!({
+                "include": self.ctx.filter.include,
+                "exclude": self.ctx.filter.exclude
+            }),
+            layout: json!({
+                "directories": layout_json,
+                "omitted": layout.omitted
+            }),
+            modules: ModuleStream {
+                ctx: &self.ctx,
+                modules: listed_modules,
+            },
+            ordering: if self.ctx.topo_order {
+                "topological"
+            } else {
+                "filesystem"
+            },
+            project: &result.project_name,
+            recommendations,
+            refactoring_order: refactor_order,
+            system_prompt: self.ctx.preamble.as_deref(),
+        };
 
-        let json_str = serde_json::to_string_pretty(&output)
+        serde_json::to_writer_pretty(&mut *writer, &output)
             .map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e))?;
-        writeln!(writer, "{}", json_str)
+        writeln!(writer)
+    }
+}
+
+/// Top-level document. Fields are in alphabetical order to match `json!` objects.
+#[derive(Serialize)]
+struct JsonOutput<'a> {
+    budget: Option<Value>,
+    filters: Value,
+    layout: Value,
+    modules: ModuleStream<'a>,
+    ordering: &'static str,
+    project: &'a str,
+    recommendations: Vec<Value>,
+    refactoring_order: Vec<Value>,
+    system_prompt: Option<&'a str>,
+}
+
+/// Serializes one module at a time straight to the writer, so the
+/// per-module JSON never accumulates into a single in-memory document.
+struct ModuleStream<'a> {
+    ctx: &'a AiContext<'a>,
+    modules: Vec<&'a Module>,
+}
+
+impl Serialize for ModuleStream<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut seq = serializer.serialize_seq(Some(self.modules.len()))?;
+        for module in &self.modules {
+            seq.serialize_element(&module_json(self.ctx, module))?;
+        }
+        seq.end()
     }
 }
+
+fn module_json(ctx: &AiContext, m: &Module) -> Value {
+    let sig = ctx.format_module_signature(m);
+    let source = ctx.definition_source(m);
+    let defs: Vec<_> = ctx
+        .visible_definitions(m)
+        .map(|d| {
+            json!({
+                "name": d.name,
+                "kind": format!("{:?}", d.kind),
+                "visibility": d.visibility.as_str(),
+                "line": d.line,
+                "signature": d.full_text(source.as_deref())
+            })
+        })
+        .collect();
+
+    json!({
+        "path": ctx.relative_path(&m.path),
+        "name": m.name,
+        "lines": m.lines,
+        "hash": ctx.module_hash(m),
+        "imports": m.imports,
+        "exports": m.exports,
+        "definitions": defs,
+        "signature": sig
+    })
+}
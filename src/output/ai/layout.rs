@@ -0,0 +1,207 @@
+//! Directory-level "Project Layout" summary: a short prose map of the codebase
+//! that precedes the per-module detail in AI output.
+
+use crate::analysis::{DependencyGraph, package_dir};
+use crate::model::{Issue, Module};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+/// Aggregate facts about one top-level directory
+#[derive(Debug, Clone)]
+pub struct DirectorySummary {
+    /// Directory relative to the project root (e.g. `src/analysis`)
+    pub path: String,
+    pub modules: usize,
+    pub lines: usize,
+    /// Most common definition kinds, most frequent first
+    pub dominant_kinds: Vec<(String, usize)>,
+    /// Up to three most-imported modules as (file name, fan-in)
+    pub top_fan_in: Vec<(String, usize)>,
+    /// Issue counts by kind name for issues located in this directory
+    pub issues: Vec<(String, usize)>,
+}
+
+impl DirectorySummary {
+    pub fn issue_count(&self) -> usize {
+        self.issues.iter().map(|(_, count)| count).sum()
+    }
+
+    /// One-line markdown rendering, also used to measure the token cost.
+    pub fn to_markdown(&self) -> String {
+        let mut line = format!(
+            "- `{}/` — {} modules, {} lines",
+            self.path, self.modules, self.lines
+        );
+
+        if !self.dominant_kinds.is_empty() {
+            let kinds: Vec<_> = self
+                .dominant_kinds
+                .iter()
+                .map(|(kind, count)| format!("{} {}", count, kind))
+                .collect();
+            line.push_str(&format!("; mostly {}", kinds.join(", ")));
+        }
+
+        if !self.top_fan_in.is_empty() {
+            let top: Vec<_> = self
+                .top_fan_in
+                .iter()
+                .map(|(name, fan_in)| format!("`{}` ({})", name, fan_in))
+                .collect();
+            line.push_str(&format!("; most imported: {}", top.join(", ")));
+        }
+
+        if !self.issues.is_empty() {
+            let kinds: Vec<_> = self
+                .issues
+                .iter()
+                .map(|(kind, count)| format!("{} {}", count, kind))
+                .collect();
+            line.push_str(&format!(
+                "; {} issues ({})",
+                self.issue_count(),
+                kinds.join(", ")
+            ));
+        }
+
+        line
+    }
+}
+
+/// Group modules by top-level directory, largest directories first.
+pub fn summarize_directories(
+    modules: &[Module],
+    issues: &[Issue],
+    graph: &DependencyGraph,
+    project_root: Option<&Path>,
+) -> Vec<DirectorySummary> {
+    let relative = |path: &Path| -> PathBuf {
+        project_root
+            .and_then(|root| path.strip_prefix(root).ok())
+            .unwrap_or(path)
+            .to_path_buf()
+    };
+
+    let mut groups: BTreeMap<PathBuf, Vec<usize>> = BTreeMap::new();
+    for (i, module) in modules.iter().enumerate() {
+        groups
+            .entry(package_dir(&relative(&module.path)))
+            .or_default()
+            .push(i);
+    }
+
+    let mut issue_counts: BTreeMap<PathBuf, BTreeMap<&'static str, usize>> = BTreeMap::new();
+    for issue in issues {
+        let mut dirs: Vec<PathBuf> = issue
+            .locations
+            .iter()
+            .map(|loc| package_dir(&relative(&loc.path)))
+            .collect();
+        dirs.sort();
+        dirs.dedup();
+        for dir in dirs {
+            *issue_counts
+                .entry(dir)
+                .or_default()
+                .entry(issue.kind.name())
+                .or_default() += 1;
+        }
+    }
+
+    let mut summaries: Vec<DirectorySummary> = groups
+        .into_iter()
+        .map(|(dir, members)| {
+            let mut kinds: BTreeMap<String, usize> = BTreeMap::new();
+            for &i in &members {
+                for def in &modules[i].definitions {
+                    let kind = format!("{:?}", def.kind).to_lowercase();
+                    *kinds.entry(kind).or_default() += 1;
+                }
+            }
+            let dominant_kinds = top_counts(kinds, 2);
+
+            let mut top_fan_in: Vec<_> = members
+                .iter()
+                .map(|&i| {
+                    let rel = relative(&modules[i].path);
+                    let name = rel.strip_prefix(&dir).unwrap_or(&rel).display().to_string();
+                    (name, graph.fan_in(&modules[i].path))
+                })
+                .filter(|(_, fan_in)| *fan_in > 0)
+                .collect();
+            top_fan_in.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+            top_fan_in.truncate(3);
+
+            let issues = issue_counts
+                .remove(&dir)
+                .map(|counts| {
+                    top_counts(
+                        counts
+                            .into_iter()
+                            .map(|(k, v)| (k.to_string(), v))
+                            .collect(),
+                        usize::MAX,
+                    )
+                })
+                .unwrap_or_default();
+
+            let path = if dir.as_os_str().is_empty() {
+                ".".to_string()
+            } else {
+                dir.display().to_string()
+            };
+
+            DirectorySummary {
+                path,
+                modules: members.len(),
+                lines: members.iter().map(|&i| modules[i].lines).sum(),
+                dominant_kinds,
+                top_fan_in,
+                issues,
+            }
+        })
+        .collect();
+
+    summaries.sort_by(|a, b| b.modules.cmp(&a.modules).then_with(|| a.path.cmp(&b.path)));
+    summaries
+}
+
+/// Sort (name, count) pairs by count descending and keep the first `limit`.
+fn top_counts(counts: BTreeMap<String, usize>, limit: usize) -> Vec<(String, usize)> {
+    let mut sorted: Vec<_> = counts.into_iter().collect();
+    sorted.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    sorted.truncate(limit);
+    sorted
+}
+
+/// Directory summaries that fit the layout token cap
+#[derive(Debug, Clone, Default)]
+pub struct ProjectLayout {
+    pub directories: Vec<DirectorySummary>,
+    /// Directories dropped to stay within the cap
+    pub omitted: usize,
+}
+
+impl ProjectLayout {
+    pub fn is_empty(&self) -> bool {
+        self.directories.is_empty()
+    }
+
+    /// Markdown section, empty when no directory fit the cap.
+    pub fn to_markdown(&self) -> String {
+        if self.is_empty() {
+            return String::new();
+        }
+
+        let mut out = String::from("## Project Layout\n\n");
+        for dir in &self.directories {
+            out.push_str(&dir.to_markdown());
+            out.push('\n');
+        }
+        if self.omitted > 0 {
+            out.push_str(&format!("- ... and {} more directories\n", self.omitted));
+        }
+        out.push('\n');
+        out
+    }
+}
@@ -1,38 +1,77 @@
 use super::AiContext;
 use crate::analysis::DependencyGraph;
-use crate::model::AnalysisResult;
+use crate::model::{AnalysisResult, Module};
 use std::io::Write;
 
-pub struct MarkdownFormatter {
-    ctx: AiContext,
+pub struct MarkdownFormatter<'a> {
+    ctx: AiContext<'a>,
 }
 
-impl MarkdownFormatter {
-    pub fn new(ctx: AiContext) -> Self {
+impl<'a> MarkdownFormatter<'a> {
+    pub fn new(ctx: AiContext<'a>) -> Self {
         Self { ctx }
     }
 
     pub fn format<W: Write>(&self, result: &AnalysisResult, writer: &mut W) -> std::io::Result<()> {
-        let graph = DependencyGraph::build(&result.modules);
+        let modules = self.ctx.filter_modules(&result.modules);
+        let graph = self.ctx.graph_for(result, &modules);
+
+        if let Some(ref preamble) = self.ctx.preamble {
+            writeln!(writer, "{}\n", preamble.trim_end())?;
+        }
 
         writeln!(writer, "# Architectural Context: {}\n", result.project_name)?;
 
+        if let Some(filters) = self.ctx.filter_summary() {
+            writeln!(writer, "*Filters: {}*\n", filters)?;
+        }
+
+        // Directory map goes first so it survives tight budgets
+        let layout = self
+            .ctx
+            .layout_summary(result, &modules, &graph)
+            .to_markdown();
+        write!(writer, "{}", layout)?;
+
         if let Some(budget) = self.ctx.token_budget {
-            self.format_with_budget(result, writer, &graph, budget)?;
+            let layout_tokens = self.ctx.count_tokens(&layout);
+            self.format_with_budget(result, &modules, writer, &graph, budget, layout_tokens)?;
         } else {
-            let ordered = self.ctx.order_modules(&result.modules, &graph);
-
-            writeln!(writer, "## Modules ({})\n", ordered.len())?;
+            let ordered = self.ctx.order_modules(&modules, &graph);
+
+            // Set when `--top` left modules out
+            let capped = if ordered.len() < modules.len() {
+                format!(
+                    "*The {} most important of {} modules*\n\n",
+                    ordered.len(),
+                    modules.len()
+                )
+            } else {
+                String::new()
+            };
+            let heading = format!(
+                "# Architectural Context: {}\n\n{}## Modules ({})\n\n{}",
+                result.project_name,
+                layout,
+                ordered.len(),
+                capped
+            );
+            write!(writer, "## Modules ({})\n\n{}", ordered.len(), capped)?;
 
-            let mut content = String::new();
+            // Each module is written as soon as it is rendered and counted on its own,
+            // so the full output is never held in memory
+            let mut total_tokens = self.ctx.count_tokens(&heading);
 
             for module in &ordered {
+                let mut content = String::new();
                 let rel_path = self.ctx.relative_path(&module.path);
+                content.push_str(&self.ctx.module_anchor(module));
                 content.push_str(&format!("### `{}`\n\n", rel_path));
 
                 if self.ctx.signatures_only {
                     let sig = self.ctx.format_module_signature(module);
                     if !sig.is_empty() {
+                        content.push_str(&self.ctx.definition_anchors(module));
                         content.push_str(&format!("```rust\n{}```\n\n", sig));
                     } else {
                         content.push_str("*No public API*\n\n");
@@ -45,16 +84,11 @@ impl MarkdownFormatter {
                     }
                     content.push('\n');
                 }
-            }
 
-            write!(writer, "{}", content)?;
+                write!(writer, "{}", content)?;
+                total_tokens += self.ctx.count_tokens(&content);
+            }
 
-            let total_tokens = self.ctx.count_tokens(&format!(
-                "# Architectural Context: {}\n\n## Modules ({})\n\n{}",
-                result.project_name,
-                ordered.len(),
-                content
-            ));
             writeln!(writer, "---\n*Context size: ~{} tokens*", total_tokens)?;
         }
 
@@ -64,63 +98,23 @@ impl MarkdownFormatter {
     fn format_with_budget<W: Write>(
         &self,
         result: &AnalysisResult,
+        modules: &[Module],
         writer: &mut W,
         graph: &DependencyGraph,
         budget: usize,
+        layout_tokens: usize,
     ) -> std::io::Result<()> {
-        let prioritized = self.ctx.prioritize_modules(&result.modules, graph);
-
-        let structure_reserve = 800;
-        let available = budget.saturating_sub(structure_reserve);
-
-        let mut used_tokens = 0;
-        let mut included = Vec::new();
-        let mut truncated = Vec::new();
-        let mut omitted = Vec::new();
-
-        for (module, score) in prioritized {
-            let content = if self.ctx.signatures_only {
-                self.ctx.format_module_signature(module)
-            } else {
-                self.ctx.format_module_full(module)
-            };
-
-            let tokens = self.ctx.count_tokens(&content);
-
-            if used_tokens + tokens <= available {
-                included.push((module, score, content, tokens));
-                used_tokens += tokens;
-            } else if !content.is_empty() {
-                let minimal = format!(
-                    "// {}\n{}",
-                    module.name,
-                    module
-                        .imports
-                        .iter()
-                        .map(|i| format!("use {};", i))
-                        .collect::<Vec<_>>()
-                        .join("\n")
-                );
-                let minimal_tokens = self.ctx.count_tokens(&minimal);
-
-                if used_tokens + minimal_tokens <= available {
-                    truncated.push((module, score, minimal, minimal_tokens));
-                    used_tokens += minimal_tokens;
-                } else {
-                    omitted.push(module);
-                }
-            }
-        }
+        let plan = self.ctx.plan_budget(modules, graph, budget, layout_tokens);
 
         writeln!(
             writer,
             "## Token Budget: {}/{}\n",
-            used_tokens + structure_reserve,
+            plan.total_tokens(),
             budget
         )?;
 
         // Refactoring order section
-        let refactor_order = self.ctx.refactoring_order(&result.modules, graph);
+        let refactor_order = self.ctx.refactoring_order(modules, graph);
         writeln!(writer, "## Suggested Refactoring Order\n")?;
         writeln!(
             writer,
@@ -141,8 +135,7 @@ impl MarkdownFormatter {
         writeln!(writer)?;
 
         // Actionable recommendations section
-        let modules_with_issues: Vec<_> = result
-            .modules
+        let modules_with_issues: Vec<_> = modules
             .iter()
             .filter_map(|m| {
                 let recs = self.ctx.file_recommendations(m, &result.issues, graph);
@@ -166,29 +159,33 @@ impl MarkdownFormatter {
             }
         }
 
-        writeln!(writer, "## Included Modules ({})\n", included.len())?;
+        writeln!(writer, "## Included Modules ({})\n", plan.included.len())?;
 
-        for (module, score, content, _tokens) in &included {
-            let rel_path = self.ctx.relative_path(&module.path);
-            writeln!(writer, "### `{}` (priority: {:.1})\n", rel_path, score)?;
-            writeln!(writer, "```rust\n{}\n```\n", content.trim())?;
+        for entry in &plan.included {
+            let rel_path = self.ctx.relative_path(&entry.module.path);
+            write!(writer, "{}", self.ctx.module_anchor(entry.module))?;
+            writeln!(
+                writer,
+                "### `{}` (priority: {:.1})\n",
+                rel_path, entry.score
+            )?;
+            if self.ctx.signatures_only {
+                write!(writer, "{}", self.ctx.definition_anchors(entry.module))?;
+            }
+            writeln!(writer, "```rust\n{}\n```\n", entry.content.trim())?;
         }
 
-        if !truncated.is_empty() {
-            writeln!(writer, "## Truncated Modules ({})\n", truncated.len())?;
-            for (module, _score, content, _tokens) in &truncated {
-                let rel_path = self.ctx.relative_path(&module.path);
+        if !plan.truncated.is_empty() {
+            writeln!(writer, "## Truncated Modules ({})\n", plan.truncated.len())?;
+            for entry in &plan.truncated {
+                let rel_path = self.ctx.relative_path(&entry.module.path);
+                write!(writer, "{}", self.ctx.module_anchor(entry.module))?;
                 writeln!(writer, "### `{}` (imports only)\n", rel_path)?;
-                writeln!(writer, "```rust\n{}\n```\n", content.trim())?;
+                writeln!(writer, "```rust\n{}\n```\n", entry.content.trim())?;
             }
         }
 
-        if !omitted.is_empty() {
-            writeln!(writer, "## Omitted Modules ({})\n", omitted.len())?;
-            for module in omitted {
-                writeln!(writer, "- `{}`", self.ctx.relative_path(&module.path))?;
-            }
-        }
+        write!(writer, "{}", plan.omitted_markdown())?;
 
         Ok(())
     }
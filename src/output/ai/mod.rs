@@ -1,41 +1,73 @@
+mod budget;
 mod context;
 mod json;
+mod layout;
 mod markdown;
+mod sources;
+pub mod templates;
 mod xml;
 
 pub use context::AiContext;
 pub use json::JsonFormatter;
 pub use markdown::MarkdownFormatter;
+pub use sources::{FileSources, SourceProvider};
 pub use xml::XmlFormatter;
 
-use crate::cli::{AiOutputFormat, PriorityStrategy};
-use crate::model::AnalysisResult;
-use crate::output::OutputFormatter;
+use crate::analysis::PathFilter;
+use crate::cli::{AiOutputFormat, PriorityStrategy, VisibilityLevel};
+use crate::model::{AnalysisResult, DefinitionKind};
+use crate::output::{OutputFormatter, OutputOptions};
 use std::collections::HashMap;
 use std::io::Write;
 use std::path::PathBuf;
+use std::sync::Arc;
+
+/// Default token cap for the Project Layout section
+pub const DEFAULT_LAYOUT_TOKENS: usize = 300;
 
 /// AI-optimized output formatter - facade that delegates to specific formatters
-pub struct AiOutput {
+pub struct AiOutput<'a> {
     pub project_root: Option<PathBuf>,
     pub topo_order: bool,
     pub signatures_only: bool,
+    pub visibility: VisibilityLevel,
+    /// Definition kinds shown in signatures; empty shows every kind
+    pub kinds: Vec<DefinitionKind>,
+    /// Show structs and enums by their field and variant names in signatures
+    pub compact_types: bool,
     pub token_budget: Option<usize>,
+    /// Most important modules listed; `None` lists all
+    pub top: Option<usize>,
     pub priority_strategy: PriorityStrategy,
     pub format: AiOutputFormat,
-    pub sources: HashMap<PathBuf, String>,
+    pub sources: Arc<dyn SourceProvider + 'a>,
+    pub filter: PathFilter,
+    /// Rendered task preamble placed at the top of the output
+    pub preamble: Option<String>,
+    pub layout_tokens: usize,
+    pub anchors: bool,
+    pub options: OutputOptions,
 }
 
-impl AiOutput {
+impl<'a> AiOutput<'a> {
     pub fn new(project_root: Option<PathBuf>) -> Self {
         Self {
             project_root,
             topo_order: true,
             signatures_only: false,
+            visibility: VisibilityLevel::Public,
+            kinds: Vec::new(),
+            compact_types: false,
             token_budget: None,
+            top: None,
             priority_strategy: PriorityStrategy::FanIn,
             format: AiOutputFormat::Markdown,
-            sources: HashMap::new(),
+            sources: Arc::new(HashMap::<PathBuf, String>::new()),
+            filter: PathFilter::default(),
+            preamble: None,
+            layout_tokens: DEFAULT_LAYOUT_TOKENS,
+            anchors: true,
+            options: OutputOptions::default(),
         }
     }
 
@@ -49,11 +81,37 @@ impl AiOutput {
         self
     }
 
+    /// Choose which definitions appear in signatures (default: public only).
+    pub fn with_visibility(mut self, visibility: VisibilityLevel) -> Self {
+        self.visibility = visibility;
+        self
+    }
+
+    /// Only show these definition kinds in signatures (default: all).
+    pub fn with_kinds(mut self, kinds: Vec<DefinitionKind>) -> Self {
+        self.kinds = kinds;
+        self
+    }
+
+    /// Show structs and enums in signatures as their field and variant names
+    /// where the parser recorded them, instead of their full definitions.
+    pub fn with_compact_types(mut self, enabled: bool) -> Self {
+        self.compact_types = enabled;
+        self
+    }
+
     pub fn with_token_budget(mut self, tokens: usize) -> Self {
         self.token_budget = Some(tokens);
         self
     }
 
+    /// List only the `count` most important modules, whether or not a token
+    /// budget applies.
+    pub fn with_top(mut self, count: usize) -> Self {
+        self.top = Some(count);
+        self
+    }
+
     pub fn with_priority(mut self, strategy: PriorityStrategy) -> Self {
         self.priority_strategy = strategy;
         self
@@ -65,22 +123,81 @@ impl AiOutput {
     }
 
     pub fn with_sources(mut self, sources: HashMap<PathBuf, String>) -> Self {
+        self.sources = Arc::new(sources);
+        self
+    }
+
+    /// Use sources that are already shared elsewhere, without copying them.
+    pub fn with_shared_sources(mut self, sources: Arc<HashMap<PathBuf, String>>) -> Self {
         self.sources = sources;
         self
     }
 
-    fn build_context(&self) -> AiContext {
+    /// Load sources on demand instead of holding them all, e.g. with
+    /// [`FileSources`] reading from the filesystem that was analyzed.
+    pub fn with_source_provider(mut self, provider: impl SourceProvider + 'a) -> Self {
+        self.sources = Arc::new(provider);
+        self
+    }
+
+    /// Only include modules matching these glob patterns.
+    pub fn with_include(mut self, patterns: Vec<String>) -> Self {
+        self.filter.include = patterns;
+        self
+    }
+
+    /// Drop modules matching these glob patterns.
+    pub fn with_exclude(mut self, patterns: Vec<String>) -> Self {
+        self.filter.exclude = patterns;
+        self
+    }
+
+    /// Prepend an already-rendered task preamble (see `templates::render_preamble`).
+    pub fn with_preamble(mut self, preamble: String) -> Self {
+        self.preamble = Some(preamble);
+        self
+    }
+
+    /// Cap the Project Layout section at this many tokens (0 disables it).
+    pub fn with_layout_tokens(mut self, tokens: usize) -> Self {
+        self.layout_tokens = tokens;
+        self
+    }
+
+    /// Emit `<!-- archmap:module -->` / `<!-- archmap:def -->` anchors in markdown (default on).
+    pub fn with_anchors(mut self, enabled: bool) -> Self {
+        self.anchors = enabled;
+        self
+    }
+
+    /// Leave issues below `options.min_severity` out of the layout counts and
+    /// recommendations.
+    pub fn with_options(mut self, options: OutputOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    fn build_context(&self) -> AiContext<'a> {
         AiContext {
             project_root: self.project_root.clone(),
             topo_order: self.topo_order,
             signatures_only: self.signatures_only,
+            visibility: self.visibility,
+            kinds: self.kinds.clone(),
+            compact_types: self.compact_types,
             token_budget: self.token_budget,
-            sources: self.sources.clone(),
+            top: self.top,
+            sources: Arc::clone(&self.sources),
+            filter: self.filter.clone(),
+            preamble: self.preamble.clone(),
+            layout_tokens: self.layout_tokens,
+            anchors: self.anchors,
+            options: self.options,
         }
     }
 }
 
-impl OutputFormatter for AiOutput {
+impl OutputFormatter for AiOutput<'_> {
     fn format<W: Write>(&self, result: &AnalysisResult, writer: &mut W) -> std::io::Result<()> {
         let ctx = self.build_context();
 
@@ -91,3 +208,705 @@ impl OutputFormatter for AiOutput {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{Definition, DefinitionKind, Module, Visibility};
+    use budget::OmittedSummary;
+    use std::borrow::Cow;
+    use std::path::Path;
+
+    #[test]
+    fn test_context_shares_sources() {
+        let sources = Arc::new(HashMap::from([(
+            PathBuf::from("src/lib.rs"),
+            "pub mod a;".to_string(),
+        )]));
+        let output = AiOutput::new(None).with_shared_sources(Arc::clone(&sources));
+
+        let ctx = output.build_context();
+
+        assert_eq!(Arc::strong_count(&sources), 3);
+        let source = ctx.sources.source(Path::new("src/lib.rs")).unwrap();
+        assert!(matches!(source, Cow::Borrowed("pub mod a;")));
+    }
+
+    fn mixed_visibility_module() -> Module {
+        let mut module = Module::new(PathBuf::from("src/lib.rs"));
+        for (name, visibility, signature) in [
+            ("open", Visibility::Public, "pub fn open()"),
+            ("helper", Visibility::Crate, "pub(crate) fn helper()"),
+            ("inner", Visibility::Private, "fn inner()"),
+        ] {
+            module.add_definition(Definition {
+                name: name.to_string(),
+                kind: DefinitionKind::Function,
+                line: 1,
+                visibility,
+                signature: Some(signature.to_string()),
+                span: None,
+                compact_signature: None,
+                decorators: Vec::new(),
+            });
+        }
+        module
+    }
+
+    fn signature_at(level: VisibilityLevel) -> String {
+        AiOutput::new(None)
+            .with_visibility(level)
+            .build_context()
+            .format_module_signature(&mixed_visibility_module())
+    }
+
+    #[test]
+    fn test_compact_types_replace_type_bodies() {
+        let mut module = Module::new(PathBuf::from("src/model.rs"));
+        module.add_definition(Definition {
+            name: "Config".to_string(),
+            kind: DefinitionKind::Struct,
+            line: 1,
+            visibility: Visibility::Public,
+            signature: Some("pub struct Config {\n    pub name: String,\n}".to_string()),
+            span: None,
+            compact_signature: Some("pub struct Config { name }".to_string()),
+            decorators: Vec::new(),
+        });
+
+        let sig = |compact: bool| {
+            AiOutput::new(None)
+                .with_compact_types(compact)
+                .build_context()
+                .format_module_signature(&module)
+        };
+        assert!(sig(false).contains("pub name: String"));
+        assert!(sig(true).contains("pub struct Config { name }"));
+        assert!(!sig(true).contains("String"));
+    }
+
+    #[test]
+    fn test_spanned_definitions_render_in_full() {
+        use crate::parser::{LanguageParser, RustParser};
+
+        let source = "use crate::store::Db;\n\n/// Settings\npub struct Config {\n    pub name: String,\n}\n\npub enum Mode {\n    Fast,\n    Safe,\n}\n\npub fn load(path: &str) -> Config {\n    todo!()\n}\n";
+        let path = PathBuf::from("/demo/src/config.rs");
+        let module = RustParser::new().parse_module(&path, source).unwrap();
+        let result = AnalysisResult::new("demo".to_string(), vec![module.clone()], Vec::new());
+        let output = AiOutput::new(Some(PathBuf::from("/demo")))
+            .with_sources(HashMap::from([(path, source.to_string())]))
+            .with_anchors(false);
+
+        let signature = output.build_context().format_module_signature(&module);
+        assert_eq!(
+            signature,
+            "use crate::store::Db;\n\npub struct Config {\n    pub name: String,\n}\n\npub enum Mode {\n    Fast,\n    Safe,\n}\n\npub fn load(path: &str) -> Config { ... }\n\n"
+        );
+
+        let mut json = Vec::new();
+        output
+            .with_format(AiOutputFormat::Json)
+            .format(&result, &mut json)
+            .unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&json).unwrap();
+        let definitions = &json["modules"][0]["definitions"];
+        assert_eq!(
+            definitions[1]["signature"],
+            "pub enum Mode {\n    Fast,\n    Safe,\n}"
+        );
+    }
+
+    #[test]
+    fn test_signature_visibility_public() {
+        let sig = signature_at(VisibilityLevel::Public);
+        assert!(sig.contains("pub fn open() { ... }"));
+        assert!(!sig.contains("helper"));
+        assert!(!sig.contains("inner"));
+    }
+
+    #[test]
+    fn test_signature_visibility_crate() {
+        let sig = signature_at(VisibilityLevel::Crate);
+        assert!(sig.contains("pub fn open()"));
+        assert!(sig.contains("pub(crate) fn helper() { ... }"));
+        assert!(!sig.contains("inner"));
+    }
+
+    #[test]
+    fn test_signature_visibility_all() {
+        let sig = signature_at(VisibilityLevel::All);
+        assert!(sig.contains("pub fn open()"));
+        assert!(sig.contains("pub(crate) fn helper()"));
+        assert!(sig.contains("// private\nfn inner() { ... }"));
+    }
+
+    #[test]
+    fn test_scala_package_visibility_keeps_its_own_modifier() {
+        let mut module = Module::new(PathBuf::from("src/data/Repo.scala"));
+        module.add_definition(Definition {
+            name: "Repo".to_string(),
+            kind: DefinitionKind::Class,
+            line: 3,
+            visibility: Visibility::Crate,
+            signature: Some("private[data] class Repo".to_string()),
+            span: None,
+            compact_signature: None,
+            decorators: Vec::new(),
+        });
+
+        let sig = AiOutput::new(None)
+            .with_visibility(VisibilityLevel::Crate)
+            .with_anchors(false)
+            .build_context()
+            .format_module_signature(&module);
+        assert_eq!(sig, "private[data] class Repo\n\n");
+    }
+
+    #[test]
+    fn test_kinds_narrow_signatures_to_the_data_model() {
+        let mut module = mixed_visibility_module();
+        for (name, kind, signature) in [
+            ("Config", DefinitionKind::Struct, "pub struct Config"),
+            ("Mode", DefinitionKind::Enum, "pub enum Mode"),
+        ] {
+            module.add_definition(Definition {
+                name: name.to_string(),
+                kind,
+                line: 2,
+                visibility: Visibility::Public,
+                signature: Some(signature.to_string()),
+                span: None,
+                compact_signature: None,
+                decorators: Vec::new(),
+            });
+        }
+
+        let kinds: Vec<DefinitionKind> = "struct,ENUM"
+            .split(',')
+            .map(|k| k.parse().unwrap())
+            .collect();
+        let sig = AiOutput::new(None)
+            .with_kinds(kinds)
+            .build_context()
+            .format_module_signature(&module);
+        assert!(sig.contains("pub struct Config\n"));
+        assert!(sig.contains("pub enum Mode\n"));
+        assert!(!sig.contains("fn open"));
+
+        assert_eq!("fn".parse::<DefinitionKind>(), Ok(DefinitionKind::Function));
+        assert!("record".parse::<DefinitionKind>().is_err());
+    }
+
+    #[test]
+    fn test_structured_output_respects_visibility() {
+        let modules = vec![mixed_visibility_module()];
+        let result = AnalysisResult::new("demo".to_string(), modules, Vec::new());
+
+        let mut json = Vec::new();
+        AiOutput::new(None)
+            .with_format(AiOutputFormat::Json)
+            .with_visibility(VisibilityLevel::Crate)
+            .format(&result, &mut json)
+            .unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&json).unwrap();
+        let visibilities: Vec<_> = json["modules"][0]["definitions"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|d| d["visibility"].as_str().unwrap())
+            .collect();
+        assert_eq!(visibilities, vec!["public", "crate"]);
+
+        let mut xml = Vec::new();
+        AiOutput::new(None)
+            .with_format(AiOutputFormat::Xml)
+            .format(&result, &mut xml)
+            .unwrap();
+        let xml = String::from_utf8(xml).unwrap();
+        assert!(xml.contains("name=\"open\" visibility=\"public\""));
+        assert!(!xml.contains("name=\"helper\""));
+    }
+
+    #[test]
+    fn test_xml_preamble_cannot_close_its_cdata() {
+        let result = AnalysisResult::new("demo".to_string(), Vec::new(), Vec::new());
+        let mut xml = Vec::new();
+        AiOutput::new(None)
+            .with_format(AiOutputFormat::Xml)
+            .with_preamble("Keep a[b[0]]> c intact".to_string())
+            .format(&result, &mut xml)
+            .unwrap();
+        let xml = String::from_utf8(xml).unwrap();
+        assert!(xml.contains(
+            "<system_prompt><![CDATA[Keep a[b[0]]]]><![CDATA[> c intact]]></system_prompt>"
+        ));
+    }
+
+    #[test]
+    fn test_stored_graph_matches_a_rebuilt_one() {
+        let module = |name: &str, imports: &[&str]| {
+            let mut module = Module::new(PathBuf::from(format!("/demo/{}.ts", name)));
+            module.imports = imports.iter().map(|&i| i.into()).collect();
+            module.lines = 40;
+            module
+        };
+        let modules = vec![
+            module("app", &["./core", "./util"]),
+            module("core", &["./util", "./app"]),
+            module("util", &[]),
+            module("cli", &["./app"]),
+        ];
+        let result = AnalysisResult::new("demo".to_string(), modules, Vec::new());
+        // The graph comes back from JSON as stored, not rebuilt
+        let restored = AnalysisResult::from_json(&result.to_json().unwrap()).unwrap();
+        let rebuilt = crate::analysis::DependencyGraph::from_result(&result);
+
+        let render = |result: &AnalysisResult| {
+            let mut buffer = Vec::new();
+            AiOutput::new(Some(PathBuf::from("/demo")))
+                .format(result, &mut buffer)
+                .unwrap();
+            String::from_utf8(buffer).unwrap()
+        };
+        assert_eq!(render(&restored), render(&result));
+
+        let ctx = AiOutput::new(None).build_context();
+        let order = |graph: &crate::analysis::DependencyGraph| -> Vec<String> {
+            ctx.prioritize_modules(&result.modules, graph)
+                .into_iter()
+                .map(|(m, _)| m.name.clone())
+                .collect()
+        };
+        assert_eq!(order(&restored.dependency_graph), order(&rebuilt));
+
+        let issues = |graph: &crate::analysis::DependencyGraph| -> Vec<String> {
+            crate::analysis::detect_issues(
+                &result.modules,
+                graph,
+                &Default::default(),
+                crate::fs::default_fs(),
+            )
+            .into_iter()
+            .map(|issue| issue.message)
+            .collect()
+        };
+        let stored = issues(&restored.dependency_graph);
+        assert!(stored.iter().any(|m| m.contains("Circular dependency")));
+        assert_eq!(stored, issues(&rebuilt));
+    }
+
+    fn layered_result(dirs: usize) -> AnalysisResult {
+        let modules: Vec<Module> = (0..dirs)
+            .map(|i| {
+                let mut module = Module::new(PathBuf::from(format!("/demo/src/area{}/mod.rs", i)));
+                module.lines = 100 + i;
+                module
+            })
+            .collect();
+        AnalysisResult::new("demo".to_string(), modules, Vec::new())
+    }
+
+    #[test]
+    fn test_layout_precedes_modules() {
+        let mut buffer = Vec::new();
+        AiOutput::new(Some(PathBuf::from("/demo")))
+            .format(&layered_result(3), &mut buffer)
+            .unwrap();
+        let output = String::from_utf8(buffer).unwrap();
+
+        let layout = output.find("## Project Layout").unwrap();
+        let modules = output.find("## Modules").unwrap();
+        assert!(layout < modules);
+        assert!(output.contains("- `src/area0/` — 1 modules, 100 lines"));
+    }
+
+    #[test]
+    fn test_top_keeps_the_most_imported_modules() {
+        let module = |name: &str, imports: &[&str]| {
+            let mut module = Module::new(PathBuf::from(format!("/demo/{}.ts", name)));
+            module.imports = imports.iter().map(|&i| i.into()).collect();
+            module
+        };
+        let modules = vec![
+            module("a", &["./core", "./util"]),
+            module("b", &["./core", "./util"]),
+            module("c", &["./core"]),
+            module("core", &[]),
+            module("util", &[]),
+        ];
+        let result = AnalysisResult::new("demo".to_string(), modules, Vec::new());
+        let sources = result
+            .modules
+            .iter()
+            .map(|m| (m.path.clone(), "export const x = 1;\n".to_string()))
+            .collect();
+        let output = AiOutput::new(Some(PathBuf::from("/demo")))
+            .with_sources(sources)
+            .with_layout_tokens(0)
+            .with_top(2);
+
+        let mut buffer = Vec::new();
+        output.format(&result, &mut buffer).unwrap();
+        let markdown = String::from_utf8(buffer).unwrap();
+        assert!(markdown.contains("## Modules (2)\n\n*The 2 most important of 5 modules*"));
+        assert!(markdown.contains("### `core.ts`"));
+        assert!(markdown.contains("### `util.ts`"));
+        assert!(!markdown.contains("### `a.ts`"));
+
+        let ctx = output.build_context();
+        let plan = ctx.plan_budget(&result.modules, &result.dependency_graph, 100_000, 0);
+        let included: Vec<_> = plan
+            .included
+            .iter()
+            .map(|e| e.module.name.as_str())
+            .collect();
+        assert_eq!(included, ["core", "util"]);
+        assert_eq!(plan.omitted_count() + plan.unlisted, 0);
+    }
+
+    #[test]
+    fn test_equal_scores_are_ordered_by_path() {
+        let names = ["d", "b", "a", "c"];
+        let modules: Vec<Module> = names
+            .iter()
+            .map(|name| Module::new(PathBuf::from(format!("/demo/{}.ts", name))))
+            .collect();
+        let mut reversed = modules.clone();
+        reversed.reverse();
+
+        let ctx = AiOutput::new(None).build_context();
+        let order = |modules: &[Module]| -> Vec<String> {
+            let graph = crate::analysis::DependencyGraph::build(modules);
+            ctx.prioritize_modules(modules, &graph)
+                .into_iter()
+                .map(|(m, _)| m.name.clone())
+                .collect()
+        };
+        assert_eq!(order(&modules), ["a", "b", "c", "d"]);
+        assert_eq!(order(&reversed), ["a", "b", "c", "d"]);
+    }
+
+    #[test]
+    fn test_layout_respects_token_cap() {
+        let result = layered_result(40);
+        let ctx = AiOutput::new(Some(PathBuf::from("/demo")))
+            .with_layout_tokens(120)
+            .build_context();
+
+        let layout = ctx.layout_summary(&result, &result.modules, &result.dependency_graph);
+        assert!(!layout.directories.is_empty());
+        assert!(layout.omitted > 0);
+        assert_eq!(layout.directories.len() + layout.omitted, 40);
+        assert!(ctx.count_tokens(&layout.to_markdown()) <= 120);
+
+        let disabled = AiOutput::new(None).with_layout_tokens(0).build_context();
+        assert!(
+            disabled
+                .layout_summary(&result, &result.modules, &result.dependency_graph)
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_layout_fits_small_budgets() {
+        let result = layered_result(40);
+        for budget in [500, 1000, 1200] {
+            let ctx = AiOutput::new(Some(PathBuf::from("/demo")))
+                .with_layout_tokens(5000)
+                .with_token_budget(budget)
+                .build_context();
+            let layout = ctx.layout_summary(&result, &result.modules, &result.dependency_graph);
+            let layout_tokens = ctx.count_tokens(&layout.to_markdown());
+            assert!(layout_tokens <= budget.saturating_sub(budget::STRUCTURE_RESERVE));
+
+            let plan = ctx.plan_budget(
+                &result.modules,
+                &result.dependency_graph,
+                budget,
+                layout_tokens,
+            );
+            assert!(
+                plan.total_tokens() <= budget,
+                "{} over {}",
+                plan.total_tokens(),
+                budget
+            );
+        }
+
+        // A layout measured over the cap is still held to it
+        let ctx = AiOutput::new(None).build_context();
+        let plan = ctx.plan_budget(&result.modules, &result.dependency_graph, 1000, 5000);
+        assert!(plan.total_tokens() <= 1000);
+    }
+
+    #[test]
+    fn test_preamble_counts_against_budget() {
+        let result = layered_result(40);
+        let preamble = "Review the layering before changing anything. ".repeat(20);
+        let plan_with = |preamble: Option<&str>| {
+            let mut output = AiOutput::new(None)
+                .with_layout_tokens(5000)
+                .with_token_budget(1200);
+            if let Some(preamble) = preamble {
+                output = output.with_preamble(preamble.to_string());
+            }
+            let ctx = output.build_context();
+            let plan = ctx.plan_budget(&result.modules, &result.dependency_graph, 1200, 0);
+            (
+                ctx.count_tokens(preamble.unwrap_or_default()),
+                plan.reserve,
+                ctx.layout_cap(),
+            )
+        };
+
+        let (_, reserve, cap) = plan_with(None);
+        let (preamble_tokens, reserve_with, cap_with) = plan_with(Some(&preamble));
+        assert!(preamble_tokens > 0);
+        assert_eq!(reserve_with, reserve + preamble_tokens);
+        assert_eq!(cap_with, cap - preamble_tokens);
+    }
+
+    fn anchored_result() -> (AnalysisResult, HashMap<PathBuf, String>) {
+        let modules = vec![mixed_visibility_module()];
+        let sources = HashMap::from([(
+            PathBuf::from("src/lib.rs"),
+            "pub fn open() {}\n".to_string(),
+        )]);
+        let result = AnalysisResult::new("demo".to_string(), modules, Vec::new());
+        (result, sources)
+    }
+
+    #[test]
+    fn test_anchors_survive_terminal_rendering() {
+        let (result, sources) = anchored_result();
+        let output = AiOutput::new(None)
+            .with_sources(sources)
+            .with_signatures_only(true)
+            .with_token_budget(4000);
+        let hash = output
+            .build_context()
+            .module_hash(&result.modules[0])
+            .unwrap();
+
+        let mut buffer = Vec::new();
+        output.format(&result, &mut buffer).unwrap();
+        let markdown = String::from_utf8(buffer).unwrap();
+
+        let module_anchor = format!(
+            "<!-- archmap:module path=\"src/lib.rs\" hash=\"{}\" -->",
+            hash
+        );
+        let def_anchor = "<!-- archmap:def name=\"open\" line=\"1\" -->";
+        assert!(markdown.contains(&module_anchor));
+        assert!(markdown.contains(&format!("{}\n```rust\n", def_anchor)));
+        assert!(!markdown.contains("```rust\n<!--"));
+
+        let rendered = crate::style::terminal_markdown(&markdown);
+        assert!(rendered.contains(&format!("{}\n", module_anchor)));
+        assert!(rendered.contains(def_anchor));
+    }
+
+    #[test]
+    fn test_definition_anchors_stay_outside_code_blocks() {
+        let (result, sources) = anchored_result();
+        let mut buffer = Vec::new();
+        AiOutput::new(None)
+            .with_sources(sources)
+            .with_signatures_only(true)
+            .format(&result, &mut buffer)
+            .unwrap();
+        let markdown = String::from_utf8(buffer).unwrap();
+
+        let def_anchor = "<!-- archmap:def name=\"open\" line=\"1\" -->";
+        assert!(markdown.contains(&format!("{}\n```rust\n", def_anchor)));
+        let code = markdown.split("```rust\n").nth(1).unwrap();
+        assert!(!code.split("```").next().unwrap().contains("archmap:"));
+    }
+
+    #[test]
+    fn test_no_anchors_and_structured_hashes() {
+        let (result, sources) = anchored_result();
+
+        let mut buffer = Vec::new();
+        AiOutput::new(None)
+            .with_sources(sources.clone())
+            .with_signatures_only(true)
+            .with_anchors(false)
+            .format(&result, &mut buffer)
+            .unwrap();
+        assert!(!String::from_utf8(buffer).unwrap().contains("archmap:"));
+
+        let mut json = Vec::new();
+        AiOutput::new(None)
+            .with_sources(sources)
+            .with_format(AiOutputFormat::Json)
+            .format(&result, &mut json)
+            .unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&json).unwrap();
+        assert_eq!(json["modules"][0]["hash"].as_str().unwrap().len(), 16);
+    }
+
+    /// Modules whose source and imports are each too large for a small budget
+    fn bulky_result(count: usize) -> (AnalysisResult, HashMap<PathBuf, String>) {
+        let mut sources = HashMap::new();
+        let modules: Vec<Module> = (0..count)
+            .map(|i| {
+                let path = PathBuf::from(format!("/demo/src/bulky{}.rs", i));
+                let mut module = Module::new(path.clone());
+                module.lines = 300;
+                module.imports = (0..40)
+                    .map(|j| format!("crate::dep{}::Item{}", j, j).into())
+                    .collect();
+                module.exports = (0..7).map(|j| format!("export{}", j).into()).collect();
+                sources.insert(path, "pub fn bulky() {}\n".repeat(200));
+                module
+            })
+            .collect();
+        let result = AnalysisResult::new("demo".to_string(), modules, Vec::new());
+        (result, sources)
+    }
+
+    #[test]
+    fn test_omitted_summaries_count_against_budget() {
+        let (result, sources) = bulky_result(3);
+        let ctx = AiOutput::new(Some(PathBuf::from("/demo")))
+            .with_sources(sources)
+            .build_context();
+
+        let plan = ctx.plan_budget(&result.modules, &result.dependency_graph, 1000, 0);
+        assert!(plan.included.is_empty() && plan.truncated.is_empty());
+        assert_eq!(plan.omitted.len(), 3);
+        assert!(plan.total_tokens() <= 1000);
+
+        let summary = &plan.omitted[0];
+        assert_eq!(summary.exports.len(), OmittedSummary::MAX_EXPORTS);
+        assert_eq!(summary.more_exports, 2);
+        assert!(
+            summary
+                .to_markdown()
+                .contains("300 lines, fan-in 0, exports: export0")
+        );
+
+        let markdown = plan.omitted_markdown();
+        assert!(markdown.starts_with("## Omitted Modules (3)"));
+        // Summaries are charged to the budget, the header to the reserve
+        let summary_tokens: usize = plan
+            .omitted
+            .iter()
+            .map(|s| ctx.count_tokens(&format!("{}\n", s.to_markdown())))
+            .sum();
+        assert_eq!(plan.used_tokens, summary_tokens);
+        assert!(ctx.count_tokens(&markdown) <= plan.total_tokens());
+    }
+
+    #[test]
+    fn test_omitted_section_stays_within_budget() {
+        let (result, sources) = bulky_result(20);
+        let ctx = AiOutput::new(Some(PathBuf::from("/demo")))
+            .with_sources(sources)
+            .build_context();
+
+        // From no room for any summary up to room for a few
+        for budget in (budget::STRUCTURE_RESERVE..budget::STRUCTURE_RESERVE + 120).step_by(7) {
+            let plan = ctx.plan_budget(&result.modules, &result.dependency_graph, budget, 0);
+            assert_eq!(plan.omitted_count(), 20);
+            assert!(
+                plan.total_tokens() <= budget,
+                "{} over {}",
+                plan.total_tokens(),
+                budget
+            );
+        }
+    }
+
+    #[test]
+    fn test_full_source_is_borrowed() {
+        let (result, sources) = bulky_result(1);
+        let ctx = AiOutput::new(Some(PathBuf::from("/demo")))
+            .with_sources(sources)
+            .build_context();
+
+        let plan = ctx.plan_budget(&result.modules, &result.dependency_graph, 100_000, 0);
+        assert_eq!(plan.included.len(), 1);
+        assert!(matches!(plan.included[0].content, Cow::Borrowed(_)));
+    }
+
+    /// Serves sources from memory, recording every path asked for
+    struct CountingSources {
+        sources: HashMap<PathBuf, String>,
+        reads: Arc<std::sync::Mutex<Vec<PathBuf>>>,
+    }
+
+    impl SourceProvider for CountingSources {
+        fn source(&self, path: &Path) -> Option<Cow<'_, str>> {
+            self.reads.lock().unwrap().push(path.to_path_buf());
+            self.sources.source(path)
+        }
+    }
+
+    #[test]
+    fn test_budget_loads_only_included_sources() {
+        let (result, sources) = bulky_result(20);
+        let full_tokens = AiOutput::new(None)
+            .build_context()
+            .count_tokens(sources.values().next().unwrap());
+        let reads = Arc::default();
+        let output = AiOutput::new(Some(PathBuf::from("/demo")))
+            .with_source_provider(CountingSources {
+                sources,
+                reads: Arc::clone(&reads),
+            })
+            .with_layout_tokens(0)
+            .with_anchors(false)
+            .with_token_budget(budget::STRUCTURE_RESERVE + 2 * full_tokens + 50);
+
+        let mut buffer = Vec::new();
+        output.format(&result, &mut buffer).unwrap();
+        let markdown = String::from_utf8(buffer).unwrap();
+
+        assert!(markdown.contains("## Included Modules (2)"));
+        // The rest don't fit even as imports, so their files are never read
+        let reads = reads.lock().unwrap();
+        assert_eq!(reads.len(), 2);
+        assert!(reads.iter().all(|path| markdown.contains(&format!(
+            "`{}` (priority",
+            path.strip_prefix("/demo").unwrap().display()
+        ))));
+    }
+
+    #[test]
+    fn test_omitted_summaries_truncated_to_fit() {
+        let (result, sources) = bulky_result(20);
+        let output = AiOutput::new(Some(PathBuf::from("/demo")))
+            .with_sources(sources)
+            .with_layout_tokens(0)
+            .with_token_budget(900);
+
+        let ctx = output.build_context();
+        let plan = ctx.plan_budget(&result.modules, &result.dependency_graph, 900, 0);
+        assert!(!plan.omitted.is_empty());
+        assert!(plan.unlisted > 0);
+        assert_eq!(plan.omitted_count(), 20);
+        assert!(plan.total_tokens() <= 900);
+
+        let mut buffer = Vec::new();
+        output.format(&result, &mut buffer).unwrap();
+        let markdown = String::from_utf8(buffer).unwrap();
+        assert!(markdown.contains("## Omitted Modules (20)"));
+        assert!(markdown.contains(&format!("- ... and {} more modules", plan.unlisted)));
+
+        let mut json = Vec::new();
+        output
+            .with_format(AiOutputFormat::Json)
+            .format(&result, &mut json)
+            .unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&json).unwrap();
+        assert!(json["modules"].as_array().unwrap().is_empty());
+        assert_eq!(
+            json["budget"]["omitted"].as_array().unwrap().len(),
+            plan.omitted.len()
+        );
+        assert_eq!(json["budget"]["omitted"][0]["more_exports"], 2);
+        assert_eq!(json["budget"]["unlisted"], plan.unlisted);
+    }
+}
@@ -0,0 +1,40 @@
+//! Where AI output gets module source text from.
+
+use crate::fs::FileSystem;
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+/// Source text of modules, asked for by path when a formatter needs it: the
+/// full file for a budgeted module, or its hash for an anchor.
+pub trait SourceProvider: Send + Sync {
+    /// The text of `path`, or `None` when there is none to show
+    fn source(&self, path: &Path) -> Option<Cow<'_, str>>;
+}
+
+/// Sources already in memory, as [`AiOutput::with_sources`](super::AiOutput::with_sources) takes them
+impl SourceProvider for HashMap<PathBuf, String> {
+    fn source(&self, path: &Path) -> Option<Cow<'_, str>> {
+        self.get(path).map(|s| Cow::Borrowed(s.as_str()))
+    }
+}
+
+/// Reads each module's file when a formatter first asks for it, so modules
+/// left out by `--signatures` or the token budget are never loaded. Nothing
+/// is kept here; a [`CachingFileSystem`](crate::fs::CachingFileSystem)
+/// underneath saves reading a file twice.
+pub struct FileSources<'a> {
+    fs: &'a dyn FileSystem,
+}
+
+impl<'a> FileSources<'a> {
+    pub fn new(fs: &'a dyn FileSystem) -> Self {
+        Self { fs }
+    }
+}
+
+impl SourceProvider for FileSources<'_> {
+    fn source(&self, path: &Path) -> Option<Cow<'_, str>> {
+        self.fs.read_to_string(path).ok().map(Cow::Owned)
+    }
+}
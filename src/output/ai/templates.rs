@@ -0,0 +1,172 @@
+//! Task preambles prepended to AI context, so agents get their instructions
+//! alongside the architecture.
+//!
+//! Templates use `{placeholder}` markers; `{{` and `}}` produce literal braces.
+
+use thiserror::Error;
+
+/// Built-in preamble for refactoring tasks
+pub const REFACTOR: &str = "\
+You are refactoring the {project_name} codebase ({module_count} modules, context budget: {budget}).
+Focus: {focus}.
+
+- Work through the suggested refactoring order: change leaf modules before the modules that depend on them.
+- Keep public signatures stable unless the task requires changing them, and update every dependent when you do.
+- Do not modify modules that were omitted or truncated from this context; ask for them first.
+";
+
+/// Built-in preamble for getting familiar with a codebase
+pub const ONBOARDING: &str = "\
+You are helping a new contributor understand the {project_name} codebase ({module_count} modules, context budget: {budget}).
+Focus: {focus}.
+
+- Explain how the modules fit together, starting from the most depended-on ones.
+- Point out the architectural issues listed below and why they matter.
+- Say so when a question needs a module that is not included in this context.
+";
+
+/// Built-in preamble for bug fixing
+pub const BUGFIX: &str = "\
+You are fixing a bug in the {project_name} codebase ({module_count} modules, context budget: {budget}).
+Focus: {focus}.
+
+- Find the smallest change that fixes the bug; avoid unrelated refactoring.
+- Check the dependents of every module you change, since they may rely on the current behavior.
+- Do not modify modules that were omitted or truncated from this context; ask for them first.
+";
+
+/// Names accepted by `--preamble` in place of a file path
+pub const BUILTIN_NAMES: &[&str] = &["refactor", "onboarding", "bugfix"];
+
+/// Look up a built-in preamble template by name.
+pub fn builtin(name: &str) -> Option<&'static str> {
+    match name {
+        "refactor" => Some(REFACTOR),
+        "onboarding" => Some(ONBOARDING),
+        "bugfix" => Some(BUGFIX),
+        _ => None,
+    }
+}
+
+#[derive(Error, Debug, PartialEq)]
+pub enum PreambleError {
+    #[error(
+        "Unknown placeholder {{{0}}} in preamble (available: {{project_name}}, {{module_count}}, {{budget}}, {{focus}})"
+    )]
+    UnknownPlaceholder(String),
+    #[error("Unclosed '{{' in preamble")]
+    Unclosed,
+}
+
+/// Values substituted into a preamble template
+#[derive(Debug, Clone)]
+pub struct PreambleVars {
+    pub project_name: String,
+    pub module_count: usize,
+    pub budget: Option<usize>,
+    pub focus: Option<String>,
+}
+
+impl PreambleVars {
+    fn get(&self, key: &str) -> Option<String> {
+        match key {
+            "project_name" => Some(self.project_name.clone()),
+            "module_count" => Some(self.module_count.to_string()),
+            "budget" => Some(
+                self.budget
+                    .map(|b| format!("{} tokens", b))
+                    .unwrap_or_else(|| "unlimited".to_string()),
+            ),
+            "focus" => Some(
+                self.focus
+                    .clone()
+                    .unwrap_or_else(|| "the whole project".to_string()),
+            ),
+            _ => None,
+        }
+    }
+}
+
+/// Fill a template's `{placeholder}` markers. Unknown placeholders are an error.
+pub fn render_preamble(template: &str, vars: &PreambleVars) -> Result<String, PreambleError> {
+    let mut output = String::with_capacity(template.len());
+    let mut chars = template.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '{' if chars.peek() == Some(&'{') => {
+                chars.next();
+                output.push('{');
+            }
+            '}' if chars.peek() == Some(&'}') => {
+                chars.next();
+                output.push('}');
+            }
+            '{' => {
+                let mut key = String::new();
+                loop {
+                    match chars.next() {
+                        Some('}') => break,
+                        Some(ch) => key.push(ch),
+                        None => return Err(PreambleError::Unclosed),
+                    }
+                }
+                let key = key.trim();
+                match vars.get(key) {
+                    Some(value) => output.push_str(&value),
+                    None => return Err(PreambleError::UnknownPlaceholder(key.to_string())),
+                }
+            }
+            _ => output.push(c),
+        }
+    }
+
+    Ok(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vars() -> PreambleVars {
+        PreambleVars {
+            project_name: "demo".to_string(),
+            module_count: 12,
+            budget: Some(4000),
+            focus: None,
+        }
+    }
+
+    #[test]
+    fn test_substitutes_variables() {
+        let rendered = render_preamble(
+            "{project_name}: {module_count} modules, {budget}, focus {focus}. Keep {{braces}}.",
+            &vars(),
+        )
+        .unwrap();
+        assert_eq!(
+            rendered,
+            "demo: 12 modules, 4000 tokens, focus the whole project. Keep {braces}."
+        );
+    }
+
+    #[test]
+    fn test_builtins_render() {
+        for name in BUILTIN_NAMES {
+            let rendered = render_preamble(builtin(name).unwrap(), &vars()).unwrap();
+            assert!(rendered.contains("demo codebase (12 modules"));
+        }
+    }
+
+    #[test]
+    fn test_unknown_placeholder_errors() {
+        assert_eq!(
+            render_preamble("Fix {ticket} in {project_name}", &vars()),
+            Err(PreambleError::UnknownPlaceholder("ticket".to_string()))
+        );
+        assert_eq!(
+            render_preamble("Broken {project_name", &vars()),
+            Err(PreambleError::Unclosed)
+        );
+    }
+}
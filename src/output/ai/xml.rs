@@ -1,20 +1,20 @@
 use super::AiContext;
-use crate::analysis::DependencyGraph;
-use crate::model::{AnalysisResult, Visibility};
+use crate::model::AnalysisResult;
 use std::io::Write;
 
-pub struct XmlFormatter {
-    ctx: AiContext,
+pub struct XmlFormatter<'a> {
+    ctx: AiContext<'a>,
 }
 
-impl XmlFormatter {
-    pub fn new(ctx: AiContext) -> Self {
+impl<'a> XmlFormatter<'a> {
+    pub fn new(ctx: AiContext<'a>) -> Self {
         Self { ctx }
     }
 
     pub fn format<W: Write>(&self, result: &AnalysisResult, writer: &mut W) -> std::io::Result<()> {
-        let graph = DependencyGraph::build(&result.modules);
-        let ordered = self.ctx.order_modules(&result.modules, &graph);
+        let modules = self.ctx.filter_modules(&result.modules);
+        let graph = self.ctx.graph_for(result, &modules);
+        let ordered = self.ctx.order_modules(&modules, &graph);
 
         writeln!(
             writer,
@@ -22,6 +22,58 @@ impl XmlFormatter {
             escape_xml(&result.project_name)
         )?;
 
+        if let Some(ref preamble) = self.ctx.preamble {
+            writeln!(
+                writer,
+                "  <system_prompt>{}</system_prompt>",
+                cdata(preamble.trim_end())
+            )?;
+        }
+
+        if !self.ctx.filter.is_empty() {
+            writeln!(writer, "  <filters>")?;
+            for pattern in &self.ctx.filter.include {
+                writeln!(writer, "    <include>{}</include>", escape_xml(pattern))?;
+            }
+            for pattern in &self.ctx.filter.exclude {
+                writeln!(writer, "    <exclude>{}</exclude>", escape_xml(pattern))?;
+            }
+            writeln!(writer, "  </filters>")?;
+        }
+
+        let layout = self.ctx.layout_summary(result, &modules, &graph);
+        if !layout.is_empty() {
+            writeln!(writer, "  <layout omitted=\"{}\">", layout.omitted)?;
+            for dir in &layout.directories {
+                writeln!(
+                    writer,
+                    "    <directory path=\"{}\" modules=\"{}\" lines=\"{}\" issues=\"{}\">",
+                    escape_xml(&dir.path),
+                    dir.modules,
+                    dir.lines,
+                    dir.issue_count()
+                )?;
+                for (kind, count) in &dir.dominant_kinds {
+                    writeln!(
+                        writer,
+                        "      <kind name=\"{}\" count=\"{}\"/>",
+                        escape_xml(kind),
+                        count
+                    )?;
+                }
+                for (path, fan_in) in &dir.top_fan_in {
+                    writeln!(
+                        writer,
+                        "      <top_module path=\"{}\" fan_in=\"{}\"/>",
+                        escape_xml(path),
+                        fan_in
+                    )?;
+                }
+                writeln!(writer, "    </directory>")?;
+            }
+            writeln!(writer, "  </layout>")?;
+        }
+
         // Refactoring order section
         writeln!(
             writer,
@@ -29,7 +81,7 @@ impl XmlFormatter {
         )?;
         for (i, module) in self
             .ctx
-            .refactoring_order(&result.modules, &graph)
+            .refactoring_order(&modules, &graph)
             .iter()
             .enumerate()
         {
@@ -46,8 +98,7 @@ impl XmlFormatter {
         writeln!(writer, "  </refactoring_order>")?;
 
         // Actionable recommendations section
-        let modules_with_issues: Vec<_> = result
-            .modules
+        let modules_with_issues: Vec<_> = modules
             .iter()
             .filter_map(|m| {
                 let recs = self.ctx.file_recommendations(m, &result.issues, &graph);
@@ -89,14 +140,20 @@ impl XmlFormatter {
             let fan_in = graph.fan_in(&module.path);
             let fan_out = graph.fan_out(&module.path);
 
+            let hash = self
+                .ctx
+                .module_hash(module)
+                .map(|h| format!(" hash=\"{}\"", h))
+                .unwrap_or_default();
             writeln!(
                 writer,
-                "    <module path=\"{}\" name=\"{}\" lines=\"{}\" fan_in=\"{}\" fan_out=\"{}\">",
+                "    <module path=\"{}\" name=\"{}\" lines=\"{}\" fan_in=\"{}\" fan_out=\"{}\"{}>",
                 escape_xml(&rel_path),
                 escape_xml(&module.name),
                 module.lines,
                 fan_in,
-                fan_out
+                fan_out,
+                hash
             )?;
 
             if !module.imports.is_empty() {
@@ -115,26 +172,24 @@ impl XmlFormatter {
                 writeln!(writer, "      </exports>")?;
             }
 
-            // Public definitions
-            let public_defs: Vec<_> = module
-                .definitions
-                .iter()
-                .filter(|d| d.visibility == Visibility::Public)
-                .collect();
+            // Definitions passing the visibility filter
+            let defs: Vec<_> = self.ctx.visible_definitions(module).collect();
 
-            if !public_defs.is_empty() {
+            if !defs.is_empty() {
+                let source = self.ctx.definition_source(module);
                 writeln!(writer, "      <definitions>")?;
-                for def in public_defs {
+                for def in defs {
                     let kind = format!("{:?}", def.kind).to_lowercase();
                     writeln!(
                         writer,
-                        "        <{} name=\"{}\" line=\"{}\">",
+                        "        <{} name=\"{}\" visibility=\"{}\" line=\"{}\">",
                         kind,
                         escape_xml(&def.name),
+                        def.visibility.as_str(),
                         def.line
                     )?;
-                    if let Some(ref sig) = def.signature {
-                        writeln!(writer, "<![CDATA[{}]]>", sig)?;
+                    if let Some(sig) = def.full_text(source.as_deref()) {
+                        writeln!(writer, "{}", cdata(sig))?;
                     }
                     writeln!(writer, "        </{}>", kind)?;
                 }
@@ -149,6 +204,12 @@ impl XmlFormatter {
     }
 }
 
+/// `s` as a CDATA section. A `]]>` inside would end the section early, so
+/// the section is closed and reopened between its `]]` and `>`.
+fn cdata(s: &str) -> String {
+    format!("<![CDATA[{}]]>", s.replace("]]>", "]]]]><![CDATA[>"))
+}
+
 fn escape_xml(s: &str) -> String {
     s.replace('&', "&amp;")
         .replace('<', "&lt;")
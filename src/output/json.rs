@@ -1,16 +1,71 @@
-use crate::model::AnalysisResult;
-use crate::output::OutputFormatter;
+use crate::analysis::rank_key_files;
+use crate::model::{AnalysisResult, Issue, IssueKind, Symbol};
+use crate::output::{CRITICAL_MODULE_LIMIT, OutputFormatter, OutputOptions};
+use crate::snapshot::issue_id;
 use serde::Serialize;
+use std::collections::{BTreeMap, HashSet};
 use std::io::Write;
 use std::path::{Path, PathBuf};
 
 pub struct JsonOutput {
     project_root: Option<PathBuf>,
+    baseline: Option<HashSet<String>>,
+    options: OutputOptions,
+    /// Files listed in `key_files`; 0 leaves the field out
+    key_files: usize,
 }
 
 impl JsonOutput {
     pub fn new(project_root: Option<PathBuf>) -> Self {
-        Self { project_root }
+        Self {
+            project_root,
+            baseline: None,
+            options: OutputOptions::default(),
+            key_files: 0,
+        }
+    }
+
+    /// Leave out issues below `options.min_severity`.
+    pub fn with_options(mut self, options: OutputOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Add an `is_new` flag to each issue, relative to these baseline issue IDs.
+    pub fn with_baseline(mut self, issue_ids: HashSet<String>) -> Self {
+        self.baseline = Some(issue_ids);
+        self
+    }
+
+    /// List the `count` files most worth reading first under `key_files`.
+    pub fn with_key_files(mut self, count: usize) -> Self {
+        self.key_files = count;
+        self
+    }
+
+    fn is_new(&self, issue: &Issue) -> Option<bool> {
+        let baseline = self.baseline.as_ref()?;
+        let root = self.project_root.as_deref().unwrap_or(Path::new(""));
+        Some(!baseline.contains(&issue_id(issue, root)))
+    }
+
+    fn boundary<'a>(&self, issue: &'a Issue) -> Option<JsonBoundary<'a>> {
+        let IssueKind::BoundaryViolation { boundary_name } = &issue.kind else {
+            return None;
+        };
+        Some(JsonBoundary {
+            name: boundary_name,
+            occurrences: issue
+                .locations
+                .iter()
+                .map(|l| JsonBoundaryOccurrence {
+                    path: self.relative_path(&l.path),
+                    line: l.line,
+                    indicator: l.indicator.as_deref(),
+                    context: l.context.as_deref(),
+                })
+                .collect(),
+        })
     }
 
     fn relative_path(&self, path: &Path) -> String {
@@ -36,6 +91,27 @@ struct JsonResult<'a> {
     project_name: &'a str,
     modules: Vec<JsonModule<'a>>,
     issues: Vec<JsonIssue<'a>>,
+    generated_files: Vec<String>,
+    critical_modules: Vec<JsonCriticalModule>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    key_files: Vec<JsonKeyFile>,
+}
+
+#[derive(Serialize)]
+struct JsonCriticalModule {
+    path: String,
+    betweenness: f64,
+    fan_in: usize,
+    fan_out: usize,
+}
+
+#[derive(Serialize)]
+struct JsonKeyFile {
+    path: String,
+    score: f64,
+    fan_in: usize,
+    betweenness: f64,
+    exports: usize,
 }
 
 #[derive(Serialize)]
@@ -43,8 +119,13 @@ struct JsonModule<'a> {
     path: String,
     name: &'a str,
     lines: usize,
-    imports: &'a [String],
-    exports: &'a [String],
+    imports: &'a [Symbol],
+    exports: &'a [Symbol],
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    tags: BTreeMap<&'a str, &'a str>,
+    /// Framework role from a class decorator or a `role` tag
+    #[serde(skip_serializing_if = "Option::is_none")]
+    role: Option<&'a str>,
 }
 
 #[derive(Serialize)]
@@ -54,6 +135,25 @@ struct JsonIssue<'a> {
     message: &'a str,
     locations: Vec<JsonLocation<'a>>,
     suggestion: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    is_new: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    boundary: Option<JsonBoundary<'a>>,
+}
+
+/// The crossed boundary and the indicator each occurrence matched
+#[derive(Serialize)]
+struct JsonBoundary<'a> {
+    name: &'a str,
+    occurrences: Vec<JsonBoundaryOccurrence<'a>>,
+}
+
+#[derive(Serialize)]
+struct JsonBoundaryOccurrence<'a> {
+    path: String,
+    line: Option<usize>,
+    indicator: Option<&'a str>,
+    context: Option<&'a str>,
 }
 
 #[derive(Serialize)]
@@ -65,6 +165,7 @@ struct JsonLocation<'a> {
 
 impl OutputFormatter for JsonOutput {
     fn format<W: Write>(&self, result: &AnalysisResult, writer: &mut W) -> std::io::Result<()> {
+        let graph = &result.dependency_graph;
         let json_result = JsonResult {
             project_name: &result.project_name,
             modules: result
@@ -76,11 +177,16 @@ impl OutputFormatter for JsonOutput {
                     lines: m.lines,
                     imports: &m.imports,
                     exports: &m.exports,
+                    tags: m
+                        .tags
+                        .iter()
+                        .map(|(k, v)| (k.as_str(), v.as_str()))
+                        .collect(),
+                    role: m.role(),
                 })
                 .collect(),
             issues: result
-                .issues
-                .iter()
+                .issues_at_least(self.options.min_severity)
                 .map(|i| JsonIssue {
                     kind: format!("{:?}", i.kind),
                     severity: i.severity.to_string(),
@@ -95,6 +201,33 @@ impl OutputFormatter for JsonOutput {
                         })
                         .collect(),
                     suggestion: i.suggestion.as_deref(),
+                    is_new: self.is_new(i),
+                    boundary: self.boundary(i),
+                })
+                .collect(),
+            generated_files: result
+                .generated_files
+                .iter()
+                .map(|p| self.relative_path(p))
+                .collect(),
+            critical_modules: graph
+                .critical_modules(CRITICAL_MODULE_LIMIT)
+                .into_iter()
+                .map(|(path, betweenness)| JsonCriticalModule {
+                    path: self.relative_path(&path),
+                    betweenness,
+                    fan_in: graph.fan_in(&path),
+                    fan_out: graph.fan_out(&path),
+                })
+                .collect(),
+            key_files: rank_key_files(&result.modules, graph, self.key_files)
+                .into_iter()
+                .map(|file| JsonKeyFile {
+                    path: self.relative_path(&file.path),
+                    score: file.score,
+                    fan_in: file.fan_in,
+                    betweenness: file.betweenness,
+                    exports: file.exports,
                 })
                 .collect(),
         };
@@ -0,0 +1,94 @@
+//! A writer that stops at a byte limit, for reports that could otherwise
+//! grow without bound (`archmap ai` on a huge repository dumps every source).
+
+use std::io::{self, Write};
+
+/// Passes writes through until `limit` bytes have been written, then keeps
+/// nothing more. The write that crosses the limit is cut after its last
+/// complete line and fails with [`io::ErrorKind::WriteZero`], so a formatter
+/// stops instead of generating the rest; [`LimitedWriter::truncated`] tells
+/// that apart from a real write error.
+pub struct LimitedWriter<W> {
+    inner: W,
+    limit: u64,
+    written: u64,
+    truncated: bool,
+}
+
+impl<W: Write> LimitedWriter<W> {
+    pub fn new(inner: W, limit: u64) -> Self {
+        Self {
+            inner,
+            limit,
+            written: 0,
+            truncated: false,
+        }
+    }
+
+    /// Whether output was cut off at the limit
+    pub fn truncated(&self) -> bool {
+        self.truncated
+    }
+
+    /// Bytes passed through to the inner writer
+    pub fn written(&self) -> u64 {
+        self.written
+    }
+
+    pub fn into_inner(self) -> W {
+        self.inner
+    }
+}
+
+impl<W: Write> Write for LimitedWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if !self.truncated {
+            let room = (self.limit - self.written) as usize;
+            if buf.len() <= room {
+                self.inner.write_all(buf)?;
+                self.written += buf.len() as u64;
+                return Ok(buf.len());
+            }
+            // Keep whole lines, which also never splits a UTF-8 character
+            let cut = buf[..room]
+                .iter()
+                .rposition(|&b| b == b'\n')
+                .map_or(0, |i| i + 1);
+            self.inner.write_all(&buf[..cut])?;
+            self.written += cut as u64;
+            self.truncated = true;
+        }
+        Err(io::Error::new(
+            io::ErrorKind::WriteZero,
+            format!("output limit of {} bytes reached", self.limit),
+        ))
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cuts_at_the_last_line_within_the_limit() {
+        let mut writer = LimitedWriter::new(Vec::new(), 12);
+        writer.write_all(b"one\n").unwrap();
+        assert!(!writer.truncated());
+
+        let err = writer.write_all(b"two\nthree\nfour\n").unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::WriteZero);
+        assert!(writer.truncated());
+        assert_eq!(writer.written(), 8);
+        // Nothing more gets through once the limit is reached
+        assert!(writer.write_all(b"x").is_err());
+        assert_eq!(writer.into_inner(), b"one\ntwo\n");
+
+        let mut exact = LimitedWriter::new(Vec::new(), 4);
+        exact.write_all(b"one\n").unwrap();
+        assert!(!exact.truncated());
+    }
+}
@@ -1,25 +1,134 @@
-use crate::model::{AnalysisResult, Issue, IssueKind, IssueSeverity};
-use crate::output::{OutputFormatter, relative_path};
+use crate::analysis::rank_key_files;
+use crate::model::{AnalysisResult, Issue, IssueKind, IssueSeverity, Location};
+use crate::output::{
+    CRITICAL_MODULE_LIMIT, OutputFormatter, OutputOptions, format_betweenness, relative_path,
+};
+use crate::snapshot::issue_id;
+use crate::style;
+use std::collections::{HashMap, HashSet};
 use std::io::Write;
 use std::path::{Path, PathBuf};
 
 pub struct MarkdownOutput {
-    pub min_severity: IssueSeverity,
+    pub options: OutputOptions,
     pub project_root: Option<PathBuf>,
+    /// Custom report template with `{{placeholder}}` markers
+    pub template: Option<String>,
+    /// Issue IDs from a baseline snapshot, used to flag new issues
+    pub baseline: Option<HashSet<String>>,
+    /// Source lines shown on each side of an issue's line; 0 shows no snippets
+    pub context_lines: usize,
+    /// File contents snippets are taken from
+    pub sources: HashMap<PathBuf, String>,
+    /// Files listed under "Key Files"; 0 leaves the section out
+    pub key_files: usize,
 }
 
 impl MarkdownOutput {
-    pub fn new(min_severity: IssueSeverity, project_root: Option<PathBuf>) -> Self {
+    pub fn new(options: OutputOptions, project_root: Option<PathBuf>) -> Self {
         Self {
-            min_severity,
+            options,
             project_root,
+            template: None,
+            baseline: None,
+            context_lines: 0,
+            sources: HashMap::new(),
+            key_files: 0,
         }
     }
 
+    pub fn with_template(mut self, template: String) -> Self {
+        self.template = Some(template);
+        self
+    }
+
+    /// Mark issues as new or pre-existing relative to these baseline issue IDs.
+    pub fn with_baseline(mut self, issue_ids: HashSet<String>) -> Self {
+        self.baseline = Some(issue_ids);
+        self
+    }
+
+    /// Show `lines` lines of source around each issue location, taken from
+    /// `sources`. Locations without a line show the top of the file instead.
+    pub fn with_context(mut self, lines: usize, sources: HashMap<PathBuf, String>) -> Self {
+        self.context_lines = lines;
+        self.sources = sources;
+        self
+    }
+
+    /// List the `count` files most worth reading first, after the critical modules.
+    pub fn with_key_files(mut self, count: usize) -> Self {
+        self.key_files = count;
+        self
+    }
+
     fn relative_path(&self, path: &Path) -> String {
         relative_path(path, self.project_root.as_ref())
     }
 
+    /// Whether an issue is absent from the baseline; `None` without a baseline.
+    fn is_new(&self, issue: &Issue) -> Option<bool> {
+        let baseline = self.baseline.as_ref()?;
+        let root = self.project_root.as_deref().unwrap_or(Path::new(""));
+        Some(!baseline.contains(&issue_id(issue, root)))
+    }
+
+    /// Highlight new issues and mute pre-existing ones when a baseline is set.
+    fn annotate(&self, issue: &Issue, text: &str) -> String {
+        match self.is_new(issue) {
+            Some(true) => format!("**NEW** {}", text),
+            Some(false) => format!("*{} (pre-existing)*", text),
+            None => text.to_string(),
+        }
+    }
+
+    /// The issue's severity marker and a space under `--severity-icons`,
+    /// otherwise nothing.
+    fn severity_prefix(issue: &Issue) -> String {
+        style::severity_icon(issue.severity)
+            .map(|icon| format!("{} ", icon))
+            .unwrap_or_default()
+    }
+
+    /// Write the source around `loc` as a fenced block nested under a list
+    /// item, the issue's line marked with `>`. Writes nothing without
+    /// `with_context` or the file's contents.
+    fn write_snippet<W: Write>(&self, writer: &mut W, loc: &Location) -> std::io::Result<()> {
+        if self.context_lines == 0 {
+            return Ok(());
+        }
+        let Some(source) = self.sources.get(&loc.path) else {
+            return Ok(());
+        };
+        let lines: Vec<&str> = source.lines().collect();
+        let (first, last) = match loc.line {
+            Some(line) => (
+                line.saturating_sub(self.context_lines).max(1),
+                line + self.context_lines,
+            ),
+            // Module-level issues show the file's header
+            None => (1, 2 * self.context_lines + 1),
+        };
+        let last = last.min(lines.len());
+        if first > last {
+            return Ok(());
+        }
+
+        let width = last.to_string().len();
+        writeln!(writer, "  ```{}", fence_language(&loc.path))?;
+        for number in first..=last {
+            let marker = if loc.line == Some(number) { '>' } else { ' ' };
+            writeln!(
+                writer,
+                "  {} {:>width$} | {}",
+                marker,
+                number,
+                lines[number - 1]
+            )?;
+        }
+        writeln!(writer, "  ```")
+    }
+
     /// Write a section with issues that show message and optional suggestion (no location).
     fn write_message_section<W: Write>(
         &self,
@@ -32,7 +141,12 @@ impl MarkdownOutput {
         }
         writeln!(writer, "{}\n", header)?;
         for issue in issues {
-            writeln!(writer, "- {}", issue.message)?;
+            writeln!(
+                writer,
+                "- {}{}",
+                Self::severity_prefix(issue),
+                self.annotate(issue, &issue.message)
+            )?;
             if let Some(ref suggestion) = issue.suggestion {
                 writeln!(writer, "  → {}", suggestion)?;
             }
@@ -56,10 +170,12 @@ impl MarkdownOutput {
             if let Some(loc) = issue.locations.first() {
                 writeln!(
                     writer,
-                    "- `{}` - {}",
+                    "- {}`{}` - {}",
+                    Self::severity_prefix(issue),
                     self.relative_path(&loc.path),
-                    issue.message
+                    self.annotate(issue, &issue.message)
                 )?;
+                self.write_snippet(writer, loc)?;
             }
             if include_suggestion {
                 if let Some(ref suggestion) = issue.suggestion {
@@ -69,14 +185,83 @@ impl MarkdownOutput {
         }
         writeln!(writer)
     }
-}
 
-impl OutputFormatter for MarkdownOutput {
-    fn format<W: Write>(&self, result: &AnalysisResult, writer: &mut W) -> std::io::Result<()> {
-        writeln!(writer, "# Architecture Analysis: {}\n", result.project_name)?;
+    /// Fill a template's `{{placeholder}}` markers. Unknown placeholders are left as-is.
+    fn render_template(&self, template: &str, result: &AnalysisResult) -> std::io::Result<String> {
+        let mut module_graph = Vec::new();
+        self.write_module_graph(&mut module_graph, result)?;
 
-        // Module Graph
-        writeln!(writer, "## Module Graph\n")?;
+        // Betweenness is the costly part of a report; skip it unless asked for
+        let mut critical_modules = Vec::new();
+        if template.contains("{{critical_modules}}") {
+            self.write_critical_modules(&mut critical_modules, result)?;
+        }
+
+        let mut issues = Vec::new();
+        self.write_issues(&mut issues, result)?;
+
+        let issue_count = result.issues_at_least(self.options.min_severity).count();
+
+        let values = [
+            ("project_name", result.project_name.clone()),
+            ("module_count", style::count(result.modules.len())),
+            (
+                "generated_count",
+                style::count(result.generated_files.len()),
+            ),
+            ("issue_count", style::count(issue_count)),
+            (
+                "health_score",
+                health_score(result.issues_at_least(self.options.min_severity)).to_string(),
+            ),
+            (
+                "module_graph",
+                String::from_utf8_lossy(&module_graph).trim().to_string(),
+            ),
+            (
+                "critical_modules",
+                String::from_utf8_lossy(&critical_modules)
+                    .trim()
+                    .to_string(),
+            ),
+            (
+                "issues",
+                String::from_utf8_lossy(&issues).trim().to_string(),
+            ),
+            ("issue_table", self.issue_table(result)),
+        ];
+
+        Ok(fill_placeholders(template, &values))
+    }
+
+    /// Render issues at or above the severity floor as a markdown table.
+    fn issue_table(&self, result: &AnalysisResult) -> String {
+        let mut table = String::from("| Severity | Kind | Location | Message |\n");
+        table.push_str("|----------|------|----------|---------|\n");
+
+        for issue in result.issues_at_least(self.options.min_severity) {
+            let location = issue
+                .locations
+                .first()
+                .map(|loc| format!("`{}`", self.relative_path(&loc.path)))
+                .unwrap_or_default();
+            table.push_str(&format!(
+                "| {} | {} | {} | {} |\n",
+                issue.severity,
+                issue.kind.name(),
+                location,
+                self.annotate(issue, &issue.message.replace('|', "\\|"))
+            ));
+        }
+
+        table.trim_end().to_string()
+    }
+
+    fn write_module_graph<W: Write>(
+        &self,
+        writer: &mut W,
+        result: &AnalysisResult,
+    ) -> std::io::Result<()> {
         for module in &result.modules {
             let imports: Vec<_> = module
                 .imports
@@ -100,13 +285,61 @@ impl OutputFormatter for MarkdownOutput {
                 )?;
             }
         }
+        Ok(())
+    }
+
+    /// Rank modules by betweenness centrality: the ones most shortest
+    /// dependency paths run through. Writes nothing when no module is a waypoint.
+    fn write_critical_modules<W: Write>(
+        &self,
+        writer: &mut W,
+        result: &AnalysisResult,
+    ) -> std::io::Result<()> {
+        let graph = &result.dependency_graph;
+        for (path, betweenness) in graph.critical_modules(CRITICAL_MODULE_LIMIT) {
+            writeln!(
+                writer,
+                "- `{}` - betweenness {} (fan-in {}, fan-out {})",
+                self.relative_path(&path),
+                format_betweenness(betweenness),
+                graph.fan_in(&path),
+                graph.fan_out(&path)
+            )?;
+        }
+        Ok(())
+    }
+
+    fn write_key_files<W: Write>(
+        &self,
+        writer: &mut W,
+        result: &AnalysisResult,
+    ) -> std::io::Result<()> {
+        let ranked = rank_key_files(&result.modules, &result.dependency_graph, self.key_files);
+        if ranked.is_empty() {
+            return Ok(());
+        }
+        writeln!(writer, "\n## Key Files to Understand First\n")?;
+        for (i, file) in ranked.iter().enumerate() {
+            writeln!(
+                writer,
+                "{}. `{}` - {} dependents, betweenness {}, {} exports",
+                i + 1,
+                self.relative_path(&file.path),
+                file.fan_in,
+                format_betweenness(file.betweenness),
+                file.exports
+            )?;
+        }
+        Ok(())
+    }
 
+    fn write_issues<W: Write>(
+        &self,
+        writer: &mut W,
+        result: &AnalysisResult,
+    ) -> std::io::Result<()> {
         // Filter and group issues
-        let filtered_issues: Vec<_> = result
-            .issues
-            .iter()
-            .filter(|i| i.severity >= self.min_severity)
-            .collect();
+        let filtered_issues: Vec<_> = result.issues_at_least(self.options.min_severity).collect();
 
         if filtered_issues.is_empty() {
             writeln!(writer, "\n## No Issues Found\n")?;
@@ -116,6 +349,19 @@ impl OutputFormatter for MarkdownOutput {
 
         writeln!(writer, "\n## Issues Found\n")?;
 
+        if self.baseline.is_some() {
+            let new_count = filtered_issues
+                .iter()
+                .filter(|i| self.is_new(i) == Some(true))
+                .count();
+            writeln!(
+                writer,
+                "*{} new, {} pre-existing since baseline*\n",
+                new_count,
+                filtered_issues.len() - new_count
+            )?;
+        }
+
         // Circular Dependencies (Error severity) - message only
         let circular: Vec<_> = filtered_issues
             .iter()
@@ -147,11 +393,16 @@ impl OutputFormatter for MarkdownOutput {
             writeln!(writer, "### 🟡 Boundary Violations\n")?;
             for issue in boundary_violations {
                 if let IssueKind::BoundaryViolation { ref boundary_name } = issue.kind {
-                    writeln!(
-                        writer,
+                    let header = format!(
                         "**{}** crossed in {} locations:",
                         boundary_name,
                         issue.locations.len()
+                    );
+                    writeln!(
+                        writer,
+                        "{}{}",
+                        Self::severity_prefix(issue),
+                        self.annotate(issue, &header)
                     )?;
 
                     for loc in issue.locations.iter().take(5) {
@@ -169,6 +420,7 @@ impl OutputFormatter for MarkdownOutput {
                             line_info,
                             context
                         )?;
+                        self.write_snippet(writer, loc)?;
                     }
 
                     if issue.locations.len() > 5 {
@@ -208,10 +460,290 @@ impl OutputFormatter for MarkdownOutput {
             true,
         )?;
 
+        // Fat Shared Modules - location + message + suggestion
+        let fat_shared: Vec<_> = filtered_issues
+            .iter()
+            .filter(|i| matches!(i.kind, IssueKind::FatSharedModule { .. }))
+            .collect();
+        self.write_location_section(writer, "### 🟡 Fat Shared Modules", &fat_shared, true)?;
+
+        // Scattered Implementations - location (the definition) + message + suggestion
+        let scattered: Vec<_> = filtered_issues
+            .iter()
+            .filter(|i| matches!(i.kind, IssueKind::ScatteredImplementation { .. }))
+            .collect();
+        self.write_location_section(writer, "### 🔵 Scattered Implementations", &scattered, true)?;
+
+        // Dead Public Functions - location + message (the suggestion is the same for all)
+        let dead: Vec<_> = filtered_issues
+            .iter()
+            .filter(|i| matches!(i.kind, IssueKind::DeadPublicFunction { .. }))
+            .collect();
+        self.write_location_section(writer, "### 🔵 Dead Public Functions", &dead, false)?;
+
+        // Too Many Imports - location + message (the suggestion is the same for all)
+        let imports: Vec<_> = filtered_issues
+            .iter()
+            .filter(|i| matches!(i.kind, IssueKind::TooManyImports { .. }))
+            .collect();
+        self.write_location_section(writer, "### 🟡 Too Many Imports", &imports, false)?;
+
+        // God Packages - largest module + message + suggestion, largest package first
+        let packages: Vec<_> = filtered_issues
+            .iter()
+            .filter(|i| matches!(i.kind, IssueKind::GodPackage { .. }))
+            .collect();
+        self.write_location_section(writer, "### 🟡 God Packages", &packages, true)?;
+
+        // Empty Tests - location + message (the suggestion is the same for all)
+        let empty_tests: Vec<_> = filtered_issues
+            .iter()
+            .filter(|i| matches!(i.kind, IssueKind::EmptyTest))
+            .collect();
+        self.write_location_section(writer, "### 🔵 Empty Tests", &empty_tests, false)?;
+
+        // Role Violations - location + message + suggestion
+        let roles: Vec<_> = filtered_issues
+            .iter()
+            .filter(|i| matches!(i.kind, IssueKind::RoleViolation { .. }))
+            .collect();
+        self.write_location_section(writer, "### 🟡 Role Violations", &roles, true)?;
+
         Ok(())
     }
 }
 
+impl OutputFormatter for MarkdownOutput {
+    fn format<W: Write>(&self, result: &AnalysisResult, writer: &mut W) -> std::io::Result<()> {
+        if let Some(ref template) = self.template {
+            let rendered = self.render_template(template, result)?;
+            return writeln!(writer, "{}", rendered.trim_end());
+        }
+
+        writeln!(writer, "# Architecture Analysis: {}\n", result.project_name)?;
+
+        if !result.generated_files.is_empty() {
+            writeln!(
+                writer,
+                "*{} generated file(s) detected*\n",
+                style::count(result.generated_files.len())
+            )?;
+        }
+
+        writeln!(writer, "## Module Graph\n")?;
+        self.write_module_graph(writer, result)?;
+
+        let mut critical_modules = Vec::new();
+        self.write_critical_modules(&mut critical_modules, result)?;
+        if !critical_modules.is_empty() {
+            writeln!(writer, "\n## Critical Modules\n")?;
+            writer.write_all(&critical_modules)?;
+        }
+        self.write_key_files(writer, result)?;
+
+        self.write_issues(writer, result)
+    }
+}
+
+/// Simple 0-100 health score: each issue deducts points by severity
+/// (error 10, warn 3, info 1).
+/// Replace each `{{key}}` in `template` with its value in one pass, so text
+/// a value brings in is never expanded itself. Unknown keys are kept.
+fn fill_placeholders(template: &str, values: &[(&str, String)]) -> String {
+    let mut output = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find("{{") {
+        output.push_str(&rest[..start]);
+        let marker = &rest[start..];
+        let value = marker.find("}}").and_then(|end| {
+            let key = &marker[2..end];
+            let (_, value) = values.iter().find(|(k, _)| *k == key)?;
+            Some((value, end + 2))
+        });
+        match value {
+            Some((value, len)) => {
+                output.push_str(value);
+                rest = &marker[len..];
+            }
+            None => {
+                output.push_str("{{");
+                rest = &marker[2..];
+            }
+        }
+    }
+    output.push_str(rest);
+    output
+}
+
+fn health_score<'a>(issues: impl IntoIterator<Item = &'a Issue>) -> usize {
+    let penalty: usize = issues
+        .into_iter()
+        .map(|i| match i.severity {
+            IssueSeverity::Error => 10,
+            IssueSeverity::Warn => 3,
+            IssueSeverity::Info => 1,
+        })
+        .sum();
+    100usize.saturating_sub(penalty)
+}
+
+/// Info string for a fenced block of this file's source
+fn fence_language(path: &Path) -> &'static str {
+    match path.extension().and_then(|e| e.to_str()) {
+        Some("rs") => "rust",
+        Some("ts" | "tsx" | "mts" | "cts") => "typescript",
+        Some("js" | "jsx" | "mjs" | "cjs") => "javascript",
+        Some("py") => "python",
+        Some("scala" | "sc") => "scala",
+        _ => "",
+    }
+}
+
 fn truncate(s: &str, max_len: usize) -> &str {
     if s.len() <= max_len { s } else { &s[..max_len] }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::Module;
+
+    fn sample_result() -> AnalysisResult {
+        AnalysisResult::new(
+            "demo".to_string(),
+            vec![Module::new(PathBuf::from("/demo/src/lib.rs"))],
+            vec![Issue::high_coupling(PathBuf::from("/demo/src/lib.rs"), 7)],
+        )
+    }
+
+    #[test]
+    fn test_template_placeholders() {
+        let formatter = MarkdownOutput::new(OutputOptions::default(), Some(PathBuf::from("/demo")))
+            .with_template(
+            "# Report for {{project_name}}\nScore: {{health_score}}\n{{issue_table}}\n{{unknown}}"
+                .to_string(),
+        );
+
+        let mut buffer = Vec::new();
+        formatter.format(&sample_result(), &mut buffer).unwrap();
+        let output = String::from_utf8(buffer).unwrap();
+
+        assert!(output.starts_with("# Report for demo\n"));
+        assert!(output.contains("Score: 97"));
+        assert!(
+            output.contains("| warn | HighCoupling | `src/lib.rs` | Imported by 7 other modules |")
+        );
+        assert!(output.contains("{{unknown}}"));
+    }
+
+    #[test]
+    fn test_template_values_are_not_expanded_again() {
+        let mut result = sample_result();
+        result.project_name = "{{issue_count}} {{".to_string();
+        let formatter = MarkdownOutput::new(OutputOptions::default(), None)
+            .with_template("{{project_name}}: {{issue_count}}".to_string());
+
+        let mut buffer = Vec::new();
+        formatter.format(&result, &mut buffer).unwrap();
+        assert_eq!(
+            String::from_utf8(buffer).unwrap(),
+            "{{issue_count}} {{: 1\n"
+        );
+    }
+
+    #[test]
+    fn test_health_score_follows_min_severity() {
+        let formatter = MarkdownOutput::new(OutputOptions::new(IssueSeverity::Error), None)
+            .with_template("{{issue_count}} issues, score {{health_score}}".to_string());
+
+        let mut buffer = Vec::new();
+        formatter.format(&sample_result(), &mut buffer).unwrap();
+        assert_eq!(String::from_utf8(buffer).unwrap(), "0 issues, score 100\n");
+    }
+
+    #[test]
+    fn test_baseline_marks_new_issues() {
+        let mut result = sample_result();
+        result
+            .issues
+            .push(Issue::high_coupling(PathBuf::from("/demo/src/api.rs"), 9));
+
+        let root = Path::new("/demo");
+        let baseline = HashSet::from([issue_id(&result.issues[0], root)]);
+        let formatter = MarkdownOutput::new(OutputOptions::default(), Some(root.to_path_buf()))
+            .with_baseline(baseline);
+
+        let mut buffer = Vec::new();
+        formatter.format(&result, &mut buffer).unwrap();
+        let output = String::from_utf8(buffer).unwrap();
+
+        assert!(output.contains("*1 new, 1 pre-existing since baseline*"));
+        assert!(output.contains("- `src/api.rs` - **NEW** Imported by 9 other modules"));
+        assert!(output.contains("- `src/lib.rs` - *Imported by 7 other modules (pre-existing)*"));
+    }
+
+    #[test]
+    fn test_critical_modules_section() {
+        let mut app = Module::new(PathBuf::from("/demo/src/app.ts"));
+        app.imports.push("./core".into());
+        let mut core = Module::new(PathBuf::from("/demo/src/core.ts"));
+        core.imports.push("./db".into());
+        let db = Module::new(PathBuf::from("/demo/src/db.ts"));
+        let modules = vec![app, core, db];
+
+        let result = AnalysisResult::new("demo".to_string(), modules, Vec::new());
+        let formatter = MarkdownOutput::new(OutputOptions::default(), Some(PathBuf::from("/demo")));
+
+        let mut buffer = Vec::new();
+        formatter.format(&result, &mut buffer).unwrap();
+        let output = String::from_utf8(buffer).unwrap();
+
+        assert!(output.contains(
+            "## Critical Modules\n\n- `src/core.ts` - betweenness 0.50 (fan-in 1, fan-out 1)\n"
+        ));
+        assert!(!output.contains("`src/app.ts` - betweenness"));
+
+        // No waypoints, no section
+        let mut buffer = Vec::new();
+        formatter.format(&sample_result(), &mut buffer).unwrap();
+        assert!(
+            !String::from_utf8(buffer)
+                .unwrap()
+                .contains("Critical Modules")
+        );
+    }
+
+    #[test]
+    fn test_context_snippets_surround_the_issue_line() {
+        let source = (1..=10)
+            .map(|n| format!("line {}", n))
+            .collect::<Vec<_>>()
+            .join("\n");
+        let path = PathBuf::from("/demo/src/db.rs");
+        let mut issue = Issue::high_coupling(path.clone(), 7);
+        issue.locations[0].line = Some(9);
+        let mut result = sample_result();
+        result.issues = vec![issue];
+
+        let sources = HashMap::from([(path, source)]);
+        let formatter = MarkdownOutput::new(OutputOptions::default(), Some(PathBuf::from("/demo")))
+            .with_context(2, sources.clone());
+        let mut buffer = Vec::new();
+        formatter.format(&result, &mut buffer).unwrap();
+        let output = String::from_utf8(buffer).unwrap();
+        assert!(output.contains(
+            "- `src/db.rs` - Imported by 7 other modules\n  ```rust\n     7 | line 7\n     8 | line 8\n  >  9 | line 9\n    10 | line 10\n  ```\n"
+        ));
+
+        // A module-level location shows the top of the file
+        result.issues[0].locations[0].line = None;
+        let formatter = MarkdownOutput::new(OutputOptions::default(), Some(PathBuf::from("/demo")))
+            .with_context(1, sources);
+        let mut buffer = Vec::new();
+        formatter.format(&result, &mut buffer).unwrap();
+        let output = String::from_utf8(buffer).unwrap();
+        assert!(
+            output.contains("  ```rust\n    1 | line 1\n    2 | line 2\n    3 | line 3\n  ```\n")
+        );
+    }
+}
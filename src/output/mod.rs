@@ -1,19 +1,59 @@
 mod ai;
 mod json;
+mod limit;
 mod markdown;
 
-pub use ai::AiOutput;
+pub use ai::{AiOutput, FileSources, SourceProvider, templates};
 pub use json::JsonOutput;
+pub use limit::LimitedWriter;
 pub use markdown::MarkdownOutput;
 
-use crate::model::AnalysisResult;
+use crate::model::{AnalysisResult, IssueSeverity};
 use std::io::Write;
 use std::path::{Path, PathBuf};
 
+/// How many modules the "critical modules" ranking shows
+pub const CRITICAL_MODULE_LIMIT: usize = 5;
+
+/// Settings every report format honors the same way
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OutputOptions {
+    /// Issues below this severity are left out of the report (not of exit codes)
+    pub min_severity: IssueSeverity,
+}
+
+impl OutputOptions {
+    pub fn new(min_severity: IssueSeverity) -> Self {
+        Self { min_severity }
+    }
+
+    /// Whether an issue of this severity is reported
+    pub fn shows(&self, severity: IssueSeverity) -> bool {
+        severity >= self.min_severity
+    }
+}
+
+impl Default for OutputOptions {
+    fn default() -> Self {
+        Self::new(IssueSeverity::Info)
+    }
+}
+
 pub trait OutputFormatter {
     fn format<W: Write>(&self, result: &AnalysisResult, writer: &mut W) -> std::io::Result<()>;
 }
 
+/// A betweenness score to two decimals, or to two significant digits when it
+/// is smaller than that, so a bridge module in a large graph doesn't read 0.00
+pub(crate) fn format_betweenness(score: f64) -> String {
+    if score == 0.0 || score >= 0.01 {
+        format!("{:.2}", score)
+    } else {
+        let decimals = 1 - score.log10().floor() as i32;
+        format!("{:.*}", decimals.max(2) as usize, score)
+    }
+}
+
 /// Convert an absolute path to a relative path based on project root.
 /// Returns the path as-is if no root is provided or if strip_prefix fails.
 pub fn relative_path(path: &Path, project_root: Option<&PathBuf>) -> String {
@@ -26,3 +66,127 @@ pub fn relative_path(path: &Path, project_root: Option<&PathBuf>) -> String {
         path.display().to_string()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::detect_boundary_violations_with_fs;
+    use crate::cli::AiOutputFormat;
+    use crate::config::Config;
+    use crate::fs::mock::MockFs;
+    use crate::model::{Issue, Module};
+
+    /// One issue of each severity, all in `src/`
+    fn mixed_result() -> AnalysisResult {
+        let lib = PathBuf::from("/demo/src/lib.rs");
+        let api = PathBuf::from("/demo/src/api.rs");
+        AnalysisResult::new(
+            "demo".to_string(),
+            vec![Module::new(lib.clone()), Module::new(api.clone())],
+            vec![
+                Issue::circular_dependency(vec![lib.clone(), api.clone(), lib.clone()]),
+                Issue::high_coupling(lib, 7),
+                Issue::dead_public_function(api, "unused".to_string(), 3),
+            ],
+        )
+    }
+
+    fn render(formatter: &impl OutputFormatter) -> String {
+        let mut buffer = Vec::new();
+        formatter.format(&mixed_result(), &mut buffer).unwrap();
+        String::from_utf8(buffer).unwrap()
+    }
+
+    #[test]
+    fn test_min_severity_applies_to_every_format() {
+        let root = Some(PathBuf::from("/demo"));
+        let warn = OutputOptions::new(IssueSeverity::Warn);
+
+        let markdown = render(&MarkdownOutput::new(warn, root.clone()));
+        assert!(markdown.contains("Imported by 7 other modules"));
+        assert!(!markdown.contains("`unused`"));
+
+        let json: serde_json::Value =
+            serde_json::from_str(&render(&JsonOutput::new(root.clone()).with_options(warn)))
+                .unwrap();
+        let severities: Vec<_> = json["issues"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|i| i["severity"].as_str().unwrap())
+            .collect();
+        assert_eq!(severities, ["error", "warn"]);
+
+        let ai_issues = |options: OutputOptions| -> u64 {
+            let ai = AiOutput::new(root.clone())
+                .with_format(AiOutputFormat::Json)
+                .with_options(options);
+            let json: serde_json::Value = serde_json::from_str(&render(&ai)).unwrap();
+            json["layout"]["directories"][0]["issues"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|i| i["count"].as_u64().unwrap())
+                .sum()
+        };
+        assert_eq!(ai_issues(OutputOptions::default()), 3);
+        assert_eq!(ai_issues(warn), 2);
+        assert_eq!(ai_issues(OutputOptions::new(IssueSeverity::Error)), 1);
+    }
+
+    #[test]
+    fn test_json_boundary_occurrences_name_the_matched_indicator() {
+        let fs = MockFs::with_files([
+            ("/demo/src/users.rs", "fn load() { sqlx::query(\"x\"); }"),
+            ("/demo/src/orders.rs", "use sea_orm::EntityTrait;"),
+            ("/demo/src/audit.rs", "use diesel::prelude::*;"),
+        ]);
+        let modules: Vec<Module> = ["users.rs", "orders.rs", "audit.rs"]
+            .iter()
+            .map(|name| Module::new(PathBuf::from("/demo/src").join(name)))
+            .collect();
+        let issues = detect_boundary_violations_with_fs(&modules, &Config::default(), &fs);
+        let result = AnalysisResult::new("demo".to_string(), modules, issues);
+
+        let mut buffer = Vec::new();
+        JsonOutput::new(Some(PathBuf::from("/demo")))
+            .format(&result, &mut buffer)
+            .unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&buffer).unwrap();
+        let boundary = &json["issues"][0]["boundary"];
+        assert_eq!(boundary["name"], "Persistence");
+        let occurrences: Vec<_> = boundary["occurrences"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|o| {
+                (
+                    o["path"].as_str().unwrap(),
+                    o["line"].as_u64().unwrap(),
+                    o["indicator"].as_str().unwrap(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            occurrences,
+            [
+                ("src/audit.rs", 1, "diesel::"),
+                ("src/orders.rs", 1, "sea_orm::"),
+                ("src/users.rs", 1, "sqlx::"),
+            ]
+        );
+        assert_eq!(
+            boundary["occurrences"][2]["context"],
+            "fn load() { sqlx::query(\"x\"); }"
+        );
+    }
+
+    #[test]
+    fn test_small_betweenness_keeps_significant_digits() {
+        assert_eq!(format_betweenness(0.0), "0.00");
+        assert_eq!(format_betweenness(0.5), "0.50");
+        assert_eq!(format_betweenness(0.01), "0.01");
+        assert_eq!(format_betweenness(0.0031), "0.0031");
+        assert_eq!(format_betweenness(0.000047), "0.000047");
+    }
+}
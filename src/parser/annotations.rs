@@ -0,0 +1,76 @@
+//! Module tags from `@archmap:` annotation comments, e.g.
+//! `// @archmap: layer=domain, owner=payments`.
+//!
+//! Scanning is plain text rather than tree-sitter so one implementation covers
+//! every language's comment syntax.
+
+use std::collections::HashMap;
+
+/// Marker that starts an annotation inside a comment
+const MARKER: &str = "@archmap:";
+
+/// Comment leaders recognized before the marker, longest first
+const COMMENT_LEADERS: &[&str] = &["///", "//!", "//", "/*", "#", "*"];
+
+/// Collect `key=value` tags from every annotation comment in a file.
+/// Later annotations override earlier ones for the same key; entries
+/// without `=` are ignored.
+pub fn parse_tags(source: &str) -> HashMap<String, String> {
+    let mut tags = HashMap::new();
+
+    for line in source.lines() {
+        let line = line.trim_start();
+        let Some(comment) = COMMENT_LEADERS
+            .iter()
+            .find_map(|leader| line.strip_prefix(leader))
+        else {
+            continue;
+        };
+        let Some(body) = comment.trim_start().strip_prefix(MARKER) else {
+            continue;
+        };
+
+        let body = body.trim_end().trim_end_matches("*/");
+        for entry in body.split(',') {
+            if let Some((key, value)) = entry.split_once('=') {
+                let key = key.trim();
+                if !key.is_empty() {
+                    tags.insert(key.to_string(), value.trim().to_string());
+                }
+            }
+        }
+    }
+
+    tags
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parses_annotations_in_each_comment_style() {
+        let source = "\
+//! Payment processing
+// @archmap: layer=domain, owner=payments
+use std::fmt;
+/* @archmap: stability = experimental */
+";
+        let tags = parse_tags(source);
+        assert_eq!(tags.len(), 3);
+        assert_eq!(tags["layer"], "domain");
+        assert_eq!(tags["owner"], "payments");
+        assert_eq!(tags["stability"], "experimental");
+
+        let python = parse_tags("# @archmap: layer=infra\nimport os\n");
+        assert_eq!(python["layer"], "infra");
+    }
+
+    #[test]
+    fn test_ignores_markers_outside_comments() {
+        let source = "let s = \"@archmap: layer=domain\";\n// @archmap: bare, owner=\n";
+        let tags = parse_tags(source);
+        assert_eq!(tags.len(), 1);
+        assert_eq!(tags["owner"], "");
+    }
+}
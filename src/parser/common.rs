@@ -1,4 +1,47 @@
-use tree_sitter::Node;
+use crate::model::Span;
+use std::cell::Cell;
+use std::time::Instant;
+use tree_sitter::{Node, ParseOptions, ParseState, Parser, Tree};
+
+thread_local! {
+    /// When tree-sitter parses on this thread give up, set by [`with_deadline`]
+    static DEADLINE: Cell<Option<Instant>> = const { Cell::new(None) };
+}
+
+/// Restores the previous deadline, even when the parse panics
+struct DeadlineGuard(Option<Instant>);
+
+impl Drop for DeadlineGuard {
+    fn drop(&mut self) {
+        DEADLINE.set(self.0);
+    }
+}
+
+/// Run `parse` with every [`parse_tree`] on this thread cancelled once
+/// `deadline` passes.
+pub fn with_deadline<R>(deadline: Option<Instant>, parse: impl FnOnce() -> R) -> R {
+    let _guard = DeadlineGuard(DEADLINE.replace(deadline));
+    parse()
+}
+
+/// Parse `source` with a tree-sitter parser. `None` when it fails or runs
+/// past the deadline set by [`with_deadline`].
+pub fn parse_tree(parser: &mut Parser, source: &str) -> Option<Tree> {
+    let deadline = DEADLINE.get();
+    let bytes = source.as_bytes();
+    let mut past_deadline = |_: &ParseState| deadline.is_some_and(|d| Instant::now() >= d);
+    let options = ParseOptions::new().progress_callback(&mut past_deadline);
+    let tree = parser.parse_with_options(
+        &mut |offset, _| bytes.get(offset..).unwrap_or_default(),
+        None,
+        Some(options),
+    );
+    if tree.is_none() {
+        // A cancelled parse would otherwise resume on the next file
+        parser.reset();
+    }
+    tree
+}
 
 /// Extract signature from a node up to the opening brace.
 /// Used by Rust and TypeScript parsers.
@@ -16,12 +59,32 @@ pub fn extract_signature_to_brace(node: &Node, source: &str) -> Option<String> {
     }
 }
 
-/// Extract full definition text from a node.
-/// Used by parsers for structs, enums, interfaces, etc.
-pub fn extract_full_definition(node: &Node, source: &str) -> Option<String> {
-    let start = node.start_byte();
-    let end = node.end_byte();
-    Some(source[start..end].to_string())
+/// First line of a definition without a trailing `{`, kept as the signature
+/// of structs, enums, interfaces, etc. in place of their full text, which
+/// [`definition_span`] locates instead.
+pub fn extract_definition_head(node: &Node, source: &str) -> Option<String> {
+    let text = &source[node.start_byte()..node.end_byte()];
+    let head = text.lines().next()?.trim_end();
+    Some(head.trim_end_matches('{').trim_end().to_string())
+}
+
+/// Byte range of a definition in the source
+pub fn definition_span(node: &Node) -> Span {
+    Span {
+        start: node.start_byte(),
+        end: node.end_byte(),
+    }
+}
+
+/// Bare name of a type reference, without path or generic arguments:
+/// `fmt::Display` → `Display`, `Repository<User>` → `Repository`.
+pub fn base_type_name(text: &str) -> String {
+    let without_generics = text.split('<').next().unwrap_or(text).trim();
+    without_generics
+        .rsplit(['.', ':'])
+        .next()
+        .unwrap_or(without_generics)
+        .to_string()
 }
 
 /// Macro to define a thread-local parser with a given language.
@@ -1,15 +1,23 @@
+mod annotations;
 mod common;
 mod python;
 mod rust;
+mod scala;
 mod typescript;
 
 use crate::model::Module;
+use serde::Serialize;
 use std::path::Path;
 use thiserror::Error;
 
-pub use common::{extract_full_definition, extract_signature_to_brace};
+pub use annotations::parse_tags;
+pub use common::{
+    base_type_name, definition_span, extract_definition_head, extract_signature_to_brace,
+    parse_tree, with_deadline,
+};
 pub use python::PythonParser;
 pub use rust::RustParser;
+pub use scala::ScalaParser;
 pub use typescript::TypeScriptParser;
 
 #[derive(Debug, Error)]
@@ -23,10 +31,19 @@ pub enum ParseError {
 }
 
 pub trait LanguageParser: Send + Sync {
+    /// Language name, as accepted by `--lang`
+    fn name(&self) -> &str;
     fn extensions(&self) -> &[&str];
     fn parse_module(&self, path: &Path, source: &str) -> Result<Module, ParseError>;
 }
 
+/// A registered language and the file extensions its parser handles
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct LanguageInfo {
+    pub name: String,
+    pub extensions: Vec<String>,
+}
+
 pub struct ParserRegistry {
     parsers: Vec<Box<dyn LanguageParser>>,
 }
@@ -38,6 +55,7 @@ impl ParserRegistry {
                 Box::new(RustParser::new()),
                 Box::new(TypeScriptParser::new()),
                 Box::new(PythonParser::new()),
+                Box::new(ScalaParser::new()),
             ],
         }
     }
@@ -46,13 +64,12 @@ impl ParserRegistry {
         let mut parsers: Vec<Box<dyn LanguageParser>> = Vec::new();
 
         for lang in languages {
-            match lang.to_lowercase().as_str() {
-                "rust" | "rs" => parsers.push(Box::new(RustParser::new())),
-                "typescript" | "ts" | "javascript" | "js" => {
-                    parsers.push(Box::new(TypeScriptParser::new()))
-                }
-                "python" | "py" => parsers.push(Box::new(PythonParser::new())),
-                _ => {}
+            let Some(parser) = parser_for(lang) else {
+                continue;
+            };
+            // `--lang ts,js` names one parser twice
+            if !parsers.iter().any(|p| p.name() == parser.name()) {
+                parsers.push(parser);
             }
         }
 
@@ -63,6 +80,11 @@ impl ParserRegistry {
         Self { parsers }
     }
 
+    /// Whether `--lang` accepts `name`, as a language or one of its aliases
+    pub fn knows_language(name: &str) -> bool {
+        parser_for(name).is_some()
+    }
+
     pub fn find_parser(&self, path: &Path) -> Option<&dyn LanguageParser> {
         let ext = path.extension()?.to_str()?;
         self.parsers
@@ -77,6 +99,28 @@ impl ParserRegistry {
             .flat_map(|p| p.extensions().iter().copied())
             .collect()
     }
+
+    /// Each registered parser's language and extensions, in registration order
+    pub fn languages(&self) -> Vec<LanguageInfo> {
+        self.parsers
+            .iter()
+            .map(|p| LanguageInfo {
+                name: p.name().to_string(),
+                extensions: p.extensions().iter().map(|e| e.to_string()).collect(),
+            })
+            .collect()
+    }
+}
+
+/// The parser for a `--lang` name or alias
+fn parser_for(name: &str) -> Option<Box<dyn LanguageParser>> {
+    match name.trim().to_lowercase().as_str() {
+        "rust" | "rs" => Some(Box::new(RustParser::new())),
+        "typescript" | "ts" | "javascript" | "js" => Some(Box::new(TypeScriptParser::new())),
+        "python" | "py" => Some(Box::new(PythonParser::new())),
+        "scala" => Some(Box::new(ScalaParser::new())),
+        _ => None,
+    }
 }
 
 impl Default for ParserRegistry {
@@ -1,11 +1,26 @@
 use crate::define_parser;
 use crate::model::{Definition, DefinitionKind, Module, Visibility};
-use crate::parser::{LanguageParser, ParseError};
+use crate::parser::{LanguageParser, ParseError, parse_tree};
 use std::path::Path;
 use tree_sitter::Node;
 
 define_parser!(PYTHON_PARSER, tree_sitter_python::LANGUAGE);
 
+/// Statements whose blocks are walked like the top level, so conditional
+/// imports (`try: import ujson as json`) and definitions are found too.
+/// Function and class bodies are not walked.
+const NESTED_BLOCKS: &[&str] = &[
+    "if_statement",
+    "elif_clause",
+    "else_clause",
+    "try_statement",
+    "except_clause",
+    "except_group_clause",
+    "finally_clause",
+    "with_statement",
+    "block",
+];
+
 pub struct PythonParser;
 
 impl PythonParser {
@@ -49,11 +64,91 @@ impl PythonParser {
                     line: node.start_position().row + 1,
                     visibility,
                     signature,
+                    span: None,
+                    compact_signature: None,
+                    decorators: Vec::new(),
                 });
             }
         }
     }
 
+    /// Names an import statement lists, without their `as` aliases. Inside
+    /// parentheses they may span several lines; tree-sitter lists each one as a
+    /// `name` field either way.
+    fn imported_names<'a>(node: &Node, source_bytes: &'a [u8]) -> Vec<&'a str> {
+        let mut cursor = node.walk();
+        node.children_by_field_name("name", &mut cursor)
+            .filter_map(|child| match child.kind() {
+                "aliased_import" => child.child_by_field_name("name"),
+                _ => Some(child),
+            })
+            .filter_map(|name| name.utf8_text(source_bytes).ok())
+            .collect()
+    }
+
+    /// `import a.b, c as d` records `a.b` and `c`.
+    fn handle_import(node: &Node, source_bytes: &[u8], module: &mut Module) {
+        for name in Self::imported_names(node, source_bytes) {
+            module.imports.push(name.into());
+        }
+    }
+
+    /// `from a.b import (c, d)` records `a.b`. A bare relative package
+    /// (`from . import c, d`) says nothing on its own, so each imported name
+    /// is recorded as a module instead: `.c` and `.d`.
+    fn handle_import_from(node: &Node, source_bytes: &[u8], module: &mut Module) {
+        let Some(Ok(from)) = node
+            .child_by_field_name("module_name")
+            .map(|n| n.utf8_text(source_bytes))
+        else {
+            return;
+        };
+        if !from.chars().all(|c| c == '.') {
+            module.imports.push(from.into());
+            return;
+        }
+        for name in Self::imported_names(node, source_bytes) {
+            module.imports.push(format!("{}{}", from, name).into());
+        }
+    }
+
+    /// Record the imports and definitions among `parent`'s children, descending
+    /// into the blocks of [`NESTED_BLOCKS`] statements.
+    fn visit_statements(parent: &Node, source_bytes: &[u8], source: &str, module: &mut Module) {
+        let mut cursor = parent.walk();
+        for node in parent.children(&mut cursor) {
+            match node.kind() {
+                "import_statement" => Self::handle_import(&node, source_bytes, module),
+                "import_from_statement" => Self::handle_import_from(&node, source_bytes, module),
+                "function_definition" => {
+                    Self::handle_function(&node, source_bytes, source, module);
+                }
+                "class_definition" => {
+                    Self::handle_class(&node, source_bytes, source, module);
+                }
+                "decorated_definition" => {
+                    // Handle decorated functions/classes
+                    let mut child_cursor = node.walk();
+                    for child in node.children(&mut child_cursor) {
+                        match child.kind() {
+                            "function_definition" => {
+                                Self::handle_function(&child, source_bytes, source, module);
+                            }
+                            "class_definition" => {
+                                Self::handle_class(&child, source_bytes, source, module);
+                            }
+                            _ => {}
+                        }
+                    }
+                }
+                kind if NESTED_BLOCKS.contains(&kind) => {
+                    Self::visit_statements(&node, source_bytes, source, module);
+                }
+                _ => {}
+            }
+        }
+    }
+
     /// Handle a class definition node, adding it to the module.
     fn handle_class(node: &Node, source_bytes: &[u8], source: &str, module: &mut Module) {
         if let Some(name_node) = node.child_by_field_name("name") {
@@ -67,6 +162,9 @@ impl PythonParser {
                     line: node.start_position().row + 1,
                     visibility,
                     signature,
+                    span: None,
+                    compact_signature: None,
+                    decorators: Vec::new(),
                 });
             }
         }
@@ -74,6 +172,10 @@ impl PythonParser {
 }
 
 impl LanguageParser for PythonParser {
+    fn name(&self) -> &str {
+        "python"
+    }
+
     fn extensions(&self) -> &[&str] {
         &["py"]
     }
@@ -83,60 +185,14 @@ impl LanguageParser for PythonParser {
         module.lines = source.lines().count();
 
         let tree = PYTHON_PARSER
-            .with(|parser| parser.borrow_mut().parse(source, None))
+            .with(|parser| parse_tree(&mut parser.borrow_mut(), source))
             .ok_or_else(|| ParseError::Parse("Failed to parse file".to_string()))?;
 
         let root = tree.root_node();
         let source_bytes = source.as_bytes();
 
         // Walk the tree to extract imports and definitions
-        let mut cursor = root.walk();
-
-        for node in root.children(&mut cursor) {
-            match node.kind() {
-                "import_statement" => {
-                    // import foo, bar
-                    let mut child_cursor = node.walk();
-                    for child in node.children(&mut child_cursor) {
-                        if child.kind() == "dotted_name" {
-                            if let Ok(name) = child.utf8_text(source_bytes) {
-                                module.imports.push(name.to_string());
-                            }
-                        }
-                    }
-                }
-                "import_from_statement" => {
-                    // from foo import bar
-                    if let Some(module_node) = node.child_by_field_name("module_name") {
-                        if let Ok(name) = module_node.utf8_text(source_bytes) {
-                            module.imports.push(name.to_string());
-                        }
-                    }
-                }
-                "function_definition" => {
-                    Self::handle_function(&node, source_bytes, source, &mut module);
-                }
-                "class_definition" => {
-                    Self::handle_class(&node, source_bytes, source, &mut module);
-                }
-                "decorated_definition" => {
-                    // Handle decorated functions/classes
-                    let mut child_cursor = node.walk();
-                    for child in node.children(&mut child_cursor) {
-                        match child.kind() {
-                            "function_definition" => {
-                                Self::handle_function(&child, source_bytes, source, &mut module);
-                            }
-                            "class_definition" => {
-                                Self::handle_class(&child, source_bytes, source, &mut module);
-                            }
-                            _ => {}
-                        }
-                    }
-                }
-                _ => {}
-            }
-        }
+        Self::visit_statements(&root, source_bytes, source, &mut module);
 
         Ok(module)
     }
@@ -147,3 +203,75 @@ impl Default for PythonParser {
         Self::new()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn imports(source: &str) -> Vec<crate::model::Symbol> {
+        PythonParser::new()
+            .parse_module(Path::new("app.py"), source)
+            .unwrap()
+            .imports
+    }
+
+    #[test]
+    fn test_conditional_and_grouped_imports() {
+        let source = r#"
+import os, numpy as np
+from .models import (
+    User,
+    Order as PurchaseOrder,
+)
+from . import (views,
+    forms)
+
+try:
+    import ujson as json
+except ImportError:
+    import json
+
+if TYPE_CHECKING:
+    from typing import Protocol
+else:
+    from typing_extensions import Protocol
+
+def handler():
+    import lazy_only_here
+"#;
+        assert_eq!(
+            imports(source),
+            [
+                "os",
+                "numpy",
+                ".models",
+                ".views",
+                ".forms",
+                "ujson",
+                "json",
+                "typing",
+                "typing_extensions",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_definitions_inside_fallbacks() {
+        let source = r#"
+try:
+    from functools import cache
+except ImportError:
+    def cache(func):
+        return func
+
+class Store:
+    def _load(self):
+        pass
+"#;
+        let module = PythonParser::new()
+            .parse_module(Path::new("store.py"), source)
+            .unwrap();
+        let names: Vec<_> = module.definitions.iter().map(|d| d.name.as_str()).collect();
+        assert_eq!(names, ["cache", "Store"]);
+    }
+}
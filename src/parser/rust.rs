@@ -1,7 +1,8 @@
 use crate::define_parser;
-use crate::model::{Definition, DefinitionKind, Module, Visibility};
+use crate::model::{Definition, DefinitionKind, Implementation, Module, Visibility};
 use crate::parser::{
-    LanguageParser, ParseError, extract_full_definition, extract_signature_to_brace,
+    LanguageParser, ParseError, base_type_name, definition_span, extract_definition_head,
+    extract_signature_to_brace, parse_tree,
 };
 use std::path::Path;
 use tree_sitter::Node;
@@ -41,7 +42,7 @@ impl RustParser {
         kind: DefinitionKind,
     ) -> Option<Definition> {
         let visibility = Self::get_visibility(node, source_bytes);
-        let signature = extract_full_definition(node, source);
+        let signature = extract_definition_head(node, source);
         let name_node = node.child_by_field_name("name")?;
         let name = name_node.utf8_text(source_bytes).ok()?;
 
@@ -51,11 +52,45 @@ impl RustParser {
             line: node.start_position().row + 1,
             visibility,
             signature,
+            span: Some(definition_span(node)),
+            compact_signature: Self::compact_type_signature(node, source),
+            decorators: Vec::new(),
         })
     }
+
+    /// Declaration of a struct or enum with its body reduced to field or variant
+    /// names, e.g. `pub struct Config<T> where T: Clone { name, value }`. Tuple
+    /// and unit structs have no names to list, so they get `None`.
+    fn compact_type_signature(node: &Node, source: &str) -> Option<String> {
+        let body = node.child_by_field_name("body")?;
+        let member_kind = match body.kind() {
+            "field_declaration_list" => "field_declaration",
+            "enum_variant_list" => "enum_variant",
+            _ => return None,
+        };
+
+        let header = source[node.start_byte()..body.start_byte()].trim_end();
+        let mut cursor = body.walk();
+        let names: Vec<&str> = body
+            .named_children(&mut cursor)
+            .filter(|child| child.kind() == member_kind)
+            .filter_map(|child| child.child_by_field_name("name"))
+            .filter_map(|name| name.utf8_text(source.as_bytes()).ok())
+            .collect();
+
+        if names.is_empty() {
+            Some(format!("{} {{}}", header))
+        } else {
+            Some(format!("{} {{ {} }}", header, names.join(", ")))
+        }
+    }
 }
 
 impl LanguageParser for RustParser {
+    fn name(&self) -> &str {
+        "rust"
+    }
+
     fn extensions(&self) -> &[&str] {
         &["rs"]
     }
@@ -65,7 +100,7 @@ impl LanguageParser for RustParser {
         module.lines = source.lines().count();
 
         let tree = RUST_PARSER
-            .with(|parser| parser.borrow_mut().parse(source, None))
+            .with(|parser| parse_tree(&mut parser.borrow_mut(), source))
             .ok_or_else(|| ParseError::Parse("Failed to parse file".to_string()))?;
 
         let root = tree.root_node();
@@ -83,7 +118,7 @@ impl LanguageParser for RustParser {
                             .trim_end_matches(';')
                             .trim()
                             .to_string();
-                        module.imports.push(import);
+                        module.imports.push(import.into());
                     }
                 }
                 "function_item" => {
@@ -98,6 +133,9 @@ impl LanguageParser for RustParser {
                                 line: node.start_position().row + 1,
                                 visibility,
                                 signature,
+                                span: None,
+                                compact_signature: None,
+                                decorators: Vec::new(),
                             });
                         }
                     }
@@ -133,6 +171,20 @@ impl LanguageParser for RustParser {
                     }
                 }
                 "impl_item" => {
+                    if let (Some(trait_node), Some(type_node)) = (
+                        node.child_by_field_name("trait"),
+                        node.child_by_field_name("type"),
+                    ) && let (Ok(trait_name), Ok(type_name)) = (
+                        trait_node.utf8_text(source_bytes),
+                        type_node.utf8_text(source_bytes),
+                    ) {
+                        module.implementations.push(Implementation {
+                            trait_name: base_type_name(trait_name),
+                            type_name: base_type_name(type_name),
+                            line: node.start_position().row + 1,
+                        });
+                    }
+
                     // For impl, try to get the type being implemented
                     let signature = extract_signature_to_brace(&node, source);
 
@@ -156,6 +208,9 @@ impl LanguageParser for RustParser {
                                 line: node.start_position().row + 1,
                                 visibility: Visibility::Private,
                                 signature,
+                                span: None,
+                                compact_signature: None,
+                                decorators: Vec::new(),
                             });
                         }
                     }
@@ -187,7 +242,7 @@ impl LanguageParser for RustParser {
                     if let Some(name_node) = node.child_by_field_name("name") {
                         if let Ok(name) = name_node.utf8_text(source_bytes) {
                             if visibility == Visibility::Public {
-                                module.exports.push(name.to_string());
+                                module.exports.push(name.into());
                             }
                         }
                     }
@@ -278,4 +333,113 @@ pub(crate) fn crate_fn() {}
             .unwrap();
         assert_eq!(crate_vis.visibility, Visibility::Crate);
     }
+
+    #[test]
+    fn test_records_trait_implementations() {
+        let parser = RustParser::new();
+        let source = r#"
+struct Store;
+impl Store {}
+impl fmt::Display for Store {}
+impl<T> Repository<T> for Store {}
+"#;
+        let module = parser.parse_module(Path::new("store.rs"), source).unwrap();
+
+        let traits: Vec<(&str, &str)> = module
+            .implementations
+            .iter()
+            .map(|i| (i.trait_name.as_str(), i.type_name.as_str()))
+            .collect();
+        assert_eq!(traits, [("Display", "Store"), ("Repository", "Store")]);
+        assert_eq!(module.implementations[0].line, 4);
+    }
+
+    #[test]
+    fn test_compact_type_signatures() {
+        let parser = RustParser::new();
+        let source = r#"
+pub struct Cache<K: Hash, V>
+where
+    V: Clone,
+{
+    /// Entries by key
+    entries: HashMap<K, V>,
+    pub capacity: usize,
+}
+
+pub enum Shape {
+    Circle { radius: f64 },
+    Square(f64),
+    Empty,
+}
+
+pub struct Meters(f64);
+"#;
+        let module = parser.parse_module(Path::new("shapes.rs"), source).unwrap();
+        let compact = |name: &str| {
+            let def = module.definitions.iter().find(|d| d.name == name).unwrap();
+            def.compact_signature.clone()
+        };
+
+        assert_eq!(
+            compact("Cache").as_deref(),
+            Some("pub struct Cache<K: Hash, V>\nwhere\n    V: Clone, { entries, capacity }")
+        );
+        assert_eq!(
+            compact("Shape").as_deref(),
+            Some("pub enum Shape { Circle, Square, Empty }")
+        );
+        assert_eq!(compact("Meters"), None);
+    }
+
+    #[test]
+    fn test_parse_gives_up_at_the_deadline() {
+        use crate::parser::with_deadline;
+        use std::time::Instant;
+
+        let parser = RustParser::new();
+        let path = Path::new("lib.rs");
+        let source = "pub fn a() {}\n".repeat(1000);
+        let late = with_deadline(Some(Instant::now()), || parser.parse_module(path, &source));
+        assert!(late.is_err());
+
+        // The cancelled parse doesn't leak into the next file
+        let module = parser.parse_module(path, "pub fn b() {}\n").unwrap();
+        assert_eq!(module.definitions.len(), 1);
+    }
+
+    #[test]
+    fn test_type_bodies_are_kept_as_spans() {
+        let fields: String = (0..50)
+            .map(|i| format!("    pub field_{}: Vec<String>,\n", i))
+            .collect();
+        let source: String = (0..200)
+            .map(|i| format!("pub struct Record{} {{\n{}}}\n\n", i, fields))
+            .collect();
+        let module = RustParser::new()
+            .parse_module(Path::new("records.rs"), &source)
+            .unwrap();
+
+        assert_eq!(module.definitions.len(), 200);
+        let kept: usize = module
+            .definitions
+            .iter()
+            .map(|d| d.signature.as_ref().map_or(0, String::len))
+            .sum();
+        // One short line per struct instead of a second copy of the file
+        assert!(
+            kept * 50 < source.len(),
+            "{} of {} bytes",
+            kept,
+            source.len()
+        );
+
+        let first = &module.definitions[0];
+        assert_eq!(first.signature.as_deref(), Some("pub struct Record0"));
+        let full = first.full_text(Some(&source)).unwrap();
+        assert!(full.starts_with("pub struct Record0 {\n    pub field_0"));
+        assert!(full.ends_with("pub field_49: Vec<String>,\n}"));
+        // Without the source, the one-line signature stands in
+        assert_eq!(first.full_text(None), Some("pub struct Record0"));
+    }
 }
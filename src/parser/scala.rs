@@ -0,0 +1,513 @@
+use crate::model::{Definition, DefinitionKind, Module, Symbol, Visibility};
+use crate::parser::{LanguageParser, ParseError};
+use std::path::Path;
+
+/// Modifiers that may precede a definition keyword without changing its kind
+const MODIFIERS: &[&str] = &[
+    "final",
+    "sealed",
+    "abstract",
+    "implicit",
+    "lazy",
+    "override",
+    "open",
+    "inline",
+    "transparent",
+    "opaque",
+    "infix",
+];
+
+/// Scala parser.
+///
+/// There is no tree-sitter grammar in the build, so this reads declarations
+/// lexically: comments and string literals are blanked out first, then braces
+/// are counted to tell top-level declarations from members. Top level means
+/// outside any braces but those of `package x { ... }` blocks, and no deeper
+/// than the first line of that scope, which also excludes members of
+/// braceless (Scala 3) bodies while allowing files that are indented as a
+/// whole.
+pub struct ScalaParser;
+
+impl ScalaParser {
+    pub fn new() -> Self {
+        Self
+    }
+
+    /// `private` and `protected` hide a definition; a qualified
+    /// `private[pkg]` opens it to a package, like `pub(crate)` in Rust.
+    fn get_visibility(qualified: bool) -> Visibility {
+        if qualified {
+            Visibility::Crate
+        } else {
+            Visibility::Private
+        }
+    }
+
+    /// Record the definition declared by a top-level line, if any.
+    fn handle_definition(line: &str, original: &str, number: usize, module: &mut Module) {
+        let mut rest = skip_annotations(line);
+        let mut visibility = Visibility::Public;
+        let mut case = false;
+        let kind = loop {
+            let word = leading_word(rest);
+            if word.is_empty() {
+                return;
+            }
+            rest = &rest[word.len()..];
+            match word {
+                "private" | "protected" => {
+                    let qualifier = rest.trim_start();
+                    let len = if qualifier.starts_with('[') {
+                        qualifier.find(']').map_or(0, |i| i + 1)
+                    } else {
+                        0
+                    };
+                    visibility = Self::get_visibility(len > 0);
+                    rest = &qualifier[len..];
+                }
+                "case" => case = true,
+                "class" if case => break DefinitionKind::Struct,
+                "class" => break DefinitionKind::Class,
+                // A case object has no fields; it is still a singleton
+                "object" => break DefinitionKind::Class,
+                "trait" => break DefinitionKind::Trait,
+                "enum" => break DefinitionKind::Enum,
+                "def" => break DefinitionKind::Function,
+                w if MODIFIERS.contains(&w) => {}
+                _ => return,
+            }
+            rest = rest.trim_start();
+        };
+
+        let rest = rest.trim_start();
+        let name: String = rest
+            .chars()
+            .take_while(|c| !c.is_whitespace() && !"([:{=".contains(*c))
+            .collect();
+        if name.is_empty() {
+            return;
+        }
+
+        module.add_definition(Definition {
+            name,
+            kind,
+            line: number,
+            visibility,
+            signature: Some(extract_signature(line, original)),
+            span: None,
+            compact_signature: None,
+            decorators: Vec::new(),
+        });
+    }
+}
+
+/// Source with comments and string literals replaced by spaces. Byte offsets
+/// and line breaks are kept, so lines of the result match lines of `source`.
+fn blank_comments_and_strings(source: &str) -> String {
+    let bytes = source.as_bytes();
+    let mut out = bytes.to_vec();
+    let blank = |out: &mut Vec<u8>, from: usize, to: usize| {
+        for b in &mut out[from..to] {
+            if *b != b'\n' {
+                *b = b' ';
+            }
+        }
+    };
+
+    let mut i = 0;
+    while i < bytes.len() {
+        let rest = &bytes[i..];
+        let end = if rest.starts_with(b"//") {
+            rest.iter()
+                .position(|&b| b == b'\n')
+                .map_or(bytes.len(), |p| i + p)
+        } else if rest.starts_with(b"/*") {
+            // Scala block comments nest
+            let mut depth = 0;
+            let mut j = i;
+            while j < bytes.len() {
+                if bytes[j..].starts_with(b"/*") {
+                    depth += 1;
+                    j += 2;
+                } else if bytes[j..].starts_with(b"*/") {
+                    depth -= 1;
+                    j += 2;
+                    if depth == 0 {
+                        break;
+                    }
+                } else {
+                    j += 1;
+                }
+            }
+            j
+        } else if rest.starts_with(b"\"\"\"") {
+            find(bytes, i + 3, b"\"\"\"").map_or(bytes.len(), |p| p + 3)
+        } else if rest[0] == b'"' {
+            let mut j = i + 1;
+            while j < bytes.len() && bytes[j] != b'"' && bytes[j] != b'\n' {
+                j += if bytes[j] == b'\\' { 2 } else { 1 };
+            }
+            (j + 1).min(bytes.len())
+        } else if rest.starts_with(b"'\\") {
+            find(bytes, i + 2, b"'").map_or(i + 1, |p| p + 1)
+        } else if rest.len() >= 3 && rest[0] == b'\'' && rest[2] == b'\'' {
+            // 'x'; a lone quote is an old-style symbol literal
+            i + 3
+        } else {
+            i += 1;
+            continue;
+        };
+        blank(&mut out, i, end);
+        i = end;
+    }
+
+    String::from_utf8(out).unwrap_or_else(|e| String::from_utf8_lossy(e.as_bytes()).into_owned())
+}
+
+fn find(bytes: &[u8], from: usize, needle: &[u8]) -> Option<usize> {
+    bytes
+        .get(from..)?
+        .windows(needle.len())
+        .position(|w| w == needle)
+        .map(|p| from + p)
+}
+
+fn leading_word(text: &str) -> &str {
+    let end = text
+        .find(|c: char| !(c.is_alphanumeric() || c == '_'))
+        .unwrap_or(text.len());
+    &text[..end]
+}
+
+/// `@main def run()` declares `run`: drop leading annotations and their arguments.
+fn skip_annotations(mut line: &str) -> &str {
+    while let Some(rest) = line.strip_prefix('@') {
+        let after_name = &rest[leading_word(rest).len()..];
+        line = match after_name.strip_prefix('(') {
+            Some(args) => {
+                let mut depth = 1;
+                let end = args
+                    .char_indices()
+                    .find(|&(_, c)| {
+                        match c {
+                            '(' => depth += 1,
+                            ')' => depth -= 1,
+                            _ => {}
+                        }
+                        depth == 0
+                    })
+                    .map_or(args.len(), |(i, _)| i + 1);
+                &args[end..]
+            }
+            None => after_name,
+        }
+        .trim_start();
+    }
+    line
+}
+
+/// Declaration up to its body: before the `{`, or for a `def` before the `=`
+/// outside any parentheses. A trailing Scala 3 `:` opening an indented body
+/// is dropped too.
+fn extract_signature(line: &str, original: &str) -> String {
+    let bytes = line.as_bytes();
+    let mut depth = 0i32;
+    let mut end = line.len();
+    for (i, &b) in bytes.iter().enumerate() {
+        match b {
+            b'(' | b'[' => depth += 1,
+            b')' | b']' => depth -= 1,
+            b'{' => {
+                end = i;
+                break;
+            }
+            b'=' if depth == 0 => {
+                let prev = i.checked_sub(1).map(|p| bytes[p]);
+                let next = bytes.get(i + 1).copied();
+                let operator = matches!(prev, Some(b'=' | b'<' | b'>' | b'!'))
+                    || matches!(next, Some(b'>' | b'='));
+                if !operator {
+                    end = i;
+                    break;
+                }
+            }
+            _ => {}
+        }
+    }
+    let signature = original.get(..end).unwrap_or(original).trim();
+    signature
+        .strip_suffix(':')
+        .unwrap_or(signature)
+        .trim_end()
+        .to_string()
+}
+
+/// Expand the selectors of one import clause:
+/// - `foo.bar._` and `foo.bar.*` are kept as they are
+/// - `foo.{A, B => C, D => _}` records `foo.A` and `foo.B`; `D` is hidden
+fn import_paths(clause: &str, imports: &mut Vec<Symbol>) {
+    let clause: String = clause.split_whitespace().collect::<Vec<_>>().join(" ");
+    let Some(open) = clause.find('{') else {
+        let path = clause.replace(' ', "");
+        if !path.is_empty() {
+            imports.push(path.into());
+        }
+        return;
+    };
+
+    let prefix = clause[..open].trim().trim_end_matches('.').replace(' ', "");
+    let selectors = clause[open + 1..].trim_end().trim_end_matches('}');
+    for selector in selectors.split(',') {
+        let selector = selector.trim();
+        let (name, renamed) = match selector.split_once("=>") {
+            Some((name, to)) => (name.trim(), Some(to.trim())),
+            None => match selector.split_once(" as ") {
+                Some((name, to)) => (name.trim(), Some(to.trim())),
+                None => (selector, None),
+            },
+        };
+        if name.is_empty() || renamed == Some("_") {
+            continue;
+        }
+        imports.push(format!("{}.{}", prefix, name).into());
+    }
+}
+
+/// Split an import statement's text into its clauses: `a.b, c.{d, e}`
+/// has two. Commas inside braces separate selectors, not clauses.
+fn import_clauses(text: &str) -> Vec<&str> {
+    let mut clauses = Vec::new();
+    let mut depth = 0;
+    let mut start = 0;
+    for (i, c) in text.char_indices() {
+        match c {
+            '{' => depth += 1,
+            '}' => depth -= 1,
+            ',' if depth == 0 => {
+                clauses.push(&text[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    clauses.push(&text[start..]);
+    clauses
+}
+
+fn brace_balance(text: &str) -> i32 {
+    text.chars().fold(0, |depth, c| match c {
+        '{' => depth + 1,
+        '}' => depth - 1,
+        _ => depth,
+    })
+}
+
+impl LanguageParser for ScalaParser {
+    fn name(&self) -> &str {
+        "scala"
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["scala"]
+    }
+
+    fn parse_module(&self, path: &Path, source: &str) -> Result<Module, ParseError> {
+        let mut module = Module::new(path.to_path_buf());
+        module.lines = source.lines().count();
+
+        let code = blank_comments_and_strings(source);
+        let mut lines = code.lines().zip(source.lines()).enumerate();
+        let mut depth = 0;
+        // Column of the top-level scope: the file's, then one per package
+        // block. Each starts unknown and is taken from its first line.
+        let mut scopes: Vec<Option<usize>> = vec![None];
+
+        while let Some((index, (line, original))) = lines.next() {
+            let trimmed = line.trim_start();
+
+            // Imports count wherever they appear; they are dependencies even
+            // when scoped to a class or method.
+            if let Some(rest) = trimmed.strip_prefix("import ") {
+                let mut text = rest.to_string();
+                while brace_balance(&text) > 0 {
+                    let Some((_, (next, _))) = lines.next() else {
+                        break;
+                    };
+                    text.push(' ');
+                    text.push_str(next);
+                }
+                for clause in import_clauses(&text) {
+                    import_paths(clause, &mut module.imports);
+                }
+                continue;
+            }
+
+            let column = line.len() - trimmed.len();
+            let packages = scopes.len() as i32 - 1;
+            let scope = scopes.last_mut().expect("the file scope is never closed");
+            let top_level =
+                !trimmed.is_empty() && depth == packages && column <= *scope.get_or_insert(column);
+            let balance = brace_balance(line);
+            if top_level {
+                // A dedent closes a braceless `package x:` body
+                *scope = Some(column);
+                if trimmed.starts_with("package ") {
+                    if balance > 0 {
+                        scopes.push(None);
+                    } else if trimmed.trim_end().ends_with(':') {
+                        *scope = None;
+                    }
+                } else {
+                    let original = original.get(column..).unwrap_or(original);
+                    Self::handle_definition(trimmed, original, index + 1, &mut module);
+                }
+            }
+            depth = (depth + balance).max(0);
+            while depth < scopes.len() as i32 - 1 {
+                scopes.pop();
+            }
+        }
+
+        Ok(module)
+    }
+}
+
+impl Default for ScalaParser {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(source: &str) -> Module {
+        ScalaParser::new()
+            .parse_module(Path::new("Orders.scala"), source)
+            .unwrap()
+    }
+
+    #[test]
+    fn test_wildcard_grouped_and_renamed_imports() {
+        let source = r#"
+package com.shop.orders
+
+import scala.concurrent._
+import com.shop.db.{Connection, Pool => DbPool, Legacy => _}
+import cats.effect.IO, cats.syntax.all.*
+import com.shop.model.{
+  Order,
+  LineItem
+}
+
+object Orders {
+  import java.time.Instant
+}
+"#;
+        assert_eq!(
+            parse(source).imports,
+            [
+                "scala.concurrent._",
+                "com.shop.db.Connection",
+                "com.shop.db.Pool",
+                "cats.effect.IO",
+                "cats.syntax.all.*",
+                "com.shop.model.Order",
+                "com.shop.model.LineItem",
+                "java.time.Instant",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_top_level_definitions_and_visibility() {
+        let source = r#"
+/* class Commented { } */
+final case class Order(id: Long, note: String = "{ not a brace")
+
+sealed trait Status
+case object Paid extends Status
+
+private class Ledger {
+  def post(entry: Entry): Unit = ()
+  class Inner
+}
+
+private[shop] object Registry:
+  def lookup(id: Long): Option[Order] = None
+
+protected def audit(order: Order): Boolean = order.id > 0
+
+@main def run(): Unit = println("} done")
+"#;
+        let module = parse(source);
+        let defs: Vec<_> = module
+            .definitions
+            .iter()
+            .map(|d| (d.name.as_str(), d.kind.clone(), d.visibility))
+            .collect();
+        assert_eq!(
+            defs,
+            [
+                ("Order", DefinitionKind::Struct, Visibility::Public),
+                ("Status", DefinitionKind::Trait, Visibility::Public),
+                ("Paid", DefinitionKind::Class, Visibility::Public),
+                ("Ledger", DefinitionKind::Class, Visibility::Private),
+                ("Registry", DefinitionKind::Class, Visibility::Crate),
+                ("audit", DefinitionKind::Function, Visibility::Private),
+                ("run", DefinitionKind::Function, Visibility::Public),
+            ]
+        );
+        assert_eq!(module.exports, ["Order", "Status", "Paid", "run"]);
+        assert_eq!(module.definitions[0].line, 3);
+        assert_eq!(
+            module.definitions[5].signature.as_deref(),
+            Some("protected def audit(order: Order): Boolean")
+        );
+        assert_eq!(
+            module.definitions[4].signature.as_deref(),
+            Some("private[shop] object Registry")
+        );
+    }
+
+    #[test]
+    fn test_definitions_inside_package_blocks() {
+        let source = r#"
+package com.shop {
+  package orders {
+    case class Order(id: Long) {
+      def total: BigDecimal = 0
+    }
+  }
+
+  object Shop
+}
+
+package billing:
+  trait Invoice:
+    def amount: BigDecimal
+
+class Ledger
+"#;
+        let names: Vec<_> = parse(source)
+            .definitions
+            .iter()
+            .map(|d| d.name.clone())
+            .collect();
+        assert_eq!(names, ["Order", "Shop", "Invoice", "Ledger"]);
+    }
+
+    #[test]
+    fn test_indented_file() {
+        let source =
+            "  object Orders {\n    def place(): Unit = ()\n  }\n  def audit(): Boolean = true\n";
+        let module = parse(source);
+        let names: Vec<_> = module.definitions.iter().map(|d| d.name.as_str()).collect();
+        assert_eq!(names, ["Orders", "audit"]);
+        assert_eq!(
+            module.definitions[1].signature.as_deref(),
+            Some("def audit(): Boolean")
+        );
+    }
+}
@@ -1,7 +1,8 @@
 use crate::define_parser;
-use crate::model::{Definition, DefinitionKind, Module, Visibility};
+use crate::model::{Definition, DefinitionKind, Implementation, Module, Visibility};
 use crate::parser::{
-    LanguageParser, ParseError, extract_full_definition, extract_signature_to_brace,
+    LanguageParser, ParseError, base_type_name, definition_span, extract_definition_head,
+    extract_signature_to_brace, parse_tree,
 };
 use std::path::Path;
 use tree_sitter::Node;
@@ -18,6 +19,10 @@ impl TypeScriptParser {
 }
 
 impl LanguageParser for TypeScriptParser {
+    fn name(&self) -> &str {
+        "typescript"
+    }
+
     fn extensions(&self) -> &[&str] {
         &["ts", "tsx", "js", "jsx"]
     }
@@ -29,9 +34,9 @@ impl LanguageParser for TypeScriptParser {
         // Use TSX parser for .tsx files, TS parser for everything else
         let ext = path.extension().and_then(|e| e.to_str()).unwrap_or("");
         let tree = if ext == "tsx" {
-            TSX_PARSER.with(|parser| parser.borrow_mut().parse(source, None))
+            TSX_PARSER.with(|parser| parse_tree(&mut parser.borrow_mut(), source))
         } else {
-            TS_PARSER.with(|parser| parser.borrow_mut().parse(source, None))
+            TS_PARSER.with(|parser| parse_tree(&mut parser.borrow_mut(), source))
         }
         .ok_or_else(|| ParseError::Parse("Failed to parse file".to_string()))?;
 
@@ -47,15 +52,24 @@ impl LanguageParser for TypeScriptParser {
                     if let Ok(text) = node.utf8_text(source_bytes) {
                         let import = extract_import_path(text);
                         if !import.is_empty() {
-                            module.imports.push(import);
+                            module.imports.push(import.into());
                         }
                     }
                 }
                 "export_statement" => {
-                    // Handle export declarations - these are public
+                    // Handle export declarations - these are public. Decorators
+                    // written before `export` belong to the statement, not the class.
+                    let decorators = decorator_names(&node, source_bytes);
                     let mut child_cursor = node.walk();
                     for child in node.children(&mut child_cursor) {
-                        add_definition(&child, source_bytes, source, &mut module, true);
+                        add_definition(
+                            &child,
+                            source_bytes,
+                            source,
+                            &mut module,
+                            true,
+                            &decorators,
+                        );
                     }
                 }
                 "lexical_declaration" | "variable_declaration" => {
@@ -65,7 +79,7 @@ impl LanguageParser for TypeScriptParser {
                         if child.kind() == "variable_declarator" {
                             if let Some(name_node) = child.child_by_field_name("name") {
                                 if let Ok(name) = name_node.utf8_text(source_bytes) {
-                                    let signature = extract_full_definition(&node, source);
+                                    let signature = extract_definition_head(&node, source);
                                     // Private declarations don't export, so push directly
                                     module.definitions.push(Definition {
                                         name: name.to_string(),
@@ -73,6 +87,9 @@ impl LanguageParser for TypeScriptParser {
                                         line: node.start_position().row + 1,
                                         visibility: Visibility::Private,
                                         signature,
+                                        span: Some(definition_span(&node)),
+                                        compact_signature: None,
+                                        decorators: Vec::new(),
                                     });
                                 }
                             }
@@ -80,7 +97,7 @@ impl LanguageParser for TypeScriptParser {
                     }
                 }
                 // Non-exported declarations (function, class, interface, type) use shared helper
-                _ => add_definition(&node, source_bytes, source, &mut module, false),
+                _ => add_definition(&node, source_bytes, source, &mut module, false, &[]),
             }
         }
 
@@ -100,13 +117,15 @@ fn extract_import_path(import_text: &str) -> String {
 }
 
 /// Add a definition to the module if the node is a recognized declaration type.
-/// Handles function, class, interface, and type alias declarations.
+/// Handles function, class, interface, and type alias declarations. Classes
+/// get `outer_decorators` (those of an enclosing `export`) and their own.
 fn add_definition(
     node: &Node,
     source_bytes: &[u8],
     source: &str,
     module: &mut Module,
     is_exported: bool,
+    outer_decorators: &[String],
 ) {
     let visibility = if is_exported {
         Visibility::Public
@@ -124,25 +143,143 @@ fn add_definition(
 
     if let Some(name_node) = node.child_by_field_name("name") {
         if let Ok(name) = name_node.utf8_text(source_bytes) {
-            let signature = if use_signature_to_brace {
-                extract_signature_to_brace(node, source)
+            let (signature, span) = if use_signature_to_brace {
+                (extract_signature_to_brace(node, source), None)
             } else {
-                extract_full_definition(node, source)
+                (
+                    extract_definition_head(node, source),
+                    Some(definition_span(node)),
+                )
             };
 
+            let mut decorators = Vec::new();
+            if kind == DefinitionKind::Class {
+                add_implementations(node, source_bytes, name, module);
+                decorators.extend_from_slice(outer_decorators);
+                decorators.extend(decorator_names(node, source_bytes));
+            }
+
             module.add_definition(Definition {
                 name: name.to_string(),
                 kind,
                 line: node.start_position().row + 1,
                 visibility,
                 signature,
+                span,
+                compact_signature: None,
+                decorators,
             });
         }
     }
 }
 
+/// Names of the decorators directly on `node`: `Component` for
+/// `@Component({...})`, `Injectable` for `@core.Injectable()`.
+fn decorator_names(node: &Node, source_bytes: &[u8]) -> Vec<String> {
+    let mut cursor = node.walk();
+    node.children(&mut cursor)
+        .filter(|child| child.kind() == "decorator")
+        .filter_map(|decorator| decorator.utf8_text(source_bytes).ok())
+        .filter_map(|text| {
+            let expr = text.trim_start_matches('@');
+            let callee = expr.split('(').next().unwrap_or(expr).trim();
+            let name = callee.rsplit('.').next().unwrap_or(callee);
+            (!name.is_empty()).then(|| name.to_string())
+        })
+        .collect()
+}
+
+/// Record the interfaces listed in a class's `implements` clause.
+fn add_implementations(node: &Node, source_bytes: &[u8], class_name: &str, module: &mut Module) {
+    let mut cursor = node.walk();
+    for heritage in node.children(&mut cursor) {
+        if heritage.kind() != "class_heritage" {
+            continue;
+        }
+        let mut heritage_cursor = heritage.walk();
+        for clause in heritage.children(&mut heritage_cursor) {
+            if clause.kind() != "implements_clause" {
+                continue;
+            }
+            let mut clause_cursor = clause.walk();
+            for interface in clause.named_children(&mut clause_cursor) {
+                if let Ok(text) = interface.utf8_text(source_bytes) {
+                    module.implementations.push(Implementation {
+                        trait_name: base_type_name(text),
+                        type_name: class_name.to_string(),
+                        line: node.start_position().row + 1,
+                    });
+                }
+            }
+        }
+    }
+}
+
 impl Default for TypeScriptParser {
     fn default() -> Self {
         Self::new()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_records_implemented_interfaces() {
+        let source = "\
+interface Repository<T> {}
+export class UserStore extends Base implements Repository<User>, events.Emitter {}
+class Plain {}
+";
+        let module = TypeScriptParser::new()
+            .parse_module(Path::new("store.ts"), source)
+            .unwrap();
+
+        let interfaces: Vec<(&str, &str)> = module
+            .implementations
+            .iter()
+            .map(|i| (i.trait_name.as_str(), i.type_name.as_str()))
+            .collect();
+        assert_eq!(
+            interfaces,
+            [("Repository", "UserStore"), ("Emitter", "UserStore")]
+        );
+        assert_eq!(module.implementations[0].line, 2);
+    }
+
+    #[test]
+    fn test_records_class_decorators() {
+        let source = "\
+import { Component } from '@angular/core';
+@Component({ selector: 'app-users', template: '<ul></ul>' })
+export class UsersComponent {}
+export @nest.Controller('users') class UsersController {}
+@Injectable()
+class UsersService {}
+export class Plain {}
+";
+        let module = TypeScriptParser::new()
+            .parse_module(Path::new("users.ts"), source)
+            .unwrap();
+
+        let decorators: Vec<(&str, Vec<&str>)> = module
+            .definitions
+            .iter()
+            .map(|d| {
+                let names = d.decorators.iter().map(String::as_str).collect();
+                (d.name.as_str(), names)
+            })
+            .collect();
+        assert_eq!(
+            decorators,
+            [
+                ("UsersComponent", vec!["Component"]),
+                ("UsersController", vec!["Controller"]),
+                ("UsersService", vec!["Injectable"]),
+                ("Plain", vec![]),
+            ]
+        );
+        assert_eq!(module.role(), Some("component"));
+    }
+}
@@ -1,4 +1,6 @@
 use super::serialize::{IssueSnapshot, ModuleSnapshot, Snapshot};
+use crate::model::{IssueSeverity, Symbol};
+use crate::output::OutputOptions;
 use std::collections::{HashMap, HashSet};
 
 #[derive(Debug, Clone)]
@@ -36,10 +38,10 @@ pub struct ModuleChange {
     pub path: String,
     pub old_lines: usize,
     pub new_lines: usize,
-    pub imports_added: Vec<String>,
-    pub imports_removed: Vec<String>,
-    pub exports_added: Vec<String>,
-    pub exports_removed: Vec<String>,
+    pub imports_added: Vec<Symbol>,
+    pub imports_removed: Vec<Symbol>,
+    pub exports_added: Vec<Symbol>,
+    pub exports_removed: Vec<Symbol>,
 }
 
 #[derive(Debug, Clone, Default)]
@@ -53,6 +55,13 @@ pub struct MetricChanges {
     pub resolved_issue_count: usize,
 }
 
+impl SnapshotDiff {
+    /// Whether the architecture got worse: new issues or more dependency cycles
+    pub fn has_regressions(&self) -> bool {
+        !self.new_issues.is_empty() || self.metric_changes.cycle_count_delta > 0
+    }
+}
+
 pub fn compute_diff(baseline: &Snapshot, current: &Snapshot) -> SnapshotDiff {
     // Module comparison
     let baseline_paths: HashSet<&str> = baseline.modules.iter().map(|m| m.path.as_str()).collect();
@@ -87,31 +96,14 @@ pub fn compute_diff(baseline: &Snapshot, current: &Snapshot) -> SnapshotDiff {
             let curr = current_map.get(path)?;
 
             if base.content_hash != curr.content_hash {
-                let base_imports: HashSet<&String> = base.imports.iter().collect();
-                let curr_imports: HashSet<&String> = curr.imports.iter().collect();
-                let base_exports: HashSet<&String> = base.exports.iter().collect();
-                let curr_exports: HashSet<&String> = curr.exports.iter().collect();
-
                 Some(ModuleChange {
                     path: path.to_string(),
                     old_lines: base.lines,
                     new_lines: curr.lines,
-                    imports_added: curr_imports
-                        .difference(&base_imports)
-                        .map(|s| (*s).clone())
-                        .collect(),
-                    imports_removed: base_imports
-                        .difference(&curr_imports)
-                        .map(|s| (*s).clone())
-                        .collect(),
-                    exports_added: curr_exports
-                        .difference(&base_exports)
-                        .map(|s| (*s).clone())
-                        .collect(),
-                    exports_removed: base_exports
-                        .difference(&curr_exports)
-                        .map(|s| (*s).clone())
-                        .collect(),
+                    imports_added: missing_from(&curr.imports, &base.imports),
+                    imports_removed: missing_from(&base.imports, &curr.imports),
+                    exports_added: missing_from(&curr.exports, &base.exports),
+                    exports_removed: missing_from(&base.exports, &curr.exports),
                 })
             } else {
                 None
@@ -119,14 +111,15 @@ pub fn compute_diff(baseline: &Snapshot, current: &Snapshot) -> SnapshotDiff {
         })
         .collect();
 
-    // Dependency changes
-    let baseline_deps: HashSet<(String, String)> = flatten_dependencies(&baseline.dependencies);
-    let current_deps: HashSet<(String, String)> = flatten_dependencies(&current.dependencies);
+    // Dependency changes; only the edges that differ are copied
+    let baseline_deps = flatten_dependencies(&baseline.dependencies);
+    let current_deps = flatten_dependencies(&current.dependencies);
+    let owned = |(from, to): &(&str, &str)| (from.to_string(), to.to_string());
 
     let added_dependencies: Vec<(String, String)> =
-        current_deps.difference(&baseline_deps).cloned().collect();
+        current_deps.difference(&baseline_deps).map(owned).collect();
     let removed_dependencies: Vec<(String, String)> =
-        baseline_deps.difference(&current_deps).cloned().collect();
+        baseline_deps.difference(&current_deps).map(owned).collect();
 
     // Issue changes
     let baseline_issue_ids: HashSet<&str> = baseline
@@ -178,15 +171,68 @@ pub fn compute_diff(baseline: &Snapshot, current: &Snapshot) -> SnapshotDiff {
     }
 }
 
-fn flatten_dependencies(deps: &HashMap<String, Vec<String>>) -> HashSet<(String, String)> {
+fn flatten_dependencies(deps: &HashMap<String, Vec<String>>) -> HashSet<(&str, &str)> {
     deps.iter()
-        .flat_map(|(from, tos)| tos.iter().map(move |to| (from.clone(), to.clone())))
+        .flat_map(|(from, tos)| tos.iter().map(move |to| (from.as_str(), to.as_str())))
+        .collect()
+}
+
+/// Names in `names` that `other` lacks, in their order in `names`. Cloning a
+/// [`Symbol`] shares it rather than copying the string.
+fn missing_from(names: &[Symbol], other: &[Symbol]) -> Vec<Symbol> {
+    let other: HashSet<&str> = other.iter().map(Symbol::as_str).collect();
+    let mut seen = HashSet::new();
+    names
+        .iter()
+        .filter(|name| !other.contains(name.as_str()) && seen.insert(name.as_str()))
+        .cloned()
         .collect()
 }
 
 /// Format diff as markdown
-pub fn format_diff_markdown(diff: &SnapshotDiff) -> String {
+/// Why two snapshots may not compare cleanly: different schemas, or different
+/// archmap versions whose detectors could disagree. `None` when they match.
+pub fn version_mismatch(baseline: &Snapshot, current: &Snapshot) -> Option<String> {
+    let written_by = |snapshot: &Snapshot| {
+        if snapshot.archmap_version.is_empty() {
+            "an older archmap".to_string()
+        } else {
+            format!("archmap {}", snapshot.archmap_version)
+        }
+    };
+    if baseline.version != current.version {
+        Some(format!(
+            "Baseline snapshot uses schema {} and the current one schema {}; the diff may be incomplete",
+            baseline.version, current.version
+        ))
+    } else if baseline.archmap_version != current.archmap_version {
+        Some(format!(
+            "Baseline snapshot was written by {} and the current one by {}; detector changes between versions can show up as new or resolved issues",
+            written_by(baseline),
+            written_by(current)
+        ))
+    } else {
+        None
+    }
+}
+
+/// Issues at or above the severity floor; ones whose severity doesn't parse
+/// (e.g. from a newer archmap) are kept.
+fn reported<'a>(issues: &'a [IssueSnapshot], options: &OutputOptions) -> Vec<&'a IssueSnapshot> {
+    issues
+        .iter()
+        .filter(|i| {
+            i.severity
+                .parse::<IssueSeverity>()
+                .map_or(true, |severity| options.shows(severity))
+        })
+        .collect()
+}
+
+pub fn format_diff_markdown(diff: &SnapshotDiff, options: &OutputOptions) -> String {
     let mut output = String::new();
+    let new_issues = reported(&diff.new_issues, options);
+    let resolved_issues = reported(&diff.resolved_issues, options);
 
     output.push_str("# Architectural Diff\n\n");
 
@@ -229,9 +275,9 @@ pub fn format_diff_markdown(diff: &SnapshotDiff) -> String {
     ));
 
     // New Issues
-    if !diff.new_issues.is_empty() {
-        output.push_str(&format!("## New Issues ({})\n\n", diff.new_issues.len()));
-        for issue in &diff.new_issues {
+    if !new_issues.is_empty() {
+        output.push_str(&format!("## New Issues ({})\n\n", new_issues.len()));
+        for issue in &new_issues {
             output.push_str(&format!(
                 "- **{}** [{}]: {}\n",
                 issue.kind, issue.severity, issue.message
@@ -244,12 +290,12 @@ pub fn format_diff_markdown(diff: &SnapshotDiff) -> String {
     }
 
     // Resolved Issues
-    if !diff.resolved_issues.is_empty() {
+    if !resolved_issues.is_empty() {
         output.push_str(&format!(
             "## Resolved Issues ({})\n\n",
-            diff.resolved_issues.len()
+            resolved_issues.len()
         ));
-        for issue in &diff.resolved_issues {
+        for issue in &resolved_issues {
             output.push_str(&format!("- ~~**{}**: {}~~\n", issue.kind, issue.message));
         }
         output.push('\n');
@@ -324,7 +370,7 @@ pub fn format_diff_markdown(diff: &SnapshotDiff) -> String {
 }
 
 /// Format diff as JSON
-pub fn format_diff_json(diff: &SnapshotDiff) -> String {
+pub fn format_diff_json(diff: &SnapshotDiff, options: &OutputOptions) -> String {
     use serde_json::json;
 
     let output = json!({
@@ -358,7 +404,7 @@ pub fn format_diff_json(diff: &SnapshotDiff) -> String {
         "removed_dependencies": diff.removed_dependencies.iter().map(|(from, to)| {
             json!({"from": from, "to": to})
         }).collect::<Vec<_>>(),
-        "new_issues": diff.new_issues.iter().map(|i| {
+        "new_issues": reported(&diff.new_issues, options).iter().map(|i| {
             json!({
                 "kind": i.kind,
                 "severity": i.severity,
@@ -366,7 +412,7 @@ pub fn format_diff_json(diff: &SnapshotDiff) -> String {
                 "locations": i.locations
             })
         }).collect::<Vec<_>>(),
-        "resolved_issues": diff.resolved_issues.iter().map(|i| {
+        "resolved_issues": reported(&diff.resolved_issues, options).iter().map(|i| {
             json!({
                 "kind": i.kind,
                 "severity": i.severity,
@@ -381,10 +427,119 @@ pub fn format_diff_json(diff: &SnapshotDiff) -> String {
 
 fn format_delta(delta: i64) -> String {
     if delta > 0 {
-        format!("+{}", delta)
+        format!("+{}", crate::style::count(delta))
     } else if delta < 0 {
-        format!("{}", delta)
+        crate::style::count(delta)
     } else {
         "0".to_string()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn issue(kind: &str, severity: &str) -> IssueSnapshot {
+        IssueSnapshot {
+            kind: kind.to_string(),
+            severity: severity.to_string(),
+            message: format!("{} message", kind),
+            locations: vec!["src/lib.rs".to_string()],
+            issue_id: kind.to_string(),
+        }
+    }
+
+    fn snapshot(issues: Vec<IssueSnapshot>) -> Snapshot {
+        Snapshot {
+            version: 1,
+            archmap_version: String::new(),
+            created_at: String::new(),
+            project_name: "demo".to_string(),
+            modules: Vec::new(),
+            issues,
+            dependencies: HashMap::new(),
+            metrics: Default::default(),
+        }
+    }
+
+    #[test]
+    fn test_min_severity_trims_the_report_not_the_regression() {
+        let baseline = snapshot(vec![issue("GodObject", "warn")]);
+        let current = snapshot(vec![
+            issue("DeadPublicFunction", "info"),
+            issue("LowCohesion", "info"),
+        ]);
+        let diff = compute_diff(&baseline, &current);
+        let error = OutputOptions::new(IssueSeverity::Error);
+
+        let markdown = format_diff_markdown(&diff, &error);
+        assert!(!markdown.contains("New Issues"));
+        assert!(!markdown.contains("Resolved Issues"));
+        assert!(
+            format_diff_markdown(&diff, &OutputOptions::default()).contains("## New Issues (2)")
+        );
+
+        let json: serde_json::Value = serde_json::from_str(&format_diff_json(
+            &diff,
+            &OutputOptions::new(IssueSeverity::Warn),
+        ))
+        .unwrap();
+        assert_eq!(json["new_issues"].as_array().unwrap().len(), 0);
+        assert_eq!(json["resolved_issues"][0]["kind"], "GodObject");
+        assert_eq!(json["summary"]["new_issue_count"], 2);
+
+        assert!(diff.has_regressions());
+    }
+
+    #[test]
+    fn test_module_changes_list_names_in_order() {
+        let module = |hash: &str, imports: &[&str], exports: &[&str]| ModuleSnapshot {
+            path: "src/app.ts".to_string(),
+            name: "app".to_string(),
+            lines: 10,
+            imports: imports.iter().map(|&i| i.into()).collect(),
+            exports: exports.iter().map(|&e| e.into()).collect(),
+            content_hash: hash.to_string(),
+        };
+        let mut baseline = snapshot(Vec::new());
+        baseline.modules = vec![module("a", &["./db", "./log"], &["App"])];
+        let mut current = snapshot(Vec::new());
+        current.modules = vec![module(
+            "b",
+            &["./z", "./log", "./a", "./z"],
+            &["App", "main"],
+        )];
+
+        let diff = compute_diff(&baseline, &current);
+        let change = &diff.modified_modules[0];
+        assert_eq!(change.imports_added, ["./z", "./a"]);
+        assert_eq!(change.imports_removed, ["./db"]);
+        assert_eq!(change.exports_added, ["main"]);
+        assert!(change.exports_removed.is_empty());
+
+        // Shared names serialize exactly as the strings they hold
+        let json = serde_json::to_value(&current.modules[0]).unwrap();
+        assert_eq!(
+            json["imports"],
+            serde_json::json!(["./z", "./log", "./a", "./z"])
+        );
+    }
+
+    #[test]
+    fn test_version_mismatch_names_both_versions() {
+        let mut baseline = snapshot(Vec::new());
+        let mut current = snapshot(Vec::new());
+        assert_eq!(version_mismatch(&baseline, &current), None);
+
+        current.archmap_version = "1.2.0".to_string();
+        let message = version_mismatch(&baseline, &current).unwrap();
+        assert!(message.contains("an older archmap and the current one by archmap 1.2.0"));
+
+        baseline.version = 2;
+        assert!(
+            version_mismatch(&baseline, &current)
+                .unwrap()
+                .starts_with("Baseline snapshot uses schema 2")
+        );
+    }
+}
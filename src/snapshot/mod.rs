@@ -1,5 +1,10 @@
 mod diff;
 mod serialize;
 
-pub use diff::{SnapshotDiff, compute_diff, format_diff_json, format_diff_markdown};
-pub use serialize::{Snapshot, load_snapshot, save_snapshot};
+pub use diff::{
+    SnapshotDiff, compute_diff, format_diff_json, format_diff_markdown, version_mismatch,
+};
+pub use serialize::{
+    SNAPSHOT_SCHEMA, Snapshot, SnapshotError, issue_id, load_snapshot, load_snapshot_with_fs,
+    save_snapshot, save_snapshot_with_fs,
+};
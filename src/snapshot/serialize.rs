@@ -1,15 +1,55 @@
+use crate::analysis::{is_relative_specifier, resolve_relative_import};
 use crate::fs::{FileSystem, default_fs};
-use crate::model::{AnalysisResult, IssueKind, Module};
+use crate::model::{AnalysisResult, Issue, IssueKind, Module, ModuleIndex, Symbol};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::hash::{Hash, Hasher};
 use std::path::{Path, PathBuf};
+use thiserror::Error;
+
+/// Format version written to new snapshots
+pub const SNAPSHOT_SCHEMA: u32 = 1;
+
+/// Why a snapshot couldn't be loaded or saved
+#[derive(Debug, Error)]
+pub enum SnapshotError {
+    #[error("Failed to read snapshot {}: {source}", path.display())]
+    Read {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    #[error("Snapshot {} is not valid snapshot JSON: {source}", path.display())]
+    Parse {
+        path: PathBuf,
+        source: serde_json::Error,
+    },
+    /// Written by a newer archmap, in a schema this build doesn't know
+    #[error(
+        "Snapshot {} uses schema {found}, but this archmap reads up to schema {supported}; upgrade archmap",
+        path.display()
+    )]
+    UnsupportedVersion {
+        path: PathBuf,
+        found: u32,
+        supported: u32,
+    },
+    #[error("Failed to serialize snapshot: {0}")]
+    Serialize(serde_json::Error),
+    #[error("Failed to write snapshot {}: {source}", path.display())]
+    Write {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+}
 
 /// Complete architectural snapshot for comparison
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Snapshot {
     /// Snapshot version for forward compatibility
     pub version: u32,
+    /// archmap version that wrote the snapshot; empty for older snapshots
+    #[serde(default)]
+    pub archmap_version: String,
     /// Timestamp when snapshot was created
     pub created_at: String,
     /// Project name from analysis
@@ -29,8 +69,8 @@ pub struct ModuleSnapshot {
     pub path: String,
     pub name: String,
     pub lines: usize,
-    pub imports: Vec<String>,
-    pub exports: Vec<String>,
+    pub imports: Vec<Symbol>,
+    pub exports: Vec<Symbol>,
     /// Hash of file content for detecting changes
     pub content_hash: String,
 }
@@ -57,7 +97,22 @@ pub struct SnapshotMetrics {
 }
 
 impl Snapshot {
+    /// IDs of every issue recorded in this snapshot.
+    pub fn issue_ids(&self) -> HashSet<String> {
+        self.issues.iter().map(|i| i.issue_id.clone()).collect()
+    }
+
     pub fn from_analysis(result: &AnalysisResult, project_root: &Path) -> Self {
+        Self::from_analysis_with_fs(result, project_root, default_fs())
+    }
+
+    /// Like [`Snapshot::from_analysis`], hashing module contents as read
+    /// through `fs`.
+    pub fn from_analysis_with_fs(
+        result: &AnalysisResult,
+        project_root: &Path,
+        fs: &dyn FileSystem,
+    ) -> Self {
         let created_at = chrono_lite_now();
 
         // Convert modules
@@ -72,7 +127,7 @@ impl Snapshot {
                     .display()
                     .to_string();
 
-                let content_hash = compute_file_hash(&m.path);
+                let content_hash = compute_file_hash_with_fs(&m.path, fs);
 
                 ModuleSnapshot {
                     path: relative_path,
@@ -90,18 +145,7 @@ impl Snapshot {
             .issues
             .iter()
             .map(|i| {
-                let locations: Vec<String> = i
-                    .locations
-                    .iter()
-                    .map(|l| {
-                        l.path
-                            .strip_prefix(project_root)
-                            .unwrap_or(&l.path)
-                            .display()
-                            .to_string()
-                    })
-                    .collect();
-
+                let locations = relative_locations(i, project_root);
                 let issue_id = compute_issue_id(&i.kind, &locations);
                 let kind_str = format!("{:?}", i.kind);
 
@@ -116,6 +160,11 @@ impl Snapshot {
             .collect();
 
         // Build dependency adjacency list
+        let index = ModuleIndex::new(&result.modules);
+        let mut by_name: HashMap<&str, &Module> = HashMap::new();
+        for module in &result.modules {
+            by_name.entry(module.name.as_str()).or_insert(module);
+        }
         let mut dependencies: HashMap<String, Vec<String>> = HashMap::new();
         for module in &result.modules {
             let from_path = module
@@ -129,17 +178,20 @@ impl Snapshot {
             let deps: Vec<String> = module
                 .imports
                 .iter()
-                .filter_map(|imp| resolve_to_module(imp, &result.modules, project_root))
+                .filter_map(|imp| {
+                    resolve_to_module(imp, &module.path, &index, &by_name, project_root)
+                })
                 .collect();
 
             dependencies.insert(from_path, deps);
         }
 
         // Compute metrics
-        let metrics = compute_metrics(&modules, &issues, &dependencies);
+        let metrics = compute_metrics(&modules, &result.issues, &dependencies);
 
         Self {
-            version: 1,
+            version: SNAPSHOT_SCHEMA,
+            archmap_version: crate::build_info::VERSION.to_string(),
             created_at,
             project_name: result.project_name.clone(),
             modules,
@@ -150,7 +202,7 @@ impl Snapshot {
     }
 }
 
-pub fn save_snapshot(snapshot: &Snapshot, path: &Path) -> std::io::Result<()> {
+pub fn save_snapshot(snapshot: &Snapshot, path: &Path) -> Result<(), SnapshotError> {
     save_snapshot_with_fs(snapshot, path, default_fs())
 }
 
@@ -158,29 +210,42 @@ pub fn save_snapshot_with_fs(
     snapshot: &Snapshot,
     path: &Path,
     fs: &dyn FileSystem,
-) -> std::io::Result<()> {
-    let json = serde_json::to_string_pretty(snapshot)
-        .map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e))?;
+) -> Result<(), SnapshotError> {
+    let json = serde_json::to_string_pretty(snapshot).map_err(SnapshotError::Serialize)?;
     fs.write(path, &json)
+        .map_err(|source| SnapshotError::Write {
+            path: path.to_path_buf(),
+            source,
+        })
 }
 
-pub fn load_snapshot(path: &Path) -> Result<Snapshot, Box<dyn std::error::Error>> {
+pub fn load_snapshot(path: &Path) -> Result<Snapshot, SnapshotError> {
     load_snapshot_with_fs(path, default_fs())
 }
 
-pub fn load_snapshot_with_fs(
-    path: &Path,
-    fs: &dyn FileSystem,
-) -> Result<Snapshot, Box<dyn std::error::Error>> {
-    let content = fs.read_to_string(path)?;
-    let snapshot: Snapshot = serde_json::from_str(&content)?;
+/// Load a snapshot written by this or an older archmap.
+pub fn load_snapshot_with_fs(path: &Path, fs: &dyn FileSystem) -> Result<Snapshot, SnapshotError> {
+    let content = fs
+        .read_to_string(path)
+        .map_err(|source| SnapshotError::Read {
+            path: path.to_path_buf(),
+            source,
+        })?;
+    let snapshot: Snapshot =
+        serde_json::from_str(&content).map_err(|source| SnapshotError::Parse {
+            path: path.to_path_buf(),
+            source,
+        })?;
+    if snapshot.version > SNAPSHOT_SCHEMA {
+        return Err(SnapshotError::UnsupportedVersion {
+            path: path.to_path_buf(),
+            found: snapshot.version,
+            supported: SNAPSHOT_SCHEMA,
+        });
+    }
     Ok(snapshot)
 }
 
-fn compute_file_hash(path: &PathBuf) -> String {
-    compute_file_hash_with_fs(path, default_fs())
-}
-
 fn compute_file_hash_with_fs(path: &PathBuf, fs: &dyn FileSystem) -> String {
     use std::collections::hash_map::DefaultHasher;
 
@@ -194,6 +259,25 @@ fn compute_file_hash_with_fs(path: &PathBuf, fs: &dyn FileSystem) -> String {
     }
 }
 
+/// Stable ID of a live issue, matching `IssueSnapshot::issue_id` for the same project root.
+pub fn issue_id(issue: &Issue, project_root: &Path) -> String {
+    compute_issue_id(&issue.kind, &relative_locations(issue, project_root))
+}
+
+fn relative_locations(issue: &Issue, project_root: &Path) -> Vec<String> {
+    issue
+        .locations
+        .iter()
+        .map(|l| {
+            l.path
+                .strip_prefix(project_root)
+                .unwrap_or(&l.path)
+                .display()
+                .to_string()
+        })
+        .collect()
+}
+
 fn compute_issue_id(kind: &IssueKind, locations: &[String]) -> String {
     use std::collections::hash_map::DefaultHasher;
 
@@ -205,7 +289,25 @@ fn compute_issue_id(kind: &IssueKind, locations: &[String]) -> String {
     format!("{:x}", hasher.finish())
 }
 
-fn resolve_to_module(import: &str, modules: &[Module], project_root: &Path) -> Option<String> {
+/// Project-relative path of the module `import` names: relative specifiers
+/// are looked up by path, anything else by the module name of its first
+/// segment (after `crate::`).
+fn resolve_to_module(
+    import: &str,
+    from: &Path,
+    index: &ModuleIndex,
+    by_name: &HashMap<&str, &Module>,
+    project_root: &Path,
+) -> Option<String> {
+    if is_relative_specifier(import) {
+        return resolve_relative_import(import, from, |p| index.get(p).is_some()).map(|p| {
+            p.strip_prefix(project_root)
+                .unwrap_or(&p)
+                .display()
+                .to_string()
+        });
+    }
+
     // Extract the first path segment
     let segments: Vec<&str> = import.split("::").collect();
     if segments.is_empty() {
@@ -220,7 +322,7 @@ fn resolve_to_module(import: &str, modules: &[Module], project_root: &Path) -> O
         segments[0]
     };
 
-    modules.iter().find(|m| m.name == search_name).map(|m| {
+    by_name.get(search_name).map(|m| {
         m.path
             .strip_prefix(project_root)
             .unwrap_or(&m.path)
@@ -231,7 +333,7 @@ fn resolve_to_module(import: &str, modules: &[Module], project_root: &Path) -> O
 
 fn compute_metrics(
     modules: &[ModuleSnapshot],
-    issues: &[IssueSnapshot],
+    issues: &[Issue],
     dependencies: &HashMap<String, Vec<String>>,
 ) -> SnapshotMetrics {
     let total_modules = modules.len();
@@ -241,7 +343,7 @@ fn compute_metrics(
     // Count cycles from issues
     let cycle_count = issues
         .iter()
-        .filter(|i| i.kind.contains("CircularDependency"))
+        .filter(|i| i.kind == IssueKind::CircularDependency)
         .count();
 
     // Compute coupling (fan-in for each module)
@@ -258,12 +360,12 @@ fn compute_metrics(
         0.0
     };
 
-    // Count issues by kind
+    // Count issues by kind, without the data some kinds carry
     let mut issue_counts: HashMap<String, usize> = HashMap::new();
     for issue in issues {
-        // Extract the base kind (before any embedded data)
-        let base_kind = issue.kind.split('(').next().unwrap_or(&issue.kind);
-        *issue_counts.entry(base_kind.to_string()).or_insert(0) += 1;
+        *issue_counts
+            .entry(issue.kind.name().to_string())
+            .or_insert(0) += 1;
     }
 
     SnapshotMetrics {
@@ -1,38 +1,227 @@
 //! Terminal styling utilities for consistent CLI output
 
+use crate::model::IssueSeverity;
 use colored::Colorize;
+use std::ffi::OsStr;
 use std::io::{self, IsTerminal, Write};
+use std::sync::atomic::{AtomicBool, AtomicU8, Ordering};
+
+/// How much progress output to print, set from the global `--quiet`/`--verbose` flags
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Verbosity {
+    /// Errors only
+    Quiet = 0,
+    Normal = 1,
+    /// Adds per-file progress and timing on stderr
+    Verbose = 2,
+}
+
+static VERBOSITY: AtomicU8 = AtomicU8::new(Verbosity::Normal as u8);
+static COLOR: AtomicBool = AtomicBool::new(true);
+/// Off with `--no-thousands-sep`
+static THOUSANDS_SEP: AtomicBool = AtomicBool::new(true);
+/// On with `--severity-icons`
+static SEVERITY_ICONS: AtomicBool = AtomicBool::new(false);
+
+/// Apply the global output flags. Colors are also disabled when `NO_COLOR` is set.
+pub fn init(verbosity: Verbosity, no_color: bool) {
+    VERBOSITY.store(verbosity as u8, Ordering::Relaxed);
+    set_color(wants_color(
+        no_color,
+        std::env::var_os("NO_COLOR").as_deref(),
+    ));
+}
+
+/// Whether to style output given `--no-color` and the value of `NO_COLOR`,
+/// which disables colors when set to anything but an empty string.
+fn wants_color(no_color: bool, no_color_env: Option<&OsStr>) -> bool {
+    !no_color && no_color_env.is_none_or(OsStr::is_empty)
+}
+
+pub fn verbosity() -> Verbosity {
+    match VERBOSITY.load(Ordering::Relaxed) {
+        0 => Verbosity::Quiet,
+        2 => Verbosity::Verbose,
+        _ => Verbosity::Normal,
+    }
+}
+
+/// Force styling on or off, overriding TTY detection
+pub fn set_color(enabled: bool) {
+    COLOR.store(enabled, Ordering::Relaxed);
+    if enabled {
+        colored::control::unset_override();
+    } else {
+        colored::control::set_override(false);
+    }
+}
+
+pub fn colors_enabled() -> bool {
+    COLOR.load(Ordering::Relaxed)
+}
+
+/// Turn digit grouping in [`count`] on or off
+pub fn set_thousands_separator(enabled: bool) {
+    THOUSANDS_SEP.store(enabled, Ordering::Relaxed);
+}
+
+/// A count for people to read, digits grouped in threes (`1,847,293`, or
+/// `+12,000` for a signed delta). Left as is under `--no-thousands-sep` and
+/// for anything that isn't a whole number.
+pub fn count(n: impl std::fmt::Display) -> String {
+    let text = n.to_string();
+    let digits = text.trim_start_matches(['+', '-']);
+    if !THOUSANDS_SEP.load(Ordering::Relaxed)
+        || digits.is_empty()
+        || !digits.bytes().all(|b| b.is_ascii_digit())
+    {
+        return text;
+    }
+
+    let mut grouped = text[..text.len() - digits.len()].to_string();
+    for (i, digit) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i).is_multiple_of(3) {
+            grouped.push(',');
+        }
+        grouped.push(digit);
+    }
+    grouped
+}
+
+/// Turn the per-issue severity markers of [`severity_icon`] on or off
+pub fn set_severity_icons(enabled: bool) {
+    SEVERITY_ICONS.store(enabled, Ordering::Relaxed);
+}
+
+/// Marker starting each issue line under `--severity-icons`, `None` without
+/// it. ASCII when colors are off or the locale isn't UTF-8.
+pub fn severity_icon(severity: IssueSeverity) -> Option<&'static str> {
+    if !SEVERITY_ICONS.load(Ordering::Relaxed) {
+        return None;
+    }
+    Some(icon(severity, colors_enabled() && utf8_locale()))
+}
+
+fn icon(severity: IssueSeverity, unicode: bool) -> &'static str {
+    match (severity, unicode) {
+        (IssueSeverity::Error, true) => "✖",
+        (IssueSeverity::Warn, true) => "⚠",
+        (IssueSeverity::Info, true) => "ℹ",
+        (IssueSeverity::Error, false) => "[E]",
+        (IssueSeverity::Warn, false) => "[W]",
+        (IssueSeverity::Info, false) => "[I]",
+    }
+}
+
+/// Whether the locale can show non-ASCII glyphs; assumed when none is set
+fn utf8_locale() -> bool {
+    let locale = ["LC_ALL", "LC_CTYPE", "LANG"]
+        .iter()
+        .filter_map(|var| std::env::var(var).ok())
+        .find(|value| !value.is_empty());
+    locale.is_none_or(|value| {
+        let value = value.to_lowercase();
+        value.contains("utf-8") || value.contains("utf8")
+    })
+}
+
+/// Color the severity markers in rendered markdown: errors red, warnings
+/// yellow, infos blue.
+fn color_severity_icons(rendered: &str) -> String {
+    if !SEVERITY_ICONS.load(Ordering::Relaxed) {
+        return rendered.to_string();
+    }
+    let mut colored = rendered.to_string();
+    for unicode in [true, false] {
+        let error = icon(IssueSeverity::Error, unicode);
+        let warn = icon(IssueSeverity::Warn, unicode);
+        let info = icon(IssueSeverity::Info, unicode);
+        colored = colored
+            .replace(error, &error.red().bold().to_string())
+            .replace(warn, &warn.yellow().bold().to_string())
+            .replace(info, &info.blue().bold().to_string());
+    }
+    colored
+}
+
+fn quiet() -> bool {
+    verbosity() == Verbosity::Quiet
+}
+
+/// Diagnostics are logged as JSON instead of printed (`--log-format json`)
+fn structured() -> bool {
+    crate::logging::json_enabled()
+}
 
 /// Print an error message to stderr
 pub fn error(msg: &str) {
+    tracing::error!("{msg}");
+    if structured() {
+        return;
+    }
     eprintln!("{} {}", "error:".red().bold(), msg);
 }
 
 /// Print a warning message to stderr
 pub fn warning(msg: &str) {
+    tracing::warn!("{msg}");
+    if quiet() || structured() {
+        return;
+    }
     eprintln!("{} {}", "warning:".yellow().bold(), msg);
 }
 
 /// Print a success message to stdout
 pub fn success(msg: &str) {
+    if quiet() {
+        return;
+    }
     println!("{} {}", "✓".green().bold(), msg);
 }
 
 /// Print a hint message to stderr (dimmed)
 pub fn hint(msg: &str) {
+    tracing::info!(hint = true, "{msg}");
+    if structured() {
+        return;
+    }
     eprintln!("{} {}", "hint:".dimmed(), msg.dimmed());
 }
 
 /// Print a status/info header
 pub fn header(msg: &str) {
+    if quiet() {
+        return;
+    }
     println!("{}", msg.cyan().bold());
 }
 
 /// Print a status update (for watch mode, etc.)
 pub fn status(msg: &str) {
+    tracing::info!("{msg}");
+    if quiet() || structured() {
+        return;
+    }
     println!("{} {}", "→".blue(), msg);
 }
 
+/// Print an unadorned progress line (file changes, server instructions)
+pub fn note(msg: &str) {
+    if quiet() {
+        return;
+    }
+    println!("{}", msg);
+}
+
+/// Print a per-file progress or timing line to stderr, only with `--verbose`
+pub fn verbose(msg: &str) {
+    tracing::debug!("{msg}");
+    if verbosity() < Verbosity::Verbose || structured() {
+        return;
+    }
+    eprintln!("{}", msg.dimmed());
+}
+
 /// Format a path for display (bright white)
 pub fn path(p: &std::path::Path) -> String {
     p.display().to_string().bright_white().to_string()
@@ -51,6 +240,15 @@ pub fn file_deleted(path_str: &str) -> String {
     format!("{} {}", "deleted:".red(), path_str)
 }
 
+/// Format a pass/fail marker for test-style reports
+pub fn check_mark(passed: bool) -> String {
+    if passed {
+        "✓".green().bold().to_string()
+    } else {
+        "✗".red().bold().to_string()
+    }
+}
+
 /// Format a label-value pair for metrics display
 pub fn metric(label: &str, value: impl std::fmt::Display) -> String {
     format!("  {}: {}", label.dimmed(), value.to_string().cyan())
@@ -66,6 +264,52 @@ pub fn url(u: &str) -> String {
     u.bright_blue().underline().to_string()
 }
 
+/// Lay out rows under a bold header row. Columns are padded to their widest
+/// cell; all-numeric columns are right-aligned.
+pub fn table(columns: &[&str], rows: &[Vec<String>]) -> String {
+    let width = |cell: &str| cell.chars().count();
+    let widths: Vec<usize> = columns
+        .iter()
+        .enumerate()
+        .map(|(i, header)| {
+            rows.iter()
+                .map(|row| width(&row[i]))
+                .fold(width(header), usize::max)
+        })
+        .collect();
+    let numeric: Vec<bool> = (0..columns.len())
+        .map(|i| {
+            !rows.is_empty()
+                && rows
+                    .iter()
+                    .all(|row| row[i].replace(',', "").parse::<f64>().is_ok())
+        })
+        .collect();
+
+    let line = |cells: Vec<&str>| {
+        let padded: Vec<String> = cells
+            .iter()
+            .enumerate()
+            .map(|(i, cell)| {
+                if numeric[i] {
+                    format!("{:>w$}", cell, w = widths[i])
+                } else {
+                    format!("{:<w$}", cell, w = widths[i])
+                }
+            })
+            .collect();
+        padded.join("  ").trim_end().to_string()
+    };
+
+    let mut out = line(columns.to_vec()).bold().to_string();
+    out.push('\n');
+    for row in rows {
+        out.push_str(&line(row.iter().map(String::as_str).collect()));
+        out.push('\n');
+    }
+    out
+}
+
 /// Check if stdout is a terminal (TTY)
 pub fn is_terminal() -> bool {
     io::stdout().is_terminal()
@@ -74,11 +318,9 @@ pub fn is_terminal() -> bool {
 /// Render markdown to the terminal with colors and formatting.
 /// If not a TTY, writes plain markdown.
 pub fn render_markdown(markdown: &str, output: &mut dyn Write) -> io::Result<()> {
-    if io::stdout().is_terminal() {
+    if colors_enabled() && io::stdout().is_terminal() {
         // Use termimad for beautiful terminal rendering
-        let skin = create_skin();
-        let rendered = skin.term_text(markdown);
-        write!(output, "{}", rendered)
+        write!(output, "{}", terminal_markdown(markdown))
     } else {
         // Plain markdown for files/pipes
         write!(output, "{}", markdown)
@@ -87,9 +329,39 @@ pub fn render_markdown(markdown: &str, output: &mut dyn Write) -> io::Result<()>
 
 /// Render markdown to terminal, with explicit TTY flag
 pub fn render_markdown_to_terminal(markdown: &str) {
+    if colors_enabled() {
+        print!("{}", terminal_markdown(markdown));
+    } else {
+        print!("{}", markdown);
+    }
+}
+
+/// Style markdown for a terminal. `<!-- archmap:... -->` anchor lines outside
+/// code blocks are passed through verbatim so termimad never wraps or restyles them.
+pub(crate) fn terminal_markdown(markdown: &str) -> String {
     let skin = create_skin();
-    let rendered = skin.term_text(markdown);
-    print!("{}", rendered);
+    let mut rendered = String::new();
+    let mut chunk = String::new();
+    let mut in_fence = false;
+
+    for line in markdown.split_inclusive('\n') {
+        if line.trim_start().starts_with("```") {
+            in_fence = !in_fence;
+        }
+        if !in_fence && line.starts_with("<!-- archmap:") {
+            if !chunk.is_empty() {
+                rendered.push_str(&skin.term_text(&chunk).to_string());
+                chunk.clear();
+            }
+            rendered.push_str(line);
+        } else {
+            chunk.push_str(line);
+        }
+    }
+    if !chunk.is_empty() {
+        rendered.push_str(&skin.term_text(&chunk).to_string());
+    }
+    color_severity_icons(&rendered)
 }
 
 /// Create a custom termimad skin with our color scheme
@@ -122,3 +394,85 @@ fn create_skin() -> termimad::MadSkin {
 
     skin
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_anchor_lines_pass_through_untouched() {
+        let anchor = format!(
+            "<!-- archmap:module path=\"src/{}/__init__.py\" hash=\"0123456789abcdef\" -->",
+            "very_long_directory_name_".repeat(4)
+        );
+        let markdown = format!("# Title\n\n{}\n### `module`\n\nSome *text*.\n", anchor);
+
+        let rendered = terminal_markdown(&markdown);
+        assert!(rendered.contains(&format!("{}\n", anchor)));
+        assert!(!rendered.contains("*text*"));
+    }
+
+    #[test]
+    fn test_count_groups_digits() {
+        assert_eq!(count(7), "7");
+        assert_eq!(count(1000), "1,000");
+        assert_eq!(count(1847293usize), "1,847,293");
+        assert_eq!(count("-120000"), "-120,000");
+        assert_eq!(count("+12000"), "+12,000");
+        assert_eq!(count(0.25), "0.25");
+    }
+
+    #[test]
+    fn test_severity_icons_fall_back_to_ascii() {
+        let glyphs: Vec<_> = [
+            IssueSeverity::Error,
+            IssueSeverity::Warn,
+            IssueSeverity::Info,
+        ]
+        .into_iter()
+        .map(|severity| (icon(severity, true), icon(severity, false)))
+        .collect();
+        assert_eq!(glyphs, [("✖", "[E]"), ("⚠", "[W]"), ("ℹ", "[I]")]);
+        assert!(glyphs.iter().all(|(_, ascii)| ascii.is_ascii()));
+    }
+
+    #[test]
+    fn test_table_aligns_columns() {
+        let rendered = table(
+            &["path", "lines"],
+            &[
+                vec!["src/lib.rs".to_string(), "7".to_string()],
+                vec!["main.rs".to_string(), "120".to_string()],
+            ],
+        );
+
+        // The header row may carry bold escape codes
+        let body: Vec<_> = rendered.lines().skip(1).collect();
+        assert_eq!(body, ["src/lib.rs      7", "main.rs       120"]);
+    }
+
+    #[test]
+    fn test_table_right_aligns_grouped_numbers() {
+        let rendered = table(
+            &["path", "lines"],
+            &[
+                vec!["src/lib.rs".to_string(), "7".to_string()],
+                vec!["main.rs".to_string(), "1,120".to_string()],
+            ],
+        );
+
+        let body: Vec<_> = rendered.lines().skip(1).collect();
+        assert_eq!(body, ["src/lib.rs      7", "main.rs     1,120"]);
+    }
+
+    #[test]
+    fn test_no_color_flag_and_env_disable_colors() {
+        let set = |value: &'static str| Some(OsStr::new(value));
+        assert!(wants_color(false, None));
+        assert!(!wants_color(true, None));
+        assert!(!wants_color(false, set("1")));
+        // An empty NO_COLOR doesn't count as set
+        assert!(wants_color(false, set("")));
+        assert!(!wants_color(true, set("")));
+    }
+}
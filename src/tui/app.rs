@@ -0,0 +1,251 @@
+use crate::model::{AnalysisResult, Issue, IssueSeverity};
+use crate::output::relative_path;
+use ratatui::crossterm::event::KeyCode;
+use ratatui::widgets::ListState;
+use std::path::{Path, PathBuf};
+
+/// Column used to order the module list
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SortKey {
+    FanIn,
+    Lines,
+    Issues,
+}
+
+impl SortKey {
+    fn next(self) -> Self {
+        match self {
+            SortKey::FanIn => SortKey::Lines,
+            SortKey::Lines => SortKey::Issues,
+            SortKey::Issues => SortKey::FanIn,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            SortKey::FanIn => "fan-in",
+            SortKey::Lines => "lines",
+            SortKey::Issues => "issues",
+        }
+    }
+}
+
+/// Pre-computed view of a single module
+pub struct ModuleEntry {
+    pub display: String,
+    pub lines: usize,
+    pub fan_in: usize,
+    pub fan_out: usize,
+    pub imports: Vec<String>,
+    pub exports: Vec<String>,
+    pub dependents: Vec<String>,
+    /// Indices into `App::issues` for issues touching this module
+    pub issues: Vec<usize>,
+}
+
+/// TUI state: module list, selection, sort order and severity filter
+pub struct App {
+    pub project_name: String,
+    pub entries: Vec<ModuleEntry>,
+    pub issues: Vec<Issue>,
+    /// Indices into `entries` in display order
+    pub order: Vec<usize>,
+    pub sort: SortKey,
+    pub min_severity: IssueSeverity,
+    pub list_state: ListState,
+    pub should_quit: bool,
+}
+
+impl App {
+    pub fn new(result: &AnalysisResult, project_root: &Path, min_severity: IssueSeverity) -> Self {
+        let root = Some(project_root.to_path_buf());
+        let graph = &result.dependency_graph;
+
+        let entries = result
+            .modules
+            .iter()
+            .map(|m| {
+                let mut dependents: Vec<String> = graph
+                    .direct_dependents(&m.path)
+                    .iter()
+                    .map(|p| relative_path(p, root.as_ref()))
+                    .collect();
+                dependents.sort();
+
+                ModuleEntry {
+                    display: relative_path(&m.path, root.as_ref()),
+                    lines: m.lines,
+                    fan_in: graph.fan_in(&m.path),
+                    fan_out: graph.fan_out(&m.path),
+                    imports: m.imports.iter().map(ToString::to_string).collect(),
+                    exports: m.exports.iter().map(ToString::to_string).collect(),
+                    dependents,
+                    issues: issues_for(&m.path, &result.issues),
+                }
+            })
+            .collect();
+
+        let mut app = Self {
+            project_name: result.project_name.clone(),
+            entries,
+            issues: result.issues.clone(),
+            order: Vec::new(),
+            sort: SortKey::FanIn,
+            min_severity,
+            list_state: ListState::default(),
+            should_quit: false,
+        };
+        app.resort();
+        app
+    }
+
+    /// Issues for an entry at or above the current severity floor
+    pub fn visible_issues<'a>(&'a self, entry: &'a ModuleEntry) -> impl Iterator<Item = &'a Issue> {
+        entry
+            .issues
+            .iter()
+            .map(|&i| &self.issues[i])
+            .filter(|issue| issue.severity >= self.min_severity)
+    }
+
+    /// Value shown next to each module for the active sort key
+    pub fn sort_value(&self, entry: &ModuleEntry) -> usize {
+        match self.sort {
+            SortKey::FanIn => entry.fan_in,
+            SortKey::Lines => entry.lines,
+            SortKey::Issues => self.visible_issues(entry).count(),
+        }
+    }
+
+    pub fn selected(&self) -> Option<&ModuleEntry> {
+        self.list_state
+            .selected()
+            .and_then(|i| self.order.get(i))
+            .map(|&i| &self.entries[i])
+    }
+
+    pub fn on_key(&mut self, code: KeyCode) {
+        match code {
+            KeyCode::Char('q') | KeyCode::Esc => self.should_quit = true,
+            KeyCode::Down | KeyCode::Char('j') => self.move_selection(1),
+            KeyCode::Up | KeyCode::Char('k') => self.move_selection(-1),
+            KeyCode::Char('s') => {
+                self.sort = self.sort.next();
+                self.resort();
+            }
+            KeyCode::Char('f') => {
+                self.min_severity = match self.min_severity {
+                    IssueSeverity::Info => IssueSeverity::Warn,
+                    IssueSeverity::Warn => IssueSeverity::Error,
+                    IssueSeverity::Error => IssueSeverity::Info,
+                };
+                if self.sort == SortKey::Issues {
+                    self.resort();
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn move_selection(&mut self, delta: isize) {
+        if self.order.is_empty() {
+            return;
+        }
+        let current = self.list_state.selected().unwrap_or(0) as isize;
+        let last = self.order.len() as isize - 1;
+        self.list_state
+            .select(Some((current + delta).clamp(0, last) as usize));
+    }
+
+    /// Re-sort the module list (descending), keeping the selected module selected
+    fn resort(&mut self) {
+        let selected = self
+            .list_state
+            .selected()
+            .and_then(|i| self.order.get(i).copied());
+
+        let mut order: Vec<usize> = (0..self.entries.len()).collect();
+        order.sort_by(|&a, &b| {
+            self.sort_value(&self.entries[b])
+                .cmp(&self.sort_value(&self.entries[a]))
+                .then_with(|| self.entries[a].display.cmp(&self.entries[b].display))
+        });
+        self.order = order;
+
+        let position = selected
+            .and_then(|s| self.order.iter().position(|&i| i == s))
+            .or(if self.order.is_empty() { None } else { Some(0) });
+        self.list_state.select(position);
+    }
+}
+
+fn issues_for(path: &PathBuf, issues: &[Issue]) -> Vec<usize> {
+    issues
+        .iter()
+        .enumerate()
+        .filter(|(_, issue)| issue.locations.iter().any(|loc| &loc.path == path))
+        .map(|(i, _)| i)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::Module;
+
+    fn sample_app() -> App {
+        let mut core = Module::new(PathBuf::from("/p/src/core.rs"));
+        core.lines = 10;
+        let mut big = Module::new(PathBuf::from("/p/src/big.rs"));
+        big.lines = 900;
+        big.imports.push("crate::core".into());
+        let modules = vec![core, big];
+
+        let result = AnalysisResult::new(
+            "p".to_string(),
+            modules,
+            vec![Issue::god_object(
+                PathBuf::from("/p/src/big.rs"),
+                900,
+                vec!["large module".to_string()],
+            )],
+        );
+        App::new(&result, Path::new("/p"), IssueSeverity::Info)
+    }
+
+    #[test]
+    fn test_sorting_and_navigation() {
+        let mut app = sample_app();
+        assert_eq!(app.selected().unwrap().display, "src/core.rs");
+        assert_eq!(app.selected().unwrap().dependents, vec!["src/big.rs"]);
+
+        app.on_key(KeyCode::Char('s'));
+        assert_eq!(app.sort, SortKey::Lines);
+        // Selection follows the module across re-sorts
+        assert_eq!(app.selected().unwrap().display, "src/core.rs");
+
+        app.on_key(KeyCode::Up);
+        assert_eq!(app.selected().unwrap().display, "src/big.rs");
+        app.on_key(KeyCode::Up);
+        assert_eq!(app.list_state.selected(), Some(0));
+
+        app.on_key(KeyCode::Char('q'));
+        assert!(app.should_quit);
+    }
+
+    #[test]
+    fn test_severity_filter() {
+        let mut app = sample_app();
+        let big = app
+            .entries
+            .iter()
+            .position(|e| e.display == "src/big.rs")
+            .unwrap();
+        assert_eq!(app.visible_issues(&app.entries[big]).count(), 1);
+
+        app.on_key(KeyCode::Char('f'));
+        app.on_key(KeyCode::Char('f'));
+        assert_eq!(app.min_severity, IssueSeverity::Error);
+        assert_eq!(app.visible_issues(&app.entries[big]).count(), 0);
+    }
+}
@@ -0,0 +1,39 @@
+//! Interactive terminal UI for browsing modules and their issues.
+//!
+//! Enabled with the `tui` cargo feature.
+
+mod app;
+mod ui;
+
+pub use app::{App, SortKey};
+
+use crate::model::{AnalysisResult, IssueSeverity};
+use ratatui::DefaultTerminal;
+use ratatui::crossterm::event::{self, Event, KeyEventKind};
+use std::io;
+use std::path::Path;
+
+/// Run the TUI until the user quits, restoring the terminal afterwards.
+pub fn run(
+    result: &AnalysisResult,
+    project_root: &Path,
+    min_severity: IssueSeverity,
+) -> io::Result<()> {
+    let mut app = App::new(result, project_root, min_severity);
+    let mut terminal = ratatui::try_init()?;
+    let outcome = event_loop(&mut terminal, &mut app);
+    ratatui::restore();
+    outcome
+}
+
+fn event_loop(terminal: &mut DefaultTerminal, app: &mut App) -> io::Result<()> {
+    while !app.should_quit {
+        terminal.draw(|frame| ui::draw(frame, app))?;
+        if let Event::Key(key) = event::read()?
+            && key.kind == KeyEventKind::Press
+        {
+            app.on_key(key.code);
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,106 @@
+use super::app::App;
+use crate::model::IssueSeverity;
+use ratatui::Frame;
+use ratatui::layout::{Constraint, Layout};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, List, ListItem, Paragraph, Wrap};
+
+pub fn draw(frame: &mut Frame, app: &mut App) {
+    let [main, footer] =
+        Layout::vertical([Constraint::Min(1), Constraint::Length(1)]).areas(frame.area());
+    let [left, right] =
+        Layout::horizontal([Constraint::Percentage(40), Constraint::Percentage(60)]).areas(main);
+
+    let items: Vec<ListItem> = app
+        .order
+        .iter()
+        .map(|&i| {
+            let entry = &app.entries[i];
+            ListItem::new(format!("{:>5}  {}", app.sort_value(entry), entry.display))
+        })
+        .collect();
+
+    let list = List::new(items)
+        .block(Block::bordered().title(format!(
+            " {} — {} modules, by {} ",
+            app.project_name,
+            app.entries.len(),
+            app.sort.label()
+        )))
+        .highlight_style(Style::default().add_modifier(Modifier::REVERSED))
+        .highlight_symbol("> ");
+    frame.render_stateful_widget(list, left, &mut app.list_state);
+
+    let details = Paragraph::new(detail_lines(app))
+        .block(Block::bordered().title(" Details "))
+        .wrap(Wrap { trim: false });
+    frame.render_widget(details, right);
+
+    let help = format!(
+        " ↑/↓ navigate   s sort ({})   f severity (≥ {})   q quit",
+        app.sort.label(),
+        app.min_severity
+    );
+    frame.render_widget(
+        Paragraph::new(help).style(Style::default().fg(Color::DarkGray)),
+        footer,
+    );
+}
+
+fn detail_lines(app: &App) -> Vec<Line<'static>> {
+    let Some(entry) = app.selected() else {
+        return vec![Line::from("No modules found.")];
+    };
+
+    let mut lines = vec![
+        Line::from(Span::styled(
+            entry.display.clone(),
+            Style::default().add_modifier(Modifier::BOLD),
+        )),
+        Line::from(format!(
+            "{} lines · fan-in {} · fan-out {}",
+            entry.lines, entry.fan_in, entry.fan_out
+        )),
+        Line::default(),
+    ];
+
+    let issues: Vec<_> = app.visible_issues(entry).collect();
+    lines.push(heading(&format!("Issues ({})", issues.len())));
+    for issue in issues {
+        let color = match issue.severity {
+            IssueSeverity::Error => Color::Red,
+            IssueSeverity::Warn => Color::Yellow,
+            IssueSeverity::Info => Color::Blue,
+        };
+        lines.push(Line::from(vec![
+            Span::styled(
+                format!("  [{}] ", issue.severity),
+                Style::default().fg(color),
+            ),
+            Span::raw(issue.message.clone()),
+        ]));
+    }
+
+    push_section(&mut lines, "Imports", &entry.imports);
+    push_section(&mut lines, "Exports", &entry.exports);
+    push_section(&mut lines, "Dependents", &entry.dependents);
+    lines
+}
+
+fn push_section(lines: &mut Vec<Line<'static>>, title: &str, items: &[String]) {
+    lines.push(Line::default());
+    lines.push(heading(&format!("{} ({})", title, items.len())));
+    for item in items {
+        lines.push(Line::from(format!("  {}", item)));
+    }
+}
+
+fn heading(text: &str) -> Line<'static> {
+    Line::from(Span::styled(
+        text.to_string(),
+        Style::default()
+            .fg(Color::Cyan)
+            .add_modifier(Modifier::BOLD),
+    ))
+}
@@ -0,0 +1,85 @@
+//! Integration tests for the async library API (`--features async`).
+#![cfg(feature = "async")]
+
+use archmap::{AnalysisOptions, ArchmapError, CancellationToken, Progress, analyze_async};
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+
+/// Fresh project directory with `count` small modules
+fn project(name: &str, count: usize) -> PathBuf {
+    let root = std::env::temp_dir().join(format!("archmap-async-{}-{}", name, std::process::id()));
+    fs::create_dir_all(root.join("src")).unwrap();
+    for i in 0..count {
+        fs::write(root.join(format!("src/m{}.rs", i)), "pub fn run() {}\n").unwrap();
+    }
+    root
+}
+
+#[tokio::test]
+async fn test_timeout_cancels_the_blocking_analysis() {
+    let root = project("timeout", 20);
+    // One walker thread, so files are parsed one after another
+    fs::write(root.join(".archmap.toml"), "threads = 1\n").unwrap();
+    let token = CancellationToken::new();
+    let parsed = Arc::new(AtomicUsize::new(0));
+    let counted = Arc::clone(&parsed);
+    let options = AnalysisOptions::builder()
+        .cancellation(token.clone())
+        .progress(move |step| {
+            if let Progress::FileParsed { .. } = step {
+                counted.fetch_add(1, Ordering::Relaxed);
+                // Slow enough that 20 files can't finish before the timeout
+                std::thread::sleep(Duration::from_millis(50));
+            }
+        })
+        .build()
+        .unwrap();
+
+    let outcome =
+        tokio::time::timeout(Duration::from_millis(100), analyze_async(&root, options)).await;
+    assert!(outcome.is_err());
+    assert!(token.is_cancelled());
+
+    // Files already being parsed finish; the walker starts no new ones
+    tokio::time::sleep(Duration::from_millis(500)).await;
+    let stopped_at = parsed.load(Ordering::Relaxed);
+    tokio::time::sleep(Duration::from_millis(300)).await;
+    fs::remove_dir_all(&root).unwrap();
+
+    assert_eq!(parsed.load(Ordering::Relaxed), stopped_at);
+    assert!(stopped_at < 20);
+}
+
+#[tokio::test]
+async fn test_async_analysis_reports_progress_and_leaves_the_token_alone() {
+    let root = project("progress", 3);
+    let (progress, mut steps) = archmap::progress_channel();
+    let token = CancellationToken::new();
+    let mut options = AnalysisOptions::default();
+    options.progress = Some(progress);
+    options.cancellation = Some(token.clone());
+
+    let result = analyze_async(&root, options).await.unwrap();
+    assert_eq!(result.modules.len(), 3);
+    assert!(!token.is_cancelled());
+
+    let mut parsed = 0;
+    while let Ok(step) = steps.try_recv() {
+        if matches!(step, Progress::FileParsed { .. }) {
+            parsed += 1;
+        }
+    }
+    assert_eq!(parsed, 3);
+
+    token.cancel();
+    let options = AnalysisOptions::builder()
+        .cancellation(token)
+        .build()
+        .unwrap();
+    let cancelled = analyze_async(&root, options).await;
+    fs::remove_dir_all(&root).unwrap();
+    assert!(matches!(cancelled, Err(ArchmapError::Cancelled)));
+}
@@ -0,0 +1,181 @@
+//! Integration tests for the exit code contract of the `archmap` binary.
+
+use archmap::commands::exit_code;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// Fresh project directory with the given files
+fn project(name: &str, files: &[(&str, &str)]) -> PathBuf {
+    let root = std::env::temp_dir().join(format!("archmap-exit-{}-{}", name, std::process::id()));
+    for (path, contents) in files {
+        let path = root.join(path);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, contents).unwrap();
+    }
+    root
+}
+
+fn archmap(root: &Path, args: &[&str]) -> i32 {
+    Command::new(env!("CARGO_BIN_EXE_archmap"))
+        .args(args)
+        .arg("--quiet")
+        .current_dir(root)
+        .output()
+        .unwrap()
+        .status
+        .code()
+        .unwrap()
+}
+
+#[test]
+fn test_usage_errors_exit_2() {
+    let root = project(
+        "usage",
+        &[
+            ("src/lib.rs", "pub fn run() {}\n"),
+            ("broken.toml", "[thresholds\n"),
+        ],
+    );
+    let bad_config = archmap(&root, &["analyze", "src", "--config", "broken.toml"]);
+    let missing_path = archmap(&root, &["analyze", "does-not-exist"]);
+    let unknown_flag = archmap(&root, &["analyze", "--no-such-flag"]);
+    fs::remove_dir_all(&root).unwrap();
+
+    assert_eq!(bad_config, exit_code::USAGE);
+    assert_eq!(missing_path, exit_code::USAGE);
+    assert_eq!(unknown_flag, exit_code::USAGE);
+}
+
+#[test]
+fn test_error_issues_exit_1_unless_exit_zero() {
+    let root = project(
+        "issues",
+        &[
+            ("src/lib.rs", "mod a;\nmod b;\n"),
+            ("src/a.rs", "use crate::b::second;\npub fn first() {}\n"),
+            ("src/b.rs", "use crate::a::first;\npub fn second() {}\n"),
+        ],
+    );
+    let plain = archmap(&root, &["analyze", "src"]);
+    let exit_zero = archmap(&root, &["analyze", "src", "--exit-zero"]);
+    let unwritable = archmap(
+        &root,
+        &[
+            "analyze",
+            "src",
+            "--exit-zero",
+            "-o",
+            "missing/dir/report.md",
+        ],
+    );
+    fs::remove_dir_all(&root).unwrap();
+
+    assert_eq!(plain, exit_code::ISSUES);
+    assert_eq!(exit_zero, exit_code::SUCCESS);
+    // --exit-zero never hides a failure to run
+    assert_eq!(unwritable, exit_code::FAILURE);
+}
+
+#[test]
+fn test_check_only_runs_selected_detectors() {
+    // A cycle (error) and a module six others import (warning)
+    let mut files = vec![
+        ("src/lib.rs", "mod a;\nmod b;\n"),
+        ("src/a.rs", "use crate::b::second;\npub fn first() {}\n"),
+        ("src/b.rs", "use crate::a::first;\npub fn second() {}\n"),
+        ("src/core.rs", "pub fn shared() {}\n"),
+    ];
+    for name in [
+        "src/c.rs", "src/d.rs", "src/e.rs", "src/f.rs", "src/g.rs", "src/h.rs",
+    ] {
+        files.push((name, "use crate::core::shared;\n"));
+    }
+    let root = project("only", &files);
+    let cycles = archmap(&root, &["check", "src", "--only", "cycles"]);
+    let coupling = archmap(&root, &["check", "src", "--only", "coupling"]);
+    let coupling_warn = archmap(
+        &root,
+        &["check", "src", "--only", "coupling", "--fail-on", "warn"],
+    );
+    let depth = archmap(&root, &["check", "src", "--only", "depth,cohesion"]);
+    let unknown = archmap(&root, &["check", "src", "--only", "cycles,spaghetti"]);
+    fs::remove_dir_all(&root).unwrap();
+
+    assert_eq!(cycles, exit_code::ISSUES);
+    // Only the warning-level coupling issue is found, so the cycle can't fail it
+    assert_eq!(coupling, exit_code::SUCCESS);
+    assert_eq!(coupling_warn, exit_code::ISSUES);
+    assert_eq!(depth, exit_code::SUCCESS);
+    assert_eq!(unknown, exit_code::USAGE);
+}
+
+#[test]
+fn test_empty_and_single_file_projects_exit_0() {
+    let empty = project("empty", &[("README.md", "# Not code\n")]);
+    let analyze = archmap(&empty, &["analyze"]);
+    let ai = archmap(&empty, &["ai"]);
+    let snapshot = archmap(&empty, &["snapshot", "--save", "baseline.json"]);
+    let saved = empty.join("baseline.json").exists();
+    fs::remove_dir_all(&empty).unwrap();
+
+    let single = project("single", &[("main.rs", "fn main() {}\n")]);
+    let single_analyze = archmap(&single, &["analyze", "-f", "json"]);
+    let single_snapshot = archmap(&single, &["snapshot", "--save", "baseline.json"]);
+    fs::remove_dir_all(&single).unwrap();
+
+    assert_eq!(analyze, exit_code::SUCCESS);
+    assert_eq!(ai, exit_code::SUCCESS);
+    assert_eq!(snapshot, exit_code::SUCCESS);
+    // An empty baseline would hide every issue a later diff should report
+    assert!(!saved);
+    assert_eq!(single_analyze, exit_code::SUCCESS);
+    assert_eq!(single_snapshot, exit_code::SUCCESS);
+}
+
+#[test]
+fn test_graph_export_exit_codes() {
+    let root = project("graph", &[("src/lib.rs", "pub fn run() {}\n")]);
+    let exported = archmap(&root, &["graph", "src", "--export", "graph.html"]);
+    let written = fs::read_to_string(root.join("graph.html")).unwrap_or_default();
+    let unwritable = archmap(
+        &root,
+        &["graph", "src", "--export", "missing/dir/graph.html"],
+    );
+    let neither = archmap(&root, &["graph", "src"]);
+    let both = archmap(
+        &root,
+        &["graph", "src", "--serve", "--export", "graph.html"],
+    );
+    fs::remove_dir_all(&root).unwrap();
+
+    assert_eq!(exported, exit_code::SUCCESS);
+    assert!(written.contains("\"nodes\":[{"));
+    assert_eq!(unwritable, exit_code::FAILURE);
+    assert_eq!(neither, exit_code::USAGE);
+    assert_eq!(both, exit_code::USAGE);
+}
+
+#[test]
+fn test_ai_output_limit() {
+    let root = project("ai-limit", &[("src/lib.rs", "pub fn run() {}\n")]);
+    let markdown = archmap(
+        &root,
+        &["ai", "src", "--max-output-bytes", "200", "-o", "ai.md"],
+    );
+    let cut = fs::read_to_string(root.join("ai.md")).unwrap_or_default();
+    let json = archmap(
+        &root,
+        &["ai", "src", "--max-output-bytes", "200", "-o", "ai.json"],
+    );
+    let refused = root.join("ai.json").exists();
+    let forced = archmap(&root, &["ai", "src", "--force", "-o", "ai.json"]);
+    fs::remove_dir_all(&root).unwrap();
+
+    assert_eq!(markdown, exit_code::SUCCESS);
+    assert!(cut.trim_end().ends_with("pass --force -->"));
+    assert!(cut.len() < 400);
+    assert_eq!(json, exit_code::FAILURE);
+    assert!(!refused);
+    assert_eq!(forced, exit_code::SUCCESS);
+}
@@ -2,6 +2,7 @@
 
 use archmap::{
     AiFormat, AiOptions, AnalysisOptions, ArchmapError, ImpactOptions, ai_context, analyze, impact,
+    languages,
 };
 use std::path::Path;
 
@@ -24,17 +25,90 @@ fn test_analyze_current_directory() {
 
 #[test]
 fn test_analyze_with_options() {
-    let options = AnalysisOptions {
-        languages: vec!["rust".to_string()],
-        exclude: vec!["tests".to_string()],
-        max_depth: 10,
-        min_cohesion: 0.2,
-    };
+    let options = AnalysisOptions::builder()
+        .languages(["rust"])
+        .exclude(["tests"])
+        .max_depth(10)
+        .min_cohesion(0.2)
+        .build()
+        .unwrap();
 
     let result = analyze(Path::new("."), options).unwrap();
     assert!(!result.modules.is_empty());
 }
 
+#[test]
+fn test_analyze_disabled_checks() {
+    let options = AnalysisOptions::builder()
+        // Very strict thresholds so these checks would otherwise fire
+        .max_depth(1)
+        .min_cohesion(1.0)
+        .disabled_checks(["LowCohesion", "deep-dependency-chain"])
+        .build()
+        .unwrap();
+
+    let result = analyze(Path::new("."), options).unwrap();
+    assert!(!result.issues.is_empty());
+    assert!(
+        result
+            .issues
+            .iter()
+            .all(|i| !matches!(i.kind.name(), "LowCohesion" | "DeepDependencyChain"))
+    );
+}
+
+#[test]
+fn test_dead_public_functions_are_opt_in() {
+    let is_dead = |i: &archmap::Issue| i.kind.name() == "DeadPublicFunction";
+
+    let default = analyze(Path::new("."), AnalysisOptions::default()).unwrap();
+    assert!(!default.issues.iter().any(is_dead));
+
+    let options = AnalysisOptions::builder()
+        .enabled_checks(["dead-public-function"])
+        .build()
+        .unwrap();
+    let result = analyze(Path::new("."), options).unwrap();
+    for issue in result.issues.iter().filter(|i| is_dead(i)) {
+        assert_eq!(issue.severity, archmap::IssueSeverity::Info);
+        assert!(issue.locations[0].line.is_some());
+    }
+}
+
+#[test]
+fn test_analyze_resolves_imports() {
+    let result = analyze(Path::new("."), AnalysisOptions::default()).unwrap();
+
+    assert!(
+        result
+            .modules
+            .iter()
+            .any(|m| !m.resolved_imports.is_empty())
+    );
+    for module in &result.modules {
+        assert_eq!(
+            module.resolved_imports,
+            result.dependency_graph.direct_dependencies(&module.path),
+            "{}",
+            module.path.display()
+        );
+    }
+}
+
+#[test]
+fn test_modules_are_sorted_by_path_every_run() {
+    // Enough files that the parallel walk finishes them out of order
+    let paths = |result: archmap::AnalysisResult| -> Vec<_> {
+        result.modules.into_iter().map(|m| m.path).collect()
+    };
+    let first = paths(analyze(Path::new("src"), AnalysisOptions::default()).unwrap());
+    let second = paths(analyze(Path::new("src"), AnalysisOptions::default()).unwrap());
+
+    assert!(first.len() > 50);
+    assert!(first.is_sorted());
+    assert_eq!(first, second);
+}
+
 #[test]
 fn test_analyze_invalid_path() {
     let result = analyze(Path::new("/nonexistent/path"), AnalysisOptions::default());
@@ -71,10 +145,7 @@ fn test_impact_analysis() {
 
 #[test]
 fn test_impact_with_depth_limit() {
-    let options = ImpactOptions {
-        languages: vec![],
-        depth: Some(1),
-    };
+    let options = ImpactOptions::builder().depth(1).build().unwrap();
 
     let result = impact(Path::new("."), Path::new("src/model/mod.rs"), options).unwrap();
 
@@ -95,13 +166,13 @@ fn test_impact_invalid_file() {
 
 #[test]
 fn test_ai_context_markdown() {
-    let options = AiOptions {
-        format: AiFormat::Markdown,
-        tokens: Some(1000),
-        signatures_only: false,
-        topo_order: true,
-        ..Default::default()
-    };
+    let options = AiOptions::builder()
+        .format(AiFormat::Markdown)
+        .tokens(1000)
+        .signatures_only(false)
+        .topo_order(true)
+        .build()
+        .unwrap();
 
     let context = ai_context(Path::new("."), options).unwrap();
 
@@ -111,11 +182,11 @@ fn test_ai_context_markdown() {
 
 #[test]
 fn test_ai_context_json() {
-    let options = AiOptions {
-        format: AiFormat::Json,
-        tokens: Some(500),
-        ..Default::default()
-    };
+    let options = AiOptions::builder()
+        .format(AiFormat::Json)
+        .tokens(500)
+        .build()
+        .unwrap();
 
     let context = ai_context(Path::new("."), options).unwrap();
 
@@ -129,11 +200,11 @@ fn test_ai_context_json() {
 
 #[test]
 fn test_ai_context_signatures_only() {
-    let options = AiOptions {
-        signatures_only: true,
-        format: AiFormat::Markdown,
-        ..Default::default()
-    };
+    let options = AiOptions::builder()
+        .signatures_only(true)
+        .format(AiFormat::Markdown)
+        .build()
+        .unwrap();
 
     let context = ai_context(Path::new("."), options).unwrap();
     assert!(!context.is_empty());
@@ -148,8 +219,9 @@ fn test_analysis_result_types() {
         let _name: &str = &module.name;
         let _path = &module.path;
         let _lines: usize = module.lines;
-        let _imports: &Vec<String> = &module.imports;
-        let _exports: &Vec<String> = &module.exports;
+        let _imports: &Vec<archmap::Symbol> = &module.imports;
+        let _exports: &Vec<archmap::Symbol> = &module.exports;
+        let _first_import: Option<&str> = module.imports.first().map(|i| i.as_str());
 
         for def in &module.definitions {
             let _def_name: &str = &def.name;
@@ -190,7 +262,852 @@ fn test_impact_result_methods() {
     // Test formatting
     let _markdown = result.to_markdown(true);
     let _json = result.to_json();
+    let _dot = result.to_dot();
+
+    // The tree, for rendering it yourself
+    let _tree = result.tree();
+    let _depth = result.depth_of(result.target());
+}
+
+fn context_size(output: &str) -> usize {
+    output
+        .split("*Context size: ~")
+        .nth(1)
+        .and_then(|s| s.split(' ').next())
+        .and_then(|s| s.parse().ok())
+        .expect("markdown output should report context size")
+}
+
+#[test]
+fn test_ai_context_exclude_filter() {
+    let full = ai_context(Path::new("."), AiOptions::default()).unwrap();
+    let filtered = ai_context(
+        Path::new("."),
+        AiOptions::builder()
+            .exclude(["src/analysis"])
+            .build()
+            .unwrap(),
+    )
+    .unwrap();
+
+    assert!(full.contains("src/analysis/"));
+    assert!(
+        !filtered.contains("src/analysis/"),
+        "Excluded directory should not appear in output"
+    );
+    assert!(filtered.contains("*Filters: exclude src/analysis*"));
+    assert!(context_size(&filtered) < context_size(&full));
+}
+
+#[test]
+fn test_ai_context_include_filter() {
+    let context = ai_context(
+        Path::new("."),
+        AiOptions::builder()
+            .include(["src/model/**"])
+            .format(AiFormat::Json)
+            .build()
+            .unwrap(),
+    )
+    .unwrap();
+
+    let json: serde_json::Value = serde_json::from_str(&context).unwrap();
+    let modules = json["modules"].as_array().unwrap();
+    assert!(!modules.is_empty());
+    assert!(
+        modules
+            .iter()
+            .all(|m| m["path"].as_str().unwrap().starts_with("src/model/"))
+    );
+}
+
+#[test]
+fn test_analyze_paths_links_sibling_directories() {
+    use archmap::Config;
+    use archmap::analysis::{PathFilter, analyze_paths};
+    use archmap::parser::ParserRegistry;
+    use std::fs;
+
+    let root = std::env::temp_dir().join(format!("archmap-multi-{}", std::process::id()));
+    fs::create_dir_all(root.join("app")).unwrap();
+    fs::create_dir_all(root.join("shared")).unwrap();
+    fs::write(
+        root.join("app/main.ts"),
+        "import { greet } from '../shared/util';\n\nexport function main() {\n  greet();\n}\n",
+    )
+    .unwrap();
+    fs::write(
+        root.join("shared/util.ts"),
+        "export function greet() {\n  return 'hi';\n}\n",
+    )
+    .unwrap();
+
+    let result = analyze_paths(
+        &root,
+        &[root.join("app"), root.join("shared")],
+        &Config::default(),
+        &ParserRegistry::new(),
+        &[],
+        &PathFilter::default(),
+    );
+    fs::remove_dir_all(&root).unwrap();
+
+    assert_eq!(result.modules.len(), 2);
+    let main = result
+        .modules
+        .iter()
+        .find(|m| m.path.ends_with("app/main.ts"))
+        .unwrap();
+    assert_eq!(main.resolved_imports, vec![root.join("shared/util.ts")]);
+}
+
+#[test]
+fn test_overlay_source_replaces_file_on_disk() {
+    use archmap::analysis::{PathFilter, analyze_paths_with_fs};
+    use archmap::fs::{OverlayFs, default_fs};
+    use archmap::parser::ParserRegistry;
+    use archmap::{Config, IssueKind};
+    use std::fs;
+
+    let root = std::env::temp_dir().join(format!("archmap-overlay-{}", std::process::id()));
+    fs::create_dir_all(&root).unwrap();
+    for name in ["orders", "users"] {
+        fs::write(
+            root.join(format!("{}.ts", name)),
+            format!("export function {name}() {{\n  return prisma.{name}.findMany();\n}}\n"),
+        )
+        .unwrap();
+    }
+    let edited = root.join("billing.ts");
+    fs::write(&edited, "export function billing() {\n  return 0;\n}\n").unwrap();
+
+    let analyze = |fs: &dyn archmap::fs::FileSystem| {
+        analyze_paths_with_fs(
+            &root,
+            std::slice::from_ref(&root),
+            &Config::default(),
+            &ParserRegistry::new(),
+            &[],
+            &PathFilter::default(),
+            fs,
+        )
+    };
+    let touches_edited = |result: &archmap::AnalysisResult| {
+        result.issues.iter().any(|i| {
+            matches!(i.kind, IssueKind::BoundaryViolation { .. })
+                && i.locations.iter().any(|l| l.path == edited)
+        })
+    };
+
+    // Two modules split the boundary evenly, so one counts as its owner
+    let on_disk = analyze(default_fs());
+    let overlay = OverlayFs::new(default_fs()).with_file(
+        &edited,
+        "export function billing() {\n  return prisma.invoice.findMany();\n}\n",
+    );
+    let buffered = analyze(&overlay);
+    fs::remove_dir_all(&root).unwrap();
+
+    assert!(!touches_edited(&on_disk));
+    assert!(touches_edited(&buffered));
+    assert_eq!(buffered.modules.len(), 3);
+}
+
+#[test]
+fn test_overlay_edits_and_removals_change_the_graph() {
+    use archmap::fs::{FileSystem, OverlayFs, default_fs};
+    use archmap::{AnalysisOptions, analyze_with_fs};
+    use std::fs;
+
+    let root = std::env::temp_dir().join(format!("archmap-overlay-edit-{}", std::process::id()));
+    fs::create_dir_all(root.join("src")).unwrap();
+    fs::write(root.join("src/app.ts"), "export const app = 1;\n").unwrap();
+    fs::write(root.join("src/db.ts"), "export const db = 1;\n").unwrap();
+    fs::write(root.join("src/legacy.ts"), "export const legacy = 1;\n").unwrap();
+
+    let app = root.join("src/app.ts");
+    let db = root.join("src/db.ts");
+    let imports_db = |fs: &dyn FileSystem| {
+        let result = analyze_with_fs(&root, AnalysisOptions::default(), fs).unwrap();
+        let app = result.module(&app).unwrap();
+        (app.resolved_imports.contains(&db), result.modules.len())
+    };
+
+    let overlay = OverlayFs::new(default_fs());
+    // Keys written differently from the walked paths still line up
+    overlay.insert(
+        root.join("src/../src/app.ts"),
+        "import { db } from './db';\nexport const app = db;\n",
+    );
+    overlay.remove(root.join("src/legacy.ts"));
+
+    let on_disk = imports_db(default_fs());
+    let buffered = imports_db(&overlay);
+    let unchanged = fs::read_to_string(&app).unwrap();
+    fs::remove_dir_all(&root).unwrap();
+
+    assert_eq!(on_disk, (false, 3));
+    assert_eq!(buffered, (true, 2));
+    assert_eq!(unchanged, "export const app = 1;\n");
+}
+
+#[test]
+fn test_huge_binary_and_minified_files_are_skipped_and_reported() {
+    use archmap::fs::MemoryFileSystem;
+    use archmap::model::DiagnosticKind;
+    use archmap::{AnalysisOptions, analyze_with_fs};
+
+    // Over the default 1024 KB limit
+    let bundle = "export const x = 1;\n".repeat(60_000);
+    let minified = format!("export const y = [{}];\n", "1,".repeat(4000));
+    let fs = MemoryFileSystem::new()
+        .with_file("/repo/src/app.ts", "export const app = 1;\n")
+        .with_file("/repo/src/bundle.ts", bundle)
+        .with_file("/repo/src/blob.ts", "export const z = 1;\0\0\0\n")
+        .with_file("/repo/src/vendor.min.ts", minified);
+
+    let result = analyze_with_fs(Path::new("/repo"), AnalysisOptions::default(), &fs).unwrap();
+    let parsed: Vec<_> = result.modules.iter().map(|m| m.path.clone()).collect();
+    assert_eq!(parsed, [Path::new("/repo/src/app.ts")]);
+
+    let mut skipped: Vec<_> = result
+        .diagnostics
+        .iter()
+        .filter(|d| d.kind == DiagnosticKind::Skipped)
+        .map(|d| (d.path.clone().unwrap(), d.message.clone()))
+        .collect();
+    skipped.sort();
+    assert_eq!(skipped.len(), 3);
+    assert_eq!(skipped[0].0, Path::new("/repo/src/blob.ts"));
+    assert!(skipped[0].1.contains("binary file"));
+    assert_eq!(skipped[1].0, Path::new("/repo/src/bundle.ts"));
+    assert!(skipped[1].1.contains("over max_file_size_kb (1024)"));
+    assert_eq!(skipped[2].0, Path::new("/repo/src/vendor.min.ts"));
+    assert!(skipped[2].1.contains("over max_line_length (5000)"));
+}
+
+#[cfg(unix)]
+#[test]
+fn test_symlinked_packages_are_analyzed_once() {
+    use archmap::{AnalysisOptions, analyze};
+    use std::fs;
+    use std::os::unix::fs::symlink;
+
+    let root = std::env::temp_dir().join(format!("archmap-symlinks-{}", std::process::id()));
+    fs::create_dir_all(root.join("shared")).unwrap();
+    fs::create_dir_all(root.join("svc-a")).unwrap();
+    fs::create_dir_all(root.join("svc-b")).unwrap();
+    let root = fs::canonicalize(&root).unwrap();
+    fs::write(root.join("shared/util.ts"), "export const u = 1;\n").unwrap();
+    fs::write(
+        root.join("svc-a/app.ts"),
+        "import { u } from './lib/util';\n",
+    )
+    .unwrap();
+    fs::write(
+        root.join("svc-b/main.ts"),
+        "import { u } from './lib/util';\n",
+    )
+    .unwrap();
+    symlink("../shared", root.join("svc-a/lib")).unwrap();
+    symlink("../shared", root.join("svc-b/lib")).unwrap();
+    // A cycle back to the root must not be walked forever
+    symlink("..", root.join("shared/loop")).unwrap();
+
+    let not_followed = analyze(&root, AnalysisOptions::default());
+    fs::write(root.join(".archmap.toml"), "follow_symlinks = true\n").unwrap();
+    let followed = analyze(&root, AnalysisOptions::default());
+    fs::remove_dir_all(&root).unwrap();
+
+    let not_followed = not_followed.unwrap();
+    assert_eq!(not_followed.modules.len(), 3);
+    assert_eq!(not_followed.dependency_graph.graph().edge_count(), 0);
+
+    let followed = followed.unwrap();
+    let paths: Vec<_> = followed.modules.iter().map(|m| m.path.clone()).collect();
+    assert_eq!(
+        paths,
+        [
+            root.join("shared/util.ts"),
+            root.join("svc-a/app.ts"),
+            root.join("svc-b/main.ts"),
+        ]
+    );
+    let util = followed.module(&root.join("shared/util.ts")).unwrap();
+    assert_eq!(
+        util.aliases,
+        [
+            root.join("svc-a/lib/util.ts"),
+            root.join("svc-b/lib/util.ts")
+        ]
+    );
+    // Both services' imports land on the one module
+    assert_eq!(followed.dependency_graph.fan_in(&util.path), 2);
+}
+
+#[test]
+fn test_languages_follow_the_lang_filter() {
+    let all = languages(&[]);
+    let names: Vec<_> = all.iter().map(|l| l.name.as_str()).collect();
+    assert_eq!(names, ["rust", "typescript", "python", "scala"]);
+    assert!(all[1].extensions.iter().any(|e| e == "tsx"));
+
+    // Aliases of one language list it once
+    let filtered = languages(&["ts".to_string(), "js".to_string(), "py".to_string()]);
+    let names: Vec<_> = filtered.iter().map(|l| l.name.as_str()).collect();
+    assert_eq!(names, ["typescript", "python"]);
+}
+
+#[test]
+fn test_analysis_runs_entirely_in_memory() {
+    use archmap::fs::MemoryFileSystem;
+    use archmap::{IssueKind, ai_context_with_fs, analyze_with_fs, impact_with_fs};
+
+    // Rooted at the crate itself, so a walk of the disk would find far more files
+    let root = Path::new(env!("CARGO_MANIFEST_DIR"));
+    let fs = MemoryFileSystem::new()
+        .with_file(
+            root.join(".archmap.toml"),
+            "disabled_checks = [\"LowCohesion\"]\n",
+        )
+        .with_file(root.join("src/lib.rs"), "mod a;\nmod b;\n")
+        .with_file(
+            root.join("src/a.rs"),
+            "use crate::b::second;\npub fn first() {}\n",
+        )
+        .with_file(
+            root.join("src/b.rs"),
+            "use crate::a::first;\npub fn second() {}\n",
+        )
+        .with_file(root.join("vendor/dep.rs"), "pub fn dep() {}\n");
 
-    // Access inner for advanced use
-    let _inner = result.inner();
+    let options = AnalysisOptions::builder()
+        .exclude(["vendor"])
+        .build()
+        .unwrap();
+    let result = analyze_with_fs(root, options, &fs).unwrap();
+    assert_eq!(result.modules.len(), 3);
+    assert!(
+        result
+            .issues
+            .iter()
+            .any(|i| matches!(i.kind, IssueKind::CircularDependency))
+    );
+    assert!(
+        result
+            .issues
+            .iter()
+            .all(|i| !matches!(i.kind, IssueKind::LowCohesion { .. }))
+    );
+
+    let impact = impact_with_fs(root, Path::new("src/b.rs"), ImpactOptions::default(), &fs);
+    assert!(impact.unwrap().total_affected() >= 1);
+
+    let context = ai_context_with_fs(root, AiOptions::default(), &fs).unwrap();
+    assert!(context.contains("second"));
+
+    let missing = analyze_with_fs(Path::new("/nowhere"), AnalysisOptions::default(), &fs);
+    assert!(matches!(missing, Err(ArchmapError::PathNotFound(_))));
+}
+
+#[test]
+fn test_snapshot_and_diff_run_entirely_in_memory() {
+    use archmap::analyze_with_fs;
+    use archmap::fs::MemoryFileSystem;
+    use archmap::snapshot::{Snapshot, compute_diff, load_snapshot_with_fs, save_snapshot_with_fs};
+
+    let root = Path::new("/repo");
+    let fs = MemoryFileSystem::new()
+        .with_file("/repo/src/lib.rs", "mod a;\nmod b;\n")
+        .with_file("/repo/src/a.rs", "pub fn first() {}\n")
+        .with_file("/repo/src/b.rs", "pub fn second() {}\n");
+
+    let result = analyze_with_fs(root, AnalysisOptions::default(), &fs).unwrap();
+    let baseline = Snapshot::from_analysis_with_fs(&result, root, &fs);
+    // Hashes come from the in-memory contents, not a failed read of the disk
+    assert!(baseline.modules.iter().all(|m| !m.content_hash.is_empty()));
+    let saved = Path::new("/repo/.archmap/baseline.json");
+    save_snapshot_with_fs(&baseline, saved, &fs).unwrap();
+
+    fs.insert(
+        "/repo/src/a.rs",
+        "use crate::b::second;\npub fn first() {}\npub fn third() {}\n",
+    );
+    fs.insert("/repo/src/c.rs", "pub fn fourth() {}\n");
+    let result = analyze_with_fs(root, AnalysisOptions::default(), &fs).unwrap();
+    let current = Snapshot::from_analysis_with_fs(&result, root, &fs);
+
+    let diff = compute_diff(&load_snapshot_with_fs(saved, &fs).unwrap(), &current);
+    assert_eq!(diff.added_modules, ["src/c.rs"]);
+    assert_eq!(diff.modified_modules.len(), 1);
+    assert_eq!(diff.modified_modules[0].path, "src/a.rs");
+    assert_eq!(diff.modified_modules[0].exports_added, ["third"]);
+    assert_eq!(diff.metric_changes.module_count_delta, 1);
+}
+
+#[test]
+fn test_invalid_options_are_rejected_before_analysis() {
+    let mut options = AnalysisOptions::default();
+    options.min_cohesion = 7.3;
+    options.languages = vec!["cobol".to_string()];
+
+    match analyze(Path::new("."), options) {
+        Err(ArchmapError::InvalidOptions(error)) => assert_eq!(error.problems.len(), 2),
+        other => panic!("expected invalid options, got {:?}", other.err()),
+    }
+}
+
+#[test]
+fn test_exclude_accepts_globs() {
+    let options = AnalysisOptions::builder()
+        .exclude(["src/analysis/**"])
+        .build()
+        .unwrap();
+    let result = analyze(Path::new("."), options).unwrap();
+    assert!(!result.modules.is_empty());
+    assert!(
+        result
+            .modules
+            .iter()
+            .all(|m| !m.path.to_string_lossy().contains("src/analysis/"))
+    );
+}
+
+/// Six small modules, held in memory so files are visited one at a time
+fn six_modules() -> archmap::fs::MemoryFileSystem {
+    let mut fs = archmap::fs::MemoryFileSystem::new();
+    for i in 0..6 {
+        fs = fs.with_file(format!("/repo/src/m{}.rs", i), "pub fn run() {}\n");
+    }
+    fs
+}
+
+#[test]
+fn test_progress_reports_every_file_and_detector() {
+    use archmap::{Progress, analyze_with_fs};
+    use std::sync::{Arc, Mutex};
+
+    let steps = Arc::new(Mutex::new(Vec::new()));
+    let recorded = Arc::clone(&steps);
+    let options = AnalysisOptions::builder()
+        .progress(move |step| recorded.lock().unwrap().push(step))
+        .build()
+        .unwrap();
+    analyze_with_fs(Path::new("/repo"), options, &six_modules()).unwrap();
+
+    let steps = steps.lock().unwrap();
+    let count = |f: fn(&Progress) -> bool| steps.iter().filter(|s| f(s)).count();
+    assert_eq!(count(|s| matches!(s, Progress::FileDiscovered { .. })), 6);
+    assert_eq!(count(|s| matches!(s, Progress::FileParsed { .. })), 6);
+    assert_eq!(
+        count(|s| matches!(s, Progress::Discovered { modules: 6 })),
+        1
+    );
+
+    let started = count(|s| matches!(s, Progress::DetectorStarted { .. }));
+    assert!(started > 0);
+    assert_eq!(
+        count(|s| matches!(s, Progress::DetectorFinished { .. })),
+        started
+    );
+    assert!(matches!(
+        steps.last(),
+        Some(Progress::DetectorFinished { index, total, .. }) if index == total
+    ));
+}
+
+#[test]
+fn test_second_run_reuses_every_parsed_file() {
+    use archmap::analysis::CACHE_DIR;
+    use archmap::{Progress, analyze};
+    use std::sync::{Arc, Mutex};
+
+    let root = std::env::temp_dir().join(format!("archmap-parse-cache-{}", std::process::id()));
+    std::fs::create_dir_all(root.join("src")).unwrap();
+    for name in ["a", "b", "c"] {
+        let source = format!(
+            "pub fn {}() {{ std::fs::read(\"x\").ok(); std::fs::write(\"y\", []).ok(); }}\n",
+            name
+        );
+        std::fs::write(root.join(format!("src/{}.rs", name)), source).unwrap();
+    }
+    // Library callers leave the project untouched unless they opt in
+    analyze(&root, AnalysisOptions::default()).unwrap();
+    let written_by_default = root.join(CACHE_DIR).exists();
+
+    let run = || {
+        let steps = Arc::new(Mutex::new(Vec::new()));
+        let recorded = Arc::clone(&steps);
+        let options = AnalysisOptions::builder()
+            .progress(move |step| recorded.lock().unwrap().push(step))
+            .cache(true)
+            .build()
+            .unwrap();
+        let result = analyze(&root, options).unwrap();
+        let steps = steps.lock().unwrap();
+        let parsed = steps
+            .iter()
+            .filter(|s| matches!(s, Progress::FileParsed { .. }))
+            .count();
+        let cached = steps
+            .iter()
+            .filter(|s| matches!(s, Progress::FileCached { .. }))
+            .count();
+        (result, parsed, cached)
+    };
+
+    let (first, first_parsed, first_cached) = run();
+    let cache_written = root.join(CACHE_DIR).is_dir();
+    let (second, second_parsed, second_cached) = run();
+    std::fs::remove_dir_all(&root).unwrap();
+
+    assert!(!written_by_default);
+    assert!(cache_written);
+    assert_eq!((first_parsed, first_cached), (3, 0));
+    assert_eq!((second_parsed, second_cached), (0, 3));
+    assert_eq!(first.modules.len(), second.modules.len());
+    let issues = |result: &archmap::AnalysisResult| serde_json::to_string(&result.issues).unwrap();
+    assert!(!first.issues.is_empty());
+    assert_eq!(issues(&first), issues(&second));
+}
+
+#[test]
+fn test_cancelling_stops_analysis_between_files() {
+    use archmap::{CancellationToken, Progress, analyze_with_fs};
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    let token = CancellationToken::new();
+    let canceller = token.clone();
+    let parsed = Arc::new(AtomicUsize::new(0));
+    let counted = Arc::clone(&parsed);
+    let options = AnalysisOptions::builder()
+        .cancellation(token)
+        .progress(move |step| {
+            if let Progress::FileParsed { parsed, .. } = step {
+                counted.store(parsed, Ordering::Relaxed);
+                if parsed == 2 {
+                    canceller.cancel();
+                }
+            }
+        })
+        .build()
+        .unwrap();
+
+    let result = analyze_with_fs(Path::new("/repo"), options, &six_modules());
+    assert!(matches!(result, Err(ArchmapError::Cancelled)));
+    assert_eq!(parsed.load(Ordering::Relaxed), 2);
+}
+
+#[test]
+fn test_graph_data_schema() {
+    use archmap::fs::MemoryFileSystem;
+    use archmap::{GraphData, graph_data_with_fs};
+
+    let fs = MemoryFileSystem::new()
+        .with_file(
+            "/repo/src/a.ts",
+            "import { b } from './b';\nexport const a = 1;\n",
+        )
+        .with_file(
+            "/repo/src/b.ts",
+            "import { a } from './a';\nexport const b = 2;\n",
+        );
+    let graph = graph_data_with_fs(Path::new("/repo"), AnalysisOptions::default(), &fs).unwrap();
+
+    let json = serde_json::to_value(&graph).unwrap();
+    let keys = |value: &serde_json::Value| {
+        let mut keys: Vec<String> = value.as_object().unwrap().keys().cloned().collect();
+        keys.sort();
+        keys
+    };
+    assert_eq!(keys(&json), ["links", "metadata", "nodes", "physics"]);
+    assert_eq!(
+        keys(&json["nodes"][0]),
+        [
+            "betweenness",
+            "category",
+            "exports",
+            "fan_in",
+            "fan_out",
+            "id",
+            "issue_count",
+            "lines",
+            "name",
+            "path",
+            "scc_id",
+            "tags"
+        ]
+    );
+    assert_eq!(keys(&json["links"][0]), ["is_cycle", "source", "target"]);
+    assert_eq!(
+        keys(&json["metadata"]),
+        [
+            "archmap_version",
+            "cycle_count",
+            "project_name",
+            "schema_version",
+            "total_dependencies",
+            "total_issues",
+            "total_modules"
+        ]
+    );
+    assert_eq!(
+        keys(&json["physics"]),
+        ["charge", "collision_radius", "link_distance"]
+    );
+    assert_eq!(
+        json["metadata"]["schema_version"],
+        archmap::graph::GRAPH_SCHEMA
+    );
+    assert!(graph.links.iter().all(|l| l.is_cycle));
+
+    let restored: GraphData = serde_json::from_value(json).unwrap();
+    assert_eq!(restored.nodes.len(), 2);
+    assert_eq!(restored.metadata.cycle_count, 1);
+}
+
+/// Flags every module defining a `run` function
+struct EntryPoints;
+
+impl archmap::Detector for EntryPoints {
+    fn name(&self) -> &str {
+        "entry-points"
+    }
+
+    fn run(&self, input: &archmap::DetectorInput<'_>) -> Vec<archmap::Issue> {
+        input
+            .modules
+            .iter()
+            .filter(|m| m.definitions.iter().any(|d| d.name == "run"))
+            .map(|m| {
+                archmap::Issue::boundary_violation(
+                    "entry-points".to_string(),
+                    vec![archmap::Location {
+                        path: m.path.clone(),
+                        line: None,
+                        context: None,
+                        indicator: None,
+                    }],
+                    "Keep entry points in main.rs".to_string(),
+                )
+            })
+            .collect()
+    }
+}
+
+#[test]
+fn test_custom_detector_issues_appear() {
+    use archmap::{DetectorSet, IssueKind, Progress, analyze_with_fs};
+    use std::sync::{Arc, Mutex};
+
+    let names = Arc::new(Mutex::new(Vec::new()));
+    let recorded = Arc::clone(&names);
+    let options = AnalysisOptions::builder()
+        .detectors(DetectorSet::empty())
+        .detector(EntryPoints)
+        .progress(move |step| {
+            if let Progress::DetectorStarted { name, .. } = step {
+                recorded.lock().unwrap().push(name);
+            }
+        })
+        .build()
+        .unwrap();
+    let result = analyze_with_fs(Path::new("/repo"), options, &six_modules()).unwrap();
+
+    assert_eq!(result.issues.len(), 6);
+    assert!(result.issues.iter().all(|i| matches!(
+        &i.kind,
+        IssueKind::BoundaryViolation { boundary_name } if boundary_name == "entry-points"
+    )));
+    assert_eq!(*names.lock().unwrap(), ["entry-points"]);
+}
+
+#[test]
+fn test_removed_detector_reports_nothing() {
+    use archmap::{DetectorSet, IssueKind, analyze_with_fs, fs::MemoryFileSystem};
+
+    let fs = MemoryFileSystem::new()
+        .with_file(
+            "/repo/src/a.ts",
+            "import { b } from './b';\nexport const a = 1;\n",
+        )
+        .with_file(
+            "/repo/src/b.ts",
+            "import { a } from './a';\nexport const b = 2;\n",
+        );
+    let cycles = |detectors: DetectorSet| {
+        let options = AnalysisOptions::builder()
+            .detectors(detectors)
+            .build()
+            .unwrap();
+        let result = analyze_with_fs(Path::new("/repo"), options, &fs).unwrap();
+        result
+            .issues
+            .iter()
+            .filter(|i| matches!(i.kind, IssueKind::CircularDependency))
+            .count()
+    };
+
+    assert_eq!(cycles(DetectorSet::builtin()), 1);
+    let mut detectors = DetectorSet::builtin();
+    assert!(detectors.remove("cycles"));
+    assert!(!detectors.remove("cycles"));
+    assert_eq!(cycles(detectors), 0);
+}
+
+#[test]
+fn test_unreadable_file_is_a_diagnostic_not_stderr() {
+    use archmap::DiagnosticKind;
+    use std::process::Command;
+
+    // Rerun just this test in a child process so its stderr can be inspected
+    const CHILD: &str = "ARCHMAP_DIAGNOSTICS_CHILD";
+    if std::env::var_os(CHILD).is_none() {
+        let output = Command::new(std::env::current_exe().unwrap())
+            .args([
+                "test_unreadable_file_is_a_diagnostic_not_stderr",
+                "--exact",
+                "--nocapture",
+            ])
+            .env(CHILD, "1")
+            .output()
+            .unwrap();
+        assert!(output.status.success(), "{:?}", output);
+        assert_eq!(String::from_utf8_lossy(&output.stderr), "");
+        return;
+    }
+
+    let root = std::env::temp_dir().join(format!("archmap-diagnostics-{}", std::process::id()));
+    std::fs::create_dir_all(root.join("src")).unwrap();
+    std::fs::write(root.join("src/lib.rs"), "pub fn run() {}\n").unwrap();
+    // A UTF-16 byte order mark followed by an unpaired surrogate
+    std::fs::write(root.join("src/corrupt.rs"), [0xff, 0xfe, 0x00, 0xd8]).unwrap();
+    let result = analyze(&root, AnalysisOptions::default());
+    std::fs::remove_dir_all(&root).unwrap();
+
+    let result = result.unwrap();
+    assert_eq!(result.modules.len(), 1);
+    assert_eq!(result.diagnostics.len(), 1);
+    let diagnostic = &result.diagnostics[0];
+    assert_eq!(diagnostic.kind, DiagnosticKind::ReadFailed);
+    assert!(
+        diagnostic
+            .path
+            .as_ref()
+            .unwrap()
+            .ends_with("src/corrupt.rs")
+    );
+}
+
+#[test]
+fn test_errors_name_what_failed() {
+    use archmap::fs::MemoryFileSystem;
+    use archmap::snapshot::{Snapshot, SnapshotError, load_snapshot_with_fs};
+    use archmap::{analyze_with_fs, impact_with_fs};
+
+    // A file no parser handles
+    let fs = MemoryFileSystem::new()
+        .with_file("/repo/src/lib.rs", "pub fn run() {}\n")
+        .with_file("/repo/README.md", "# Notes\n");
+    let err = impact_with_fs(
+        Path::new("/repo"),
+        Path::new("README.md"),
+        ImpactOptions::default(),
+        &fs,
+    )
+    .err()
+    .expect("no parser for markdown");
+    assert!(
+        matches!(&err, ArchmapError::UnsupportedLanguage { languages, .. } if languages.len() == 4)
+    );
+    assert_eq!(
+        err.to_string(),
+        "No parser for README.md; analyzed languages: rust, typescript, python, scala"
+    );
+
+    // A snapshot from a newer schema, and one that doesn't exist
+    let result = analyze_with_fs(Path::new("/repo"), AnalysisOptions::default(), &fs).unwrap();
+    let mut snapshot = Snapshot::from_analysis(&result, Path::new("/repo"));
+    snapshot.version = 99;
+    let fs = fs.with_file("/repo/new.json", serde_json::to_string(&snapshot).unwrap());
+    let err: ArchmapError = load_snapshot_with_fs(Path::new("/repo/new.json"), &fs)
+        .unwrap_err()
+        .into();
+    assert!(matches!(
+        err,
+        ArchmapError::Snapshot(SnapshotError::UnsupportedVersion {
+            found: 99,
+            supported: 1,
+            ..
+        })
+    ));
+    assert_eq!(
+        err.to_string(),
+        "Snapshot /repo/new.json uses schema 99, but this archmap reads up to schema 1; upgrade archmap"
+    );
+    let err = load_snapshot_with_fs(Path::new("/repo/old.json"), &fs).unwrap_err();
+    assert!(matches!(err, SnapshotError::Read { .. }));
+    assert!(
+        err.to_string()
+            .starts_with("Failed to read snapshot /repo/old.json: ")
+    );
+
+    let err = ArchmapError::Format {
+        format: "json".to_string(),
+        source: std::io::Error::other("disk full"),
+    };
+    assert_eq!(err.to_string(), "Failed to format json output: disk full");
+}
+
+#[test]
+#[cfg(feature = "encoding")]
+fn test_sources_with_byte_order_marks_are_decoded() {
+    let root = std::env::temp_dir().join(format!("archmap-encodings-{}", std::process::id()));
+    std::fs::create_dir_all(&root).unwrap();
+    let source = "import { helper } from \"./util\";\nexport function greet() {}\n";
+    let mut bom_utf8 = vec![0xef, 0xbb, 0xbf];
+    bom_utf8.extend(source.as_bytes());
+    let bare_utf16: Vec<u8> = source.encode_utf16().flat_map(u16::to_le_bytes).collect();
+    let mut utf16 = vec![0xff, 0xfe];
+    utf16.extend(&bare_utf16);
+    std::fs::write(root.join("bom.ts"), bom_utf8).unwrap();
+    std::fs::write(root.join("wide.ts"), utf16).unwrap();
+    std::fs::write(root.join("bare.ts"), bare_utf16).unwrap();
+    std::fs::write(root.join("util.ts"), "export function helper() {}\n").unwrap();
+    let result = analyze(&root, AnalysisOptions::default());
+    std::fs::remove_dir_all(&root).unwrap();
+
+    let result = result.unwrap();
+    assert!(result.diagnostics.is_empty(), "{:?}", result.diagnostics);
+    for name in ["bom.ts", "wide.ts", "bare.ts"] {
+        let module = result
+            .modules
+            .iter()
+            .find(|m| m.path.ends_with(name))
+            .unwrap();
+        assert_eq!(module.imports, ["./util"]);
+        let definitions: Vec<_> = module.definitions.iter().map(|d| d.name.as_str()).collect();
+        assert_eq!(definitions, ["greet"]);
+    }
+}
+
+#[test]
+fn test_project_of_only_unreadable_files_is_a_parse_error() {
+    let root = std::env::temp_dir().join(format!("archmap-parse-error-{}", std::process::id()));
+    std::fs::create_dir_all(root.join("src")).unwrap();
+    std::fs::write(root.join("src/a.rs"), [0xff, 0xfe, 0x00]).unwrap();
+    std::fs::write(root.join("src/b.rs"), [0xc3, 0x28]).unwrap();
+    let result = analyze(&root, AnalysisOptions::default());
+    std::fs::remove_dir_all(&root).unwrap();
+
+    let err = result.err().expect("no file parsed");
+    let ArchmapError::Parse { failures } = &err else {
+        panic!("expected a parse error, got {:?}", err);
+    };
+    assert_eq!(failures.len(), 2);
+    assert!(failures[0].path.as_ref().unwrap().ends_with("src/a.rs"));
+    assert!(err.to_string().starts_with(
+        "2 source file(s) found, but none could be read or parsed; first: Failed to read "
+    ));
 }